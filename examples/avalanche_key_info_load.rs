@@ -21,7 +21,7 @@ fn main() {
     info!("loading key");
     let k = key::Key::from_private_key(&private_key).unwrap();
     let info = k.to_info(network_id).unwrap();
-    assert_eq!(private_key, k.private_key);
+    assert_eq!(k.private_key, private_key.as_str());
     assert_eq!(info.x_address, k.address("X", network_id).unwrap());
     assert_eq!(info.p_address, k.address("P", network_id).unwrap());
     assert_eq!(info.c_address, k.address("C", network_id).unwrap());
@@ -20,7 +20,7 @@ fn main() {
     let info = key::PrivateKeyInfo::load(&key_path).unwrap();
     println!("{}", info.to_string().unwrap());
 
-    let k = key::Key::from_private_key(&info.private_key).unwrap();
+    let k = key::Key::from_private_key(info.private_key.expose_str()).unwrap();
     assert_eq!(info.private_key, k.private_key);
     assert_eq!(info.x_address, k.address("X", network_id).unwrap());
     assert_eq!(info.p_address, k.address("P", network_id).unwrap());
@@ -42,13 +42,13 @@ fn main() {
     let dek = ab!(kms_manager.generate_data_key(&cmk.id, None)).unwrap();
 
     let dek_ciphertext_decrypted = ab!(kms_manager.decrypt(&cmk.id, None, dek.ciphertext)).unwrap();
-    assert_eq!(dek.plaintext, dek_ciphertext_decrypted);
+    assert_eq!(dek.plaintext.expose(), dek_ciphertext_decrypted);
 
     let dek_plaintext_encrypted =
-        ab!(kms_manager.encrypt(&cmk.id, None, dek.plaintext.clone())).unwrap();
+        ab!(kms_manager.encrypt(&cmk.id, None, dek.plaintext.expose().to_vec())).unwrap();
     let dek_plaintext_encrypted_decrypted =
         ab!(kms_manager.decrypt(&cmk.id, None, dek_plaintext_encrypted)).unwrap();
-    assert_eq!(dek.plaintext, dek_plaintext_encrypted_decrypted);
+    assert_eq!(dek.plaintext.expose(), dek_plaintext_encrypted_decrypted);
     assert_eq!(dek_ciphertext_decrypted, dek_plaintext_encrypted_decrypted);
 
     let plaintext = "Hello World!";
@@ -0,0 +1,421 @@
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fmt::Write,
+    fs,
+    io::{self, Error, ErrorKind},
+    net::SocketAddr,
+    process::Command,
+    sync::{Arc, RwLock},
+};
+
+use hyper::{
+    header,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{avalanche::heartbeat, remediation};
+
+/// Default port of the "avalanched" metrics endpoint, next to the avalanchego
+/// HTTP (9650) and staking (9651) ports.
+pub const DEFAULT_PORT: u16 = 9652;
+
+pub const METRICS_PATH: &str = "/metrics";
+
+/// Prometheus text exposition format.
+/// ref. https://prometheus.io/docs/instrumenting/exposition_formats/
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// systemd services whose restarts are exposed, supervised by systemd.
+pub const SERVICES: [&str; 2] = ["avalanche", "avalanched"];
+
+/// Represents the Prometheus "/metrics" endpoint of "avalanched" on each node,
+/// for the existing scrapers of the hosts to see the agent state
+/// (the agent health, the backup age, the restarts, the artifact versions).
+/// The cluster security group does not allow the port, so the scrapers
+/// need their own ingress rule (e.g., from the scraper security group).
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct AgentMetrics {
+    /// None for "DEFAULT_PORT".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+}
+
+impl AgentMetrics {
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(DEFAULT_PORT)
+    }
+
+    /// Fails if the port conflicts with the avalanchego ports.
+    pub fn validate(&self, http_port: u32, staking_port: u32) -> io::Result<()> {
+        let port = self.port() as u32;
+        if port == 0 || port == http_port || port == staking_port {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'agent_metrics.port' {} conflicts with the avalanchego ports ({}, {})",
+                    port, http_port, staking_port
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    heartbeat: Option<heartbeat::Heartbeat>,
+    heartbeat_published_at: Option<u64>,
+    remediation_actions: BTreeMap<&'static str, u64>,
+}
+
+/// Represents the agent state updated by the "avalanched run" tasks,
+/// and encoded on every scrape.
+#[derive(Debug)]
+pub struct Registry {
+    started_at: u64,
+    state: RwLock<State>,
+}
+
+impl Registry {
+    pub fn new(started_at: u64) -> Self {
+        Self {
+            started_at,
+            state: RwLock::new(State::default()),
+        }
+    }
+
+    /// Records the latest collected heartbeat, for the artifact versions and the health.
+    pub fn set_heartbeat(&self, hb: &heartbeat::Heartbeat) {
+        self.state.write().unwrap().heartbeat = Some(hb.clone());
+    }
+
+    pub fn heartbeat_published(&self, at: u64) {
+        self.state.write().unwrap().heartbeat_published_at = Some(at);
+    }
+
+    pub fn remediation_action(&self, action: &remediation::Action) {
+        let mut state = self.state.write().unwrap();
+        *state
+            .remediation_actions
+            .entry(action.as_str())
+            .or_insert(0) += 1;
+    }
+
+    /// Encodes the metrics in the Prometheus text format.
+    /// "service_restarts" is the restart count of each systemd service, if known.
+    pub fn encode(
+        &self,
+        now: u64,
+        last_backup: Option<u64>,
+        service_restarts: &[(&str, Option<u64>)],
+    ) -> String {
+        let state = self.state.read().unwrap();
+        let mut out = String::new();
+
+        write_metric(
+            &mut out,
+            "avalanched_up",
+            "1 if the avalanched agent is running.",
+            "gauge",
+            &[(vec![], 1)],
+        );
+        write_metric(
+            &mut out,
+            "avalanched_start_time_seconds",
+            "Unix time when the avalanched agent started.",
+            "gauge",
+            &[(vec![], self.started_at)],
+        );
+
+        let mut info_labels = vec![("avalanched_version", env!("CARGO_PKG_VERSION"))];
+        if let Some(hb) = &state.heartbeat {
+            info_labels.push(("avalanchego_version", hb.avalanchego_version.as_str()));
+            info_labels.push(("avalanchego_bin_sha256", hb.avalanchego_bin_sha256.as_str()));
+            info_labels.push(("config_sha256", hb.config_sha256.as_str()));
+        }
+        write_metric(
+            &mut out,
+            "avalanched_build_info",
+            "Artifact versions of the node, always 1.",
+            "gauge",
+            &[(info_labels, 1)],
+        );
+        if let Some(hb) = &state.heartbeat {
+            let plugins: Vec<(Vec<(&str, &str)>, u64)> = hb
+                .plugins
+                .iter()
+                .map(|(name, sha256)| {
+                    (
+                        vec![("plugin", name.as_str()), ("sha256", sha256.as_str())],
+                        1,
+                    )
+                })
+                .collect();
+            if !plugins.is_empty() {
+                write_metric(
+                    &mut out,
+                    "avalanched_plugin_info",
+                    "Plugin checksums of the node, always 1.",
+                    "gauge",
+                    &plugins,
+                );
+            }
+            if let Some(healthy) = hb.healthy {
+                write_metric(
+                    &mut out,
+                    "avalanched_node_healthy",
+                    "1 if the avalanchego health API reported healthy at the last heartbeat.",
+                    "gauge",
+                    &[(vec![], u64::from(healthy))],
+                );
+            }
+        }
+        if let Some(at) = state.heartbeat_published_at {
+            write_metric(
+                &mut out,
+                "avalanched_heartbeat_last_published_timestamp_seconds",
+                "Unix time of the last heartbeat published to S3.",
+                "gauge",
+                &[(vec![], at)],
+            );
+        }
+
+        if let Some(at) = last_backup {
+            write_metric(
+                &mut out,
+                "avalanched_backup_last_timestamp_seconds",
+                "Unix time of the last 'avalanched backup upload'.",
+                "gauge",
+                &[(vec![], at)],
+            );
+            write_metric(
+                &mut out,
+                "avalanched_backup_age_seconds",
+                "Seconds since the last 'avalanched backup upload'.",
+                "gauge",
+                &[(vec![], now.saturating_sub(at))],
+            );
+        }
+
+        let restarts: Vec<(Vec<(&str, &str)>, u64)> = service_restarts
+            .iter()
+            .filter_map(|(service, n)| n.map(|n| (vec![("service", *service)], n)))
+            .collect();
+        if !restarts.is_empty() {
+            write_metric(
+                &mut out,
+                "avalanched_service_restarts_total",
+                "Restarts of the systemd service since the boot.",
+                "counter",
+                &restarts,
+            );
+        }
+        let actions: Vec<(Vec<(&str, &str)>, u64)> = state
+            .remediation_actions
+            .iter()
+            .map(|(action, n)| (vec![("action", *action)], *n))
+            .collect();
+        if !actions.is_empty() {
+            write_metric(
+                &mut out,
+                "avalanched_remediation_actions_total",
+                "Remediation actions of the agent since it started.",
+                "counter",
+                &actions,
+            );
+        }
+        out
+    }
+}
+
+fn write_metric(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    kind: &str,
+    samples: &[(Vec<(&str, &str)>, u64)],
+) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+    for (labels, v) in samples.iter() {
+        if labels.is_empty() {
+            writeln!(out, "{} {}", name, v).unwrap();
+            continue;
+        }
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(k, v)| format!("{}=\"{}\"", k, escape_label_value(v)))
+            .collect();
+        writeln!(out, "{}{{{}}} {}", name, labels.join(","), v).unwrap();
+    }
+}
+
+fn escape_label_value(v: &str) -> String {
+    v.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Returns the unix time of the last "avalanched backup upload" on this node.
+pub fn last_backup() -> Option<u64> {
+    fs::read_to_string(heartbeat::LAST_BACKUP_FILE_PATH)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+}
+
+/// Returns the restarts of the systemd service (e.g., "avalanche"),
+/// counted by systemd since the boot.
+pub fn service_restarts(service: &str) -> io::Result<u64> {
+    // not "bash::run", which logs on every scrape
+    let output = Command::new("systemctl")
+        .args(["show", "--property=NRestarts", "--value", service])
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "'systemctl show {}' failed {}",
+                service,
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        ));
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<u64>()
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid NRestarts of '{}' ({})", service, e),
+            )
+        })
+}
+
+/// Serves "/metrics" on all interfaces until the process exits.
+pub async fn serve(port: u16, registry: Arc<Registry>) -> io::Result<()> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let make_svc = make_service_fn(move |_| {
+        let registry = registry.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let registry = registry.clone();
+                async move { Ok::<_, Infallible>(handle(registry, req).await) }
+            }))
+        }
+    });
+    let server = Server::try_bind(&addr)
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::AddrInUse,
+                format!("failed to bind {} ({})", addr, e),
+            )
+        })?
+        .serve(make_svc);
+    info!("serving agent metrics at http://{}{}", addr, METRICS_PATH);
+    server.await.map_err(|e| {
+        Error::new(
+            ErrorKind::Other,
+            format!("agent metrics server failed {}", e),
+        )
+    })
+}
+
+async fn handle(registry: Arc<Registry>, req: Request<Body>) -> Response<Body> {
+    if req.method() != Method::GET || req.uri().path() != METRICS_PATH {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .unwrap();
+    }
+    let ret = tokio::task::spawn_blocking(move || {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let restarts: Vec<(&str, Option<u64>)> = SERVICES
+            .iter()
+            .map(|s| match service_restarts(s) {
+                Ok(n) => (*s, Some(n)),
+                Err(e) => {
+                    warn!("failed to read the restarts of '{}' ({})", s, e);
+                    (*s, None)
+                }
+            })
+            .collect();
+        registry.encode(now, last_backup(), &restarts)
+    })
+    .await;
+    match ret {
+        Ok(body) => Response::builder()
+            .header(header::CONTENT_TYPE, CONTENT_TYPE)
+            .body(Body::from(body))
+            .unwrap(),
+        Err(e) => Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from(e.to_string()))
+            .unwrap(),
+    }
+}
+
+#[test]
+fn test_agent_metrics() {
+    use crate::utils::prometheus;
+
+    let cfg = AgentMetrics::default();
+    assert_eq!(cfg.port(), DEFAULT_PORT);
+    assert!(cfg.validate(9650, 9651).is_ok());
+    assert!(AgentMetrics { port: Some(9650) }
+        .validate(9650, 9651)
+        .is_err());
+
+    let registry = Registry::new(1000);
+    let encoded = registry.encode(2000, None, &[("avalanche", None)]);
+    assert!(encoded.contains("avalanched_up 1\n"));
+    assert!(encoded.contains("avalanched_start_time_seconds 1000\n"));
+    assert!(!encoded.contains("avalanched_backup_age_seconds"));
+    assert!(!encoded.contains("avalanched_service_restarts_total"));
+
+    let mut plugins = BTreeMap::new();
+    plugins.insert(String::from("subnet\"evm"), String::from("abc"));
+    registry.set_heartbeat(&heartbeat::Heartbeat {
+        node_id: String::from("NodeID-1"),
+        machine_id: String::from("i-1"),
+        kind: String::from("anchor"),
+        public_ip: String::from("1.2.3.4"),
+        avalanched_version: String::from("0.0.5"),
+        avalanchego_version: String::from("avalanche/1.7.10"),
+        disk_total_bytes: 0,
+        disk_available_bytes: 0,
+        uptime_seconds: 0,
+        last_backup: None,
+        config_sha256: String::from("cfg"),
+        avalanchego_config: BTreeMap::new(),
+        avalanchego_bin_sha256: String::from("bin"),
+        plugins,
+        index_backfill: None,
+        healthy: Some(true),
+        timestamp: 1500,
+    });
+    registry.heartbeat_published(1500);
+    registry.remediation_action(&remediation::Action::RestartNode);
+    registry.remediation_action(&remediation::Action::RestartNode);
+
+    let encoded = registry.encode(2000, Some(1400), &[("avalanche", Some(3))]);
+    assert!(encoded.contains("avalanched_backup_age_seconds 600\n"));
+    assert!(encoded.contains("avalanched_service_restarts_total{service=\"avalanche\"} 3\n"));
+    assert!(encoded.contains("avalanched_remediation_actions_total{action=\"restart-node\"} 2\n"));
+    assert!(encoded.contains("avalanched_plugin_info{plugin=\"subnet\\\"evm\",sha256=\"abc\"} 1\n"));
+    assert!(encoded.contains("avalanched_node_healthy 1\n"));
+
+    // parses back with the same scraper as the avalanchego metrics
+    let scrape = prometheus::Scrape::from_bytes(encoded.as_bytes()).unwrap();
+    let m = prometheus::match_metric(&scrape.metrics, |s| {
+        s.metric == "avalanched_heartbeat_last_published_timestamp_seconds"
+    });
+    assert_eq!(m.value.to_f64(), 1500_f64);
+}
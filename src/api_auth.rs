@@ -0,0 +1,106 @@
+use std::{
+    io::{self, Error, ErrorKind},
+    sync::Arc,
+};
+
+use log::info;
+
+use crate::{
+    aws::{envelope, s3},
+    utils::random,
+    StorageNamespace,
+};
+
+/// Length of the generated API auth password.
+pub const PASSWORD_LEN: usize = 32;
+
+/// Generates the per-cluster API auth password.
+/// The password never leaves the S3 bucket unencrypted, and the nodes
+/// and the CLI issue the per-node tokens with it.
+pub fn generate_password() -> String {
+    random::string(PASSWORD_LEN)
+}
+
+/// Envelope-encrypts the password and uploads to "StorageNamespace::ApiAuthPasswordEncrypted".
+pub async fn put_password(
+    s3_manager: &s3::Manager,
+    envelope: &envelope::Envelope,
+    s3_bucket: &str,
+    id: &str,
+    password: &str,
+) -> io::Result<()> {
+    let sealed = envelope
+        .seal_aes_256(password.as_bytes())
+        .await
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to seal API auth password ({})", e.message()),
+            )
+        })?;
+    info!("uploading encrypted API auth password");
+    s3_manager
+        .put_bytes(
+            sealed,
+            Arc::new(s3_bucket.to_string()),
+            Arc::new(StorageNamespace::ApiAuthPasswordEncrypted(id.to_string()).encode()),
+        )
+        .await
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to upload API auth password ({})", e.message()),
+            )
+        })
+}
+
+/// Downloads and decrypts the password.
+/// Returns "None" if the password has not been generated yet.
+pub async fn get_password(
+    s3_manager: &s3::Manager,
+    envelope: &envelope::Envelope,
+    s3_bucket: &str,
+    id: &str,
+) -> io::Result<Option<String>> {
+    let sealed = s3_manager
+        .get_bytes(
+            s3_bucket,
+            &StorageNamespace::ApiAuthPasswordEncrypted(id.to_string()).encode(),
+            None,
+        )
+        .await
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to download API auth password ({})", e.message()),
+            )
+        })?;
+    let sealed = match sealed {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let d = envelope.unseal_aes_256(&sealed).await.map_err(|e| {
+        Error::new(
+            ErrorKind::Other,
+            format!("failed to unseal API auth password ({})", e.message()),
+        )
+    })?;
+    String::from_utf8(d).map(Some).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid API auth password {}", e),
+        )
+    })
+}
+
+#[test]
+fn test_generate_password() {
+    let p1 = generate_password();
+    let p2 = generate_password();
+    assert_eq!(p1.len(), PASSWORD_LEN);
+    assert_ne!(p1, p2);
+    assert_eq!(
+        StorageNamespace::ApiAuthPasswordEncrypted(String::from("test")).encode(),
+        "test/api-auth-password.seal_aes_256.encrypted"
+    );
+}
@@ -0,0 +1,254 @@
+use std::io::{self, Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+use crate::avalanche::{
+    avalanchego::config as avalanchego_config, coreth::config as coreth_config, node,
+};
+
+/// coreth "eth-apis" that expose the debug and tracing methods.
+/// ref. https://github.com/ava-labs/coreth/blob/v0.8.6/plugin/evm/config.go
+pub const DEBUG_ETH_APIS: [&str; 5] = [
+    "internal-public-debug",
+    "internal-private-debug",
+    "public-debug",
+    "private-debug",
+    "debug-tracer",
+];
+
+/// Represents the avalanchego APIs enabled per node class,
+/// overriding "avalanchego_config" and "coreth_config" on the nodes.
+/// Once set, the nodes in the public NLB target group must not enable
+/// the admin, debug, or keystore APIs.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct ApiExposure {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor_nodes: Option<NodeApis>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub non_anchor_nodes: Option<NodeApis>,
+}
+
+/// Represents the APIs of a node class.
+/// The unset fields keep the cluster-wide configuration.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct NodeApis {
+    /// Set "false" to keep the node class out of the NLB target group
+    /// (e.g., private anchor nodes with the admin APIs). Default "true".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nlb_target: Option<bool>,
+
+    /// "api-admin-enabled" and coreth "coreth-admin-api-enabled".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin: Option<bool>,
+    /// coreth "eth-apis" of "DEBUG_ETH_APIS" (e.g., "debug_traceTransaction").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug: Option<bool>,
+    /// "api-metrics-enabled".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<bool>,
+    /// "index-enabled".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<bool>,
+    /// "api-keystore-enabled".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keystore: Option<bool>,
+}
+
+impl NodeApis {
+    pub fn is_nlb_target(&self) -> bool {
+        self.nlb_target.unwrap_or(true)
+    }
+
+    /// Overwrites the configuration with the set fields.
+    pub fn apply(
+        &self,
+        avalanchego_config: &mut avalanchego_config::Config,
+        coreth_config: &mut coreth_config::Config,
+    ) {
+        if let Some(v) = self.admin {
+            avalanchego_config.api_admin_enabled = Some(v);
+            coreth_config.coreth_admin_api_enabled = Some(v);
+        }
+        if let Some(v) = self.metrics {
+            avalanchego_config.api_metrics_enabled = Some(v);
+        }
+        if let Some(v) = self.index {
+            avalanchego_config.index_enabled = Some(v);
+        }
+        if let Some(v) = self.keystore {
+            avalanchego_config.api_keystore_enabled = Some(v);
+        }
+        match self.debug {
+            Some(true) => {
+                let mut apis = coreth_config.eth_apis.clone().unwrap_or_else(|| {
+                    coreth_config::DEFAULT_ETH_APIS
+                        .iter()
+                        .map(|v| v.to_string())
+                        .collect()
+                });
+                for api in DEBUG_ETH_APIS.iter() {
+                    if !apis.iter().any(|v| v == api) {
+                        apis.push(api.to_string());
+                    }
+                }
+                coreth_config.eth_apis = Some(apis);
+            }
+            Some(false) => {
+                if let Some(apis) = coreth_config.eth_apis.as_mut() {
+                    apis.retain(|v| !DEBUG_ETH_APIS.contains(&v.as_str()));
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Returns the enabled admin, debug, and keystore APIs,
+    /// with the overrides applied to the cluster-wide configuration.
+    pub fn dangerous_apis(
+        &self,
+        avalanchego_config: &avalanchego_config::Config,
+        coreth_config: &coreth_config::Config,
+    ) -> Vec<&'static str> {
+        let (mut a, mut c) = (avalanchego_config.clone(), coreth_config.clone());
+        self.apply(&mut a, &mut c);
+
+        let mut apis = Vec::new();
+        if a.api_admin_enabled == Some(true) || c.coreth_admin_api_enabled == Some(true) {
+            apis.push("admin");
+        }
+        if c.eth_apis
+            .unwrap_or_default()
+            .iter()
+            .any(|v| DEBUG_ETH_APIS.contains(&v.as_str()))
+        {
+            apis.push("debug");
+        }
+        if a.api_keystore_enabled == Some(true) {
+            apis.push("keystore");
+        }
+        apis
+    }
+}
+
+impl ApiExposure {
+    /// Returns the APIs of the node class, or the defaults if unset.
+    pub fn node_apis(&self, kind: &node::Kind) -> NodeApis {
+        match kind {
+            node::Kind::Anchor => self.anchor_nodes.clone(),
+            node::Kind::NonAnchor => self.non_anchor_nodes.clone(),
+        }
+        .unwrap_or_default()
+    }
+
+    /// Validates that no node class in the NLB target group enables
+    /// the dangerous APIs, and that the NLB has at least one node class.
+    pub fn validate(
+        &self,
+        avalanchego_config: &avalanchego_config::Config,
+        coreth_config: &coreth_config::Config,
+        has_anchor_nodes: bool,
+    ) -> io::Result<()> {
+        let mut classes = vec![("non_anchor_nodes", node::Kind::NonAnchor)];
+        if has_anchor_nodes {
+            classes.push(("anchor_nodes", node::Kind::Anchor));
+        }
+
+        let mut nlb_targets = 0;
+        for (name, kind) in classes.iter() {
+            let apis = self.node_apis(kind);
+            if !apis.is_nlb_target() {
+                continue;
+            }
+            nlb_targets += 1;
+
+            let dangerous = apis.dangerous_apis(avalanchego_config, coreth_config);
+            if !dangerous.is_empty() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "'api_exposure.{}' exposes the {:?} APIs on the public NLB target group (disable them, or set 'nlb_target: false')",
+                        name, dangerous
+                    ),
+                ));
+            }
+        }
+        if nlb_targets == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'api_exposure' leaves no node class in the NLB target group",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_api_exposure() {
+    let avalanchego_config = avalanchego_config::Config::default();
+    let coreth_config = coreth_config::Config::default();
+
+    // the defaults enable the admin and keystore APIs
+    let exposure = ApiExposure::default();
+    assert!(exposure
+        .validate(&avalanchego_config, &coreth_config, true)
+        .is_err());
+
+    let locked = NodeApis {
+        admin: Some(false),
+        keystore: Some(false),
+        ..NodeApis::default()
+    };
+    let private = NodeApis {
+        nlb_target: Some(false),
+        admin: Some(true),
+        debug: Some(true),
+        ..NodeApis::default()
+    };
+    let exposure = ApiExposure {
+        anchor_nodes: Some(private.clone()),
+        non_anchor_nodes: Some(locked.clone()),
+    };
+    assert!(exposure
+        .validate(&avalanchego_config, &coreth_config, true)
+        .is_ok());
+
+    // no NLB target
+    let exposure = ApiExposure {
+        anchor_nodes: None,
+        non_anchor_nodes: Some(private.clone()),
+    };
+    assert!(exposure
+        .validate(&avalanchego_config, &coreth_config, false)
+        .is_err());
+
+    // debug on the public nodes
+    let exposure = ApiExposure {
+        anchor_nodes: None,
+        non_anchor_nodes: Some(NodeApis {
+            debug: Some(true),
+            ..locked.clone()
+        }),
+    };
+    assert!(exposure
+        .validate(&avalanchego_config, &coreth_config, false)
+        .is_err());
+
+    let (mut a, mut c) = (avalanchego_config.clone(), coreth_config.clone());
+    private.apply(&mut a, &mut c);
+    assert_eq!(a.api_admin_enabled, Some(true));
+    assert_eq!(c.coreth_admin_api_enabled, Some(true));
+    let apis = c.eth_apis.clone().unwrap();
+    assert!(apis.contains(&String::from("public-eth")));
+    assert!(apis.contains(&String::from("debug-tracer")));
+
+    let disabled = NodeApis {
+        debug: Some(false),
+        ..NodeApis::default()
+    };
+    disabled.apply(&mut a, &mut c);
+    let apis = c.eth_apis.unwrap();
+    assert!(apis.contains(&String::from("public-eth")));
+    assert!(!apis.contains(&String::from("debug-tracer")));
+}
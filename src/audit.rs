@@ -0,0 +1,218 @@
+use std::{
+    fmt,
+    io::{self, Error, ErrorKind},
+    sync::Arc,
+};
+
+use chrono::{SecondsFormat, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aws::{operation::Operation, s3},
+    StorageNamespace,
+};
+
+/// Represents the result of an audited operation.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Success,
+    Failure,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Success => write!(f, "success"),
+            Outcome::Failure => write!(f, "failure"),
+        }
+    }
+}
+
+/// Represents a CLI operation against the cluster, written once
+/// the operation finishes. Each record is a new S3 object
+/// under "StorageNamespace::AuditDir", so the log is append-only.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Record {
+    pub cluster_id: String,
+    pub operation_id: String,
+    /// Local user name of the operator.
+    pub operator: String,
+    /// AWS caller identity ARN, if resolved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caller_arn: Option<String>,
+    /// Subcommand path (e.g., "subnet create").
+    pub command: String,
+    /// SHA-256 of the command-line arguments and the spec file,
+    /// so the inputs can be verified without storing the secrets.
+    pub inputs_sha256: String,
+    /// RFC 3339 timestamps.
+    pub started_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<Outcome>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Record {
+    pub fn new(cluster_id: &str, op: &Operation, inputs_sha256: &str) -> Self {
+        Self {
+            cluster_id: cluster_id.to_string(),
+            operation_id: op.id.clone(),
+            operator: op.operator.clone(),
+            caller_arn: None,
+            command: op.command.clone(),
+            inputs_sha256: inputs_sha256.to_string(),
+            started_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            finished_at: None,
+            outcome: None,
+            error: None,
+        }
+    }
+
+    /// Marks the record finished, failed if the error is set.
+    pub fn finish(&mut self, error: Option<String>) {
+        self.finished_at = Some(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true));
+        self.outcome = Some(if error.is_some() {
+            Outcome::Failure
+        } else {
+            Outcome::Success
+        });
+        self.error = error;
+    }
+
+    /// Returns the S3 key of the record. The keys sort by the start time.
+    pub fn s3_key(&self) -> String {
+        let ts: String = self
+            .started_at
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect();
+        StorageNamespace::AuditRecord(
+            self.cluster_id.clone(),
+            format!("{}-{}", ts, self.operation_id),
+        )
+        .encode()
+    }
+}
+
+/// Returns the hex-encoded SHA-256 of the arguments and the spec file contents.
+pub fn inputs_sha256(args: &[String], spec_file: Option<&[u8]>) -> String {
+    let mut ctx = ring::digest::Context::new(&ring::digest::SHA256);
+    for arg in args.iter() {
+        ctx.update(arg.as_bytes());
+        ctx.update(&[0]);
+    }
+    if let Some(d) = spec_file {
+        ctx.update(d);
+    }
+    hex::encode(ctx.finish().as_ref())
+}
+
+/// Writes the record as a new object in the S3 bucket.
+pub async fn put(s3_manager: &s3::Manager, s3_bucket: &str, record: &Record) -> io::Result<()> {
+    let d = serde_json::to_vec(record).map_err(|e| {
+        Error::new(
+            ErrorKind::Other,
+            format!("failed to serialize audit record {}", e),
+        )
+    })?;
+    info!(
+        "writing audit record for operation '{}'",
+        record.operation_id
+    );
+    s3_manager
+        .put_bytes(
+            d,
+            Arc::new(s3_bucket.to_string()),
+            Arc::new(record.s3_key()),
+        )
+        .await
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to write audit record ({})", e.message()),
+            )
+        })
+}
+
+/// Lists the audit records of the cluster, oldest first.
+pub async fn list(s3_manager: &s3::Manager, s3_bucket: &str, id: &str) -> io::Result<Vec<Record>> {
+    let objects = s3_manager
+        .list_objects(
+            Arc::new(s3_bucket.to_string()),
+            Some(Arc::new(s3::append_slash(
+                &StorageNamespace::AuditDir(id.to_string()).encode(),
+            ))),
+        )
+        .await
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to list audit records ({})", e.message()),
+            )
+        })?;
+
+    let mut keys: Vec<String> = objects
+        .iter()
+        .filter_map(|o| o.key().map(String::from))
+        // skips the session transcripts under the same prefix
+        .filter(|k| k.ends_with(".json"))
+        .collect();
+    keys.sort();
+
+    let mut records = Vec::new();
+    for key in keys.iter() {
+        let d = s3_manager
+            .get_bytes(s3_bucket, key, None)
+            .await
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("failed to read audit record '{}' ({})", key, e.message()),
+                )
+            })?;
+        if let Some(d) = d {
+            let record: Record = serde_json::from_slice(&d).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid audit record '{}': {}", key, e),
+                )
+            })?;
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+#[test]
+fn test_record() {
+    let op = Operation {
+        id: String::from("op-1"),
+        operator: String::from("alice"),
+        command: String::from("subnet create"),
+    };
+    let h = inputs_sha256(&[String::from("apply"), String::from("-s")], Some(b"id: x"));
+    assert_eq!(h.len(), 64);
+    assert_ne!(
+        h,
+        inputs_sha256(&[String::from("apply-s")], Some(b"id: x")),
+        "argument boundaries must change the hash"
+    );
+
+    let mut record = Record::new("test", &op, &h);
+    assert!(record.s3_key().starts_with("test/audit/"));
+    assert!(record.s3_key().ends_with("Z-op-1.json"));
+
+    record.finish(Some(String::from("failed")));
+    assert_eq!(record.outcome, Some(Outcome::Failure));
+    let d = serde_json::to_string(&record).unwrap();
+    assert!(d.contains("\"outcome\":\"failure\""));
+    assert!(!d.contains("caller_arn"));
+    let parsed: Record = serde_json::from_str(&d).unwrap();
+    assert_eq!(parsed, record);
+}
@@ -0,0 +1,143 @@
+use std::{
+    collections::HashMap,
+    io::{self, Error, ErrorKind},
+};
+
+use aws_sdk_cloudformation::model::Tag;
+use chrono::{DateTime, Duration, Utc};
+
+/// Stack tags to find the cluster (and its spec in the S3 bucket)
+/// of the tagged stacks, without the local spec file.
+pub const TAG_CLUSTER_ID: &str = "avalanche-ops:cluster-id";
+pub const TAG_S3_BUCKET: &str = "avalanche-ops:s3-bucket";
+/// Stack tag of the time (RFC3339) after which "reap" deletes the cluster.
+pub const TAG_EXPIRES_AT: &str = "avalanche-ops:expires-at";
+
+/// Minimum "auto_delete_after", to not delete the cluster while "apply" is still running.
+pub const MIN_AUTO_DELETE_AFTER_MINUTES: i64 = 60;
+
+/// Returns the stack tags of the cluster, which propagate to the stack resources.
+pub fn stack_tags(cluster_id: &str, s3_bucket: &str) -> Vec<Tag> {
+    vec![
+        Tag::builder().key(TAG_CLUSTER_ID).value(cluster_id).build(),
+        Tag::builder().key(TAG_S3_BUCKET).value(s3_bucket).build(),
+    ]
+}
+
+/// Parses the "auto_delete_after" duration (e.g., "90m", "72h", "14d").
+pub fn parse_duration(s: &str) -> io::Result<Duration> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "invalid 'auto_delete_after' '{}' (e.g., '90m', '72h', '14d')",
+                s
+            ),
+        )
+    };
+    let s = s.trim();
+    if s.len() < 2 {
+        return Err(invalid());
+    }
+    let (n, unit) = s.split_at(s.len() - 1);
+    let n = n.parse::<i64>().map_err(|_| invalid())?;
+    let d = match unit {
+        "m" => Duration::minutes(n),
+        "h" => Duration::hours(n),
+        "d" => Duration::days(n),
+        _ => return Err(invalid()),
+    };
+    if d < Duration::minutes(MIN_AUTO_DELETE_AFTER_MINUTES) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "'auto_delete_after' '{}' <minimum {} minutes",
+                s, MIN_AUTO_DELETE_AFTER_MINUTES
+            ),
+        ));
+    }
+    Ok(d)
+}
+
+/// Represents the cluster found from the stack tags.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Tagged {
+    pub cluster_id: String,
+    pub s3_bucket: String,
+    /// Set for the CI preview networks.
+    pub job_id: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl Tagged {
+    /// Returns None if the stack was not created by avalanche-ops with the tags.
+    pub fn from_tags(tags: &HashMap<String, String>) -> Option<Self> {
+        let cluster_id = tags.get(TAG_CLUSTER_ID)?.clone();
+        let s3_bucket = tags.get(TAG_S3_BUCKET)?.clone();
+        let expires_at = tags
+            .get(TAG_EXPIRES_AT)
+            .and_then(|v| DateTime::parse_from_rfc3339(v).ok())
+            .map(|v| v.with_timezone(&Utc));
+        Some(Self {
+            cluster_id,
+            s3_bucket,
+            job_id: tags.get(crate::ci_preview::TAG_CI_JOB_ID).cloned(),
+            expires_at,
+        })
+    }
+
+    /// Returns true if the cluster belongs to the CI job, or has expired.
+    pub fn should_reap(&self, job_id: Option<&str>, now: DateTime<Utc>) -> bool {
+        if let Some(job_id) = job_id {
+            return self.job_id.as_deref() == Some(job_id);
+        }
+        matches!(self.expires_at, Some(v) if now >= v)
+    }
+}
+
+/// Returns the expiry of the cluster from the "auto_delete_after" of its spec,
+/// counted from the creation of its first stack.
+pub fn expires_at(created_at: DateTime<Utc>, auto_delete_after: &str) -> io::Result<DateTime<Utc>> {
+    Ok(created_at + parse_duration(auto_delete_after)?)
+}
+
+#[test]
+fn test_auto_delete() {
+    assert_eq!(parse_duration("90m").unwrap(), Duration::minutes(90));
+    assert_eq!(parse_duration("72h").unwrap(), Duration::hours(72));
+    assert_eq!(parse_duration(" 14d ").unwrap(), Duration::days(14));
+    assert!(parse_duration("30m").is_err());
+    assert!(parse_duration("72").is_err());
+    assert!(parse_duration("h").is_err());
+    assert!(parse_duration("3w").is_err());
+
+    let now = Utc::now();
+    assert_eq!(expires_at(now, "2h").unwrap(), now + Duration::hours(2));
+
+    let mut tags: HashMap<String, String> = stack_tags("aops-custom-test", "bucket")
+        .into_iter()
+        .map(|t| (t.key.unwrap(), t.value.unwrap()))
+        .collect();
+    let tagged = Tagged::from_tags(&tags).unwrap();
+    assert_eq!(tagged.cluster_id, "aops-custom-test");
+    assert_eq!(tagged.s3_bucket, "bucket");
+    assert!(tagged.job_id.is_none());
+    assert!(!tagged.should_reap(None, now));
+    assert!(!tagged.should_reap(Some("1234567890"), now));
+
+    tags.insert(
+        String::from(TAG_EXPIRES_AT),
+        (now + Duration::hours(1)).to_rfc3339(),
+    );
+    tags.insert(
+        String::from(crate::ci_preview::TAG_CI_JOB_ID),
+        String::from("1234567890"),
+    );
+    let tagged = Tagged::from_tags(&tags).unwrap();
+    assert!(tagged.should_reap(Some("1234567890"), now));
+    assert!(!tagged.should_reap(Some("other"), now));
+    assert!(!tagged.should_reap(None, now));
+    assert!(tagged.should_reap(None, now + Duration::hours(2)));
+
+    assert!(Tagged::from_tags(&HashMap::new()).is_none());
+}
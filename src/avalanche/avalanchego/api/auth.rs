@@ -0,0 +1,63 @@
+use std::{
+    io::{self, Error, ErrorKind},
+    time::Duration,
+};
+
+use log::info;
+
+use crate::{avalanche::avalanchego::api::jsonrpc, utils::http};
+
+/// Auth API path, the only API that does not require the token.
+pub const PATH: &str = "/ext/auth";
+
+/// Issues a new token for all API endpoints of the node ("*").
+/// The tokens are signed with the password hash salted per node process,
+/// so the token is only valid on the node (until it restarts).
+/// ref. https://docs.avax.network/apis/avalanchego/apis/auth#authnewtoken
+pub async fn new_token(url: &str, password: &str) -> io::Result<String> {
+    info!("issuing new API auth token for {}", url);
+
+    let d = serde_json::json!({
+        "jsonrpc": jsonrpc::DEFAULT_VERSION,
+        "id": jsonrpc::DEFAULT_ID,
+        "method": "auth.newToken",
+        "params": {
+            "password": password,
+            "endpoints": ["*"],
+        },
+    });
+    let req = http::create_json_post(url, PATH, &d.to_string())?;
+    let buf = http::read_bytes(
+        req,
+        Duration::from_secs(10),
+        url.starts_with("https"),
+        false,
+    )
+    .await?;
+
+    let resp: serde_json::Value = serde_json::from_slice(&buf)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to decode {}", e)))?;
+    if let Some(err) = resp.get("error") {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("auth.newToken failed ({})", err),
+        ));
+    }
+    match resp
+        .get("result")
+        .and_then(|v| v.get("token"))
+        .and_then(|v| v.as_str())
+    {
+        Some(v) => Ok(v.to_string()),
+        None => Err(Error::new(
+            ErrorKind::Other,
+            "auth.newToken returned no token",
+        )),
+    }
+}
+
+/// Issues a new token and attaches it to all subsequent API calls to the node.
+pub async fn authorize(url: &str, password: &str) -> io::Result<()> {
+    let token = new_token(url, password).await?;
+    http::set_bearer_token(url, &token)
+}
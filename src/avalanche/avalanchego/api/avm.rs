@@ -70,6 +70,7 @@ pub async fn get_balance(url: &str, path: &str, xaddr: &str) -> io::Result<GetBa
             info!("sending via curl --insecure");
             let mut cmd = Command::new("curl");
             cmd.arg("--insecure");
+            cmd.args(http::curl_auth_args(joined.as_str()));
             cmd.arg("-X POST");
             cmd.arg("--header 'content-type:application/json;'");
             cmd.arg(format!("--data '{}'", d));
@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     avalanche::avalanchego::api::jsonrpc,
-    utils::{big_int, http},
+    utils::{big_int, http, websocket},
 };
 
 /// ref. https://docs.avax.network/build/avalanchego-apis/c-chain#eth_getassetbalance
@@ -44,6 +44,7 @@ pub async fn get_balance(url: &str, path: &str, paddr: &str) -> io::Result<GetBa
             info!("sending via curl --insecure");
             let mut cmd = Command::new("curl");
             cmd.arg("--insecure");
+            cmd.args(http::curl_auth_args(joined.as_str()));
             cmd.arg("-X POST");
             cmd.arg("--header 'content-type:application/json;'");
             cmd.arg(format!("--data '{}'", d));
@@ -85,3 +86,295 @@ pub async fn get_balance(url: &str, path: &str, paddr: &str) -> io::Result<GetBa
     };
     Ok(resp)
 }
+
+/// ref. https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_chainid
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct ChainIdResponse {
+    pub jsonrpc: String,
+    pub id: u32,
+    pub result: String,
+}
+
+/// Calls "eth_chainId" over the websocket (e.g., "ws://[ADDR]:9650/ext/bc/C/ws"),
+/// to verify the websocket subscriptions are reachable.
+pub async fn chain_id_via_websocket(ws_url: &str) -> io::Result<u64> {
+    let mut data = jsonrpc::DataWithParamsArray::default();
+    data.method = String::from("eth_chainId");
+    data.params = Some(Vec::new());
+    let d = data.encode_json()?;
+
+    let resp = websocket::request(ws_url, &d, Duration::from_secs(10)).await?;
+    let resp: ChainIdResponse = serde_json::from_str(&resp).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("failed to decode '{}' ({})", resp, e),
+        )
+    })?;
+    u64::from_str_radix(resp.result.trim_start_matches("0x"), 16).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid chain ID '{}' ({})", resp.result, e),
+        )
+    })
+}
+
+/// C-chain JSON-RPC path of the node.
+pub const C_CHAIN_RPC_PATH: &str = "/ext/bc/C/rpc";
+
+/// Sends the JSON-RPC request to the full RPC URL
+/// (e.g., "http://[ADDR]:9650/ext/bc/C/rpc"), and returns the "result" field.
+async fn call(
+    rpc_url: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> io::Result<serde_json::Value> {
+    let d = serde_json::json!({
+        "jsonrpc": jsonrpc::DEFAULT_VERSION,
+        "id": jsonrpc::DEFAULT_ID,
+        "method": method,
+        "params": params,
+    });
+    let req = http::create_json_post(rpc_url, "", &d.to_string())?;
+    let buf = http::read_bytes(
+        req,
+        Duration::from_secs(15),
+        rpc_url.starts_with("https"),
+        false,
+    )
+    .await?;
+
+    let resp: serde_json::Value = serde_json::from_slice(&buf)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to decode {}", e)))?;
+    if let Some(err) = resp.get("error") {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("{} failed ({})", method, err),
+        ));
+    }
+    match resp.get("result") {
+        Some(v) => Ok(v.clone()),
+        None => Err(Error::new(
+            ErrorKind::Other,
+            format!("{} returned no result", method),
+        )),
+    }
+}
+
+fn parse_hex_u128(v: &serde_json::Value) -> io::Result<u128> {
+    let s = v.as_str().unwrap_or_default();
+    u128::from_str_radix(s.trim_start_matches("0x"), 16).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid hex quantity '{}' ({})", v, e),
+        )
+    })
+}
+
+/// ref. https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_chainid
+pub async fn chain_id(rpc_url: &str) -> io::Result<u64> {
+    Ok(parse_hex_u128(&call(rpc_url, "eth_chainId", serde_json::json!([])).await?)? as u64)
+}
+
+/// ref. https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_blocknumber
+pub async fn block_number(rpc_url: &str) -> io::Result<u64> {
+    Ok(parse_hex_u128(&call(rpc_url, "eth_blockNumber", serde_json::json!([])).await?)? as u64)
+}
+
+/// ref. https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_gasprice
+pub async fn gas_price(rpc_url: &str) -> io::Result<u128> {
+    parse_hex_u128(&call(rpc_url, "eth_gasPrice", serde_json::json!([])).await?)
+}
+
+/// Returns the nonce of the address including the pending transactions.
+/// ref. https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_gettransactioncount
+pub async fn get_transaction_count(rpc_url: &str, eth_addr: &str) -> io::Result<u64> {
+    let result = call(
+        rpc_url,
+        "eth_getTransactionCount",
+        serde_json::json!([eth_addr, "pending"]),
+    )
+    .await?;
+    Ok(parse_hex_u128(&result)? as u64)
+}
+
+/// Returns the transaction hash.
+/// ref. https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_sendrawtransaction
+pub async fn send_raw_transaction(rpc_url: &str, raw: &[u8]) -> io::Result<String> {
+    let result = call(
+        rpc_url,
+        "eth_sendRawTransaction",
+        serde_json::json!([format!("0x{}", hex::encode(raw))]),
+    )
+    .await?;
+    Ok(result.as_str().unwrap_or_default().to_string())
+}
+
+/// Executes the read-only call (e.g., precompile getter) at the latest block,
+/// and returns the output bytes.
+/// ref. https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_call
+pub async fn call_contract(rpc_url: &str, to: &str, data: &[u8]) -> io::Result<Vec<u8>> {
+    let result = call(
+        rpc_url,
+        "eth_call",
+        serde_json::json!([{"to": to, "data": format!("0x{}", hex::encode(data))}, "latest"]),
+    )
+    .await?;
+    let s = result.as_str().unwrap_or_default();
+    hex::decode(s.trim_start_matches("0x")).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid eth_call output '{}' ({})", s, e),
+        )
+    })
+}
+
+/// Returns None if the transaction is not yet accepted,
+/// or whether the accepted transaction succeeded.
+/// ref. https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_gettransactionreceipt
+pub async fn get_transaction_receipt_status(
+    rpc_url: &str,
+    tx_hash: &str,
+) -> io::Result<Option<bool>> {
+    let result = call(
+        rpc_url,
+        "eth_getTransactionReceipt",
+        serde_json::json!([tx_hash]),
+    )
+    .await?;
+    if result.is_null() {
+        return Ok(None);
+    }
+    Ok(Some(parse_hex_u128(&result["status"])? == 1))
+}
+
+/// Represents the event log in the receipt or "eth_getLogs".
+/// ref. https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_getfilterchanges
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Log {
+    pub address: String,
+    pub topics: Vec<String>,
+    pub data: String,
+    #[serde(default)]
+    pub block_number: String,
+}
+
+/// Waits for the accepted receipt, and returns its logs.
+/// Fails if the transaction reverted or is not accepted in time.
+pub async fn wait_for_receipt_logs(
+    rpc_url: &str,
+    tx_hash: &str,
+    timeout_dur: Duration,
+) -> io::Result<Vec<Log>> {
+    let start = std::time::Instant::now();
+    loop {
+        let result = call(
+            rpc_url,
+            "eth_getTransactionReceipt",
+            serde_json::json!([tx_hash]),
+        )
+        .await?;
+        if !result.is_null() {
+            if parse_hex_u128(&result["status"])? != 1 {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!("transaction {} reverted", tx_hash),
+                ));
+            }
+            return serde_json::from_value(result["logs"].clone()).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid receipt logs ({})", e),
+                )
+            });
+        }
+        if start.elapsed() > timeout_dur {
+            return Err(Error::new(
+                ErrorKind::TimedOut,
+                format!("transaction {} not accepted in {:?}", tx_hash, timeout_dur),
+            ));
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// Returns the logs of the address from the block, where "None" topic matches any.
+/// ref. https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_getlogs
+pub async fn get_logs(
+    rpc_url: &str,
+    address: &str,
+    topics: &[Option<String>],
+    from_block: u64,
+) -> io::Result<Vec<Log>> {
+    let result = call(
+        rpc_url,
+        "eth_getLogs",
+        serde_json::json!([{
+            "address": address,
+            "topics": topics,
+            "fromBlock": format!("0x{:x}", from_block),
+            "toBlock": "latest",
+        }]),
+    )
+    .await?;
+    serde_json::from_value(result)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("invalid logs ({})", e)))
+}
+
+/// Returns the deployed bytecode (empty for the EOA).
+/// ref. https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_getcode
+pub async fn get_code(rpc_url: &str, address: &str) -> io::Result<Vec<u8>> {
+    let result = call(
+        rpc_url,
+        "eth_getCode",
+        serde_json::json!([address, "latest"]),
+    )
+    .await?;
+    let s = result.as_str().unwrap_or_default();
+    hex::decode(s.trim_start_matches("0x")).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid eth_getCode output '{}' ({})", s, e),
+        )
+    })
+}
+
+/// Returns the balance in wei on the full RPC URL.
+/// ref. https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_getbalance
+pub async fn get_balance_wei(rpc_url: &str, address: &str) -> io::Result<u128> {
+    parse_hex_u128(
+        &call(
+            rpc_url,
+            "eth_getBalance",
+            serde_json::json!([address, "latest"]),
+        )
+        .await?,
+    )
+}
+
+/// Subscribes to the new block headers over the websocket, and waits for
+/// the first notification. Returns the block number of the notification.
+/// ref. https://docs.avax.network/apis/avalanchego/apis/c-chain#eth_subscribe
+pub async fn new_head_via_websocket(ws_url: &str, timeout_dur: Duration) -> io::Result<u64> {
+    let d = serde_json::json!({
+        "jsonrpc": jsonrpc::DEFAULT_VERSION,
+        "id": jsonrpc::DEFAULT_ID,
+        "method": "eth_subscribe",
+        "params": ["newHeads"],
+    });
+    let msgs = websocket::request_messages(ws_url, &d.to_string(), 2, timeout_dur).await?;
+    let notification: serde_json::Value = serde_json::from_str(&msgs[1]).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("failed to decode '{}' ({})", msgs[1], e),
+        )
+    })?;
+    Ok(parse_hex_u128(&notification["params"]["result"]["number"])? as u64)
+}
+
+#[test]
+fn test_parse_hex_u128() {
+    assert_eq!(parse_hex_u128(&serde_json::json!("0xa86a")).unwrap(), 43114);
+    assert_eq!(parse_hex_u128(&serde_json::json!("0x0")).unwrap(), 0);
+    assert!(parse_hex_u128(&serde_json::json!(null)).is_err());
+}
@@ -101,6 +101,7 @@ pub async fn check(u: Arc<String>, liveness: bool) -> io::Result<Response> {
             info!("sending via curl --insecure");
             let mut cmd = Command::new("curl");
             cmd.arg("--insecure");
+            cmd.args(http::curl_auth_args(joined.as_str()));
             cmd.arg(joined.as_str());
 
             let output = cmd.output()?;
@@ -0,0 +1,149 @@
+use std::{
+    io::{self, Error, ErrorKind},
+    time::Duration,
+};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    avalanche::avalanchego::api::{eth, jsonrpc, platform},
+    utils::http,
+};
+
+/// Index of the accepted C-chain blocks.
+/// ref. https://docs.avax.network/apis/avalanchego/apis/index-api
+pub const C_CHAIN_BLOCK_PATH: &str = "ext/index/C/block";
+/// Index of the accepted P-chain blocks.
+pub const P_CHAIN_BLOCK_PATH: &str = "ext/index/P/block";
+
+/// Represents the index backfill progress of a chain.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct ChainBackfill {
+    /// e.g., "C", "P".
+    pub chain: String,
+    /// Number of the accepted blocks in the index.
+    pub indexed: u64,
+    /// Height of the last accepted block of the chain.
+    pub height: u64,
+}
+
+impl ChainBackfill {
+    /// The index starts with the first block accepted after the genesis,
+    /// so the fully indexed chain has as many blocks as its height.
+    pub fn progress_percent(&self) -> f64 {
+        if self.height == 0 || self.indexed >= self.height {
+            return 100_f64;
+        }
+        self.indexed as f64 * 100_f64 / self.height as f64
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.indexed >= self.height
+    }
+}
+
+/// Returns the number of the accepted containers in the index
+/// (e.g., "ext/index/C/block"), or zero if none is indexed yet.
+/// ref. https://docs.avax.network/apis/avalanchego/apis/index-api#indexgetlastaccepted
+pub async fn get_indexed(url: &str, path: &str) -> io::Result<u64> {
+    let d = serde_json::json!({
+        "jsonrpc": jsonrpc::DEFAULT_VERSION,
+        "id": jsonrpc::DEFAULT_ID,
+        "method": "index.getLastAccepted",
+        "params": { "encoding": "hex" },
+    });
+    let req = http::create_json_post(url, path, &d.to_string())?;
+    let buf = http::read_bytes(
+        req,
+        Duration::from_secs(15),
+        url.starts_with("https"),
+        false,
+    )
+    .await?;
+    let resp: serde_json::Value = serde_json::from_slice(&buf)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to decode {}", e)))?;
+    parse_indexed(&resp)
+}
+
+fn parse_indexed(resp: &serde_json::Value) -> io::Result<u64> {
+    if let Some(err) = resp.get("error") {
+        // e.g., "no containers have been accepted"
+        if err.to_string().contains("no containers") {
+            return Ok(0);
+        }
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("index.getLastAccepted failed ({})", err),
+        ));
+    }
+    let index = resp["result"]["index"].as_str().unwrap_or_default();
+    let index = index.parse::<u64>().map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid index '{}' ({})", index, e),
+        )
+    })?;
+    Ok(index + 1)
+}
+
+/// Returns the backfill progress of the C-chain and P-chain indexes of the node.
+/// The chains whose index is not available (e.g., still bootstrapping) are skipped.
+pub async fn get_backfill(url: &str) -> Vec<ChainBackfill> {
+    info!("getting index backfill progress via {}", url);
+
+    let mut backfill = Vec::new();
+    let c_height = eth::block_number(&format!("{}{}", url, eth::C_CHAIN_RPC_PATH)).await;
+    match (get_indexed(url, C_CHAIN_BLOCK_PATH).await, c_height) {
+        (Ok(indexed), Ok(height)) => backfill.push(ChainBackfill {
+            chain: String::from("C"),
+            indexed,
+            height,
+        }),
+        (Err(e), _) | (_, Err(e)) => warn!("failed to get C-chain index progress ({})", e),
+    }
+    match (
+        get_indexed(url, P_CHAIN_BLOCK_PATH).await,
+        platform::get_height(url).await,
+    ) {
+        (Ok(indexed), Ok(height)) => backfill.push(ChainBackfill {
+            chain: String::from("P"),
+            indexed,
+            height,
+        }),
+        (Err(e), _) | (_, Err(e)) => warn!("failed to get P-chain index progress ({})", e),
+    }
+    backfill
+}
+
+#[test]
+fn test_parse_indexed() {
+    let resp = serde_json::json!({
+        "jsonrpc": "2.0",
+        "result": {
+            "id": "6fXf5hncR8LXvwtM8iezFQBpK5cubV6y1dWgpJCcNyzGB1EzY",
+            "bytes": "0x00",
+            "timestamp": "2021-04-02T15:34:00.262979-07:00",
+            "encoding": "hex",
+            "index": "9"
+        },
+        "id": 1
+    });
+    assert_eq!(parse_indexed(&resp).unwrap(), 10);
+
+    let resp = serde_json::json!({
+        "jsonrpc": "2.0",
+        "error": {"code": -32000, "message": "no containers have been accepted", "data": null},
+        "id": 1
+    });
+    assert_eq!(parse_indexed(&resp).unwrap(), 0);
+
+    let b = ChainBackfill {
+        chain: String::from("C"),
+        indexed: 25,
+        height: 100,
+    };
+    assert_eq!(b.progress_percent(), 25_f64);
+    assert!(!b.is_complete());
+}
@@ -9,7 +9,10 @@ use std::{
 use log::info;
 use serde::{Deserialize, Serialize};
 
-use crate::{avalanche::avalanchego::api::jsonrpc, utils::http};
+use crate::{
+    avalanche::{avalanchego::api::jsonrpc, bls},
+    utils::http,
+};
 
 /// ref. https://docs.avax.network/build/avalanchego-apis/info/#infogetnodeversion
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
@@ -97,6 +100,7 @@ pub async fn get_node_version(url: &str) -> io::Result<GetNodeVersionResponse> {
             info!("sending via curl --insecure");
             let mut cmd = Command::new("curl");
             cmd.arg("--insecure");
+            cmd.args(http::curl_auth_args(joined.as_str()));
             cmd.arg("-X POST");
             cmd.arg("--header 'content-type:application/json;'");
             cmd.arg(format!("--data '{}'", d));
@@ -186,6 +190,7 @@ pub async fn get_vms(url: &str) -> io::Result<GetVmsResponse> {
             info!("sending via curl --insecure");
             let mut cmd = Command::new("curl");
             cmd.arg("--insecure");
+            cmd.args(http::curl_auth_args(joined.as_str()));
             cmd.arg("-X POST");
             cmd.arg("--header 'content-type:application/json;'");
             cmd.arg(format!("--data '{}'", d));
@@ -227,3 +232,182 @@ pub async fn get_vms(url: &str) -> io::Result<GetVmsResponse> {
     };
     Ok(resp)
 }
+
+/// ref. https://docs.avax.network/apis/avalanchego/apis/info#infogetnodeid
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct GetNodeIdResponse {
+    pub jsonrpc: String,
+    pub id: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<GetNodeIdResult>,
+}
+
+/// ref. https://docs.avax.network/apis/avalanchego/apis/info#infogetnodeid
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+pub struct GetNodeIdResult {
+    #[serde(rename = "nodeID", skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    /// Only returned by the nodes with the BLS signer key.
+    #[serde(rename = "nodePOP", skip_serializing_if = "Option::is_none")]
+    pub node_pop: Option<bls::ProofOfPossession>,
+}
+
+/// e.g., "info.getNodeID".
+/// ref. https://docs.avax.network/apis/avalanchego/apis/info#infogetnodeid
+pub async fn get_node_id(url: &str) -> io::Result<GetNodeIdResponse> {
+    info!("getting node ID for {}", url);
+
+    let mut data = jsonrpc::DataWithParamsArray::default();
+    data.method = String::from("info.getNodeID");
+
+    let d = data.encode_json()?;
+
+    let resp: GetNodeIdResponse = {
+        if url.starts_with("https") {
+            let joined = http::join_uri(url, "ext/info")?;
+
+            // TODO: implement this with native Rust
+            info!("sending via curl --insecure");
+            let mut cmd = Command::new("curl");
+            cmd.arg("--insecure");
+            cmd.args(http::curl_auth_args(joined.as_str()));
+            cmd.arg("-X POST");
+            cmd.arg("--header 'content-type:application/json;'");
+            cmd.arg(format!("--data '{}'", d));
+            cmd.arg(joined.as_str());
+
+            let output = cmd.output()?;
+            match serde_json::from_slice(&output.stdout) {
+                Ok(p) => p,
+                Err(e) => {
+                    return Err(Error::new(
+                        ErrorKind::Other,
+                        format!("failed to decode {}", e),
+                    ));
+                }
+            }
+        } else {
+            let req = http::create_json_post(url, "ext/info", &d)?;
+            let buf = match http::read_bytes(
+                req,
+                Duration::from_secs(5),
+                url.starts_with("https"),
+                false,
+            )
+            .await
+            {
+                Ok(u) => u,
+                Err(e) => return Err(e),
+            };
+            match serde_json::from_slice(&buf) {
+                Ok(p) => p,
+                Err(e) => {
+                    return Err(Error::new(
+                        ErrorKind::Other,
+                        format!("failed to decode {}", e),
+                    ));
+                }
+            }
+        }
+    };
+    Ok(resp)
+}
+
+/// Transaction fees in nAVAX of the network.
+/// ref. https://docs.avax.network/apis/avalanchego/apis/info#infogettxfee
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+pub struct TxFee {
+    pub tx_fee: u64,
+    pub create_subnet_tx_fee: u64,
+    pub transform_subnet_tx_fee: u64,
+    pub add_primary_network_validator_fee: u64,
+    pub add_primary_network_delegator_fee: u64,
+    pub add_subnet_validator_fee: u64,
+    pub add_subnet_delegator_fee: u64,
+}
+
+/// e.g., "info.getTxFee".
+/// ref. https://docs.avax.network/apis/avalanchego/apis/info#infogettxfee
+pub async fn get_tx_fee(url: &str) -> io::Result<TxFee> {
+    info!("getting tx fee for {}", url);
+
+    let mut data = jsonrpc::DataWithParamsArray::default();
+    data.method = String::from("info.getTxFee");
+    let d = data.encode_json()?;
+
+    let req = http::create_json_post(url, "ext/info", &d)?;
+    let buf =
+        http::read_bytes(req, Duration::from_secs(5), url.starts_with("https"), false).await?;
+    let resp: serde_json::Value = match serde_json::from_slice(&buf) {
+        Ok(p) => p,
+        Err(e) => {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("failed to decode {}", e),
+            ));
+        }
+    };
+    match resp.get("result") {
+        Some(result) => parse_tx_fee(result),
+        None => Err(Error::new(
+            ErrorKind::Other,
+            format!("info.getTxFee returned no result ({})", resp),
+        )),
+    }
+}
+
+fn parse_tx_fee(v: &serde_json::Value) -> io::Result<TxFee> {
+    let parse_u64 = |field: &str| -> io::Result<u64> {
+        let s = match v.get(field).and_then(|x| x.as_str()) {
+            Some(s) => s,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!("missing field '{}' in {}", field, v),
+                ))
+            }
+        };
+        s.parse::<u64>().map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid {} '{}' ({})", field, s, e),
+            )
+        })
+    };
+    Ok(TxFee {
+        tx_fee: parse_u64("txFee")?,
+        create_subnet_tx_fee: parse_u64("createSubnetTxFee")?,
+        transform_subnet_tx_fee: parse_u64("transformSubnetTxFee")?,
+        add_primary_network_validator_fee: parse_u64("addPrimaryNetworkValidatorFee")?,
+        add_primary_network_delegator_fee: parse_u64("addPrimaryNetworkDelegatorFee")?,
+        add_subnet_validator_fee: parse_u64("addSubnetValidatorFee")?,
+        add_subnet_delegator_fee: parse_u64("addSubnetDelegatorFee")?,
+    })
+}
+
+#[test]
+fn test_get_node_id_response() {
+    let resp: GetNodeIdResponse = serde_json::from_str(
+        r#"{"jsonrpc":"2.0","result":{"nodeID":"NodeID-5mb46qkSBj81k9g9e4VFjGGSbaaSLFRzD","nodePOP":{"publicKey":"0x8f95","proofOfPossession":"0x86a3"}},"id":1}"#,
+    )
+    .unwrap();
+    let result = resp.result.unwrap();
+    assert_eq!(
+        result.node_id,
+        Some(String::from("NodeID-5mb46qkSBj81k9g9e4VFjGGSbaaSLFRzD"))
+    );
+    assert_eq!(result.node_pop.unwrap().proof_of_possession, "0x86a3");
+}
+
+#[test]
+fn test_parse_tx_fee() {
+    let v: serde_json::Value = serde_json::from_str(
+        r#"{"txFee":"1000000","createAssetTxFee":"10000000","createSubnetTxFee":"1000000000","transformSubnetTxFee":"10000000000","createBlockchainTxFee":"1000000000","addPrimaryNetworkValidatorFee":"0","addPrimaryNetworkDelegatorFee":"0","addSubnetValidatorFee":"1000000","addSubnetDelegatorFee":"1000000"}"#,
+    )
+    .unwrap();
+    let fee = parse_tx_fee(&v).unwrap();
+    assert_eq!(fee.tx_fee, 1000000);
+    assert_eq!(fee.transform_subnet_tx_fee, 10000000000);
+    assert_eq!(fee.add_primary_network_delegator_fee, 0);
+    assert!(parse_tx_fee(&serde_json::json!({"txFee": "1000000"})).is_err());
+}
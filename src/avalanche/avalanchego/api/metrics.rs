@@ -1278,6 +1278,7 @@ pub async fn get(u: Arc<String>) -> io::Result<Metrics> {
             info!("sending via curl --insecure");
             let mut cmd = Command::new("curl");
             cmd.arg("--insecure");
+            cmd.args(http::curl_auth_args(joined.as_str()));
             cmd.arg(joined.as_str());
 
             let output = cmd.output()?;
@@ -1,7 +1,9 @@
+pub mod auth;
 pub mod avax;
 pub mod avm;
 pub mod eth;
 pub mod health;
+pub mod index;
 pub mod info;
 pub mod jsonrpc;
 pub mod metrics;
@@ -10,7 +10,10 @@ use log::info;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    avalanche::avalanchego::api::{avax, jsonrpc},
+    avalanche::{
+        avalanchego::api::{avax, jsonrpc},
+        formatting,
+    },
     utils::http,
 };
 
@@ -78,6 +81,7 @@ pub async fn get_balance(url: &str, path: &str, paddr: &str) -> io::Result<GetBa
             info!("sending via curl --insecure");
             let mut cmd = Command::new("curl");
             cmd.arg("--insecure");
+            cmd.args(http::curl_auth_args(joined.as_str()));
             cmd.arg("-X POST");
             cmd.arg("--header 'content-type:application/json;'");
             cmd.arg(format!("--data '{}'", d));
@@ -246,6 +250,462 @@ impl _GetBalanceResponse {
     }
 }
 
+/// P-chain API path.
+pub const PATH: &str = "/ext/bc/P";
+
+/// Sends the JSON-RPC request to the P-chain API, and returns the "result" field.
+async fn call(url: &str, method: &str, params: serde_json::Value) -> io::Result<serde_json::Value> {
+    let d = serde_json::json!({
+        "jsonrpc": jsonrpc::DEFAULT_VERSION,
+        "id": jsonrpc::DEFAULT_ID,
+        "method": method,
+        "params": params,
+    });
+    let req = http::create_json_post(url, PATH, &d.to_string())?;
+    let buf = http::read_bytes(
+        req,
+        Duration::from_secs(30),
+        url.starts_with("https"),
+        false,
+    )
+    .await?;
+
+    let resp: serde_json::Value = match serde_json::from_slice(&buf) {
+        Ok(p) => p,
+        Err(e) => {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("failed to decode {}", e),
+            ));
+        }
+    };
+    if let Some(err) = resp.get("error") {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("{} failed ({})", method, err),
+        ));
+    }
+    match resp.get("result") {
+        Some(v) => Ok(v.clone()),
+        None => Err(Error::new(
+            ErrorKind::Other,
+            format!("{} returned no result", method),
+        )),
+    }
+}
+
+fn get_str(v: &serde_json::Value, field: &str) -> io::Result<String> {
+    match v.get(field).and_then(|x| x.as_str()) {
+        Some(s) => Ok(s.to_string()),
+        None => Err(Error::new(
+            ErrorKind::Other,
+            format!("missing field '{}' in {}", field, v),
+        )),
+    }
+}
+
+/// Fetches all the UTXOs of the address, and returns the codec-encoded bytes.
+/// ref. https://docs.avax.network/apis/avalanchego/apis/p-chain#platformgetutxos
+pub async fn get_utxos(url: &str, paddr: &str) -> io::Result<Vec<Vec<u8>>> {
+    info!("getting UTXOs for {} via {}", paddr, url);
+
+    const LIMIT: usize = 1024;
+    let mut utxos: Vec<Vec<u8>> = Vec::new();
+    let mut start_index: Option<serde_json::Value> = None;
+    loop {
+        let mut params = serde_json::json!({
+            "addresses": [paddr],
+            "limit": LIMIT,
+            "encoding": "hex",
+        });
+        if let Some(idx) = &start_index {
+            params["startIndex"] = idx.clone();
+        }
+        let result = call(url, "platform.getUTXOs", params).await?;
+
+        let fetched = match result.get("utxos").and_then(|x| x.as_array()) {
+            Some(v) => v.clone(),
+            None => Vec::new(),
+        };
+        for u in fetched.iter() {
+            let encoded = u.as_str().unwrap_or_default();
+            utxos.push(formatting::decode_hex_with_checksum(encoded)?);
+        }
+        if fetched.len() < LIMIT {
+            break;
+        }
+        start_index = result.get("endIndex").cloned();
+    }
+    Ok(utxos)
+}
+
+/// Returns the control keys and threshold of the subnet.
+/// ref. https://docs.avax.network/apis/avalanchego/apis/p-chain#platformgetsubnets
+pub async fn get_subnet_owners(url: &str, subnet_id: &str) -> io::Result<(Vec<String>, u32)> {
+    info!("getting subnet {} owners via {}", subnet_id, url);
+
+    let result = call(
+        url,
+        "platform.getSubnets",
+        serde_json::json!({ "ids": [subnet_id] }),
+    )
+    .await?;
+    let subnet = match result
+        .get("subnets")
+        .and_then(|x| x.as_array())
+        .and_then(|x| x.first())
+    {
+        Some(v) => v.clone(),
+        None => {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("subnet {} not found", subnet_id),
+            ));
+        }
+    };
+
+    let mut control_keys: Vec<String> = Vec::new();
+    if let Some(keys) = subnet.get("controlKeys").and_then(|x| x.as_array()) {
+        for k in keys.iter() {
+            control_keys.push(k.as_str().unwrap_or_default().to_string());
+        }
+    }
+    let threshold = get_str(&subnet, "threshold")?;
+    let threshold = threshold.parse::<u32>().map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid threshold '{}' ({})", threshold, e),
+        )
+    })?;
+    Ok((control_keys, threshold))
+}
+
+/// Returns the staking asset ID of the subnet (AVAX for the primary network).
+/// ref. https://docs.avax.network/apis/avalanchego/apis/p-chain#platformgetstakingassetid
+pub async fn get_staking_asset_id(url: &str, subnet_id: Option<&str>) -> io::Result<String> {
+    let params = match subnet_id {
+        Some(v) => serde_json::json!({ "subnetID": v }),
+        None => serde_json::json!({}),
+    };
+    let result = call(url, "platform.getStakingAssetID", params).await?;
+    get_str(&result, "assetID")
+}
+
+/// Issues the signed transaction bytes, and returns the transaction ID.
+/// ref. https://docs.avax.network/apis/avalanchego/apis/p-chain#platformissuetx
+pub async fn issue_tx(url: &str, tx: &[u8]) -> io::Result<String> {
+    let result = call(
+        url,
+        "platform.issueTx",
+        serde_json::json!({
+            "tx": formatting::encode_hex_with_checksum(tx),
+            "encoding": "hex",
+        }),
+    )
+    .await?;
+    get_str(&result, "txID")
+}
+
+/// Returns the transaction status (e.g., "Committed", "Dropped").
+/// ref. https://docs.avax.network/apis/avalanchego/apis/p-chain#platformgettxstatus
+pub async fn get_tx_status(url: &str, tx_id: &str) -> io::Result<String> {
+    let result = call(
+        url,
+        "platform.getTxStatus",
+        serde_json::json!({ "txID": tx_id }),
+    )
+    .await?;
+    get_str(&result, "status")
+}
+
+/// Subnet ID of the primary network.
+pub const PRIMARY_NETWORK_ID: &str = "11111111111111111111111111111111LpoYY";
+
+/// Represents a current or pending validator of the subnet.
+/// ref. https://docs.avax.network/apis/avalanchego/apis/p-chain#platformgetcurrentvalidators
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct SubnetValidator {
+    pub node_id: String,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub weight: u64,
+    pub pending: bool,
+}
+
+/// Returns the current and pending validators of the subnet.
+/// ref. https://docs.avax.network/apis/avalanchego/apis/p-chain#platformgetcurrentvalidators
+/// ref. https://docs.avax.network/apis/avalanchego/apis/p-chain#platformgetpendingvalidators
+pub async fn get_validators(url: &str, subnet_id: &str) -> io::Result<Vec<SubnetValidator>> {
+    info!("getting subnet {} validators via {}", subnet_id, url);
+
+    let mut validators: Vec<SubnetValidator> = Vec::new();
+    for (method, pending) in [
+        ("platform.getCurrentValidators", false),
+        ("platform.getPendingValidators", true),
+    ] {
+        let result = call(url, method, serde_json::json!({ "subnetID": subnet_id })).await?;
+        let fetched = match result.get("validators").and_then(|x| x.as_array()) {
+            Some(v) => v.clone(),
+            None => Vec::new(),
+        };
+        for v in fetched.iter() {
+            validators.push(parse_subnet_validator(v, pending)?);
+        }
+    }
+    Ok(validators)
+}
+
+fn parse_subnet_validator(v: &serde_json::Value, pending: bool) -> io::Result<SubnetValidator> {
+    let parse_u64 = |field: &str| -> io::Result<u64> {
+        // permissionless validators report "stakeAmount" instead of "weight"
+        let s = match get_str(v, field) {
+            Ok(s) => s,
+            Err(e) if field == "weight" => match get_str(v, "stakeAmount") {
+                Ok(s) => s,
+                Err(_) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        };
+        s.parse::<u64>().map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid {} '{}' ({})", field, s, e),
+            )
+        })
+    };
+    Ok(SubnetValidator {
+        node_id: get_str(v, "nodeID")?,
+        start_time: parse_u64("startTime")?,
+        end_time: parse_u64("endTime")?,
+        weight: parse_u64("weight")?,
+        pending,
+    })
+}
+
+/// Represents the reward status of a current primary network validator.
+/// ref. https://docs.avax.network/apis/avalanchego/apis/p-chain#platformgetcurrentvalidators
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ValidatorUptime {
+    pub node_id: String,
+    pub end_time: u64,
+    pub weight: u64,
+    /// Uptime measured by the queried node, in percent.
+    pub uptime_percent: f64,
+    pub connected: bool,
+    /// Reward in nAVAX if the validator meets the uptime requirement.
+    pub potential_reward: u64,
+}
+
+/// Returns the uptimes of the current primary network validators.
+/// All the current validators are returned if "node_ids" is empty.
+pub async fn get_validator_uptimes(
+    url: &str,
+    node_ids: &[String],
+) -> io::Result<Vec<ValidatorUptime>> {
+    info!("getting {} validator uptimes via {}", node_ids.len(), url);
+
+    let mut params = serde_json::json!({ "subnetID": PRIMARY_NETWORK_ID });
+    if !node_ids.is_empty() {
+        params["nodeIDs"] = serde_json::json!(node_ids);
+    }
+    let result = call(url, "platform.getCurrentValidators", params).await?;
+    let fetched = match result.get("validators").and_then(|x| x.as_array()) {
+        Some(v) => v.clone(),
+        None => Vec::new(),
+    };
+    let mut uptimes = Vec::new();
+    for v in fetched.iter() {
+        uptimes.push(parse_validator_uptime(v)?);
+    }
+    Ok(uptimes)
+}
+
+fn parse_validator_uptime(v: &serde_json::Value) -> io::Result<ValidatorUptime> {
+    let validator = parse_subnet_validator(v, false)?;
+    let uptime = get_str(v, "uptime")?;
+    let uptime = uptime.parse::<f64>().map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid uptime '{}' ({})", uptime, e),
+        )
+    })?;
+    let potential_reward = match get_str(v, "potentialReward") {
+        Ok(s) => s.parse::<u64>().map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid potentialReward '{}' ({})", s, e),
+            )
+        })?,
+        Err(_) => 0,
+    };
+    Ok(ValidatorUptime {
+        node_id: validator.node_id,
+        end_time: validator.end_time,
+        weight: validator.weight,
+        // older avalanchego reports the uptime in fraction (e.g., "0.9875")
+        uptime_percent: if uptime <= 1_f64 {
+            uptime * 100_f64
+        } else {
+            uptime
+        },
+        connected: v
+            .get("connected")
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false),
+        potential_reward,
+    })
+}
+
+/// Represents a delegation to the primary network validator.
+/// ref. https://docs.avax.network/apis/avalanchego/apis/p-chain#platformgetcurrentvalidators
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct Delegation {
+    pub tx_id: String,
+    pub node_id: String,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub stake_amount: u64,
+    /// P-chain addresses of the reward owner (e.g., "P-avax1...").
+    pub reward_owner_addresses: Vec<String>,
+    pub potential_reward: u64,
+}
+
+/// Represents a current primary network validator with its delegations.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct DelegatedValidator {
+    pub node_id: String,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub weight: u64,
+    /// Percentage of the delegator rewards the validator takes.
+    pub delegation_fee_percent: f64,
+    pub delegations: Vec<Delegation>,
+}
+
+impl DelegatedValidator {
+    /// Returns the sum of the delegated stakes.
+    pub fn delegated_weight(&self) -> u64 {
+        self.delegations.iter().map(|d| d.stake_amount).sum()
+    }
+}
+
+/// Returns the current primary network validators with their delegations.
+/// The delegations are only returned for the specified nodes, so "node_ids"
+/// must not be empty.
+pub async fn get_delegated_validators(
+    url: &str,
+    node_ids: &[String],
+) -> io::Result<Vec<DelegatedValidator>> {
+    info!(
+        "getting {} validator delegations via {}",
+        node_ids.len(),
+        url
+    );
+    if node_ids.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, "empty node IDs"));
+    }
+
+    let result = call(
+        url,
+        "platform.getCurrentValidators",
+        serde_json::json!({ "subnetID": PRIMARY_NETWORK_ID, "nodeIDs": node_ids }),
+    )
+    .await?;
+    let fetched = match result.get("validators").and_then(|x| x.as_array()) {
+        Some(v) => v.clone(),
+        None => Vec::new(),
+    };
+    let mut validators = Vec::new();
+    for v in fetched.iter() {
+        validators.push(parse_delegated_validator(v)?);
+    }
+    Ok(validators)
+}
+
+fn parse_delegated_validator(v: &serde_json::Value) -> io::Result<DelegatedValidator> {
+    let validator = parse_subnet_validator(v, false)?;
+    let delegation_fee = get_str(v, "delegationFee").unwrap_or_else(|_| String::from("0"));
+    let delegation_fee_percent = delegation_fee.parse::<f64>().map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid delegationFee '{}' ({})", delegation_fee, e),
+        )
+    })?;
+
+    let mut delegations = Vec::new();
+    if let Some(delegators) = v.get("delegators").and_then(|x| x.as_array()) {
+        for d in delegators.iter() {
+            let parsed = parse_subnet_validator(d, false)?;
+            let reward_owner_addresses = d
+                .get("rewardOwner")
+                .and_then(|x| x.get("addresses"))
+                .and_then(|x| x.as_array())
+                .map(|addrs| {
+                    addrs
+                        .iter()
+                        .filter_map(|a| a.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default();
+            delegations.push(Delegation {
+                tx_id: get_str(d, "txID")?,
+                node_id: parsed.node_id,
+                start_time: parsed.start_time,
+                end_time: parsed.end_time,
+                stake_amount: parsed.weight,
+                reward_owner_addresses,
+                potential_reward: get_str(d, "potentialReward")
+                    .ok()
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .unwrap_or(0),
+            });
+        }
+    }
+    Ok(DelegatedValidator {
+        node_id: validator.node_id,
+        start_time: validator.start_time,
+        end_time: validator.end_time,
+        weight: validator.weight,
+        delegation_fee_percent,
+        delegations,
+    })
+}
+
+/// Returns the minimum stakes of the primary network validator
+/// and delegator in nAVAX.
+/// ref. https://docs.avax.network/apis/avalanchego/apis/p-chain#platformgetminstake
+pub async fn get_min_stake(url: &str) -> io::Result<(u64, u64)> {
+    info!("getting min stake via {}", url);
+
+    let result = call(url, "platform.getMinStake", serde_json::json!({})).await?;
+    let parse_u64 = |field: &str| -> io::Result<u64> {
+        let s = get_str(&result, field)?;
+        s.parse::<u64>().map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid {} '{}' ({})", field, s, e),
+            )
+        })
+    };
+    Ok((
+        parse_u64("minValidatorStake")?,
+        parse_u64("minDelegatorStake")?,
+    ))
+}
+
+/// Returns the height of the last accepted P-chain block.
+/// ref. https://docs.avax.network/apis/avalanchego/apis/p-chain#platformgetheight
+pub async fn get_height(url: &str) -> io::Result<u64> {
+    let result = call(url, "platform.getHeight", serde_json::json!({})).await?;
+    let s = get_str(&result, "height")?;
+    s.parse::<u64>().map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid height '{}' ({})", s, e),
+        )
+    })
+}
+
 #[test]
 fn test_convert() {
     // ref. https://docs.avax.network/build/avalanchego-apis/p-chain/#platformgetbalance
@@ -299,3 +759,104 @@ fn test_convert() {
     };
     assert_eq!(parsed, expected);
 }
+
+/// Returns the percentage of the current stake (weight) held by the nodes.
+/// Pending validators are not counted.
+pub fn stake_percent(validators: &[SubnetValidator], node_ids: &[String]) -> f64 {
+    let mut total: u128 = 0;
+    let mut held: u128 = 0;
+    for v in validators.iter().filter(|v| !v.pending) {
+        total += v.weight as u128;
+        if node_ids.contains(&v.node_id) {
+            held += v.weight as u128;
+        }
+    }
+    if total == 0 {
+        return 0_f64;
+    }
+    held as f64 * 100_f64 / total as f64
+}
+
+#[test]
+fn test_stake_percent() {
+    let v = |node_id: &str, weight: u64, pending: bool| SubnetValidator {
+        node_id: node_id.to_string(),
+        start_time: 0,
+        end_time: 0,
+        weight,
+        pending,
+    };
+    let validators = vec![
+        v("NodeID-A", 100, false),
+        v("NodeID-B", 300, false),
+        v("NodeID-C", 1000, true),
+    ];
+    assert_eq!(
+        stake_percent(&validators, &[String::from("NodeID-A")]),
+        25_f64
+    );
+    assert_eq!(
+        stake_percent(&validators, &[String::from("NodeID-C")]),
+        0_f64
+    );
+    assert_eq!(stake_percent(&[], &[String::from("NodeID-A")]), 0_f64);
+}
+
+#[test]
+fn test_parse_subnet_validator() {
+    let v: serde_json::Value = serde_json::from_str(
+        r#"{"txID":"2NNkpYTGfTFLSGXJcHtVv6drwVU2cczhmjK2uhvwDyxwsjzZMm","startTime":"1600368632","endTime":"1602960455","weight":"20","nodeID":"NodeID-5mb46qkSBj81k9g9e4VFjGGSbaaSLFRzD"}"#,
+    )
+    .unwrap();
+    let parsed = parse_subnet_validator(&v, false).unwrap();
+    assert_eq!(
+        parsed,
+        SubnetValidator {
+            node_id: String::from("NodeID-5mb46qkSBj81k9g9e4VFjGGSbaaSLFRzD"),
+            start_time: 1600368632,
+            end_time: 1602960455,
+            weight: 20,
+            pending: false,
+        }
+    );
+}
+
+#[test]
+fn test_parse_validator_uptime() {
+    let v: serde_json::Value = serde_json::from_str(
+        r#"{"txID":"2NNkpYTGfTFLSGXJcHtVv6drwVU2cczhmjK2uhvwDyxwsjzZMm","startTime":"1600368632","endTime":"1602960455","stakeAmount":"2000000000000","nodeID":"NodeID-5mb46qkSBj81k9g9e4VFjGGSbaaSLFRzD","potentialReward":"117431493426","uptime":"0.9875","connected":true}"#,
+    )
+    .unwrap();
+    let parsed = parse_validator_uptime(&v).unwrap();
+    assert_eq!(parsed.weight, 2000000000000);
+    assert_eq!(parsed.uptime_percent, 98.75);
+    assert_eq!(parsed.potential_reward, 117431493426);
+    assert!(parsed.connected);
+
+    let mut v = v;
+    v["uptime"] = serde_json::json!("87.5000");
+    v["connected"] = serde_json::json!(false);
+    let parsed = parse_validator_uptime(&v).unwrap();
+    assert_eq!(parsed.uptime_percent, 87.5);
+    assert!(!parsed.connected);
+}
+
+#[test]
+fn test_parse_delegated_validator() {
+    let v: serde_json::Value = serde_json::from_str(
+        r#"{"txID":"2NNkpYTGfTFLSGXJcHtVv6drwVU2cczhmjK2uhvwDyxwsjzZMm","startTime":"1600368632","endTime":"1602960455","stakeAmount":"2000000000000","nodeID":"NodeID-5mb46qkSBj81k9g9e4VFjGGSbaaSLFRzD","delegationFee":"2.0000","uptime":"0.9900","connected":true,"delegators":[{"txID":"Bbai8nzGVcyn2VmeYcbS74zfjJLjDacGNVuzuvAQkHn1uWfoV","startTime":"1600368700","endTime":"1602960000","stakeAmount":"25000000000","nodeID":"NodeID-5mb46qkSBj81k9g9e4VFjGGSbaaSLFRzD","rewardOwner":{"locktime":"0","threshold":"1","addresses":["P-avax18jma8ppw3nhx5r4ap8clazz0dps7rv5u00z96u"]},"potentialReward":"1000000"}]}"#,
+    )
+    .unwrap();
+    let parsed = parse_delegated_validator(&v).unwrap();
+    assert_eq!(parsed.weight, 2000000000000);
+    assert_eq!(parsed.delegation_fee_percent, 2_f64);
+    assert_eq!(parsed.delegations.len(), 1);
+    assert_eq!(parsed.delegated_weight(), 25000000000);
+    assert_eq!(parsed.delegations[0].potential_reward, 1000000);
+    assert_eq!(
+        parsed.delegations[0].reward_owner_addresses,
+        vec![String::from(
+            "P-avax18jma8ppw3nhx5r4ap8clazz0dps7rv5u00z96u"
+        )]
+    );
+}
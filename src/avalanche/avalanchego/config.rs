@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     fs::{self, File},
     io::{self, Error, ErrorKind, Write},
     path::Path,
@@ -80,6 +81,11 @@ pub struct Config {
     /// MUST BE a valid path in remote host machine.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub staking_tls_cert_file: Option<String>,
+    /// BLS signer key, generated by "avalanched" if not exists.
+    /// Only supported by avalanchego v1.9.0 or later.
+    /// MUST BE a valid path in remote host machine.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub staking_signer_key_file: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub bootstrap_ips: Option<String>,
@@ -126,6 +132,16 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_ipcs_enabled: Option<bool>,
 
+    /// Set "true" to require the bearer token on all API calls
+    /// (except "/ext/auth" that issues the tokens).
+    /// ref. https://docs.avax.network/apis/avalanchego/apis/auth
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_auth_required: Option<bool>,
+    /// File of the password to issue the tokens.
+    /// MUST BE a valid path in remote host machine.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_auth_password_file: Option<String>,
+
     /// A list of whitelisted subnet IDs (comma-separated).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub whitelisted_subnets: Option<String>,
@@ -165,6 +181,11 @@ pub struct Config {
     pub network_minimum_timeout: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub network_require_validator_to_connect: Option<bool>,
+
+    /// Other avalanchego flags without the fields above
+    /// (e.g., "snow-mixed-query-num-push-vdr"), written as they are.
+    #[serde(flatten)]
+    pub extra_flags: BTreeMap<String, serde_json::Value>,
 }
 
 /// Default "config-file" path on the remote linux machines.
@@ -207,6 +228,8 @@ pub const DEFAULT_STAKING_PORT: u32 = 9651;
 pub const DEFAULT_STAKING_TLS_KEY_FILE: &str = "/etc/pki/tls/certs/avalanched.pki.key";
 /// MUST BE a valid path in remote host machine.
 pub const DEFAULT_STAKING_TLS_CERT_FILE: &str = "/etc/pki/tls/certs/avalanched.pki.crt";
+/// Suggested "staking-signer-key-file", next to the TLS staking key.
+pub const DEFAULT_STAKING_SIGNER_KEY_FILE: &str = "/etc/pki/tls/certs/avalanched.pki.signer.key";
 
 /// Default snow sample size.
 /// NOTE: keep this in sync with "avalanchego/config/flags.go".
@@ -225,6 +248,9 @@ pub const DEFAULT_API_METRICS_ENABLED: bool = true;
 pub const DEFAULT_API_HEALTH_ENABLED: bool = true;
 pub const DEFAULT_API_IPCS_ENABLED: bool = true;
 
+/// MUST BE a valid path in remote host machine.
+pub const DEFAULT_API_AUTH_PASSWORD_FILE: &str = "/etc/avalanche/api-auth-password";
+
 /// ref. https://github.com/ava-labs/avalanchego/blob/v1.7.6/config/flags.go#L25-L44
 pub const DEFAULT_CHAIN_CONFIG_DIR: &str = "/etc/avalanche/configs/chains";
 pub const DEFAULT_SUBNET_CONFIG_DIR: &str = "/etc/avalanche/configs/subnets";
@@ -263,6 +289,7 @@ impl Config {
             staking_port: DEFAULT_STAKING_PORT,
             staking_tls_key_file: Some(String::from(DEFAULT_STAKING_TLS_KEY_FILE)),
             staking_tls_cert_file: Some(String::from(DEFAULT_STAKING_TLS_CERT_FILE)),
+            staking_signer_key_file: None,
 
             bootstrap_ips: None,
             bootstrap_ids: None,
@@ -287,6 +314,9 @@ impl Config {
             api_health_enabled: Some(DEFAULT_API_HEALTH_ENABLED),
             api_ipcs_enabled: Some(DEFAULT_API_IPCS_ENABLED),
 
+            api_auth_required: None,
+            api_auth_password_file: None,
+
             whitelisted_subnets: None,
 
             chain_config_dir: String::from(DEFAULT_CHAIN_CONFIG_DIR),
@@ -305,6 +335,8 @@ impl Config {
 
             network_minimum_timeout: None,
             network_require_validator_to_connect: None,
+
+            extra_flags: BTreeMap::new(),
         }
     }
 
@@ -319,6 +351,35 @@ impl Config {
         !self.is_mainnet() && (self.network_id == 0 || self.network_id > 5)
     }
 
+    /// Appends the bootstrap IPs and IDs to the existing
+    /// "bootstrap-ips" and "bootstrap-ids", skipping the duplicate node IDs.
+    pub fn merge_bootstrap_nodes(&mut self, ips: &[String], ids: &[String]) {
+        let split = |s: &Option<String>| -> Vec<String> {
+            match s {
+                Some(v) => v
+                    .split(',')
+                    .filter(|x| !x.is_empty())
+                    .map(String::from)
+                    .collect(),
+                None => Vec::new(),
+            }
+        };
+        let mut merged_ips = split(&self.bootstrap_ips);
+        let mut merged_ids = split(&self.bootstrap_ids);
+        for (ip, id) in ips.iter().zip(ids.iter()) {
+            if merged_ids.contains(id) {
+                continue;
+            }
+            merged_ips.push(ip.clone());
+            merged_ids.push(id.clone());
+        }
+        if merged_ids.is_empty() {
+            return;
+        }
+        self.bootstrap_ips = Some(merged_ips.join(","));
+        self.bootstrap_ids = Some(merged_ids.join(","));
+    }
+
     /// Converts to string with JSON encoder.
     pub fn encode_json(&self) -> io::Result<String> {
         match serde_json::to_string(&self) {
@@ -465,6 +526,14 @@ impl Config {
             ));
         }
 
+        // api auth
+        if self.api_auth_required == Some(true) && self.api_auth_password_file.is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'api-auth-required' but empty 'api-auth-password-file'",
+            ));
+        }
+
         // state sync
         if self.state_sync_ids.is_some() && self.state_sync_ips.is_none() {
             return Err(Error::new(
@@ -527,3 +596,26 @@ fn test_config() {
 
     fs::remove_file(p).unwrap();
 }
+
+#[test]
+fn test_config_merge_bootstrap_nodes() {
+    let mut config = Config::default();
+    config.merge_bootstrap_nodes(&[], &[]);
+    assert!(config.bootstrap_ips.is_none());
+    assert!(config.bootstrap_ids.is_none());
+
+    config.bootstrap_ips = Some(String::from("1.2.3.4:9651"));
+    config.bootstrap_ids = Some(String::from("NodeID-A"));
+    config.merge_bootstrap_nodes(
+        &[String::from("5.6.7.8:9651"), String::from("1.2.3.4:9651")],
+        &[String::from("NodeID-B"), String::from("NodeID-A")],
+    );
+    assert_eq!(
+        config.bootstrap_ips,
+        Some(String::from("1.2.3.4:9651,5.6.7.8:9651"))
+    );
+    assert_eq!(
+        config.bootstrap_ids,
+        Some(String::from("NodeID-A,NodeID-B"))
+    );
+}
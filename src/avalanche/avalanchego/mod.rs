@@ -1,3 +1,6 @@
 pub mod api;
 pub mod config;
 pub mod genesis;
+pub mod release;
+pub mod subnet_config;
+pub mod upgrade;
@@ -0,0 +1,290 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    io::{self, Error, ErrorKind},
+    time::Duration,
+};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::http;
+
+/// ref. https://docs.github.com/en/rest/releases/releases#list-releases
+pub const RELEASES_URL: &str = "https://api.github.com/repos/ava-labs/avalanchego/releases";
+
+/// Represents the avalanchego release in GitHub.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    /// Returns the linux tarball asset for the architecture
+    /// (e.g., "avalanchego-linux-amd64-v1.7.10.tar.gz").
+    pub fn linux_asset(&self, arch: &str) -> Option<&Asset> {
+        let name = format!("avalanchego-linux-{}-{}.tar.gz", arch, self.tag_name);
+        self.assets.iter().find(|a| a.name == name)
+    }
+}
+
+/// Fetches the recent releases from GitHub.
+pub async fn fetch_releases() -> io::Result<Vec<Release>> {
+    info!("fetching avalanchego releases from {}", RELEASES_URL);
+    let b = http::get_https_following_redirects(RELEASES_URL, Duration::from_secs(30)).await?;
+    serde_json::from_slice(&b)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to parse releases {}", e)))
+}
+
+/// Returns the newest release that matches the constraint
+/// and is newer than the current version (if any).
+pub fn latest_matching<'a>(
+    releases: &'a [Release],
+    constraint: &Constraint,
+    current: Option<&Version>,
+) -> Option<(&'a Release, Version)> {
+    let mut latest: Option<(&Release, Version)> = None;
+    for r in releases.iter() {
+        if r.draft || r.prerelease {
+            continue;
+        }
+        let v = match Version::parse(&r.tag_name) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if !constraint.matches(&v) {
+            continue;
+        }
+        if let Some(cur) = current {
+            if v <= *cur {
+                continue;
+            }
+        }
+        if latest.as_ref().map(|(_, lv)| v > *lv).unwrap_or(true) {
+            latest = Some((r, v));
+        }
+    }
+    latest
+}
+
+/// Represents the semantic version (e.g., "v1.7.10").
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "v{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    pub fn parse(s: &str) -> io::Result<Self> {
+        let (major, minor, patch) = parse_parts(s)?;
+        match (major, minor, patch) {
+            (Some(major), Some(minor), Some(patch)) => Ok(Self {
+                major,
+                minor,
+                patch,
+            }),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid version '{}'", s),
+            )),
+        }
+    }
+}
+
+/// Represents the comma-separated version requirements
+/// (e.g., "^1.7", "~1.7.10", ">=1.7.0, <1.8.0", "1.7.x", "*").
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Constraint {
+    comparators: Vec<(Op, Version)>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+}
+
+impl Constraint {
+    pub fn parse(s: &str) -> io::Result<Self> {
+        let mut comparators = Vec::new();
+        for part in s.split(',') {
+            let part = part.trim();
+            if part.is_empty() || part == "*" {
+                continue;
+            }
+
+            let (op, v) = if let Some(v) = part.strip_prefix(">=") {
+                (">=", v)
+            } else if let Some(v) = part.strip_prefix("<=") {
+                ("<=", v)
+            } else if let Some(v) = part.strip_prefix('>') {
+                (">", v)
+            } else if let Some(v) = part.strip_prefix('<') {
+                ("<", v)
+            } else if let Some(v) = part.strip_prefix('=') {
+                ("=", v)
+            } else if let Some(v) = part.strip_prefix('^') {
+                ("^", v)
+            } else if let Some(v) = part.strip_prefix('~') {
+                ("~", v)
+            } else {
+                ("", part)
+            };
+
+            let (major, minor, patch) = parse_parts(v.trim())?;
+            let major = major.ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid constraint '{}'", part),
+                )
+            })?;
+            let lower = Version {
+                major,
+                minor: minor.unwrap_or(0),
+                patch: patch.unwrap_or(0),
+            };
+            // exclusive upper bound for the partial versions and caret/tilde
+            let upper = match (op, minor, patch) {
+                ("^", _, _) if major > 0 => Some(Version::new(major + 1, 0, 0)),
+                ("^", Some(minor), _) => Some(Version::new(0, minor + 1, 0)),
+                ("~", Some(minor), _) | ("", Some(minor), None) | ("=", Some(minor), None) => {
+                    Some(Version::new(major, minor + 1, 0))
+                }
+                ("~", None, _) | ("^", None, _) | ("", None, _) | ("=", None, _) => {
+                    Some(Version::new(major + 1, 0, 0))
+                }
+                _ => None,
+            };
+
+            match op {
+                ">=" => comparators.push((Op::GreaterEq, lower)),
+                ">" => comparators.push((Op::Greater, lower)),
+                "<=" => comparators.push((Op::LessEq, lower)),
+                "<" => comparators.push((Op::Less, lower)),
+                _ => {
+                    if (op.is_empty() || op == "=") && patch.is_some() {
+                        comparators.push((Op::Exact, lower));
+                        continue;
+                    }
+                    comparators.push((Op::GreaterEq, lower));
+                    if let Some(upper) = upper {
+                        comparators.push((Op::Less, upper));
+                    }
+                }
+            }
+        }
+        Ok(Self { comparators })
+    }
+
+    pub fn matches(&self, v: &Version) -> bool {
+        self.comparators.iter().all(|(op, cv)| {
+            let ord = v.cmp(cv);
+            match op {
+                Op::Exact => ord == Ordering::Equal,
+                Op::Greater => ord == Ordering::Greater,
+                Op::GreaterEq => ord != Ordering::Less,
+                Op::Less => ord == Ordering::Less,
+                Op::LessEq => ord != Ordering::Greater,
+            }
+        })
+    }
+}
+
+/// Parses "v1.7.10", "1.7", "1.7.x" into the optional parts.
+fn parse_parts(s: &str) -> io::Result<(Option<u64>, Option<u64>, Option<u64>)> {
+    let s = s.trim().trim_start_matches('v');
+    let mut parts = [None, None, None];
+    for (i, p) in s.split('.').enumerate() {
+        if i >= 3 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid version '{}'", s),
+            ));
+        }
+        if p == "x" || p == "*" {
+            break;
+        }
+        let n = p.parse::<u64>().map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid version '{}' ({})", s, e),
+            )
+        })?;
+        parts[i] = Some(n);
+    }
+    Ok((parts[0], parts[1], parts[2]))
+}
+
+#[test]
+fn test_constraint() {
+    let v = |s: &str| Version::parse(s).unwrap();
+
+    let c = Constraint::parse("^1.7").unwrap();
+    assert!(c.matches(&v("v1.7.0")));
+    assert!(c.matches(&v("v1.9.3")));
+    assert!(!c.matches(&v("v2.0.0")));
+    assert!(!c.matches(&v("v1.6.9")));
+
+    let c = Constraint::parse("~1.7.10").unwrap();
+    assert!(c.matches(&v("v1.7.12")));
+    assert!(!c.matches(&v("v1.8.0")));
+    assert!(!c.matches(&v("v1.7.9")));
+
+    let c = Constraint::parse("1.7.x").unwrap();
+    assert!(c.matches(&v("v1.7.3")));
+    assert!(!c.matches(&v("v1.8.0")));
+
+    let c = Constraint::parse(">=1.7.0, <1.7.5").unwrap();
+    assert!(c.matches(&v("v1.7.4")));
+    assert!(!c.matches(&v("v1.7.5")));
+
+    assert!(Constraint::parse("*").unwrap().matches(&v("v9.9.9")));
+    assert!(Constraint::parse("^abc").is_err());
+
+    let release = |tag: &str, prerelease: bool| Release {
+        tag_name: tag.to_string(),
+        prerelease,
+        draft: false,
+        assets: Vec::new(),
+    };
+    let releases = vec![
+        release("v1.8.0", false),
+        release("v1.7.11", true),
+        release("v1.7.10", false),
+        release("v1.7.9", false),
+    ];
+    let c = Constraint::parse("~1.7").unwrap();
+    let (r, _) = latest_matching(&releases, &c, None).unwrap();
+    assert_eq!(r.tag_name, "v1.7.10");
+    assert!(latest_matching(&releases, &c, Some(&v("v1.7.10"))).is_none());
+}
@@ -0,0 +1,74 @@
+use std::{
+    fs::{self, File},
+    io::{self, Error, ErrorKind, Write},
+    path::Path,
+    string::String,
+};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+/// To be persisted in "subnet_config_dir" as "[SUBNET_ID].json".
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/chains#SubnetConfig
+/// ref. https://serde.rs/container-attrs.html
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    /// If true, this node only connects to the validators of this subnet
+    /// (and the allowed nodes) for the subnet messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validator_only: Option<bool>,
+    /// Non-validator node IDs allowed to connect to this node
+    /// when "validator_only" is true.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_nodes: Option<Vec<String>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::default()
+    }
+}
+
+impl Config {
+    pub fn default() -> Self {
+        Self {
+            validator_only: None,
+            allowed_nodes: None,
+        }
+    }
+
+    /// Saves the current configuration to disk
+    /// and overwrites the file.
+    pub fn sync(&self, file_path: &str) -> io::Result<()> {
+        info!("syncing subnet Config to '{}'", file_path);
+        let path = Path::new(file_path);
+        let parent_dir = path.parent().expect("unexpected None parent");
+        fs::create_dir_all(parent_dir)?;
+
+        let ret = serde_json::to_vec(self);
+        let d = match ret {
+            Ok(d) => d,
+            Err(e) => {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!("failed to serialize subnet Config to JSON {}", e),
+                ));
+            }
+        };
+        let mut f = File::create(file_path)?;
+        f.write_all(&d)?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_config() {
+    let config = Config {
+        validator_only: Some(true),
+        allowed_nodes: Some(vec![String::from("NodeID-A")]),
+    };
+    let s = serde_json::to_string(&config).unwrap();
+    assert_eq!(s, r#"{"validatorOnly":true,"allowedNodes":["NodeID-A"]}"#);
+}
@@ -0,0 +1,80 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind},
+};
+
+/// Name of the chain upgrade file, to be placed in
+/// "[chain_config_dir]/[CHAIN_ID]/upgrade.json".
+/// ref. https://docs.avax.network/nodes/maintain/chain-config-flags#subnet-chain-configs
+pub const FILE_NAME: &str = "upgrade.json";
+
+/// Loads the upgrade file, and returns its contents and activation timestamps.
+pub fn load(file_path: &str) -> io::Result<(Vec<u8>, Vec<u64>)> {
+    let d = fs::read(file_path)?;
+    let timestamps = activation_timestamps(&d)?;
+    Ok((d, timestamps))
+}
+
+/// Returns all the activation timestamps (unix seconds) in the upgrade file
+/// (e.g., "blockTimestamp" in subnet-evm "precompileUpgrades").
+pub fn activation_timestamps(d: &[u8]) -> io::Result<Vec<u64>> {
+    let v: serde_json::Value = serde_json::from_slice(d).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid upgrade JSON: {}", e),
+        )
+    })?;
+    let mut timestamps = Vec::new();
+    collect_timestamps(&v, &mut timestamps);
+    timestamps.sort_unstable();
+    Ok(timestamps)
+}
+
+fn collect_timestamps(v: &serde_json::Value, timestamps: &mut Vec<u64>) {
+    match v {
+        serde_json::Value::Object(m) => {
+            for (k, v) in m.iter() {
+                if k == "blockTimestamp" || k.ends_with("Timestamp") {
+                    if let Some(ts) = v.as_u64() {
+                        timestamps.push(ts);
+                        continue;
+                    }
+                }
+                collect_timestamps(v, timestamps);
+            }
+        }
+        serde_json::Value::Array(a) => {
+            for v in a.iter() {
+                collect_timestamps(v, timestamps);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn test_activation_timestamps() {
+    let d = r#"
+{
+    "precompileUpgrades": [
+        {
+            "feeManagerConfig": {
+                "adminAddresses": ["0x8db97C7cEcE249c2b98bDC0226Cc4C2A57BF52FC"],
+                "blockTimestamp": 1668950000
+            }
+        },
+        {
+            "txAllowListConfig": {
+                "blockTimestamp": 1668900000,
+                "disable": true
+            }
+        }
+    ]
+}
+"#;
+    assert_eq!(
+        activation_timestamps(d.as_bytes()).unwrap(),
+        vec![1668900000, 1668950000]
+    );
+    assert!(activation_timestamps(b"{").is_err());
+}
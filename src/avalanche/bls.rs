@@ -0,0 +1,88 @@
+use std::{
+    cmp::Ordering,
+    fs::File,
+    io::{self, Error, ErrorKind, Write},
+    path::Path,
+};
+
+use log::info;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::secret_bytes::SecretBytes;
+
+/// Order of the BLS12-381 scalar field, big-endian.
+/// The secret key must be a non-zero scalar below it.
+/// ref. https://github.com/supranational/blst
+const CURVE_ORDER: [u8; 32] = [
+    0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8, 0x05,
+    0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+];
+
+/// Generates a BLS signer key in the format of "staking-signer-key-file"
+/// (32-byte big-endian secret key), so the key exists before avalanchego
+/// starts and can be backed up with the TLS staking key.
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/utils/crypto/bls#SecretKeyFromBytes
+pub fn generate(key_path: &str) -> io::Result<()> {
+    info!("creating BLS signer key with key path {}", key_path);
+    if Path::new(key_path).exists() {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("key path {} already exists", key_path),
+        ));
+    }
+
+    let random = SystemRandom::new();
+    let mut b = SecretBytes::zeroed(32);
+    loop {
+        random
+            .fill(b.expose_mut())
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed fill {:?}", e)))?;
+        if is_valid_secret_key(b.expose()) {
+            break;
+        }
+    }
+
+    let mut f = File::create(key_path)?;
+    f.write_all(b.expose())?;
+    Ok(())
+}
+
+/// Returns true if the bytes are a non-zero scalar below the curve order.
+pub fn is_valid_secret_key(b: &[u8]) -> bool {
+    b.len() == 32 && b.iter().any(|v| *v != 0) && b.cmp(&CURVE_ORDER[..]) == Ordering::Less
+}
+
+/// Represents the BLS public key and its proof of possession,
+/// required to register the node as a validator.
+/// ref. https://docs.avax.network/apis/avalanchego/apis/info#infogetnodeid
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofOfPossession {
+    pub public_key: String,
+    pub proof_of_possession: String,
+}
+
+#[test]
+fn test_generate() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    assert!(!is_valid_secret_key(&[0u8; 32]));
+    assert!(!is_valid_secret_key(&CURVE_ORDER));
+    assert!(!is_valid_secret_key(&[0xff; 32]));
+    assert!(!is_valid_secret_key(&[1u8; 31]));
+    let mut below = CURVE_ORDER;
+    below[31] = 0;
+    assert!(is_valid_secret_key(&below));
+
+    let p = crate::utils::random::tmp_path(10, Some(".key")).unwrap();
+    generate(&p).unwrap();
+    let b = std::fs::read(&p).unwrap();
+    assert!(is_valid_secret_key(&b));
+    assert!(generate(&p).is_err());
+    std::fs::remove_file(&p).unwrap();
+
+    let pop: ProofOfPossession =
+        serde_json::from_str(r#"{"publicKey":"0x8f95","proofOfPossession":"0x86a3"}"#).unwrap();
+    assert_eq!(pop.public_key, "0x8f95");
+}
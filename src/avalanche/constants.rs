@@ -52,4 +52,13 @@ lazy_static! {
         m.insert("local", 12345);
         m
     };
+
+    /// Public API endpoints for the existing public networks.
+    /// ref. https://docs.avax.network/apis/avalanchego/public-api-server
+    pub static ref NETWORK_ID_TO_PUBLIC_API_ENDPOINT: HashMap<u32, &'static str> = {
+        let mut m = HashMap::new();
+        m.insert(1, "https://api.avax.network");
+        m.insert(5, "https://api.avax-test.network");
+        m
+    };
 }
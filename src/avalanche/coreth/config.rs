@@ -42,6 +42,10 @@ pub struct Config {
     pub preimages_enabled: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pruning_enabled: Option<bool>,
+    /// Syncs the C-chain state summary from the peers, instead of
+    /// executing all the blocks since genesis.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_sync_enabled: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub snapshot_async: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -103,6 +107,19 @@ pub const DEFAULT_PROFILE_FREQUENCY: i64 = 15 * 60 * 1000 * 1000 * 1000; // 15-m
 pub const DEFAULT_PROFILE_MAX_FILES: i64 = 5;
 
 pub const DEFAULT_METRICS_ENABLED: bool = true;
+
+/// Enabled "eth-apis" if unset.
+/// ref. https://github.com/ava-labs/coreth/blob/v0.8.6/plugin/evm/config.go
+pub const DEFAULT_ETH_APIS: [&str; 8] = [
+    "public-eth",
+    "public-eth-filter",
+    "net",
+    "web3",
+    "internal-public-eth",
+    "internal-public-blockchain",
+    "internal-public-transaction-pool",
+    "internal-public-account",
+];
 pub const DEFAULT_LOG_LEVEL: &str = "info";
 
 impl Default for Config {
@@ -129,6 +146,7 @@ impl Config {
 
             preimages_enabled: None,
             pruning_enabled: None,
+            state_sync_enabled: None,
             snapshot_async: None,
             snapshot_verification_enabled: None,
 
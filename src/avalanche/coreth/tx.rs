@@ -0,0 +1,131 @@
+use std::io::{self, Error, ErrorKind};
+
+use sha3::{Digest, Keccak256};
+
+use crate::{avalanche::key, utils::prefix};
+
+/// Represents the pre-EIP-2718 (legacy) transaction, signed with
+/// the EIP-155 replay protection.
+/// ref. https://eips.ethereum.org/EIPS/eip-155
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct LegacyTx {
+    pub nonce: u64,
+    /// In wei.
+    pub gas_price: u128,
+    pub gas: u64,
+    pub to: [u8; 20],
+    /// In wei.
+    pub value: u128,
+    pub data: Vec<u8>,
+}
+
+impl LegacyTx {
+    /// Signs the transaction, and returns the raw transaction bytes
+    /// for "eth_sendRawTransaction" and the transaction hash.
+    pub fn sign(&self, k: &key::Key, chain_id: u64) -> io::Result<(Vec<u8>, String)> {
+        let digest =
+            Keccak256::digest(self.encode(&[rlp_uint(chain_id as u128), rlp_uint(0), rlp_uint(0)]));
+        let sig = k.sign_digest(&digest)?;
+        let v = sig[64] as u128 + chain_id as u128 * 2 + 35;
+        let raw = self.encode(&[
+            rlp_uint(v),
+            rlp_bytes(strip_leading_zeros(&sig[..32])),
+            rlp_bytes(strip_leading_zeros(&sig[32..64])),
+        ]);
+        let hash = format!("0x{}", hex::encode(Keccak256::digest(&raw)));
+        Ok((raw, hash))
+    }
+
+    fn encode(&self, tail: &[Vec<u8>]) -> Vec<u8> {
+        let mut items = vec![
+            rlp_uint(self.nonce as u128),
+            rlp_uint(self.gas_price),
+            rlp_uint(self.gas as u128),
+            rlp_bytes(&self.to),
+            rlp_uint(self.value),
+            rlp_bytes(&self.data),
+        ];
+        items.extend_from_slice(tail);
+        rlp_list(&items)
+    }
+}
+
+/// Parses the "0x"-prefixed hex address.
+pub fn parse_address(addr: &str) -> io::Result<[u8; 20]> {
+    let b = hex::decode(prefix::strip_0x(addr)).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid address '{}' ({})", addr, e),
+        )
+    })?;
+    if b.len() != 20 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("address '{}' must be 20-byte", addr),
+        ));
+    }
+    let mut to = [0u8; 20];
+    to.copy_from_slice(&b);
+    Ok(to)
+}
+
+fn strip_leading_zeros(b: &[u8]) -> &[u8] {
+    let i = b.iter().position(|x| *x != 0).unwrap_or(b.len());
+    &b[i..]
+}
+
+/// ref. https://ethereum.org/en/developers/docs/data-structures-and-encoding/rlp/
+fn rlp_uint(v: u128) -> Vec<u8> {
+    rlp_bytes(strip_leading_zeros(&v.to_be_bytes()))
+}
+
+fn rlp_bytes(b: &[u8]) -> Vec<u8> {
+    if b.len() == 1 && b[0] < 0x80 {
+        return b.to_vec();
+    }
+    let mut d = rlp_length(b.len(), 0x80);
+    d.extend_from_slice(b);
+    d
+}
+
+fn rlp_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload: Vec<u8> = items.concat();
+    let mut d = rlp_length(payload.len(), 0xc0);
+    d.extend(payload);
+    d
+}
+
+fn rlp_length(n: usize, offset: u8) -> Vec<u8> {
+    if n < 56 {
+        return vec![offset + n as u8];
+    }
+    let len = strip_leading_zeros(&(n as u64).to_be_bytes()).to_vec();
+    let mut d = vec![offset + 55 + len.len() as u8];
+    d.extend(len);
+    d
+}
+
+#[test]
+fn test_legacy_tx() {
+    // ref. https://eips.ethereum.org/EIPS/eip-155 "Example"
+    let k = key::Key::from_private_key_hex(
+        "0x4646464646464646464646464646464646464646464646464646464646464646",
+    )
+    .unwrap();
+    let tx = LegacyTx {
+        nonce: 9,
+        gas_price: 20_000_000_000,
+        gas: 21000,
+        to: parse_address("0x3535353535353535353535353535353535353535").unwrap(),
+        value: 1_000_000_000_000_000_000,
+        data: Vec::new(),
+    };
+    let (raw, _) = tx.sign(&k, 1).unwrap();
+    assert_eq!(
+        hex::encode(raw),
+        "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+    );
+
+    assert!(parse_address("0x35").is_err());
+    assert_eq!(rlp_length(56, 0x80), vec![0xb8, 56]);
+}
@@ -1,6 +1,6 @@
 use std::io::{self, Error, ErrorKind};
 
-use bech32::{ToBase32, Variant};
+use bech32::{FromBase32, ToBase32, Variant};
 use bitcoin::util::base58;
 use openssl::sha::sha256;
 
@@ -81,6 +81,93 @@ pub fn address(chain_id_alias: &str, hrp: &str, d: &[u8]) -> io::Result<String>
     Ok(format!("{}-{}", chain_id_alias, encoded))
 }
 
+/// Implements "formatting.ParseAddress/ParseBech32".
+/// Returns the 20-byte short address, ignoring the chain ID alias and hrp.
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/utils/formatting#ParseAddress
+pub fn parse_address(addr: &str) -> io::Result<Vec<u8>> {
+    let encoded = match addr.split_once('-') {
+        Some((_, v)) => v,
+        None => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("no separator found in address '{}'", addr),
+            ));
+        }
+    };
+    let (_, data, _) = match bech32::decode(encoded) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("failed bech32::decode {}", e),
+            ));
+        }
+    };
+    let d = match Vec::<u8>::from_base32(&data) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("failed from_base32 {}", e),
+            ));
+        }
+    };
+    if d.len() != 20 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("unexpected address length {} (expected 20)", d.len()),
+        ));
+    }
+    Ok(d)
+}
+
+/// Implements "formatting.Encode" with "formatting.Hex".
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/utils/formatting#Encode
+pub fn encode_hex_with_checksum(d: &[u8]) -> String {
+    let checksum = sha256(d);
+    let checksum_length = checksum.len();
+    let checksum = &checksum[checksum_length - CHECKSUM_LENGTH..];
+
+    let mut checked = d.to_vec();
+    checked.extend_from_slice(checksum);
+    format!("0x{}", hex::encode(&checked))
+}
+
+/// Implements "formatting.Decode" with "formatting.Hex".
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/utils/formatting#Decode
+pub fn decode_hex_with_checksum(d: &str) -> io::Result<Vec<u8>> {
+    let decoded = match hex::decode(d.trim_start_matches("0x")) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("failed to decode hex ({})", e),
+            ));
+        }
+    };
+    let decoded_length = decoded.len();
+    if decoded_length < CHECKSUM_LENGTH {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("input too short {}", decoded_length),
+        ));
+    }
+
+    let checksum = &decoded[decoded_length - CHECKSUM_LENGTH..];
+    let orig = &decoded[..decoded_length - CHECKSUM_LENGTH];
+    let orig_checksum = sha256(orig);
+    let orig_checksum_length = orig_checksum.len();
+    let orig_checksum = &orig_checksum[orig_checksum_length - CHECKSUM_LENGTH..];
+    if !eq_vectors(checksum, orig_checksum) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid checksum {:?} != {:?}", checksum, orig_checksum),
+        ));
+    }
+
+    Ok(orig.to_vec())
+}
+
 #[test]
 fn test_encode_c58_with_checksum() {
     // ref. https://github.com/ava-labs/avalanchego/blob/v1.7.5/utils/formatting/encoding_test.go#L71
@@ -111,3 +198,27 @@ fn test_encode_c58_with_checksum() {
     let decoded = decode_cb58_with_checksum(&hashed).unwrap();
     assert_eq!(d, decoded);
 }
+
+#[test]
+fn test_address() {
+    let d: Vec<u8> = (0..20).collect();
+    let addr = address("P", "fuji", &d).unwrap();
+    assert!(addr.starts_with("P-fuji1"));
+    assert_eq!(parse_address(&addr).unwrap(), d);
+    assert!(parse_address("fuji1abc").is_err());
+}
+
+#[test]
+fn test_encode_hex_with_checksum() {
+    let d: Vec<u8> = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 255];
+    let encoded = encode_hex_with_checksum(&d);
+    assert!(encoded.starts_with("0x00010203040506070809ff"));
+    assert_eq!(decode_hex_with_checksum(&encoded).unwrap(), d);
+
+    let mut corrupted = encoded.clone();
+    corrupted.pop();
+    corrupted.push('0');
+    if corrupted != encoded {
+        assert!(decode_hex_with_checksum(&corrupted).is_err());
+    }
+}
@@ -0,0 +1,333 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Error, ErrorKind},
+};
+
+use openssl::{
+    hash::MessageDigest,
+    pkey::PKey,
+    sign::{Signer, Verifier},
+    x509::X509,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::avalanche::{
+    avalanchego::{api::index, config as avalanchego_config},
+    node,
+};
+
+/// Local file that "avalanched backup upload" updates with the unix time of the last backup.
+pub const LAST_BACKUP_FILE_PATH: &str = "/var/lib/avalanched/last-backup";
+
+/// avalanchego config keys that "avalanched" sets per node, rather than from the spec.
+pub const NODE_SPECIFIC_CONFIG_KEYS: [&str; 4] = [
+    "public-ip",
+    "bootstrap-ips",
+    "bootstrap-ids",
+    "whitelisted-subnets",
+];
+
+/// Represents the periodic node status report from "avalanched".
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Heartbeat {
+    pub node_id: String,
+    pub machine_id: String,
+    pub kind: String,
+    pub public_ip: String,
+    pub avalanched_version: String,
+    pub avalanchego_version: String,
+    /// Disk space of the database directory.
+    pub disk_total_bytes: u64,
+    pub disk_available_bytes: u64,
+    /// System uptime.
+    pub uptime_seconds: u64,
+    /// Unix time in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_backup: Option<u64>,
+    /// SHA-256 checksum of the avalanchego config file.
+    pub config_sha256: String,
+    /// avalanchego config file contents.
+    #[serde(default)]
+    pub avalanchego_config: BTreeMap<String, serde_json::Value>,
+    /// SHA-256 checksum of the avalanchego binary.
+    #[serde(default)]
+    pub avalanchego_bin_sha256: String,
+    /// SHA-256 checksums of the plugins, with the file name.
+    #[serde(default)]
+    pub plugins: BTreeMap<String, String>,
+    /// Index backfill progress, only on the index-enabled nodes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_backfill: Option<Vec<index::ChainBackfill>>,
+    /// avalanchego health API result, None if not reachable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub healthy: Option<bool>,
+    /// Unix time in seconds.
+    pub timestamp: u64,
+}
+
+/// Represents a field that differs between the spec and the running node.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Drift {
+    pub field: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl Heartbeat {
+    /// Returns the config, binary, and plugin fields that differ from the spec.
+    /// The binary and plugin checksums are only compared if expected,
+    /// since the artifacts may have been uploaded without provenance.
+    pub fn drift(
+        &self,
+        expected_config: &avalanchego_config::Config,
+        expected_bin_sha256: Option<&str>,
+        expected_plugins: &BTreeMap<String, Option<String>>,
+    ) -> io::Result<Vec<Drift>> {
+        let mut drifts: Vec<Drift> = Vec::new();
+
+        let expected: BTreeMap<String, serde_json::Value> =
+            serde_json::from_str(&expected_config.encode_json()?).map_err(|e| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("failed to parse avalanchego config {}", e),
+                )
+            })?;
+        let mut keys: Vec<&String> = expected
+            .keys()
+            .chain(self.avalanchego_config.keys())
+            .collect();
+        keys.sort();
+        keys.dedup();
+        for k in keys {
+            if NODE_SPECIFIC_CONFIG_KEYS.contains(&k.as_str()) {
+                continue;
+            }
+            let (e, a) = (expected.get(k), self.avalanchego_config.get(k));
+            if e != a {
+                drifts.push(Drift {
+                    field: format!("avalanchego_config.{}", k),
+                    expected: e.map(|v| v.to_string()).unwrap_or_default(),
+                    actual: a.map(|v| v.to_string()).unwrap_or_default(),
+                });
+            }
+        }
+
+        if let Some(e) = expected_bin_sha256 {
+            if e != self.avalanchego_bin_sha256 {
+                drifts.push(Drift {
+                    field: String::from("avalanchego_bin"),
+                    expected: e.to_string(),
+                    actual: self.avalanchego_bin_sha256.clone(),
+                });
+            }
+        }
+
+        for (name, e) in expected_plugins.iter() {
+            match (e, self.plugins.get(name)) {
+                (_, None) => drifts.push(Drift {
+                    field: format!("plugins.{}", name),
+                    expected: e.clone().unwrap_or_else(|| String::from("(present)")),
+                    actual: String::new(),
+                }),
+                (Some(e), Some(a)) if e != a => drifts.push(Drift {
+                    field: format!("plugins.{}", name),
+                    expected: e.clone(),
+                    actual: a.clone(),
+                }),
+                _ => {}
+            }
+        }
+        for (name, a) in self.plugins.iter() {
+            if !expected_plugins.contains_key(name) {
+                drifts.push(Drift {
+                    field: format!("plugins.{}", name),
+                    expected: String::new(),
+                    actual: a.clone(),
+                });
+            }
+        }
+
+        Ok(drifts)
+    }
+}
+
+/// Represents the heartbeat signed with the node staking key,
+/// so that the reader can verify it was sent by the node ID.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Signed {
+    /// JSON-encoded heartbeat.
+    pub payload: String,
+    /// Hex-encoded SHA-256 signature of the payload.
+    pub signature: String,
+    /// PEM-encoded staking certificate.
+    pub cert: String,
+}
+
+impl Signed {
+    pub fn sign(heartbeat: &Heartbeat, key_path: &str, cert_path: &str) -> io::Result<Self> {
+        let payload = serde_json::to_string(heartbeat).map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to serialize heartbeat {}", e),
+            )
+        })?;
+        let key = PKey::private_key_from_pem(&fs::read(key_path)?).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("failed to load staking key {}", e),
+            )
+        })?;
+        let signature = Signer::new(MessageDigest::sha256(), &key)
+            .and_then(|mut s| {
+                s.update(payload.as_bytes())?;
+                s.sign_to_vec()
+            })
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to sign {}", e)))?;
+        Ok(Self {
+            payload,
+            signature: hex::encode(signature),
+            cert: fs::read_to_string(cert_path)?,
+        })
+    }
+
+    /// Verifies the signature, and that the certificate matches the heartbeat node ID.
+    pub fn verify(&self) -> io::Result<Heartbeat> {
+        let heartbeat: Heartbeat = serde_json::from_str(&self.payload).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("failed to parse heartbeat {}", e),
+            )
+        })?;
+        let node_id = node::load_id_from_pem(self.cert.as_bytes())?;
+        if node_id != heartbeat.node_id {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "certificate node ID {} does not match heartbeat {}",
+                    node_id, heartbeat.node_id
+                ),
+            ));
+        }
+
+        let signature = hex::decode(&self.signature).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid signature hex {}", e),
+            )
+        })?;
+        let verified = X509::from_pem(self.cert.as_bytes())
+            .and_then(|c| c.public_key())
+            .and_then(|k| {
+                let mut v = Verifier::new(MessageDigest::sha256(), &k)?;
+                v.update(self.payload.as_bytes())?;
+                v.verify(&signature)
+            })
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("failed to verify {}", e)))?;
+        if !verified {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid heartbeat signature for {}", node_id),
+            ));
+        }
+        Ok(heartbeat)
+    }
+}
+
+#[test]
+fn test_heartbeat() {
+    let heartbeat = Heartbeat {
+        node_id: String::from("NodeID-29HTAG5cfN2fw79A67Jd5zY9drcT51EBG"),
+        machine_id: String::from("i-123"),
+        kind: String::from("anchor"),
+        public_ip: String::from("1.2.3.4"),
+        avalanched_version: String::from("0.0.1"),
+        avalanchego_version: String::from("avalanche/1.7.10"),
+        disk_total_bytes: 100,
+        disk_available_bytes: 50,
+        uptime_seconds: 10,
+        last_backup: None,
+        config_sha256: String::from("abc"),
+        avalanchego_config: BTreeMap::new(),
+        avalanchego_bin_sha256: String::from("bin"),
+        plugins: BTreeMap::new(),
+        index_backfill: None,
+        healthy: None,
+        timestamp: 1,
+    };
+    let signed = Signed::sign(
+        &heartbeat,
+        "./artifacts/test.insecure.key",
+        "./artifacts/test.insecure.crt",
+    )
+    .unwrap();
+    assert_eq!(signed.verify().unwrap(), heartbeat);
+
+    let mut tampered = signed.clone();
+    tampered.payload = tampered
+        .payload
+        .replace("\"disk_available_bytes\":50", "\"disk_available_bytes\":90");
+    assert!(tampered.verify().is_err());
+
+    // signed by another node
+    let mut other = heartbeat;
+    other.node_id = String::from("NodeID-7Xhw2mDxuDS44j42TCB6U5579esbSt3Lg");
+    let signed = Signed::sign(
+        &other,
+        "./artifacts/test.insecure.key",
+        "./artifacts/test.insecure.crt",
+    )
+    .unwrap();
+    assert!(signed.verify().is_err());
+}
+
+#[test]
+fn test_drift() {
+    let mut config = avalanchego_config::Config::default();
+    let mut heartbeat: Heartbeat = serde_json::from_value(serde_json::json!({
+        "node_id": "NodeID-29HTAG5cfN2fw79A67Jd5zY9drcT51EBG",
+        "machine_id": "i-123",
+        "kind": "anchor",
+        "public_ip": "1.2.3.4",
+        "avalanched_version": "0.0.1",
+        "avalanchego_version": "avalanche/1.7.10",
+        "disk_total_bytes": 100,
+        "disk_available_bytes": 50,
+        "uptime_seconds": 10,
+        "config_sha256": "abc",
+        "timestamp": 1,
+    }))
+    .unwrap();
+    heartbeat.avalanchego_config = serde_json::from_str(&config.encode_json().unwrap()).unwrap();
+    heartbeat
+        .avalanchego_config
+        .insert(String::from("public-ip"), serde_json::json!("1.2.3.4"));
+    heartbeat.avalanchego_bin_sha256 = String::from("bin");
+    heartbeat
+        .plugins
+        .insert(String::from("evm"), String::from("evm-hash"));
+
+    let mut plugins = BTreeMap::new();
+    plugins.insert(String::from("evm"), None);
+    assert!(heartbeat
+        .drift(&config, Some("bin"), &plugins)
+        .unwrap()
+        .is_empty());
+
+    config.log_level = Some(String::from("DEBUG"));
+    plugins.insert(String::from("evm"), Some(String::from("new-evm-hash")));
+    plugins.insert(String::from("subnet-evm"), None);
+    let drifts = heartbeat.drift(&config, Some("new-bin"), &plugins).unwrap();
+    let fields: Vec<&str> = drifts.iter().map(|d| d.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "avalanchego_config.log-level",
+            "avalanchego_bin",
+            "plugins.evm",
+            "plugins.subnet-evm"
+        ]
+    );
+}
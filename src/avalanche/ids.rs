@@ -0,0 +1,103 @@
+use std::io::{self, Error, ErrorKind};
+
+use crate::avalanche::formatting;
+
+pub const ID_LEN: usize = 32;
+pub const SHORT_ID_LEN: usize = 20;
+
+pub const NODE_ID_PREFIX: &str = "NodeID-";
+
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/ids#ID
+pub type Id = [u8; ID_LEN];
+
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/ids#ShortID
+pub type ShortId = [u8; SHORT_ID_LEN];
+
+/// Both the primary network ID and the P-chain ID.
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/ids#Empty
+pub const EMPTY_ID: Id = [0; ID_LEN];
+
+/// Implements "ids.FromString".
+pub fn parse_id(s: &str) -> io::Result<Id> {
+    let d = formatting::decode_cb58_with_checksum(s)?;
+    if d.len() != ID_LEN {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "invalid ID '{}' length {} (expected {})",
+                s,
+                d.len(),
+                ID_LEN
+            ),
+        ));
+    }
+    let mut id: Id = EMPTY_ID;
+    id.copy_from_slice(&d);
+    Ok(id)
+}
+
+/// Implements "ids.ID.String".
+pub fn encode_id(id: &Id) -> String {
+    formatting::encode_cb58_with_checksum(id)
+}
+
+/// Implements "ids.NodeIDFromString".
+pub fn parse_node_id(s: &str) -> io::Result<ShortId> {
+    let encoded = match s.strip_prefix(NODE_ID_PREFIX) {
+        Some(v) => v,
+        None => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("node ID '{}' missing prefix '{}'", s, NODE_ID_PREFIX),
+            ));
+        }
+    };
+    let d = formatting::decode_cb58_with_checksum(encoded)?;
+    to_short_id(&d)
+}
+
+/// Parses the bech32 address (e.g., "P-fuji1...") to its short ID.
+pub fn parse_short_address(addr: &str) -> io::Result<ShortId> {
+    let d = formatting::parse_address(addr)?;
+    to_short_id(&d)
+}
+
+fn to_short_id(d: &[u8]) -> io::Result<ShortId> {
+    if d.len() != SHORT_ID_LEN {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "invalid short ID length {} (expected {})",
+                d.len(),
+                SHORT_ID_LEN
+            ),
+        ));
+    }
+    let mut id: ShortId = [0; SHORT_ID_LEN];
+    id.copy_from_slice(d);
+    Ok(id)
+}
+
+#[test]
+fn test_ids() {
+    assert_eq!(
+        encode_id(&EMPTY_ID),
+        "11111111111111111111111111111111LpoYY"
+    );
+    assert_eq!(
+        parse_id("11111111111111111111111111111111LpoYY").unwrap(),
+        EMPTY_ID
+    );
+    assert!(parse_id("NodeID-7Xhw2mDxuDS44j42TCB6U5579esbSt3Lg").is_err());
+
+    let node_id = parse_node_id("NodeID-7Xhw2mDxuDS44j42TCB6U5579esbSt3Lg").unwrap();
+    assert_eq!(
+        format!(
+            "{}{}",
+            NODE_ID_PREFIX,
+            formatting::encode_cb58_with_checksum(&node_id)
+        ),
+        "NodeID-7Xhw2mDxuDS44j42TCB6U5579esbSt3Lg"
+    );
+    assert!(parse_node_id("7Xhw2mDxuDS44j42TCB6U5579esbSt3Lg").is_err());
+}
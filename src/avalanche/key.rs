@@ -12,13 +12,16 @@ use log::info;
 use ring::digest::{digest, SHA256};
 use ripemd::{Digest, Ripemd160};
 use rust_embed::RustEmbed;
-use secp256k1::{self, rand::rngs::OsRng, PublicKey, Secp256k1, SecretKey};
+use secp256k1::{self, rand::rngs::OsRng, Message, PublicKey, Secp256k1, SecretKey};
 use serde::{Deserialize, Serialize};
 use sha3::Keccak256;
 
 use crate::{
     avalanche::{constants, formatting},
-    utils::prefix,
+    utils::{
+        prefix,
+        secret_bytes::{self, SecretBytes},
+    },
 };
 
 pub const PRIVATE_KEY_ENCODE_PREFIX: &str = "PrivateKey-";
@@ -58,11 +61,13 @@ pub struct Key {
 
     /// AVAX wallet compatible private key.
     /// NEVER save mainnet-funded wallet keys here.
-    pub private_key: String,
+    #[serde(with = "secret_bytes::exposed")]
+    pub private_key: SecretBytes,
     /// Used for importing keys in MetaMask and subnet-cli.
     /// ref. https://github.com/ava-labs/subnet-cli/blob/5b69345a3fba534fb6969002f41c8d3e69026fed/internal/key/key.go#L238-L258
     /// NEVER save mainnet-funded wallet keys here.
-    pub private_key_hex: String,
+    #[serde(with = "secret_bytes::exposed")]
+    pub private_key_hex: SecretBytes,
 
     /// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/utils/hashing#PubkeyBytesToAddress
     pub short_address: String,
@@ -93,8 +98,8 @@ impl Key {
         Ok(Self {
             secret_key: Some(secret_key),
             public_key: Some(public_key),
-            private_key,
-            private_key_hex,
+            private_key: private_key.into(),
+            private_key_hex: private_key_hex.into(),
             short_address,
             eth_address,
         })
@@ -143,13 +148,58 @@ impl Key {
         Ok(Self {
             secret_key: Some(secret_key),
             public_key: Some(public_key),
-            private_key,
-            private_key_hex,
+            private_key: private_key.into(),
+            private_key_hex: private_key_hex.into(),
             short_address,
             eth_address,
         })
     }
 
+    /// Loads the specified Secp256k1 key with hex encoding.
+    /// Takes the "private_key_hex" field in the "Key" struct.
+    pub fn from_private_key_hex(private_key_hex: &str) -> io::Result<Self> {
+        let priv_bytes = match hex::decode(prefix::strip_0x(private_key_hex.trim())) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("failed to decode hex private key ({})", e),
+                ));
+            }
+        };
+        let enc = formatting::encode_cb58_with_checksum(&priv_bytes);
+        Self::from_private_key(&format!("{}{}", PRIVATE_KEY_ENCODE_PREFIX, enc))
+    }
+
+    /// Returns the 20-byte short address ("ids.ShortID").
+    pub fn short_address_bytes(&self) -> io::Result<Vec<u8>> {
+        public_key_to_short_address_bytes(&self.public_key.expect("unexpected empty public_key"))
+    }
+
+    /// Signs the 32-byte SHA256 digest, and returns the recoverable signature
+    /// in "[r || s || v]" format.
+    /// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/utils/crypto#PrivateKeySECP256K1R.SignHash
+    pub fn sign_digest(&self, digest: &[u8]) -> io::Result<[u8; 65]> {
+        let msg = match Message::from_slice(digest) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid digest ({})", e),
+                ));
+            }
+        };
+        let secp = Secp256k1::new();
+        let sig = secp
+            .sign_ecdsa_recoverable(&msg, &self.secret_key.expect("unexpected empty secret_key"));
+        let (rec_id, rs) = sig.serialize_compact();
+
+        let mut d = [0u8; 65];
+        d[..64].copy_from_slice(&rs);
+        d[64] = rec_id.to_i32() as u8;
+        Ok(d)
+    }
+
     /// Implements "crypto.PublicKeySECP256K1R.Address()" and "formatting.FormatAddress".
     /// "human readable part" (hrp) must be valid output from "constants.GetHRP(networkID)".
     /// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/utils/constants
@@ -302,12 +352,12 @@ fn test_key() {
     let _ = env_logger::builder().is_test(true).try_init();
 
     let generated_key = Key::generate().unwrap();
-    info!("{}", generated_key.private_key);
+    info!("{}", generated_key.private_key.expose_str());
     info!("{}", generated_key.short_address.clone());
     info!("{}", generated_key.address("X", 9999).unwrap());
 
-    let parsed_key = Key::from_private_key(&generated_key.private_key).unwrap();
-    info!("{}", parsed_key.private_key);
+    let parsed_key = Key::from_private_key(generated_key.private_key.expose_str()).unwrap();
+    info!("{}", parsed_key.private_key.expose_str());
     info!("{}", parsed_key.short_address.clone());
     info!("{}", parsed_key.address("X", 9999).unwrap());
 
@@ -1124,12 +1174,36 @@ fn test_key() {
     );
 }
 
+#[test]
+fn test_sign_digest() {
+    use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+
+    let key = Key::from_private_key(EWOQ_KEY).unwrap();
+    assert_eq!(
+        Key::from_private_key_hex(key.private_key_hex.expose_str()).unwrap(),
+        key
+    );
+
+    let digest = compute_sha256(b"hello");
+    let sig = key.sign_digest(&digest).unwrap();
+
+    let rec_id = RecoveryId::from_i32(sig[64] as i32).unwrap();
+    let rsig = RecoverableSignature::from_compact(&sig[..64], rec_id).unwrap();
+    let secp = Secp256k1::new();
+    let recovered = secp
+        .recover_ecdsa(&Message::from_slice(&digest).unwrap(), &rsig)
+        .unwrap();
+    assert_eq!(recovered, key.public_key.unwrap());
+}
+
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
 #[serde(rename_all = "snake_case")]
 pub struct PrivateKeyInfo {
     /// CB58-encoded private key with the prefix "PrivateKey-".
-    pub private_key: String,
-    pub private_key_hex: String,
+    #[serde(with = "secret_bytes::exposed")]
+    pub private_key: SecretBytes,
+    #[serde(with = "secret_bytes::exposed")]
+    pub private_key_hex: SecretBytes,
     pub x_address: String,
     pub p_address: String,
     pub c_address: String,
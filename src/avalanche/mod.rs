@@ -1,9 +1,13 @@
 pub mod avalanchego;
+pub mod bls;
 pub mod constants;
 pub mod coreth;
 pub mod formatting;
+pub mod heartbeat;
+pub mod ids;
 pub mod key;
 pub mod node;
 pub mod packer;
+pub mod platformvm;
 pub mod subnet_evm;
 pub mod vm;
@@ -16,7 +16,7 @@ use crate::{
 
 /// Defines the node type.
 /// MUST BE either "anchor" or "non-anchor"
-#[derive(Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub enum Kind {
     Anchor,
     NonAnchor,
@@ -216,7 +216,17 @@ pub fn load_id(cert_path: &str) -> io::Result<String> {
     }
 
     let pub_key_contents = fs::read(cert_path).unwrap();
-    let pub_key = X509::from_pem(&pub_key_contents.to_vec()).unwrap();
+    load_id_from_pem(&pub_key_contents)
+}
+
+/// Loads a node ID from the PEM-encoded X509 certificate contents.
+pub fn load_id_from_pem(pem: &[u8]) -> io::Result<String> {
+    let pub_key = X509::from_pem(pem).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("failed to parse certificate {}", e),
+        )
+    })?;
 
     // ref. "tls.Certificate.Leaf.Raw" in Go
     // ref. "tls.X509KeyPair"
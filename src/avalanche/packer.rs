@@ -1,3 +1,5 @@
+use std::io::{self, Error, ErrorKind};
+
 /// number of bytes per byte
 pub const BYTE_LEN: usize = 1;
 
@@ -15,3 +17,122 @@ pub const BOOL_LEN: usize = 1;
 
 /// number of bytes per IP
 pub const IP_LEN: usize = 16 + SHORT_LEN;
+
+/// Implements "wrappers.Packer" with big-endian encoding.
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/utils/wrappers#Packer
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Packer {
+    pub bytes: Vec<u8>,
+}
+
+impl Default for Packer {
+    fn default() -> Self {
+        Self::default()
+    }
+}
+
+impl Packer {
+    pub fn default() -> Self {
+        Self { bytes: Vec::new() }
+    }
+
+    pub fn pack_byte(&mut self, v: u8) {
+        self.bytes.push(v);
+    }
+
+    pub fn pack_u16(&mut self, v: u16) {
+        self.bytes.extend_from_slice(&v.to_be_bytes());
+    }
+
+    pub fn pack_u32(&mut self, v: u32) {
+        self.bytes.extend_from_slice(&v.to_be_bytes());
+    }
+
+    pub fn pack_u64(&mut self, v: u64) {
+        self.bytes.extend_from_slice(&v.to_be_bytes());
+    }
+
+    /// Packs the bytes without the length prefix (e.g., "ids.ID").
+    pub fn pack_fixed_bytes(&mut self, d: &[u8]) {
+        self.bytes.extend_from_slice(d);
+    }
+
+    /// Packs the bytes with the 4-byte length prefix.
+    pub fn pack_bytes(&mut self, d: &[u8]) {
+        self.pack_u32(d.len() as u32);
+        self.pack_fixed_bytes(d);
+    }
+}
+
+/// Reads the bytes packed by "Packer".
+pub struct Unpacker<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Unpacker<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    pub fn unpack_fixed_bytes(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.offset + n > self.bytes.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "insufficient length to unpack {} bytes at offset {} (length {})",
+                    n,
+                    self.offset,
+                    self.bytes.len()
+                ),
+            ));
+        }
+        let d = &self.bytes[self.offset..self.offset + n];
+        self.offset += n;
+        Ok(d)
+    }
+
+    pub fn unpack_byte(&mut self) -> io::Result<u8> {
+        Ok(self.unpack_fixed_bytes(BYTE_LEN)?[0])
+    }
+
+    pub fn unpack_u16(&mut self) -> io::Result<u16> {
+        let d = self.unpack_fixed_bytes(SHORT_LEN)?;
+        Ok(u16::from_be_bytes([d[0], d[1]]))
+    }
+
+    pub fn unpack_u32(&mut self) -> io::Result<u32> {
+        let d = self.unpack_fixed_bytes(INT_LEN)?;
+        Ok(u32::from_be_bytes([d[0], d[1], d[2], d[3]]))
+    }
+
+    pub fn unpack_u64(&mut self) -> io::Result<u64> {
+        let d = self.unpack_fixed_bytes(LONG_LEN)?;
+        let mut b = [0u8; LONG_LEN];
+        b.copy_from_slice(d);
+        Ok(u64::from_be_bytes(b))
+    }
+}
+
+#[test]
+fn test_packer() {
+    let mut p = Packer::default();
+    p.pack_byte(1);
+    p.pack_u16(2);
+    p.pack_u32(3);
+    p.pack_u64(4);
+    p.pack_bytes(&[5, 6]);
+    assert_eq!(
+        p.bytes,
+        vec![1, 0, 2, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 2, 5, 6]
+    );
+
+    let mut u = Unpacker::new(&p.bytes);
+    assert_eq!(u.unpack_byte().unwrap(), 1);
+    assert_eq!(u.unpack_u16().unwrap(), 2);
+    assert_eq!(u.unpack_u32().unwrap(), 3);
+    assert_eq!(u.unpack_u64().unwrap(), 4);
+    assert_eq!(u.unpack_u32().unwrap(), 2);
+    assert_eq!(u.unpack_fixed_bytes(2).unwrap(), &[5, 6]);
+    assert!(u.unpack_byte().is_err());
+}
@@ -0,0 +1,3 @@
+pub mod multisig;
+pub mod txs;
+pub mod wallet;
@@ -0,0 +1,206 @@
+use std::{
+    fs::{self, File},
+    io::{self, Error, ErrorKind, Write},
+    path::Path,
+};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::avalanche::{
+    constants, formatting,
+    ids::{self, Id, ShortId},
+    key,
+    platformvm::txs,
+};
+
+/// Represents the transaction that requires signatures from multiple keys
+/// (e.g., N-of-M subnet control keys), to be exported and signed by each
+/// operator on their own machine before submission.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct PartiallySignedTx {
+    pub network_id: u32,
+    /// Codec-encoded unsigned transaction in hex with checksum.
+    pub unsigned_tx: String,
+    /// P-chain addresses of the signers for each credential,
+    /// in the signature order.
+    pub signers: Vec<Vec<String>>,
+    /// Hex-encoded signatures for each credential, None if not signed yet.
+    pub signatures: Vec<Vec<Option<String>>>,
+}
+
+impl PartiallySignedTx {
+    pub fn new(network_id: u32, unsigned: &[u8], signers: &[Vec<ShortId>]) -> io::Result<Self> {
+        let hrp = match constants::NETWORK_ID_TO_HRP.get(&network_id) {
+            Some(v) => v,
+            None => constants::FALLBACK_HRP,
+        };
+        let mut encoded_signers: Vec<Vec<String>> = Vec::new();
+        for cred_signers in signers.iter() {
+            let mut addrs: Vec<String> = Vec::new();
+            for addr in cred_signers.iter() {
+                addrs.push(formatting::address("P", hrp, addr)?);
+            }
+            encoded_signers.push(addrs);
+        }
+        Ok(Self {
+            network_id,
+            unsigned_tx: formatting::encode_hex_with_checksum(unsigned),
+            signatures: signers.iter().map(|s| vec![None; s.len()]).collect(),
+            signers: encoded_signers,
+        })
+    }
+
+    pub fn load(file_path: &str) -> io::Result<Self> {
+        info!("loading PartiallySignedTx from {}", file_path);
+
+        if !Path::new(file_path).exists() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("file {} does not exists", file_path),
+            ));
+        }
+
+        let f = File::open(file_path).map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to open {} ({})", file_path, e),
+            )
+        })?;
+        serde_json::from_reader(f)
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("invalid JSON: {}", e)))
+    }
+
+    pub fn sync(&self, file_path: &str) -> io::Result<()> {
+        info!("syncing PartiallySignedTx to '{}'", file_path);
+        let path = Path::new(file_path);
+        if let Some(parent_dir) = path.parent() {
+            fs::create_dir_all(parent_dir)?;
+        }
+
+        let d = serde_json::to_vec_pretty(self).map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to serialize PartiallySignedTx to JSON {}", e),
+            )
+        })?;
+        let mut f = File::create(file_path)?;
+        f.write_all(&d)?;
+
+        Ok(())
+    }
+
+    /// Signs all the credentials that the key is a signer of,
+    /// and returns the number of newly added signatures.
+    pub fn sign(&mut self, k: &key::Key) -> io::Result<usize> {
+        let addr = k.address("P", self.network_id)?;
+        let unsigned = formatting::decode_hex_with_checksum(&self.unsigned_tx)?;
+        let sig = k.sign_digest(&txs::compute_sha256(&unsigned))?;
+
+        let mut added = 0;
+        for (i, cred_signers) in self.signers.iter().enumerate() {
+            for (j, signer) in cred_signers.iter().enumerate() {
+                if signer == &addr && self.signatures[i][j].is_none() {
+                    self.signatures[i][j] = Some(hex::encode(sig));
+                    added += 1;
+                }
+            }
+        }
+        Ok(added)
+    }
+
+    /// Returns the signer addresses that have not signed yet.
+    pub fn missing_signers(&self) -> Vec<String> {
+        let mut missing: Vec<String> = Vec::new();
+        for (i, cred_signers) in self.signers.iter().enumerate() {
+            for (j, signer) in cred_signers.iter().enumerate() {
+                if self.signatures[i][j].is_none() && !missing.contains(signer) {
+                    missing.push(signer.clone());
+                }
+            }
+        }
+        missing
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing_signers().is_empty()
+    }
+
+    /// Returns the signed transaction bytes and its transaction ID.
+    pub fn signed(&self) -> io::Result<(Vec<u8>, Id)> {
+        if !self.is_complete() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("missing signatures from {:?}", self.missing_signers()),
+            ));
+        }
+        let unsigned = formatting::decode_hex_with_checksum(&self.unsigned_tx)?;
+
+        let mut creds: Vec<Vec<[u8; 65]>> = Vec::new();
+        for (i, sigs) in self.signatures.iter().enumerate() {
+            let mut cred: Vec<[u8; 65]> = Vec::new();
+            for (j, sig) in sigs.iter().enumerate() {
+                let d = hex::decode(sig.clone().unwrap_or_default()).map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("invalid signature from {} ({})", self.signers[i][j], e),
+                    )
+                })?;
+                if d.len() != 65 {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "invalid signature length {} from {}",
+                            d.len(),
+                            self.signers[i][j]
+                        ),
+                    ));
+                }
+                let mut s = [0u8; 65];
+                s.copy_from_slice(&d);
+                cred.push(s);
+            }
+            creds.push(cred);
+        }
+        Ok(txs::pack_signed(unsigned, &creds))
+    }
+}
+
+/// Parses the comma-separated P-chain addresses into the short addresses.
+pub fn parse_addresses(addrs: &str) -> io::Result<Vec<ShortId>> {
+    let mut parsed: Vec<ShortId> = Vec::new();
+    for addr in addrs.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        parsed.push(ids::parse_short_address(addr)?);
+    }
+    Ok(parsed)
+}
+
+#[test]
+fn test_partially_signed_tx() {
+    let k1 = key::Key::generate().unwrap();
+    let k2 = key::Key::generate().unwrap();
+    let mut a1: ShortId = [0; ids::SHORT_ID_LEN];
+    a1.copy_from_slice(&k1.short_address_bytes().unwrap());
+    let mut a2: ShortId = [0; ids::SHORT_ID_LEN];
+    a2.copy_from_slice(&k2.short_address_bytes().unwrap());
+
+    let unsigned = vec![0, 0, 1, 2, 3];
+    let mut tx = PartiallySignedTx::new(5, &unsigned, &[vec![a1], vec![a1, a2]]).unwrap();
+    assert!(!tx.is_complete());
+    assert!(tx.signed().is_err());
+
+    assert_eq!(tx.sign(&k1).unwrap(), 2);
+    assert_eq!(tx.sign(&k1).unwrap(), 0);
+    assert_eq!(tx.missing_signers(), vec![k2.address("P", 5).unwrap()]);
+
+    // round-trip as if exported to another operator
+    let tx: PartiallySignedTx = serde_json::from_str(&serde_json::to_string(&tx).unwrap()).unwrap();
+    let mut tx = tx;
+    assert_eq!(tx.sign(&k2).unwrap(), 1);
+    assert!(tx.is_complete());
+
+    let (signed, _) = tx.signed().unwrap();
+    // unsigned bytes + number of credentials + 2 credentials (3 signatures)
+    assert_eq!(signed.len(), unsigned.len() + 4 + 2 * (4 + 4) + 3 * 65);
+}
@@ -0,0 +1,997 @@
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    io::{self, Error, ErrorKind},
+};
+
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+
+use crate::avalanche::{
+    ids::{self, Id, ShortId},
+    key,
+    packer::{Packer, Unpacker},
+};
+
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/platformvm/txs#CodecVersion
+pub const CODEC_VERSION: u16 = 0;
+
+/// Type IDs registered in the P-chain codec.
+/// ref. https://github.com/ava-labs/avalanchego/blob/v1.9.0/vms/platformvm/txs/codec.go
+pub const TYPE_ID_SECP256K1_TRANSFER_INPUT: u32 = 5;
+pub const TYPE_ID_SECP256K1_TRANSFER_OUTPUT: u32 = 7;
+pub const TYPE_ID_SECP256K1_CREDENTIAL: u32 = 9;
+pub const TYPE_ID_SECP256K1_INPUT: u32 = 10;
+pub const TYPE_ID_SECP256K1_OUTPUT_OWNERS: u32 = 11;
+pub const TYPE_ID_ADD_VALIDATOR_TX: u32 = 12;
+pub const TYPE_ID_ADD_SUBNET_VALIDATOR_TX: u32 = 13;
+pub const TYPE_ID_ADD_DELEGATOR_TX: u32 = 14;
+pub const TYPE_ID_CREATE_SUBNET_TX: u32 = 16;
+pub const TYPE_ID_REMOVE_SUBNET_VALIDATOR_TX: u32 = 23;
+pub const TYPE_ID_TRANSFORM_SUBNET_TX: u32 = 24;
+pub const TYPE_ID_ADD_PERMISSIONLESS_VALIDATOR_TX: u32 = 25;
+pub const TYPE_ID_ADD_PERMISSIONLESS_DELEGATOR_TX: u32 = 26;
+pub const TYPE_ID_EMPTY_SIGNER: u32 = 27;
+
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/platformvm/reward#PercentDenominator
+pub const PERCENT_DENOMINATOR: u64 = 1_000_000;
+
+pub const NANO_AVAX: u64 = 1;
+pub const MILLI_AVAX: u64 = 1_000_000 * NANO_AVAX;
+pub const AVAX: u64 = 1_000 * MILLI_AVAX;
+
+/// Represents the P-chain transaction fees.
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/genesis#TxFeeConfig
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TxFees {
+    pub tx_fee: u64,
+    pub create_subnet_tx_fee: u64,
+    pub transform_subnet_tx_fee: u64,
+    pub add_subnet_validator_fee: u64,
+    pub add_subnet_delegator_fee: u64,
+    pub add_primary_network_validator_fee: u64,
+    pub add_primary_network_delegator_fee: u64,
+}
+
+impl TxFees {
+    /// Returns the fees hard-coded in avalanchego genesis parameters.
+    /// Custom networks use the local network parameters.
+    /// ref. https://github.com/ava-labs/avalanchego/blob/v1.9.0/genesis/params.go
+    pub fn new(network_id: u32) -> Self {
+        let create_subnet_tx_fee = match network_id {
+            1 | 5 => AVAX,
+            _ => 100 * MILLI_AVAX,
+        };
+        let transform_subnet_tx_fee = match network_id {
+            1 => 10 * AVAX,
+            5 => AVAX,
+            _ => 100 * MILLI_AVAX,
+        };
+        Self {
+            tx_fee: MILLI_AVAX,
+            create_subnet_tx_fee,
+            transform_subnet_tx_fee,
+            add_subnet_validator_fee: MILLI_AVAX,
+            add_subnet_delegator_fee: MILLI_AVAX,
+            add_primary_network_validator_fee: 0,
+            add_primary_network_delegator_fee: 0,
+        }
+    }
+}
+
+/// Implements "secp256k1fx.OutputOwners".
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/secp256k1fx#OutputOwners
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct OutputOwners {
+    pub locktime: u64,
+    pub threshold: u32,
+    /// MUST BE sorted and unique.
+    pub addrs: Vec<ShortId>,
+}
+
+impl OutputOwners {
+    /// Creates the output owners with a single address.
+    pub fn new(addr: ShortId) -> Self {
+        Self {
+            locktime: 0,
+            threshold: 1,
+            addrs: vec![addr],
+        }
+    }
+
+    /// Creates the N-of-M output owners, with the addresses sorted and deduplicated.
+    pub fn new_multisig(threshold: u32, addrs: &[ShortId]) -> io::Result<Self> {
+        let mut addrs = addrs.to_vec();
+        addrs.sort();
+        addrs.dedup();
+        if threshold == 0 || threshold as usize > addrs.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("threshold {} out of range [1, {}]", threshold, addrs.len()),
+            ));
+        }
+        Ok(Self {
+            locktime: 0,
+            threshold,
+            addrs,
+        })
+    }
+
+    fn pack(&self, p: &mut Packer) {
+        p.pack_u64(self.locktime);
+        p.pack_u32(self.threshold);
+        p.pack_u32(self.addrs.len() as u32);
+        for addr in self.addrs.iter() {
+            p.pack_fixed_bytes(addr);
+        }
+    }
+
+    fn unpack(u: &mut Unpacker) -> io::Result<Self> {
+        let locktime = u.unpack_u64()?;
+        let threshold = u.unpack_u32()?;
+        let n = u.unpack_u32()?;
+        let mut addrs = Vec::new();
+        for _ in 0..n {
+            let mut addr: ShortId = [0; ids::SHORT_ID_LEN];
+            addr.copy_from_slice(u.unpack_fixed_bytes(ids::SHORT_ID_LEN)?);
+            addrs.push(addr);
+        }
+        Ok(Self {
+            locktime,
+            threshold,
+            addrs,
+        })
+    }
+}
+
+/// Implements "avax.TransferableOutput" with "secp256k1fx.TransferOutput".
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/components/avax#TransferableOutput
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TransferableOutput {
+    pub asset_id: Id,
+    pub amount: u64,
+    pub owners: OutputOwners,
+}
+
+impl TransferableOutput {
+    fn pack(&self, p: &mut Packer) {
+        p.pack_fixed_bytes(&self.asset_id);
+        p.pack_u32(TYPE_ID_SECP256K1_TRANSFER_OUTPUT);
+        p.pack_u64(self.amount);
+        self.owners.pack(p);
+    }
+
+    fn packed(&self) -> Vec<u8> {
+        let mut p = Packer::default();
+        self.pack(&mut p);
+        p.bytes
+    }
+}
+
+/// Implements "avax.SortTransferableOutputs".
+pub fn sort_outputs(outs: &mut [TransferableOutput]) {
+    outs.sort_by_key(|o| o.packed());
+}
+
+/// Implements "avax.TransferableInput" with "secp256k1fx.TransferInput".
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/components/avax#TransferableInput
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TransferableInput {
+    pub tx_id: Id,
+    pub output_index: u32,
+    pub asset_id: Id,
+    pub amount: u64,
+    /// MUST BE sorted and unique.
+    pub sig_indices: Vec<u32>,
+}
+
+impl TransferableInput {
+    fn pack(&self, p: &mut Packer) {
+        p.pack_fixed_bytes(&self.tx_id);
+        p.pack_u32(self.output_index);
+        p.pack_fixed_bytes(&self.asset_id);
+        p.pack_u32(TYPE_ID_SECP256K1_TRANSFER_INPUT);
+        p.pack_u64(self.amount);
+        pack_u32s(p, &self.sig_indices);
+    }
+
+    /// Implements "avax.SortTransferableInputsWithSigners" ordering.
+    fn cmp_utxo_id(&self, other: &Self) -> Ordering {
+        self.tx_id
+            .cmp(&other.tx_id)
+            .then(self.output_index.cmp(&other.output_index))
+    }
+}
+
+/// Represents the unspent transaction output with "secp256k1fx.TransferOutput".
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/components/avax#UTXO
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Utxo {
+    pub tx_id: Id,
+    pub output_index: u32,
+    pub asset_id: Id,
+    pub amount: u64,
+    pub owners: OutputOwners,
+}
+
+impl Utxo {
+    /// Parses the codec-encoded UTXO bytes.
+    /// Returns None for the unsupported output types (e.g., stakeable locked outputs).
+    pub fn unpack(d: &[u8]) -> io::Result<Option<Self>> {
+        let mut u = Unpacker::new(d);
+        let codec_version = u.unpack_u16()?;
+        if codec_version != CODEC_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown codec version {}", codec_version),
+            ));
+        }
+
+        let mut tx_id: Id = ids::EMPTY_ID;
+        tx_id.copy_from_slice(u.unpack_fixed_bytes(ids::ID_LEN)?);
+        let output_index = u.unpack_u32()?;
+        let mut asset_id: Id = ids::EMPTY_ID;
+        asset_id.copy_from_slice(u.unpack_fixed_bytes(ids::ID_LEN)?);
+
+        let type_id = u.unpack_u32()?;
+        if type_id != TYPE_ID_SECP256K1_TRANSFER_OUTPUT {
+            return Ok(None);
+        }
+        let amount = u.unpack_u64()?;
+        let owners = OutputOwners::unpack(&mut u)?;
+        Ok(Some(Self {
+            tx_id,
+            output_index,
+            asset_id,
+            amount,
+            owners,
+        }))
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut p = Packer::default();
+        p.pack_u16(CODEC_VERSION);
+        p.pack_fixed_bytes(&self.tx_id);
+        p.pack_u32(self.output_index);
+        TransferableOutput {
+            asset_id: self.asset_id,
+            amount: self.amount,
+            owners: self.owners.clone(),
+        }
+        .pack(&mut p);
+        p.bytes
+    }
+}
+
+/// Implements "avax.BaseTx".
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/components/avax#BaseTx
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct BaseTx {
+    pub network_id: u32,
+    pub blockchain_id: Id,
+    pub outs: Vec<TransferableOutput>,
+    pub ins: Vec<TransferableInput>,
+    pub memo: Vec<u8>,
+}
+
+impl BaseTx {
+    fn pack(&self, p: &mut Packer) {
+        p.pack_u32(self.network_id);
+        p.pack_fixed_bytes(&self.blockchain_id);
+        p.pack_u32(self.outs.len() as u32);
+        for o in self.outs.iter() {
+            o.pack(p);
+        }
+        p.pack_u32(self.ins.len() as u32);
+        for i in self.ins.iter() {
+            i.pack(p);
+        }
+        p.pack_bytes(&self.memo);
+    }
+}
+
+/// Implements "validator.Validator".
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/platformvm/validator#Validator
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Validator {
+    pub node_id: ShortId,
+    /// Unix time in seconds.
+    pub start: u64,
+    /// Unix time in seconds.
+    pub end: u64,
+    pub weight: u64,
+}
+
+impl Validator {
+    fn pack(&self, p: &mut Packer) {
+        p.pack_fixed_bytes(&self.node_id);
+        p.pack_u64(self.start);
+        p.pack_u64(self.end);
+        p.pack_u64(self.weight);
+    }
+}
+
+/// Parameters for "TransformSubnetTx".
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/platformvm/txs#TransformSubnetTx
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct TransformSubnetParams {
+    pub subnet_id: String,
+    /// The asset to use for staking and rewards on the subnet,
+    /// which must be already exported to the P-chain.
+    pub asset_id: String,
+    pub initial_supply: u64,
+    pub maximum_supply: u64,
+    /// In units of "PERCENT_DENOMINATOR".
+    pub min_consumption_rate: u64,
+    /// In units of "PERCENT_DENOMINATOR".
+    pub max_consumption_rate: u64,
+    pub min_validator_stake: u64,
+    pub max_validator_stake: u64,
+    /// In seconds.
+    pub min_stake_duration: u32,
+    /// In seconds.
+    pub max_stake_duration: u32,
+    /// In units of "PERCENT_DENOMINATOR".
+    pub min_delegation_fee: u32,
+    pub min_delegator_stake: u64,
+    pub max_validator_weight_factor: u8,
+    /// In units of "PERCENT_DENOMINATOR".
+    pub uptime_requirement: u32,
+}
+
+impl TransformSubnetParams {
+    /// Implements "TransformSubnetTx.SyntacticVerify".
+    /// ref. https://github.com/ava-labs/avalanchego/blob/v1.9.0/vms/platformvm/txs/transform_subnet_tx.go
+    pub fn verify(&self) -> io::Result<()> {
+        let subnet_id = ids::parse_id(&self.subnet_id)?;
+        if subnet_id == ids::EMPTY_ID {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "cannot transform the primary network",
+            ));
+        }
+        let asset_id = ids::parse_id(&self.asset_id)?;
+        if asset_id == ids::EMPTY_ID {
+            return Err(Error::new(ErrorKind::InvalidInput, "empty 'asset_id'"));
+        }
+
+        let checks: Vec<(bool, &str)> = vec![
+            (
+                self.initial_supply != 0,
+                "'initial_supply' must be non-zero",
+            ),
+            (
+                self.initial_supply <= self.maximum_supply,
+                "'initial_supply' must be <= 'maximum_supply'",
+            ),
+            (
+                self.min_consumption_rate <= self.max_consumption_rate,
+                "'min_consumption_rate' must be <= 'max_consumption_rate'",
+            ),
+            (
+                self.max_consumption_rate <= PERCENT_DENOMINATOR,
+                "'max_consumption_rate' must be <= 1,000,000",
+            ),
+            (
+                self.min_validator_stake != 0,
+                "'min_validator_stake' must be non-zero",
+            ),
+            (
+                self.min_validator_stake <= self.initial_supply,
+                "'min_validator_stake' must be <= 'initial_supply'",
+            ),
+            (
+                self.min_validator_stake <= self.max_validator_stake,
+                "'min_validator_stake' must be <= 'max_validator_stake'",
+            ),
+            (
+                self.max_validator_stake <= self.maximum_supply,
+                "'max_validator_stake' must be <= 'maximum_supply'",
+            ),
+            (
+                self.min_stake_duration != 0,
+                "'min_stake_duration' must be non-zero",
+            ),
+            (
+                self.min_stake_duration <= self.max_stake_duration,
+                "'min_stake_duration' must be <= 'max_stake_duration'",
+            ),
+            (
+                self.min_delegation_fee as u64 <= PERCENT_DENOMINATOR,
+                "'min_delegation_fee' must be <= 1,000,000",
+            ),
+            (
+                self.min_delegator_stake != 0,
+                "'min_delegator_stake' must be non-zero",
+            ),
+            (
+                self.max_validator_weight_factor != 0,
+                "'max_validator_weight_factor' must be non-zero",
+            ),
+            (
+                self.uptime_requirement as u64 <= PERCENT_DENOMINATOR,
+                "'uptime_requirement' must be <= 1,000,000",
+            ),
+        ];
+        for (ok, msg) in checks.iter() {
+            if !ok {
+                return Err(Error::new(ErrorKind::InvalidInput, msg.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Represents the unsigned P-chain transactions.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum UnsignedTx {
+    /// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/platformvm/txs#AddValidatorTx
+    AddValidator {
+        base: BaseTx,
+        validator: Validator,
+        stake_outs: Vec<TransferableOutput>,
+        rewards_owner: OutputOwners,
+        shares: u32,
+    },
+    /// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/platformvm/txs#AddDelegatorTx
+    AddDelegator {
+        base: BaseTx,
+        validator: Validator,
+        stake_outs: Vec<TransferableOutput>,
+        rewards_owner: OutputOwners,
+    },
+    /// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/platformvm/txs#CreateSubnetTx
+    CreateSubnet { base: BaseTx, owner: OutputOwners },
+    /// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/platformvm/txs#AddSubnetValidatorTx
+    AddSubnetValidator {
+        base: BaseTx,
+        validator: Validator,
+        subnet_id: Id,
+        subnet_auth: Vec<u32>,
+    },
+    /// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/platformvm/txs#RemoveSubnetValidatorTx
+    RemoveSubnetValidator {
+        base: BaseTx,
+        node_id: ShortId,
+        subnet_id: Id,
+        subnet_auth: Vec<u32>,
+    },
+    /// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/platformvm/txs#TransformSubnetTx
+    TransformSubnet {
+        base: BaseTx,
+        params: TransformSubnetParams,
+        subnet_auth: Vec<u32>,
+    },
+    /// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/platformvm/txs#AddPermissionlessValidatorTx
+    AddPermissionlessValidator {
+        base: BaseTx,
+        validator: Validator,
+        subnet_id: Id,
+        stake_outs: Vec<TransferableOutput>,
+        validator_rewards_owner: OutputOwners,
+        delegator_rewards_owner: OutputOwners,
+        delegation_shares: u32,
+    },
+    /// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/platformvm/txs#AddPermissionlessDelegatorTx
+    AddPermissionlessDelegator {
+        base: BaseTx,
+        validator: Validator,
+        subnet_id: Id,
+        stake_outs: Vec<TransferableOutput>,
+        delegation_rewards_owner: OutputOwners,
+    },
+}
+
+impl UnsignedTx {
+    pub fn base(&self) -> &BaseTx {
+        match self {
+            UnsignedTx::AddValidator { base, .. } => base,
+            UnsignedTx::AddDelegator { base, .. } => base,
+            UnsignedTx::CreateSubnet { base, .. } => base,
+            UnsignedTx::AddSubnetValidator { base, .. } => base,
+            UnsignedTx::RemoveSubnetValidator { base, .. } => base,
+            UnsignedTx::TransformSubnet { base, .. } => base,
+            UnsignedTx::AddPermissionlessValidator { base, .. } => base,
+            UnsignedTx::AddPermissionlessDelegator { base, .. } => base,
+        }
+    }
+
+    /// Returns true if the transaction requires the subnet owner signatures.
+    pub fn has_subnet_auth(&self) -> bool {
+        matches!(
+            self,
+            UnsignedTx::AddSubnetValidator { .. }
+                | UnsignedTx::RemoveSubnetValidator { .. }
+                | UnsignedTx::TransformSubnet { .. }
+        )
+    }
+
+    /// Returns the codec-encoded bytes with the codec version and type ID,
+    /// which is hashed for signing.
+    pub fn pack(&self) -> io::Result<Vec<u8>> {
+        let mut p = Packer::default();
+        p.pack_u16(CODEC_VERSION);
+        match self {
+            UnsignedTx::AddValidator {
+                base,
+                validator,
+                stake_outs,
+                rewards_owner,
+                shares,
+            } => {
+                p.pack_u32(TYPE_ID_ADD_VALIDATOR_TX);
+                base.pack(&mut p);
+                validator.pack(&mut p);
+                p.pack_u32(stake_outs.len() as u32);
+                for o in stake_outs.iter() {
+                    o.pack(&mut p);
+                }
+                p.pack_u32(TYPE_ID_SECP256K1_OUTPUT_OWNERS);
+                rewards_owner.pack(&mut p);
+                p.pack_u32(*shares);
+            }
+            UnsignedTx::AddDelegator {
+                base,
+                validator,
+                stake_outs,
+                rewards_owner,
+            } => {
+                p.pack_u32(TYPE_ID_ADD_DELEGATOR_TX);
+                base.pack(&mut p);
+                validator.pack(&mut p);
+                p.pack_u32(stake_outs.len() as u32);
+                for o in stake_outs.iter() {
+                    o.pack(&mut p);
+                }
+                p.pack_u32(TYPE_ID_SECP256K1_OUTPUT_OWNERS);
+                rewards_owner.pack(&mut p);
+            }
+            UnsignedTx::CreateSubnet { base, owner } => {
+                p.pack_u32(TYPE_ID_CREATE_SUBNET_TX);
+                base.pack(&mut p);
+                p.pack_u32(TYPE_ID_SECP256K1_OUTPUT_OWNERS);
+                owner.pack(&mut p);
+            }
+            UnsignedTx::AddSubnetValidator {
+                base,
+                validator,
+                subnet_id,
+                subnet_auth,
+            } => {
+                p.pack_u32(TYPE_ID_ADD_SUBNET_VALIDATOR_TX);
+                base.pack(&mut p);
+                validator.pack(&mut p);
+                p.pack_fixed_bytes(subnet_id);
+                pack_subnet_auth(&mut p, subnet_auth);
+            }
+            UnsignedTx::RemoveSubnetValidator {
+                base,
+                node_id,
+                subnet_id,
+                subnet_auth,
+            } => {
+                p.pack_u32(TYPE_ID_REMOVE_SUBNET_VALIDATOR_TX);
+                base.pack(&mut p);
+                p.pack_fixed_bytes(node_id);
+                p.pack_fixed_bytes(subnet_id);
+                pack_subnet_auth(&mut p, subnet_auth);
+            }
+            UnsignedTx::TransformSubnet {
+                base,
+                params,
+                subnet_auth,
+            } => {
+                p.pack_u32(TYPE_ID_TRANSFORM_SUBNET_TX);
+                base.pack(&mut p);
+                p.pack_fixed_bytes(&ids::parse_id(&params.subnet_id)?);
+                p.pack_fixed_bytes(&ids::parse_id(&params.asset_id)?);
+                p.pack_u64(params.initial_supply);
+                p.pack_u64(params.maximum_supply);
+                p.pack_u64(params.min_consumption_rate);
+                p.pack_u64(params.max_consumption_rate);
+                p.pack_u64(params.min_validator_stake);
+                p.pack_u64(params.max_validator_stake);
+                p.pack_u32(params.min_stake_duration);
+                p.pack_u32(params.max_stake_duration);
+                p.pack_u32(params.min_delegation_fee);
+                p.pack_u64(params.min_delegator_stake);
+                p.pack_byte(params.max_validator_weight_factor);
+                p.pack_u32(params.uptime_requirement);
+                pack_subnet_auth(&mut p, subnet_auth);
+            }
+            UnsignedTx::AddPermissionlessValidator {
+                base,
+                validator,
+                subnet_id,
+                stake_outs,
+                validator_rewards_owner,
+                delegator_rewards_owner,
+                delegation_shares,
+            } => {
+                p.pack_u32(TYPE_ID_ADD_PERMISSIONLESS_VALIDATOR_TX);
+                base.pack(&mut p);
+                validator.pack(&mut p);
+                p.pack_fixed_bytes(subnet_id);
+                // subnet validators do not register BLS keys
+                p.pack_u32(TYPE_ID_EMPTY_SIGNER);
+                p.pack_u32(stake_outs.len() as u32);
+                for o in stake_outs.iter() {
+                    o.pack(&mut p);
+                }
+                p.pack_u32(TYPE_ID_SECP256K1_OUTPUT_OWNERS);
+                validator_rewards_owner.pack(&mut p);
+                p.pack_u32(TYPE_ID_SECP256K1_OUTPUT_OWNERS);
+                delegator_rewards_owner.pack(&mut p);
+                p.pack_u32(*delegation_shares);
+            }
+            UnsignedTx::AddPermissionlessDelegator {
+                base,
+                validator,
+                subnet_id,
+                stake_outs,
+                delegation_rewards_owner,
+            } => {
+                p.pack_u32(TYPE_ID_ADD_PERMISSIONLESS_DELEGATOR_TX);
+                base.pack(&mut p);
+                validator.pack(&mut p);
+                p.pack_fixed_bytes(subnet_id);
+                p.pack_u32(stake_outs.len() as u32);
+                for o in stake_outs.iter() {
+                    o.pack(&mut p);
+                }
+                p.pack_u32(TYPE_ID_SECP256K1_OUTPUT_OWNERS);
+                delegation_rewards_owner.pack(&mut p);
+            }
+        }
+        Ok(p.bytes)
+    }
+
+    /// Signs the transaction, and returns the signed bytes and its transaction ID.
+    /// Each input is signed by the key, and so is the subnet auth (if any).
+    pub fn sign(&self, k: &key::Key) -> io::Result<(Vec<u8>, Id)> {
+        let unsigned = self.pack()?;
+        let hash = compute_sha256(&unsigned);
+        let sig = k.sign_digest(&hash)?;
+
+        let mut creds: Vec<Vec<[u8; 65]>> = Vec::new();
+        for input in self.base().ins.iter() {
+            creds.push(vec![sig; input.sig_indices.len()]);
+        }
+        match self {
+            UnsignedTx::AddSubnetValidator { subnet_auth, .. }
+            | UnsignedTx::RemoveSubnetValidator { subnet_auth, .. }
+            | UnsignedTx::TransformSubnet { subnet_auth, .. } => {
+                creds.push(vec![sig; subnet_auth.len()]);
+            }
+            _ => {}
+        }
+
+        Ok(pack_signed(unsigned, &creds))
+    }
+}
+
+/// Appends the credentials to the unsigned transaction bytes,
+/// and returns the signed bytes and its transaction ID.
+pub fn pack_signed(unsigned: Vec<u8>, creds: &[Vec<[u8; 65]>]) -> (Vec<u8>, Id) {
+    let mut p = Packer { bytes: unsigned };
+    p.pack_u32(creds.len() as u32);
+    for sigs in creds.iter() {
+        p.pack_u32(TYPE_ID_SECP256K1_CREDENTIAL);
+        p.pack_u32(sigs.len() as u32);
+        for s in sigs.iter() {
+            p.pack_fixed_bytes(s);
+        }
+    }
+
+    let mut tx_id: Id = ids::EMPTY_ID;
+    tx_id.copy_from_slice(&compute_sha256(&p.bytes));
+    (p.bytes, tx_id)
+}
+
+fn pack_u32s(p: &mut Packer, v: &[u32]) {
+    p.pack_u32(v.len() as u32);
+    for x in v.iter() {
+        p.pack_u32(*x);
+    }
+}
+
+fn pack_subnet_auth(p: &mut Packer, sig_indices: &[u32]) {
+    p.pack_u32(TYPE_ID_SECP256K1_INPUT);
+    pack_u32s(p, sig_indices);
+}
+
+pub fn compute_sha256(input: &[u8]) -> Vec<u8> {
+    digest(&SHA256, input).as_ref().into()
+}
+
+/// Represents the amounts to burn and stake for an asset.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct Spend {
+    pub burn: u64,
+    pub stake: u64,
+}
+
+/// Selects the unlocked UTXOs owned by the address to cover the amounts
+/// per asset, and returns the sorted inputs, change outputs, and stake outputs.
+/// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/vms/platformvm/utxo#Handler
+pub fn spend(
+    utxos: &[Utxo],
+    owner: &ShortId,
+    now_unix: u64,
+    amounts: &BTreeMap<Id, Spend>,
+) -> io::Result<(
+    Vec<TransferableInput>,
+    Vec<TransferableOutput>,
+    Vec<TransferableOutput>,
+)> {
+    let spent = spend_from(
+        utxos,
+        &OutputOwners::new(*owner),
+        &[*owner],
+        now_unix,
+        amounts,
+    )?;
+    Ok((spent.ins, spent.change_outs, spent.stake_outs))
+}
+
+/// Represents the selected inputs with their signers, and the outputs.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Spent {
+    pub ins: Vec<TransferableInput>,
+    /// Signer addresses of each input in the signature order.
+    pub signers: Vec<Vec<ShortId>>,
+    pub change_outs: Vec<TransferableOutput>,
+    pub stake_outs: Vec<TransferableOutput>,
+}
+
+/// Selects the unlocked UTXOs that can be signed by the signers
+/// (N-of-M UTXOs require N signers), and returns the change and stake
+/// outputs owned by "owners".
+pub fn spend_from(
+    utxos: &[Utxo],
+    owners: &OutputOwners,
+    signers: &[ShortId],
+    now_unix: u64,
+    amounts: &BTreeMap<Id, Spend>,
+) -> io::Result<Spent> {
+    let mut ins: Vec<(TransferableInput, Vec<ShortId>)> = Vec::new();
+    let mut change_outs: Vec<TransferableOutput> = Vec::new();
+    let mut stake_outs: Vec<TransferableOutput> = Vec::new();
+
+    for (asset_id, amount) in amounts.iter() {
+        let target = amount.burn + amount.stake;
+        if target == 0 {
+            continue;
+        }
+
+        let mut consumed: u64 = 0;
+        for utxo in utxos.iter() {
+            if consumed >= target {
+                break;
+            }
+            if utxo.asset_id != *asset_id || utxo.owners.locktime > now_unix {
+                continue;
+            }
+            let (sig_indices, input_signers) =
+                match select_signers(&utxo.owners.addrs, utxo.owners.threshold, signers) {
+                    Some(v) => v,
+                    None => continue,
+                };
+            consumed += utxo.amount;
+            ins.push((
+                TransferableInput {
+                    tx_id: utxo.tx_id,
+                    output_index: utxo.output_index,
+                    asset_id: *asset_id,
+                    amount: utxo.amount,
+                    sig_indices,
+                },
+                input_signers,
+            ));
+        }
+        if consumed < target {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "insufficient funds for asset {} (need {}, have {})",
+                    ids::encode_id(asset_id),
+                    target,
+                    consumed
+                ),
+            ));
+        }
+
+        if amount.stake > 0 {
+            stake_outs.push(TransferableOutput {
+                asset_id: *asset_id,
+                amount: amount.stake,
+                owners: owners.clone(),
+            });
+        }
+        if consumed > target {
+            change_outs.push(TransferableOutput {
+                asset_id: *asset_id,
+                amount: consumed - target,
+                owners: owners.clone(),
+            });
+        }
+    }
+
+    ins.sort_by(|a, b| a.0.cmp_utxo_id(&b.0));
+    sort_outputs(&mut change_outs);
+    sort_outputs(&mut stake_outs);
+    let (ins, signers) = ins.into_iter().unzip();
+    Ok(Spent {
+        ins,
+        signers,
+        change_outs,
+        stake_outs,
+    })
+}
+
+/// Returns the first "threshold" signature indices of the addresses
+/// that can be signed by the signers, and the matching signer addresses.
+/// Returns None if there are not enough signers.
+pub fn select_signers(
+    addrs: &[ShortId],
+    threshold: u32,
+    signers: &[ShortId],
+) -> Option<(Vec<u32>, Vec<ShortId>)> {
+    let mut sig_indices: Vec<u32> = Vec::new();
+    let mut selected: Vec<ShortId> = Vec::new();
+    for (i, addr) in addrs.iter().enumerate() {
+        if sig_indices.len() == threshold as usize {
+            break;
+        }
+        if signers.contains(addr) {
+            sig_indices.push(i as u32);
+            selected.push(*addr);
+        }
+    }
+    if sig_indices.len() < threshold as usize {
+        return None;
+    }
+    Some((sig_indices, selected))
+}
+
+#[test]
+fn test_utxo() {
+    let utxo = Utxo {
+        tx_id: [1; ids::ID_LEN],
+        output_index: 2,
+        asset_id: [3; ids::ID_LEN],
+        amount: 1000,
+        owners: OutputOwners::new([4; ids::SHORT_ID_LEN]),
+    };
+    let d = utxo.pack();
+    assert_eq!(Utxo::unpack(&d).unwrap(), Some(utxo));
+
+    // stakeable locked outputs are not supported
+    let mut d = d;
+    d[2 + 32 + 4 + 32 + 3] = 22;
+    assert_eq!(Utxo::unpack(&d).unwrap(), None);
+}
+
+#[test]
+fn test_spend() {
+    let owner: ShortId = [9; ids::SHORT_ID_LEN];
+    let avax: Id = [1; ids::ID_LEN];
+    let token: Id = [2; ids::ID_LEN];
+    let utxos = vec![
+        Utxo {
+            tx_id: [5; ids::ID_LEN],
+            output_index: 0,
+            asset_id: avax,
+            amount: 10,
+            owners: OutputOwners::new(owner),
+        },
+        Utxo {
+            tx_id: [4; ids::ID_LEN],
+            output_index: 0,
+            asset_id: token,
+            amount: 100,
+            owners: OutputOwners::new(owner),
+        },
+        Utxo {
+            tx_id: [3; ids::ID_LEN],
+            output_index: 0,
+            asset_id: token,
+            amount: 100,
+            owners: OutputOwners::new([8; ids::SHORT_ID_LEN]),
+        },
+    ];
+
+    let mut amounts = BTreeMap::new();
+    amounts.insert(avax, Spend { burn: 1, stake: 0 });
+    amounts.insert(token, Spend { burn: 0, stake: 60 });
+    let (ins, change_outs, stake_outs) = spend(&utxos, &owner, 0, &amounts).unwrap();
+    assert_eq!(ins.len(), 2);
+    assert_eq!(ins[0].tx_id, [4; ids::ID_LEN]);
+    assert_eq!(ins[1].tx_id, [5; ids::ID_LEN]);
+    assert_eq!(change_outs.len(), 2);
+    assert_eq!(change_outs[0].asset_id, avax);
+    assert_eq!(change_outs[0].amount, 9);
+    assert_eq!(change_outs[1].amount, 40);
+    assert_eq!(stake_outs.len(), 1);
+    assert_eq!(stake_outs[0].amount, 60);
+
+    amounts.insert(
+        token,
+        Spend {
+            burn: 0,
+            stake: 101,
+        },
+    );
+    assert!(spend(&utxos, &owner, 0, &amounts).is_err());
+}
+
+#[test]
+fn test_spend_from_multisig() {
+    let (a, b, c): (ShortId, ShortId, ShortId) = ([1; 20], [2; 20], [3; 20]);
+    let owners = OutputOwners::new_multisig(2, &[c, a, b]).unwrap();
+    assert_eq!(owners.addrs, vec![a, b, c]);
+    assert!(OutputOwners::new_multisig(4, &[a, b, c]).is_err());
+
+    let avax: Id = [1; ids::ID_LEN];
+    let utxos = vec![Utxo {
+        tx_id: [5; ids::ID_LEN],
+        output_index: 0,
+        asset_id: avax,
+        amount: 10,
+        owners: owners.clone(),
+    }];
+    let mut amounts = BTreeMap::new();
+    amounts.insert(avax, Spend { burn: 1, stake: 0 });
+
+    let spent = spend_from(&utxos, &owners, &[c, b], 0, &amounts).unwrap();
+    assert_eq!(spent.ins[0].sig_indices, vec![1, 2]);
+    assert_eq!(spent.signers[0], vec![b, c]);
+    assert_eq!(spent.change_outs[0].owners, owners);
+
+    // 1 signer cannot spend 2-of-3 UTXOs
+    assert!(spend_from(&utxos, &owners, &[a], 0, &amounts).is_err());
+}
+
+#[test]
+fn test_transform_subnet_params() {
+    let mut params = TransformSubnetParams {
+        subnet_id: ids::encode_id(&[1; ids::ID_LEN]),
+        asset_id: ids::encode_id(&[2; ids::ID_LEN]),
+        initial_supply: 1_000,
+        maximum_supply: 2_000,
+        min_consumption_rate: 100_000,
+        max_consumption_rate: 120_000,
+        min_validator_stake: 10,
+        max_validator_stake: 500,
+        min_stake_duration: 24 * 60 * 60,
+        max_stake_duration: 365 * 24 * 60 * 60,
+        min_delegation_fee: 20_000,
+        min_delegator_stake: 1,
+        max_validator_weight_factor: 5,
+        uptime_requirement: 800_000,
+    };
+    assert!(params.verify().is_ok());
+
+    let tx = UnsignedTx::TransformSubnet {
+        base: BaseTx {
+            network_id: 5,
+            blockchain_id: ids::EMPTY_ID,
+            outs: Vec::new(),
+            ins: Vec::new(),
+            memo: Vec::new(),
+        },
+        params: params.clone(),
+        subnet_auth: vec![0],
+    };
+    let d = tx.pack().unwrap();
+    // codec version + type ID + base tx + IDs + fields + subnet auth
+    assert_eq!(d.len(), 2 + 4 + 48 + 64 + 8 * 6 + 4 * 3 + 8 + 1 + 4 + 12);
+    assert_eq!(&d[2..6], &TYPE_ID_TRANSFORM_SUBNET_TX.to_be_bytes());
+
+    params.subnet_id = ids::encode_id(&ids::EMPTY_ID);
+    assert!(params.verify().is_err());
+    params.subnet_id = ids::encode_id(&[1; ids::ID_LEN]);
+    params.initial_supply = 3_000;
+    assert!(params.verify().is_err());
+}
@@ -0,0 +1,475 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Error, ErrorKind},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::info;
+use tokio::time::sleep;
+
+use crate::avalanche::{
+    avalanchego::api::platform,
+    constants, formatting,
+    ids::{self, Id, ShortId},
+    key,
+    platformvm::{
+        multisig::PartiallySignedTx,
+        txs::{self, BaseTx, OutputOwners, Spend, TxFees, UnsignedTx, Utxo, Validator},
+    },
+};
+
+/// Builds, signs, and issues the P-chain transactions.
+/// By default, the local key pays for the fees and stakes, and signs everything.
+/// With "with_multisig", the transactions that need signatures from the other
+/// keys are returned as partially signed, to be exported for the co-signers.
+pub struct Wallet {
+    pub key: key::Key,
+    pub network_id: u32,
+    /// P-chain API endpoint (e.g., "https://api.avax-test.network").
+    pub http_rpc: String,
+    pub p_address: String,
+    pub short_address: ShortId,
+    pub avax_asset_id: Id,
+    pub fees: TxFees,
+    /// Owners of the UTXOs to spend, and of the change and stake outputs.
+    pub owners: OutputOwners,
+    /// Addresses expected to sign the transactions, including this key.
+    pub signers: Vec<ShortId>,
+}
+
+/// Represents the result of issuing a transaction.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Issued {
+    /// Transaction ID of the committed transaction.
+    Committed(String),
+    /// Transaction that still requires signatures from the other keys.
+    PartiallySigned(PartiallySignedTx),
+}
+
+impl Wallet {
+    pub async fn new(k: key::Key, network_id: u32, http_rpc: &str) -> io::Result<Self> {
+        let p_address = k.address("P", network_id)?;
+        let mut short_address: ShortId = [0; ids::SHORT_ID_LEN];
+        short_address.copy_from_slice(&k.short_address_bytes()?);
+
+        let avax_asset_id = platform::get_staking_asset_id(http_rpc, None).await?;
+        let avax_asset_id = ids::parse_id(&avax_asset_id)?;
+        info!(
+            "loaded wallet {} (AVAX asset ID {})",
+            p_address,
+            ids::encode_id(&avax_asset_id)
+        );
+
+        Ok(Self {
+            key: k,
+            network_id,
+            http_rpc: http_rpc.to_string(),
+            p_address,
+            short_address,
+            avax_asset_id,
+            fees: TxFees::new(network_id),
+            owners: OutputOwners::new(short_address),
+            signers: vec![short_address],
+        })
+    }
+
+    /// Sets the co-signers of the transactions (e.g., subnet control keys).
+    /// If "funding_threshold" is set, the wallet spends the N-of-M UTXOs
+    /// owned by the signers, rather than the ones owned by this key.
+    pub fn with_multisig(
+        mut self,
+        signers: &[ShortId],
+        funding_threshold: Option<u32>,
+    ) -> io::Result<Self> {
+        let mut all_signers = signers.to_vec();
+        all_signers.push(self.short_address);
+        all_signers.sort();
+        all_signers.dedup();
+        if let Some(threshold) = funding_threshold {
+            self.owners = OutputOwners::new_multisig(threshold, &all_signers)?;
+        }
+        self.signers = all_signers;
+        Ok(self)
+    }
+
+    async fn utxos(&self) -> io::Result<Vec<Utxo>> {
+        let hrp = match constants::NETWORK_ID_TO_HRP.get(&self.network_id) {
+            Some(v) => v,
+            None => constants::FALLBACK_HRP,
+        };
+
+        // N-of-M UTXOs are returned for each owner address
+        let mut utxos: Vec<Utxo> = Vec::new();
+        for addr in self.owners.addrs.iter() {
+            let p_address = formatting::address("P", hrp, addr)?;
+            for d in platform::get_utxos(&self.http_rpc, &p_address)
+                .await?
+                .iter()
+            {
+                if let Some(utxo) = Utxo::unpack(d)? {
+                    if !utxos
+                        .iter()
+                        .any(|u| u.tx_id == utxo.tx_id && u.output_index == utxo.output_index)
+                    {
+                        utxos.push(utxo);
+                    }
+                }
+            }
+        }
+        Ok(utxos)
+    }
+
+    /// Returns the signature indices and the signers in the subnet control keys.
+    async fn subnet_auth(&self, subnet_id: &str) -> io::Result<(Vec<u32>, Vec<ShortId>)> {
+        let (control_keys, threshold) =
+            platform::get_subnet_owners(&self.http_rpc, subnet_id).await?;
+
+        // "platform.getSubnets" returns the control keys in the sorted order
+        let mut addrs: Vec<ShortId> = Vec::new();
+        for k in control_keys.iter() {
+            addrs.push(ids::parse_short_address(k)?);
+        }
+        match txs::select_signers(&addrs, threshold, &self.signers) {
+            Some(v) => Ok(v),
+            None => Err(Error::new(
+                ErrorKind::PermissionDenied,
+                format!(
+                    "subnet {} requires {} signatures from control keys {:?} (missing co-signers?)",
+                    subnet_id, threshold, control_keys
+                ),
+            )),
+        }
+    }
+
+    /// Selects the UTXOs and returns the base transaction, stake outputs,
+    /// and the signers of each input.
+    async fn spend(
+        &self,
+        amounts: BTreeMap<Id, Spend>,
+    ) -> io::Result<(BaseTx, Vec<txs::TransferableOutput>, Vec<Vec<ShortId>>)> {
+        let utxos = self.utxos().await?;
+        let spent = txs::spend_from(&utxos, &self.owners, &self.signers, now_unix(), &amounts)?;
+        Ok((
+            BaseTx {
+                network_id: self.network_id,
+                blockchain_id: ids::EMPTY_ID,
+                outs: spent.change_outs,
+                ins: spent.ins,
+                memo: Vec::new(),
+            },
+            spent.stake_outs,
+            spent.signers,
+        ))
+    }
+
+    /// Signs the transaction with this key, and issues it if no other
+    /// signature is required. Otherwise, returns the partially signed transaction.
+    async fn issue(&self, tx: UnsignedTx, signers: Vec<Vec<ShortId>>) -> io::Result<Issued> {
+        let mut partial = PartiallySignedTx::new(self.network_id, &tx.pack()?, &signers)?;
+        partial.sign(&self.key)?;
+        if !partial.is_complete() {
+            info!(
+                "tx requires signatures from {:?}",
+                partial.missing_signers()
+            );
+            return Ok(Issued::PartiallySigned(partial));
+        }
+        let tx_id = issue_signed(&self.http_rpc, &partial).await?;
+        Ok(Issued::Committed(tx_id))
+    }
+
+    /// Creates the subnet owned by the N-of-M control keys.
+    /// The subnet ID is the committed transaction ID.
+    pub async fn create_subnet(&self, control_keys: OutputOwners) -> io::Result<Issued> {
+        let mut amounts = BTreeMap::new();
+        amounts.insert(
+            self.avax_asset_id,
+            Spend {
+                burn: self.fees.create_subnet_tx_fee,
+                stake: 0,
+            },
+        );
+        let (base, _, signers) = self.spend(amounts).await?;
+
+        self.issue(
+            UnsignedTx::CreateSubnet {
+                base,
+                owner: control_keys,
+            },
+            signers,
+        )
+        .await
+    }
+
+    /// Adds the validator to the permissioned subnet.
+    /// The node must be validating the primary network for the whole period.
+    pub async fn add_subnet_validator(
+        &self,
+        subnet_id: &str,
+        validator: Validator,
+    ) -> io::Result<Issued> {
+        let (subnet_auth, subnet_signers) = self.subnet_auth(subnet_id).await?;
+
+        let mut amounts = BTreeMap::new();
+        amounts.insert(
+            self.avax_asset_id,
+            Spend {
+                burn: self.fees.add_subnet_validator_fee,
+                stake: 0,
+            },
+        );
+        let (base, _, mut signers) = self.spend(amounts).await?;
+        signers.push(subnet_signers);
+
+        self.issue(
+            UnsignedTx::AddSubnetValidator {
+                base,
+                validator,
+                subnet_id: ids::parse_id(subnet_id)?,
+                subnet_auth,
+            },
+            signers,
+        )
+        .await
+    }
+
+    /// Removes the validator from the permissioned subnet.
+    pub async fn remove_subnet_validator(
+        &self,
+        subnet_id: &str,
+        node_id: ShortId,
+    ) -> io::Result<Issued> {
+        let (subnet_auth, subnet_signers) = self.subnet_auth(subnet_id).await?;
+
+        let mut amounts = BTreeMap::new();
+        amounts.insert(
+            self.avax_asset_id,
+            Spend {
+                burn: self.fees.tx_fee,
+                stake: 0,
+            },
+        );
+        let (base, _, mut signers) = self.spend(amounts).await?;
+        signers.push(subnet_signers);
+
+        self.issue(
+            UnsignedTx::RemoveSubnetValidator {
+                base,
+                node_id,
+                subnet_id: ids::parse_id(subnet_id)?,
+                subnet_auth,
+            },
+            signers,
+        )
+        .await
+    }
+
+    /// Transforms the permissioned subnet to the elastic subnet.
+    /// The subnet asset for rewards ("maximum_supply - initial_supply")
+    /// is burned from this wallet.
+    pub async fn transform_subnet(
+        &self,
+        params: &txs::TransformSubnetParams,
+    ) -> io::Result<Issued> {
+        params.verify()?;
+        let (subnet_auth, subnet_signers) = self.subnet_auth(&params.subnet_id).await?;
+
+        let mut amounts = BTreeMap::new();
+        amounts.insert(
+            self.avax_asset_id,
+            Spend {
+                burn: self.fees.transform_subnet_tx_fee,
+                stake: 0,
+            },
+        );
+        amounts.insert(
+            ids::parse_id(&params.asset_id)?,
+            Spend {
+                burn: params.maximum_supply - params.initial_supply,
+                stake: 0,
+            },
+        );
+        let (base, _, mut signers) = self.spend(amounts).await?;
+        signers.push(subnet_signers);
+
+        self.issue(
+            UnsignedTx::TransformSubnet {
+                base,
+                params: params.clone(),
+                subnet_auth,
+            },
+            signers,
+        )
+        .await
+    }
+
+    /// Adds the node as a primary network validator,
+    /// staking AVAX with the rewards to this wallet.
+    /// "shares" is the delegation fee in 1/10,000 of a percent (e.g., 20000 for 2%).
+    pub async fn add_validator(&self, validator: Validator, shares: u32) -> io::Result<Issued> {
+        let (base, stake_outs, signers) = self
+            .spend_stake(
+                &ids::encode_id(&self.avax_asset_id),
+                validator.weight,
+                self.fees.add_primary_network_validator_fee,
+            )
+            .await?;
+
+        self.issue(
+            UnsignedTx::AddValidator {
+                base,
+                validator,
+                stake_outs,
+                rewards_owner: self.owners.clone(),
+                shares,
+            },
+            signers,
+        )
+        .await
+    }
+
+    /// Delegates AVAX to the primary network validator,
+    /// with the rewards to this wallet.
+    pub async fn add_delegator(&self, validator: Validator) -> io::Result<Issued> {
+        let (base, stake_outs, signers) = self
+            .spend_stake(
+                &ids::encode_id(&self.avax_asset_id),
+                validator.weight,
+                self.fees.add_primary_network_delegator_fee,
+            )
+            .await?;
+
+        self.issue(
+            UnsignedTx::AddDelegator {
+                base,
+                validator,
+                stake_outs,
+                rewards_owner: self.owners.clone(),
+            },
+            signers,
+        )
+        .await
+    }
+
+    /// Adds the permissionless validator to the elastic subnet,
+    /// staking the subnet asset with the rewards to this wallet.
+    pub async fn add_permissionless_validator(
+        &self,
+        subnet_id: &str,
+        asset_id: &str,
+        validator: Validator,
+        delegation_shares: u32,
+    ) -> io::Result<Issued> {
+        let (base, stake_outs, signers) = self
+            .spend_stake(
+                asset_id,
+                validator.weight,
+                self.fees.add_subnet_validator_fee,
+            )
+            .await?;
+
+        self.issue(
+            UnsignedTx::AddPermissionlessValidator {
+                base,
+                validator,
+                subnet_id: ids::parse_id(subnet_id)?,
+                stake_outs,
+                validator_rewards_owner: self.owners.clone(),
+                delegator_rewards_owner: self.owners.clone(),
+                delegation_shares,
+            },
+            signers,
+        )
+        .await
+    }
+
+    /// Adds the permissionless delegator to the elastic subnet validator,
+    /// staking the subnet asset with the rewards to this wallet.
+    pub async fn add_permissionless_delegator(
+        &self,
+        subnet_id: &str,
+        asset_id: &str,
+        validator: Validator,
+    ) -> io::Result<Issued> {
+        let (base, stake_outs, signers) = self
+            .spend_stake(
+                asset_id,
+                validator.weight,
+                self.fees.add_subnet_delegator_fee,
+            )
+            .await?;
+
+        self.issue(
+            UnsignedTx::AddPermissionlessDelegator {
+                base,
+                validator,
+                subnet_id: ids::parse_id(subnet_id)?,
+                stake_outs,
+                delegation_rewards_owner: self.owners.clone(),
+            },
+            signers,
+        )
+        .await
+    }
+
+    async fn spend_stake(
+        &self,
+        asset_id: &str,
+        stake: u64,
+        fee: u64,
+    ) -> io::Result<(BaseTx, Vec<txs::TransferableOutput>, Vec<Vec<ShortId>>)> {
+        let asset_id = ids::parse_id(asset_id)?;
+        let mut amounts = BTreeMap::new();
+        amounts.insert(
+            self.avax_asset_id,
+            Spend {
+                burn: fee,
+                stake: 0,
+            },
+        );
+        amounts.entry(asset_id).or_insert_with(Spend::default).stake += stake;
+        self.spend(amounts).await
+    }
+}
+
+/// Issues the fully signed transaction, and waits until it is committed.
+pub async fn issue_signed(http_rpc: &str, tx: &PartiallySignedTx) -> io::Result<String> {
+    let (signed, tx_id) = tx.signed()?;
+    let tx_id = ids::encode_id(&tx_id);
+
+    let issued_tx_id = platform::issue_tx(http_rpc, &signed).await?;
+    if issued_tx_id != tx_id {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("issued tx ID {} != expected {}", issued_tx_id, tx_id),
+        ));
+    }
+    info!("issued tx {}", tx_id);
+
+    for _ in 0..30 {
+        sleep(Duration::from_secs(2)).await;
+        let status = platform::get_tx_status(http_rpc, &tx_id).await?;
+        info!("tx {} status {}", tx_id, status);
+        match status.as_str() {
+            "Committed" => return Ok(tx_id),
+            "Dropped" | "Aborted" => {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!("tx {} {}", tx_id, status),
+                ));
+            }
+            _ => {}
+        }
+    }
+    Err(Error::new(
+        ErrorKind::TimedOut,
+        format!("tx {} not committed in time", tx_id),
+    ))
+}
+
+pub fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("unexpected None duration_since")
+        .as_secs()
+}
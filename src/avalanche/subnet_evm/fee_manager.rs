@@ -0,0 +1,147 @@
+use std::io::{self, Error, ErrorKind};
+
+use sha3::{Digest, Keccak256};
+
+use crate::avalanche::{coreth::tx, subnet_evm::genesis::FeeConfig};
+
+/// Address of the fee manager precompile, enabled by "fee_manager_config" in the genesis.
+/// ref. https://github.com/ava-labs/subnet-evm/blob/master/precompile/params.go
+pub const ADDRESS: &str = "0x0200000000000000000000000000000000000003";
+
+/// Gas limit of "setFeeConfig", which writes the eight fields and the last changed block
+/// (20,000 per slot) and reads the allow list, with the intrinsic gas and the margin.
+/// ref. https://github.com/ava-labs/subnet-evm/blob/master/precompile/fee_config_manager.go
+pub const SET_FEE_CONFIG_GAS: u64 = 300_000;
+
+const SET_FEE_CONFIG_SIGNATURE: &str =
+    "setFeeConfig(uint256,uint256,uint256,uint256,uint256,uint256,uint256,uint256)";
+const GET_FEE_CONFIG_SIGNATURE: &str = "getFeeConfig()";
+const READ_ALLOW_LIST_SIGNATURE: &str = "readAllowList(address)";
+
+/// Role of the address in the precompile allow list.
+/// ref. https://github.com/ava-labs/subnet-evm/blob/master/precompile/allow_list.go
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Role {
+    None,
+    Enabled,
+    Admin,
+}
+
+/// Returns the calldata of "setFeeConfig", in the same field order as the ABI.
+pub fn set_fee_config_calldata(fee_config: &FeeConfig) -> io::Result<Vec<u8>> {
+    fee_config.validate()?;
+    let mut d = selector(SET_FEE_CONFIG_SIGNATURE);
+    for v in fee_config_fields(fee_config) {
+        d.extend(encode_u64(v.unwrap_or_default()));
+    }
+    Ok(d)
+}
+
+pub fn get_fee_config_calldata() -> Vec<u8> {
+    selector(GET_FEE_CONFIG_SIGNATURE)
+}
+
+/// Decodes the "getFeeConfig" output.
+pub fn parse_fee_config(d: &[u8]) -> io::Result<FeeConfig> {
+    let words = decode_u64_words(d, 8)?;
+    Ok(FeeConfig {
+        gas_limit: Some(words[0]),
+        target_block_rate: Some(words[1]),
+        min_base_fee: Some(words[2]),
+        target_gas: Some(words[3]),
+        base_fee_change_denominator: Some(words[4]),
+        min_block_gas_cost: Some(words[5]),
+        max_block_gas_cost: Some(words[6]),
+        block_gas_cost_step: Some(words[7]),
+    })
+}
+
+pub fn read_allow_list_calldata(addr: &str) -> io::Result<Vec<u8>> {
+    let mut d = selector(READ_ALLOW_LIST_SIGNATURE);
+    d.extend([0u8; 12]);
+    d.extend(tx::parse_address(addr)?);
+    Ok(d)
+}
+
+/// Decodes the "readAllowList" output.
+pub fn parse_role(d: &[u8]) -> io::Result<Role> {
+    match decode_u64_words(d, 1)?[0] {
+        0 => Ok(Role::None),
+        1 => Ok(Role::Enabled),
+        2 => Ok(Role::Admin),
+        v => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("unknown allow list role {}", v),
+        )),
+    }
+}
+
+/// Returns the fields in the ABI order.
+pub fn fee_config_fields(fee_config: &FeeConfig) -> [Option<u64>; 8] {
+    [
+        fee_config.gas_limit,
+        fee_config.target_block_rate,
+        fee_config.min_base_fee,
+        fee_config.target_gas,
+        fee_config.base_fee_change_denominator,
+        fee_config.min_block_gas_cost,
+        fee_config.max_block_gas_cost,
+        fee_config.block_gas_cost_step,
+    ]
+}
+
+fn selector(signature: &str) -> Vec<u8> {
+    Keccak256::digest(signature.as_bytes())[..4].to_vec()
+}
+
+fn encode_u64(v: u64) -> [u8; 32] {
+    let mut w = [0u8; 32];
+    w[24..].copy_from_slice(&v.to_be_bytes());
+    w
+}
+
+fn decode_u64_words(d: &[u8], n: usize) -> io::Result<Vec<u64>> {
+    if d.len() != n * 32 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("expected {} words, got {} bytes", n, d.len()),
+        ));
+    }
+    let mut words = Vec::with_capacity(n);
+    for w in d.chunks(32) {
+        if w[..24].iter().any(|b| *b != 0) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("word 0x{} overflows u64", hex::encode(w)),
+            ));
+        }
+        let mut b = [0u8; 8];
+        b.copy_from_slice(&w[24..]);
+        words.push(u64::from_be_bytes(b));
+    }
+    Ok(words)
+}
+
+#[test]
+fn test_fee_manager() {
+    assert_eq!(hex::encode(get_fee_config_calldata()), "5fbbc0d2");
+
+    let fee_config = FeeConfig::default();
+    let d = set_fee_config_calldata(&fee_config).unwrap();
+    assert_eq!(hex::encode(&d[..4]), "8f10b586");
+    assert_eq!(d.len(), 4 + 8 * 32);
+    assert_eq!(parse_fee_config(&d[4..]).unwrap(), fee_config);
+
+    let invalid = FeeConfig {
+        target_gas: None,
+        ..FeeConfig::default()
+    };
+    assert!(set_fee_config_calldata(&invalid).is_err());
+    assert!(parse_fee_config(&d[4..36]).is_err());
+
+    let d = read_allow_list_calldata("0x8db97C7cEcE249c2b98bDC0226Cc4C2A57BF52FC").unwrap();
+    assert_eq!(hex::encode(&d[..4]), "eb54dae1");
+    assert_eq!(d.len(), 36);
+    assert_eq!(parse_role(&encode_u64(2)).unwrap(), Role::Admin);
+    assert!(parse_role(&encode_u64(3)).is_err());
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashSet},
     fs::{self, File},
     io::{self, Error, ErrorKind, Write},
     path::Path,
@@ -10,7 +10,7 @@ use log::info;
 use num_bigint::BigInt;
 use serde::{Deserialize, Serialize};
 
-use crate::utils::big_int;
+use crate::{avalanche::coreth::tx, utils::big_int};
 
 /// ref. https://pkg.go.dev/github.com/ava-labs/subnet-evm/core#Genesis
 /// ref. https://pkg.go.dev/github.com/ava-labs/subnet-evm/params#ChainConfig
@@ -147,8 +147,89 @@ impl Genesis {
 
         Ok(())
     }
+
+    /// Validates the chain config, the fee config, and the precompile configs,
+    /// before creating the blockchain with the genesis.
+    pub fn validate(&self) -> io::Result<()> {
+        let config = self
+            .config
+            .as_ref()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "'config' is required"))?;
+
+        match config.chain_id {
+            None | Some(0) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'config.chain_id' is required",
+                ));
+            }
+            // ref. https://github.com/ava-labs/coreth/blob/v0.8.6/plugin/evm/vm.go#L326-L328
+            Some(v) if RESERVED_CHAIN_IDS.contains(&v) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("'config.chain_id' {} is reserved for the C-chain", v),
+                ));
+            }
+            _ => {}
+        }
+
+        let fee_config = config.fee_config.as_ref().ok_or_else(|| {
+            Error::new(ErrorKind::InvalidInput, "'config.fee_config' is required")
+        })?;
+        fee_config.validate()?;
+        // ref. https://github.com/ava-labs/subnet-evm/pull/63
+        if fee_config.gas_limit.map(BigInt::from) != Some(self.gas_limit.clone()) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'gas_limit' {} must be equal to 'config.fee_config.gas_limit' {:?}",
+                    self.gas_limit, fee_config.gas_limit
+                ),
+            ));
+        }
+
+        for (name, allow_list) in [
+            (
+                "contract_deployer_allow_list_config",
+                &config.contract_deployer_allow_list_config,
+            ),
+            (
+                "contract_native_minter_config",
+                &config.contract_native_minter_config,
+            ),
+            ("tx_allow_list_config", &config.tx_allow_list_config),
+            ("fee_manager_config", &config.fee_manager_config),
+        ] {
+            if let Some(v) = allow_list {
+                v.validate(name)?;
+            }
+        }
+        // no admin can enable any address, so no transaction can ever be issued
+        if let Some(v) = &config.tx_allow_list_config {
+            if v.allow_list_admins.clone().unwrap_or_default().is_empty() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'config.tx_allow_list_config' requires at least one admin address",
+                ));
+            }
+        }
+
+        if let Some(v) = &config.warp_config {
+            v.validate()?;
+        }
+
+        if let Some(alloc) = &self.alloc {
+            for addr in alloc.keys() {
+                tx::parse_address(addr)?;
+            }
+        }
+        Ok(())
+    }
 }
 
+/// Chain IDs of the C-chain (local, Fuji, mainnet) that override the subnet chain config.
+pub const RESERVED_CHAIN_IDS: [u64; 3] = [43112, 43113, 43114];
+
 /// ref. https://pkg.go.dev/github.com/ava-labs/subnet-evm/params#ChainConfig
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -188,8 +269,19 @@ pub struct ChainConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub allow_fee_recipients: Option<bool>,
 
+    /// Precompiles activated at "block_timestamp" of each config.
+    /// ref. https://docs.avax.network/subnets/customize-a-subnet#precompiles
     #[serde(skip_serializing_if = "Option::is_none")]
     pub contract_deployer_allow_list_config: Option<ContractDeployerAllowListConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_native_minter_config: Option<AllowListConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_allow_list_config: Option<AllowListConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_manager_config: Option<AllowListConfig>,
+    /// Enables the cross-subnet messages (e.g., Teleporter).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warp_config: Option<WarpConfig>,
 }
 
 impl Default for ChainConfig {
@@ -227,6 +319,10 @@ impl ChainConfig {
             allow_fee_recipients: None,
 
             contract_deployer_allow_list_config: Some(ContractDeployerAllowListConfig::default()),
+            contract_native_minter_config: None,
+            tx_allow_list_config: None,
+            fee_manager_config: None,
+            warp_config: None,
         }
     }
 }
@@ -278,29 +374,160 @@ impl FeeConfig {
             block_gas_cost_step: Some(500000),
         }
     }
+
+    /// Same checks as subnet-evm "FeeConfig.Verify", all fields required.
+    /// ref. https://github.com/ava-labs/subnet-evm/blob/master/commontype/fee_config.go
+    pub fn validate(&self) -> io::Result<()> {
+        let required = |name: &str, v: Option<u64>, min: u64| -> io::Result<u64> {
+            match v {
+                Some(v) if v >= min => Ok(v),
+                Some(v) => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("'fee_config.{}' must be >={} (got {})", name, min, v),
+                )),
+                None => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("'fee_config.{}' is required", name),
+                )),
+            }
+        };
+        required("gas_limit", self.gas_limit, 1)?;
+        required("target_block_rate", self.target_block_rate, 1)?;
+        required("min_base_fee", self.min_base_fee, 0)?;
+        required("target_gas", self.target_gas, 1)?;
+        required(
+            "base_fee_change_denominator",
+            self.base_fee_change_denominator,
+            1,
+        )?;
+        let min_block_gas_cost = required("min_block_gas_cost", self.min_block_gas_cost, 0)?;
+        let max_block_gas_cost = required("max_block_gas_cost", self.max_block_gas_cost, 0)?;
+        required("block_gas_cost_step", self.block_gas_cost_step, 0)?;
+        if min_block_gas_cost > max_block_gas_cost {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'fee_config.min_block_gas_cost' {} must be <= 'max_block_gas_cost' {}",
+                    min_block_gas_cost, max_block_gas_cost
+                ),
+            ));
+        }
+        Ok(())
+    }
 }
 
-/// ref. https://github.com/ava-labs/subnet-evm/blob/master/precompile/contract_deployer_allow_list.go
+/// Represents the allow list based precompile config
+/// (contract deployer, native minter, tx allow list, fee manager).
+/// ref. https://github.com/ava-labs/subnet-evm/blob/master/precompile/allow_list.go
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
-pub struct ContractDeployerAllowListConfig {
+pub struct AllowListConfig {
+    /// Activates the precompile at the block timestamp (unix seconds).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub block_timestamp: Option<u64>,
     #[serde(rename = "adminAddresses", skip_serializing_if = "Option::is_none")]
     pub allow_list_admins: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled_addresses: Option<Vec<String>>,
 }
 
-impl Default for ContractDeployerAllowListConfig {
+/// ref. https://github.com/ava-labs/subnet-evm/blob/master/precompile/contract_deployer_allow_list.go
+pub type ContractDeployerAllowListConfig = AllowListConfig;
+
+impl Default for AllowListConfig {
     fn default() -> Self {
         Self::default()
     }
 }
 
-impl ContractDeployerAllowListConfig {
+impl AllowListConfig {
     pub fn default() -> Self {
         Self {
             block_timestamp: Some(0),
             allow_list_admins: None,
+            enabled_addresses: None,
+        }
+    }
+
+    pub fn validate(&self, name: &str) -> io::Result<()> {
+        if self.block_timestamp.is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("'config.{}.block_timestamp' is required", name),
+            ));
+        }
+        let admins = self.allow_list_admins.clone().unwrap_or_default();
+        let enabled = self.enabled_addresses.clone().unwrap_or_default();
+        let mut seen = HashSet::new();
+        for addr in admins.iter().chain(enabled.iter()) {
+            let parsed = tx::parse_address(addr).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid address in 'config.{}' ({})", name, e),
+                )
+            })?;
+            // an address has one role
+            if !seen.insert(parsed) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("duplicate address '{}' in 'config.{}'", addr, name),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// ref. https://github.com/ava-labs/subnet-evm/blob/master/precompile/contracts/warp/config.go
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WarpConfig {
+    /// Activates the precompile at the block timestamp (unix seconds).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_timestamp: Option<u64>,
+    /// Percentage of the source subnet stake required to verify the message
+    /// (0 for the default 67).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quorum_numerator: Option<u64>,
+}
+
+/// ref. https://github.com/ava-labs/subnet-evm/blob/master/precompile/contracts/warp/config.go
+pub const WARP_QUORUM_NUMERATOR_MINIMUM: u64 = 33;
+pub const WARP_QUORUM_DENOMINATOR: u64 = 100;
+
+impl Default for WarpConfig {
+    fn default() -> Self {
+        Self::default()
+    }
+}
+
+impl WarpConfig {
+    pub fn default() -> Self {
+        Self {
+            block_timestamp: Some(0),
+            quorum_numerator: None,
+        }
+    }
+
+    pub fn validate(&self) -> io::Result<()> {
+        if self.block_timestamp.is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'config.warp_config.block_timestamp' is required",
+            ));
+        }
+        match self.quorum_numerator {
+            None | Some(0) => Ok(()),
+            Some(v) if (WARP_QUORUM_NUMERATOR_MINIMUM..=WARP_QUORUM_DENOMINATOR).contains(&v) => {
+                Ok(())
+            }
+            Some(v) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'config.warp_config.quorum_numerator' {} must be in [{}, {}]",
+                    v, WARP_QUORUM_NUMERATOR_MINIMUM, WARP_QUORUM_DENOMINATOR
+                ),
+            )),
         }
     }
 }
@@ -404,3 +631,68 @@ fn test_parse() {
     let d = d.encode_json().unwrap();
     info!("{}", d);
 }
+
+#[test]
+fn test_validate() {
+    let mut genesis = Genesis::default();
+    assert!(genesis.validate().is_ok());
+
+    let mut config = genesis.config.clone().unwrap();
+    config.chain_id = Some(43114);
+    genesis.config = Some(config.clone());
+    assert!(genesis.validate().is_err());
+
+    config.chain_id = Some(2000777);
+    let mut fee_config = FeeConfig::default();
+    fee_config.gas_limit = Some(8000000);
+    config.fee_config = Some(fee_config.clone());
+    genesis.config = Some(config.clone());
+    // must match "Genesis.gas_limit"
+    assert!(genesis.validate().is_err());
+    genesis.gas_limit = BigInt::from(8000000);
+    assert!(genesis.validate().is_ok());
+
+    fee_config.min_block_gas_cost = Some(20000000);
+    assert!(fee_config.validate().is_err());
+    fee_config.min_block_gas_cost = Some(0);
+    fee_config.target_block_rate = None;
+    assert!(fee_config.validate().is_err());
+
+    config.tx_allow_list_config = Some(AllowListConfig::default());
+    genesis.config = Some(config.clone());
+    assert!(genesis.validate().is_err());
+    config.tx_allow_list_config = Some(AllowListConfig {
+        allow_list_admins: Some(vec![String::from(
+            "0x8db97C7cEcE249c2b98bDC0226Cc4C2A57BF52FC",
+        )]),
+        enabled_addresses: Some(vec![String::from(
+            "0x8db97C7cEcE249c2b98bDC0226Cc4C2A57BF52FC",
+        )]),
+        ..AllowListConfig::default()
+    });
+    genesis.config = Some(config.clone());
+    assert!(genesis.validate().is_err());
+    config.tx_allow_list_config = Some(AllowListConfig {
+        allow_list_admins: Some(vec![String::from(
+            "0x8db97C7cEcE249c2b98bDC0226Cc4C2A57BF52FC",
+        )]),
+        ..AllowListConfig::default()
+    });
+    config.fee_manager_config = Some(AllowListConfig {
+        allow_list_admins: Some(vec![String::from("0x8db9")]),
+        ..AllowListConfig::default()
+    });
+    genesis.config = Some(config.clone());
+    assert!(genesis.validate().is_err());
+
+    config.fee_manager_config = None;
+    config.warp_config = Some(WarpConfig {
+        quorum_numerator: Some(20),
+        ..WarpConfig::default()
+    });
+    genesis.config = Some(config.clone());
+    assert!(genesis.validate().is_err());
+    config.warp_config = Some(WarpConfig::default());
+    genesis.config = Some(config);
+    assert!(genesis.validate().is_ok());
+}
@@ -1,2 +1,4 @@
 pub mod config;
+pub mod fee_manager;
 pub mod genesis;
+pub mod teleporter;
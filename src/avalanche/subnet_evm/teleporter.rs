@@ -0,0 +1,314 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    avalanche::{avalanchego::api::eth, coreth::tx, ids},
+    utils::prefix,
+};
+
+/// Address of the warp precompile, enabled by "warp_config" in the genesis.
+/// ref. https://github.com/ava-labs/subnet-evm/blob/master/precompile/contracts/warp/module.go
+pub const WARP_PRECOMPILE_ADDRESS: &str = "0x0200000000000000000000000000000000000005";
+
+/// Gas limit of "sendCrossChainMessage", which stores the receipt queue
+/// and calls the warp precompile, with the margin.
+pub const SEND_GAS: u64 = 500_000;
+
+/// Gas limit that the relayer must provide to execute the test message on the destination.
+pub const DEFAULT_REQUIRED_GAS_LIMIT: u64 = 100_000;
+
+/// Balance the deployer address needs for the pre-signed deployment transaction
+/// (the gas price and the gas limit are fixed in the signed transaction).
+/// ref. https://github.com/ava-labs/teleporter/blob/main/utils/contract-deployment/README.md
+pub const DEPLOYER_FUNDING_WEI: u128 = 10_000_000_000_000_000_000;
+
+/// Files of the Teleporter release, with the version suffix
+/// (e.g., "TeleporterMessenger_Deployment_Transaction_v1.0.0.txt").
+const DEPLOYMENT_TRANSACTION_FILE_PREFIX: &str = "TeleporterMessenger_Deployment_Transaction_";
+const DEPLOYER_ADDRESS_FILE_PREFIX: &str = "TeleporterMessenger_Deployer_Address_";
+const CONTRACT_ADDRESS_FILE_PREFIX: &str = "TeleporterMessenger_Contract_Address_";
+
+/// "sendCrossChainMessage(TeleporterMessageInput)".
+/// ref. https://github.com/ava-labs/teleporter/blob/main/contracts/src/Teleporter/ITeleporterMessenger.sol
+const SEND_CROSS_CHAIN_MESSAGE_SIGNATURE: &str =
+    "sendCrossChainMessage((bytes32,address,(address,uint256),uint256,address[],bytes))";
+
+/// Represents the pre-signed (Nick's method) deployment of the TeleporterMessenger,
+/// which deploys the contract at the same address on every chain.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Deployment {
+    pub raw_tx: Vec<u8>,
+    pub deployer_address: String,
+    pub contract_address: String,
+}
+
+impl Deployment {
+    /// Loads the release files in the directory.
+    pub fn load(dir: &str) -> io::Result<Self> {
+        let read = |file_prefix: &str| -> io::Result<String> {
+            for entry in fs::read_dir(Path::new(dir))? {
+                let path = entry?.path();
+                let file_name = path
+                    .file_name()
+                    .and_then(|f| f.to_str())
+                    .unwrap_or_default();
+                if file_name.starts_with(file_prefix) {
+                    return Ok(fs::read_to_string(&path)?.trim().to_string());
+                }
+            }
+            Err(Error::new(
+                ErrorKind::NotFound,
+                format!("no '{}*' file in {}", file_prefix, dir),
+            ))
+        };
+
+        let raw_tx_hex = read(DEPLOYMENT_TRANSACTION_FILE_PREFIX)?;
+        let raw_tx = hex::decode(prefix::strip_0x(&raw_tx_hex)).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid deployment transaction ({})", e),
+            )
+        })?;
+        let deployer_address = read(DEPLOYER_ADDRESS_FILE_PREFIX)?;
+        let contract_address = read(CONTRACT_ADDRESS_FILE_PREFIX)?;
+        tx::parse_address(&deployer_address)?;
+        tx::parse_address(&contract_address)?;
+        Ok(Self {
+            raw_tx,
+            deployer_address,
+            contract_address,
+        })
+    }
+}
+
+/// Returns the calldata of "sendCrossChainMessage" without the relayer fee,
+/// allowing any relayer to deliver.
+pub fn send_cross_chain_message_calldata(
+    destination_blockchain_id: &ids::Id,
+    destination_address: &[u8; 20],
+    required_gas_limit: u64,
+    message: &[u8],
+) -> Vec<u8> {
+    let mut d = Keccak256::digest(SEND_CROSS_CHAIN_MESSAGE_SIGNATURE.as_bytes())[..4].to_vec();
+    // offset of the dynamic tuple
+    d.extend(word_u64(32));
+
+    // tuple head: 7 slots (the fee info tuple is static, so inlined)
+    let head_len = 7 * 32;
+    d.extend(destination_blockchain_id);
+    d.extend(word_address(destination_address));
+    d.extend(word_address(&[0u8; 20]));
+    d.extend(word_u64(0));
+    d.extend(word_u64(required_gas_limit));
+    d.extend(word_u64(head_len));
+    // after the empty relayer addresses array (one length slot)
+    d.extend(word_u64(head_len + 32));
+
+    d.extend(word_u64(0));
+    d.extend(word_u64(message.len() as u64));
+    d.extend(message);
+    let padding = (32 - message.len() % 32) % 32;
+    d.extend(vec![0u8; padding]);
+    d
+}
+
+/// Returns the message ID in the "SendCrossChainMessage" event of the send receipt,
+/// indexed as (messageID, destinationBlockchainID).
+pub fn message_id_from_logs(
+    logs: &[eth::Log],
+    teleporter_address: &str,
+    destination_blockchain_id: &ids::Id,
+) -> Option<String> {
+    let destination_topic = format!("0x{}", hex::encode(destination_blockchain_id));
+    logs.iter()
+        .filter(|l| l.address.eq_ignore_ascii_case(teleporter_address))
+        .find(|l| l.topics.len() >= 3 && l.topics[2].eq_ignore_ascii_case(&destination_topic))
+        .map(|l| l.topics[1].clone())
+}
+
+/// Returns the topics that match the destination events of the message
+/// ("ReceiveCrossChainMessage", "MessageExecuted", "MessageExecutionFailed"),
+/// all indexed by the message ID first, so the delivery is detected
+/// even if the destination address does not execute the message.
+pub fn delivery_topics(message_id: &str) -> Vec<Option<String>> {
+    vec![None, Some(message_id.to_string())]
+}
+
+/// Returns the test message payload.
+pub fn test_message(seq: u64, sent_at: &str) -> Vec<u8> {
+    format!("avalanche-ops teleporter test {} {}", seq, sent_at).into_bytes()
+}
+
+fn word_u64(v: u64) -> [u8; 32] {
+    let mut w = [0u8; 32];
+    w[24..].copy_from_slice(&v.to_be_bytes());
+    w
+}
+
+fn word_address(addr: &[u8; 20]) -> [u8; 32] {
+    let mut w = [0u8; 32];
+    w[12..].copy_from_slice(addr);
+    w
+}
+
+/// Result of a test message.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Delivery {
+    pub seq: u64,
+    pub message_id: String,
+    pub source_tx: String,
+    pub delivered: bool,
+    /// From sending on the source to the event on the destination.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+}
+
+/// Results of the test messages from the source to the destination blockchain.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Report {
+    pub source_blockchain_id: String,
+    pub destination_blockchain_id: String,
+    pub teleporter_address: String,
+    pub sent: u64,
+    pub delivered: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p50_latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_latency_ms: Option<u64>,
+    pub deliveries: Vec<Delivery>,
+}
+
+impl Report {
+    pub fn new(
+        source_blockchain_id: &str,
+        destination_blockchain_id: &str,
+        teleporter_address: &str,
+        deliveries: Vec<Delivery>,
+    ) -> Self {
+        let mut latencies: Vec<u64> = deliveries.iter().filter_map(|d| d.latency_ms).collect();
+        latencies.sort_unstable();
+        let p50_latency_ms = if latencies.is_empty() {
+            None
+        } else {
+            Some(latencies[(latencies.len() - 1) / 2])
+        };
+        Self {
+            source_blockchain_id: source_blockchain_id.to_string(),
+            destination_blockchain_id: destination_blockchain_id.to_string(),
+            teleporter_address: teleporter_address.to_string(),
+            sent: deliveries.len() as u64,
+            delivered: deliveries.iter().filter(|d| d.delivered).count() as u64,
+            p50_latency_ms,
+            max_latency_ms: latencies.last().copied(),
+            deliveries,
+        }
+    }
+
+    pub fn encode_yaml(&self) -> io::Result<String> {
+        serde_yaml::to_string(self)
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to serialize {}", e)))
+    }
+}
+
+#[test]
+fn test_teleporter() {
+    let destination: ids::Id = [7u8; 32];
+    let to = tx::parse_address("0x8db97C7cEcE249c2b98bDC0226Cc4C2A57BF52FC").unwrap();
+    let message = test_message(1, "2023-01-01T00:00:00+00:00");
+    let d = send_cross_chain_message_calldata(&destination, &to, 100_000, &message);
+    let words = &d[4..];
+    assert_eq!(words.len() % 32, 0);
+    assert_eq!(words[31], 32);
+    assert_eq!(&words[32..64], &destination);
+    assert_eq!(&words[64 + 12..96], &to);
+    assert_eq!(&words[32 * 6 - 3..32 * 6], &[0x01, 0x86, 0xa0]);
+    assert_eq!(words[32 * 6 + 31], 224);
+    assert_eq!(&words[32 * 7 + 30..32 * 8], &[0x01, 0x00]);
+    // empty relayer addresses, then the message length and bytes
+    assert_eq!(&words[32 * 8..32 * 9], &[0u8; 32]);
+    assert_eq!(words[32 * 9 + 31] as usize, message.len());
+    assert_eq!(&words[32 * 10..32 * 10 + message.len()], &message[..]);
+
+    let logs = vec![
+        eth::Log {
+            address: String::from(WARP_PRECOMPILE_ADDRESS),
+            topics: vec![String::from("0x01")],
+            ..eth::Log::default()
+        },
+        eth::Log {
+            address: String::from("0x253B2784C75E510DD0FF1DA844684A1AC0AA5FCF"),
+            topics: vec![
+                String::from("0xaa"),
+                String::from("0xbb"),
+                format!("0x{}", hex::encode(destination)),
+            ],
+            ..eth::Log::default()
+        },
+    ];
+    assert_eq!(
+        message_id_from_logs(
+            &logs,
+            "0x253B2784C75E510DD0FF1DA844684A1AC0AA5FCF"
+                .to_lowercase()
+                .as_str(),
+            &destination
+        ),
+        Some(String::from("0xbb"))
+    );
+    assert_eq!(
+        message_id_from_logs(&logs, WARP_PRECOMPILE_ADDRESS, &destination),
+        None
+    );
+
+    let delivery = |seq: u64, latency_ms: Option<u64>| Delivery {
+        seq,
+        delivered: latency_ms.is_some(),
+        latency_ms,
+        ..Delivery::default()
+    };
+    let report = Report::new(
+        "a",
+        "b",
+        "c",
+        vec![
+            delivery(0, Some(300)),
+            delivery(1, None),
+            delivery(2, Some(100)),
+            delivery(3, Some(200)),
+        ],
+    );
+    assert_eq!(report.sent, 4);
+    assert_eq!(report.delivered, 3);
+    assert_eq!(report.p50_latency_ms, Some(200));
+    assert_eq!(report.max_latency_ms, Some(300));
+
+    let dir = tempfile::tempdir().unwrap();
+    assert!(Deployment::load(dir.path().to_str().unwrap()).is_err());
+    for (prefix, content) in [
+        (DEPLOYMENT_TRANSACTION_FILE_PREFIX, "0xf9abcd"),
+        (
+            DEPLOYER_ADDRESS_FILE_PREFIX,
+            "0x618FEdD9A45a8C456812ecAAE70C671c6249DfaC\n",
+        ),
+        (
+            CONTRACT_ADDRESS_FILE_PREFIX,
+            "0x253b2784c75e510dD0fF1da844684a1aC0aa5fcf",
+        ),
+    ] {
+        fs::write(dir.path().join(format!("{}v1.0.0.txt", prefix)), content).unwrap();
+    }
+    let deployment = Deployment::load(dir.path().to_str().unwrap()).unwrap();
+    assert_eq!(deployment.raw_tx, vec![0xf9, 0xab, 0xcd]);
+    assert_eq!(
+        deployment.deployer_address,
+        "0x618FEdD9A45a8C456812ecAAE70C671c6249DfaC"
+    );
+}
@@ -0,0 +1,114 @@
+use std::io::{self, Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+/// Default maximum session duration of the role (IAM default).
+pub const DEFAULT_MAX_SESSION_DURATION_SECONDS: u32 = 3600;
+/// Maximum session duration allowed by IAM.
+pub const MAX_SESSION_DURATION_SECONDS: u32 = 43200;
+
+/// Represents the read-only IAM role of the cluster, for the auditors and the
+/// on-call responders to inspect the cluster without any mutation capability.
+/// The role reads the cluster S3 objects (e.g., heartbeats, audit log), the stacks,
+/// the instances, the node logs and metrics, but has no KMS decrypt permission,
+/// so the encrypted keys in the bucket stay sealed.
+/// "apply" creates the role stack after the non-anchor nodes,
+/// and updates it on every run.
+/// Use with "avalanche-ops-aws --read-only" (see "AVALANCHE_OPS_READ_ONLY").
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Auditor {
+    /// IAM principals allowed to assume the role: the account IDs (12 digits),
+    /// or the ARNs of the IAM users or roles (e.g., SSO permission set roles).
+    pub trusted_principals: Vec<String>,
+    /// None for "DEFAULT_MAX_SESSION_DURATION_SECONDS".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_session_duration_seconds: Option<u32>,
+}
+
+impl Auditor {
+    pub fn max_session_duration_seconds(&self) -> u32 {
+        self.max_session_duration_seconds
+            .unwrap_or(DEFAULT_MAX_SESSION_DURATION_SECONDS)
+    }
+
+    pub fn validate(&self) -> io::Result<()> {
+        if self.trusted_principals.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'auditor.trusted_principals' cannot be empty",
+            ));
+        }
+        for p in self.trusted_principals.iter() {
+            let is_account_id = p.len() == 12 && p.chars().all(|c| c.is_ascii_digit());
+            let is_iam_arn = p.starts_with("arn:") && p.split(':').nth(2) == Some("iam");
+            if !(is_account_id || is_iam_arn) || p.contains(',') {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "invalid 'auditor.trusted_principals' '{}' (must be an account ID or an IAM ARN)",
+                        p
+                    ),
+                ));
+            }
+        }
+        let d = self.max_session_duration_seconds();
+        if !(DEFAULT_MAX_SESSION_DURATION_SECONDS..=MAX_SESSION_DURATION_SECONDS).contains(&d) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'auditor.max_session_duration_seconds' must be {}-{} (got {})",
+                    DEFAULT_MAX_SESSION_DURATION_SECONDS, MAX_SESSION_DURATION_SECONDS, d
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the CloudFormation auditor role template parameters.
+    pub fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("TrustedPrincipals", self.trusted_principals.join(",")),
+            (
+                "MaxSessionDuration",
+                self.max_session_duration_seconds().to_string(),
+            ),
+        ]
+    }
+}
+
+#[test]
+fn test_auditor() {
+    let auditor = Auditor {
+        trusted_principals: vec![
+            String::from("123456789012"),
+            String::from("arn:aws:iam::123456789012:role/oncall"),
+        ],
+        ..Default::default()
+    };
+    assert!(auditor.validate().is_ok());
+    assert_eq!(
+        auditor.parameters(),
+        vec![
+            (
+                "TrustedPrincipals",
+                String::from("123456789012,arn:aws:iam::123456789012:role/oncall")
+            ),
+            ("MaxSessionDuration", String::from("3600")),
+        ]
+    );
+
+    assert!(Auditor::default().validate().is_err());
+    for p in ["12345", "arn:aws:s3:::my-bucket", "oncall"] {
+        let auditor = Auditor {
+            trusted_principals: vec![String::from(p)],
+            ..Default::default()
+        };
+        assert!(auditor.validate().is_err());
+    }
+    let auditor = Auditor {
+        trusted_principals: vec![String::from("123456789012")],
+        max_session_duration_seconds: Some(60),
+    };
+    assert!(auditor.validate().is_err());
+}
@@ -0,0 +1,222 @@
+use std::io::{self, Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of the alert thresholds, bounded by the template conditions.
+pub const MAX_ALERT_THRESHOLDS: usize = 3;
+/// Maximum number of the alert emails, bounded by the template conditions.
+pub const MAX_ALERT_EMAILS: usize = 3;
+
+/// Default alert thresholds in the percentages of "monthly_limit_usd".
+pub const DEFAULT_ALERT_THRESHOLD_PERCENTS: [u32; 2] = [80, 100];
+
+/// Represents the monthly AWS Budget of the cluster, filtered by the
+/// "avalanche-ops:cluster-id" tag, which must be activated as a cost
+/// allocation tag in the billing console (once per account).
+/// The budget notifies the SNS topic (and "alert_emails") at each threshold,
+/// and optionally scales the non-anchor nodes to zero once the actual cost
+/// exceeds "scale_down_threshold_percent".
+/// "apply" creates the budget stack after the non-anchor nodes,
+/// and updates it on every run.
+/// ref. https://docs.aws.amazon.com/cost-management/latest/userguide/budgets-managing-costs.html
+/// ref. https://docs.aws.amazon.com/awsaccountbilling/latest/aboutv2/activating-tags.html
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Budget {
+    /// Monthly cost limit in USD.
+    pub monthly_limit_usd: u32,
+    /// Percentages of "monthly_limit_usd" to alert at, up to "MAX_ALERT_THRESHOLDS".
+    /// Empty for "DEFAULT_ALERT_THRESHOLD_PERCENTS".
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alert_threshold_percents: Vec<u32>,
+    /// Emails to subscribe to the alert topic, up to "MAX_ALERT_EMAILS".
+    /// Each must confirm the subscription from the email.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alert_emails: Vec<String>,
+    /// Set "true" to scale the non-anchor nodes ASG to zero when the actual cost
+    /// exceeds "scale_down_threshold_percent". The anchor nodes keep running.
+    /// Restore the ASG capacity to scale back up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale_down_non_anchor_nodes: Option<bool>,
+    /// None for 100 percent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale_down_threshold_percent: Option<u32>,
+}
+
+impl Budget {
+    pub fn alert_threshold_percents(&self) -> Vec<u32> {
+        if self.alert_threshold_percents.is_empty() {
+            DEFAULT_ALERT_THRESHOLD_PERCENTS.to_vec()
+        } else {
+            self.alert_threshold_percents.clone()
+        }
+    }
+
+    pub fn scale_down_threshold_percent(&self) -> u32 {
+        self.scale_down_threshold_percent.unwrap_or(100)
+    }
+
+    pub fn validate(&self) -> io::Result<()> {
+        if self.monthly_limit_usd == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'budget.monthly_limit_usd' must be positive",
+            ));
+        }
+        let thresholds = self.alert_threshold_percents();
+        if thresholds.len() > MAX_ALERT_THRESHOLDS {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'budget.alert_threshold_percents' must have 1-{} thresholds (got {})",
+                    MAX_ALERT_THRESHOLDS,
+                    thresholds.len()
+                ),
+            ));
+        }
+        for v in thresholds
+            .iter()
+            .chain(self.scale_down_threshold_percent.iter())
+        {
+            if *v == 0 || *v > 1000 {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("'budget' threshold must be 1-1000 percent (got {})", v),
+                ));
+            }
+        }
+        if self.alert_emails.len() > MAX_ALERT_EMAILS {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'budget.alert_emails' must have at most {} emails (got {})",
+                    MAX_ALERT_EMAILS,
+                    self.alert_emails.len()
+                ),
+            ));
+        }
+        for email in self.alert_emails.iter() {
+            if !email.contains('@') || email.contains(',') {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid 'budget.alert_emails' '{}'", email),
+                ));
+            }
+        }
+        if self.scale_down_threshold_percent.is_some()
+            && self.scale_down_non_anchor_nodes != Some(true)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'budget.scale_down_threshold_percent' requires 'scale_down_non_anchor_nodes'",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the CloudFormation budget template parameters.
+    /// Returns all optional parameters, to reset the removed ones on the stack update.
+    pub fn parameters(&self) -> Vec<(&'static str, String)> {
+        const THRESHOLD_KEYS: [&str; MAX_ALERT_THRESHOLDS] =
+            ["AlertThreshold1", "AlertThreshold2", "AlertThreshold3"];
+
+        let thresholds = self.alert_threshold_percents();
+        let mut params = vec![("MonthlyLimitUsd", self.monthly_limit_usd.to_string())];
+        for (i, k) in THRESHOLD_KEYS.iter().enumerate() {
+            // "0" to disable
+            params.push((k, thresholds.get(i).copied().unwrap_or(0).to_string()));
+        }
+        params.push(("AlertEmails", self.alert_emails.join(",")));
+        params.push(("AlertEmailsCount", self.alert_emails.len().to_string()));
+        params.push((
+            "ScaleDownNonAnchorNodes",
+            (self.scale_down_non_anchor_nodes == Some(true)).to_string(),
+        ));
+        params.push((
+            "ScaleDownThreshold",
+            self.scale_down_threshold_percent().to_string(),
+        ));
+        params
+    }
+}
+
+#[test]
+fn test_budget() {
+    let budget = Budget {
+        monthly_limit_usd: 500,
+        ..Default::default()
+    };
+    assert!(budget.validate().is_ok());
+    assert_eq!(budget.alert_threshold_percents(), vec![80, 100]);
+    assert_eq!(
+        budget.parameters(),
+        vec![
+            ("MonthlyLimitUsd", String::from("500")),
+            ("AlertThreshold1", String::from("80")),
+            ("AlertThreshold2", String::from("100")),
+            ("AlertThreshold3", String::from("0")),
+            ("AlertEmails", String::new()),
+            ("AlertEmailsCount", String::from("0")),
+            ("ScaleDownNonAnchorNodes", String::from("false")),
+            ("ScaleDownThreshold", String::from("100")),
+        ]
+    );
+
+    let budget = Budget {
+        monthly_limit_usd: 500,
+        alert_threshold_percents: vec![50],
+        alert_emails: vec![
+            String::from("ops@example.com"),
+            String::from("oncall@example.com"),
+        ],
+        scale_down_non_anchor_nodes: Some(true),
+        scale_down_threshold_percent: Some(120),
+    };
+    assert!(budget.validate().is_ok());
+    assert_eq!(
+        budget.parameters(),
+        vec![
+            ("MonthlyLimitUsd", String::from("500")),
+            ("AlertThreshold1", String::from("50")),
+            ("AlertThreshold2", String::from("0")),
+            ("AlertThreshold3", String::from("0")),
+            (
+                "AlertEmails",
+                String::from("ops@example.com,oncall@example.com")
+            ),
+            ("AlertEmailsCount", String::from("2")),
+            ("ScaleDownNonAnchorNodes", String::from("true")),
+            ("ScaleDownThreshold", String::from("120")),
+        ]
+    );
+
+    assert!(Budget::default().validate().is_err());
+    assert!(Budget {
+        monthly_limit_usd: 500,
+        alert_threshold_percents: vec![25, 50, 75, 100],
+        ..Default::default()
+    }
+    .validate()
+    .is_err());
+    assert!(Budget {
+        monthly_limit_usd: 500,
+        alert_threshold_percents: vec![0],
+        ..Default::default()
+    }
+    .validate()
+    .is_err());
+    assert!(Budget {
+        monthly_limit_usd: 500,
+        alert_emails: vec![String::from("ops")],
+        ..Default::default()
+    }
+    .validate()
+    .is_err());
+    assert!(Budget {
+        monthly_limit_usd: 500,
+        scale_down_threshold_percent: Some(100),
+        ..Default::default()
+    }
+    .validate()
+    .is_err());
+}
@@ -0,0 +1,22 @@
+use std::{
+    io::{self, Error, ErrorKind},
+    process::Command,
+};
+
+/// Runs the AWS CLI with the arguments (e.g., ["route53", "change-resource-record-sets", ...]),
+/// and returns its standard output. Only for the services without the SDK client
+/// in this version. The CLI must be installed with the same credentials.
+pub fn run(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("aws").args(args).output()?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "'aws {}' failed {}",
+                args.iter().take(2).cloned().collect::<Vec<_>>().join(" "),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     thread,
     time::{Duration, Instant},
 };
@@ -10,12 +11,10 @@ use aws_sdk_cloudformation::{
     Client,
 };
 use aws_types::SdkConfig as AwsSdkConfig;
+use chrono::{DateTime, TimeZone, Utc};
 use log::{info, warn};
 
-use crate::errors::{
-    Error::{Other, API},
-    Result,
-};
+use crate::errors::{from_sdk, Error::Other, Result};
 
 /// Implements AWS CloudFormation manager.
 #[derive(Debug, Clone)]
@@ -61,10 +60,7 @@ impl Manager {
         let resp = match ret {
             Ok(v) => v,
             Err(e) => {
-                return Err(API {
-                    message: format!("failed create_stack {:?}", e),
-                    is_retryable: is_error_retryable(&e),
-                });
+                return Err(from_sdk(format!("failed create_stack {:?}", e), &e));
             }
         };
 
@@ -87,10 +83,10 @@ impl Manager {
             Ok(_) => {}
             Err(e) => {
                 if !is_error_delete_stack_does_not_exist(&e) {
-                    return Err(API {
-                        message: format!("failed schedule_key_deletion {:?}", e),
-                        is_retryable: is_error_retryable(&e),
-                    });
+                    return Err(from_sdk(
+                        format!("failed schedule_key_deletion {:?}", e),
+                        &e,
+                    ));
                 }
                 warn!("stack already deleted ({})", e);
                 return Ok(Stack::new(
@@ -110,6 +106,188 @@ impl Manager {
         ))
     }
 
+    /// Updates the parameters of a CloudFormation stack with the previous template,
+    /// keeping the previous values for the parameters not overridden.
+    /// The separate caller is expected to poll the status asynchronously.
+    pub async fn update_stack_parameters(
+        &self,
+        stack_name: &str,
+        capabilities: Option<Vec<Capability>>,
+        overrides: Vec<Parameter>,
+    ) -> Result<Stack> {
+        info!("updating stack '{}' parameters", stack_name);
+        let ret = self
+            .cli
+            .describe_stacks()
+            .stack_name(stack_name)
+            .send()
+            .await;
+        let stacks = match ret {
+            Ok(v) => v.stacks.unwrap_or_default(),
+            Err(e) => {
+                return Err(from_sdk(format!("failed describe_stacks {:?}", e), &e));
+            }
+        };
+        let stack = match stacks.first() {
+            Some(v) => v,
+            None => {
+                return Err(Other {
+                    message: format!("failed to find stack {}", stack_name),
+                    is_retryable: false,
+                });
+            }
+        };
+
+        let mut parameters = overrides.clone();
+        for p in stack.parameters().unwrap_or_default().iter() {
+            let k = p.parameter_key().unwrap_or_default();
+            if overrides
+                .iter()
+                .any(|o| o.parameter_key().unwrap_or_default() == k)
+            {
+                continue;
+            }
+            parameters.push(
+                Parameter::builder()
+                    .parameter_key(k)
+                    .use_previous_value(true)
+                    .build(),
+            );
+        }
+
+        let ret = self
+            .cli
+            .update_stack()
+            .stack_name(stack_name)
+            .set_capabilities(capabilities)
+            .use_previous_template(true)
+            .set_parameters(Some(parameters))
+            .send()
+            .await;
+        let resp = match ret {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(from_sdk(format!("failed update_stack {:?}", e), &e));
+            }
+        };
+
+        let stack_id = resp.stack_id().unwrap_or_default();
+        info!("updating stack '{}' with '{}'", stack_name, stack_id);
+        Ok(Stack::new(
+            stack_name,
+            stack_id,
+            StackStatus::UpdateInProgress,
+            None,
+        ))
+    }
+
+    /// Lists all stacks with the tag key, except the deleted stacks.
+    pub async fn list_stacks_with_tag(&self, tag_key: &str) -> Result<Vec<TaggedStack>> {
+        let mut stacks = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            let resp = self
+                .cli
+                .describe_stacks()
+                .set_next_token(next_token.clone())
+                .send()
+                .await
+                .map_err(|e| from_sdk(format!("failed describe_stacks {:?}", e), &e))?;
+            for stack in resp.stacks().unwrap_or_default() {
+                let tags: HashMap<String, String> = stack
+                    .tags()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|t| {
+                        (
+                            t.key().unwrap_or_default().to_string(),
+                            t.value().unwrap_or_default().to_string(),
+                        )
+                    })
+                    .collect();
+                if !tags.contains_key(tag_key) {
+                    continue;
+                }
+                let status = stack
+                    .stack_status()
+                    .cloned()
+                    .unwrap_or_else(|| StackStatus::Unknown(String::from("unknown")));
+                if status == StackStatus::DeleteComplete {
+                    continue;
+                }
+                stacks.push(TaggedStack {
+                    name: stack.stack_name().unwrap_or_default().to_string(),
+                    status,
+                    created_at: stack
+                        .creation_time()
+                        .and_then(|t| Utc.timestamp_opt(t.secs(), 0).single()),
+                    tags,
+                });
+            }
+            next_token = resp.next_token().map(String::from);
+            if next_token.is_none() {
+                break;
+            }
+        }
+        info!("found {} stacks with the tag '{}'", stacks.len(), tag_key);
+        Ok(stacks)
+    }
+
+    /// Lists the stack events since the time, oldest first.
+    /// ref. https://docs.aws.amazon.com/AWSCloudFormation/latest/APIReference/API_DescribeStackEvents.html
+    pub async fn list_stack_events(
+        &self,
+        stack_name: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<StackEvent>> {
+        info!("listing events of stack '{}' since {}", stack_name, since);
+        let mut events = Vec::new();
+        let mut next_token: Option<String> = None;
+        // newest first, so stop at the first older event
+        'pages: loop {
+            let resp = self
+                .cli
+                .describe_stack_events()
+                .stack_name(stack_name)
+                .set_next_token(next_token.clone())
+                .send()
+                .await
+                .map_err(|e| from_sdk(format!("failed describe_stack_events {:?}", e), &e))?;
+            for ev in resp.stack_events().unwrap_or_default() {
+                let timestamp = match ev
+                    .timestamp()
+                    .and_then(|t| Utc.timestamp_opt(t.secs(), 0).single())
+                {
+                    Some(v) => v,
+                    None => continue,
+                };
+                if timestamp < since {
+                    break 'pages;
+                }
+                events.push(StackEvent {
+                    timestamp,
+                    logical_resource_id: ev.logical_resource_id().unwrap_or_default().to_string(),
+                    physical_resource_id: ev.physical_resource_id().unwrap_or_default().to_string(),
+                    resource_type: ev.resource_type().unwrap_or_default().to_string(),
+                    resource_status: ev
+                        .resource_status()
+                        .map(|s| s.as_str().to_string())
+                        .unwrap_or_default(),
+                    resource_status_reason: ev
+                        .resource_status_reason()
+                        .unwrap_or_default()
+                        .to_string(),
+                });
+            }
+            next_token = resp.next_token().map(String::from);
+            if next_token.is_none() {
+                break;
+            }
+        }
+        events.reverse();
+        Ok(events)
+    }
+
     /// Polls CloudFormation stack status.
     pub async fn poll_stack(
         &self,
@@ -157,10 +335,14 @@ impl Manager {
                         info!("stack already deleted as desired");
                         return Ok(Stack::new(stack_name, "", desired_status, None));
                     }
-                    return Err(API {
-                        message: format!("failed describe_stacks {:?}", e),
-                        is_retryable: is_error_retryable(&e),
-                    });
+                    let err = from_sdk(format!("failed describe_stacks {:?}", e), &e);
+                    // the long-running polls should not fail on the API rate limits
+                    if err.is_throttling() || err.is_timeout() {
+                        warn!("retrying describe_stacks ({})", err.message());
+                        cnt += 1;
+                        continue;
+                    }
+                    return Err(err);
                 }
             };
             let stacks = stacks.unwrap();
@@ -195,6 +377,16 @@ impl Manager {
                 });
             }
 
+            if desired_status.eq(&StackStatus::UpdateComplete)
+                && (current_status.eq(&StackStatus::UpdateRollbackComplete)
+                    || current_status.eq(&StackStatus::UpdateRollbackFailed))
+            {
+                return Err(Other {
+                    message: String::from("stack update failed thus rolled back"),
+                    is_retryable: false,
+                });
+            }
+
             if desired_status.eq(&StackStatus::DeleteComplete)
                 && current_status.eq(&StackStatus::DeleteFailed)
             {
@@ -248,6 +440,26 @@ impl Stack {
     }
 }
 
+/// Represents the CloudFormation stack found by the tag.
+#[derive(Debug, Clone)]
+pub struct TaggedStack {
+    pub name: String,
+    pub status: StackStatus,
+    pub created_at: Option<DateTime<Utc>>,
+    pub tags: HashMap<String, String>,
+}
+
+/// Represents a CloudFormation stack event.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StackEvent {
+    pub timestamp: DateTime<Utc>,
+    pub logical_resource_id: String,
+    pub physical_resource_id: String,
+    pub resource_type: String,
+    pub resource_status: String,
+    pub resource_status_reason: String,
+}
+
 #[inline]
 pub fn is_error_retryable<E>(e: &SdkError<E>) -> bool {
     match e {
@@ -9,7 +9,9 @@ use std::{
 };
 
 use aws_sdk_cloudwatch::{
-    model::MetricDatum, types::SdkError as MetricsSdkError, Client as MetricsClient,
+    model::{Dimension, HistoryItemType, Metric, MetricDataQuery, MetricDatum, MetricStat, ScanBy},
+    types::SdkError as MetricsSdkError,
+    Client as MetricsClient,
 };
 use aws_sdk_cloudwatchlogs::{
     error::{
@@ -18,11 +20,13 @@ use aws_sdk_cloudwatchlogs::{
     types::SdkError as LogsSdkError,
     Client as LogsClient,
 };
+use aws_smithy_types::DateTime as SmithyDateTime;
 use aws_types::SdkConfig as AwsSdkConfig;
+use chrono::{DateTime, TimeZone, Utc};
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
 
-use crate::errors::{Error::API, Result};
+use crate::errors::{from_sdk, Result};
 
 /// Implements AWS CloudWatch manager.
 #[derive(Debug, Clone)]
@@ -73,10 +77,7 @@ impl Manager {
                     info!("successfully post metrics");
                 }
                 Err(e) => {
-                    return Err(API {
-                        message: format!("failed put_metric_data {:?}", e),
-                        is_retryable: is_metrics_error_retryable(&e),
-                    });
+                    return Err(from_sdk(format!("failed put_metric_data {:?}", e), &e));
                 }
             };
         } else {
@@ -95,10 +96,7 @@ impl Manager {
                         info!("successfully post {} metrics in batch", batch_n);
                     }
                     Err(e) => {
-                        return Err(API {
-                            message: format!("failed put_metric_data {:?}", e),
-                            is_retryable: is_metrics_error_retryable(&e),
-                        });
+                        return Err(from_sdk(format!("failed put_metric_data {:?}", e), &e));
                     }
                 }
                 thread::sleep(time::Duration::from_secs(1));
@@ -108,6 +106,83 @@ impl Manager {
         Ok(())
     }
 
+    /// Fetches the metric statistics and the metric math expressions
+    /// in the namespace, following the pagination.
+    /// Returns the results in the order of the queries, with the newest datapoints first.
+    /// ref. https://docs.aws.amazon.com/AmazonCloudWatch/latest/APIReference/API_GetMetricData.html
+    /// ref. https://docs.aws.amazon.com/AmazonCloudWatch/latest/monitoring/using-metric-math.html
+    pub async fn get_metric_data(
+        &self,
+        namespace: &str,
+        queries: &[Query],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        period_seconds: i32,
+    ) -> Result<Vec<MetricResult>> {
+        info!(
+            "getting CloudWatch {} metric queries in '{}'",
+            queries.len(),
+            namespace
+        );
+        let data_queries: Vec<MetricDataQuery> = queries
+            .iter()
+            .map(|q| q.to_metric_data_query(namespace, period_seconds))
+            .collect();
+
+        let mut results: Vec<MetricResult> = queries
+            .iter()
+            .filter(|q| q.return_data())
+            .map(|q| MetricResult {
+                id: q.id().to_string(),
+                label: String::new(),
+                timestamps: Vec::new(),
+                values: Vec::new(),
+            })
+            .collect();
+        let mut token = String::new();
+        loop {
+            let mut builder = self
+                .metrics_cli
+                .get_metric_data()
+                .set_metric_data_queries(Some(data_queries.clone()))
+                .start_time(SmithyDateTime::from_secs(start.timestamp()))
+                .end_time(SmithyDateTime::from_secs(end.timestamp()))
+                .scan_by(ScanBy::TimestampDescending);
+            if !token.is_empty() {
+                builder = builder.next_token(token.to_owned());
+            }
+            let resp = match builder.send().await {
+                Ok(v) => v,
+                Err(e) => {
+                    return Err(from_sdk(format!("failed get_metric_data {:?}", e), &e));
+                }
+            };
+
+            for r in resp.metric_data_results().unwrap_or_default() {
+                let id = r.id().unwrap_or_default();
+                let result = match results.iter_mut().find(|v| v.id == id) {
+                    Some(v) => v,
+                    None => continue,
+                };
+                result.label = r.label().unwrap_or_default().to_string();
+                for ts in r.timestamps().unwrap_or_default() {
+                    result
+                        .timestamps
+                        .push(Utc.timestamp_opt(ts.secs(), 0).unwrap());
+                }
+                result
+                    .values
+                    .extend_from_slice(r.values().unwrap_or_default());
+            }
+
+            token = resp.next_token.unwrap_or_default();
+            if token.is_empty() {
+                break;
+            }
+        }
+        Ok(results)
+    }
+
     /// Creates a CloudWatch log group.
     /// ref. https://docs.aws.amazon.com/AWSCloudFormation/latest/UserGuide/aws-resource-logs-loggroup.html
     pub async fn create_log_group(&self, log_group_name: &str) -> Result<()> {
@@ -122,10 +197,7 @@ impl Manager {
             Ok(_) => false,
             Err(e) => {
                 if !is_logs_error_create_log_group_already_exists(&e) {
-                    return Err(API {
-                        message: format!("failed create_log_group {:?}", e),
-                        is_retryable: is_logs_error_retryable(&e),
-                    });
+                    return Err(from_sdk(format!("failed create_log_group {:?}", e), &e));
                 }
                 warn!("log_group already exists ({})", e);
                 true
@@ -159,10 +231,7 @@ impl Manager {
                     ignore_err = true
                 }
                 if !ignore_err {
-                    return Err(API {
-                        message: format!("failed delete_log_group {:?}", e),
-                        is_retryable: is_logs_error_retryable(&e),
-                    });
+                    return Err(from_sdk(format!("failed delete_log_group {:?}", e), &e));
                 }
                 false
             }
@@ -172,6 +241,119 @@ impl Manager {
         };
         Ok(())
     }
+
+    /// Fetches the log events of the streams since the time that match
+    /// the filter pattern, following the pagination, up to "limit" events.
+    /// ref. https://docs.aws.amazon.com/AmazonCloudWatchLogs/latest/APIReference/API_FilterLogEvents.html
+    /// ref. https://docs.aws.amazon.com/AmazonCloudWatch/latest/logs/FilterAndPatternSyntax.html
+    pub async fn filter_log_events(
+        &self,
+        log_group_name: &str,
+        log_stream_names: Vec<String>,
+        filter_pattern: &str,
+        start: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<LogEvent>> {
+        info!(
+            "filtering log events of {:?} in '{}' since {}",
+            log_stream_names, log_group_name, start
+        );
+        let mut events = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            let resp = self
+                .logs_cli
+                .filter_log_events()
+                .log_group_name(log_group_name)
+                .set_log_stream_names(Some(log_stream_names.clone()))
+                .filter_pattern(filter_pattern)
+                .start_time(start.timestamp_millis())
+                .set_next_token(next_token.clone())
+                .send()
+                .await
+                .map_err(|e| from_sdk(format!("failed filter_log_events {:?}", e), &e))?;
+            for ev in resp.events().unwrap_or_default() {
+                let timestamp = match ev
+                    .timestamp
+                    .and_then(|ms| Utc.timestamp_millis_opt(ms).single())
+                {
+                    Some(v) => v,
+                    None => continue,
+                };
+                events.push(LogEvent {
+                    timestamp,
+                    log_stream_name: ev.log_stream_name().unwrap_or_default().to_string(),
+                    message: ev.message().unwrap_or_default().to_string(),
+                });
+            }
+            next_token = resp.next_token().map(String::from);
+            if next_token.is_none() || events.len() >= limit {
+                break;
+            }
+        }
+        events.truncate(limit);
+        Ok(events)
+    }
+
+    /// Lists the state changes of the alarm since the time.
+    /// ref. https://docs.aws.amazon.com/AmazonCloudWatch/latest/APIReference/API_DescribeAlarmHistory.html
+    pub async fn list_alarm_state_changes(
+        &self,
+        alarm_name: &str,
+        start: DateTime<Utc>,
+    ) -> Result<Vec<AlarmStateChange>> {
+        info!("listing state changes of alarm '{}'", alarm_name);
+        let mut changes = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            let resp = self
+                .metrics_cli
+                .describe_alarm_history()
+                .alarm_name(alarm_name)
+                .history_item_type(HistoryItemType::StateUpdate)
+                .start_date(SmithyDateTime::from_secs(start.timestamp()))
+                .end_date(SmithyDateTime::from_secs(Utc::now().timestamp()))
+                .set_next_token(next_token.clone())
+                .send()
+                .await
+                .map_err(|e| from_sdk(format!("failed describe_alarm_history {:?}", e), &e))?;
+            for item in resp.alarm_history_items().unwrap_or_default() {
+                let timestamp = match item
+                    .timestamp()
+                    .and_then(|t| Utc.timestamp_opt(t.secs(), 0).single())
+                {
+                    Some(v) => v,
+                    None => continue,
+                };
+                changes.push(AlarmStateChange {
+                    timestamp,
+                    alarm_name: item.alarm_name().unwrap_or_default().to_string(),
+                    summary: item.history_summary().unwrap_or_default().to_string(),
+                });
+            }
+            next_token = resp.next_token().map(String::from);
+            if next_token.is_none() {
+                break;
+            }
+        }
+        Ok(changes)
+    }
+}
+
+/// Represents a CloudWatch Logs event.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LogEvent {
+    pub timestamp: DateTime<Utc>,
+    pub log_stream_name: String,
+    pub message: String,
+}
+
+/// Represents a CloudWatch alarm state change (e.g., "Alarm updated from OK to ALARM").
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AlarmStateChange {
+    pub timestamp: DateTime<Utc>,
+    pub alarm_name: String,
+    pub summary: String,
 }
 
 #[inline]
@@ -218,6 +400,152 @@ fn is_logs_error_delete_log_group_does_not_exist(e: &LogsSdkError<DeleteLogGroup
     }
 }
 
+/// Represents a "GetMetricData" query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    /// Statistic of the metric across all datapoints in each period.
+    /// "stat" is "Average", "Minimum", "Maximum", "Sum", "SampleCount",
+    /// or a percentile (e.g., "p99", "p99.9").
+    /// The statistics across the datapoints of all nodes make the fleet-level
+    /// aggregates (e.g., "Minimum" of the accepted blocks across the nodes).
+    Stat {
+        id: String,
+        metric_name: String,
+        dimensions: Vec<(String, String)>,
+        stat: String,
+        /// Set "false" for the inputs of the expressions only.
+        return_data: bool,
+    },
+    /// Metric math expression on the other query IDs (e.g., "m2 - m1").
+    Expression {
+        id: String,
+        expression: String,
+        label: String,
+    },
+}
+
+impl Query {
+    pub fn stat(id: &str, metric_name: &str, stat: &str) -> Self {
+        Query::Stat {
+            id: id.to_string(),
+            metric_name: metric_name.to_string(),
+            dimensions: Vec::new(),
+            stat: stat.to_string(),
+            return_data: true,
+        }
+    }
+
+    pub fn expression(id: &str, expression: &str, label: &str) -> Self {
+        Query::Expression {
+            id: id.to_string(),
+            expression: expression.to_string(),
+            label: label.to_string(),
+        }
+    }
+
+    /// Matches the datapoints with the dimension value.
+    pub fn dimension(mut self, name: &str, value: &str) -> Self {
+        if let Query::Stat { dimensions, .. } = &mut self {
+            dimensions.push((name.to_string(), value.to_string()));
+        }
+        self
+    }
+
+    /// Only uses the query as an input of the expressions.
+    pub fn hidden(mut self) -> Self {
+        if let Query::Stat { return_data, .. } = &mut self {
+            *return_data = false;
+        }
+        self
+    }
+
+    pub fn id(&self) -> &str {
+        match self {
+            Query::Stat { id, .. } | Query::Expression { id, .. } => id,
+        }
+    }
+
+    pub fn return_data(&self) -> bool {
+        match self {
+            Query::Stat { return_data, .. } => *return_data,
+            Query::Expression { .. } => true,
+        }
+    }
+
+    fn to_metric_data_query(&self, namespace: &str, period_seconds: i32) -> MetricDataQuery {
+        match self {
+            Query::Stat {
+                id,
+                metric_name,
+                dimensions,
+                stat,
+                return_data,
+            } => {
+                let dimensions: Vec<Dimension> = dimensions
+                    .iter()
+                    .map(|(k, v)| Dimension::builder().name(k).value(v).build())
+                    .collect();
+                MetricDataQuery::builder()
+                    .id(id)
+                    .metric_stat(
+                        MetricStat::builder()
+                            .metric(
+                                Metric::builder()
+                                    .namespace(namespace)
+                                    .metric_name(metric_name)
+                                    .set_dimensions(Some(dimensions))
+                                    .build(),
+                            )
+                            .period(period_seconds)
+                            .stat(stat)
+                            .build(),
+                    )
+                    .return_data(*return_data)
+                    .build()
+            }
+            Query::Expression {
+                id,
+                expression,
+                label,
+            } => MetricDataQuery::builder()
+                .id(id)
+                .expression(expression)
+                .label(label)
+                .return_data(true)
+                .build(),
+        }
+    }
+}
+
+/// Represents the "GetMetricData" result of a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricResult {
+    pub id: String,
+    pub label: String,
+    /// Newest first.
+    pub timestamps: Vec<DateTime<Utc>>,
+    pub values: Vec<f64>,
+}
+
+impl MetricResult {
+    /// Returns the value of the newest datapoint.
+    pub fn latest(&self) -> Option<f64> {
+        self.values.first().copied()
+    }
+}
+
+/// Returns true if the statistic is supported by "GetMetricData".
+/// ref. https://docs.aws.amazon.com/AmazonCloudWatch/latest/monitoring/Statistics-definitions.html
+pub fn is_valid_stat(stat: &str) -> bool {
+    match stat {
+        "Average" | "Minimum" | "Maximum" | "Sum" | "SampleCount" => true,
+        _ => match stat.strip_prefix('p') {
+            Some(v) => matches!(v.parse::<f64>(), Ok(p) if (0.0..=100.0).contains(&p)),
+            None => false,
+        },
+    }
+}
+
 pub async fn spawn_put_metric_data(
     cw_manager: Manager,
     namespace: &str,
@@ -655,3 +983,32 @@ fn test_config() {
     assert!(ret.is_ok());
     fs::remove_file(p).unwrap();
 }
+
+#[test]
+fn test_query() {
+    assert!(is_valid_stat("Minimum"));
+    assert!(is_valid_stat("p99"));
+    assert!(is_valid_stat("p99.9"));
+    assert!(!is_valid_stat("p101"));
+    assert!(!is_valid_stat("median"));
+
+    let q = Query::stat("m1", "clock_offset_ms", "p99")
+        .dimension("NodeKind", "anchor")
+        .hidden();
+    assert_eq!(q.id(), "m1");
+    assert!(!q.return_data());
+    let d = q.to_metric_data_query("test-avalanche", 300);
+    assert_eq!(d.return_data, Some(false));
+    let stat = d.metric_stat.unwrap();
+    assert_eq!(stat.stat.as_deref(), Some("p99"));
+    assert_eq!(stat.period, Some(300));
+    let metric = stat.metric.unwrap();
+    assert_eq!(metric.namespace.as_deref(), Some("test-avalanche"));
+    assert_eq!(metric.dimensions.unwrap().len(), 1);
+
+    let q = Query::expression("e1", "m2 - m1", "spread");
+    assert!(q.return_data());
+    let d = q.to_metric_data_query("test-avalanche", 300);
+    assert_eq!(d.expression.as_deref(), Some("m2 - m1"));
+    assert!(d.metric_stat.is_none());
+}
@@ -0,0 +1,193 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use aws_sdk_dynamodb::{
+    model::{
+        AttributeDefinition, AttributeValue, BillingMode, KeySchemaElement, KeyType,
+        ScalarAttributeType, TableStatus,
+    },
+    types::SdkError,
+    Client,
+};
+use aws_types::SdkConfig as AwsSdkConfig;
+use log::info;
+use tokio::time::sleep;
+
+use crate::errors::{from_sdk, Error::Other, Result};
+
+pub type Item = HashMap<String, AttributeValue>;
+
+/// Implements AWS DynamoDB manager.
+#[derive(Debug, Clone)]
+pub struct Manager {
+    #[allow(dead_code)]
+    shared_config: AwsSdkConfig,
+    cli: Client,
+}
+
+impl Manager {
+    pub fn new(shared_config: &AwsSdkConfig) -> Self {
+        let cloned = shared_config.clone();
+        let cli = Client::new(shared_config);
+        Self {
+            shared_config: cloned,
+            cli,
+        }
+    }
+
+    /// Creates an on-demand table with the string hash key, if it does not exist.
+    /// Waits until the table becomes active.
+    pub async fn create_table_if_not_exists(&self, table_name: &str, hash_key: &str) -> Result<()> {
+        info!("creating table '{}' if not exists", table_name);
+        let ret = self
+            .cli
+            .create_table()
+            .table_name(table_name)
+            .attribute_definitions(
+                AttributeDefinition::builder()
+                    .attribute_name(hash_key)
+                    .attribute_type(ScalarAttributeType::S)
+                    .build(),
+            )
+            .key_schema(
+                KeySchemaElement::builder()
+                    .attribute_name(hash_key)
+                    .key_type(KeyType::Hash)
+                    .build(),
+            )
+            .billing_mode(BillingMode::PayPerRequest)
+            .send()
+            .await;
+        match ret {
+            Ok(_) => info!("created table '{}'", table_name),
+            Err(SdkError::ServiceError { err, .. }) if err.is_resource_in_use_exception() => {
+                info!("table '{}' already exists", table_name)
+            }
+            Err(e) => return Err(from_sdk(format!("failed create_table {:?}", e), &e)),
+        }
+
+        let (timeout, itv) = (Duration::from_secs(120), Duration::from_secs(5));
+        let start = Instant::now();
+        loop {
+            let resp = self
+                .cli
+                .describe_table()
+                .table_name(table_name)
+                .send()
+                .await
+                .map_err(|e| from_sdk(format!("failed describe_table {:?}", e), &e))?;
+            let status = resp.table().and_then(|t| t.table_status());
+            if status == Some(&TableStatus::Active) {
+                return Ok(());
+            }
+            if start.elapsed() > timeout {
+                return Err(Other {
+                    message: format!(
+                        "table '{}' not active in time (status {:?})",
+                        table_name, status
+                    ),
+                    is_retryable: true,
+                });
+            }
+            info!("waiting for table '{}' (status {:?})", table_name, status);
+            sleep(itv).await;
+        }
+    }
+
+    /// Puts the item only if the condition holds.
+    /// Returns false if the condition check failed.
+    pub async fn put_item_if(
+        &self,
+        table_name: &str,
+        item: Item,
+        condition: &str,
+        names: HashMap<String, String>,
+        values: Item,
+    ) -> Result<bool> {
+        let ret = self
+            .cli
+            .put_item()
+            .table_name(table_name)
+            .set_item(Some(item))
+            .condition_expression(condition)
+            .set_expression_attribute_names(Some(names))
+            // must not be empty if set
+            .set_expression_attribute_values(if values.is_empty() {
+                None
+            } else {
+                Some(values)
+            })
+            .send()
+            .await;
+        match ret {
+            Ok(_) => Ok(true),
+            Err(SdkError::ServiceError { err, .. })
+                if err.is_conditional_check_failed_exception() =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(from_sdk(format!("failed put_item {:?}", e), &e)),
+        }
+    }
+
+    /// Returns the item with the string hash key, or "None" if not found.
+    pub async fn get_item(
+        &self,
+        table_name: &str,
+        hash_key: &str,
+        hash_key_value: &str,
+    ) -> Result<Option<Item>> {
+        let resp = self
+            .cli
+            .get_item()
+            .table_name(table_name)
+            .key(hash_key, AttributeValue::S(hash_key_value.to_string()))
+            .consistent_read(true)
+            .send()
+            .await
+            .map_err(|e| from_sdk(format!("failed get_item {:?}", e), &e))?;
+        Ok(resp.item)
+    }
+
+    /// Deletes the item with the string hash key, only if the condition holds
+    /// (if any). Returns false if the condition check failed.
+    pub async fn delete_item_if(
+        &self,
+        table_name: &str,
+        hash_key: &str,
+        hash_key_value: &str,
+        condition: Option<(&str, HashMap<String, String>, Item)>,
+    ) -> Result<bool> {
+        let mut req = self
+            .cli
+            .delete_item()
+            .table_name(table_name)
+            .key(hash_key, AttributeValue::S(hash_key_value.to_string()));
+        if let Some((condition, names, values)) = condition {
+            req = req
+                .condition_expression(condition)
+                .set_expression_attribute_names(Some(names))
+                .set_expression_attribute_values(Some(values));
+        }
+        match req.send().await {
+            Ok(_) => Ok(true),
+            Err(SdkError::ServiceError { err, .. })
+                if err.is_conditional_check_failed_exception() =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(from_sdk(format!("failed delete_item {:?}", e), &e)),
+        }
+    }
+}
+
+#[inline]
+pub fn is_error_retryable<E>(e: &SdkError<E>) -> bool {
+    match e {
+        SdkError::TimeoutError(_) | SdkError::ResponseError { .. } => true,
+        SdkError::DispatchFailure(e) => e.is_timeout() || e.is_io(),
+        _ => false,
+    }
+}
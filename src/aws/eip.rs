@@ -0,0 +1,143 @@
+use std::io::{self, Error, ErrorKind};
+
+use aws_types::SdkConfig as AwsSdkConfig;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aws::{ec2, lease},
+    errors::Result,
+};
+
+/// Table of the Elastic IP leases, shared by all clusters in the region.
+/// Created by "apply" with the first pool, and never deleted by "delete".
+pub const TABLE_NAME: &str = "avalanche-ops-eip-leases";
+const HASH_KEY: &str = "allocation_id";
+
+/// Default limit of the Elastic IP addresses per region.
+/// ref. https://docs.aws.amazon.com/AWSEC2/latest/UserGuide/elastic-ip-addresses-eip.html#using-instance-addressing-limit
+pub const DEFAULT_REGION_QUOTA: u32 = 5;
+
+/// Represents the pool of the Elastic IP addresses for the non-anchor nodes,
+/// allocated by "apply" and leased by "avalanched" at boot, so the replaced
+/// instances keep the same public IPs (e.g., firewalled by the partners).
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Pool {
+    /// Number of the addresses to allocate.
+    /// None for "machine.non_anchor_nodes". Set higher to keep the spare
+    /// addresses for the rolling updates that launch before terminating.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u32>,
+}
+
+impl Pool {
+    pub fn size(&self, non_anchor_nodes: u32) -> u32 {
+        self.size.unwrap_or(non_anchor_nodes)
+    }
+
+    pub fn validate(&self, non_anchor_nodes: u32) -> io::Result<()> {
+        let size = self.size(non_anchor_nodes);
+        if size == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'elastic_ip_pool.size' must be positive",
+            ));
+        }
+        if size < non_anchor_nodes {
+            warn!(
+                "'elastic_ip_pool.size' {} < {} non-anchor nodes, the rest keep the auto-assigned public IPs",
+                size, non_anchor_nodes
+            );
+        }
+        if size > DEFAULT_REGION_QUOTA {
+            warn!(
+                "'elastic_ip_pool.size' {} exceeds the default quota {} per region, request the increase first",
+                size, DEFAULT_REGION_QUOTA
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Represents an allocated Elastic IP address of the pool.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Address {
+    pub allocation_id: String,
+    pub public_ip: String,
+}
+
+/// Implements the Elastic IP leases, keyed by the allocation IDs.
+#[derive(Debug, Clone)]
+pub struct Manager {
+    lease_manager: lease::Manager,
+    ec2_manager: ec2::Manager,
+}
+
+impl Manager {
+    pub fn new(shared_config: &AwsSdkConfig) -> Self {
+        Self {
+            lease_manager: lease::Manager::new(shared_config, TABLE_NAME, HASH_KEY),
+            ec2_manager: ec2::Manager::new(shared_config),
+        }
+    }
+
+    pub async fn create_table_if_not_exists(&self) -> Result<()> {
+        self.lease_manager.create_table_if_not_exists().await
+    }
+
+    /// Leases the first free address of the pool to the instance,
+    /// and associates the address with the instance.
+    /// Returns "None" if all addresses are leased by the live instances.
+    pub async fn acquire(
+        &self,
+        cluster_id: &str,
+        addresses: &[Address],
+        instance_id: &str,
+    ) -> Result<Option<Address>> {
+        let keys: Vec<String> = addresses.iter().map(|a| a.allocation_id.clone()).collect();
+        let allocation_id = match self
+            .lease_manager
+            .acquire(cluster_id, &keys, instance_id)
+            .await?
+        {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let address = addresses
+            .iter()
+            .find(|a| a.allocation_id == allocation_id)
+            .cloned()
+            .unwrap();
+
+        self.ec2_manager
+            .associate_address(&address.allocation_id, instance_id)
+            .await?;
+        info!(
+            "associated '{}' ({}) with '{}'",
+            address.allocation_id, address.public_ip, instance_id
+        );
+        Ok(Some(address))
+    }
+
+    /// Deletes the leases of the pool (e.g., on "delete").
+    pub async fn delete_leases(&self, addresses: &[Address]) -> Result<()> {
+        let keys: Vec<String> = addresses.iter().map(|a| a.allocation_id.clone()).collect();
+        self.lease_manager.delete(&keys).await
+    }
+}
+
+#[test]
+fn test_pool() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let pool = Pool::default();
+    assert_eq!(pool.size(3), 3);
+    assert!(pool.validate(3).is_ok());
+    assert!(pool.validate(0).is_err());
+
+    let pool = Pool { size: Some(4) };
+    assert_eq!(pool.size(3), 4);
+    assert!(pool.validate(3).is_ok());
+}
@@ -0,0 +1,353 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Error, ErrorKind},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// First port of the extra targets of the weighted instances,
+/// redirected to the node HTTP port on the instance.
+pub const WEIGHTED_PORT_BASE: u32 = 19650;
+/// Maximum weight of an instance type, the number of its targets.
+pub const MAX_WEIGHT: u32 = 10;
+
+/// Represents the health check and deregistration settings
+/// of the NLB target group for the node HTTP port.
+/// The unset fields keep the template defaults.
+/// ref. https://docs.aws.amazon.com/elasticloadbalancing/latest/network/target-group-health-checks.html
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct TargetGroup {
+    /// "TCP" (default), "HTTP", or "HTTPS".
+    /// The HTTP health checks on "/ext/health" fail while the node
+    /// is bootstrapping, so prefer the higher thresholds with them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_check_protocol: Option<String>,
+    /// Port number, or "traffic-port" (default) for the node HTTP port.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_check_port: Option<String>,
+    /// Only for the "HTTP" and "HTTPS" health checks (e.g., "/ext/health/liveness").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_check_path: Option<String>,
+    /// 10 or 30 (default) seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_check_interval_seconds: Option<u32>,
+    /// Consecutive successes to mark the target healthy (2-10, default 3).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub healthy_threshold: Option<u32>,
+    /// Consecutive failures to mark the target unhealthy (2-10, default 3).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unhealthy_threshold: Option<u32>,
+    /// Seconds to drain the deregistering target (0-3600, default 60).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deregistration_delay_seconds: Option<u32>,
+    /// Set "true" to route the same client IP to the same node
+    /// (e.g., websocket subscriptions). Not supported with the TLS listener.
+    /// ref. https://docs.aws.amazon.com/elasticloadbalancing/latest/network/load-balancer-target-groups.html#sticky-sessions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stickiness: Option<bool>,
+    /// Set "true" to let "avalanched" register the instance once avalanchego
+    /// is bootstrapped and healthy, and deregister it while the node is not
+    /// (e.g., re-syncing), instead of the ASG registering it at launch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub register_on_readiness: Option<bool>,
+    /// Weights of the instance types (1-10, default 1), so the bigger API nodes
+    /// receive proportionally more connections (e.g., "c6a.4xlarge: 4").
+    /// The NLB has no target weights, so "avalanched" registers the instance
+    /// as many targets as the weight: the node HTTP port, and the extra ports
+    /// from "WEIGHTED_PORT_BASE" redirected to the HTTP port.
+    /// Requires "register_on_readiness". The security group ingress of the extra
+    /// ports is created with the NLB, so set this before the first "apply".
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub weights: BTreeMap<String, u32>,
+}
+
+impl TargetGroup {
+    /// Validates the settings against the NLB target group limits.
+    pub fn validate(&self) -> io::Result<()> {
+        let protocol = self.health_check_protocol.as_deref().unwrap_or("TCP");
+        if !["TCP", "HTTP", "HTTPS"].contains(&protocol) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'health_check_protocol' must be 'TCP', 'HTTP', or 'HTTPS' (got '{}')",
+                    protocol
+                ),
+            ));
+        }
+        if let Some(path) = &self.health_check_path {
+            if protocol == "TCP" {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'health_check_path' requires the 'HTTP' or 'HTTPS' 'health_check_protocol'",
+                ));
+            }
+            if !path.starts_with('/') {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("'health_check_path' must start with '/' (got '{}')", path),
+                ));
+            }
+        }
+        if let Some(port) = &self.health_check_port {
+            if port != "traffic-port" && port.parse::<u16>().map(|v| v == 0).unwrap_or(true) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "'health_check_port' must be a port or 'traffic-port' (got '{}')",
+                        port
+                    ),
+                ));
+            }
+        }
+        if let Some(v) = self.health_check_interval_seconds {
+            if v != 10 && v != 30 {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "'health_check_interval_seconds' must be 10 or 30 (got {})",
+                        v
+                    ),
+                ));
+            }
+        }
+        for (k, v) in [
+            ("healthy_threshold", self.healthy_threshold),
+            ("unhealthy_threshold", self.unhealthy_threshold),
+        ] {
+            if let Some(v) = v {
+                if !(2..=10).contains(&v) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("'{}' must be 2-10 (got {})", k, v),
+                    ));
+                }
+            }
+        }
+        if let Some(v) = self.deregistration_delay_seconds {
+            if v > 3600 {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("'deregistration_delay_seconds' must be 0-3600 (got {})", v),
+                ));
+            }
+        }
+        for (instance_type, v) in self.weights.iter() {
+            if !(1..=MAX_WEIGHT).contains(v) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "'weights' of '{}' must be 1-{} (got {})",
+                        instance_type, MAX_WEIGHT, v
+                    ),
+                ));
+            }
+        }
+        if !self.weights.is_empty() && !self.is_register_on_readiness() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'weights' requires 'register_on_readiness'",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the CloudFormation template parameters for the set fields.
+    pub fn parameters(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        if let Some(v) = &self.health_check_protocol {
+            params.push(("NlbHealthCheckProtocol", v.clone()));
+        }
+        if let Some(v) = &self.health_check_port {
+            params.push(("NlbHealthCheckPort", v.clone()));
+        }
+        if let Some(v) = &self.health_check_path {
+            params.push(("NlbHealthCheckPath", v.clone()));
+        }
+        if let Some(v) = self.health_check_interval_seconds {
+            params.push(("NlbHealthCheckIntervalSeconds", v.to_string()));
+        }
+        if let Some(v) = self.healthy_threshold {
+            params.push(("NlbHealthyThreshold", v.to_string()));
+        }
+        if let Some(v) = self.unhealthy_threshold {
+            params.push(("NlbUnhealthyThreshold", v.to_string()));
+        }
+        if let Some(v) = self.deregistration_delay_seconds {
+            params.push(("NlbDeregistrationDelaySeconds", v.to_string()));
+        }
+        if let Some(v) = self.stickiness {
+            params.push(("NlbStickiness", v.to_string()));
+        }
+        if let Some(v) = self.register_on_readiness {
+            params.push(("NlbRegisterOnReadiness", v.to_string()));
+        }
+        if !self.weights.is_empty() {
+            let (from, to) = weighted_port_range();
+            params.push(("NlbWeightedPortFrom", from.to_string()));
+            params.push(("NlbWeightedPortTo", to.to_string()));
+        }
+        params
+    }
+
+    pub fn is_register_on_readiness(&self) -> bool {
+        self.register_on_readiness.unwrap_or(false)
+    }
+
+    /// Returns the weight of the instance type.
+    pub fn weight(&self, instance_type: &str) -> u32 {
+        self.weights.get(instance_type).copied().unwrap_or(1)
+    }
+
+    /// Returns the extra target ports of the instance type,
+    /// besides the node HTTP port.
+    pub fn weighted_ports(&self, instance_type: &str) -> Vec<u32> {
+        (0..self.weight(instance_type) - 1)
+            .map(|i| WEIGHTED_PORT_BASE + i)
+            .collect()
+    }
+}
+
+/// Returns the first and the last ports of the extra targets.
+pub fn weighted_port_range() -> (u32, u32) {
+    (WEIGHTED_PORT_BASE, WEIGHTED_PORT_BASE + MAX_WEIGHT - 2)
+}
+
+/// Returns the AWS CLI command to register (or deregister) the instance
+/// in the target group, on the target group port.
+/// ref. https://docs.aws.amazon.com/cli/latest/reference/elbv2/register-targets.html
+/// The extra ports register the same instance as the additional targets.
+pub fn target_registration_cmd(
+    region: &str,
+    target_group_arn: &str,
+    instance_id: &str,
+    weighted_ports: &[u32],
+    register: bool,
+) -> String {
+    let mut targets = format!("Id={}", instance_id);
+    for port in weighted_ports.iter() {
+        targets.push_str(&format!(" Id={},Port={}", instance_id, port));
+    }
+    format!(
+        "aws elbv2 {} --region {} --target-group-arn {} --targets {}",
+        if register {
+            "register-targets"
+        } else {
+            "deregister-targets"
+        },
+        region,
+        target_group_arn,
+        targets
+    )
+}
+
+/// Returns the command to redirect the extra target port to the node HTTP port,
+/// which adds the iptables rule only once.
+pub fn port_redirect_cmd(port: u32, http_port: u32) -> String {
+    let rule = format!(
+        "PREROUTING -p tcp --dport {} -j REDIRECT --to-ports {}",
+        port, http_port
+    );
+    format!(
+        "iptables -t nat -C {} 2>/dev/null || iptables -t nat -A {}",
+        rule, rule
+    )
+}
+
+#[test]
+fn test_target_group() {
+    let tg = TargetGroup::default();
+    assert!(tg.validate().is_ok());
+    assert!(tg.parameters().is_empty());
+
+    let tg = TargetGroup {
+        health_check_protocol: Some(String::from("HTTP")),
+        health_check_path: Some(String::from("/ext/health/liveness")),
+        unhealthy_threshold: Some(10),
+        deregistration_delay_seconds: Some(300),
+        stickiness: Some(true),
+        register_on_readiness: Some(true),
+        ..TargetGroup::default()
+    };
+    assert!(tg.validate().is_ok());
+    assert!(tg.is_register_on_readiness());
+    assert_eq!(
+        tg.parameters(),
+        vec![
+            ("NlbHealthCheckProtocol", String::from("HTTP")),
+            ("NlbHealthCheckPath", String::from("/ext/health/liveness")),
+            ("NlbUnhealthyThreshold", String::from("10")),
+            ("NlbDeregistrationDelaySeconds", String::from("300")),
+            ("NlbStickiness", String::from("true")),
+            ("NlbRegisterOnReadiness", String::from("true")),
+        ]
+    );
+    assert_eq!(
+        target_registration_cmd("us-west-2", "arn:tg", "i-123", &[], false),
+        "aws elbv2 deregister-targets --region us-west-2 --target-group-arn arn:tg --targets Id=i-123"
+    );
+
+    let tg = TargetGroup {
+        register_on_readiness: Some(true),
+        weights: BTreeMap::from([(String::from("c6a.4xlarge"), 3)]),
+        ..TargetGroup::default()
+    };
+    assert!(tg.validate().is_ok());
+    assert_eq!(tg.weight("c6a.large"), 1);
+    assert!(tg.weighted_ports("c6a.large").is_empty());
+    assert_eq!(tg.weighted_ports("c6a.4xlarge"), vec![19650, 19651]);
+    assert_eq!(
+        tg.parameters(),
+        vec![
+            ("NlbRegisterOnReadiness", String::from("true")),
+            ("NlbWeightedPortFrom", String::from("19650")),
+            ("NlbWeightedPortTo", String::from("19658")),
+        ]
+    );
+    assert_eq!(
+        target_registration_cmd("us-west-2", "arn:tg", "i-123", &[19650], true),
+        "aws elbv2 register-targets --region us-west-2 --target-group-arn arn:tg --targets Id=i-123 Id=i-123,Port=19650"
+    );
+    assert_eq!(
+        port_redirect_cmd(19650, 9650),
+        "iptables -t nat -C PREROUTING -p tcp --dport 19650 -j REDIRECT --to-ports 9650 2>/dev/null || iptables -t nat -A PREROUTING -p tcp --dport 19650 -j REDIRECT --to-ports 9650"
+    );
+
+    for invalid in [
+        TargetGroup {
+            health_check_path: Some(String::from("/ext/health")),
+            ..TargetGroup::default()
+        },
+        TargetGroup {
+            health_check_protocol: Some(String::from("UDP")),
+            ..TargetGroup::default()
+        },
+        TargetGroup {
+            health_check_port: Some(String::from("0")),
+            ..TargetGroup::default()
+        },
+        TargetGroup {
+            health_check_interval_seconds: Some(15),
+            ..TargetGroup::default()
+        },
+        TargetGroup {
+            healthy_threshold: Some(1),
+            ..TargetGroup::default()
+        },
+        TargetGroup {
+            deregistration_delay_seconds: Some(3601),
+            ..TargetGroup::default()
+        },
+        TargetGroup {
+            weights: BTreeMap::from([(String::from("c6a.4xlarge"), 2)]),
+            ..TargetGroup::default()
+        },
+        TargetGroup {
+            register_on_readiness: Some(true),
+            weights: BTreeMap::from([(String::from("c6a.4xlarge"), 11)]),
+            ..TargetGroup::default()
+        },
+    ] {
+        assert!(invalid.validate().is_err(), "{:?}", invalid);
+    }
+}
@@ -1,7 +1,9 @@
 use std::{
+    collections::VecDeque,
     fs::{self, File},
     io::{Cursor, Read, Write},
-    sync::Arc,
+    num::NonZeroU32,
+    sync::{Arc, Mutex},
 };
 
 use aws_sdk_kms::model::{DataKeySpec, EncryptionAlgorithmSpec};
@@ -10,23 +12,42 @@ use log::info;
 /// "NONCE_LEN" is the per-record nonce (iv_length), 12-byte
 /// ref. https://www.rfc-editor.org/rfc/rfc8446#appendix-E.2
 use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2;
 use ring::rand::{SecureRandom, SystemRandom};
 
 use crate::{
     aws::kms,
     errors::{Error::Other, Result},
-    utils::humanize,
+    fips,
+    utils::{humanize, secret_bytes::SecretBytes},
 };
 
 const DEK_AES_256_LENGTH: usize = 32;
 
 const AAD_TAG: &str = "avalanche-ops-envelope-encryption";
 
+/// Salt length for the passphrase-derived key.
+const PASSPHRASE_SALT_LEN: usize = 16;
+
+/// PBKDF2-HMAC-SHA256 iterations for the passphrase-derived key.
+/// ref. https://cheatsheetseries.owasp.org/cheatsheets/Password_Storage_Cheat_Sheet.html
+const PASSPHRASE_PBKDF2_ITERATIONS: u32 = 600_000;
+
+/// Default number of the plaintext data keys to cache.
+pub const DEFAULT_DATA_KEY_CACHE_CAPACITY: usize = 64;
+
+/// Max number of the objects to seal with the same cached data key,
+/// well below the AES-GCM limit for the random nonces.
+/// ref. https://docs.aws.amazon.com/encryption-sdk/latest/developer-guide/data-key-caching.html
+const MAX_DATA_KEY_USES: u32 = 1000;
+
 /// Implements envelope encryption manager.
 #[derive(std::clone::Clone)]
 pub struct Envelope {
     aws_kms_manager: Option<kms::Manager>,
     aws_kms_key_id: Option<String>,
+    /// Shared by the clones, so the spawned tasks hit the same cache.
+    data_key_cache: Option<Arc<Mutex<DataKeyCache>>>,
 }
 
 impl Envelope {
@@ -34,7 +55,72 @@ impl Envelope {
         Self {
             aws_kms_manager,
             aws_kms_key_id,
+            data_key_cache: None,
+        }
+    }
+
+    /// Caches up to "capacity" plaintext data keys, so that sealing
+    /// and unsealing many objects do not call KMS for each object.
+    pub fn with_data_key_cache(mut self, capacity: usize) -> Self {
+        self.data_key_cache = Some(Arc::new(Mutex::new(DataKeyCache::new(capacity))));
+        self
+    }
+
+    /// Returns the (plaintext, ciphertext) data key to seal, reusing the
+    /// cached one up to "MAX_DATA_KEY_USES" times.
+    async fn data_key_to_seal(
+        &self,
+        kms_manager: &kms::Manager,
+        key_id: &str,
+    ) -> Result<(SecretBytes, Vec<u8>)> {
+        if let Some(cache) = &self.data_key_cache {
+            if let Some(v) = cache.lock().unwrap().take_sealing() {
+                return Ok(v);
+            }
+        }
+        let dek = kms_manager
+            .generate_data_key(key_id, Some(DataKeySpec::Aes256))
+            .await?;
+        let plaintext = dek.plaintext;
+        if let Some(cache) = &self.data_key_cache {
+            cache
+                .lock()
+                .unwrap()
+                .put_sealing(&dek.ciphertext, plaintext.expose());
         }
+        Ok((plaintext, dek.ciphertext))
+    }
+
+    /// Returns the plaintext data key of the ciphertext,
+    /// calling KMS only on the cache miss.
+    async fn data_key_to_unseal(
+        &self,
+        kms_manager: &kms::Manager,
+        key_id: &str,
+        dek_ciphertext: Vec<u8>,
+    ) -> Result<SecretBytes> {
+        if let Some(cache) = &self.data_key_cache {
+            if let Some(v) = cache.lock().unwrap().get(&dek_ciphertext) {
+                return Ok(v);
+            }
+        }
+        // use the default "SYMMETRIC_DEFAULT"
+        let plaintext = SecretBytes::new(
+            kms_manager
+                .decrypt(
+                    key_id,
+                    Some(EncryptionAlgorithmSpec::SymmetricDefault),
+                    dek_ciphertext.clone(),
+                )
+                .await?,
+        );
+        if let Some(cache) = &self.data_key_cache {
+            cache
+                .lock()
+                .unwrap()
+                .put(&dek_ciphertext, plaintext.expose());
+        }
+        Ok(plaintext)
     }
 
     /// Envelope-encrypts the data using AWS KMS data-encryption key (DEK)
@@ -56,15 +142,13 @@ impl Envelope {
         let kms_manager = self.aws_kms_manager.clone().unwrap();
         let key_id = self.aws_kms_key_id.clone().unwrap();
 
-        let dek = kms_manager
-            .generate_data_key(&key_id, Some(DataKeySpec::Aes256))
-            .await?;
-        if dek.plaintext.len() != DEK_AES_256_LENGTH {
+        let (dek_plaintext, dek_ciphertext) = self.data_key_to_seal(&kms_manager, &key_id).await?;
+        if dek_plaintext.len() != DEK_AES_256_LENGTH {
             return Err(Other {
                 message: format!(
                     "DEK.plaintext for AES_256 must be {}-byte, got {}-byte",
                     DEK_AES_256_LENGTH,
-                    dek.plaintext.len()
+                    dek_plaintext.len()
                 ),
                 is_retryable: false,
             });
@@ -81,7 +165,7 @@ impl Envelope {
                 });
             }
         }
-        let unbound_key = match UnboundKey::new(&AES_256_GCM, &dek.plaintext) {
+        let unbound_key = match UnboundKey::new(&AES_256_GCM, dek_plaintext.expose()) {
             Ok(v) => v,
             Err(e) => {
                 return Err(Other {
@@ -128,7 +212,7 @@ impl Envelope {
         }
 
         // DEK.ciphertext "length"
-        match encrypted.write_u16::<LittleEndian>(dek.ciphertext.len() as u16) {
+        match encrypted.write_u16::<LittleEndian>(dek_ciphertext.len() as u16) {
             Ok(_) => {}
             Err(e) => {
                 return Err(Other {
@@ -150,7 +234,7 @@ impl Envelope {
         }
 
         // DEK.ciphertext
-        match encrypted.write_all(&dek.ciphertext) {
+        match encrypted.write_all(&dek_ciphertext) {
             Ok(_) => {}
             Err(e) => {
                 return Err(Other {
@@ -262,15 +346,10 @@ impl Envelope {
                 });
             }
         };
-        // use the default "SYMMETRIC_DEFAULT"
-        let dek_plain = kms_manager
-            .decrypt(
-                &key_id,
-                Some(EncryptionAlgorithmSpec::SymmetricDefault),
-                dek_ciphertext,
-            )
+        let dek_plain = self
+            .data_key_to_unseal(&kms_manager, &key_id, dek_ciphertext)
             .await?;
-        let unbound_key = match UnboundKey::new(&AES_256_GCM, &dek_plain) {
+        let unbound_key = match UnboundKey::new(&AES_256_GCM, dek_plain.expose()) {
             Ok(v) => v,
             Err(e) => {
                 return Err(Other {
@@ -406,6 +485,206 @@ impl Envelope {
     }
 }
 
+/// Encrypts the data locally with "AES_256_GCM", using the key derived
+/// from the passphrase with PBKDF2 (no KMS access required).
+/// The encrypted data are aligned as below:
+/// [ Salt bytes "length" ][ Nonce bytes "length" ][ Salt bytes ][ Nonce bytes ][ data ciphertext ]
+pub fn seal_aes_256_passphrase(passphrase: &[u8], d: &[u8]) -> Result<Vec<u8>> {
+    check_passphrase_allowed()?;
+    if passphrase.is_empty() {
+        return Err(Other {
+            message: String::from("empty passphrase"),
+            is_retryable: false,
+        });
+    }
+
+    let random = SystemRandom::new();
+    let mut salt = [0u8; PASSPHRASE_SALT_LEN];
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    random
+        .fill(&mut salt)
+        .and_then(|_| random.fill(&mut nonce_bytes))
+        .map_err(|e| Other {
+            message: format!(
+                "failed to generate ring.random for salt and nonce ({:?})",
+                e
+            ),
+            is_retryable: false,
+        })?;
+
+    let safe_key = passphrase_key(passphrase, &salt)?;
+    let mut cipher = d.to_vec();
+    safe_key
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce_bytes),
+            Aad::from(AAD_TAG),
+            &mut cipher,
+        )
+        .map_err(|e| Other {
+            message: format!("failed to seal ({:?})", e),
+            is_retryable: false,
+        })?;
+
+    let mut encrypted = Vec::new();
+    encrypted
+        .write_u16::<LittleEndian>(PASSPHRASE_SALT_LEN as u16)
+        .and_then(|_| encrypted.write_u16::<LittleEndian>(NONCE_LEN as u16))
+        .and_then(|_| encrypted.write_all(&salt))
+        .and_then(|_| encrypted.write_all(&nonce_bytes))
+        .and_then(|_| encrypted.write_all(&cipher))
+        .map_err(|e| Other {
+            message: format!("failed to write ({:?})", e),
+            is_retryable: false,
+        })?;
+    Ok(encrypted)
+}
+
+/// Decrypts the data sealed by "seal_aes_256_passphrase".
+/// Fails if the passphrase is wrong or the data were tampered with.
+pub fn unseal_aes_256_passphrase(passphrase: &[u8], d: &[u8]) -> Result<Vec<u8>> {
+    check_passphrase_allowed()?;
+    let mut buf = Cursor::new(d);
+    let read_len = |buf: &mut Cursor<&[u8]>| {
+        buf.read_u16::<LittleEndian>().map_err(|e| Other {
+            message: format!("failed to read ({:?})", e),
+            is_retryable: false,
+        })
+    };
+    let salt_len = read_len(&mut buf)? as usize;
+    let nonce_len = read_len(&mut buf)? as usize;
+    if salt_len != PASSPHRASE_SALT_LEN || nonce_len != NONCE_LEN {
+        return Err(Other {
+            message: format!(
+                "unexpected salt/nonce length {}/{} (expected {}/{})",
+                salt_len, nonce_len, PASSPHRASE_SALT_LEN, NONCE_LEN
+            ),
+            is_retryable: false,
+        });
+    }
+
+    let mut salt = zero_vec(salt_len);
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    let mut cipher = Vec::new();
+    buf.read_exact(&mut salt)
+        .and_then(|_| buf.read_exact(&mut nonce_bytes))
+        .and_then(|_| buf.read_to_end(&mut cipher))
+        .map_err(|e| Other {
+            message: format!("failed to read ({:?})", e),
+            is_retryable: false,
+        })?;
+
+    let safe_key = passphrase_key(passphrase, &salt)?;
+    let decrypted = safe_key
+        .open_in_place(
+            Nonce::assume_unique_for_key(nonce_bytes),
+            Aad::from(AAD_TAG),
+            &mut cipher,
+        )
+        .map_err(|e| Other {
+            message: format!("failed to open, wrong passphrase? ({:?})", e),
+            is_retryable: false,
+        })?;
+    Ok(decrypted.to_vec())
+}
+
+/// In the FIPS mode, only the data keys generated by the KMS (FIPS-validated
+/// HSMs) are allowed, so the passphrase-derived keys are rejected.
+fn check_passphrase_allowed() -> Result<()> {
+    if fips::enabled() {
+        return Err(Other {
+            message: String::from(
+                "passphrase encryption is not allowed in the FIPS mode (use the KMS envelope encryption)",
+            ),
+            is_retryable: false,
+        });
+    }
+    Ok(())
+}
+
+fn passphrase_key(passphrase: &[u8], salt: &[u8]) -> Result<LessSafeKey> {
+    let mut key = SecretBytes::zeroed(DEK_AES_256_LENGTH);
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PASSPHRASE_PBKDF2_ITERATIONS).unwrap(),
+        salt,
+        passphrase,
+        key.expose_mut(),
+    );
+    let unbound_key = UnboundKey::new(&AES_256_GCM, key.expose()).map_err(|e| Other {
+        message: format!("failed to create UnboundKey ({:?})", e),
+        is_retryable: false,
+    })?;
+    Ok(LessSafeKey::new(unbound_key))
+}
+
+/// Bounded LRU cache of the plaintext data keys, keyed by the data key
+/// ciphertext (unique per "GenerateDataKey"). The plaintext keys are
+/// zeroized when evicted or dropped.
+pub struct DataKeyCache {
+    capacity: usize,
+    /// Least recently used first.
+    entries: VecDeque<(Vec<u8>, SecretBytes)>,
+    /// Ciphertext and the number of uses of the data key for sealing.
+    sealing: Option<(Vec<u8>, u32)>,
+}
+
+impl DataKeyCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: VecDeque::new(),
+            sealing: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the plaintext key, marking it as the most recently used.
+    pub fn get(&mut self, ciphertext: &[u8]) -> Option<SecretBytes> {
+        let idx = self.entries.iter().position(|(c, _)| c == ciphertext)?;
+        let entry = self.entries.remove(idx).unwrap();
+        let plaintext = entry.1.clone();
+        self.entries.push_back(entry);
+        Some(plaintext)
+    }
+
+    /// Inserts the key, evicting the least recently used key if full.
+    pub fn put(&mut self, ciphertext: &[u8], plaintext: &[u8]) {
+        if let Some(idx) = self.entries.iter().position(|(c, _)| c == ciphertext) {
+            self.entries.remove(idx);
+        }
+        while self.entries.len() >= self.capacity {
+            // "SecretBytes" wipes the plaintext on drop
+            self.entries.pop_front();
+        }
+        self.entries
+            .push_back((ciphertext.to_vec(), SecretBytes::new(plaintext.to_vec())));
+    }
+
+    /// Returns the (plaintext, ciphertext) key for sealing the next object,
+    /// or None if the key is evicted or has reached the max uses.
+    fn take_sealing(&mut self) -> Option<(SecretBytes, Vec<u8>)> {
+        let (ciphertext, uses) = self.sealing.take()?;
+        if uses >= MAX_DATA_KEY_USES {
+            return None;
+        }
+        let plaintext = self.get(&ciphertext)?;
+        self.sealing = Some((ciphertext.clone(), uses + 1));
+        Some((plaintext, ciphertext))
+    }
+
+    fn put_sealing(&mut self, ciphertext: &[u8], plaintext: &[u8]) {
+        self.put(ciphertext, plaintext);
+        self.sealing = Some((ciphertext.to_vec(), 1));
+    }
+}
+
 fn zero_vec(n: usize) -> Vec<u8> {
     (0..n).map(|_| 0).collect()
 }
@@ -443,3 +722,40 @@ pub async fn spawn_unseal_aes_256_file(
     .await
     .expect("failed spawn await")
 }
+
+#[test]
+fn test_data_key_cache() {
+    let mut cache = DataKeyCache::new(2);
+    cache.put(b"c1", b"p1");
+    cache.put(b"c2", b"p2");
+    assert_eq!(cache.get(b"c1").unwrap().expose(), b"p1");
+    // "c2" is the least recently used
+    cache.put(b"c3", b"p3");
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get(b"c2").is_none());
+    assert_eq!(cache.get(b"c3").unwrap().expose(), b"p3");
+
+    cache.put_sealing(b"c4", b"p4");
+    let (plaintext, ciphertext) = cache.take_sealing().unwrap();
+    assert_eq!(
+        (plaintext.expose(), ciphertext.as_slice()),
+        (&b"p4"[..], &b"c4"[..])
+    );
+    cache.sealing = Some((b"c4".to_vec(), MAX_DATA_KEY_USES));
+    assert!(cache.take_sealing().is_none());
+    // no reuse once evicted
+    cache.put_sealing(b"c5", b"p5");
+    cache.put(b"c6", b"p6");
+    cache.put(b"c7", b"p7");
+    assert!(cache.take_sealing().is_none());
+}
+
+#[test]
+fn test_passphrase() {
+    let d = b"hello world";
+    let encrypted = seal_aes_256_passphrase(b"secret", d).unwrap();
+    assert!(!encrypted.windows(d.len()).any(|w| w == d));
+    assert_eq!(unseal_aes_256_passphrase(b"secret", &encrypted).unwrap(), d);
+    assert!(unseal_aes_256_passphrase(b"wrong", &encrypted).is_err());
+    assert!(seal_aes_256_passphrase(b"", d).is_err());
+}
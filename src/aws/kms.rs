@@ -5,31 +5,26 @@ use std::{
 };
 
 use aws_sdk_kms::{
-    error::{
-        CreateKeyError, CreateKeyErrorKind, DecryptError, DecryptErrorKind, EncryptError,
-        EncryptErrorKind, GenerateDataKeyError, GenerateDataKeyErrorKind, ScheduleKeyDeletionError,
-        ScheduleKeyDeletionErrorKind,
-    },
+    error::{ScheduleKeyDeletionError, ScheduleKeyDeletionErrorKind},
     model::{DataKeySpec, EncryptionAlgorithmSpec, Tag},
     types::{Blob, SdkError},
-    Client,
+    Client, Endpoint,
 };
 use aws_types::SdkConfig as AwsSdkConfig;
+use hyper::Uri;
 use log::{info, warn};
 
 use crate::{
-    errors::{
-        Error::{Other, API},
-        Result,
-    },
-    utils::humanize,
+    errors::{from_sdk, Error::Other, Result},
+    fips,
+    utils::{humanize, secret_bytes::SecretBytes},
 };
 
 /// Represents the data encryption key.
 #[derive(Debug)]
 pub struct DEK {
     pub ciphertext: Vec<u8>,
-    pub plaintext: Vec<u8>,
+    pub plaintext: SecretBytes,
 }
 
 impl DEK {
@@ -37,7 +32,7 @@ impl DEK {
         // ref. https://doc.rust-lang.org/1.0.0/style/ownership/constructors.html
         Self {
             ciphertext: cipher,
-            plaintext: plain,
+            plaintext: SecretBytes::new(plain),
         }
     }
 }
@@ -53,7 +48,11 @@ pub struct Manager {
 impl Manager {
     pub fn new(shared_config: &AwsSdkConfig) -> Self {
         let cloned = shared_config.clone();
-        let cli = Client::new(shared_config);
+        let cli = if fips::enabled() {
+            Client::from_conf(fips_config(shared_config))
+        } else {
+            Client::new(shared_config)
+        };
         Self {
             shared_config: cloned,
             cli,
@@ -79,10 +78,7 @@ impl Manager {
         let resp = match ret {
             Ok(v) => v,
             Err(e) => {
-                return Err(API {
-                    message: format!("failed create_key {:?}", e),
-                    is_retryable: is_error_retryable_create_key(&e),
-                });
+                return Err(from_sdk(format!("failed create_key {:?}", e), &e));
             }
         };
 
@@ -126,10 +122,10 @@ impl Manager {
                     ignore_err = true
                 }
                 if !ignore_err {
-                    return Err(API {
-                        message: format!("failed schedule_key_deletion {:?}", e),
-                        is_retryable: is_error_retryable(&e),
-                    });
+                    return Err(from_sdk(
+                        format!("failed schedule_key_deletion {:?}", e),
+                        &e,
+                    ));
                 }
                 false
             }
@@ -141,6 +137,33 @@ impl Manager {
         Ok(())
     }
 
+    /// Returns the state of the KMS CMK (e.g., "Enabled", "PendingDeletion").
+    /// ref. https://docs.aws.amazon.com/kms/latest/developerguide/key-state.html
+    pub async fn describe_key_state(&self, key_id: &str) -> Result<String> {
+        let ret = self.cli.describe_key().key_id(key_id).send().await;
+        let resp = match ret {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(from_sdk(format!("failed describe_key {:?}", e), &e));
+            }
+        };
+        Ok(resp
+            .key_metadata
+            .and_then(|m| m.key_state)
+            .map(|s| s.as_str().to_string())
+            .unwrap_or_default())
+    }
+
+    /// Lists one key to check the KMS endpoint is reachable
+    /// and the caller is allowed, before any key is created.
+    pub async fn check_access(&self) -> Result<()> {
+        let ret = self.cli.list_keys().limit(1).send().await;
+        match ret {
+            Ok(_) => Ok(()),
+            Err(e) => Err(from_sdk(format!("failed list_keys {:?}", e), &e)),
+        }
+    }
+
     /// Encrypts data. The maximum size of the data KMS can encrypt is 4096 bytes for
     /// "SYMMETRIC_DEFAULT" encryption algorithm. To specify a KMS key, use its key ID,
     /// key ARN, alias name, or alias ARN.
@@ -169,18 +192,15 @@ impl Manager {
         let resp = match ret {
             Ok(v) => v,
             Err(e) => {
-                return Err(API {
-                    message: format!("failed encrypt {:?}", e),
-                    is_retryable: is_error_retryable_encrypt(&e),
-                });
+                return Err(from_sdk(format!("failed encrypt {:?}", e), &e));
             }
         };
 
         let ciphertext = match resp.ciphertext_blob() {
             Some(v) => v,
             None => {
-                return Err(API {
-                    message: String::from("EncryptOutput.ciphertext_blob not foun"),
+                return Err(Other {
+                    message: String::from("EncryptOutput.ciphertext_blob not found"),
                     is_retryable: false,
                 });
             }
@@ -221,18 +241,15 @@ impl Manager {
         let resp = match ret {
             Ok(v) => v,
             Err(e) => {
-                return Err(API {
-                    message: format!("failed decrypt {:?}", e),
-                    is_retryable: is_error_retryable_decrypt(&e),
-                });
+                return Err(from_sdk(format!("failed decrypt {:?}", e), &e));
             }
         };
 
         let plaintext = match resp.plaintext() {
             Some(v) => v,
             None => {
-                return Err(API {
-                    message: String::from("DecryptOutput.plaintext not foun"),
+                return Err(Other {
+                    message: String::from("DecryptOutput.plaintext not found"),
                     is_retryable: false,
                 });
             }
@@ -362,10 +379,7 @@ impl Manager {
         let resp = match ret {
             Ok(v) => v,
             Err(e) => {
-                return Err(API {
-                    message: format!("failed generate_data_key {:?}", e),
-                    is_retryable: is_error_retryable_generate_data_key(&e),
-                });
+                return Err(from_sdk(format!("failed generate_data_key {:?}", e), &e));
             }
         };
 
@@ -378,6 +392,20 @@ impl Manager {
     }
 }
 
+/// Overrides the endpoint with the FIPS endpoint of the region.
+fn fips_config(shared_config: &AwsSdkConfig) -> aws_sdk_kms::Config {
+    let region = shared_config
+        .region()
+        .map(|r| r.to_string())
+        .unwrap_or_default();
+    let uri: Uri = fips::kms_endpoint(&region)
+        .parse()
+        .expect("unexpected invalid FIPS endpoint");
+    aws_sdk_kms::config::Builder::from(shared_config)
+        .endpoint_resolver(Endpoint::immutable(uri))
+        .build()
+}
+
 /// Represents the KMS CMK.
 #[derive(Debug)]
 pub struct Key {
@@ -404,65 +432,6 @@ pub fn is_error_retryable<E>(e: &SdkError<E>) -> bool {
     }
 }
 
-#[inline]
-pub fn is_error_retryable_create_key(e: &SdkError<CreateKeyError>) -> bool {
-    match e {
-        SdkError::ServiceError { err, .. } => {
-            matches!(
-                err.kind,
-                CreateKeyErrorKind::DependencyTimeoutException(_)
-                    | CreateKeyErrorKind::KmsInternalException(_)
-            )
-        }
-        _ => false,
-    }
-}
-
-#[inline]
-pub fn is_error_retryable_generate_data_key(e: &SdkError<GenerateDataKeyError>) -> bool {
-    match e {
-        SdkError::ServiceError { err, .. } => {
-            matches!(
-                err.kind,
-                GenerateDataKeyErrorKind::DependencyTimeoutException(_)
-                    | GenerateDataKeyErrorKind::KmsInternalException(_)
-                    | GenerateDataKeyErrorKind::KeyUnavailableException(_)
-            )
-        }
-        _ => false,
-    }
-}
-
-#[inline]
-pub fn is_error_retryable_encrypt(e: &SdkError<EncryptError>) -> bool {
-    match e {
-        SdkError::ServiceError { err, .. } => {
-            matches!(
-                err.kind,
-                EncryptErrorKind::DependencyTimeoutException(_)
-                    | EncryptErrorKind::KmsInternalException(_)
-                    | EncryptErrorKind::KeyUnavailableException(_)
-            )
-        }
-        _ => false,
-    }
-}
-
-#[inline]
-pub fn is_error_retryable_decrypt(e: &SdkError<DecryptError>) -> bool {
-    match e {
-        SdkError::ServiceError { err, .. } => {
-            matches!(
-                err.kind,
-                DecryptErrorKind::DependencyTimeoutException(_)
-                    | DecryptErrorKind::KmsInternalException(_)
-                    | DecryptErrorKind::KeyUnavailableException(_)
-            )
-        }
-        _ => false,
-    }
-}
-
 #[inline]
 fn is_error_schedule_key_deletion_does_not_exist(e: &SdkError<ScheduleKeyDeletionError>) -> bool {
     match e {
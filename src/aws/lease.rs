@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_sdk_ec2::model::InstanceStateName;
+use aws_types::SdkConfig as AwsSdkConfig;
+use chrono::Utc;
+use log::info;
+
+use crate::{
+    aws::{dynamodb, ec2},
+    errors::{Error::Other, Result},
+};
+
+/// Represents the lease of a shared resource (e.g., Elastic IP, node slot)
+/// by an instance.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Lease {
+    pub key: String,
+    pub cluster_id: String,
+    pub instance_id: String,
+    /// Unix seconds.
+    pub leased_at: i64,
+}
+
+impl Lease {
+    fn to_item(&self, hash_key: &str) -> dynamodb::Item {
+        let s = |v: &str| AttributeValue::S(v.to_string());
+        HashMap::from([
+            (String::from(hash_key), s(&self.key)),
+            (String::from("cluster_id"), s(&self.cluster_id)),
+            (String::from("instance_id"), s(&self.instance_id)),
+            (
+                String::from("leased_at"),
+                AttributeValue::N(self.leased_at.to_string()),
+            ),
+        ])
+    }
+
+    fn from_item(hash_key: &str, item: &dynamodb::Item) -> Self {
+        let s = |k: &str| match item.get(k) {
+            Some(AttributeValue::S(v)) => v.clone(),
+            _ => String::new(),
+        };
+        Self {
+            key: s(hash_key),
+            cluster_id: s("cluster_id"),
+            instance_id: s("instance_id"),
+            leased_at: match item.get("leased_at") {
+                Some(AttributeValue::N(v)) => v.parse::<i64>().unwrap_or_default(),
+                _ => 0,
+            },
+        }
+    }
+}
+
+/// Implements the instance leases with the DynamoDB conditional writes.
+/// The lease held by a terminated instance is taken over.
+#[derive(Debug, Clone)]
+pub struct Manager {
+    dynamodb_manager: dynamodb::Manager,
+    ec2_manager: ec2::Manager,
+    table_name: String,
+    hash_key: String,
+}
+
+impl Manager {
+    pub fn new(shared_config: &AwsSdkConfig, table_name: &str, hash_key: &str) -> Self {
+        Self {
+            dynamodb_manager: dynamodb::Manager::new(shared_config),
+            ec2_manager: ec2::Manager::new(shared_config),
+            table_name: table_name.to_string(),
+            hash_key: hash_key.to_string(),
+        }
+    }
+
+    pub async fn create_table_if_not_exists(&self) -> Result<()> {
+        self.dynamodb_manager
+            .create_table_if_not_exists(&self.table_name, &self.hash_key)
+            .await
+    }
+
+    /// Leases the first free key to the instance. The key leased by the instance
+    /// before (e.g., reboot), or held by a terminated instance, is taken over.
+    /// Returns "None" if all keys are leased by the live instances.
+    pub async fn acquire(
+        &self,
+        cluster_id: &str,
+        keys: &[String],
+        instance_id: &str,
+    ) -> Result<Option<String>> {
+        for key in keys.iter() {
+            let held = self
+                .dynamodb_manager
+                .get_item(&self.table_name, &self.hash_key, key)
+                .await?
+                .as_ref()
+                .map(|item| Lease::from_item(&self.hash_key, item));
+            let prev = match &held {
+                None => None,
+                Some(lease) if lease.instance_id == instance_id => Some(lease.instance_id.clone()),
+                Some(lease) => {
+                    if self.is_instance_alive(&lease.instance_id).await? {
+                        continue;
+                    }
+                    info!(
+                        "taking over '{}' from the terminated instance '{}'",
+                        key, lease.instance_id
+                    );
+                    Some(lease.instance_id.clone())
+                }
+            };
+
+            let lease = Lease {
+                key: key.clone(),
+                cluster_id: cluster_id.to_string(),
+                instance_id: instance_id.to_string(),
+                leased_at: Utc::now().timestamp(),
+            };
+            let acquired = match prev {
+                None => {
+                    self.dynamodb_manager
+                        .put_item_if(
+                            &self.table_name,
+                            lease.to_item(&self.hash_key),
+                            "attribute_not_exists(#id)",
+                            HashMap::from([(String::from("#id"), self.hash_key.clone())]),
+                            HashMap::new(),
+                        )
+                        .await?
+                }
+                Some(prev) => {
+                    self.dynamodb_manager
+                        .put_item_if(
+                            &self.table_name,
+                            lease.to_item(&self.hash_key),
+                            "#inst = :prev",
+                            HashMap::from([(String::from("#inst"), String::from("instance_id"))]),
+                            HashMap::from([(String::from(":prev"), AttributeValue::S(prev))]),
+                        )
+                        .await?
+                }
+            };
+            if !acquired {
+                info!("'{}' leased by another instance", key);
+                continue;
+            }
+            info!("leased '{}' to '{}'", key, instance_id);
+            return Ok(Some(key.clone()));
+        }
+        Ok(None)
+    }
+
+    /// Returns the current lease of the key, if any.
+    pub async fn get(&self, key: &str) -> Result<Option<Lease>> {
+        Ok(self
+            .dynamodb_manager
+            .get_item(&self.table_name, &self.hash_key, key)
+            .await?
+            .as_ref()
+            .map(|item| Lease::from_item(&self.hash_key, item)))
+    }
+
+    /// Deletes the leases regardless of the holders (e.g., on "delete").
+    pub async fn delete(&self, keys: &[String]) -> Result<()> {
+        for key in keys.iter() {
+            self.dynamodb_manager
+                .delete_item_if(&self.table_name, &self.hash_key, key, None)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn is_instance_alive(&self, instance_id: &str) -> Result<bool> {
+        match self.ec2_manager.describe_instance(instance_id).await {
+            Ok(inst) => Ok(!matches!(
+                inst.state.and_then(|s| s.name),
+                Some(InstanceStateName::ShuttingDown) | Some(InstanceStateName::Terminated)
+            )),
+            Err(Other { .. }) => Ok(false),
+            Err(e) if e.is_not_found() => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[test]
+fn test_lease() {
+    let lease = Lease {
+        key: String::from("eipalloc-1"),
+        cluster_id: String::from("test"),
+        instance_id: String::from("i-1"),
+        leased_at: 1,
+    };
+    let item = lease.to_item("allocation_id");
+    assert_eq!(
+        item.get("allocation_id"),
+        Some(&AttributeValue::S(String::from("eipalloc-1")))
+    );
+    assert_eq!(Lease::from_item("allocation_id", &item), lease);
+}
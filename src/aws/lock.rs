@@ -0,0 +1,365 @@
+use std::{
+    collections::HashMap,
+    env, fmt,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use aws_sdk_dynamodb::model::AttributeValue;
+use aws_types::SdkConfig as AwsSdkConfig;
+use chrono::{TimeZone, Utc};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use crate::{
+    aws::{dynamodb, operation},
+    errors::{Error::Other, Result},
+    utils::random,
+};
+
+/// Table of the cluster locks, shared by all clusters in the region.
+/// Created on the first lock, and never deleted by "delete".
+pub const TABLE_NAME: &str = "avalanche-ops-locks";
+const HASH_KEY: &str = "cluster_id";
+
+/// The guard extends the lock every third of the TTL while held,
+/// so the lock left by a crashed process expires on its own soon.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Overrides "DEFAULT_TTL" in seconds.
+pub const TTL_SECONDS_ENV: &str = "AVALANCHE_OPS_LOCK_TTL_SECONDS";
+
+/// Represents the exclusive lock of a cluster,
+/// held by the operation that mutates the cluster resources.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Lock {
+    pub cluster_id: String,
+    pub owner: String,
+    pub operation_id: String,
+    pub command: String,
+    /// Random token to release only the lock this process acquired.
+    pub token: String,
+    /// Unix seconds.
+    pub acquired_at: i64,
+    pub expires_at: i64,
+}
+
+impl fmt::Display for Lock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cluster '{}' locked by '{}' (operation '{}', command '{}') since {} until {}",
+            self.cluster_id,
+            self.owner,
+            self.operation_id,
+            self.command,
+            rfc3339(self.acquired_at),
+            rfc3339(self.expires_at),
+        )
+    }
+}
+
+/// Formats the unix seconds, or the raw number if out of range.
+fn rfc3339(unix_seconds: i64) -> String {
+    match Utc.timestamp_opt(unix_seconds, 0).single() {
+        Some(t) => t.to_rfc3339(),
+        None => unix_seconds.to_string(),
+    }
+}
+
+impl Lock {
+    fn new(cluster_id: &str, ttl: Duration) -> Self {
+        let op = operation::current().unwrap_or_else(|| operation::Operation::new(""));
+        let now = Utc::now().timestamp();
+        Self {
+            cluster_id: cluster_id.to_string(),
+            owner: op.operator,
+            operation_id: op.id,
+            command: op.command,
+            token: random::string(32),
+            acquired_at: now,
+            expires_at: now + ttl.as_secs() as i64,
+        }
+    }
+
+    fn to_item(&self) -> dynamodb::Item {
+        let s = |v: &str| AttributeValue::S(v.to_string());
+        let n = |v: i64| AttributeValue::N(v.to_string());
+        HashMap::from([
+            (String::from(HASH_KEY), s(&self.cluster_id)),
+            (String::from("owner"), s(&self.owner)),
+            (String::from("operation_id"), s(&self.operation_id)),
+            (String::from("command"), s(&self.command)),
+            (String::from("lock_token"), s(&self.token)),
+            (String::from("acquired_at"), n(self.acquired_at)),
+            (String::from("expires_at"), n(self.expires_at)),
+        ])
+    }
+
+    fn from_item(item: &dynamodb::Item) -> Self {
+        let s = |k: &str| match item.get(k) {
+            Some(AttributeValue::S(v)) => v.clone(),
+            _ => String::new(),
+        };
+        let n = |k: &str| match item.get(k) {
+            Some(AttributeValue::N(v)) => v.parse::<i64>().unwrap_or_default(),
+            _ => 0,
+        };
+        Self {
+            cluster_id: s(HASH_KEY),
+            owner: s("owner"),
+            operation_id: s("operation_id"),
+            command: s("command"),
+            token: s("lock_token"),
+            acquired_at: n("acquired_at"),
+            expires_at: n("expires_at"),
+        }
+    }
+}
+
+/// Implements the cluster lock with the DynamoDB conditional writes.
+#[derive(Debug, Clone)]
+pub struct Manager {
+    dynamodb_manager: dynamodb::Manager,
+}
+
+impl Manager {
+    pub fn new(shared_config: &AwsSdkConfig) -> Self {
+        Self {
+            dynamodb_manager: dynamodb::Manager::new(shared_config),
+        }
+    }
+
+    /// Acquires the lock of the cluster, unless held by another operation.
+    /// The expired lock is taken over.
+    pub async fn acquire(&self, cluster_id: &str, ttl: Duration) -> Result<Lock> {
+        self.dynamodb_manager
+            .create_table_if_not_exists(TABLE_NAME, HASH_KEY)
+            .await?;
+
+        let lock = Lock::new(cluster_id, ttl);
+        info!("acquiring lock for cluster '{}'", cluster_id);
+        let acquired = self
+            .dynamodb_manager
+            .put_item_if(
+                TABLE_NAME,
+                lock.to_item(),
+                "attribute_not_exists(#id) OR #exp < :now",
+                HashMap::from([
+                    (String::from("#id"), String::from(HASH_KEY)),
+                    (String::from("#exp"), String::from("expires_at")),
+                ]),
+                HashMap::from([(
+                    String::from(":now"),
+                    AttributeValue::N(lock.acquired_at.to_string()),
+                )]),
+            )
+            .await?;
+        if acquired {
+            info!("acquired lock (operation '{}')", lock.operation_id);
+            return Ok(lock);
+        }
+
+        let msg = match self.get(cluster_id).await? {
+            Some(held) => format!("{}; retry later, or 'unlock' if stale", held),
+            None => format!(
+                "cluster '{}' lock released while acquiring; retry",
+                cluster_id
+            ),
+        };
+        Err(Other {
+            message: msg,
+            is_retryable: true,
+        })
+    }
+
+    /// Extends the lock to "ttl" from now, only if still held by the same acquirer.
+    /// Returns false if the lock was taken over (expired or unlocked).
+    pub async fn renew(&self, lock: &Lock, ttl: Duration) -> Result<bool> {
+        let mut renewed = lock.clone();
+        renewed.expires_at = Utc::now().timestamp() + ttl.as_secs() as i64;
+        self.dynamodb_manager
+            .put_item_if(
+                TABLE_NAME,
+                renewed.to_item(),
+                "#tok = :tok",
+                HashMap::from([(String::from("#tok"), String::from("lock_token"))]),
+                HashMap::from([(String::from(":tok"), AttributeValue::S(lock.token.clone()))]),
+            )
+            .await
+    }
+
+    /// Releases the lock, only if still held by the same acquirer.
+    pub async fn release(&self, lock: &Lock) -> Result<()> {
+        info!("releasing lock for cluster '{}'", lock.cluster_id);
+        let released = self
+            .dynamodb_manager
+            .delete_item_if(
+                TABLE_NAME,
+                HASH_KEY,
+                &lock.cluster_id,
+                Some((
+                    "#tok = :tok",
+                    HashMap::from([(String::from("#tok"), String::from("lock_token"))]),
+                    HashMap::from([(String::from(":tok"), AttributeValue::S(lock.token.clone()))]),
+                )),
+            )
+            .await?;
+        if !released {
+            warn!(
+                "lock for cluster '{}' was taken over (expired or unlocked)",
+                lock.cluster_id
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the current lock of the cluster, if any.
+    pub async fn get(&self, cluster_id: &str) -> Result<Option<Lock>> {
+        let item = self
+            .dynamodb_manager
+            .get_item(TABLE_NAME, HASH_KEY, cluster_id)
+            .await;
+        match item {
+            Ok(item) => Ok(item.as_ref().map(Lock::from_item)),
+            // table not created yet
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Deletes the lock regardless of the holder (e.g., crashed process).
+    pub async fn force_release(&self, cluster_id: &str) -> Result<()> {
+        warn!("force-releasing lock for cluster '{}'", cluster_id);
+        self.dynamodb_manager
+            .delete_item_if(TABLE_NAME, HASH_KEY, cluster_id, None)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Holds the lock until dropped, so the lock is released
+/// on every return path and on panics. The lock is extended
+/// in the background, so the long operations keep the lock.
+pub struct Guard {
+    shared_config: AwsSdkConfig,
+    lock: Lock,
+    /// Stops the heartbeat once dropped.
+    stop_heartbeat: Option<mpsc::Sender<()>>,
+    heartbeat: Option<thread::JoinHandle<()>>,
+}
+
+impl Guard {
+    /// Acquires the lock with the TTL from "AVALANCHE_OPS_LOCK_TTL_SECONDS"
+    /// (or "DEFAULT_TTL").
+    pub async fn acquire(shared_config: &AwsSdkConfig, cluster_id: &str) -> Result<Self> {
+        let ttl = env::var(TTL_SECONDS_ENV)
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TTL);
+        let lock = Manager::new(shared_config).acquire(cluster_id, ttl).await?;
+
+        let (stop_heartbeat, stopped) = mpsc::channel();
+        let heartbeat = spawn_heartbeat(shared_config.clone(), lock.clone(), ttl, stopped);
+        Ok(Self {
+            shared_config: shared_config.clone(),
+            lock,
+            stop_heartbeat: Some(stop_heartbeat),
+            heartbeat: Some(heartbeat),
+        })
+    }
+
+    pub fn lock(&self) -> &Lock {
+        &self.lock
+    }
+}
+
+/// Extends the lock every third of the TTL on a separate thread with
+/// its own runtime, since the guard may be held within the blocking calls.
+fn spawn_heartbeat(
+    shared_config: AwsSdkConfig,
+    lock: Lock,
+    ttl: Duration,
+    stopped: mpsc::Receiver<()>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let rt = match Runtime::new() {
+            Ok(rt) => rt,
+            Err(e) => {
+                warn!("failed Runtime::new {}, lock not extended", e);
+                return;
+            }
+        };
+        let manager = Manager::new(&shared_config);
+        let interval = std::cmp::max(ttl / 3, Duration::from_secs(1));
+        loop {
+            match stopped.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => {}
+                // guard dropped
+                _ => return,
+            }
+            match rt.block_on(manager.renew(&lock, ttl)) {
+                Ok(true) => info!("extended lock for cluster '{}'", lock.cluster_id),
+                Ok(false) => {
+                    warn!(
+                        "lock for cluster '{}' was taken over (expired or unlocked), no longer held",
+                        lock.cluster_id
+                    );
+                    return;
+                }
+                // retried at the next interval, before the lock expires
+                Err(e) => warn!("failed to extend lock ({})", e.message()),
+            }
+        }
+    })
+}
+
+impl Drop for Guard {
+    /// Releases on a separate thread with its own runtime,
+    /// since the guard may be dropped within the async contexts.
+    fn drop(&mut self) {
+        // stops the heartbeat first, not to extend the released lock
+        drop(self.stop_heartbeat.take());
+        if let Some(heartbeat) = self.heartbeat.take() {
+            let _ = heartbeat.join();
+        }
+
+        let shared_config = self.shared_config.clone();
+        let lock = self.lock.clone();
+        let ret = thread::spawn(move || -> Result<()> {
+            let rt = Runtime::new().map_err(|e| Other {
+                message: format!("failed Runtime::new {}", e),
+                is_retryable: false,
+            })?;
+            rt.block_on(Manager::new(&shared_config).release(&lock))
+        })
+        .join();
+        match ret {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!(
+                "failed to release lock ({}), expires on its own",
+                e.message()
+            ),
+            Err(_) => warn!("lock release thread panicked, expires on its own"),
+        }
+    }
+}
+
+#[test]
+fn test_lock() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let lock = Lock::new("test", Duration::from_secs(60));
+    assert_eq!(lock.expires_at - lock.acquired_at, 60);
+    assert_eq!(lock.token.len(), 32);
+
+    let item = lock.to_item();
+    assert_eq!(
+        item.get(HASH_KEY),
+        Some(&AttributeValue::S(String::from("test")))
+    );
+    assert_eq!(Lock::from_item(&item), lock);
+    assert!(lock.to_string().contains("cluster 'test' locked by"));
+}
@@ -1,4 +1,7 @@
-use std::io;
+use std::{
+    env,
+    io::{self, Error, ErrorKind},
+};
 
 use aws_config::{self, meta::region::RegionProviderChain};
 use aws_sdk_ec2::Region;
@@ -6,23 +9,63 @@ use aws_types::SdkConfig as AwsSdkConfig;
 use log::info;
 use serde::{Deserialize, Serialize};
 
+pub mod auditor;
+pub mod budget;
+pub mod cli;
 pub mod cloudformation;
 pub mod cloudwatch;
+pub mod dynamodb;
 pub mod ec2;
+pub mod eip;
+pub mod elbv2;
 pub mod envelope;
 pub mod kms;
+pub mod lease;
+pub mod lock;
+pub mod operation;
+pub mod route53;
 pub mod s3;
+pub mod savingsplans;
+pub mod servicequotas;
+pub mod ssm;
 pub mod sts;
+pub mod vpc;
 
 /// Loads an AWS config from default environments.
+/// If the operation is set for the current process and "AVALANCHE_OPS_ASSUME_ROLE_ARN"
+/// is non-empty, the config uses the role session tagged with the operation.
 pub async fn load_config(reg: Option<String>) -> io::Result<AwsSdkConfig> {
     info!("loading AWS configuration for region {:?}", reg);
-    let regp = RegionProviderChain::first_try(reg.map(Region::new))
+    let regp = RegionProviderChain::first_try(reg.clone().map(Region::new))
         .or_default_provider()
         .or_else(Region::new("us-west-2"));
 
     let shared_config = aws_config::from_env().region(regp).load().await;
-    Ok(shared_config)
+
+    let op = match operation::current() {
+        Some(op) => op,
+        None => return Ok(shared_config),
+    };
+    info!(
+        "operation ID '{}' (operator '{}', command '{}')",
+        op.id, op.operator, op.command
+    );
+    let role_arn = env::var(operation::ASSUME_ROLE_ARN_ENV).unwrap_or_default();
+    if role_arn.is_empty() {
+        return Ok(shared_config);
+    }
+
+    let creds = operation::assume_role(&shared_config, &role_arn, &op)
+        .await
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    let regp = RegionProviderChain::first_try(reg.map(Region::new))
+        .or_default_provider()
+        .or_else(Region::new("us-west-2"));
+    Ok(aws_config::from_env()
+        .region(regp)
+        .credentials_provider(creds)
+        .load()
+        .await)
 }
 
 /// Represents the current AWS resource status.
@@ -46,6 +89,10 @@ pub struct Resources {
     /// MUST BE NON-EMPTY.
     #[serde(default)]
     pub s3_bucket: String,
+    /// Lifecycle rules for the objects in "s3_bucket" (e.g., backups retention).
+    /// None to not create any lifecycle rule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s3_lifecycle: Option<s3::Lifecycle>,
 
     /// Region for s3 where database backup resides.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -57,6 +104,27 @@ pub struct Resources {
     /// Non-empty to download the database for bootstrapping.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub db_backup_s3_key: Option<String>,
+    /// Buckets with the same database backup key (e.g., in the other regions),
+    /// tried in order if the download from "db_backup_s3_bucket"
+    /// (and the replica bucket, if any) fails.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub db_backup_s3_mirrors: Option<Vec<s3::Mirror>>,
+    /// Bandwidth limits of the S3 transfers from "avalanched" per node class
+    /// (e.g., database backup uploads and downloads).
+    /// None for no limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s3_transfer_limits: Option<s3::TransferLimits>,
+
+    /// Region of the secondary bucket to replicate the install artifacts
+    /// and database backups to. Must be different from "region".
+    /// If the primary backup region is unavailable, "avalanched" falls back
+    /// to the replica bucket to download the database backup.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replica_s3_region: Option<String>,
+    /// Secondary bucket in "replica_s3_region".
+    /// If not exists, it creates automatically.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replica_s3_bucket: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub instance_system_logs: Option<bool>,
@@ -67,6 +135,87 @@ pub struct Resources {
     /// NON-EMPTY TO ENABLE HTTPS over NLB.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub nlb_acm_certificate_arn: Option<String>,
+    /// Health check and deregistration settings of the NLB target group.
+    /// Only applied when the NLB is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nlb_target_group: Option<elbv2::TargetGroup>,
+    /// Set "true" to spread the NLB traffic evenly across the nodes in all zones,
+    /// rather than across the nodes in the zone of the NLB node.
+    /// Only applied when the NLB is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nlb_cross_zone_load_balancing: Option<bool>,
+    /// Seconds (60-6000) before the NLB closes the idle TCP connections
+    /// (e.g., websocket subscriptions without traffic). None for 350 seconds.
+    /// The TLS listener ("nlb_acm_certificate_arn") is fixed at 350 seconds.
+    /// Only applied when the NLB is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nlb_tcp_idle_timeout_seconds: Option<u32>,
+    /// Set "true" to deliver the NLB access logs to the S3 bucket
+    /// (see "rpc-usage"). The NLB only logs the TLS listener connections,
+    /// so requires "nlb_acm_certificate_arn".
+    /// Only applied when the NLB is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nlb_access_logs: Option<bool>,
+
+    /// Threshold of the per node kind alarm on the max "clock_offset_ms"
+    /// that "avalanched" publishes. None for 100 milliseconds.
+    /// Only applied when the ASG is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clock_offset_alarm_threshold_ms: Option<u32>,
+
+    /// VPC peering connection or transit gateway attachment from
+    /// an existing VPC, for the private access to the node HTTP RPC.
+    /// Only applied when the VPC is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_connectivity: Option<vpc::PrivateConnectivity>,
+    /// VPC endpoint service (PrivateLink) on the NLB for the other AWS accounts.
+    /// Removing it deletes the service on the next "apply".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nlb_endpoint_service: Option<vpc::EndpointService>,
+    /// Set "true" to create the S3 gateway endpoint, and the KMS, CloudWatch,
+    /// and SSM interface endpoints in the VPC, to keep the control-plane
+    /// traffic off the internet (and NAT). The interface endpoints are
+    /// charged hourly per availability zone.
+    /// Only applied when the VPC is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vpc_endpoints: Option<bool>,
+    /// Service ID of "nlb_endpoint_service".
+    /// READ ONLY -- DO NOT SET.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nlb_endpoint_service_id: Option<String>,
+    /// Service name of "nlb_endpoint_service", for the consumers to create the endpoints.
+    /// READ ONLY -- DO NOT SET.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nlb_endpoint_service_name: Option<String>,
+
+    /// Elastic IP addresses for the non-anchor nodes, leased at boot,
+    /// so the replaced instances keep the same public IPs.
+    /// "apply" allocates the missing addresses on every run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elastic_ip_pool: Option<eip::Pool>,
+    /// Allocated addresses of "elastic_ip_pool".
+    /// READ ONLY -- DO NOT SET.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elastic_ip_pool_addresses: Option<Vec<eip::Address>>,
+
+    /// Monthly budget of the cluster, with the alerts and the optional
+    /// scale-down of the non-anchor nodes.
+    /// "apply" creates the budget stack after the non-anchor nodes,
+    /// and updates it on every run. Removing it deletes the stack.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget: Option<budget::Budget>,
+    /// Read-only IAM role for the auditors and the on-call responders.
+    /// "apply" creates the role stack after the non-anchor nodes,
+    /// and updates it on every run. Removing it deletes the stack.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auditor: Option<auditor::Auditor>,
+
+    /// True if the cluster was created in the FIPS mode (see "fips").
+    /// Subsequent runs must use the same mode.
+    /// None if not applied yet.
+    /// READ ONLY -- DO NOT SET.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fips: Option<bool>,
 
     /// KMS CMK ID to encrypt resources.
     /// None if not created yet.
@@ -82,7 +231,13 @@ pub struct Resources {
     /// READ ONLY -- DO NOT SET.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ec2_key_name: Option<String>,
+    /// OpenSSH public key (e.g., "ssh-ed25519 AAAA... ops@example.com")
+    /// to import as the EC2 key pair, instead of generating a new private key.
+    /// Use "${ssm:...}" to read the centrally-issued key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ec2_key_import_public_key: Option<String>,
     /// Only updated after creation.
+    /// None if "ec2_key_import_public_key" is set.
     /// READ ONLY -- DO NOT SET.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ec2_key_path: Option<String>,
@@ -116,6 +271,12 @@ pub struct Resources {
     /// READ ONLY -- DO NOT SET.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cloudformation_vpc_public_subnet_ids: Option<Vec<String>>,
+    /// VPC peering connection ID, or transit gateway attachment ID
+    /// from "cloudformation_vpc", with "private_connectivity".
+    /// Only updated after creation.
+    /// READ ONLY -- DO NOT SET.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cloudformation_vpc_private_connection_id: Option<String>,
 
     /// CloudFormation stack name of Auto Scaling Group (ASG)
     /// for anchor nodes.
@@ -138,6 +299,16 @@ pub struct Resources {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cloudformation_asg_non_anchor_nodes_logical_id: Option<String>,
 
+    /// CloudFormation stack name of Auto Scaling Group (ASG)
+    /// for the ICM relayer (if "icm_relayer" is set).
+    /// READ ONLY -- DO NOT SET.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cloudformation_asg_icm_relayer: Option<String>,
+    /// Only updated after creation.
+    /// READ ONLY -- DO NOT SET.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cloudformation_asg_icm_relayer_logical_id: Option<String>,
+
     /// Only updated after creation.
     /// READ ONLY -- DO NOT SET.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -151,8 +322,41 @@ pub struct Resources {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cloudformation_asg_nlb_dns_name: Option<String>,
 
+    /// CloudFormation stack name of the budget.
+    /// None if "budget" is not set.
+    /// READ ONLY -- DO NOT SET.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cloudformation_budget: Option<String>,
+    /// SNS topic ARN of the budget alerts, for the other subscribers (e.g., Slack).
+    /// Only updated after creation.
+    /// READ ONLY -- DO NOT SET.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cloudformation_budget_alert_topic_arn: Option<String>,
+    /// CloudFormation stack name of the auditor role.
+    /// None if "auditor" is not set.
+    /// READ ONLY -- DO NOT SET.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cloudformation_auditor_role: Option<String>,
+    /// ARN of the read-only auditor role to assume.
+    /// Only updated after creation.
+    /// READ ONLY -- DO NOT SET.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cloudformation_auditor_role_arn: Option<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cloudwatch_avalanche_metrics_namespace: Option<String>,
+    /// Metric schema version of the node metrics (see "metric_schema").
+    /// None for the clusters created before the schema version 2,
+    /// whose metrics have no cluster, node, and zone dimensions.
+    /// Set to 2 to migrate an existing cluster to the per-cluster namespace,
+    /// which keeps publishing to the old namespace (see below).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cloudwatch_metrics_schema_version: Option<u32>,
+    /// Namespace to keep publishing the metrics of the schema version 1 to,
+    /// so the existing dashboards and alarms keep working during the migration.
+    /// Set by "apply" on the migration. Remove once the dashboards are updated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cloudwatch_metrics_legacy_namespace: Option<String>,
 }
 
 impl Default for Resources {
@@ -168,20 +372,46 @@ impl Resources {
             region: String::from("us-west-2"),
 
             s3_bucket: String::from(""),
+            s3_lifecycle: None,
 
             db_backup_s3_region: None,
             db_backup_s3_bucket: None,
             db_backup_s3_key: None,
+            db_backup_s3_mirrors: None,
+            s3_transfer_limits: None,
+
+            replica_s3_region: None,
+            replica_s3_bucket: None,
 
             instance_system_logs: Some(true),
             instance_system_metrics: Some(true),
 
             nlb_acm_certificate_arn: None,
+            nlb_target_group: None,
+            nlb_cross_zone_load_balancing: None,
+            nlb_tcp_idle_timeout_seconds: None,
+            nlb_access_logs: None,
+
+            clock_offset_alarm_threshold_ms: None,
+
+            private_connectivity: None,
+            nlb_endpoint_service: None,
+            nlb_endpoint_service_id: None,
+            nlb_endpoint_service_name: None,
+            vpc_endpoints: None,
+
+            elastic_ip_pool: None,
+            elastic_ip_pool_addresses: None,
+            budget: None,
+            auditor: None,
+
+            fips: None,
 
             kms_cmk_id: None,
             kms_cmk_arn: None,
 
             ec2_key_name: None,
+            ec2_key_import_public_key: None,
             ec2_key_path: None,
 
             cloudformation_ec2_instance_role: None,
@@ -191,6 +421,7 @@ impl Resources {
             cloudformation_vpc_id: None,
             cloudformation_vpc_security_group_id: None,
             cloudformation_vpc_public_subnet_ids: None,
+            cloudformation_vpc_private_connection_id: None,
 
             cloudformation_asg_anchor_nodes: None,
             cloudformation_asg_anchor_nodes_logical_id: None,
@@ -198,11 +429,20 @@ impl Resources {
             cloudformation_asg_non_anchor_nodes: None,
             cloudformation_asg_non_anchor_nodes_logical_id: None,
 
+            cloudformation_asg_icm_relayer: None,
+            cloudformation_asg_icm_relayer_logical_id: None,
+
             cloudformation_asg_nlb_arn: None,
             cloudformation_asg_nlb_target_group_arn: None,
             cloudformation_asg_nlb_dns_name: None,
+            cloudformation_budget: None,
+            cloudformation_budget_alert_topic_arn: None,
+            cloudformation_auditor_role: None,
+            cloudformation_auditor_role_arn: None,
 
             cloudwatch_avalanche_metrics_namespace: None,
+            cloudwatch_metrics_schema_version: None,
+            cloudwatch_metrics_legacy_namespace: None,
         }
     }
 }
@@ -0,0 +1,161 @@
+use std::{env, sync::RwLock};
+
+use aws_sdk_cloudformation::model::Tag;
+use aws_sdk_sts::{model, Client};
+use aws_types::{Credentials, SdkConfig as AwsSdkConfig};
+use lazy_static::lazy_static;
+use log::info;
+
+use crate::{
+    errors::{Error::API, Result},
+    utils::{random, time},
+};
+
+/// Overrides the generated operation ID (e.g., CI run ID).
+pub const OPERATION_ID_ENV: &str = "AVALANCHE_OPS_OPERATION_ID";
+
+/// Non-empty to assume the role with the operation session tags
+/// for all AWS API calls, so that CloudTrail records the operation.
+pub const ASSUME_ROLE_ARN_ENV: &str = "AVALANCHE_OPS_ASSUME_ROLE_ARN";
+
+/// Prefix of the role session names, so the callers can be compared across operations.
+pub const SESSION_NAME_PREFIX: &str = "avalanche-ops-";
+
+pub const TAG_OPERATION_ID: &str = "avalanche-ops:operation-id";
+pub const TAG_OPERATOR: &str = "avalanche-ops:operator";
+pub const TAG_COMMAND: &str = "avalanche-ops:command";
+
+lazy_static! {
+    static ref CURRENT: RwLock<Option<Operation>> = RwLock::new(None);
+}
+
+/// Represents a single CLI invocation that mutates AWS resources.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Operation {
+    pub id: String,
+    pub operator: String,
+    pub command: String,
+}
+
+impl Operation {
+    pub fn new(command: &str) -> Self {
+        let id = match env::var(OPERATION_ID_ENV) {
+            Ok(v) if !v.is_empty() => sanitize(&v),
+            _ => format!("op-{}-{}", time::get(12), random::string(8)),
+        };
+        Self {
+            id,
+            operator: sanitize(&whoami::username()),
+            command: sanitize(command),
+        }
+    }
+
+    /// Returns the key-value pairs to tag the sessions and resources with.
+    pub fn tags(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (TAG_OPERATION_ID, self.id.clone()),
+            (TAG_OPERATOR, self.operator.clone()),
+            (TAG_COMMAND, self.command.clone()),
+        ]
+    }
+
+    /// Returns the CloudFormation stack tags, which propagate to the stack resources.
+    pub fn stack_tags(&self) -> Vec<Tag> {
+        self.tags()
+            .into_iter()
+            .map(|(k, v)| Tag::builder().key(k).value(v).build())
+            .collect()
+    }
+}
+
+/// Sets the operation for the current process, used by "aws::load_config".
+pub fn init(command: &str) -> Operation {
+    let op = Operation::new(command);
+    *CURRENT.write().unwrap() = Some(op.clone());
+    op
+}
+
+/// Returns the operation of the current process, if initialized.
+pub fn current() -> Option<Operation> {
+    CURRENT.read().unwrap().clone()
+}
+
+/// Returns the CloudFormation stack tags of the current operation, if any.
+pub fn current_stack_tags() -> Vec<Tag> {
+    current().map(|op| op.stack_tags()).unwrap_or_default()
+}
+
+/// Assumes the role with the operation session tags, and returns
+/// the temporary credentials. The operation ID is used in the role session name,
+/// which appears in the CloudTrail "userIdentity" of every API call.
+/// The role trust policy must allow "sts:TagSession".
+pub async fn assume_role(
+    shared_config: &AwsSdkConfig,
+    role_arn: &str,
+    op: &Operation,
+) -> Result<Credentials> {
+    info!(
+        "assuming role '{}' for operation '{}' (operator '{}')",
+        role_arn, op.id, op.operator
+    );
+    let mut session_name = format!("{}{}", SESSION_NAME_PREFIX, op.id);
+    session_name.truncate(64);
+    let mut req = Client::new(shared_config)
+        .assume_role()
+        .role_arn(role_arn)
+        .role_session_name(session_name);
+    for (k, v) in op.tags() {
+        req = req.tags(model::Tag::builder().key(k).value(v).build());
+    }
+    let resp = req.send().await.map_err(|e| API {
+        message: format!("failed assume_role {:?}", e),
+        is_retryable: false,
+    })?;
+
+    let creds = resp.credentials().ok_or_else(|| API {
+        message: String::from("assume_role returned no credentials"),
+        is_retryable: false,
+    })?;
+    Ok(Credentials::new(
+        creds.access_key_id().unwrap_or_default(),
+        creds.secret_access_key().unwrap_or_default(),
+        creds.session_token().map(String::from),
+        None,
+        "avalanche-ops-operation",
+    ))
+}
+
+/// Keeps the characters allowed in both the STS role session name
+/// and the session tag values.
+fn sanitize(s: &str) -> String {
+    let mut sanitized: String = s
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "_=.@-".contains(c) {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    sanitized.truncate(64);
+    sanitized
+}
+
+#[test]
+fn test_operation() {
+    assert_eq!(sanitize("ci user/ops,1"), "ci-user-ops-1");
+    assert_eq!(sanitize(&"a".repeat(100)).len(), 64);
+
+    assert!(current().is_none());
+    assert!(current_stack_tags().is_empty());
+
+    let op = init("apply");
+    assert!(op.id.starts_with("op-"));
+    assert_eq!(current(), Some(op.clone()));
+    let tags = current_stack_tags();
+    assert_eq!(tags.len(), 3);
+    assert_eq!(tags[0].key(), Some(TAG_OPERATION_ID));
+    assert_eq!(tags[0].value(), Some(op.id.as_str()));
+    assert_eq!(tags[2].value(), Some("apply"));
+}
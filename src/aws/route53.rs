@@ -0,0 +1,96 @@
+use std::io::{self, Error, ErrorKind};
+
+use log::info;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::aws::cli;
+
+/// Default TTL of the records, short enough to fail over (and back) quickly.
+pub const DEFAULT_TTL_SECONDS: u32 = 60;
+
+/// Creates or updates the CNAME record to the target DNS name (e.g., the NLB),
+/// and returns the change ID.
+/// Runs the AWS CLI (see "cli::run").
+/// ref. https://docs.aws.amazon.com/cli/latest/reference/route53/change-resource-record-sets.html
+pub fn upsert_cname(
+    hosted_zone_id: &str,
+    record_name: &str,
+    target: &str,
+    ttl: u32,
+) -> io::Result<String> {
+    info!(
+        "upserting CNAME '{}' to '{}' in hosted zone '{}'",
+        record_name, target, hosted_zone_id
+    );
+    let out = cli::run(&[
+        "route53",
+        "change-resource-record-sets",
+        "--hosted-zone-id",
+        hosted_zone_id,
+        "--change-batch",
+        &upsert_cname_change_batch(record_name, target, ttl),
+        "--output",
+        "json",
+    ])?;
+    let resp: ChangeResourceRecordSets = serde_json::from_str(&out)
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("invalid JSON: {}", e)))?;
+    info!(
+        "upserted '{}' (change '{}', status '{}')",
+        record_name, resp.change_info.id, resp.change_info.status
+    );
+    Ok(resp.change_info.id)
+}
+
+fn upsert_cname_change_batch(record_name: &str, target: &str, ttl: u32) -> String {
+    json!({
+        "Comment": "avalanche-ops failover",
+        "Changes": [{
+            "Action": "UPSERT",
+            "ResourceRecordSet": {
+                "Name": record_name,
+                "Type": "CNAME",
+                "TTL": ttl,
+                "ResourceRecords": [{ "Value": target }],
+            },
+        }],
+    })
+    .to_string()
+}
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "PascalCase")]
+struct ChangeResourceRecordSets {
+    change_info: ChangeInfo,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "PascalCase")]
+struct ChangeInfo {
+    id: String,
+    status: String,
+}
+
+#[test]
+fn test_route53() {
+    let batch: serde_json::Value = serde_json::from_str(&upsert_cname_change_batch(
+        "rpc.example.com",
+        "my-nlb-123.elb.us-east-1.amazonaws.com",
+        60,
+    ))
+    .unwrap();
+    let set = &batch["Changes"][0]["ResourceRecordSet"];
+    assert_eq!(batch["Changes"][0]["Action"], "UPSERT");
+    assert_eq!(set["Name"], "rpc.example.com");
+    assert_eq!(set["TTL"], 60);
+    assert_eq!(
+        set["ResourceRecords"][0]["Value"],
+        "my-nlb-123.elb.us-east-1.amazonaws.com"
+    );
+
+    let resp: ChangeResourceRecordSets = serde_json::from_str(
+        r#"{"ChangeInfo":{"Id":"/change/C123","Status":"PENDING","SubmittedAt":"2022-10-01T00:00:00Z"}}"#,
+    )
+    .unwrap();
+    assert_eq!(resp.change_info.id, "/change/C123");
+}
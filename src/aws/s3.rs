@@ -1,34 +1,313 @@
-use std::{fs, path::Path, sync::Arc};
+use std::{collections::HashMap, fs, io, path::Path, sync::Arc, time::Duration};
 
 use aws_sdk_s3::{
-    error::{CreateBucketError, CreateBucketErrorKind, DeleteBucketError},
+    error::{
+        CreateBucketError, CreateBucketErrorKind, DeleteBucketError,
+        GetBucketLifecycleConfigurationError, GetObjectError, HeadObjectError,
+    },
     model::{
-        BucketCannedAcl, BucketLocationConstraint, CreateBucketConfiguration, Delete, Object,
-        ObjectCannedAcl, ObjectIdentifier, PublicAccessBlockConfiguration, ServerSideEncryption,
-        ServerSideEncryptionByDefault, ServerSideEncryptionConfiguration, ServerSideEncryptionRule,
+        AbortIncompleteMultipartUpload, BucketCannedAcl, BucketLifecycleConfiguration,
+        BucketLocationConstraint, BucketVersioningStatus, CompletedMultipartUpload, CompletedPart,
+        CreateBucketConfiguration, Delete, ExpirationStatus, LifecycleExpiration, LifecycleRule,
+        LifecycleRuleFilter, NoncurrentVersionExpiration, Object, ObjectCannedAcl,
+        ObjectIdentifier, ObjectVersion, Payer, PublicAccessBlockConfiguration,
+        RequestPaymentConfiguration, ServerSideEncryption, ServerSideEncryptionByDefault,
+        ServerSideEncryptionConfiguration, ServerSideEncryptionRule, Transition,
+        TransitionStorageClass, VersioningConfiguration,
     },
+    presigning::config::PresigningConfig,
     types::{ByteStream, SdkError},
     Client,
 };
 use aws_types::SdkConfig as AwsSdkConfig;
 use log::{debug, info, warn};
-use tokio::{fs::File, io::AsyncWriteExt};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncWriteExt},
+};
 use tokio_stream::StreamExt;
 
 use crate::{
+    avalanche::node,
     errors::{
-        Error::{Other, API},
+        from_sdk,
+        Error::{self, Other, API},
         Result,
     },
-    utils::humanize,
+    utils::{humanize, rate_limit::RateLimiter},
 };
 
+/// Consecutive failed attempts without progress before "get_object_resumable" gives up.
+pub const DEFAULT_DOWNLOAD_MAX_ATTEMPTS: u32 = 8;
+
+/// Extension of the file recording the ETag of the partially downloaded object.
+pub const DOWNLOAD_ETAG_FILE_EXT: &str = ".download-etag";
+
+/// Represents an S3 bucket that mirrors the objects with the same keys
+/// (e.g., the database backups copied to another region).
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Mirror {
+    pub region: String,
+    pub bucket: String,
+}
+
+/// Represents who owns the bucket name, which is global across the accounts.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum BucketOwnership {
+    /// Exists and accessible by the caller (e.g., created by the previous "apply").
+    Owned,
+    /// Does not exist, available to create.
+    Available,
+    /// Exists in the other account.
+    OwnedByOther,
+}
+
+/// Returns the offset to resume the download from.
+/// Restarts from zero if the partial file is of a different object,
+/// or larger than the object.
+pub fn resume_offset(existing_len: u64, object_size: u64, same_object: bool) -> u64 {
+    if same_object && existing_len <= object_size {
+        existing_len
+    } else {
+        0
+    }
+}
+
+#[test]
+fn test_resume_offset() {
+    assert_eq!(resume_offset(0, 100, true), 0);
+    assert_eq!(resume_offset(40, 100, true), 40);
+    assert_eq!(resume_offset(100, 100, true), 100);
+    // overwritten object
+    assert_eq!(resume_offset(40, 100, false), 0);
+    assert_eq!(resume_offset(140, 100, true), 0);
+}
+
+/// Minimum transfer limit, to not stall the bootstrap and the backups.
+pub const MIN_TRANSFER_BYTES_PER_SECOND: u64 = 1024 * 1024;
+
+/// Represents the bandwidth limits of the S3 transfers from a node.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct TransferLimit {
+    /// Average upload throughput limit (e.g., database backups).
+    /// None for no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_bytes_per_second: Option<u64>,
+    /// Average download throughput limit (e.g., database backups).
+    /// None for no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_bytes_per_second: Option<u64>,
+}
+
+/// Represents the S3 transfer limits per node class, so the database backup
+/// transfers on "avalanched" do not saturate the instance network
+/// and delay the consensus messages.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct TransferLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor_nodes: Option<TransferLimit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub non_anchor_nodes: Option<TransferLimit>,
+}
+
+impl TransferLimits {
+    /// Returns the limit of the node class, or no limit if unset.
+    pub fn node_limit(&self, kind: &node::Kind) -> TransferLimit {
+        match kind {
+            node::Kind::Anchor => self.anchor_nodes.clone(),
+            node::Kind::NonAnchor => self.non_anchor_nodes.clone(),
+        }
+        .unwrap_or_default()
+    }
+
+    pub fn validate(&self) -> io::Result<()> {
+        for (class, limit) in [
+            ("anchor_nodes", &self.anchor_nodes),
+            ("non_anchor_nodes", &self.non_anchor_nodes),
+        ] {
+            let limit = match limit {
+                Some(v) => v,
+                None => continue,
+            };
+            for (k, v) in [
+                ("upload_bytes_per_second", limit.upload_bytes_per_second),
+                ("download_bytes_per_second", limit.download_bytes_per_second),
+            ] {
+                if let Some(v) = v {
+                    if v < MIN_TRANSFER_BYTES_PER_SECOND {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!(
+                                "'s3_transfer_limits.{}.{}' {} below min {}",
+                                class, k, v, MIN_TRANSFER_BYTES_PER_SECOND
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_transfer_limits() {
+    let limits = TransferLimits {
+        anchor_nodes: None,
+        non_anchor_nodes: Some(TransferLimit {
+            upload_bytes_per_second: Some(50 * 1024 * 1024),
+            download_bytes_per_second: None,
+        }),
+    };
+    assert!(limits.validate().is_ok());
+    assert_eq!(
+        limits.node_limit(&node::Kind::Anchor),
+        TransferLimit::default()
+    );
+    assert_eq!(
+        limits
+            .node_limit(&node::Kind::NonAnchor)
+            .upload_bytes_per_second,
+        Some(50 * 1024 * 1024)
+    );
+
+    let limits = TransferLimits {
+        anchor_nodes: Some(TransferLimit {
+            upload_bytes_per_second: None,
+            download_bytes_per_second: Some(1024),
+        }),
+        non_anchor_nodes: None,
+    };
+    assert!(limits.validate().is_err());
+}
+
+/// Represents the lifecycle rules for the objects of a cluster.
+/// ref. https://docs.aws.amazon.com/AmazonS3/latest/userguide/object-lifecycle-mgmt.html
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Lifecycle {
+    /// Days after which the database backups are deleted.
+    /// None to keep the backups forever.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backups_expiration_days: Option<i32>,
+    /// Days after which the database backups are moved to Glacier.
+    /// None to keep the backups in the standard storage class.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backups_glacier_transition_days: Option<i32>,
+    /// Days after which the incomplete multipart uploads are aborted
+    /// (e.g., interrupted backup uploads).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub abort_incomplete_multipart_upload_days: Option<i32>,
+    /// Days after which the overwritten versions are deleted
+    /// (e.g., the past spec states in the versioned bucket).
+    /// None to keep the past versions forever.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub noncurrent_versions_expiration_days: Option<i32>,
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self::default()
+    }
+}
+
+impl Lifecycle {
+    pub fn default() -> Self {
+        Self {
+            backups_expiration_days: None,
+            backups_glacier_transition_days: None,
+            abort_incomplete_multipart_upload_days: Some(7),
+            noncurrent_versions_expiration_days: Some(90),
+        }
+    }
+
+    /// Validates the lifecycle rules.
+    pub fn validate(&self) -> io::Result<()> {
+        for (k, v) in [
+            ("backups_expiration_days", self.backups_expiration_days),
+            (
+                "backups_glacier_transition_days",
+                self.backups_glacier_transition_days,
+            ),
+            (
+                "abort_incomplete_multipart_upload_days",
+                self.abort_incomplete_multipart_upload_days,
+            ),
+            (
+                "noncurrent_versions_expiration_days",
+                self.noncurrent_versions_expiration_days,
+            ),
+        ] {
+            if let Some(days) = v {
+                if days <= 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("'{}' must be positive (got {})", k, days),
+                    ));
+                }
+            }
+        }
+
+        // S3 rejects the expiration that is not later than the transition
+        if let (Some(expiration_days), Some(transition_days)) = (
+            self.backups_expiration_days,
+            self.backups_glacier_transition_days,
+        ) {
+            if expiration_days <= transition_days {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "'backups_expiration_days' {} must be greater than 'backups_glacier_transition_days' {}",
+                        expiration_days, transition_days
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_lifecycle_validate() {
+    assert!(Lifecycle::default().validate().is_ok());
+
+    let lifecycle = Lifecycle {
+        backups_expiration_days: Some(90),
+        backups_glacier_transition_days: Some(30),
+        abort_incomplete_multipart_upload_days: Some(7),
+        noncurrent_versions_expiration_days: Some(90),
+    };
+    assert!(lifecycle.validate().is_ok());
+
+    let lifecycle = Lifecycle {
+        backups_expiration_days: Some(30),
+        backups_glacier_transition_days: Some(30),
+        abort_incomplete_multipart_upload_days: None,
+        noncurrent_versions_expiration_days: None,
+    };
+    assert!(lifecycle.validate().is_err());
+
+    let lifecycle = Lifecycle {
+        backups_expiration_days: Some(0),
+        backups_glacier_transition_days: None,
+        abort_incomplete_multipart_upload_days: None,
+        noncurrent_versions_expiration_days: None,
+    };
+    assert!(lifecycle.validate().is_err());
+}
+
 /// Implements AWS S3 manager.
 #[derive(Debug, Clone)]
 pub struct Manager {
     #[allow(dead_code)]
     shared_config: AwsSdkConfig,
     cli: Client,
+    transfer_limit: TransferLimit,
 }
 
 impl Manager {
@@ -38,9 +317,22 @@ impl Manager {
         Self {
             shared_config: cloned,
             cli,
+            transfer_limit: TransferLimit::default(),
         }
     }
 
+    /// Sets the bandwidth limits of the object uploads and downloads
+    /// (e.g., database backups), applied to each transfer.
+    pub fn set_transfer_limit(&mut self, transfer_limit: TransferLimit) {
+        self.transfer_limit = transfer_limit;
+    }
+
+    fn download_rate_limiter(&self) -> Option<RateLimiter> {
+        self.transfer_limit
+            .download_bytes_per_second
+            .map(RateLimiter::new)
+    }
+
     /// Creates a S3 bucket.
     pub async fn create_bucket(&self, s3_bucket: &str) -> Result<()> {
         let reg = self.shared_config.region().unwrap();
@@ -66,10 +358,7 @@ impl Manager {
             Ok(_) => false,
             Err(e) => {
                 if !is_error_bucket_already_exist(&e) {
-                    return Err(API {
-                        message: format!("failed create_bucket {:?}", e),
-                        is_retryable: is_error_retryable(&e),
-                    });
+                    return Err(from_sdk(format!("failed create_bucket {:?}", e), &e));
                 }
                 warn!("bucket already exists ({})", e);
                 true
@@ -93,10 +382,7 @@ impl Manager {
             .public_access_block_configuration(public_access_block_cfg)
             .send()
             .await
-            .map_err(|e| API {
-                message: format!("failed put_public_access_block {}", e),
-                is_retryable: is_error_retryable(&e),
-            })?;
+            .map_err(|e| from_sdk(format!("failed put_public_access_block {}", e), &e))?;
 
         let algo = ServerSideEncryption::Aes256;
         let sse = ServerSideEncryptionByDefault::builder()
@@ -114,10 +400,7 @@ impl Manager {
             .server_side_encryption_configuration(server_side_encryption_cfg)
             .send()
             .await
-            .map_err(|e| API {
-                message: format!("failed put_bucket_encryption {}", e),
-                is_retryable: is_error_retryable(&e),
-            })?;
+            .map_err(|e| from_sdk(format!("failed put_bucket_encryption {}", e), &e))?;
 
         Ok(())
     }
@@ -135,10 +418,7 @@ impl Manager {
             Ok(_) => {}
             Err(e) => {
                 if !is_error_bucket_does_not_exist(&e) {
-                    return Err(API {
-                        message: format!("failed delete_bucket {:?}", e),
-                        is_retryable: is_error_retryable(&e),
-                    });
+                    return Err(from_sdk(format!("failed delete_bucket {:?}", e), &e));
                 }
                 warn!("bucket already deleted or does not exist ({})", e);
             }
@@ -148,6 +428,23 @@ impl Manager {
         Ok(())
     }
 
+    /// Returns the ownership of the bucket name.
+    /// ref. https://docs.aws.amazon.com/AmazonS3/latest/API/API_HeadBucket.html
+    pub async fn bucket_ownership(&self, s3_bucket: &str) -> Result<BucketOwnership> {
+        let ret = self.cli.head_bucket().bucket(s3_bucket).send().await;
+        match ret {
+            Ok(_) => Ok(BucketOwnership::Owned),
+            // "HEAD" has no error body, so the status is all there is
+            Err(SdkError::ServiceError { raw, .. }) if raw.http().status().as_u16() == 404 => {
+                Ok(BucketOwnership::Available)
+            }
+            Err(SdkError::ServiceError { raw, .. }) if raw.http().status().as_u16() == 403 => {
+                Ok(BucketOwnership::OwnedByOther)
+            }
+            Err(e) => Err(from_sdk(format!("failed head_bucket {:?}", e), &e)),
+        }
+    }
+
     /// Deletes objects by "prefix".
     /// If "prefix" is "None", empties a S3 bucket, deleting all files.
     /// ref. https://github.com/awslabs/aws-sdk-rust/blob/main/examples/s3/src/bin/delete-objects.rs
@@ -189,10 +486,7 @@ impl Manager {
             match ret {
                 Ok(_) => {}
                 Err(e) => {
-                    return Err(API {
-                        message: format!("failed delete_bucket {:?}", e),
-                        is_retryable: is_error_retryable(&e),
-                    });
+                    return Err(from_sdk(format!("failed delete_bucket {:?}", e), &e));
                 }
             };
             info!("deleted {} objets in S3 bucket '{}'", n, s3_bucket);
@@ -244,10 +538,7 @@ impl Manager {
             let ret = match builder.send().await {
                 Ok(r) => r,
                 Err(e) => {
-                    return Err(API {
-                        message: format!("failed list_objects_v2 {:?}", e),
-                        is_retryable: is_error_retryable(&e),
-                    });
+                    return Err(from_sdk(format!("failed list_objects_v2 {:?}", e), &e));
                 }
             };
             if ret.key_count == 0 {
@@ -260,7 +551,7 @@ impl Manager {
             for obj in contents.iter() {
                 let k = obj.key().unwrap_or("");
                 if k.is_empty() {
-                    return Err(API {
+                    return Err(Other {
                         message: String::from("empty key returned"),
                         is_retryable: false,
                     });
@@ -315,6 +606,19 @@ impl Manager {
         file_path: Arc<String>,
         s3_bucket: Arc<String>,
         s3_key: Arc<String>,
+    ) -> Result<()> {
+        self.put_object_with_metadata(file_path, s3_bucket, s3_key, None)
+            .await
+    }
+
+    /// Writes an object to a S3 bucket using stream, with the user-defined
+    /// metadata (e.g., checksums), which is returned by "head_object_metadata".
+    pub async fn put_object_with_metadata(
+        &self,
+        file_path: Arc<String>,
+        s3_bucket: Arc<String>,
+        s3_key: Arc<String>,
+        metadata: Option<HashMap<String, String>>,
     ) -> Result<()> {
         if !Path::new(&file_path.to_string()).exists() {
             return Err(Other {
@@ -335,6 +639,17 @@ impl Manager {
             s3_bucket,
             s3_key
         );
+        if let Some(bytes_per_second) = self.transfer_limit.upload_bytes_per_second {
+            return self
+                .put_object_throttled(
+                    file_path.as_str(),
+                    s3_bucket.as_str(),
+                    s3_key.as_str(),
+                    metadata,
+                    bytes_per_second,
+                )
+                .await;
+        }
 
         let byte_stream = ByteStream::from_path(Path::new(file_path.as_str()))
             .await
@@ -348,16 +663,405 @@ impl Manager {
             .key(s3_key.to_string())
             .body(byte_stream)
             .acl(ObjectCannedAcl::Private)
+            .set_metadata(metadata)
+            .send()
+            .await
+            .map_err(|e| from_sdk(format!("failed put_object {}", e), &e))?;
+
+        Ok(())
+    }
+
+    /// Writes an object with the multipart upload, sleeping between the parts
+    /// to keep the average throughput under "bytes_per_second".
+    async fn put_object_throttled(
+        &self,
+        file_path: &str,
+        s3_bucket: &str,
+        s3_key: &str,
+        metadata: Option<HashMap<String, String>>,
+        bytes_per_second: u64,
+    ) -> Result<()> {
+        let size = fs::metadata(file_path)
+            .map_err(|e| Other {
+                message: format!("failed metadata {}", e),
+                is_retryable: false,
+            })?
+            .len();
+        info!(
+            "uploading with the limit {}/s",
+            humanize::bytes(bytes_per_second as f64)
+        );
+
+        let upload = self
+            .cli
+            .create_multipart_upload()
+            .bucket(s3_bucket)
+            .key(s3_key)
+            .acl(ObjectCannedAcl::Private)
+            .set_metadata(metadata)
             .send()
             .await
-            .map_err(|e| API {
-                message: format!("failed put_object {}", e),
-                is_retryable: is_error_retryable(&e),
+            .map_err(|e| from_sdk(format!("failed create_multipart_upload {}", e), &e))?;
+        let upload_id = upload.upload_id().unwrap_or("").to_string();
+
+        let ret = match self
+            .upload_parts_throttled(
+                file_path,
+                s3_bucket,
+                s3_key,
+                &upload_id,
+                size,
+                bytes_per_second,
+            )
+            .await
+        {
+            Ok(parts) => {
+                self.complete_multipart_upload(s3_bucket, s3_key, &upload_id, parts)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = ret {
+            self.abort_multipart_upload(s3_bucket, s3_key, &upload_id, &e)
+                .await;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    async fn upload_parts_throttled(
+        &self,
+        file_path: &str,
+        s3_bucket: &str,
+        s3_key: &str,
+        upload_id: &str,
+        size: u64,
+        bytes_per_second: u64,
+    ) -> Result<Vec<CompletedPart>> {
+        let mut file = File::open(file_path).await.map_err(|e| Other {
+            message: format!("failed File::open {}", e),
+            is_retryable: false,
+        })?;
+        let part_size = upload_part_size(size);
+        let mut limiter = RateLimiter::new(bytes_per_second);
+
+        let mut parts: Vec<CompletedPart> = Vec::new();
+        let mut uploaded: u64 = 0;
+        let mut part_number: i32 = 1;
+        while uploaded < size || part_number == 1 {
+            let n = std::cmp::min(part_size, size - uploaded);
+            let mut buf = vec![0_u8; n as usize];
+            file.read_exact(&mut buf).await.map_err(|e| Other {
+                message: format!("failed File.read_exact {}", e),
+                is_retryable: false,
             })?;
 
+            debug!("uploading part {} ({} bytes)", part_number, n);
+            let output = self
+                .cli
+                .upload_part()
+                .bucket(s3_bucket)
+                .key(s3_key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(ByteStream::from(buf))
+                .send()
+                .await
+                .map_err(|e| from_sdk(format!("failed upload_part {}", e), &e))?;
+            parts.push(
+                CompletedPart::builder()
+                    .e_tag(output.e_tag().unwrap_or(""))
+                    .part_number(part_number)
+                    .build(),
+            );
+
+            uploaded += n;
+            part_number += 1;
+            limiter.consume(n).await;
+        }
+        Ok(parts)
+    }
+
+    /// Writes the bytes to a S3 bucket, without writing them to a local file
+    /// (e.g., the decrypted spec).
+    pub async fn put_bytes(
+        &self,
+        d: Vec<u8>,
+        s3_bucket: Arc<String>,
+        s3_key: Arc<String>,
+    ) -> Result<()> {
+        info!(
+            "starting put_bytes (size {}) to 's3://{}/{}'",
+            humanize::bytes(d.len() as f64),
+            s3_bucket,
+            s3_key
+        );
+        self.cli
+            .put_object()
+            .bucket(s3_bucket.to_string())
+            .key(s3_key.to_string())
+            .body(ByteStream::from(d))
+            .acl(ObjectCannedAcl::Private)
+            .send()
+            .await
+            .map_err(|e| from_sdk(format!("failed put_object {}", e), &e))?;
+
+        Ok(())
+    }
+
+    /// Enables the object versioning, so the overwritten objects
+    /// (e.g., the spec state) can be restored.
+    pub async fn put_bucket_versioning(&self, s3_bucket: &str) -> Result<()> {
+        info!("enabling versioning for S3 bucket '{}'", s3_bucket);
+        self.cli
+            .put_bucket_versioning()
+            .bucket(s3_bucket)
+            .versioning_configuration(
+                VersioningConfiguration::builder()
+                    .status(BucketVersioningStatus::Enabled)
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|e| from_sdk(format!("failed put_bucket_versioning {:?}", e), &e))?;
+
+        Ok(())
+    }
+
+    /// Replaces the bucket policy (e.g., to allow the NLB access log delivery).
+    pub async fn put_bucket_policy(&self, s3_bucket: &str, policy: &str) -> Result<()> {
+        info!("putting the policy of S3 bucket '{}'", s3_bucket);
+        self.cli
+            .put_bucket_policy()
+            .bucket(s3_bucket)
+            .policy(policy)
+            .send()
+            .await
+            .map_err(|e| from_sdk(format!("failed put_bucket_policy {:?}", e), &e))?;
+
+        Ok(())
+    }
+
+    /// Allows the public bucket policy (e.g., for the public snapshots),
+    /// while still blocking the public ACLs.
+    pub async fn allow_public_policy(&self, s3_bucket: &str) -> Result<()> {
+        info!(
+            "setting S3 bucket '{}' public_access_block configuration to allow the policy",
+            s3_bucket
+        );
+        let public_access_block_cfg = PublicAccessBlockConfiguration::builder()
+            .block_public_acls(true)
+            .block_public_policy(false)
+            .ignore_public_acls(true)
+            .restrict_public_buckets(false)
+            .build();
+        self.cli
+            .put_public_access_block()
+            .bucket(s3_bucket)
+            .public_access_block_configuration(public_access_block_cfg)
+            .send()
+            .await
+            .map_err(|e| from_sdk(format!("failed put_public_access_block {}", e), &e))?;
+
+        Ok(())
+    }
+
+    /// Sets whether the requesters pay the downloads of the bucket.
+    /// ref. https://docs.aws.amazon.com/AmazonS3/latest/userguide/RequesterPaysBuckets.html
+    pub async fn put_bucket_request_payment(
+        &self,
+        s3_bucket: &str,
+        requester_pays: bool,
+    ) -> Result<()> {
+        let payer = if requester_pays {
+            Payer::Requester
+        } else {
+            Payer::BucketOwner
+        };
+        info!(
+            "putting the request payment {:?} of S3 bucket '{}'",
+            payer, s3_bucket
+        );
+        self.cli
+            .put_bucket_request_payment()
+            .bucket(s3_bucket)
+            .request_payment_configuration(
+                RequestPaymentConfiguration::builder().payer(payer).build(),
+            )
+            .send()
+            .await
+            .map_err(|e| from_sdk(format!("failed put_bucket_request_payment {:?}", e), &e))?;
+
+        Ok(())
+    }
+
+    /// Reads the small object (e.g., the spec) into memory,
+    /// optionally at the version. Returns "None" if the bucket
+    /// or the object does not exist.
+    pub async fn get_bytes(
+        &self,
+        s3_bucket: &str,
+        s3_key: &str,
+        version_id: Option<&str>,
+    ) -> Result<Option<Vec<u8>>> {
+        info!(
+            "starting get_bytes 's3://{}/{}' (version {:?})",
+            s3_bucket, s3_key, version_id
+        );
+        let ret = self
+            .cli
+            .get_object()
+            .bucket(s3_bucket)
+            .key(s3_key)
+            .set_version_id(version_id.map(String::from))
+            .send()
+            .await;
+        let output = match ret {
+            Ok(output) => output,
+            Err(e) => {
+                if is_error_get_object_not_found(&e) {
+                    return Ok(None);
+                }
+                return Err(from_sdk(format!("failed get_object {:?}", e), &e));
+            }
+        };
+
+        let d = output.body.collect().await.map_err(|e| Other {
+            message: format!("failed ByteStream::collect {}", e),
+            is_retryable: false,
+        })?;
+        Ok(Some(d.into_bytes().to_vec()))
+    }
+
+    /// Lists the versions of the object, latest first.
+    pub async fn list_object_versions(
+        &self,
+        s3_bucket: &str,
+        s3_key: &str,
+    ) -> Result<Vec<ObjectVersion>> {
+        info!("listing versions of 's3://{}/{}'", s3_bucket, s3_key);
+        let mut versions: Vec<ObjectVersion> = Vec::new();
+        let (mut key_marker, mut version_id_marker) = (None, None);
+        loop {
+            let resp = self
+                .cli
+                .list_object_versions()
+                .bucket(s3_bucket)
+                .prefix(s3_key)
+                .set_key_marker(key_marker)
+                .set_version_id_marker(version_id_marker)
+                .send()
+                .await
+                .map_err(|e| from_sdk(format!("failed list_object_versions {:?}", e), &e))?;
+            versions.extend(
+                resp.versions()
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|v| v.key() == Some(s3_key))
+                    .cloned(),
+            );
+            if !resp.is_truncated {
+                break;
+            }
+            key_marker = resp.next_key_marker.clone();
+            version_id_marker = resp.next_version_id_marker.clone();
+        }
+
+        versions.sort_by(|a, b| {
+            let a_modified = a.last_modified.map(|t| t.as_nanos()).unwrap_or_default();
+            let b_modified = b.last_modified.map(|t| t.as_nanos()).unwrap_or_default();
+            b_modified.cmp(&a_modified)
+        });
+        Ok(versions)
+    }
+
+    /// Deletes all versions and delete markers under the prefix,
+    /// since the versioned bucket cannot be deleted otherwise.
+    pub async fn delete_object_versions(
+        &self,
+        s3_bucket: &str,
+        prefix: Option<&str>,
+    ) -> Result<()> {
+        info!(
+            "deleting object versions in S3 bucket '{}' (prefix {:?})",
+            s3_bucket, prefix
+        );
+        let mut n = 0;
+        loop {
+            let resp = self
+                .cli
+                .list_object_versions()
+                .bucket(s3_bucket)
+                .set_prefix(prefix.map(String::from))
+                .max_keys(1000)
+                .send()
+                .await
+                .map_err(|e| from_sdk(format!("failed list_object_versions {:?}", e), &e))?;
+
+            let mut object_ids: Vec<ObjectIdentifier> = Vec::new();
+            for v in resp.versions().unwrap_or_default() {
+                object_ids.push(
+                    ObjectIdentifier::builder()
+                        .set_key(v.key().map(String::from))
+                        .set_version_id(v.version_id().map(String::from))
+                        .build(),
+                );
+            }
+            for m in resp.delete_markers().unwrap_or_default() {
+                object_ids.push(
+                    ObjectIdentifier::builder()
+                        .set_key(m.key().map(String::from))
+                        .set_version_id(m.version_id().map(String::from))
+                        .build(),
+                );
+            }
+            if object_ids.is_empty() {
+                break;
+            }
+
+            // "DeleteObjects" takes up to 1,000 keys
+            for chunk in object_ids.chunks(1000) {
+                n += chunk.len();
+                self.cli
+                    .delete_objects()
+                    .bucket(s3_bucket)
+                    .delete(Delete::builder().set_objects(Some(chunk.to_vec())).build())
+                    .send()
+                    .await
+                    .map_err(|e| from_sdk(format!("failed delete_objects {:?}", e), &e))?;
+            }
+        }
+        info!("deleted {} object versions in S3 bucket '{}'", n, s3_bucket);
+
         Ok(())
     }
 
+    /// Returns the user-defined metadata of the object.
+    /// Returns "None" if the object does not exist.
+    pub async fn head_object_metadata(
+        &self,
+        s3_bucket: &str,
+        s3_key: &str,
+    ) -> Result<Option<HashMap<String, String>>> {
+        let ret = self
+            .cli
+            .head_object()
+            .bucket(s3_bucket)
+            .key(s3_key)
+            .send()
+            .await;
+        match ret {
+            Ok(out) => Ok(Some(out.metadata.unwrap_or_default())),
+            Err(e) => {
+                if is_error_head_not_found(&e) {
+                    return Ok(None);
+                }
+                Err(from_sdk(format!("failed head_object {:?}", e), &e))
+            }
+        }
+    }
+
     /// Downloads an object from a S3 bucket using stream.
     ///
     /// WARN: use stream! otherwise it can cause OOM -- don't do the following!
@@ -387,10 +1091,7 @@ impl Manager {
             .key(s3_key.to_string())
             .send()
             .await
-            .map_err(|e| API {
-                message: format!("failed head_object {}", e),
-                is_retryable: is_error_retryable(&e),
-            })?;
+            .map_err(|e| from_sdk(format!("failed head_object {}", e), &e))?;
 
         info!(
             "starting get_object 's3://{}/{}' (content type '{}', size {})",
@@ -406,10 +1107,7 @@ impl Manager {
             .key(s3_key.to_string())
             .send()
             .await
-            .map_err(|e| API {
-                message: format!("failed get_object {}", e),
-                is_retryable: is_error_retryable(&e),
-            })?;
+            .map_err(|e| from_sdk(format!("failed get_object {}", e), &e))?;
 
         // ref. https://docs.rs/tokio-stream/latest/tokio_stream/
         let mut file = File::create(file_path.as_str()).await.map_err(|e| Other {
@@ -418,6 +1116,7 @@ impl Manager {
         })?;
 
         info!("writing byte stream to file {}", file_path);
+        let mut limiter = self.download_rate_limiter();
         while let Some(d) = output.body.try_next().await.map_err(|e| Other {
             message: format!("failed ByteStream::try_next {}", e),
             is_retryable: false,
@@ -426,14 +1125,590 @@ impl Manager {
                 message: format!("failed File.write_all {}", e),
                 is_retryable: false,
             })?;
+            if let Some(limiter) = limiter.as_mut() {
+                limiter.consume(d.len() as u64).await;
+            }
+        }
+        file.flush().await.map_err(|e| Other {
+            message: format!("failed File.flush {}", e),
+            is_retryable: false,
+        })?;
+
+        Ok(())
+    }
+
+    /// Downloads an object with the ranged GETs, resuming from the partial
+    /// "file_path" of the previous attempts (or the previous runs).
+    /// Gives up after "max_attempts" consecutive failures without progress.
+    /// The object ETag is recorded next to the file, so the partial file
+    /// of a different object (e.g., overwritten backup) restarts from zero.
+    /// ref. https://docs.aws.amazon.com/AmazonS3/latest/userguide/range-get-olap.html
+    pub async fn get_object_resumable(
+        &self,
+        s3_bucket: &str,
+        s3_key: &str,
+        file_path: &str,
+        max_attempts: u32,
+    ) -> Result<()> {
+        let head_output = self
+            .cli
+            .head_object()
+            .bucket(s3_bucket)
+            .key(s3_key)
+            .send()
+            .await
+            .map_err(|e| from_sdk(format!("failed head_object {}", e), &e))?;
+        let size = head_output.content_length() as u64;
+        let etag = head_output.e_tag().unwrap_or_default().to_string();
+
+        let etag_path = format!("{}{}", file_path, DOWNLOAD_ETAG_FILE_EXT);
+        let prev_etag = fs::read_to_string(&etag_path).unwrap_or_default();
+        let existing_len = fs::metadata(file_path).map(|m| m.len()).unwrap_or(0);
+        let mut offset = resume_offset(existing_len, size, prev_etag == etag);
+        if offset == 0 && existing_len > 0 {
+            warn!(
+                "discarding partial file {} ({} bytes) of a different object",
+                file_path, existing_len
+            );
+        }
+        fs::write(&etag_path, &etag).map_err(|e| Other {
+            message: format!("failed to write {} {}", etag_path, e),
+            is_retryable: false,
+        })?;
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path)
+            .await
+            .map_err(|e| Other {
+                message: format!("failed to open {} {}", file_path, e),
+                is_retryable: false,
+            })?;
+        file.set_len(offset).await.map_err(|e| Other {
+            message: format!("failed File::set_len {}", e),
+            is_retryable: false,
+        })?;
+
+        info!(
+            "starting resumable get_object 's3://{}/{}' (size {}, resuming from {})",
+            s3_bucket,
+            s3_key,
+            humanize::bytes(size as f64),
+            humanize::bytes(offset as f64),
+        );
+        let mut failures = 0;
+        while offset < size {
+            match self
+                .get_object_range(s3_bucket, s3_key, &etag, offset, &mut file)
+                .await
+            {
+                Ok(n) => {
+                    offset += n;
+                    if n > 0 {
+                        failures = 0;
+                    }
+                }
+                Err((n, e)) => {
+                    offset += n;
+                    if n > 0 {
+                        failures = 0;
+                    }
+                    failures += 1;
+                    if failures >= max_attempts {
+                        return Err(Other {
+                            message: format!(
+                                "failed get_object 's3://{}/{}' at {} of {} bytes after {} attempts ({})",
+                                s3_bucket, s3_key, offset, size, failures, e
+                            ),
+                            is_retryable: true,
+                        });
+                    }
+                    let backoff = Duration::from_secs(2_u64.pow(failures.min(6)));
+                    warn!(
+                        "failed ranged get_object at {} of {} bytes ({}), retrying in {:?}",
+                        offset, size, e, backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                }
+            }
         }
         file.flush().await.map_err(|e| Other {
             message: format!("failed File.flush {}", e),
             is_retryable: false,
         })?;
 
+        fs::remove_file(&etag_path).map_err(|e| Other {
+            message: format!("failed to remove {} {}", etag_path, e),
+            is_retryable: false,
+        })?;
+        info!(
+            "downloaded 's3://{}/{}' to {}",
+            s3_bucket, s3_key, file_path
+        );
+        Ok(())
+    }
+
+    /// Appends the object bytes from "offset" to the file.
+    /// Returns the number of bytes written, even on failures.
+    async fn get_object_range(
+        &self,
+        s3_bucket: &str,
+        s3_key: &str,
+        etag: &str,
+        offset: u64,
+        file: &mut File,
+    ) -> std::result::Result<u64, (u64, String)> {
+        let mut req = self
+            .cli
+            .get_object()
+            .bucket(s3_bucket)
+            .key(s3_key)
+            .range(format!("bytes={}-", offset));
+        if !etag.is_empty() {
+            // fails if the object is overwritten while downloading
+            req = req.if_match(etag);
+        }
+        let mut output = req.send().await.map_err(|e| (0, e.to_string()))?;
+
+        let mut limiter = self.download_rate_limiter();
+        let mut n = 0_u64;
+        loop {
+            match output.body.try_next().await {
+                Ok(Some(d)) => {
+                    file.write_all(&d).await.map_err(|e| (n, e.to_string()))?;
+                    n += d.len() as u64;
+                    if let Some(limiter) = limiter.as_mut() {
+                        limiter.consume(d.len() as u64).await;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    // keep the bytes written so far
+                    file.flush().await.map_err(|e| (n, e.to_string()))?;
+                    return Err((n, e.to_string()));
+                }
+            }
+        }
+        Ok(n)
+    }
+
+    /// Generates a presigned URL to download the object without AWS credentials.
+    /// The URL is valid for "expires_in" (up to 7 days).
+    /// ref. https://docs.aws.amazon.com/AmazonS3/latest/userguide/ShareObjectPreSignedURL.html
+    pub async fn presign_get(
+        &self,
+        s3_bucket: &str,
+        s3_key: &str,
+        expires_in: Duration,
+    ) -> Result<String> {
+        info!(
+            "presigning get_object 's3://{}/{}' (expires in {:?})",
+            s3_bucket, s3_key, expires_in
+        );
+        let presigning_config = PresigningConfig::expires_in(expires_in).map_err(|e| Other {
+            message: format!("failed PresigningConfig::expires_in {}", e),
+            is_retryable: false,
+        })?;
+        let presigned = self
+            .cli
+            .get_object()
+            .bucket(s3_bucket)
+            .key(s3_key)
+            .presigned(presigning_config)
+            .await
+            .map_err(|e| from_sdk(format!("failed get_object presigned {:?}", e), &e))?;
+        Ok(presigned.uri().to_string())
+    }
+
+    /// Generates a presigned URL to upload the object without AWS credentials.
+    /// The URL is valid for "expires_in" (up to 7 days).
+    /// ref. https://docs.aws.amazon.com/AmazonS3/latest/userguide/PresignedUrlUploadObject.html
+    pub async fn presign_put(
+        &self,
+        s3_bucket: &str,
+        s3_key: &str,
+        expires_in: Duration,
+    ) -> Result<String> {
+        info!(
+            "presigning put_object 's3://{}/{}' (expires in {:?})",
+            s3_bucket, s3_key, expires_in
+        );
+        let presigning_config = PresigningConfig::expires_in(expires_in).map_err(|e| Other {
+            message: format!("failed PresigningConfig::expires_in {}", e),
+            is_retryable: false,
+        })?;
+        let presigned = self
+            .cli
+            .put_object()
+            .bucket(s3_bucket)
+            .key(s3_key)
+            .presigned(presigning_config)
+            .await
+            .map_err(|e| from_sdk(format!("failed put_object presigned {:?}", e), &e))?;
+        Ok(presigned.uri().to_string())
+    }
+
+    /// Creates (or overwrites) the lifecycle rules for the objects under "id" prefix.
+    /// The rules of other prefixes (e.g., other clusters sharing the bucket) are kept as is,
+    /// since "PutBucketLifecycleConfiguration" replaces the whole configuration.
+    /// ref. https://docs.aws.amazon.com/AmazonS3/latest/API/API_PutBucketLifecycleConfiguration.html
+    pub async fn put_bucket_lifecycle(
+        &self,
+        s3_bucket: &str,
+        id: &str,
+        lifecycle: &Lifecycle,
+    ) -> Result<()> {
+        info!(
+            "putting lifecycle rules {:?} for '{}' in S3 bucket '{}'",
+            lifecycle, id, s3_bucket
+        );
+
+        let ret = self
+            .cli
+            .get_bucket_lifecycle_configuration()
+            .bucket(s3_bucket)
+            .send()
+            .await;
+        let existing_rules = match ret {
+            Ok(out) => out.rules.unwrap_or_default(),
+            Err(e) => {
+                if !is_error_no_such_lifecycle_configuration(&e) {
+                    return Err(from_sdk(
+                        format!("failed get_bucket_lifecycle_configuration {:?}", e),
+                        &e,
+                    ));
+                }
+                Vec::new()
+            }
+        };
+
+        let backups_rule_id = format!("{}-backups", id);
+        let multipart_rule_id = format!("{}-abort-incomplete-multipart-upload", id);
+        let noncurrent_rule_id = format!("{}-noncurrent-versions", id);
+        let mut rules: Vec<LifecycleRule> = existing_rules
+            .into_iter()
+            .filter(|r| {
+                let rule_id = r.id().unwrap_or("");
+                rule_id != backups_rule_id
+                    && rule_id != multipart_rule_id
+                    && rule_id != noncurrent_rule_id
+            })
+            .collect();
+
+        if lifecycle.backups_expiration_days.is_some()
+            || lifecycle.backups_glacier_transition_days.is_some()
+        {
+            let mut builder = LifecycleRule::builder()
+                .id(backups_rule_id)
+                .status(ExpirationStatus::Enabled)
+                .filter(LifecycleRuleFilter::Prefix(append_slash(
+                    &crate::StorageNamespace::BackupsDir(id.to_string()).encode(),
+                )));
+            if let Some(days) = lifecycle.backups_glacier_transition_days {
+                builder = builder.transitions(
+                    Transition::builder()
+                        .days(days)
+                        .storage_class(TransitionStorageClass::Glacier)
+                        .build(),
+                );
+            }
+            if let Some(days) = lifecycle.backups_expiration_days {
+                builder = builder.expiration(LifecycleExpiration::builder().days(days).build());
+            }
+            rules.push(builder.build());
+        }
+        if let Some(days) = lifecycle.abort_incomplete_multipart_upload_days {
+            rules.push(
+                LifecycleRule::builder()
+                    .id(multipart_rule_id)
+                    .status(ExpirationStatus::Enabled)
+                    .filter(LifecycleRuleFilter::Prefix(append_slash(id)))
+                    .abort_incomplete_multipart_upload(
+                        AbortIncompleteMultipartUpload::builder()
+                            .days_after_initiation(days)
+                            .build(),
+                    )
+                    .build(),
+            );
+        }
+        if let Some(days) = lifecycle.noncurrent_versions_expiration_days {
+            rules.push(
+                LifecycleRule::builder()
+                    .id(noncurrent_rule_id)
+                    .status(ExpirationStatus::Enabled)
+                    .filter(LifecycleRuleFilter::Prefix(append_slash(id)))
+                    .noncurrent_version_expiration(
+                        NoncurrentVersionExpiration::builder()
+                            .noncurrent_days(days)
+                            .build(),
+                    )
+                    .build(),
+            );
+        }
+
+        if rules.is_empty() {
+            self.cli
+                .delete_bucket_lifecycle()
+                .bucket(s3_bucket)
+                .send()
+                .await
+                .map_err(|e| from_sdk(format!("failed delete_bucket_lifecycle {:?}", e), &e))?;
+            return Ok(());
+        }
+
+        self.cli
+            .put_bucket_lifecycle_configuration()
+            .bucket(s3_bucket)
+            .lifecycle_configuration(
+                BucketLifecycleConfiguration::builder()
+                    .set_rules(Some(rules))
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|e| {
+                from_sdk(
+                    format!("failed put_bucket_lifecycle_configuration {:?}", e),
+                    &e,
+                )
+            })?;
+
+        Ok(())
+    }
+
+    /// Copies an object from the source bucket to the destination bucket,
+    /// both in the region of this manager.
+    /// Objects larger than 5 GiB are copied in parts, as a single
+    /// "CopyObject" call cannot handle them.
+    /// ref. https://docs.aws.amazon.com/AmazonS3/latest/userguide/copy-object.html
+    ///
+    /// "If a single piece of data must be accessible from more than one task
+    /// concurrently, then it must be shared using synchronization primitives such as Arc."
+    /// ref. https://tokio.rs/tokio/tutorial/spawning
+    pub async fn copy_object(
+        &self,
+        src_s3_bucket: Arc<String>,
+        src_s3_key: Arc<String>,
+        dst_s3_bucket: Arc<String>,
+        dst_s3_key: Arc<String>,
+    ) -> Result<()> {
+        let size = self.head_object_size(&src_s3_bucket, &src_s3_key).await?;
+        self.copy_object_with_size(src_s3_bucket, src_s3_key, size, dst_s3_bucket, dst_s3_key)
+            .await
+    }
+
+    /// Returns the size of the object in bytes.
+    pub async fn head_object_size(&self, s3_bucket: &str, s3_key: &str) -> Result<i64> {
+        let head_output = self
+            .cli
+            .head_object()
+            .bucket(s3_bucket)
+            .key(s3_key)
+            .send()
+            .await
+            .map_err(|e| from_sdk(format!("failed head_object {}", e), &e))?;
+        Ok(head_output.content_length())
+    }
+
+    /// Copies an object of the given size (see "head_object_size") to the
+    /// destination bucket in the region of this manager. The source bucket
+    /// may be in a different region (e.g., replicating to another region),
+    /// since the copy is done server-side by the destination region, but
+    /// its size must be read with the manager of the source region.
+    pub async fn copy_object_with_size(
+        &self,
+        src_s3_bucket: Arc<String>,
+        src_s3_key: Arc<String>,
+        size: i64,
+        dst_s3_bucket: Arc<String>,
+        dst_s3_key: Arc<String>,
+    ) -> Result<()> {
+        let reg = self.shared_config.region().unwrap();
+        let copy_source = format!("{}/{}", src_s3_bucket, src_s3_key);
+        info!(
+            "starting copy_object 's3://{}/{}' (size {}) to 's3://{}/{}' in region {}",
+            src_s3_bucket,
+            src_s3_key,
+            humanize::bytes(size as f64),
+            dst_s3_bucket,
+            dst_s3_key,
+            reg
+        );
+
+        if size <= MAX_COPY_OBJECT_SIZE {
+            self.cli
+                .copy_object()
+                .copy_source(copy_source)
+                .bucket(dst_s3_bucket.to_string())
+                .key(dst_s3_key.to_string())
+                .acl(ObjectCannedAcl::Private)
+                .send()
+                .await
+                .map_err(|e| from_sdk(format!("failed copy_object {}", e), &e))?;
+            return Ok(());
+        }
+
+        let upload = self
+            .cli
+            .create_multipart_upload()
+            .bucket(dst_s3_bucket.to_string())
+            .key(dst_s3_key.to_string())
+            .acl(ObjectCannedAcl::Private)
+            .send()
+            .await
+            .map_err(|e| from_sdk(format!("failed create_multipart_upload {}", e), &e))?;
+        let upload_id = upload.upload_id().unwrap_or("").to_string();
+
+        let ret = match self
+            .copy_parts(&copy_source, &dst_s3_bucket, &dst_s3_key, &upload_id, size)
+            .await
+        {
+            Ok(parts) => {
+                self.complete_multipart_upload(&dst_s3_bucket, &dst_s3_key, &upload_id, parts)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = ret {
+            self.abort_multipart_upload(&dst_s3_bucket, &dst_s3_key, &upload_id, &e)
+                .await;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Copies the source object in "COPY_PART_SIZE" ranges to the multipart upload.
+    async fn copy_parts(
+        &self,
+        copy_source: &str,
+        dst_s3_bucket: &str,
+        dst_s3_key: &str,
+        upload_id: &str,
+        size: i64,
+    ) -> Result<Vec<CompletedPart>> {
+        let mut parts: Vec<CompletedPart> = Vec::new();
+        let mut start: i64 = 0;
+        let mut part_number: i32 = 1;
+        while start < size {
+            let end = std::cmp::min(start + COPY_PART_SIZE, size) - 1;
+            debug!("copying part {} (bytes={}-{})", part_number, start, end);
+            let output = self
+                .cli
+                .upload_part_copy()
+                .copy_source(copy_source)
+                .copy_source_range(format!("bytes={}-{}", start, end))
+                .bucket(dst_s3_bucket)
+                .key(dst_s3_key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .send()
+                .await
+                .map_err(|e| from_sdk(format!("failed upload_part_copy {}", e), &e))?;
+            let e_tag = match output.copy_part_result() {
+                Some(v) => v.e_tag().unwrap_or("").to_string(),
+                None => String::new(),
+            };
+            parts.push(
+                CompletedPart::builder()
+                    .e_tag(e_tag)
+                    .part_number(part_number)
+                    .build(),
+            );
+
+            start = end + 1;
+            part_number += 1;
+        }
+        Ok(parts)
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        s3_bucket: &str,
+        s3_key: &str,
+        upload_id: &str,
+        parts: Vec<CompletedPart>,
+    ) -> Result<()> {
+        self.cli
+            .complete_multipart_upload()
+            .bucket(s3_bucket)
+            .key(s3_key)
+            .upload_id(upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|e| from_sdk(format!("failed complete_multipart_upload {}", e), &e))?;
         Ok(())
     }
+
+    /// Aborts the failed multipart upload, so the uploaded parts are not
+    /// left behind (and billed) in the bucket.
+    async fn abort_multipart_upload(
+        &self,
+        s3_bucket: &str,
+        s3_key: &str,
+        upload_id: &str,
+        cause: &Error,
+    ) {
+        warn!("aborting multipart upload {} ({})", upload_id, cause);
+        if let Err(e) = self
+            .cli
+            .abort_multipart_upload()
+            .bucket(s3_bucket)
+            .key(s3_key)
+            .upload_id(upload_id)
+            .send()
+            .await
+        {
+            warn!("failed abort_multipart_upload {} ({})", upload_id, e);
+        }
+    }
+}
+
+/// Maximum object size that can be copied in a single "CopyObject" call.
+/// ref. https://docs.aws.amazon.com/AmazonS3/latest/API/API_CopyObject.html
+const MAX_COPY_OBJECT_SIZE: i64 = 5 * 1024 * 1024 * 1024;
+
+/// Part size for multipart copies.
+const COPY_PART_SIZE: i64 = 1024 * 1024 * 1024;
+
+/// Minimum part size for the throttled uploads, small enough
+/// to keep the bursts between the throttling sleeps short.
+const MIN_UPLOAD_PART_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Maximum number of parts in a multipart upload.
+/// ref. https://docs.aws.amazon.com/AmazonS3/latest/userguide/qfacts.html
+const MAX_UPLOAD_PARTS: u64 = 10000;
+
+/// Returns the part size to upload the object of "size" bytes within "MAX_UPLOAD_PARTS".
+fn upload_part_size(size: u64) -> u64 {
+    std::cmp::max(MIN_UPLOAD_PART_SIZE, size.div_ceil(MAX_UPLOAD_PARTS))
+}
+
+#[test]
+fn test_upload_part_size() {
+    assert_eq!(upload_part_size(0), MIN_UPLOAD_PART_SIZE);
+    assert_eq!(upload_part_size(1024), MIN_UPLOAD_PART_SIZE);
+    // 300 GB backup
+    let size = 300 * 1024 * 1024 * 1024;
+    let part_size = upload_part_size(size);
+    assert!(part_size > MIN_UPLOAD_PART_SIZE);
+    assert!(size.div_ceil(part_size) <= MAX_UPLOAD_PARTS);
+}
+
+#[inline]
+fn is_error_get_object_not_found(e: &SdkError<GetObjectError>) -> bool {
+    match e {
+        SdkError::ServiceError { err, .. } => {
+            err.is_no_such_key() || format!("{:?}", err).contains("NoSuchBucket")
+        }
+        _ => false,
+    }
 }
 
 #[inline]
@@ -470,6 +1745,27 @@ fn is_error_bucket_does_not_exist(e: &SdkError<DeleteBucketError>) -> bool {
     }
 }
 
+#[inline]
+fn is_error_head_not_found(e: &SdkError<HeadObjectError>) -> bool {
+    match e {
+        SdkError::ServiceError { err, .. } => err.is_not_found(),
+        _ => false,
+    }
+}
+
+#[inline]
+fn is_error_no_such_lifecycle_configuration(
+    e: &SdkError<GetBucketLifecycleConfigurationError>,
+) -> bool {
+    match e {
+        SdkError::ServiceError { err, .. } => {
+            let msg = format!("{:?}", err);
+            msg.contains("NoSuchLifecycleConfiguration")
+        }
+        _ => false,
+    }
+}
+
 #[test]
 fn test_append_slash() {
     let s = "hello";
@@ -571,3 +1867,80 @@ pub async fn spawn_get_object(
     .await
     .expect("failed spawn await")
 }
+
+pub async fn spawn_get_object_resumable(
+    s3_manager: Manager,
+    s3_bucket: &str,
+    s3_key: &str,
+    file_path: &str,
+) -> Result<()> {
+    let s3_bucket = s3_bucket.to_string();
+    let s3_key = s3_key.to_string();
+    let file_path = file_path.to_string();
+    tokio::spawn(async move {
+        s3_manager
+            .get_object_resumable(
+                &s3_bucket,
+                &s3_key,
+                &file_path,
+                DEFAULT_DOWNLOAD_MAX_ATTEMPTS,
+            )
+            .await
+    })
+    .await
+    .expect("failed spawn await")
+}
+
+/// Downloads the object from the first source that succeeds, in order
+/// (e.g., the primary bucket, the replica, and the mirrors).
+/// The partial file carries over to the next source with the same ETag
+/// (e.g., the replicated objects).
+pub async fn spawn_get_object_from_mirrors(
+    sources: Vec<(Manager, String)>,
+    s3_key: &str,
+    file_path: &str,
+) -> Result<()> {
+    let mut last_err = None;
+    for (i, (s3_manager, s3_bucket)) in sources.into_iter().enumerate() {
+        if i > 0 {
+            warn!(
+                "falling back to source {} 's3://{}/{}' ({:?})",
+                i, s3_bucket, s3_key, last_err
+            );
+        }
+        match spawn_get_object_resumable(s3_manager, &s3_bucket, s3_key, file_path).await {
+            Ok(_) => return Ok(()),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or(Other {
+        message: String::from("no download source"),
+        is_retryable: false,
+    }))
+}
+
+pub async fn spawn_copy_object(
+    s3_manager: Manager,
+    src_s3_bucket: &str,
+    src_s3_key: &str,
+    dst_s3_bucket: &str,
+    dst_s3_key: &str,
+) -> Result<()> {
+    let s3_manager_arc = Arc::new(s3_manager);
+    let src_s3_bucket_arc = Arc::new(src_s3_bucket.to_string());
+    let src_s3_key_arc = Arc::new(src_s3_key.to_string());
+    let dst_s3_bucket_arc = Arc::new(dst_s3_bucket.to_string());
+    let dst_s3_key_arc = Arc::new(dst_s3_key.to_string());
+    tokio::spawn(async move {
+        s3_manager_arc
+            .copy_object(
+                src_s3_bucket_arc,
+                src_s3_key_arc,
+                dst_s3_bucket_arc,
+                dst_s3_key_arc,
+            )
+            .await
+    })
+    .await
+    .expect("failed spawn await")
+}
@@ -0,0 +1,162 @@
+use std::io::{self, Error, ErrorKind};
+
+use log::info;
+use serde::Deserialize;
+
+use crate::{aws::cli, cost};
+
+/// Lists the rates of the instance type (Linux, shared tenancy) under the active
+/// EC2 Instance and Compute savings plans of the account.
+/// Runs the AWS CLI (see "cli::run").
+/// ref. https://docs.aws.amazon.com/cli/latest/reference/savingsplans/describe-savings-plans.html
+/// ref. https://docs.aws.amazon.com/cli/latest/reference/savingsplans/describe-savings-plan-rates.html
+pub fn list_active_rates(
+    region: &str,
+    instance_type: &str,
+) -> io::Result<Vec<cost::SavingsPlanRate>> {
+    info!("listing active savings plans rates of '{}'", instance_type);
+    // savings plans are global, the API is only in "us-east-1"
+    let out = cli::run(&[
+        "savingsplans",
+        "describe-savings-plans",
+        "--region",
+        "us-east-1",
+        "--states",
+        "active",
+        "--output",
+        "json",
+    ])?;
+    let plans = SavingsPlans::parse_from_str(&out)?;
+
+    let mut rates = Vec::new();
+    for plan in plans.savings_plans.iter() {
+        if !plan.applies_to(region, instance_type) {
+            continue;
+        }
+        let out = cli::run(&[
+            "savingsplans",
+            "describe-savings-plan-rates",
+            "--region",
+            "us-east-1",
+            "--savings-plan-id",
+            &plan.savings_plan_id,
+            "--filters",
+            &format!("name=region,values={}", region),
+            &format!("name=instanceType,values={}", instance_type),
+            "name=productDescription,values=Linux/UNIX",
+            "name=tenancy,values=shared",
+            "--output",
+            "json",
+        ])?;
+        if let Some(rate) = Rates::parse_from_str(&out)?.box_usage_rate() {
+            rates.push(cost::SavingsPlanRate {
+                savings_plan_id: plan.savings_plan_id.clone(),
+                commitment_hourly_usd: plan.commitment.parse().unwrap_or(0.0),
+                rate_hourly_usd: rate,
+            });
+        }
+    }
+    info!("listed {} savings plans rates", rates.len());
+    Ok(rates)
+}
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SavingsPlans {
+    #[serde(default)]
+    savings_plans: Vec<SavingsPlan>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SavingsPlan {
+    savings_plan_id: String,
+    /// "Compute", "EC2Instance", or "SageMaker".
+    savings_plan_type: String,
+    /// Hourly commitment in USD.
+    commitment: String,
+    #[serde(default)]
+    region: Option<String>,
+    #[serde(default)]
+    ec2_instance_family: Option<String>,
+}
+
+impl SavingsPlans {
+    fn parse_from_str(s: &str) -> io::Result<Self> {
+        serde_json::from_str(s)
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("invalid JSON: {}", e)))
+    }
+}
+
+impl SavingsPlan {
+    /// Returns true if the plan discounts the instance type in the region.
+    /// The EC2 Instance savings plans are per instance family and region.
+    fn applies_to(&self, region: &str, instance_type: &str) -> bool {
+        match self.savings_plan_type.as_str() {
+            "Compute" => true,
+            "EC2Instance" => {
+                let family = instance_type.split('.').next().unwrap_or_default();
+                self.region.as_deref() == Some(region)
+                    && self.ec2_instance_family.as_deref() == Some(family)
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Rates {
+    #[serde(default)]
+    search_results: Vec<Rate>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Rate {
+    rate: String,
+    #[serde(default)]
+    usage_type: String,
+}
+
+impl Rates {
+    fn parse_from_str(s: &str) -> io::Result<Self> {
+        serde_json::from_str(s)
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("invalid JSON: {}", e)))
+    }
+
+    /// Returns the hourly rate of the instance usage (e.g., "USW2-BoxUsage:c6a.large"),
+    /// excluding the dedicated and the host usages.
+    fn box_usage_rate(&self) -> Option<f64> {
+        self.search_results
+            .iter()
+            .find(|r| r.usage_type.contains("BoxUsage:"))
+            .and_then(|r| r.rate.parse().ok())
+    }
+}
+
+#[test]
+fn test_savings_plans() {
+    let plans = SavingsPlans::parse_from_str(
+        r#"{"savingsPlans":[
+{"savingsPlanId":"a","savingsPlanType":"Compute","commitment":"1.5","region":""},
+{"savingsPlanId":"b","savingsPlanType":"EC2Instance","commitment":"0.5","region":"us-west-2","ec2InstanceFamily":"c6a"},
+{"savingsPlanId":"c","savingsPlanType":"SageMaker","commitment":"2.0"}]}"#,
+    )
+    .unwrap();
+    assert_eq!(plans.savings_plans.len(), 3);
+    assert!(plans.savings_plans[0].applies_to("us-west-2", "c6a.large"));
+    assert!(plans.savings_plans[1].applies_to("us-west-2", "c6a.large"));
+    assert!(!plans.savings_plans[1].applies_to("us-east-1", "c6a.large"));
+    assert!(!plans.savings_plans[1].applies_to("us-west-2", "m5.large"));
+    assert!(!plans.savings_plans[2].applies_to("us-west-2", "c6a.large"));
+
+    let rates = Rates::parse_from_str(
+        r#"{"searchResults":[
+{"rate":"0.09","usageType":"USW2-DedicatedUsage:c6a.large"},
+{"rate":"0.0489","usageType":"USW2-BoxUsage:c6a.large"}]}"#,
+    )
+    .unwrap();
+    assert_eq!(rates.box_usage_rate(), Some(0.0489));
+    assert_eq!(Rates::parse_from_str("{}").unwrap().box_usage_rate(), None);
+}
@@ -0,0 +1,354 @@
+use std::{
+    fmt,
+    io::{self, Error, ErrorKind},
+};
+
+use log::{info, warn};
+
+use crate::aws::{cli, ec2};
+
+/// Instance type of the ASG template default ("InstanceTypes"),
+/// when the spec sets no "machine.instance_types".
+pub const DEFAULT_INSTANCE_TYPE: &str = "c6a.large";
+
+/// Represents the service quotas that "apply" may run into.
+/// ref. https://docs.aws.amazon.com/general/latest/gr/ec2-service.html#limits_ec2
+/// ref. https://docs.aws.amazon.com/general/latest/gr/elb.html#limits_elastic_load_balancer
+/// ref. https://docs.aws.amazon.com/vpc/latest/userguide/amazon-vpc-limits.html
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Quota {
+    StandardVcpus,
+    FVcpus,
+    GVtVcpus,
+    InfVcpus,
+    PVcpus,
+    XVcpus,
+    ElasticIps,
+    NetworkLoadBalancers,
+    Vpcs,
+}
+
+impl Quota {
+    pub fn service_code(&self) -> &'static str {
+        match self {
+            Quota::NetworkLoadBalancers => "elasticloadbalancing",
+            Quota::Vpcs => "vpc",
+            _ => "ec2",
+        }
+    }
+
+    pub fn quota_code(&self) -> &'static str {
+        match self {
+            Quota::StandardVcpus => "L-1216C47A",
+            Quota::FVcpus => "L-74FC7D96",
+            Quota::GVtVcpus => "L-DB2E81BA",
+            Quota::InfVcpus => "L-1945791B",
+            Quota::PVcpus => "L-417A185B",
+            Quota::XVcpus => "L-7295265B",
+            Quota::ElasticIps => "L-0263D0A3",
+            Quota::NetworkLoadBalancers => "L-69A177A2",
+            Quota::Vpcs => "L-F678F1CE",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Quota::StandardVcpus => {
+                "Running On-Demand Standard (A, C, D, H, I, M, R, T, Z) instances"
+            }
+            Quota::FVcpus => "Running On-Demand F instances",
+            Quota::GVtVcpus => "Running On-Demand G and VT instances",
+            Quota::InfVcpus => "Running On-Demand Inf instances",
+            Quota::PVcpus => "Running On-Demand P instances",
+            Quota::XVcpus => "Running On-Demand X instances",
+            Quota::ElasticIps => "EC2-VPC Elastic IPs",
+            Quota::NetworkLoadBalancers => "Network Load Balancers per Region",
+            Quota::Vpcs => "VPCs per Region",
+        }
+    }
+
+    /// Returns the On-Demand vCPU quota of the instance family
+    /// (e.g., "c6a.large" is under the standard instances).
+    /// None for the families with their own quotas not checked here
+    /// (e.g., "trn1", "hpc6a", "mac1", "u-6tb1").
+    pub fn from_instance_type(instance_type: &str) -> Option<Self> {
+        let family = instance_type.split('.').next().unwrap_or_default();
+        if ["dl", "hpc", "mac", "trn", "u-"]
+            .iter()
+            .any(|p| family.starts_with(p))
+        {
+            return None;
+        }
+        if family.starts_with("inf") {
+            return Some(Quota::InfVcpus);
+        }
+        if family.starts_with("vt") {
+            return Some(Quota::GVtVcpus);
+        }
+        match family.chars().next()? {
+            'a' | 'c' | 'd' | 'h' | 'i' | 'm' | 'r' | 't' | 'z' => Some(Quota::StandardVcpus),
+            'f' => Some(Quota::FVcpus),
+            'g' => Some(Quota::GVtVcpus),
+            'p' => Some(Quota::PVcpus),
+            'x' => Some(Quota::XVcpus),
+            _ => None,
+        }
+    }
+}
+
+/// Represents the resources that "apply" is about to create.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Needs {
+    /// The prioritized instance type of the ASGs.
+    pub instance_type: String,
+    /// Total number of the nodes (anchor and non-anchor).
+    pub nodes: u32,
+    /// Elastic IPs not allocated yet.
+    pub elastic_ips: u32,
+    /// Network load balancers not created yet.
+    pub nlbs: u32,
+    /// VPCs not created yet.
+    pub vpcs: u32,
+}
+
+/// Represents the quota that the resources in the spec would exceed.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Shortfall {
+    pub quota: Quota,
+    /// Applied quota value in the region.
+    pub value: f64,
+    /// Resources in use, excluding the ones of this cluster.
+    pub in_use: u32,
+    pub required: u32,
+}
+
+impl Shortfall {
+    /// Returns the smallest quota value to request.
+    pub fn request_value(&self) -> u32 {
+        self.in_use + self.required
+    }
+}
+
+impl fmt::Display for Shortfall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "'{}' (service code '{}', quota code '{}') is {}, but {} in use + {} required: request the increase to at least {}",
+            self.quota.name(),
+            self.quota.service_code(),
+            self.quota.quota_code(),
+            self.value,
+            self.in_use,
+            self.required,
+            self.request_value()
+        )
+    }
+}
+
+/// Checks the applied service quotas in the region against the needs,
+/// and returns the quotas that would be exceeded.
+/// The quota values and the load balancer count come from the AWS CLI (see "cli::run").
+/// ref. https://docs.aws.amazon.com/cli/latest/reference/service-quotas/get-service-quota.html
+pub async fn check(
+    ec2_manager: &ec2::Manager,
+    region: &str,
+    id: &str,
+    needs: &Needs,
+) -> io::Result<Vec<Shortfall>> {
+    let mut usages: Vec<(Quota, u32, u32)> = Vec::new();
+    if needs.nodes > 0 {
+        match Quota::from_instance_type(&needs.instance_type) {
+            Some(quota) => {
+                let vcpus = ec2_manager
+                    .instance_type_vcpus(&needs.instance_type)
+                    .await?;
+                let instances = ec2_manager
+                    .list_instances(&ec2::InstanceFilter::default().alive())
+                    .await?;
+                usages.push((
+                    quota,
+                    vcpus_in_use(&instances, quota, id),
+                    needs.nodes * vcpus,
+                ));
+            }
+            None => warn!(
+                "no vCPU quota check for the instance type '{}'",
+                needs.instance_type
+            ),
+        }
+    }
+    if needs.elastic_ips > 0 {
+        let in_use = ec2_manager.count_addresses().await?;
+        usages.push((Quota::ElasticIps, in_use, needs.elastic_ips));
+    }
+    if needs.nlbs > 0 {
+        // no ELBv2 client in the SDK of this version
+        let in_use = parse_count(&cli::run(&[
+            "elbv2",
+            "describe-load-balancers",
+            "--region",
+            region,
+            "--query",
+            "length(LoadBalancers[?Type=='network'])",
+            "--output",
+            "json",
+        ])?)?;
+        usages.push((Quota::NetworkLoadBalancers, in_use, needs.nlbs));
+    }
+    if needs.vpcs > 0 {
+        let in_use = ec2_manager.count_vpcs().await?;
+        usages.push((Quota::Vpcs, in_use, needs.vpcs));
+    }
+
+    let mut shortfalls = Vec::new();
+    for (quota, in_use, required) in usages {
+        let value = quota_value(region, quota)?;
+        info!(
+            "quota '{}' {} ({} in use, {} required)",
+            quota.name(),
+            value,
+            in_use,
+            required
+        );
+        if f64::from(in_use + required) > value {
+            shortfalls.push(Shortfall {
+                quota,
+                value,
+                in_use,
+                required,
+            });
+        }
+    }
+    Ok(shortfalls)
+}
+
+/// Returns the applied quota value, or the default value
+/// if the quota has never been adjusted in the account.
+fn quota_value(region: &str, quota: Quota) -> io::Result<f64> {
+    let args = |subcmd| {
+        vec![
+            "service-quotas",
+            subcmd,
+            "--region",
+            region,
+            "--service-code",
+            quota.service_code(),
+            "--quota-code",
+            quota.quota_code(),
+            "--query",
+            "Quota.Value",
+            "--output",
+            "text",
+        ]
+    };
+    let out = match cli::run(&args("get-service-quota")) {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("falling back to the default quota value ({})", e);
+            cli::run(&args("get-aws-default-service-quota"))?
+        }
+    };
+    out.trim().parse().map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid quota value '{}' ({})", out.trim(), e),
+        )
+    })
+}
+
+fn parse_count(s: &str) -> io::Result<u32> {
+    s.trim().parse().map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid count '{}' ({})", s.trim(), e),
+        )
+    })
+}
+
+/// Sums the vCPUs of the On-Demand instances under the quota,
+/// excluding the instances of the cluster, which are replaced (not added)
+/// by the "apply" of the same spec.
+fn vcpus_in_use(instances: &[ec2::Droplet], quota: Quota, id: &str) -> u32 {
+    instances
+        .iter()
+        .filter(|i| i.instance_lifecycle.as_deref() != Some("spot"))
+        .filter(|i| i.cluster_id.as_deref() != Some(id))
+        .filter(|i| Quota::from_instance_type(&i.instance_type) == Some(quota))
+        .map(|i| i.vcpus.unwrap_or(0))
+        .sum()
+}
+
+#[test]
+fn test_servicequotas() {
+    use chrono::Utc;
+
+    assert_eq!(
+        Quota::from_instance_type("c6a.large"),
+        Some(Quota::StandardVcpus)
+    );
+    assert_eq!(
+        Quota::from_instance_type("im4gn.large"),
+        Some(Quota::StandardVcpus)
+    );
+    assert_eq!(
+        Quota::from_instance_type("inf1.xlarge"),
+        Some(Quota::InfVcpus)
+    );
+    assert_eq!(
+        Quota::from_instance_type("vt1.3xlarge"),
+        Some(Quota::GVtVcpus)
+    );
+    assert_eq!(
+        Quota::from_instance_type("g5.xlarge"),
+        Some(Quota::GVtVcpus)
+    );
+    assert_eq!(
+        Quota::from_instance_type("x2idn.16xlarge"),
+        Some(Quota::XVcpus)
+    );
+    assert_eq!(Quota::from_instance_type("trn1.2xlarge"), None);
+    assert_eq!(Quota::from_instance_type("hpc6a.48xlarge"), None);
+
+    let droplet = |instance_type: &str, vcpus: u32, spot: bool, id: Option<&str>| ec2::Droplet {
+        instance_id: String::from("i-1"),
+        launched_at_utc: Utc::now(),
+        instance_state_code: 16,
+        instance_state_name: String::from("running"),
+        availability_zone: String::from("us-west-2a"),
+        public_hostname: String::new(),
+        public_ipv4: String::new(),
+        private_ipv4: String::new(),
+        asg_name: None,
+        instance_type: String::from(instance_type),
+        vcpus: Some(vcpus),
+        instance_lifecycle: if spot {
+            Some(String::from("spot"))
+        } else {
+            None
+        },
+        cluster_id: id.map(String::from),
+    };
+    let instances = vec![
+        droplet("c6a.large", 2, false, Some("my-id")),
+        droplet("m5.xlarge", 4, false, None),
+        droplet("m5.xlarge", 4, true, None),
+        droplet("g5.xlarge", 4, false, Some("other")),
+    ];
+    assert_eq!(vcpus_in_use(&instances, Quota::StandardVcpus, "my-id"), 4);
+    assert_eq!(vcpus_in_use(&instances, Quota::StandardVcpus, "other"), 6);
+    assert_eq!(vcpus_in_use(&instances, Quota::GVtVcpus, "my-id"), 4);
+    assert_eq!(parse_count("12\n").unwrap(), 12);
+    assert!(parse_count("None").is_err());
+
+    let s = Shortfall {
+        quota: Quota::ElasticIps,
+        value: 5.0,
+        in_use: 4,
+        required: 3,
+    };
+    assert_eq!(s.request_value(), 7);
+    assert_eq!(
+        s.to_string(),
+        "'EC2-VPC Elastic IPs' (service code 'ec2', quota code 'L-0263D0A3') is 5, but 4 in use + 3 required: request the increase to at least 7"
+    );
+}
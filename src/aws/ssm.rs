@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use aws_sdk_ssm::{model::CommandInvocationStatus, types::SdkError, Client};
+use aws_types::SdkConfig as AwsSdkConfig;
+use log::{info, warn};
+use tokio::time::{sleep, Instant};
+
+use crate::errors::{from_sdk, Error::Other, Result};
+
+/// Implements AWS SSM Parameter Store and Run Command manager.
+#[derive(Debug, Clone)]
+pub struct Manager {
+    #[allow(dead_code)]
+    shared_config: AwsSdkConfig,
+    cli: Client,
+}
+
+impl Manager {
+    pub fn new(shared_config: &AwsSdkConfig) -> Self {
+        let cloned = shared_config.clone();
+        let cli = Client::new(shared_config);
+        Self {
+            shared_config: cloned,
+            cli,
+        }
+    }
+
+    /// Returns the parameter value, decrypting "SecureString" parameters.
+    pub async fn get_parameter(&self, name: &str) -> Result<String> {
+        info!("fetching parameter '{}'", name);
+        let resp = self
+            .cli
+            .get_parameter()
+            .name(name)
+            .with_decryption(true)
+            .send()
+            .await
+            .map_err(|e| from_sdk(format!("failed get_parameter '{}' {:?}", name, e), &e))?;
+
+        Ok(resp
+            .parameter()
+            .and_then(|p| p.value())
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    /// Runs the shell script on the instances with "AWS-RunShellScript".
+    /// Returns the command ID.
+    /// ref. https://docs.aws.amazon.com/systems-manager/latest/APIReference/API_SendCommand.html
+    pub async fn send_command(
+        &self,
+        instance_ids: Vec<String>,
+        script: &str,
+        comment: &str,
+        timeout: Duration,
+    ) -> Result<String> {
+        info!(
+            "sending command '{}' to {} instances",
+            comment,
+            instance_ids.len()
+        );
+        let resp = self
+            .cli
+            .send_command()
+            .document_name("AWS-RunShellScript")
+            .set_instance_ids(Some(instance_ids))
+            .parameters("commands", vec![script.to_string()])
+            .parameters("executionTimeout", vec![timeout.as_secs().to_string()])
+            .comment(comment)
+            .send()
+            .await
+            .map_err(|e| from_sdk(format!("failed send_command {:?}", e), &e))?;
+
+        match resp.command().and_then(|c| c.command_id()) {
+            Some(v) => Ok(v.to_string()),
+            None => Err(Other {
+                message: String::from("no command ID in send_command response"),
+                is_retryable: false,
+            }),
+        }
+    }
+
+    /// Polls the command on the instance until it finishes, or times out.
+    /// Returns the final status and the (truncated) standard error output.
+    pub async fn poll_command(
+        &self,
+        command_id: &str,
+        instance_id: &str,
+        timeout: Duration,
+    ) -> Result<(CommandInvocationStatus, String)> {
+        let start = Instant::now();
+        loop {
+            let ret = self
+                .cli
+                .get_command_invocation()
+                .command_id(command_id)
+                .instance_id(instance_id)
+                .send()
+                .await;
+            match ret {
+                Ok(resp) => {
+                    let status = resp
+                        .status
+                        .clone()
+                        .unwrap_or(CommandInvocationStatus::Pending);
+                    match status {
+                        CommandInvocationStatus::Pending
+                        | CommandInvocationStatus::InProgress
+                        | CommandInvocationStatus::Delayed => {}
+                        _ => {
+                            let stderr = resp.standard_error_content.unwrap_or_default();
+                            return Ok((status, stderr));
+                        }
+                    }
+                }
+                // not yet registered right after "send_command"
+                Err(e) if format!("{:?}", e).contains("InvocationDoesNotExist") => {
+                    warn!(
+                        "command '{}' not yet invoked on '{}'",
+                        command_id, instance_id
+                    );
+                }
+                Err(e) => {
+                    return Err(from_sdk(
+                        format!("failed get_command_invocation {:?}", e),
+                        &e,
+                    ));
+                }
+            }
+            if start.elapsed() > timeout {
+                return Err(Other {
+                    message: format!(
+                        "command '{}' on '{}' did not finish in {:?}",
+                        command_id, instance_id, timeout
+                    ),
+                    is_retryable: true,
+                });
+            }
+            sleep(Duration::from_secs(5)).await;
+        }
+    }
+}
+
+#[inline]
+pub fn is_error_retryable<E>(e: &SdkError<E>) -> bool {
+    match e {
+        SdkError::TimeoutError(_) | SdkError::ResponseError { .. } => true,
+        SdkError::DispatchFailure(e) => e.is_timeout() || e.is_io(),
+        _ => false,
+    }
+}
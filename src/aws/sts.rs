@@ -1,9 +1,14 @@
-use aws_sdk_sts::{types::SdkError, Client};
+use aws_sdk_sts::{types::SdkError, Client, Endpoint};
 use aws_types::SdkConfig as AwsSdkConfig;
+use hyper::Uri;
 use log::info;
 use serde::{Deserialize, Serialize};
 
-use crate::errors::{Error::API, Result};
+use crate::{
+    aws::operation,
+    errors::{from_sdk, Result},
+    fips,
+};
 
 /// Implements AWS STS manager.
 #[derive(Debug, Clone)]
@@ -16,7 +21,11 @@ pub struct Manager {
 impl Manager {
     pub fn new(shared_config: &AwsSdkConfig) -> Self {
         let cloned = shared_config.clone();
-        let cli = Client::new(shared_config);
+        let cli = if fips::enabled() {
+            Client::from_conf(fips_config(shared_config))
+        } else {
+            Client::new(shared_config)
+        };
         Self {
             shared_config: cloned,
             cli,
@@ -30,10 +39,7 @@ impl Manager {
         let resp = match ret {
             Ok(v) => v,
             Err(e) => {
-                return Err(API {
-                    message: format!("failed get_caller_identity {:?}", e),
-                    is_retryable: is_error_retryable(&e),
-                });
+                return Err(from_sdk(format!("failed get_caller_identity {:?}", e), &e));
             }
         };
 
@@ -45,6 +51,20 @@ impl Manager {
     }
 }
 
+/// Overrides the endpoint with the FIPS endpoint of the region.
+fn fips_config(shared_config: &AwsSdkConfig) -> aws_sdk_sts::Config {
+    let region = shared_config
+        .region()
+        .map(|r| r.to_string())
+        .unwrap_or_default();
+    let uri: Uri = fips::sts_endpoint(&region)
+        .parse()
+        .expect("unexpected invalid FIPS endpoint");
+    aws_sdk_sts::config::Builder::from(shared_config)
+        .endpoint_resolver(Endpoint::immutable(uri))
+        .build()
+}
+
 /// Represents the caller identity.
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
 pub struct Identity {
@@ -62,6 +82,37 @@ impl Identity {
             user_id: String::from(user_id),
         }
     }
+
+    /// Returns true if both identities are the same caller, ignoring
+    /// the per-operation role session names (see "operation::assume_role").
+    pub fn is_same_caller(&self, other: &Identity) -> bool {
+        self.without_operation_session() == other.without_operation_session()
+    }
+
+    /// Returns the partition of the caller ARN (e.g., "aws", "aws-cn",
+    /// "aws-us-gov"), to build the ARNs of the other resources.
+    pub fn partition(&self) -> &str {
+        match self.role_arn.split(':').nth(1) {
+            Some(partition) if !partition.is_empty() => partition,
+            _ => "aws",
+        }
+    }
+
+    fn without_operation_session(&self) -> Self {
+        // e.g., "arn:aws:sts::123:assumed-role/ROLE/avalanche-ops-op-..."
+        // e.g., "AROAEXAMPLE:avalanche-ops-op-..."
+        let strip = |s: &str, sep: char| match s.rsplit_once(sep) {
+            Some((principal, session)) if session.starts_with(operation::SESSION_NAME_PREFIX) => {
+                principal.to_string()
+            }
+            _ => s.to_string(),
+        };
+        Self {
+            account_id: self.account_id.clone(),
+            role_arn: strip(&self.role_arn, '/'),
+            user_id: strip(&self.user_id, ':'),
+        }
+    }
 }
 
 #[inline]
@@ -72,3 +123,40 @@ pub fn is_error_retryable<E>(e: &SdkError<E>) -> bool {
         _ => false,
     }
 }
+
+#[test]
+fn test_identity() {
+    let a = Identity::new(
+        "123",
+        "arn:aws:sts::123:assumed-role/admin/avalanche-ops-op-1",
+        "AROAEXAMPLE:avalanche-ops-op-1",
+    );
+    let b = Identity::new(
+        "123",
+        "arn:aws:sts::123:assumed-role/admin/avalanche-ops-op-2",
+        "AROAEXAMPLE:avalanche-ops-op-2",
+    );
+    assert!(a.is_same_caller(&b));
+
+    // other sessions are different callers
+    let c = Identity::new(
+        "123",
+        "arn:aws:sts::123:assumed-role/admin/alice",
+        "AROAEXAMPLE:alice",
+    );
+    let d = Identity::new(
+        "123",
+        "arn:aws:sts::123:assumed-role/admin/bob",
+        "AROAEXAMPLE:bob",
+    );
+    assert!(!c.is_same_caller(&d));
+
+    assert_eq!(a.partition(), "aws");
+    let e = Identity::new(
+        "123",
+        "arn:aws-cn:sts::123:assumed-role/admin/alice",
+        "AROAEXAMPLE:alice",
+    );
+    assert_eq!(e.partition(), "aws-cn");
+    assert!(!a.is_same_caller(&c));
+}
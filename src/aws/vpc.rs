@@ -0,0 +1,272 @@
+use std::io::{self, Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of the peer CIDRs, bounded by the template conditions.
+pub const MAX_PEER_CIDRS: usize = 4;
+
+/// Represents the private connectivity from an existing (e.g., corporate) VPC,
+/// so the internal systems reach the node HTTP RPC without traversing the internet.
+/// Set either "peer_vpc_id" for the VPC peering connection, or "transit_gateway_id"
+/// for the transit gateway attachment. The VPC stack creates the routes to
+/// "peer_cidrs" and allows them the node HTTP port.
+/// The routes and the security group rules on the peer side are NOT created.
+/// Only applied when the VPC is created.
+/// ref. https://docs.aws.amazon.com/vpc/latest/peering/what-is-vpc-peering.html
+/// ref. https://docs.aws.amazon.com/vpc/latest/tgw/tgw-vpc-attachments.html
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct PrivateConnectivity {
+    /// VPC ID to peer with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peer_vpc_id: Option<String>,
+    /// AWS account ID of "peer_vpc_id", if different from the caller.
+    /// The peer account must accept the connection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peer_owner_id: Option<String>,
+    /// Region of "peer_vpc_id", if different from the cluster region.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peer_region: Option<String>,
+
+    /// Existing transit gateway ID to attach the VPC to.
+    /// Shared transit gateways must auto-accept the attachments.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transit_gateway_id: Option<String>,
+
+    /// CIDRs of the internal systems (e.g., "172.16.0.0/16"), up to "MAX_PEER_CIDRS".
+    /// Must not overlap with the cluster VPC CIDR "10.0.0.0/16".
+    #[serde(default)]
+    pub peer_cidrs: Vec<String>,
+}
+
+impl PrivateConnectivity {
+    pub fn validate(&self) -> io::Result<()> {
+        match (&self.peer_vpc_id, &self.transit_gateway_id) {
+            (Some(_), Some(_)) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'private_connectivity' sets both 'peer_vpc_id' and 'transit_gateway_id'",
+                ));
+            }
+            (None, None) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'private_connectivity' requires 'peer_vpc_id' or 'transit_gateway_id'",
+                ));
+            }
+            _ => {}
+        }
+        if self.transit_gateway_id.is_some()
+            && (self.peer_owner_id.is_some() || self.peer_region.is_some())
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'peer_owner_id' and 'peer_region' only apply to 'peer_vpc_id'",
+            ));
+        }
+        if self.peer_cidrs.is_empty() || self.peer_cidrs.len() > MAX_PEER_CIDRS {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'peer_cidrs' must have 1-{} CIDRs (got {})",
+                    MAX_PEER_CIDRS,
+                    self.peer_cidrs.len()
+                ),
+            ));
+        }
+        for cidr in self.peer_cidrs.iter() {
+            let (ip, prefix) = parse_cidr(cidr)?;
+            // cluster VPC "10.0.0.0/16"
+            let overlaps = if prefix <= 16 {
+                mask(ip, prefix) == mask(0x0a00_0000, prefix)
+            } else {
+                mask(ip, 16) == 0x0a00_0000
+            };
+            if overlaps {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "'peer_cidrs' {} overlaps with the VPC CIDR 10.0.0.0/16",
+                        cidr
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the CloudFormation VPC template parameters.
+    pub fn parameters(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![
+            ("PeerCidrs", self.peer_cidrs.join(",")),
+            ("PeerCidrsCount", self.peer_cidrs.len().to_string()),
+        ];
+        if let Some(v) = &self.peer_vpc_id {
+            params.push(("PeerVpcId", v.clone()));
+        }
+        if let Some(v) = &self.peer_owner_id {
+            params.push(("PeerOwnerId", v.clone()));
+        }
+        if let Some(v) = &self.peer_region {
+            params.push(("PeerRegion", v.clone()));
+        }
+        if let Some(v) = &self.transit_gateway_id {
+            params.push(("TransitGatewayId", v.clone()));
+        }
+        params
+    }
+}
+
+/// Represents the VPC endpoint service (PrivateLink) on the NLB,
+/// so the other AWS accounts consume the RPC endpoint from their VPCs.
+/// "apply" creates the service once the NLB is created, and syncs
+/// the allowed principals and the acceptance setting on every run.
+/// ref. https://docs.aws.amazon.com/vpc/latest/privatelink/create-endpoint-service.html
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct EndpointService {
+    /// Set "false" to accept the endpoint connections without the manual approval.
+    /// Default "true".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acceptance_required: Option<bool>,
+    /// IAM principal ARNs allowed to create the endpoints
+    /// (e.g., "arn:aws:iam::123456789012:root"), or "*" for all.
+    #[serde(default)]
+    pub allowed_principals: Vec<String>,
+}
+
+impl EndpointService {
+    pub fn acceptance_required(&self) -> bool {
+        self.acceptance_required.unwrap_or(true)
+    }
+
+    pub fn validate(&self) -> io::Result<()> {
+        for p in self.allowed_principals.iter() {
+            if p != "*" && !p.starts_with("arn:") {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "'nlb_endpoint_service.allowed_principals' must be ARNs or '*' (got '{}')",
+                        p
+                    ),
+                ));
+            }
+        }
+        if self.allowed_principals.iter().any(|p| p == "*") && !self.acceptance_required() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'nlb_endpoint_service' allows all principals ('*') without 'acceptance_required'",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the principals to add and to remove, to sync the current ones.
+    pub fn diff_principals(&self, current: &[String]) -> (Vec<String>, Vec<String>) {
+        let to_add = self
+            .allowed_principals
+            .iter()
+            .filter(|p| !current.contains(p))
+            .cloned()
+            .collect();
+        let to_remove = current
+            .iter()
+            .filter(|p| !self.allowed_principals.contains(p))
+            .cloned()
+            .collect();
+        (to_add, to_remove)
+    }
+}
+
+fn parse_cidr(cidr: &str) -> io::Result<(u32, u32)> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid IPv4 CIDR '{}'", cidr),
+        )
+    };
+    let (ip, prefix) = cidr.split_once('/').ok_or_else(invalid)?;
+    let ip: std::net::Ipv4Addr = ip.parse().map_err(|_| invalid())?;
+    let prefix: u32 = prefix.parse().map_err(|_| invalid())?;
+    if prefix > 32 {
+        return Err(invalid());
+    }
+    Ok((u32::from(ip), prefix))
+}
+
+fn mask(ip: u32, prefix: u32) -> u32 {
+    if prefix == 0 {
+        0
+    } else {
+        ip & (u32::MAX << (32 - prefix))
+    }
+}
+
+#[test]
+fn test_private_connectivity() {
+    let c = PrivateConnectivity {
+        peer_vpc_id: Some(String::from("vpc-123")),
+        peer_cidrs: vec![String::from("172.16.0.0/16")],
+        ..PrivateConnectivity::default()
+    };
+    assert!(c.validate().is_ok());
+    assert_eq!(
+        c.parameters(),
+        vec![
+            ("PeerCidrs", String::from("172.16.0.0/16")),
+            ("PeerCidrsCount", String::from("1")),
+            ("PeerVpcId", String::from("vpc-123")),
+        ]
+    );
+
+    // both
+    let mut c2 = c.clone();
+    c2.transit_gateway_id = Some(String::from("tgw-123"));
+    assert!(c2.validate().is_err());
+
+    // overlaps
+    for cidr in ["10.0.1.0/24", "10.0.0.0/8", "0.0.0.0/0"] {
+        let mut c2 = c.clone();
+        c2.peer_cidrs = vec![cidr.to_string()];
+        assert!(c2.validate().is_err(), "{}", cidr);
+    }
+    let mut c2 = c.clone();
+    c2.peer_cidrs = vec![String::from("10.1.0.0/16")];
+    assert!(c2.validate().is_ok());
+
+    let mut c2 = c;
+    c2.peer_cidrs = vec![String::from("172.16.0.0/33")];
+    assert!(c2.validate().is_err());
+}
+
+#[test]
+fn test_endpoint_service() {
+    let s = EndpointService {
+        acceptance_required: None,
+        allowed_principals: vec![
+            String::from("arn:aws:iam::123456789012:root"),
+            String::from("arn:aws:iam::210987654321:root"),
+        ],
+    };
+    assert!(s.validate().is_ok());
+    let (to_add, to_remove) = s.diff_principals(&[
+        String::from("arn:aws:iam::123456789012:root"),
+        String::from("arn:aws:iam::555555555555:root"),
+    ]);
+    assert_eq!(to_add, vec![String::from("arn:aws:iam::210987654321:root")]);
+    assert_eq!(
+        to_remove,
+        vec![String::from("arn:aws:iam::555555555555:root")]
+    );
+
+    let s = EndpointService {
+        acceptance_required: Some(false),
+        allowed_principals: vec![String::from("*")],
+    };
+    assert!(s.validate().is_err());
+    let s = EndpointService {
+        acceptance_required: None,
+        allowed_principals: vec![String::from("123456789012")],
+    };
+    assert!(s.validate().is_err());
+}
@@ -0,0 +1,90 @@
+use std::thread;
+
+use clap::ArgMatches;
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self, api_auth,
+    avalanche::avalanchego::api::auth,
+    aws::{self, envelope, kms, s3},
+};
+
+/// Attaches the API auth tokens to the node API calls of the (nested) subcommand,
+/// if the spec requires the tokens ("avalanchego_config.api_auth_required").
+/// Issues the tokens for the NLB, the current nodes, and "--http-rpc" (if any).
+/// Never fails the command, the failures are only logged.
+pub fn authorize(matches: &ArgMatches) {
+    let (name, _) = match matches.subcommand() {
+        Some(v) => v,
+        None => return,
+    };
+    // "apply" issues the tokens once the nodes are created
+    if name == crate::apply::NAME {
+        return;
+    }
+    let spec_file_path = match crate::audit::deepest_value_of(matches, "SPEC_FILE_PATH") {
+        Some(v) => v,
+        None => return,
+    };
+    let spec = match avalanche_ops::Spec::load(&spec_file_path) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    if spec.avalanchego_config.api_auth_required != Some(true) {
+        return;
+    }
+
+    let mut urls = Vec::new();
+    if let Some(v) = crate::audit::deepest_value_of(matches, "HTTP_RPC") {
+        if !v.is_empty() {
+            urls.push(v);
+        }
+    }
+    if let Some(v) = spec.endpoints.as_ref().and_then(|e| e.http_rpc.clone()) {
+        urls.push(v);
+    }
+    for node in spec.current_nodes.clone().unwrap_or_default().iter() {
+        urls.push(node.http_endpoint.clone());
+    }
+
+    let ret = thread::spawn(move || -> Result<(), String> {
+        let rt = Runtime::new().map_err(|e| e.to_string())?;
+        let password = match rt.block_on(get_password(&spec))? {
+            Some(v) => v,
+            None => return Err(String::from("no API auth password (run 'apply' first)")),
+        };
+        for url in urls.iter() {
+            match rt.block_on(auth::authorize(url, &password)) {
+                Ok(_) => info!("attached API auth token for {}", url),
+                Err(e) => warn!("failed to issue API auth token for {} ({})", url, e),
+            }
+        }
+        Ok(())
+    })
+    .join();
+    match ret {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => warn!("failed to attach API auth tokens ({})", e),
+        Err(_) => warn!("API auth thread panicked"),
+    }
+}
+
+/// Downloads and decrypts the API auth password of the cluster.
+async fn get_password(spec: &avalanche_ops::Spec) -> Result<Option<String>, String> {
+    let aws_resources = match &spec.aws_resources {
+        Some(v) => v.clone(),
+        None => return Ok(None),
+    };
+    let shared_config = aws::load_config(Some(aws_resources.region.clone()))
+        .await
+        .map_err(|e| e.to_string())?;
+    let s3_manager = s3::Manager::new(&shared_config);
+    let envelope = envelope::Envelope::new(
+        Some(kms::Manager::new(&shared_config)),
+        aws_resources.kms_cmk_id.clone(),
+    );
+    api_auth::get_password(&s3_manager, &envelope, &aws_resources.s3_bucket, &spec.id)
+        .await
+        .map_err(|e| e.to_string())
+}
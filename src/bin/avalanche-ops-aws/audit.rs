@@ -0,0 +1,123 @@
+use std::{any::Any, env, fs, thread};
+
+use clap::ArgMatches;
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self, audit,
+    aws::{self, operation, s3, sts},
+};
+
+/// Commands that only read or write local files, or only read the cluster.
+const NOT_AUDITED: [&str; 7] = [
+    crate::default_spec::NAME,
+    crate::read_spec::NAME,
+    crate::redact_spec::NAME,
+    crate::diff_spec::NAME,
+    crate::encrypt_spec::NAME,
+    crate::history::NAME,
+    crate::status::NAME,
+];
+
+/// Records the CLI operation in the audit log of the cluster.
+pub struct Recorder {
+    spec_file_path: String,
+    record: audit::Record,
+}
+
+impl Recorder {
+    /// Starts recording the operation against the spec of the (nested) subcommand.
+    /// Returns "None" for the commands without the spec or not audited.
+    pub fn start(matches: &ArgMatches, op: &operation::Operation) -> Option<Self> {
+        let (name, _) = matches.subcommand()?;
+        if NOT_AUDITED.contains(&name) {
+            return None;
+        }
+        let spec_file_path = deepest_value_of(matches, "SPEC_FILE_PATH")?;
+
+        // hashes the inputs before the command updates the spec
+        let args: Vec<String> = env::args().skip(1).collect();
+        let spec_file = fs::read(&spec_file_path).ok();
+        let inputs_sha256 = audit::inputs_sha256(&args, spec_file.as_deref());
+
+        // the cluster ID is set once finished, since the spec
+        // may be encrypted and is only loaded after the command
+        Some(Self {
+            spec_file_path,
+            record: audit::Record::new("", op, &inputs_sha256),
+        })
+    }
+
+    /// Writes the record with the result of the operation.
+    /// Never fails the operation, the failures are only logged.
+    pub fn finish(mut self, panic: Option<&(dyn Any + Send)>) {
+        let error = panic.map(|p| {
+            if let Some(s) = p.downcast_ref::<String>() {
+                s.clone()
+            } else if let Some(s) = p.downcast_ref::<&str>() {
+                s.to_string()
+            } else {
+                String::from("unknown panic")
+            }
+        });
+        self.record.finish(error);
+
+        let ret = thread::spawn(move || -> Result<(), String> {
+            let spec =
+                avalanche_ops::Spec::load(&self.spec_file_path).map_err(|e| e.to_string())?;
+            let aws_resources = match &spec.aws_resources {
+                Some(v) => v.clone(),
+                None => return Ok(()),
+            };
+            self.record.cluster_id = spec.id.clone();
+
+            let rt = Runtime::new().map_err(|e| e.to_string())?;
+            rt.block_on(async move {
+                let shared_config = aws::load_config(Some(aws_resources.region.clone()))
+                    .await
+                    .map_err(|e| e.to_string())?;
+                self.record.caller_arn = sts::Manager::new(&shared_config)
+                    .get_identity()
+                    .await
+                    .ok()
+                    .map(|v| v.role_arn);
+
+                let s3_manager = s3::Manager::new(&shared_config);
+                audit::put(&s3_manager, &aws_resources.s3_bucket, &self.record)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                info!(
+                    "recorded operation '{}' ({}) in the audit log",
+                    self.record.operation_id,
+                    self.record
+                        .outcome
+                        .map(|v| v.to_string())
+                        .unwrap_or_default()
+                );
+                Ok(())
+            })
+        })
+        .join();
+        match ret {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("failed to record the operation in the audit log ({})", e),
+            Err(_) => warn!("audit log thread panicked"),
+        }
+    }
+}
+
+/// Returns the argument value (e.g., "SPEC_FILE_PATH") of the deepest subcommand, if any.
+pub fn deepest_value_of(matches: &ArgMatches, id: &str) -> Option<String> {
+    let mut found = None;
+    let mut cur = matches;
+    while let Some((_, sub_matches)) = cur.subcommand() {
+        if sub_matches.is_valid_arg(id) {
+            if let Some(v) = sub_matches.value_of(id) {
+                found = Some(v.to_string());
+            }
+        }
+        cur = sub_matches;
+    }
+    found
+}
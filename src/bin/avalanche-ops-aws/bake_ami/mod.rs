@@ -0,0 +1,343 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use aws_sdk_ec2::model::{InstanceStateName, Tag};
+use chrono::Utc;
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, ec2, s3, ssm},
+};
+
+pub const NAME: &str = "bake-ami";
+
+/// Same as the default in the ASG template.
+const BASE_IMAGE_ID_SSM_PARAMETER: &str =
+    "/aws/service/canonical/ubuntu/server/20.04/stable/current/amd64/hvm/ebs-gp2/ami-id";
+
+const DEFAULT_INSTANCE_TYPE: &str = "c5.xlarge";
+
+/// Same as "AVALANCHE_BIN_PATH" in the ASG template.
+const AVALANCHE_BIN_PATH: &str = "/usr/local/bin/avalanche";
+
+/// Max time for the builder to install the packages and the artifacts.
+const BUILD_TIMEOUT: Duration = Duration::from_secs(1800);
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Bakes the AMI with the artifacts and the tunings installed by 'avalanched install', and sets it in the spec")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load and update")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("BASE_IMAGE_ID")
+                .long("base-image-id")
+                .help("Sets the AMI to build from (default the latest Ubuntu 20.04 image)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("INSTANCE_TYPE")
+                .long("instance-type")
+                .help("Sets the instance type of the builder (default the first in 'machine.instance_types')")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .short('p')
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    base_image_id: Option<&str>,
+    instance_type: Option<&str>,
+    skip_prompt: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let mut spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+    // the builder needs the instance role, the VPC, and the uploaded artifacts
+    let not_applied = || {
+        Error::new(
+            ErrorKind::NotFound,
+            "the instance role or the VPC not found (run 'apply' first)",
+        )
+    };
+    let instance_profile_arn = aws_resources
+        .cloudformation_ec2_instance_profile_arn
+        .clone()
+        .ok_or_else(not_applied)?;
+    let security_group_id = aws_resources
+        .cloudformation_vpc_security_group_id
+        .clone()
+        .ok_or_else(not_applied)?;
+    let subnet_id = aws_resources
+        .cloudformation_vpc_public_subnet_ids
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .next()
+        .ok_or_else(not_applied)?;
+    let instance_type = instance_type
+        .map(String::from)
+        .or_else(|| {
+            spec.machine
+                .instance_types
+                .clone()
+                .and_then(|v| v.into_iter().next())
+        })
+        .unwrap_or_else(|| String::from(DEFAULT_INSTANCE_TYPE));
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let ec2_manager = ec2::Manager::new(&shared_config);
+
+    let base_image_id = match base_image_id {
+        Some(v) => v.to_string(),
+        None => rt
+            .block_on(ssm::Manager::new(&shared_config).get_parameter(BASE_IMAGE_ID_SSM_PARAMETER))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?,
+    };
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\nbaking AMI for '{}' from '{}' with the builder {} (replaces {:?})\n",
+            spec.id, base_image_id, instance_type, spec.machine.image_id
+        )),
+        ResetColor
+    )?;
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to bake the AMI.",
+            "Yes, let's launch the builder and bake the AMI!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'bake-ami' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    let name = format!("{}-{}", spec.id, Utc::now().format("%Y%m%d%H%M%S"));
+    // "avalanched install" reads the cluster ID and the paths from the tags
+    let tags = vec![
+        Tag::builder()
+            .key("Name")
+            .value(format!("{}-ami-builder", spec.id))
+            .build(),
+        Tag::builder().key("ID").value(&spec.id).build(),
+        Tag::builder()
+            .key("S3_BUCKET_NAME")
+            .value(&aws_resources.s3_bucket)
+            .build(),
+        Tag::builder()
+            .key("AVALANCHE_BIN_PATH")
+            .value(AVALANCHE_BIN_PATH)
+            .build(),
+        Tag::builder().key("BAKE_AMI").value(&name).build(),
+    ];
+
+    info!(
+        "STEP: launching the builder instance from '{}'",
+        base_image_id
+    );
+    let instance_id = rt
+        .block_on(ec2_manager.run_builder_instance(
+            &base_image_id,
+            &instance_type,
+            &subnet_id,
+            &security_group_id,
+            &instance_profile_arn,
+            &user_data(&aws_resources.s3_bucket, &spec.id),
+            tags.clone(),
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    let baked = bake(&rt, &ec2_manager, &instance_id, &name, tags);
+    // the image (if any) no longer needs the builder
+    if let Err(e) = rt.block_on(ec2_manager.terminate_instance(&instance_id)) {
+        warn!(
+            "failed to terminate the builder '{}' ({}), terminate manually",
+            instance_id,
+            e.message()
+        );
+    }
+    let image_id = baked?;
+
+    info!("STEP: updating the spec with '{}'", image_id);
+    spec.machine.image_id = Some(image_id.clone());
+    spec.sync(spec_file_path)?;
+    let s3_manager = s3::Manager::new(&shared_config);
+    rt.block_on(s3_manager.put_bytes(
+        spec.encode_state_yaml()?.into_bytes(),
+        Arc::new(aws_resources.s3_bucket.clone()),
+        Arc::new(avalanche_ops::StorageNamespace::ConfigFile(spec.id.clone()).encode()),
+    ))
+    .expect("failed put_object ConfigFile");
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\nbaked AMI '{}' ('{}'), set as 'machine.image_id' in {}\n",
+            image_id, name, spec_file_path
+        )),
+        ResetColor
+    )?;
+    println!("# the new instances boot from the AMI once the ASGs are re-created");
+    println!(
+        "aws ec2 describe-images --region {} --image-ids {}",
+        aws_resources.region, image_id
+    );
+    Ok(())
+}
+
+/// Waits for the builder to install and shut itself down,
+/// and creates the image from the stopped builder.
+fn bake(
+    rt: &Runtime,
+    ec2_manager: &ec2::Manager,
+    instance_id: &str,
+    name: &str,
+    tags: Vec<Tag>,
+) -> io::Result<String> {
+    let start = Instant::now();
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let instance = rt
+            .block_on(ec2_manager.describe_instance(instance_id))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+        let state = instance
+            .state()
+            .and_then(|s| s.name())
+            .cloned()
+            .unwrap_or_else(|| InstanceStateName::Unknown(String::new()));
+        match state {
+            InstanceStateName::Stopped => break,
+            InstanceStateName::Terminated | InstanceStateName::ShuttingDown => {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!("builder '{}' terminated before the install", instance_id),
+                ));
+            }
+            _ => {}
+        }
+        if start.elapsed() > BUILD_TIMEOUT {
+            return Err(Error::new(
+                ErrorKind::TimedOut,
+                format!(
+                    "builder '{}' not stopped in {:?} (see /var/log/cloud-init-output.log)",
+                    instance_id, BUILD_TIMEOUT
+                ),
+            ));
+        }
+        info!(
+            "waiting for the builder '{}' to install ({:?}, {:?})",
+            instance_id,
+            state,
+            start.elapsed()
+        );
+    }
+
+    info!("STEP: creating the image '{}'", name);
+    rt.block_on(ec2_manager.create_image(instance_id, name, tags, POLL_INTERVAL))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))
+}
+
+/// Installs the same packages as the ASG template and runs the install
+/// phase of "avalanched", and shuts down (stops) only if all succeed.
+fn user_data(s3_bucket: &str, id: &str) -> String {
+    format!(
+        "#!/bin/bash
+set -xeu
+
+export DEBIAN_FRONTEND=noninteractive
+sudo apt-get update -y && sudo apt-get upgrade -y \\
+&& sudo apt-get install -y \\
+    curl wget unzip zip gzip tar libssl-dev \\
+    python3-pip python-setuptools chrony
+
+curl https://awscli.amazonaws.com/awscli-exe-linux-x86_64.zip -o awscli.v2.zip
+unzip awscli.v2.zip
+sudo ./aws/install
+sudo snap install amazon-ssm-agent --classic
+
+mkdir -p /tmp/install-cloudwatch-logs
+pushd /tmp/install-cloudwatch-logs
+wget https://s3.amazonaws.com/amazoncloudwatch-agent/ubuntu/amd64/latest/amazon-cloudwatch-agent.deb
+sudo dpkg -i -E ./amazon-cloudwatch-agent.deb
+popd
+
+aws s3 cp s3://{}/{} /tmp/avalanched
+chmod +x /tmp/avalanched
+sudo mv /tmp/avalanched /usr/local/bin/avalanched
+sudo /usr/local/bin/avalanched install
+
+sudo shutdown -h now
+",
+        s3_bucket,
+        avalanche_ops::StorageNamespace::AvalanchedBin(id.to_string()).encode()
+    )
+}
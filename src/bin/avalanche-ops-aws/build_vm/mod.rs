@@ -0,0 +1,482 @@
+use std::{
+    fs,
+    io::{self, stdout, Error, ErrorKind},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use aws_sdk_ec2::model::{InstanceStateName, Tag};
+use chrono::Utc;
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::vm,
+    aws::{self, ec2, s3, ssm},
+    utils::{compress, hash, provenance, random},
+    vm_build,
+};
+
+pub const NAME: &str = "build-vm";
+
+/// Same as "bake-ami".
+const BASE_IMAGE_ID_SSM_PARAMETER: &str =
+    "/aws/service/canonical/ubuntu/server/20.04/stable/current/amd64/hvm/ebs-gp2/ami-id";
+
+const DEFAULT_INSTANCE_TYPE: &str = "c5.2xlarge";
+
+/// Max time for the builder to install the toolchain and build.
+const BUILD_TIMEOUT: Duration = Duration::from_secs(3600);
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Builds the subnet VM from the git repository in a temporary builder instance, and uploads the plugin with the checksum to the cluster")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("REPO")
+                .long("repo")
+                .help("Sets the git repository URL of the VM (e.g., https://github.com/ava-labs/subnet-evm.git)")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("REF")
+                .long("ref")
+                .help("Sets the git tag, branch, or commit to build")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("VM_NAME")
+                .long("vm-name")
+                .help("Sets the VM name to derive the VM ID (the plugin file name) from")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("VM_ID")
+                .long("vm-id")
+                .help("Sets the VM ID (the plugin file name), instead of '--vm-name'")
+                .required(false)
+                .takes_value(true)
+                .conflicts_with("VM_NAME")
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("BUILD_COMMAND")
+                .long("build-command")
+                .help("Sets the build command in the repository root ('{output}' for the plugin path)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value(vm_build::DEFAULT_BUILD_COMMAND),
+        )
+        .arg(
+            Arg::new("GO_VERSION")
+                .long("go-version")
+                .help("Sets the Go toolchain version of the builder")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value(vm_build::DEFAULT_GO_VERSION),
+        )
+        .arg(
+            Arg::new("INSTANCE_TYPE")
+                .long("instance-type")
+                .help("Sets the instance type of the builder")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value(DEFAULT_INSTANCE_TYPE),
+        )
+        .arg(
+            Arg::new("NOTIFY_AVALANCHED")
+                .long("notify-avalanched")
+                .help("Set to notify avalanched to pull the new plugin (restarts the nodes)")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("FORCE")
+                .long("force")
+                .help("Runs even outside of the maintenance window in the spec")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(crate::quorum::skip_quorum_check_arg())
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .short('p')
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub struct Options {
+    pub repo: String,
+    pub git_ref: String,
+    pub vm_name: Option<String>,
+    pub vm_id: Option<String>,
+    pub build_command: String,
+    pub go_version: String,
+    pub instance_type: String,
+    pub notify_avalanched: bool,
+    pub force: bool,
+    pub skip_quorum_check: bool,
+    pub skip_prompt: bool,
+}
+
+pub fn execute(log_level: &str, spec_file_path: &str, opts: Options) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    vm_build::validate(&opts.repo, &opts.git_ref, &opts.build_command)?;
+    let vm_id = match (&opts.vm_id, &opts.vm_name) {
+        (Some(v), _) => v.clone(),
+        (None, Some(name)) => vm::id_from_str(name)?,
+        (None, None) => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "either '--vm-name' or '--vm-id' is required",
+            ))
+        }
+    };
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    // the builder has no signing key
+    if spec.install_artifacts.require_signed_artifacts {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "'install_artifacts.require_signed_artifacts' refuses the unsigned built plugins (sign and use 'sync-artifacts')",
+        ));
+    }
+    if opts.notify_avalanched {
+        spec.check_maintenance_window("build-vm --notify-avalanched", opts.force)?;
+        crate::quorum::check(
+            &spec,
+            "build-vm --notify-avalanched",
+            opts.skip_quorum_check,
+        )?;
+    }
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+    // the builder uploads with the instance role, in the cluster VPC
+    let not_applied = || {
+        Error::new(
+            ErrorKind::NotFound,
+            "the instance role or the VPC not found (run 'apply' first)",
+        )
+    };
+    let instance_profile_arn = aws_resources
+        .cloudformation_ec2_instance_profile_arn
+        .clone()
+        .ok_or_else(not_applied)?;
+    let security_group_id = aws_resources
+        .cloudformation_vpc_security_group_id
+        .clone()
+        .ok_or_else(not_applied)?;
+    let subnet_id = aws_resources
+        .cloudformation_vpc_public_subnet_ids
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .next()
+        .ok_or_else(not_applied)?;
+
+    let plugin_s3_key = format!(
+        "{}/{}{}",
+        avalanche_ops::StorageNamespace::PluginsDir(spec.id.clone()).encode(),
+        vm_id,
+        compress::Encoder::Zstd(3).ext()
+    );
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\nbuilding '{}' at '{}' with the builder {} into 's3://{}/{}'\n",
+            opts.repo, opts.git_ref, opts.instance_type, aws_resources.s3_bucket, plugin_s3_key
+        )),
+        ResetColor
+    )?;
+    if !opts.skip_prompt {
+        let options = &[
+            "No, I am not ready to build the VM.",
+            "Yes, let's launch the builder and build the VM!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'build-vm' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let ec2_manager = ec2::Manager::new(&shared_config);
+    let s3_manager = s3::Manager::new(&shared_config);
+    let base_image_id = rt
+        .block_on(ssm::Manager::new(&shared_config).get_parameter(BASE_IMAGE_ID_SSM_PARAMETER))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    let build_id = Utc::now().format("%Y%m%d%H%M%S").to_string();
+    let tags = vec![
+        Tag::builder()
+            .key("Name")
+            .value(format!("{}-vm-builder", spec.id))
+            .build(),
+        Tag::builder().key("ID").value(&spec.id).build(),
+        Tag::builder().key("BUILD_VM").value(&build_id).build(),
+    ];
+    info!("STEP: launching the builder instance for '{}'", build_id);
+    let instance_id = rt
+        .block_on(ec2_manager.run_builder_instance(
+            &base_image_id,
+            &opts.instance_type,
+            &subnet_id,
+            &security_group_id,
+            &instance_profile_arn,
+            &vm_build::user_data(
+                &aws_resources.s3_bucket,
+                &spec.id,
+                &build_id,
+                &opts.repo,
+                &opts.git_ref,
+                &opts.build_command,
+                &opts.go_version,
+            ),
+            tags,
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    let stopped = wait_stopped(&rt, &ec2_manager, &instance_id);
+    // the outputs (if any) are in S3
+    if let Err(e) = rt.block_on(ec2_manager.terminate_instance(&instance_id)) {
+        warn!(
+            "failed to terminate the builder '{}' ({}), terminate manually",
+            instance_id,
+            e.message()
+        );
+    }
+    stopped?;
+
+    info!("STEP: downloading the build outputs of '{}'", build_id);
+    let s3_bucket = Arc::new(aws_resources.s3_bucket.clone());
+    let get_string = |file_name: &str| -> io::Result<String> {
+        let key = vm_build::build_file_key(&spec.id, &build_id, file_name);
+        let d = rt
+            .block_on(s3_manager.get_bytes(&s3_bucket, &key, None))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!(
+                        "build '{}' failed, '{}' not found (see the builder console output of '{}')",
+                        build_id, key, instance_id
+                    ),
+                )
+            })?;
+        Ok(String::from_utf8_lossy(&d).trim().to_string())
+    };
+    let sha256 = get_string(vm_build::CHECKSUM_FILE)?;
+    let commit = get_string(vm_build::COMMIT_FILE)?;
+
+    let tmp_plugin_path = random::tmp_path(15, None)?;
+    rt.block_on(s3_manager.get_object(
+        s3_bucket.clone(),
+        Arc::new(vm_build::build_file_key(
+            &spec.id,
+            &build_id,
+            vm_build::PLUGIN_FILE,
+        )),
+        Arc::new(tmp_plugin_path.clone()),
+    ))
+    .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    let downloaded = hash::sha256_file(&tmp_plugin_path)?;
+    if downloaded != sha256 {
+        fs::remove_file(&tmp_plugin_path)?;
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "plugin checksum mismatch (built {}, downloaded {})",
+                sha256, downloaded
+            ),
+        ));
+    }
+
+    info!("STEP: uploading the plugin to '{}'", plugin_s3_key);
+    let tmp_compressed_path = random::tmp_path(15, Some(compress::Encoder::Zstd(3).ext()))?;
+    compress::pack_file(
+        &tmp_plugin_path,
+        &tmp_compressed_path,
+        compress::Encoder::Zstd(3),
+    )?;
+    // "avalanched" verifies the unpacked plugin against the checksum
+    let plugin_provenance = provenance::Provenance {
+        sha256: sha256.clone(),
+        signature: None,
+    };
+    rt.block_on(s3_manager.put_object_with_metadata(
+        Arc::new(tmp_compressed_path.clone()),
+        s3_bucket.clone(),
+        Arc::new(plugin_s3_key.clone()),
+        Some(plugin_provenance.to_metadata()),
+    ))
+    .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    fs::remove_file(&tmp_plugin_path)?;
+    fs::remove_file(&tmp_compressed_path)?;
+
+    info!("STEP: updating the plugins manifest");
+    let manifest_key = avalanche_ops::StorageNamespace::PluginsManifest(spec.id.clone()).encode();
+    let mut manifest = match rt
+        .block_on(s3_manager.get_bytes(&s3_bucket, &manifest_key, None))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?
+    {
+        Some(d) => vm_build::Manifest::decode(&d)?,
+        None => vm_build::Manifest::default(),
+    };
+    let replaced = manifest.upsert(vm_build::Plugin {
+        vm_id: vm_id.clone(),
+        vm_name: opts.vm_name.clone(),
+        repo: opts.repo.clone(),
+        git_ref: opts.git_ref.clone(),
+        commit: commit.clone(),
+        sha256: sha256.clone(),
+        s3_key: plugin_s3_key.clone(),
+        built_at: Utc::now().to_rfc3339(),
+    });
+    rt.block_on(s3_manager.put_bytes(
+        manifest.encode_yaml()?.into_bytes(),
+        s3_bucket.clone(),
+        Arc::new(manifest_key.clone()),
+    ))
+    .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    if opts.notify_avalanched {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print("\n\n\nSTEP: notify avalanched to pull the new plugin\n"),
+            ResetColor
+        )?;
+        crate::sync_artifacts::notify(&rt, &s3_manager, &spec)?;
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\nbuilt '{}' ({}) at {} (sha256 {})\n",
+            vm_id, opts.git_ref, commit, sha256
+        )),
+        ResetColor
+    )?;
+    if let Some(p) = replaced {
+        println!(
+            "# replaced the plugin built at {} ({})",
+            p.commit, p.git_ref
+        );
+    }
+    println!("# plugins manifest");
+    println!(
+        "aws s3 cp s3://{}/{} -",
+        aws_resources.s3_bucket, manifest_key
+    );
+    if !opts.notify_avalanched {
+        println!("# the new nodes install the plugin, or notify the running nodes with '--notify-avalanched'");
+    }
+    Ok(())
+}
+
+/// Waits for the builder to build and shut itself down.
+fn wait_stopped(rt: &Runtime, ec2_manager: &ec2::Manager, instance_id: &str) -> io::Result<()> {
+    let start = Instant::now();
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let instance = rt
+            .block_on(ec2_manager.describe_instance(instance_id))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+        let state = instance
+            .state()
+            .and_then(|s| s.name())
+            .cloned()
+            .unwrap_or_else(|| InstanceStateName::Unknown(String::new()));
+        match state {
+            InstanceStateName::Stopped => return Ok(()),
+            InstanceStateName::Terminated | InstanceStateName::ShuttingDown => {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!("builder '{}' terminated before the build", instance_id),
+                ));
+            }
+            _ => {}
+        }
+        if start.elapsed() > BUILD_TIMEOUT {
+            return Err(Error::new(
+                ErrorKind::TimedOut,
+                format!(
+                    "builder '{}' not stopped in {:?} (see /var/log/cloud-init-output.log)",
+                    instance_id, BUILD_TIMEOUT
+                ),
+            ));
+        }
+        info!(
+            "waiting for the builder '{}' to build ({:?}, {:?})",
+            instance_id,
+            state,
+            start.elapsed()
+        );
+    }
+}
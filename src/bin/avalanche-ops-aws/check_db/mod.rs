@@ -0,0 +1,235 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    time::Duration,
+};
+
+use aws_sdk_ssm::model::CommandInvocationStatus;
+use chrono::Utc;
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, s3, ssm},
+    db_check,
+};
+
+pub const NAME: &str = "check-db";
+
+/// Time to read the whole database on the node.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(4 * 60 * 60);
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Runs 'avalanched check-db' on the node via SSM to verify the database offline, and prints the report")
+        .arg(
+            Arg::new("NODE")
+                .help("Sets the node ID (or the instance ID) to check")
+                .required(true)
+                .index(1)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("DB_DIR")
+                .long("db-dir")
+                .help("Sets the database directory on the node to check (e.g., the mounted cloned volume), instead of the live database")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("STOP_NODE")
+                .long("stop-node")
+                .help("Stops avalanchego on the node during the check of the live database")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .short('p')
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    node: &str,
+    db_dir: Option<&str>,
+    stop_node: bool,
+    skip_prompt: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+    let target = spec
+        .current_nodes
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|n| n.node_id == node || n.machine_id == node)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("node '{}' not found in the spec current nodes", node),
+            )
+        })?;
+    if db_dir.is_none() && !stop_node {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "the live database is in use (use '--stop-node', or '--db-dir' of the cloned volume)",
+        ));
+    }
+
+    if db_dir.is_none() {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Red),
+            Print(format!(
+                "\nstopping '{}' ({}) during the check, the node is offline until the whole database is read\n",
+                target.node_id, target.machine_id
+            )),
+            ResetColor
+        )?;
+        if !skip_prompt {
+            let options = &[
+                "No, I am not ready to stop the node.",
+                "Yes, let's stop the node and check the database!",
+            ];
+            let selected = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt("Select your 'check-db' option")
+                .items(&options[..])
+                .default(0)
+                .interact()
+                .unwrap();
+            if selected == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let ssm_manager = ssm::Manager::new(&shared_config);
+    let s3_manager = s3::Manager::new(&shared_config);
+
+    let report_name = format!(
+        "{}-{}",
+        Utc::now().format("%Y%m%d%H%M%S"),
+        target.machine_id
+    );
+    info!("STEP: checking the database on '{}'", target.machine_id);
+    let command_id = rt
+        .block_on(ssm_manager.send_command(
+            vec![target.machine_id.clone()],
+            &db_check::check_script(&report_name, db_dir, stop_node),
+            &format!("{} {} {}", NAME, spec.id, report_name),
+            COMMAND_TIMEOUT,
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    let (status, stderr) = rt
+        .block_on(ssm_manager.poll_command(&command_id, &target.machine_id, COMMAND_TIMEOUT))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    if status != CommandInvocationStatus::Success {
+        // corruptions fail the command, after the report is uploaded
+        warn!("'avalanched check-db' exited with {:?}", status);
+    }
+
+    let s3_key = db_check::Report::s3_key(&spec.id, &report_name);
+    let report = match rt
+        .block_on(s3_manager.get_bytes(&aws_resources.s3_bucket, &s3_key, None))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?
+    {
+        Some(d) => db_check::Report::decode_json(&d)?,
+        None => {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "no report uploaded by 'avalanched check-db' ({:?}, {})",
+                    status,
+                    stderr.trim()
+                ),
+            ));
+        }
+    };
+
+    let color = if report.is_healthy() {
+        Color::Green
+    } else {
+        Color::Red
+    };
+    execute!(
+        stdout(),
+        SetForegroundColor(color),
+        Print(format!(
+            "\n'{}' ({:?}) on '{}': {} databases, {} files, {} bytes read in {} seconds, {} corruptions\n",
+            report.db_dir,
+            report.source,
+            target.node_id,
+            report.scan.databases,
+            report.scan.files,
+            report.scan.bytes,
+            report.elapsed_seconds,
+            report.scan.findings.len()
+        )),
+        ResetColor
+    )?;
+    for f in report.scan.findings.iter() {
+        println!("  {:?} '{}': {}", f.kind, f.path, f.detail);
+    }
+    println!("\nreport in s3://{}/{}\n", aws_resources.s3_bucket, s3_key);
+
+    if !report.is_healthy() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "found {} corruptions on '{}' (replace the node, or restore the database)",
+                report.scan.findings.len(),
+                target.node_id
+            ),
+        ));
+    }
+    Ok(())
+}
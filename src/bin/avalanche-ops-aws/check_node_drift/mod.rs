@@ -0,0 +1,222 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, stdout, Error, ErrorKind},
+    path::Path,
+    sync::Arc,
+};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::node,
+    aws::{self, s3},
+    utils::{compress, hash, provenance},
+};
+
+pub const NAME: &str = "check-node-drift";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Lists the nodes whose avalanchego config, binary, or plugins differ from the spec (using heartbeats)")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("FAIL_ON_DRIFT")
+                .long("fail-on-drift")
+                .help("Exits with an error if any node drifted (e.g., for CI)")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(log_level: &str, spec_file_path: &str, fail_on_drift: bool) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+    let s3_bucket = Arc::new(aws_resources.s3_bucket.clone());
+
+    let (expected_bin_sha256, expected_plugins) =
+        expected_artifacts(&rt, &s3_manager, &s3_bucket, &spec)?;
+    let heartbeats = crate::fleet::fetch_heartbeats(&rt, &s3_manager, s3_bucket, &spec.id)?;
+
+    let mut drifted = 0;
+    for hb in heartbeats.iter() {
+        let kind = if hb.kind == node::Kind::Anchor.as_str() {
+            node::Kind::Anchor
+        } else {
+            node::Kind::NonAnchor
+        };
+        let (expected_config, _) = spec.node_configs(&kind);
+        let drifts = hb.drift(
+            &expected_config,
+            expected_bin_sha256.as_deref(),
+            &expected_plugins,
+        )?;
+        if drifts.is_empty() {
+            info!("{} ({}) matches the spec", hb.node_id, hb.machine_id);
+            continue;
+        }
+        drifted += 1;
+
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Red),
+            Print(format!(
+                "\n{} ({}, {}) drifted in {} fields\n",
+                hb.node_id,
+                hb.machine_id,
+                hb.kind,
+                drifts.len()
+            )),
+            ResetColor
+        )?;
+        for d in drifts.iter() {
+            println!(
+                "  {}: expected '{}', actual '{}'",
+                d.field, d.expected, d.actual
+            );
+        }
+    }
+    println!();
+
+    let expected = spec.machine.anchor_nodes.unwrap_or(0) + spec.machine.non_anchor_nodes;
+    if (heartbeats.len() as u32) < expected {
+        warn!(
+            "{} heartbeats found (expected {} nodes in spec), missing nodes not checked",
+            heartbeats.len(),
+            expected
+        );
+    }
+    if drifted > 0 && fail_on_drift {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "{} of {} nodes drifted from the spec",
+                drifted,
+                heartbeats.len()
+            ),
+        ));
+    }
+    info!(
+        "'check-node-drift' found {} of {} nodes drifted",
+        drifted,
+        heartbeats.len()
+    );
+    Ok(())
+}
+
+/// Expected avalanchego binary checksum and plugin checksums (if any) with the file name.
+type ExpectedArtifacts = (Option<String>, BTreeMap<String, Option<String>>);
+
+/// Returns the expected avalanchego binary checksum and plugin checksums.
+/// Prefers the local install artifacts in the spec, and falls back
+/// to the provenance of the uploaded artifacts in S3.
+fn expected_artifacts(
+    rt: &Runtime,
+    s3_manager: &s3::Manager,
+    s3_bucket: &Arc<String>,
+    spec: &avalanche_ops::Spec,
+) -> io::Result<ExpectedArtifacts> {
+    let head_sha256 = |s3_key: &str| -> io::Result<Option<String>> {
+        let metadata = rt
+            .block_on(s3_manager.head_object_metadata(s3_bucket, s3_key))
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("failed head_object_metadata {}", e),
+                )
+            })?;
+        Ok(metadata
+            .as_ref()
+            .and_then(provenance::Provenance::from_metadata)
+            .map(|p| p.sha256))
+    };
+
+    let avalanchego_bin = &spec.install_artifacts.avalanchego_bin;
+    let bin_sha256 = if Path::new(avalanchego_bin).exists() {
+        Some(hash::sha256_file(avalanchego_bin)?)
+    } else {
+        head_sha256(
+            &avalanche_ops::StorageNamespace::AvalancheBinCompressed(spec.id.clone()).encode(),
+        )?
+    };
+
+    let mut plugins = BTreeMap::new();
+    let plugins_dir = spec
+        .install_artifacts
+        .plugins_dir
+        .clone()
+        .unwrap_or_default();
+    if !plugins_dir.is_empty() && Path::new(&plugins_dir).exists() {
+        for entry in fs::read_dir(&plugins_dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name().to_str().unwrap().to_string();
+            if provenance::is_provenance_file(&file_name) {
+                continue;
+            }
+            plugins.insert(
+                file_name,
+                Some(hash::sha256_file(entry.path().to_str().unwrap())?),
+            );
+        }
+    } else {
+        let objects = rt
+            .block_on(s3_manager.list_objects(
+                s3_bucket.clone(),
+                Some(Arc::new(s3::append_slash(
+                    &avalanche_ops::StorageNamespace::PluginsDir(spec.id.clone()).encode(),
+                ))),
+            ))
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed list_objects {}", e)))?;
+        for obj in objects.iter() {
+            let s3_key = obj.key().unwrap();
+            // e.g., "[ID]/install/plugins/evm.zstd" is unpacked to "evm"
+            let file_name = Path::new(s3_key).file_name().unwrap().to_str().unwrap();
+            let file_name = file_name.trim_end_matches(compress::Encoder::Zstd(3).ext());
+            plugins.insert(file_name.to_string(), head_sha256(s3_key)?);
+        }
+    }
+
+    Ok((bin_sha256, plugins))
+}
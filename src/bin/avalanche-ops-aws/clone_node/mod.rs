@@ -0,0 +1,283 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    time::Duration,
+};
+
+use aws_sdk_ec2::model::Tag;
+use chrono::Utc;
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, ec2},
+};
+
+pub const NAME: &str = "clone-node";
+
+/// Device name of the data volume in the launch template.
+const DATA_VOLUME_DEVICE_NAME: &str = "/dev/xvdb";
+
+/// Mount path of the data volume, same as the node.
+const DATA_VOLUME_PATH: &str = "/avalanche-data";
+
+const MAX_EXPIRES_IN_HOURS: u64 = 7 * 24;
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Launches a standalone non-validating instance from a snapshot of the node data volume, for debugging")
+        .arg(
+            Arg::new("NODE_ID")
+                .help("Sets the node ID (or the instance ID) to clone")
+                .required(true)
+                .index(1)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("EXPIRES_IN_HOURS")
+                .long("expires-in-hours")
+                .short('e')
+                .help("Sets the hours until the clone terminates itself (up to 168, 7 days)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("24"),
+        )
+        .arg(
+            Arg::new("INSTANCE_TYPE")
+                .long("instance-type")
+                .help("Sets the instance type of the clone (default the same as the node)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .short('p')
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    node_id: &str,
+    expires_in_hours: u64,
+    instance_type: Option<&str>,
+    skip_prompt: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    if expires_in_hours == 0 || expires_in_hours > MAX_EXPIRES_IN_HOURS {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "'--expires-in-hours' {} not in [1, {}]",
+                expires_in_hours, MAX_EXPIRES_IN_HOURS
+            ),
+        ));
+    }
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+
+    let node = spec
+        .current_nodes
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|n| n.node_id == node_id || n.machine_id == node_id)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("node '{}' not found in the spec current nodes", node_id),
+            )
+        })?;
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let ec2_manager = ec2::Manager::new(&shared_config);
+
+    let src = rt
+        .block_on(ec2_manager.describe_instance(&node.machine_id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    let volume_id = src
+        .block_device_mappings()
+        .unwrap_or_default()
+        .iter()
+        .find(|m| m.device_name() == Some(DATA_VOLUME_DEVICE_NAME))
+        .and_then(|m| m.ebs())
+        .and_then(|ebs| ebs.volume_id())
+        .map(String::from)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "data volume '{}' not attached to '{}'",
+                    DATA_VOLUME_DEVICE_NAME, node.machine_id
+                ),
+            )
+        })?;
+
+    let expires_at = Utc::now() + chrono::Duration::hours(expires_in_hours as i64);
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\ncloning node {} ({}, volume {}), expires at {}\n",
+            node.node_id,
+            node.machine_id,
+            volume_id,
+            expires_at.to_rfc3339()
+        )),
+        ResetColor
+    )?;
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to clone the node.",
+            "Yes, let's snapshot the volume and launch the clone!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'clone-node' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    let name = format!(
+        "{}-clone-{}",
+        spec.id,
+        node.node_id
+            .trim_start_matches("NodeID-")
+            .chars()
+            .take(8)
+            .collect::<String>()
+    );
+    let tags = vec![
+        Tag::builder().key("Name").value(&name).build(),
+        Tag::builder().key("CLONE_OF_ID").value(&spec.id).build(),
+        Tag::builder()
+            .key("CLONE_OF_INSTANCE_ID")
+            .value(&node.machine_id)
+            .build(),
+        Tag::builder()
+            .key("CLONE_OF_NODE_ID")
+            .value(&node.node_id)
+            .build(),
+        Tag::builder()
+            .key("AUTO_EXPIRE_AT")
+            .value(expires_at.to_rfc3339())
+            .build(),
+    ];
+
+    info!("STEP: snapshotting data volume '{}'", volume_id);
+    let snapshot_id = rt
+        .block_on(ec2_manager.create_snapshot(
+            &volume_id,
+            &format!("{} data volume of {}", name, node.node_id),
+            tags.clone(),
+            Duration::from_secs(30),
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    info!("STEP: launching clone instance from '{}'", snapshot_id);
+    let instance_id = rt
+        .block_on(ec2_manager.run_instance_from(
+            &src,
+            instance_type,
+            DATA_VOLUME_DEVICE_NAME,
+            &snapshot_id,
+            &user_data(expires_in_hours),
+            tags,
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\nlaunched clone '{}' from snapshot '{}'\n",
+            instance_id, snapshot_id
+        )),
+        ResetColor
+    )?;
+    println!(
+        "# the data volume is mounted at '{}', and avalanched/avalanchego are NOT installed",
+        DATA_VOLUME_PATH
+    );
+    println!("# do NOT start avalanchego with the staking certificates of the live node");
+    println!(
+        "aws ssm start-session --region {} --target {}",
+        aws_resources.region, instance_id
+    );
+    println!(
+        "# terminates itself in {} hours; the snapshot '{}' is NOT deleted",
+        expires_in_hours, snapshot_id
+    );
+    println!(
+        "aws ec2 delete-snapshot --region {} --snapshot-id {}",
+        aws_resources.region, snapshot_id
+    );
+    Ok(())
+}
+
+/// Mounts the cloned data volume without installing the node software,
+/// and schedules the shutdown (which terminates the instance) at the expiry.
+fn user_data(expires_in_hours: u64) -> String {
+    format!(
+        "#!/bin/bash
+set -x
+shutdown -h +{}
+mkdir -p {}
+until [ -b /dev/nvme1n1 ]; do sleep 5; done
+mount /dev/nvme1n1 {} -t ext4
+",
+        expires_in_hours * 60,
+        DATA_VOLUME_PATH,
+        DATA_VOLUME_PATH
+    )
+}
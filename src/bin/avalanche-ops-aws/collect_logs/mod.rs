@@ -0,0 +1,302 @@
+use std::{
+    fs,
+    io::{self, stdout, Error, ErrorKind},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+use aws_sdk_ssm::model::CommandInvocationStatus;
+use chrono::Utc;
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+use walkdir::WalkDir;
+
+use avalanche_ops::{
+    self,
+    avalanche::node,
+    aws::{self, s3, ssm},
+    support_bundle,
+    utils::{compress, random},
+};
+
+pub const NAME: &str = "collect-logs";
+
+/// Time for the collection script on each node.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Collects the node logs and metrics via SSM into a single redacted archive (e.g., for upstream bug reports)")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("NODES")
+                .long("nodes")
+                .short('n')
+                .help("Sets the comma-separated node IDs (or instance IDs) to collect from (default all current nodes)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SINCE")
+                .long("since")
+                .help("Sets the log window (e.g., '30m', '2h', '1d')")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("2h"),
+        )
+        .arg(
+            Arg::new("OUTPUT_FILE_PATH")
+                .long("output-file-path")
+                .short('o')
+                .help("The archive to write (default '[ID]-logs-[TIMESTAMP].tar.gz' in the current directory)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    nodes: Option<&str>,
+    since: &str,
+    output_file_path: Option<&str>,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let since_minutes = support_bundle::parse_since(since)?;
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+
+    let current_nodes = spec.current_nodes.clone().unwrap_or_default();
+    let selected: Vec<node::Node> = match nodes {
+        Some(ids) => {
+            let mut selected = Vec::new();
+            for id in ids.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+                match current_nodes
+                    .iter()
+                    .find(|n| n.node_id == id || n.machine_id == id)
+                {
+                    Some(n) => selected.push(n.clone()),
+                    None => {
+                        return Err(Error::new(
+                            ErrorKind::NotFound,
+                            format!("node '{}' not found in the spec current nodes", id),
+                        ));
+                    }
+                }
+            }
+            selected
+        }
+        None => current_nodes,
+    };
+    if selected.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "no node to collect logs from",
+        ));
+    }
+
+    let bundle_id = Utc::now().format("%Y%m%d%H%M%S").to_string();
+    let output_file_path = match output_file_path {
+        Some(v) => v.to_string(),
+        None => format!("{}-logs-{}.tar.gz", spec.id, bundle_id),
+    };
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let ssm_manager = ssm::Manager::new(&shared_config);
+    let s3_manager = s3::Manager::new(&shared_config);
+
+    let http_scheme = if spec.avalanchego_config.http_tls_enabled == Some(true) {
+        "https"
+    } else {
+        "http"
+    };
+    let http_endpoint = format!(
+        "{}://127.0.0.1:{}",
+        http_scheme, spec.avalanchego_config.http_port
+    );
+
+    info!(
+        "STEP: collecting logs of the last {} minutes from {} nodes",
+        since_minutes,
+        selected.len()
+    );
+    let mut commands = Vec::new();
+    for n in selected.iter() {
+        let s3_key = avalanche_ops::StorageNamespace::SupportBundleNode(
+            spec.id.clone(),
+            bundle_id.clone(),
+            n.machine_id.clone(),
+        )
+        .encode();
+        let script = support_bundle::collect_script(
+            since_minutes,
+            &spec.avalanchego_config.log_dir,
+            &http_endpoint,
+            &format!("s3://{}/{}", aws_resources.s3_bucket, s3_key),
+        );
+        let command_id = rt
+            .block_on(ssm_manager.send_command(
+                vec![n.machine_id.clone()],
+                &script,
+                &format!("{} {} {}", NAME, spec.id, bundle_id),
+                COMMAND_TIMEOUT,
+            ))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+        commands.push((n, command_id, s3_key));
+    }
+
+    let bundle_dir = tempfile::tempdir()?;
+    let mut summary = vec![format!(
+        "cluster {} logs of the last {} minutes, collected at {}",
+        spec.id,
+        since_minutes,
+        Utc::now().to_rfc3339()
+    )];
+    let mut collected = 0;
+    for (n, command_id, s3_key) in commands.iter() {
+        let ret = rt.block_on(ssm_manager.poll_command(command_id, &n.machine_id, COMMAND_TIMEOUT));
+        let status = match ret {
+            Ok((CommandInvocationStatus::Success, _)) => String::from("collected"),
+            Ok((status, stderr)) => format!("failed ({:?}, {})", status, stderr.trim()),
+            Err(e) => format!("failed ({})", e.message()),
+        };
+        if status != "collected" {
+            warn!("{} ({}) {}", n.node_id, n.machine_id, status);
+            summary.push(format!(
+                "{} ({}, {}): {}",
+                n.node_id, n.machine_id, n.kind, status
+            ));
+            continue;
+        }
+
+        let tmp_path = random::tmp_path(10, Some(".tar.gz"))?;
+        rt.block_on(s3::spawn_get_object(
+            s3_manager.clone(),
+            &aws_resources.s3_bucket,
+            s3_key,
+            &tmp_path,
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+        rt.block_on(s3_manager.delete_objects(
+            Arc::new(aws_resources.s3_bucket.clone()),
+            Some(Arc::new(s3_key.clone())),
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+        let node_dir = bundle_dir.path().join(&n.node_id);
+        compress::unpack_directory(
+            &tmp_path,
+            node_dir.to_str().unwrap(),
+            compress::DirDecoder::TarGzip,
+        )?;
+        fs::remove_file(&tmp_path)?;
+
+        collected += 1;
+        summary.push(format!(
+            "{} ({}, {}): {}",
+            n.node_id, n.machine_id, n.kind, status
+        ));
+    }
+    if collected == 0 {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "failed to collect logs from all nodes\n{}",
+                summary.join("\n")
+            ),
+        ));
+    }
+
+    info!("STEP: redacting the collected logs");
+    let redactor = support_bundle::Redactor::new(spec.redaction_identifiers());
+    redact_dir(bundle_dir.path(), &redactor)?;
+    fs::write(
+        bundle_dir.path().join("spec.yaml"),
+        spec.redact()?.encode_yaml()?,
+    )?;
+    fs::write(
+        bundle_dir.path().join("summary.txt"),
+        redactor.redact(&summary.join("\n")) + "\n",
+    )?;
+
+    compress::pack_directory(
+        bundle_dir.path().to_str().unwrap(),
+        &output_file_path,
+        compress::DirEncoder::TarGzip,
+    )?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\nwrote logs of {} of {} nodes to '{}' (review before sharing)\n",
+            collected,
+            selected.len(),
+            output_file_path
+        )),
+        ResetColor
+    )?;
+    for line in summary.iter().skip(1) {
+        println!("  {}", line);
+    }
+    Ok(())
+}
+
+/// Redacts all files in the directory, dropping the files that cannot be
+/// redacted (e.g., compressed rotated logs).
+fn redact_dir(dir: &Path, redactor: &support_bundle::Redactor) -> io::Result<()> {
+    for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let p = entry.path();
+        match fs::read_to_string(p) {
+            Ok(s) => fs::write(p, redactor.redact(&s))?,
+            Err(_) => {
+                warn!("dropping non-text file '{}' (cannot redact)", p.display());
+                fs::remove_file(p)?;
+            }
+        }
+    }
+    Ok(())
+}
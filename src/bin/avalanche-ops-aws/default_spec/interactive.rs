@@ -0,0 +1,362 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    path::Path,
+};
+
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect, Select};
+use log::warn;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, ec2, savingsplans},
+    cost, DefaultSpecOption,
+};
+
+const DEFAULT_INSTANCE_TYPES: [&str; 4] = ["c6a.large", "m6a.large", "m5.large", "c5.large"];
+
+const REGIONS: [&str; 8] = [
+    "us-west-2",
+    "us-east-1",
+    "us-east-2",
+    "eu-west-1",
+    "eu-central-1",
+    "ap-northeast-1",
+    "ap-southeast-1",
+    "ap-northeast-2",
+];
+
+/// Machine fields that are not part of "DefaultSpecOption".
+pub struct Machine {
+    pub anchor_nodes: Option<u32>,
+    pub non_anchor_nodes: u32,
+    pub instance_types: Vec<String>,
+}
+
+/// Walks through the spec choices, and updates the options in place.
+/// Returns the machine choices to overwrite the defaults with.
+pub fn run(opt: &mut DefaultSpecOption) -> io::Result<Machine> {
+    let theme = ColorfulTheme::default();
+    let to_err = |e: io::Error| Error::new(ErrorKind::Other, format!("failed prompt {}", e));
+
+    section("Network")?;
+    let networks = [
+        "custom (new private network with its own anchor nodes and genesis)",
+        "fuji (public test network, no anchor nodes)",
+        "mainnet (public network, no anchor nodes)",
+    ];
+    let selected = Select::with_theme(&theme)
+        .with_prompt("Which network should the nodes join?")
+        .items(&networks)
+        .default(0)
+        .interact()
+        .map_err(to_err)?;
+    opt.network_name = String::from(["custom", "fuji", "mainnet"][selected]);
+    let is_custom = selected == 0;
+    if is_custom {
+        opt.keys_to_generate = Input::with_theme(&theme)
+            .with_prompt("How many pre-funded keys to generate?")
+            .default(avalanche_ops::DEFAULT_KEYS_TO_GENERATE)
+            .validate_with(|v: &usize| {
+                if *v == 0 {
+                    Err("must generate at least 1 key")
+                } else {
+                    Ok(())
+                }
+            })
+            .interact_text()
+            .map_err(to_err)?;
+    }
+
+    section("Region")?;
+    let mut regions: Vec<&str> = REGIONS.to_vec();
+    regions.push("other");
+    let selected = Select::with_theme(&theme)
+        .with_prompt("Which AWS region?")
+        .items(&regions)
+        .default(0)
+        .interact()
+        .map_err(to_err)?;
+    opt.region = if regions[selected] == "other" {
+        Input::with_theme(&theme)
+            .with_prompt("AWS region")
+            .validate_with(|v: &String| {
+                // e.g., "ap-southeast-3"
+                let parts: Vec<&str> = v.split('-').collect();
+                if parts.len() == 3 && parts[2].parse::<u32>().is_ok() {
+                    Ok(())
+                } else {
+                    Err("invalid region (e.g., ap-southeast-3)")
+                }
+            })
+            .interact_text()
+            .map_err(to_err)?
+    } else {
+        regions[selected].to_string()
+    };
+
+    section("Instance types")?;
+    hint("the auto scaling groups launch the first available type in the order selected")?;
+    let items: Vec<String> = cost::ON_DEMAND_HOURLY_USD
+        .iter()
+        .map(|(t, price)| format!("{:<12} ~${:.3}/hour", t, price))
+        .collect();
+    let defaults: Vec<bool> = cost::ON_DEMAND_HOURLY_USD
+        .iter()
+        .map(|(t, _)| DEFAULT_INSTANCE_TYPES.contains(t))
+        .collect();
+    let instance_types: Vec<String> = loop {
+        let selected = MultiSelect::with_theme(&theme)
+            .with_prompt("Which EC2 instance types? (space to select)")
+            .items(&items)
+            .defaults(&defaults)
+            .interact()
+            .map_err(to_err)?;
+        if !selected.is_empty() {
+            break selected
+                .iter()
+                .map(|i| cost::ON_DEMAND_HOURLY_USD[*i].0.to_string())
+                .collect();
+        }
+        hint("select at least one instance type")?;
+    };
+
+    section("Node counts")?;
+    let anchor_nodes = if is_custom {
+        Some(count_prompt(
+            &theme,
+            "How many anchor nodes (bootstrap the custom network)?",
+            avalanche_ops::DEFAULT_MACHINE_ANCHOR_NODES,
+            avalanche_ops::MIN_MACHINE_ANCHOR_NODES,
+            avalanche_ops::MAX_MACHINE_ANCHOR_NODES,
+        )?)
+    } else {
+        None
+    };
+    let non_anchor_nodes = count_prompt(
+        &theme,
+        "How many non-anchor nodes?",
+        avalanche_ops::DEFAULT_MACHINE_NON_ANCHOR_NODES,
+        avalanche_ops::MIN_MACHINE_NON_ANCHOR_NODES,
+        avalanche_ops::MAX_MACHINE_NON_ANCHOR_NODES,
+    )?;
+    let nodes = anchor_nodes.unwrap_or(0) + non_anchor_nodes;
+    let on_demand_hourly = cost::on_demand_hourly(&instance_types[0]).unwrap_or(0.0);
+    let account = Confirm::with_theme(&theme)
+        .with_prompt("Apply the reserved instances and savings plans of the account to the estimate? (requires the AWS credentials)")
+        .default(false)
+        .interact()
+        .map_err(to_err)?;
+    let estimate = if account {
+        account_estimate(&opt.region, &instance_types[0], nodes, on_demand_hourly)
+    } else {
+        cost::Estimate::new(&instance_types[0], nodes, on_demand_hourly, &[], 0, &[])
+    };
+    hint(&format!(
+        "~${:.0}/month for {} x {} on-demand (excluding EBS, NLB, and data transfer)",
+        estimate.on_demand_monthly_usd(),
+        nodes,
+        instance_types[0],
+    ))?;
+    if account {
+        hint(&format!(
+            "~${:.0}/month with {} reserved and {:.1} savings plan covered nodes (assuming the savings plans have unused commitment)",
+            estimate.projected_monthly_usd(),
+            estimate.reserved_nodes,
+            estimate.savings_plan_nodes,
+        ))?;
+    }
+
+    section("HTTPS")?;
+    let https = Confirm::with_theme(&theme)
+        .with_prompt(
+            "Serve the NLB endpoint over HTTPS? (requires an ACM certificate in the region)",
+        )
+        .default(false)
+        .interact()
+        .map_err(to_err)?;
+    if https {
+        let prefix = format!("arn:aws:acm:{}:", opt.region);
+        opt.nlb_acm_certificate_arn = Input::with_theme(&theme)
+            .with_prompt("ACM certificate ARN")
+            .validate_with(|v: &String| {
+                if v.starts_with(&prefix) {
+                    Ok(())
+                } else {
+                    Err(format!("must be an ACM certificate ARN in '{}'", prefix))
+                }
+            })
+            .interact_text()
+            .map_err(to_err)?;
+    }
+
+    section("Backups")?;
+    hint("'avalanched backup upload' stores the database backups in the S3 bucket")?;
+    let retain = Confirm::with_theme(&theme)
+        .with_prompt("Expire the old backups?")
+        .default(true)
+        .interact()
+        .map_err(to_err)?;
+    if retain {
+        let expiration: u32 = Input::with_theme(&theme)
+            .with_prompt("Delete the backups after how many days?")
+            .default(30)
+            .validate_with(|v: &u32| if *v == 0 { Err("must be > 0") } else { Ok(()) })
+            .interact_text()
+            .map_err(to_err)?;
+        opt.s3_backups_expiration_days = expiration.to_string();
+
+        let glacier = Confirm::with_theme(&theme)
+            .with_prompt(
+                "Move the backups to Glacier before expiring? (cheaper, slower to restore)",
+            )
+            .default(false)
+            .interact()
+            .map_err(to_err)?;
+        if glacier {
+            let transition: u32 = Input::with_theme(&theme)
+                .with_prompt("Move to Glacier after how many days?")
+                .default(7)
+                .validate_with(|v: &u32| {
+                    if *v == 0 || *v >= expiration {
+                        Err(format!("must be between 1 and {}", expiration - 1))
+                    } else {
+                        Ok(())
+                    }
+                })
+                .interact_text()
+                .map_err(to_err)?;
+            opt.s3_backups_glacier_transition_days = transition.to_string();
+        }
+    }
+
+    section("Install artifacts")?;
+    if opt.install_artifacts_avalanched_bin.is_empty() {
+        opt.install_artifacts_avalanched_bin =
+            file_prompt(&theme, "Path to the avalanched binary")?;
+    }
+    if opt.install_artifacts_avalanche_bin.is_empty() {
+        opt.install_artifacts_avalanche_bin =
+            file_prompt(&theme, "Path to the avalanchego binary")?;
+    }
+
+    println!();
+    Ok(Machine {
+        anchor_nodes,
+        non_anchor_nodes,
+        instance_types,
+    })
+}
+
+/// Returns the estimate with the unused reserved instances and the savings plans
+/// of the account, or the on-demand estimate if the lookups fail.
+fn account_estimate(
+    region: &str,
+    instance_type: &str,
+    nodes: u32,
+    on_demand_hourly: f64,
+) -> cost::Estimate {
+    let rt = Runtime::new().unwrap();
+    let shared_config = match rt.block_on(aws::load_config(Some(region.to_string()))) {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("failed to load AWS config, using on-demand prices ({})", e);
+            return cost::Estimate::new(instance_type, nodes, on_demand_hourly, &[], 0, &[]);
+        }
+    };
+    let ec2_manager = ec2::Manager::new(&shared_config);
+
+    let reservations = rt
+        .block_on(ec2_manager.list_active_reservations(instance_type))
+        .unwrap_or_else(|e| {
+            warn!("failed to list reserved instances ({})", e.message());
+            Vec::new()
+        });
+    // the running instances of the same type already use the reservations
+    let running = if reservations.is_empty() {
+        0
+    } else {
+        rt.block_on(
+            ec2_manager.list_instances(
+                &ec2::InstanceFilter::default()
+                    .instance_type(instance_type)
+                    .alive(),
+            ),
+        )
+        .map(|v| v.len() as u32)
+        .unwrap_or_else(|e| {
+            warn!("failed to list running instances ({})", e.message());
+            0
+        })
+    };
+    let savings_plans =
+        savingsplans::list_active_rates(region, instance_type).unwrap_or_else(|e| {
+            warn!("failed to list savings plans ({})", e);
+            Vec::new()
+        });
+    cost::Estimate::new(
+        instance_type,
+        nodes,
+        on_demand_hourly,
+        &reservations,
+        running,
+        &savings_plans,
+    )
+}
+
+fn count_prompt(
+    theme: &ColorfulTheme,
+    prompt: &str,
+    default: u32,
+    min: u32,
+    max: u32,
+) -> io::Result<u32> {
+    Input::with_theme(theme)
+        .with_prompt(prompt)
+        .default(default)
+        .validate_with(|v: &u32| {
+            if *v < min || *v > max {
+                Err(format!("must be between {} and {}", min, max))
+            } else {
+                Ok(())
+            }
+        })
+        .interact_text()
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed prompt {}", e)))
+}
+
+fn file_prompt(theme: &ColorfulTheme, prompt: &str) -> io::Result<String> {
+    Input::with_theme(theme)
+        .with_prompt(prompt)
+        .validate_with(|v: &String| {
+            if Path::new(v).is_file() {
+                Ok(())
+            } else {
+                Err(format!("file '{}' does not exist", v))
+            }
+        })
+        .interact_text()
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed prompt {}", e)))
+}
+
+fn section(title: &str) -> io::Result<()> {
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!("\n# {}\n", title)),
+        ResetColor
+    )
+}
+
+fn hint(msg: &str) -> io::Result<()> {
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::DarkGrey),
+        Print(format!("  ({})\n", msg)),
+        ResetColor
+    )
+}
@@ -1,4 +1,6 @@
-use std::io::{self, stdout};
+mod interactive;
+
+use std::io::{self, stdout, Error, ErrorKind};
 
 use clap::{Arg, Command};
 use crossterm::{
@@ -6,7 +8,10 @@ use crossterm::{
     style::{Color, Print, ResetColor, SetForegroundColor},
 };
 
-use avalanche_ops::{self, avalanche::avalanchego::config as avalanchego_config, utils::home_dir};
+use avalanche_ops::{
+    self, avalanche::avalanchego::config as avalanchego_config, ci_preview, spec_profile,
+    utils::home_dir,
+};
 
 pub const NAME: &str = "default-spec";
 
@@ -30,11 +35,27 @@ pub fn command() -> Command<'static> {
                 .long("region")
                 .short('r')
                 .help("Sets the AWS region for API calls/endpoints")
-                .required(true)
+                .required(false)
                 .takes_value(true)
                 .allow_invalid_utf8(false)
                 .default_value("us-west-2"),
         )
+        .arg(
+            Arg::new("S3_BACKUPS_EXPIRATION_DAYS") 
+                .long("s3-backups-expiration-days")
+                .help("Sets the days to expire database backups in the S3 bucket (empty to keep forever)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("S3_BACKUPS_GLACIER_TRANSITION_DAYS") 
+                .long("s3-backups-glacier-transition-days")
+                .help("Sets the days to transition database backups in the S3 bucket to Glacier")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
         .arg(
             Arg::new("DB_BACKUP_S3_REGION") 
                 .long("db-backup-s3-region")
@@ -59,6 +80,91 @@ pub fn command() -> Command<'static> {
                 .takes_value(true)
                 .allow_invalid_utf8(false),
         )
+        .arg(
+            Arg::new("REPLICA_S3_REGION") 
+                .long("replica-s3-region")
+                .help("Sets S3 region to replicate install artifacts and database backups to")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("REPLICA_S3_BUCKET") 
+                .long("replica-s3-bucket")
+                .help("Sets S3 bucket to replicate install artifacts and database backups to")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("TRACKED_SUBNET_IDS") 
+                .long("tracked-subnet-ids")
+                .help("Sets comma-separated subnet IDs to track (e.g., private subnet on Fuji/mainnet)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("TRACKED_SUBNETS_VALIDATOR_ONLY") 
+                .long("tracked-subnets-validator-only")
+                .help("Sets to only connect to the subnet validators (and allowed nodes) for the tracked subnets")
+                .required(false)
+                .takes_value(false)
+                .requires("TRACKED_SUBNET_IDS")
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("TRACKED_SUBNETS_ALLOWED_NODES") 
+                .long("tracked-subnets-allowed-nodes")
+                .help("Sets comma-separated non-validator node IDs allowed to connect for the tracked subnets")
+                .required(false)
+                .takes_value(true)
+                .requires("TRACKED_SUBNETS_VALIDATOR_ONLY")
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("EXTERNAL_BOOTSTRAP_IPS") 
+                .long("external-bootstrap-ips")
+                .help("Sets comma-separated staking IP:PORT of the external bootstrap nodes not managed by this tool")
+                .required(false)
+                .takes_value(true)
+                .requires("EXTERNAL_BOOTSTRAP_IDS")
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("EXTERNAL_BOOTSTRAP_IDS") 
+                .long("external-bootstrap-ids")
+                .help("Sets comma-separated node IDs of the external bootstrap nodes (same order as '--external-bootstrap-ips')")
+                .required(false)
+                .takes_value(true)
+                .requires("EXTERNAL_BOOTSTRAP_IPS")
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("CHAIN_UPGRADE_FILES") 
+                .long("chain-upgrade-files")
+                .help("Sets comma-separated '[CHAIN_ID]=[FILE_PATH]' for the chain upgrade files (e.g., subnet-evm precompile activations)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("AVALANCHEGO_RELEASE_CONSTRAINT") 
+                .long("avalanchego-release-constraint")
+                .help("Sets the semver constraint for avalanchego releases to track (e.g., '^1.7')")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("AVALANCHEGO_RELEASE_AUTO_UPGRADE") 
+                .long("avalanchego-release-auto-upgrade")
+                .help("Sets to auto-upgrade the test network with the tracked release")
+                .required(false)
+                .takes_value(false)
+                .requires("AVALANCHEGO_RELEASE_CONSTRAINT")
+                .allow_invalid_utf8(false),
+        )
         .arg(
             Arg::new("NLB_ACM_CERTIFICATE_ARN") 
                 .long("nlb-acm-certificate-arn")
@@ -71,7 +177,7 @@ pub fn command() -> Command<'static> {
             Arg::new("INSTALL_ARTIFACTS_AVALANCHED_BIN") 
                 .long("install-artifacts-avalanched-bin")
                 .help("Sets the Avalanched binary path in the local machine to be shared with remote machines")
-                .required(true)
+                .required_unless_present("INTERACTIVE")
                 .takes_value(true)
                 .allow_invalid_utf8(false),
         )
@@ -79,7 +185,7 @@ pub fn command() -> Command<'static> {
             Arg::new("INSTALL_ARTIFACTS_AVALANCHE_BIN") 
                 .long("install-artifacts-avalanche-bin")
                 .help("Sets the Avalanche node binary path in the local machine to be shared with remote machines")
-                .required(true)
+                .required_unless_present("INTERACTIVE")
                 .takes_value(true)
                 .allow_invalid_utf8(false),
         )
@@ -91,6 +197,14 @@ pub fn command() -> Command<'static> {
                 .takes_value(true)
                 .allow_invalid_utf8(false),
         )
+        .arg(
+            Arg::new("INSTALL_ARTIFACTS_SIGNING_PUBLIC_KEY") 
+                .long("install-artifacts-signing-public-key")
+                .help("Sets the hex-encoded ed25519 public key to verify '[FILE].sig' of avalanchego and plugins (requires signed artifacts)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
         .arg(
             Arg::new("NETWORK_NAME") 
                 .long("network-name")
@@ -222,6 +336,50 @@ pub fn command() -> Command<'static> {
                 .takes_value(false)
                 .allow_invalid_utf8(false),
         )
+        .arg(
+            Arg::new("PROFILE")
+                .long("profile")
+                .short('p')
+                .help("Sets the preset of node counts, instance types, volumes, and avalanchego flags (explicit flags take precedence)")
+                .required(false)
+                .takes_value(true)
+                .possible_values(spec_profile::names())
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("CI_JOB_ID")
+                .long("ci-job-id")
+                .help("Sets the CI job ID to create the ephemeral preview network (no NLB, tagged for 'reap')")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("CI_TTL_MINUTES")
+                .long("ci-ttl-minutes")
+                .help("Sets the minutes after which 'reap' deletes the CI preview network")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("120"),
+        )
+        .arg(
+            Arg::new("ID_SEED")
+                .long("id-seed")
+                .help("Sets the seed of the cluster ID and the S3 bucket name (e.g., CI job ID), to generate the same names across re-runs")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("INTERACTIVE")
+                .long("interactive")
+                .short('i')
+                .help("Walks through the network, region, instances, HTTPS, and backup choices (overrides the flags)")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
         .arg(
             Arg::new("SPEC_FILE_PATH")
                 .long("spec-file-path")
@@ -233,13 +391,87 @@ pub fn command() -> Command<'static> {
         )
 }
 
-pub fn execute(opt: avalanche_ops::DefaultSpecOption) -> io::Result<()> {
+/// Fills in the options from the profile, unless set explicitly by the flags.
+pub fn apply_profile(
+    sub_matches: &clap::ArgMatches,
+    opt: &mut avalanche_ops::DefaultSpecOption,
+) -> io::Result<Option<spec_profile::Profile>> {
+    let profile = match sub_matches.value_of("PROFILE") {
+        Some(name) => spec_profile::Profile::get(name)?,
+        None => return Ok(None),
+    };
+    if sub_matches.occurrences_of("NETWORK_NAME") == 0 {
+        opt.network_name = profile.network_name.to_string();
+    }
+    opt.enable_subnet_evm = opt.enable_subnet_evm || profile.enable_subnet_evm;
+    Ok(Some(profile))
+}
+
+/// Returns the CI preview network of the "--ci-job-id", if set.
+pub fn ci_preview(sub_matches: &clap::ArgMatches) -> io::Result<Option<ci_preview::CiPreview>> {
+    let job_id = match sub_matches.value_of("CI_JOB_ID") {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let ttl_minutes = sub_matches
+        .value_of("CI_TTL_MINUTES")
+        .unwrap_or_default()
+        .parse::<u32>()
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid '--ci-ttl-minutes' {}", e),
+            )
+        })?;
+    if ttl_minutes == 0 || ttl_minutes > ci_preview::MAX_TTL_MINUTES {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "'--ci-ttl-minutes' must be 1-{} (got {})",
+                ci_preview::MAX_TTL_MINUTES,
+                ttl_minutes
+            ),
+        ));
+    }
+    Ok(Some(ci_preview::CiPreview::new(job_id, ttl_minutes)))
+}
+
+pub fn execute(
+    mut opt: avalanche_ops::DefaultSpecOption,
+    profile: Option<spec_profile::Profile>,
+    ci_preview: Option<ci_preview::CiPreview>,
+    interactive: bool,
+) -> io::Result<()> {
     // ref. https://github.com/env-logger-rs/env_logger/issues/47
     env_logger::init_from_env(
         env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, opt.clone().log_level),
     );
 
-    let spec = avalanche_ops::Spec::default_aws(opt.clone());
+    let machine = if interactive {
+        Some(interactive::run(&mut opt)?)
+    } else {
+        None
+    };
+
+    if opt.external_bootstrap_ips.split(',').count()
+        != opt.external_bootstrap_ids.split(',').count()
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "'--external-bootstrap-ips' and '--external-bootstrap-ids' must have the same length",
+        ));
+    }
+
+    let mut spec = avalanche_ops::Spec::default_aws(opt.clone());
+    if let Some(profile) = profile {
+        profile.apply(&mut spec);
+    }
+    if let Some(machine) = machine {
+        spec.machine.anchor_nodes = machine.anchor_nodes;
+        spec.machine.non_anchor_nodes = machine.non_anchor_nodes;
+        spec.machine.instance_types = Some(machine.instance_types);
+    }
+    spec.ci_preview = ci_preview;
     spec.validate()?;
 
     let spec_file_path = {
@@ -308,7 +540,7 @@ pub fn execute(opt: avalanche_ops::DefaultSpecOption) -> io::Result<()> {
             SetForegroundColor(Color::Cyan),
             Print(format!(
                 "cat <<EOF > /tmp/test.key\n{}\nEOF\ncat /tmp/test.key\n",
-                keys[0].private_key_hex
+                keys[0].private_key_hex.expose_str()
             )),
             ResetColor
         )?;
@@ -0,0 +1,249 @@
+use std::io::{self, stdout, Error, ErrorKind};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::{
+        avalanchego::api::platform,
+        ids,
+        platformvm::{txs, wallet},
+    },
+};
+
+pub const NAME: &str = "delegate";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Delegates stake to the cluster (or any primary network) validators, after checking the live network limits")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("PRIVATE_KEY_PATH")
+                .long("private-key-path")
+                .help("Sets the key path to fund the stake, which also owns the rewards, or 'prefunded:N' for the N-th prefunded key in the spec")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("HTTP_RPC")
+                .long("http-rpc")
+                .help("Sets the P-chain API endpoint (defaults to the public API, or 'endpoints.http_rpc' in spec)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("NODE_IDS")
+                .long("node-ids")
+                .help("Sets the comma-separated validator node IDs to delegate to (default to the cluster nodes)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("STAKE_AMOUNT")
+                .long("stake-amount")
+                .help("Sets the stake in nano-AVAX to delegate to each validator")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("STAKE_DURATION")
+                .long("stake-duration")
+                .help("Sets the delegation duration in seconds (default to the validation end time)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("START_DELAY_SECONDS")
+                .long("start-delay-seconds")
+                .help("Sets the delay in seconds before the delegation starts")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("60"),
+        )
+        .arg(
+            Arg::new("MAX_DELEGATION_FEE_PERCENT")
+                .long("max-delegation-fee-percent")
+                .help("Rejects the validators taking more than this percent of the delegator rewards")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .args(crate::tx::multisig_args())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    private_key_path: &str,
+    http_rpc: &str,
+    node_ids: &str,
+    stake_amount: u64,
+    stake_duration: Option<u64>,
+    start_delay_seconds: u64,
+    max_delegation_fee_percent: Option<f64>,
+    skip_prompt: bool,
+    multisig_opts: &crate::tx::MultisigOpts,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let node_ids = crate::list_delegations::resolve_node_ids(&spec, node_ids)?;
+    let http_rpc = crate::elastic_subnet::get_http_rpc(&spec, http_rpc)?;
+
+    let rt = Runtime::new().unwrap();
+    let validators = rt.block_on(platform::get_delegated_validators(&http_rpc, &node_ids))?;
+    let params = crate::tx::load_network_params(&rt, &spec, &http_rpc)?;
+    let limits = params.delegation_limits();
+
+    // validate all before issuing any, to not leave partial delegations
+    let start = wallet::now_unix() + start_delay_seconds;
+    let mut delegations: Vec<txs::Validator> = Vec::new();
+    for node_id in node_ids.iter() {
+        let v = match validators.iter().find(|v| &v.node_id == node_id) {
+            Some(v) => v,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("{} is not a current primary network validator", node_id),
+                ))
+            }
+        };
+        let end = match stake_duration {
+            Some(d) => start + d,
+            None => v.end_time,
+        };
+        params.validate_delegator(stake_amount, end.saturating_sub(start))?;
+        limits.validate(v, stake_amount, start, end, max_delegation_fee_percent)?;
+        delegations.push(txs::Validator {
+            node_id: ids::parse_node_id(node_id)?,
+            start,
+            end,
+            weight: stake_amount,
+        });
+    }
+
+    let k = crate::elastic_subnet::load_funding_key(&spec, private_key_path)?;
+    let mut w = crate::tx::load_wallet(
+        &rt,
+        k,
+        spec.avalanchego_config.network_id,
+        &http_rpc,
+        multisig_opts,
+    )?;
+    w.fees = params.tx_fees.clone();
+    let required =
+        (stake_amount + w.fees.add_primary_network_delegator_fee) * delegations.len() as u64;
+    let balance = rt
+        .block_on(platform::get_balance(
+            &http_rpc,
+            platform::PATH,
+            &w.p_address,
+        ))?
+        .result
+        .and_then(|r| r.balance)
+        .unwrap_or(0);
+    if balance < required {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} P-chain balance {} < {} required for the stakes and the fees",
+                w.p_address, balance, required
+            ),
+        ));
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nDelegating {} nAVAX from {} to {} validator(s) via {}\n",
+            stake_amount,
+            w.p_address,
+            delegations.len(),
+            http_rpc
+        )),
+        ResetColor
+    )?;
+    if !skip_prompt {
+        let options = &["No, I am not ready to delegate!", "Yes, let's delegate!"];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'delegate' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    for (node_id, d) in node_ids.iter().zip(delegations.into_iter()) {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print(format!(
+                "\n\n\nSTEP: issue AddDelegatorTx for {} (weight {}, end {})\n",
+                node_id, d.weight, d.end
+            )),
+            ResetColor
+        )?;
+        let issued = rt.block_on(w.add_delegator(d))?;
+        if let Some(tx_id) = crate::tx::handle_issued(
+            issued,
+            &spec.id,
+            &format!("delegate-{}", node_id),
+            multisig_opts,
+        )? {
+            println!("delegated to {} (tx {})", node_id, tx_id);
+        }
+    }
+
+    println!();
+    info!("'delegate' all success!");
+    Ok(())
+}
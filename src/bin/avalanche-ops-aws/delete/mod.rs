@@ -1,27 +1,13 @@
-use std::{
-    fs,
-    io::{self, stdout, Error, ErrorKind},
-    path::Path,
-    sync::Arc,
-    thread,
-    time::Duration,
-};
+use std::io::{self, stdout};
 
-use aws_sdk_cloudformation::model::StackStatus;
 use clap::{Arg, Command};
 use crossterm::{
     execute,
     style::{Color, Print, ResetColor, SetForegroundColor},
 };
 use dialoguer::{theme::ColorfulTheme, Select};
-use log::info;
-use tokio::runtime::Runtime;
 
-use avalanche_ops::{
-    self,
-    aws::{self, cloudformation, cloudwatch, ec2, kms, s3, sts},
-    utils::compress,
-};
+use avalanche_ops::deployer;
 
 pub const NAME: &str = "delete";
 
@@ -84,9 +70,6 @@ pub fn command() -> Command<'static> {
         )
 }
 
-// 50-minute
-const MAX_WAIT_SECONDS: u64 = 50 * 60;
-
 pub fn execute(
     log_level: &str,
     spec_file_path: &str,
@@ -100,46 +83,23 @@ pub fn execute(
         env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
     );
 
-    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
-    let aws_resources = spec.aws_resources.clone().unwrap();
-
-    let rt = Runtime::new().unwrap();
-    let shared_config = rt
-        .block_on(aws::load_config(Some(aws_resources.region.clone())))
-        .unwrap();
-
-    let sts_manager = sts::Manager::new(&shared_config);
-    let current_identity = rt.block_on(sts_manager.get_identity()).unwrap();
-
-    // validate identity
-    match aws_resources.identity {
-        Some(identity) => {
-            // AWS calls must be made from the same caller
-            if identity != current_identity {
-                return Err(Error::new(
-                    ErrorKind::Other,
-                    format!(
-                        "config identity {:?} != currently loaded identity {:?}",
-                        identity, current_identity
-                    ),
-                ));
-            }
-        }
-        None => {
-            return Err(Error::new(ErrorKind::Other, "unknown identity"));
+    let opts = deployer::DeleteOptions {
+        delete_cloudwatch_log_group,
+        delete_s3_objects,
+        delete_s3_bucket,
+    };
+    deployer::delete::run(spec_file_path, &opts, &|spec| {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Blue),
+            Print(format!("\nLoaded configuration: '{}'\n", spec_file_path)),
+            ResetColor
+        )?;
+        println!("{}\n", spec.encode_yaml()?);
+        if skip_prompt {
+            return Ok(true);
         }
-    }
-
-    execute!(
-        stdout(),
-        SetForegroundColor(Color::Blue),
-        Print(format!("\nLoaded configuration: '{}'\n", spec_file_path)),
-        ResetColor
-    )?;
-    let spec_contents = spec.encode_yaml().unwrap();
-    println!("{}\n", spec_contents);
 
-    if !skip_prompt {
         let options = &[
             "No, I am not ready to delete resources!",
             "Yes, let's delete resources!",
@@ -148,290 +108,8 @@ pub fn execute(
             .with_prompt("Select your 'delete' option")
             .items(&options[..])
             .default(0)
-            .interact()
-            .unwrap();
-        if selected == 0 {
-            return Ok(());
-        }
-    }
-
-    info!("deleting resources...");
-    let s3_manager = s3::Manager::new(&shared_config);
-    let kms_manager = kms::Manager::new(&shared_config);
-    let ec2_manager = ec2::Manager::new(&shared_config);
-    let cloudformation_manager = cloudformation::Manager::new(&shared_config);
-    let cw_manager = cloudwatch::Manager::new(&shared_config);
-
-    // delete this first since EC2 key delete does not depend on ASG/VPC
-    // (mainly to speed up delete operation)
-    if aws_resources.ec2_key_name.is_some() && aws_resources.ec2_key_path.is_some() {
-        thread::sleep(Duration::from_secs(2));
-        execute!(
-            stdout(),
-            SetForegroundColor(Color::Red),
-            Print("\n\n\nSTEP: delete EC2 key pair\n"),
-            ResetColor
-        )?;
-
-        let ec2_key_path = aws_resources.ec2_key_path.unwrap();
-        if Path::new(ec2_key_path.as_str()).exists() {
-            fs::remove_file(ec2_key_path.as_str()).unwrap();
-        }
-        let ec2_key_path_compressed =
-            format!("{}{}", ec2_key_path, compress::Encoder::Zstd(3).ext());
-        if Path::new(ec2_key_path_compressed.as_str()).exists() {
-            fs::remove_file(ec2_key_path_compressed.as_str()).unwrap();
-        }
-        let ec2_key_path_compressed_encrypted = format!("{}.encrypted", ec2_key_path_compressed);
-        if Path::new(ec2_key_path_compressed_encrypted.as_str()).exists() {
-            fs::remove_file(ec2_key_path_compressed_encrypted.as_str()).unwrap();
-        }
-        rt.block_on(ec2_manager.delete_key_pair(aws_resources.ec2_key_name.unwrap().as_str()))
-            .unwrap();
-    }
-
-    // delete this first since KMS key delete does not depend on ASG/VPC
-    // (mainly to speed up delete operation)
-    if aws_resources.kms_cmk_id.is_some() && aws_resources.kms_cmk_arn.is_some() {
-        thread::sleep(Duration::from_secs(2));
-        execute!(
-            stdout(),
-            SetForegroundColor(Color::Red),
-            Print("\n\n\nSTEP: delete KMS key\n"),
-            ResetColor
-        )?;
-
-        let cmk_id = aws_resources.kms_cmk_id.unwrap();
-        rt.block_on(kms_manager.schedule_to_delete(cmk_id.as_str()))
-            .unwrap();
-    }
-
-    // IAM roles can be deleted without being blocked on ASG/VPC
-    if aws_resources
-        .cloudformation_ec2_instance_profile_arn
-        .is_some()
-    {
-        thread::sleep(Duration::from_secs(2));
-        execute!(
-            stdout(),
-            SetForegroundColor(Color::Red),
-            Print("\n\n\nSTEP: trigger delete EC2 instance role\n"),
-            ResetColor
-        )?;
-
-        let ec2_instance_role_stack_name = aws_resources
-            .cloudformation_ec2_instance_role
-            .clone()
-            .unwrap();
-        rt.block_on(cloudformation_manager.delete_stack(ec2_instance_role_stack_name.as_str()))
-            .unwrap();
-    }
-
-    if aws_resources
-        .cloudformation_asg_non_anchor_nodes_logical_id
-        .is_some()
-    {
-        thread::sleep(Duration::from_secs(2));
-        execute!(
-            stdout(),
-            SetForegroundColor(Color::Red),
-            Print("\n\n\nSTEP: triggering delete ASG for non-anchor nodes\n"),
-            ResetColor
-        )?;
-
-        let asg_non_anchor_nodes_stack_name = aws_resources
-            .cloudformation_asg_non_anchor_nodes
-            .clone()
-            .unwrap();
-        rt.block_on(cloudformation_manager.delete_stack(asg_non_anchor_nodes_stack_name.as_str()))
-            .unwrap();
-    }
-
-    if spec.machine.anchor_nodes.unwrap_or(0) > 0
-        && aws_resources
-            .cloudformation_asg_anchor_nodes_logical_id
-            .is_some()
-    {
-        thread::sleep(Duration::from_secs(2));
-        execute!(
-            stdout(),
-            SetForegroundColor(Color::Red),
-            Print("\n\n\nSTEP: triggering delete ASG for anchor nodes\n"),
-            ResetColor
-        )?;
-
-        let asg_anchor_nodes_stack_name = aws_resources
-            .cloudformation_asg_anchor_nodes
-            .clone()
-            .unwrap();
-        rt.block_on(cloudformation_manager.delete_stack(asg_anchor_nodes_stack_name.as_str()))
-            .unwrap();
-    }
-
-    if aws_resources
-        .cloudformation_asg_non_anchor_nodes_logical_id
-        .is_some()
-    {
-        thread::sleep(Duration::from_secs(2));
-        execute!(
-            stdout(),
-            SetForegroundColor(Color::Red),
-            Print("\n\n\nSTEP: confirming delete ASG for non-anchor nodes\n"),
-            ResetColor
-        )?;
-
-        let asg_non_anchor_nodes_stack_name =
-            aws_resources.cloudformation_asg_non_anchor_nodes.unwrap();
-
-        let desired_capacity = spec.machine.non_anchor_nodes;
-        let mut wait_secs = 300 + 60 * desired_capacity as u64;
-        if wait_secs > MAX_WAIT_SECONDS {
-            wait_secs = MAX_WAIT_SECONDS;
-        }
-        rt.block_on(cloudformation_manager.poll_stack(
-            asg_non_anchor_nodes_stack_name.as_str(),
-            StackStatus::DeleteComplete,
-            Duration::from_secs(wait_secs),
-            Duration::from_secs(30),
-        ))
-        .unwrap();
-    }
-
-    if spec.machine.anchor_nodes.unwrap_or(0) > 0
-        && aws_resources
-            .cloudformation_asg_anchor_nodes_logical_id
-            .is_some()
-    {
-        thread::sleep(Duration::from_secs(2));
-        execute!(
-            stdout(),
-            SetForegroundColor(Color::Red),
-            Print("\n\n\nSTEP: confirming delete ASG for anchor nodes\n"),
-            ResetColor
-        )?;
-
-        let asg_anchor_nodes_stack_name = aws_resources.cloudformation_asg_anchor_nodes.unwrap();
-
-        let desired_capacity = spec.machine.anchor_nodes.unwrap();
-        let mut wait_secs = 300 + 60 * desired_capacity as u64;
-        if wait_secs > MAX_WAIT_SECONDS {
-            wait_secs = MAX_WAIT_SECONDS;
-        }
-        rt.block_on(cloudformation_manager.poll_stack(
-            asg_anchor_nodes_stack_name.as_str(),
-            StackStatus::DeleteComplete,
-            Duration::from_secs(wait_secs),
-            Duration::from_secs(30),
-        ))
-        .unwrap();
-    }
-
-    // VPC delete must run after associated EC2 instances are terminated due to dependencies
-    if aws_resources.cloudformation_vpc_id.is_some()
-        && aws_resources.cloudformation_vpc_security_group_id.is_some()
-        && aws_resources.cloudformation_vpc_public_subnet_ids.is_some()
-    {
-        thread::sleep(Duration::from_secs(2));
-        execute!(
-            stdout(),
-            SetForegroundColor(Color::Red),
-            Print("\n\n\nSTEP: delete VPC\n"),
-            ResetColor
-        )?;
-
-        let vpc_stack_name = aws_resources.cloudformation_vpc.unwrap();
-        rt.block_on(cloudformation_manager.delete_stack(vpc_stack_name.as_str()))
-            .unwrap();
-        thread::sleep(Duration::from_secs(10));
-        rt.block_on(cloudformation_manager.poll_stack(
-            vpc_stack_name.as_str(),
-            StackStatus::DeleteComplete,
-            Duration::from_secs(500),
-            Duration::from_secs(30),
-        ))
-        .unwrap();
-    }
-
-    if aws_resources
-        .cloudformation_ec2_instance_profile_arn
-        .is_some()
-    {
-        thread::sleep(Duration::from_secs(2));
-        execute!(
-            stdout(),
-            SetForegroundColor(Color::Red),
-            Print("\n\n\nSTEP: confirming delete EC2 instance role\n"),
-            ResetColor
-        )?;
-
-        let ec2_instance_role_stack_name = aws_resources.cloudformation_ec2_instance_role.unwrap();
-        rt.block_on(cloudformation_manager.poll_stack(
-            ec2_instance_role_stack_name.as_str(),
-            StackStatus::DeleteComplete,
-            Duration::from_secs(500),
-            Duration::from_secs(30),
-        ))
-        .unwrap();
-    }
-
-    if delete_cloudwatch_log_group {
-        // deletes the one auto-created by nodes
-        thread::sleep(Duration::from_secs(2));
-        execute!(
-            stdout(),
-            SetForegroundColor(Color::Red),
-            Print("\n\n\nSTEP: cloudwatch log groups\n"),
-            ResetColor
-        )?;
-        rt.block_on(cw_manager.delete_log_group(&spec.id)).unwrap();
-    }
-
-    if delete_s3_objects {
-        thread::sleep(Duration::from_secs(1));
-        execute!(
-            stdout(),
-            SetForegroundColor(Color::Red),
-            Print("\n\n\nSTEP: delete S3 objects\n"),
-            ResetColor
-        )?;
-        thread::sleep(Duration::from_secs(5));
-        rt.block_on(s3_manager.delete_objects(
-            Arc::new(aws_resources.s3_bucket.clone()),
-            Some(Arc::new(spec.id)),
-        ))
-        .unwrap();
-    }
-
-    if delete_s3_bucket {
-        thread::sleep(Duration::from_secs(1));
-        execute!(
-            stdout(),
-            SetForegroundColor(Color::Red),
-            Print("\n\n\nSTEP: delete S3 bucket\n"),
-            ResetColor
-        )?;
-        thread::sleep(Duration::from_secs(5));
-        rt.block_on(s3_manager.delete_bucket(&aws_resources.s3_bucket))
-            .unwrap();
-        // NOTE: do not delete db backups...
-        if aws_resources.db_backup_s3_bucket.is_some() {
-            info!(
-                "skipping deleting {}",
-                aws_resources.db_backup_s3_bucket.clone().unwrap()
-            );
-            // rt.block_on(
-            //     s3_manager
-            //         .delete_objects(&aws_resources.s3_bucket_db_backup.clone().unwrap(), None),
-            // )
-            // .unwrap();
-            // rt.block_on(
-            //     s3_manager.delete_bucket(&aws_resources.s3_bucket_db_backup.clone().unwrap()),
-            // )
-            // .unwrap();
-        }
-    }
-
-    println!();
-    info!("delete all success!");
+            .interact()?;
+        Ok(selected == 1)
+    })?;
     Ok(())
 }
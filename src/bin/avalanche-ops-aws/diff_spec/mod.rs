@@ -0,0 +1,74 @@
+use std::io::{self, Error, ErrorKind};
+
+use clap::{Arg, Command};
+
+use avalanche_ops::{
+    self,
+    spec_diff::{self, Impact},
+};
+
+pub const NAME: &str = "diff-spec";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Lists the changed fields between two specs, with what applying the new spec does (no-op, in-place update, node restart, replacement)")
+        .arg(
+            Arg::new("OLD_SPEC_FILE_PATH")
+                .help("The current spec file")
+                .required(true)
+                .index(1)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("NEW_SPEC_FILE_PATH")
+                .help("The edited spec file")
+                .required(true)
+                .index(2)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("FAIL_ON_IMPACT")
+                .long("fail-on-impact")
+                .help("Exits with an error if any change has this impact or higher (e.g., for CI)")
+                .required(false)
+                .takes_value(true)
+                .possible_value("in-place-update")
+                .possible_value("node-restart")
+                .possible_value("replacement")
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    old_spec_file_path: &str,
+    new_spec_file_path: &str,
+    fail_on_impact: Option<&str>,
+) -> io::Result<()> {
+    let old = avalanche_ops::Spec::load(old_spec_file_path)?;
+    let new = avalanche_ops::Spec::load(new_spec_file_path)?;
+
+    let changes = spec_diff::diff(&old, &new)?;
+    if changes.is_empty() {
+        println!("no changes");
+        return Ok(());
+    }
+
+    spec_diff::print_changes(&changes)?;
+
+    let max = spec_diff::max_impact(&changes);
+    println!("\n{} change(s), highest impact: {}", changes.len(), max);
+
+    let threshold = match fail_on_impact {
+        Some("in-place-update") => Impact::InPlaceUpdate,
+        Some("node-restart") => Impact::NodeRestart,
+        Some("replacement") => Impact::Replacement,
+        _ => return Ok(()),
+    };
+    if max >= threshold {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("highest impact '{}' >= '{}'", max, threshold),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,205 @@
+use std::io::{self, stdout, Error, ErrorKind};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::{
+        ids,
+        platformvm::{txs, wallet},
+    },
+};
+
+pub const NAME: &str = "add-delegator";
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Delegates the subnet asset to a permissionless validator of the elastic subnet")
+        .args(super::common_args())
+        .arg(
+            Arg::new("NODE_ID")
+                .long("node-id")
+                .help("Sets the validator node ID to delegate to")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("STAKE_AMOUNT")
+                .long("stake-amount")
+                .help("Sets the amount of the subnet asset to delegate")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("START_DELAY_SECONDS")
+                .long("start-delay-seconds")
+                .help("Sets the delay in seconds before the delegation starts")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("60"),
+        )
+        .arg(
+            Arg::new("STAKE_DURATION")
+                .long("stake-duration")
+                .help("Sets the delegation duration in seconds (default to the subnet minimum)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    private_key_path: &str,
+    http_rpc: &str,
+    node_id: &str,
+    stake_amount: u64,
+    start_delay_seconds: u64,
+    stake_duration: Option<u64>,
+    skip_prompt: bool,
+    multisig_opts: &crate::tx::MultisigOpts,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let mut spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let mut elastic_subnet = match &spec.elastic_subnet {
+        Some(v) if v.transform_subnet_tx_id.is_some() => v.clone(),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "subnet not transformed yet (run 'elastic-subnet transform' first)",
+            ));
+        }
+    };
+    let params = elastic_subnet.params.clone();
+    let parsed_node_id = ids::parse_node_id(node_id)?;
+
+    if stake_amount < params.min_delegator_stake {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "stake amount {} < minimum delegator stake {}",
+                stake_amount, params.min_delegator_stake
+            ),
+        ));
+    }
+    let stake_duration = stake_duration.unwrap_or(params.min_stake_duration as u64);
+    if stake_duration < params.min_stake_duration as u64
+        || stake_duration > params.max_stake_duration as u64
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "stake duration {} out of range [{}, {}]",
+                stake_duration, params.min_stake_duration, params.max_stake_duration
+            ),
+        ));
+    }
+
+    // the delegation period must be within the validation period
+    let start = wallet::now_unix() + start_delay_seconds;
+    let end = start + stake_duration;
+    if let Some(validators) = &elastic_subnet.validators {
+        if let Some(v) = validators.iter().find(|v| v.node_id == node_id) {
+            if end > v.end_time {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "delegation ends at {} after the validator {} ends at {}",
+                        end, node_id, v.end_time
+                    ),
+                ));
+            }
+        }
+    }
+
+    let http_rpc = super::get_http_rpc(&spec, http_rpc)?;
+    let k = super::load_funding_key(&spec, private_key_path)?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nDelegating {} to {} on subnet {} via {} (duration {}s)\n",
+            stake_amount, node_id, params.subnet_id, http_rpc, stake_duration
+        )),
+        ResetColor
+    )?;
+    if !skip_prompt {
+        let options = &["No, I am not ready to delegate!", "Yes, let's delegate!"];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'add-delegator' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print("\n\n\nSTEP: issue AddPermissionlessDelegatorTx\n"),
+        ResetColor
+    )?;
+    let rt = Runtime::new().unwrap();
+    let w = crate::tx::load_wallet(
+        &rt,
+        k,
+        spec.avalanchego_config.network_id,
+        &http_rpc,
+        multisig_opts,
+    )?;
+    let issued = rt.block_on(w.add_permissionless_delegator(
+        &params.subnet_id,
+        &params.asset_id,
+        txs::Validator {
+            node_id: parsed_node_id,
+            start,
+            end,
+            weight: stake_amount,
+        },
+    ))?;
+    let tx_id = match crate::tx::handle_issued(
+        issued,
+        &spec.id,
+        &format!("add-permissionless-delegator-{}", node_id),
+        multisig_opts,
+    )? {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    let mut delegators = elastic_subnet.delegators.clone().unwrap_or_default();
+    delegators.push(avalanche_ops::PermissionlessStaker {
+        node_id: node_id.to_string(),
+        tx_id: tx_id.clone(),
+        weight: stake_amount,
+        start_time: start,
+        end_time: end,
+    });
+    elastic_subnet.delegators = Some(delegators);
+    spec.elastic_subnet = Some(elastic_subnet);
+    spec.sync(spec_file_path)?;
+
+    println!("\nadded delegator to {} (tx {})", node_id, tx_id);
+    info!("'elastic-subnet add-delegator' all success!");
+    Ok(())
+}
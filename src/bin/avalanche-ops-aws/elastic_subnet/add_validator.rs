@@ -0,0 +1,248 @@
+use std::io::{self, stdout, Error, ErrorKind};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::{
+        ids,
+        platformvm::{txs, wallet},
+    },
+};
+
+pub const NAME: &str = "add-validator";
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Registers the nodes as the permissionless validators of the elastic subnet")
+        .args(super::common_args())
+        .arg(
+            Arg::new("NODE_IDS")
+                .long("node-ids")
+                .help(
+                    "Sets comma-separated node IDs to register (default to all nodes in the spec)",
+                )
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("STAKE_AMOUNT")
+                .long("stake-amount")
+                .help("Sets the amount of the subnet asset to stake per validator")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("START_DELAY_SECONDS")
+                .long("start-delay-seconds")
+                .help("Sets the delay in seconds before the staking starts")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("60"),
+        )
+        .arg(
+            Arg::new("STAKE_DURATION")
+                .long("stake-duration")
+                .help("Sets the stake duration in seconds (default to the subnet minimum)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("DELEGATION_FEE")
+                .long("delegation-fee")
+                .help(
+                    "Sets the delegation fee in units of 1,000,000 (default to the subnet minimum)",
+                )
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    private_key_path: &str,
+    http_rpc: &str,
+    node_ids: &str,
+    stake_amount: u64,
+    start_delay_seconds: u64,
+    stake_duration: Option<u64>,
+    delegation_fee: Option<u32>,
+    skip_prompt: bool,
+    multisig_opts: &crate::tx::MultisigOpts,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let mut spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let mut elastic_subnet = match &spec.elastic_subnet {
+        Some(v) if v.transform_subnet_tx_id.is_some() => v.clone(),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "subnet not transformed yet (run 'elastic-subnet transform' first)",
+            ));
+        }
+    };
+    let params = elastic_subnet.params.clone();
+
+    let node_ids: Vec<String> = {
+        if node_ids.is_empty() {
+            spec.current_nodes
+                .clone()
+                .expect("unexpected None current_nodes (run 'apply' first)")
+                .iter()
+                .map(|n| n.node_id.clone())
+                .collect()
+        } else {
+            node_ids.split(',').map(String::from).collect()
+        }
+    };
+    for node_id in node_ids.iter() {
+        ids::parse_node_id(node_id)?;
+    }
+
+    if stake_amount < params.min_validator_stake || stake_amount > params.max_validator_stake {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "stake amount {} out of range [{}, {}]",
+                stake_amount, params.min_validator_stake, params.max_validator_stake
+            ),
+        ));
+    }
+    let stake_duration = stake_duration.unwrap_or(params.min_stake_duration as u64);
+    if stake_duration < params.min_stake_duration as u64
+        || stake_duration > params.max_stake_duration as u64
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "stake duration {} out of range [{}, {}]",
+                stake_duration, params.min_stake_duration, params.max_stake_duration
+            ),
+        ));
+    }
+    let delegation_fee = delegation_fee.unwrap_or(params.min_delegation_fee);
+    if delegation_fee < params.min_delegation_fee
+        || delegation_fee as u64 > txs::PERCENT_DENOMINATOR
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "delegation fee {} out of range [{}, {}]",
+                delegation_fee,
+                params.min_delegation_fee,
+                txs::PERCENT_DENOMINATOR
+            ),
+        ));
+    }
+
+    let http_rpc = super::get_http_rpc(&spec, http_rpc)?;
+    let k = super::load_funding_key(&spec, private_key_path)?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nAdding {} validators to subnet {} via {} (stake {}, duration {}s, delegation fee {})\n",
+            node_ids.len(),
+            params.subnet_id,
+            http_rpc,
+            stake_amount,
+            stake_duration,
+            delegation_fee
+        )),
+        ResetColor
+    )?;
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to add validators!",
+            "Yes, let's add validators!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'add-validator' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    let rt = Runtime::new().unwrap();
+    let w = crate::tx::load_wallet(
+        &rt,
+        k,
+        spec.avalanchego_config.network_id,
+        &http_rpc,
+        multisig_opts,
+    )?;
+    for node_id in node_ids.iter() {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print(format!(
+                "\n\n\nSTEP: issue AddPermissionlessValidatorTx for {}\n",
+                node_id
+            )),
+            ResetColor
+        )?;
+        let start = wallet::now_unix() + start_delay_seconds;
+        let end = start + stake_duration;
+        let issued = rt.block_on(w.add_permissionless_validator(
+            &params.subnet_id,
+            &params.asset_id,
+            txs::Validator {
+                node_id: ids::parse_node_id(node_id)?,
+                start,
+                end,
+                weight: stake_amount,
+            },
+            delegation_fee,
+        ))?;
+        let tx_id = match crate::tx::handle_issued(
+            issued,
+            &spec.id,
+            &format!("add-permissionless-validator-{}", node_id),
+            multisig_opts,
+        )? {
+            Some(v) => v,
+            None => continue,
+        };
+
+        // persist per validator in case of partial failures
+        let mut validators = elastic_subnet.validators.clone().unwrap_or_default();
+        validators.push(avalanche_ops::PermissionlessStaker {
+            node_id: node_id.clone(),
+            tx_id: tx_id.clone(),
+            weight: stake_amount,
+            start_time: start,
+            end_time: end,
+        });
+        elastic_subnet.validators = Some(validators);
+        spec.elastic_subnet = Some(elastic_subnet.clone());
+        spec.sync(spec_file_path)?;
+        println!("added validator {} (tx {})", node_id, tx_id);
+    }
+
+    println!();
+    info!("'elastic-subnet add-validator' all success!");
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind},
+};
+
+use clap::{Arg, Command};
+
+use avalanche_ops::{
+    self,
+    avalanche::{constants, key},
+};
+
+pub mod add_delegator;
+pub mod add_validator;
+pub mod transform;
+
+pub const NAME: &str = "elastic-subnet";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Transforms a permissioned subnet to an elastic (permissionless) subnet, and registers its stakers")
+        .subcommands(vec![
+            transform::subcommand(),
+            add_validator::subcommand(),
+            add_delegator::subcommand(),
+        ])
+}
+
+/// Returns the arguments shared by all "elastic-subnet" sub-commands.
+fn common_args() -> Vec<Arg<'static>> {
+    let mut args = vec![
+        Arg::new("LOG_LEVEL")
+            .long("log-level")
+            .short('l')
+            .help("Sets the log level")
+            .required(false)
+            .takes_value(true)
+            .possible_value("debug")
+            .possible_value("info")
+            .allow_invalid_utf8(false)
+            .default_value("info"),
+        Arg::new("SPEC_FILE_PATH")
+            .long("spec-file-path")
+            .short('s')
+            .help("The spec file to load and update")
+            .required(true)
+            .takes_value(true)
+            .allow_invalid_utf8(false),
+        Arg::new("PRIVATE_KEY_PATH")
+            .long("private-key-path")
+            .help("Sets the file path of the private key (CB58 'PrivateKey-' or hex) that pays for the transaction, or 'prefunded:N' for the N-th prefunded key in the spec")
+            .required(true)
+            .takes_value(true)
+            .allow_invalid_utf8(false),
+        Arg::new("HTTP_RPC")
+            .long("http-rpc")
+            .help("Sets the P-chain API endpoint (default to the public API for Fuji/mainnet, otherwise the spec endpoint)")
+            .required(false)
+            .takes_value(true)
+            .allow_invalid_utf8(false),
+        Arg::new("SKIP_PROMPT")
+            .long("skip-prompt")
+            .help("Skips prompt mode")
+            .required(false)
+            .takes_value(false)
+            .allow_invalid_utf8(false),
+    ];
+    args.extend(crate::tx::multisig_args());
+    args
+}
+
+/// Loads the key from the file with either CB58 "PrivateKey-" or hex encoding.
+pub fn load_key(private_key_path: &str) -> io::Result<key::Key> {
+    let contents = fs::read_to_string(private_key_path)?;
+    let contents = contents.trim();
+    if contents.starts_with(key::PRIVATE_KEY_ENCODE_PREFIX) {
+        key::Key::from_private_key(contents)
+    } else {
+        key::Key::from_private_key_hex(contents)
+    }
+}
+
+/// Loads the key that funds the transaction, signing on the CLI:
+/// "prefunded:N" for the N-th prefunded key in the local spec,
+/// otherwise the key file (see "load_key").
+pub fn load_funding_key(
+    spec: &avalanche_ops::Spec,
+    private_key_path: &str,
+) -> io::Result<key::Key> {
+    match private_key_path.strip_prefix(avalanche_ops::PREFUNDED_KEY_PATH_PREFIX) {
+        Some(index) => {
+            let index = index.parse::<usize>().map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid prefunded key index '{}' ({})", index, e),
+                )
+            })?;
+            spec.prefunded_key(index)
+        }
+        None => load_key(private_key_path),
+    }
+}
+
+/// Returns the P-chain API endpoint to issue transactions.
+pub fn get_http_rpc(spec: &avalanche_ops::Spec, http_rpc: &str) -> io::Result<String> {
+    if !http_rpc.is_empty() {
+        return Ok(http_rpc.to_string());
+    }
+    if let Some(v) =
+        constants::NETWORK_ID_TO_PUBLIC_API_ENDPOINT.get(&spec.avalanchego_config.network_id)
+    {
+        return Ok(v.to_string());
+    }
+    match spec.endpoints.clone().and_then(|e| e.http_rpc) {
+        Some(v) => Ok(v),
+        None => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "no '--http-rpc' and no 'endpoints.http_rpc' in spec (run 'apply' first)",
+        )),
+    }
+}
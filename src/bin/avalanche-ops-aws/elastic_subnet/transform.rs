@@ -0,0 +1,256 @@
+use std::io::{self, stdout, Error, ErrorKind};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{self, avalanche::platformvm::txs};
+
+pub const NAME: &str = "transform";
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Builds and issues the TransformSubnetTx to make the permissioned subnet elastic")
+        .args(super::common_args())
+        .arg(
+            Arg::new("SUBNET_ID")
+                .long("subnet-id")
+                .help("Sets the permissioned subnet ID to transform")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("ASSET_ID")
+                .long("asset-id")
+                .help("Sets the staking asset ID (must be already exported to the P-chain)")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("INITIAL_SUPPLY")
+                .long("initial-supply")
+                .help("Sets the initial supply of the staking asset")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("MAXIMUM_SUPPLY")
+                .long("maximum-supply")
+                .help("Sets the maximum supply of the staking asset ('maximum - initial' is burned for rewards)")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("MIN_CONSUMPTION_RATE")
+                .long("min-consumption-rate")
+                .help("Sets the minimum consumption rate of the reward pool (in units of 1,000,000)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("100000"),
+        )
+        .arg(
+            Arg::new("MAX_CONSUMPTION_RATE")
+                .long("max-consumption-rate")
+                .help("Sets the maximum consumption rate of the reward pool (in units of 1,000,000)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("120000"),
+        )
+        .arg(
+            Arg::new("MIN_VALIDATOR_STAKE")
+                .long("min-validator-stake")
+                .help("Sets the minimum validator stake")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("MAX_VALIDATOR_STAKE")
+                .long("max-validator-stake")
+                .help("Sets the maximum validator stake (including delegations)")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("MIN_STAKE_DURATION")
+                .long("min-stake-duration")
+                .help("Sets the minimum stake duration in seconds")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("86400"),
+        )
+        .arg(
+            Arg::new("MAX_STAKE_DURATION")
+                .long("max-stake-duration")
+                .help("Sets the maximum stake duration in seconds")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("31536000"),
+        )
+        .arg(
+            Arg::new("MIN_DELEGATION_FEE")
+                .long("min-delegation-fee")
+                .help("Sets the minimum delegation fee (in units of 1,000,000)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("20000"),
+        )
+        .arg(
+            Arg::new("MIN_DELEGATOR_STAKE")
+                .long("min-delegator-stake")
+                .help("Sets the minimum delegator stake")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("MAX_VALIDATOR_WEIGHT_FACTOR")
+                .long("max-validator-weight-factor")
+                .help("Sets the maximum ratio of the delegations to the validator stake")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("5"),
+        )
+        .arg(
+            Arg::new("UPTIME_REQUIREMENT")
+                .long("uptime-requirement")
+                .help("Sets the uptime required for rewards (in units of 1,000,000)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("800000"),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    private_key_path: &str,
+    http_rpc: &str,
+    params: txs::TransformSubnetParams,
+    skip_prompt: bool,
+    multisig_opts: &crate::tx::MultisigOpts,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let mut spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    if let Some(elastic_subnet) = &spec.elastic_subnet {
+        if let Some(tx_id) = &elastic_subnet.transform_subnet_tx_id {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "subnet {} already transformed (tx {})",
+                    elastic_subnet.params.subnet_id, tx_id
+                ),
+            ));
+        }
+    }
+    params.verify()?;
+
+    let http_rpc = super::get_http_rpc(&spec, http_rpc)?;
+    let rt = Runtime::new().unwrap();
+    let network_params = crate::tx::load_network_params(&rt, &spec, &http_rpc)?;
+    network_params.validate_transform_subnet(&params)?;
+    let k = super::load_funding_key(&spec, private_key_path)?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nTransforming subnet {} via {} with:\n{}\n",
+            params.subnet_id,
+            http_rpc,
+            serde_yaml::to_string(&params).unwrap_or_default()
+        )),
+        ResetColor
+    )?;
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to transform the subnet!",
+            "Yes, let's transform the subnet (irreversible)!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'transform' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print("\n\n\nSTEP: issue TransformSubnetTx\n"),
+        ResetColor
+    )?;
+    let mut w = crate::tx::load_wallet(
+        &rt,
+        k,
+        spec.avalanchego_config.network_id,
+        &http_rpc,
+        multisig_opts,
+    )?;
+    w.fees = network_params.tx_fees;
+    let issued = rt.block_on(w.transform_subnet(&params))?;
+    let tx_id = match crate::tx::handle_issued(issued, &spec.id, "transform-subnet", multisig_opts)?
+    {
+        Some(v) => v,
+        None => {
+            println!(
+                "once issued, set 'elastic_subnet.transform_subnet_tx_id' in spec '{}'",
+                spec_file_path
+            );
+            spec.elastic_subnet = Some(avalanche_ops::ElasticSubnet {
+                params,
+                transform_subnet_tx_id: None,
+                validators: None,
+                delegators: None,
+            });
+            spec.sync(spec_file_path)?;
+            return Ok(());
+        }
+    };
+
+    spec.elastic_subnet = Some(avalanche_ops::ElasticSubnet {
+        params,
+        transform_subnet_tx_id: Some(tx_id.clone()),
+        validators: None,
+        delegators: None,
+    });
+    spec.sync(spec_file_path)?;
+
+    println!();
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nTransformed subnet (tx {}), saved to spec '{}'\n",
+            tx_id, spec_file_path
+        )),
+        ResetColor
+    )?;
+    info!("'elastic-subnet transform' all success!");
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::{
+    env, fs,
+    io::{self, Error, ErrorKind},
+};
+
+use clap::{Arg, Command};
+use dialoguer::{theme::ColorfulTheme, Password};
+use log::info;
+
+use avalanche_ops::{self, spec_encryption};
+
+pub const NAME: &str = "encrypt-spec";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Encrypts the local spec file at rest (KMS or passphrase), which other commands then decrypt transparently")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to encrypt in place")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("KMS_KEY_ARN")
+                .long("kms-key-arn")
+                .help("Sets the KMS key ARN to envelope-encrypt with (if empty, uses the passphrase)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("DECRYPT")
+                .long("decrypt")
+                .help("Decrypts the spec file back to plaintext")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    kms_key_arn: Option<&str>,
+    decrypt: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let current = spec_encryption::read(spec_file_path)?;
+    if current == Some(spec_encryption::Encryption::Passphrase) {
+        prompt_passphrase(false)?;
+    }
+    // decrypts first, so the spec can be re-encrypted with the other key
+    let spec = avalanche_ops::Spec::load(spec_file_path)?;
+    let d = spec.encode_yaml()?.into_bytes();
+
+    if decrypt {
+        if current.is_none() {
+            info!("'{}' is not encrypted", spec_file_path);
+            return Ok(());
+        }
+        fs::write(spec_file_path, &d)?;
+        info!("decrypted '{}'", spec_file_path);
+        return Ok(());
+    }
+
+    let encryption = match kms_key_arn {
+        Some(arn) => spec_encryption::Encryption::Kms(arn.to_string()),
+        None => {
+            if current != Some(spec_encryption::Encryption::Passphrase) {
+                prompt_passphrase(true)?;
+            }
+            spec_encryption::Encryption::Passphrase
+        }
+    };
+    let sealed = spec_encryption::seal(&encryption, &d)?;
+    fs::write(spec_file_path, &sealed)?;
+    info!("encrypted '{}' ({:?})", spec_file_path, encryption);
+
+    if encryption == spec_encryption::Encryption::Passphrase {
+        println!(
+            "\nset '{}' to run the other commands with the spec\n",
+            spec_encryption::PASSPHRASE_ENV
+        );
+    }
+    Ok(())
+}
+
+/// Reads the passphrase from the terminal, unless already set in the environment.
+fn prompt_passphrase(confirm: bool) -> io::Result<()> {
+    if env::var(spec_encryption::PASSPHRASE_ENV).is_ok() {
+        return Ok(());
+    }
+    let theme = ColorfulTheme::default();
+    let mut prompt = Password::with_theme(&theme);
+    prompt.with_prompt("Spec passphrase");
+    if confirm {
+        prompt.with_confirmation("Confirm passphrase", "Passphrases do not match");
+    }
+    let passphrase = prompt
+        .interact()
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to read passphrase {}", e)))?;
+    env::set_var(spec_encryption::PASSPHRASE_ENV, passphrase);
+    Ok(())
+}
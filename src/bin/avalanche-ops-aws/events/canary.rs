@@ -0,0 +1,211 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Error, ErrorKind},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use clap::Arg;
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{self, aws::s3, canary};
+
+/// Max time for the canaries to apply the event and report healthy.
+const UPDATE_TIMEOUT: Duration = Duration::from_secs(1200);
+
+/// Heartbeats are published every 5-min, so the older ones
+/// mean the canary is down.
+const HEARTBEAT_STALE_SECONDS: u64 = 600;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn skip_canary_arg() -> Arg<'static> {
+    Arg::new("SKIP_CANARY")
+        .long("skip-canary")
+        .help("Updates the whole fleet at once, ignoring 'canary' in the spec")
+        .required(false)
+        .takes_value(false)
+        .allow_invalid_utf8(false)
+}
+
+/// Puts the update event. With "canary" in the spec, the event first targets
+/// the canary nodes, and only targets the rest of the fleet once the canaries
+/// have applied the event ("applied_after" seconds after the event, or running
+/// the expected binary) and stayed healthy for the soak time.
+/// Returns an error without updating the rest if any canary fails.
+pub fn put_event(
+    rt: &Runtime,
+    s3_manager: &s3::Manager,
+    spec: &avalanche_ops::Spec,
+    event_s3_key: &str,
+    applied_after: u64,
+    expected_bin_sha256: Option<&str>,
+    skip_canary: bool,
+) -> io::Result<()> {
+    let s3_bucket = Arc::new(
+        spec.aws_resources
+            .as_ref()
+            .expect("unexpected None aws_resources")
+            .s3_bucket
+            .clone(),
+    );
+    let canary = match &spec.canary {
+        Some(v) if !skip_canary => v,
+        _ => {
+            return put_rollout(
+                rt,
+                s3_manager,
+                &s3_bucket,
+                event_s3_key,
+                &canary::Rollout::fleet(Vec::new()),
+            )
+        }
+    };
+
+    let heartbeats = crate::fleet::fetch_heartbeats(rt, s3_manager, s3_bucket.clone(), &spec.id)?;
+    let canaries = canary.select(&heartbeats);
+    if canaries.is_empty() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            "no heartbeat found to pick the canary nodes (use '--skip-canary' to update all)",
+        ));
+    }
+    let rest: Vec<String> = heartbeats
+        .iter()
+        .map(|hb| hb.machine_id.clone())
+        .filter(|id| !canaries.contains(id))
+        .collect();
+
+    info!("STEP: updating the canary nodes {:?}", canaries);
+    let put_unix = unix_now();
+    put_rollout(
+        rt,
+        s3_manager,
+        &s3_bucket,
+        event_s3_key,
+        &canary::Rollout {
+            stage: canary::Stage::Canary,
+            machine_ids: canaries.clone(),
+        },
+    )?;
+
+    // wait for every canary to apply the event
+    let start = Instant::now();
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let statuses = fetch_statuses(
+            rt,
+            s3_manager,
+            &s3_bucket,
+            spec,
+            &canaries,
+            put_unix + applied_after,
+            expected_bin_sha256,
+        )?;
+        // the restarted canaries are unhealthy until bootstrapped
+        let pending: BTreeMap<&String, &canary::Status> = statuses
+            .iter()
+            .filter(|(_, s)| **s != canary::Status::Healthy)
+            .collect();
+        if pending.is_empty() {
+            break;
+        }
+        if start.elapsed() > UPDATE_TIMEOUT {
+            return Err(Error::new(
+                ErrorKind::TimedOut,
+                format!(
+                    "canaries {:?} not healthy with the update in {:?}, halting the rollout (the rest of the fleet is not updated)",
+                    pending, UPDATE_TIMEOUT
+                ),
+            ));
+        }
+        info!("waiting for the canaries {:?} to apply the event", pending);
+    }
+
+    info!(
+        "STEP: soaking the canary nodes for {} seconds",
+        canary.soak_seconds()
+    );
+    let soak_start = Instant::now();
+    while soak_start.elapsed() < Duration::from_secs(canary.soak_seconds()) {
+        thread::sleep(POLL_INTERVAL);
+        let statuses = fetch_statuses(
+            rt,
+            s3_manager,
+            &s3_bucket,
+            spec,
+            &canaries,
+            unix_now() - HEARTBEAT_STALE_SECONDS,
+            expected_bin_sha256,
+        )?;
+        for (id, s) in statuses.iter() {
+            if *s != canary::Status::Healthy {
+                warn!("canary {} failed ({:?})", id, s);
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!(
+                        "canary {} failed the soak ({:?}), halting the rollout (the rest of the fleet is not updated)",
+                        id, s
+                    ),
+                ));
+            }
+        }
+        info!(
+            "canaries healthy for {:?} (soak {} seconds)",
+            soak_start.elapsed(),
+            canary.soak_seconds()
+        );
+    }
+
+    info!("STEP: updating the rest of the fleet {:?}", rest);
+    put_rollout(
+        rt,
+        s3_manager,
+        &s3_bucket,
+        event_s3_key,
+        &canary::Rollout::fleet(rest),
+    )
+}
+
+fn put_rollout(
+    rt: &Runtime,
+    s3_manager: &s3::Manager,
+    s3_bucket: &Arc<String>,
+    event_s3_key: &str,
+    rollout: &canary::Rollout,
+) -> io::Result<()> {
+    rt.block_on(s3_manager.put_bytes(
+        rollout.encode_json()?.into_bytes(),
+        s3_bucket.clone(),
+        Arc::new(event_s3_key.to_string()),
+    ))
+    .map_err(|e| Error::new(ErrorKind::Other, format!("failed put_bytes {}", e)))
+}
+
+fn fetch_statuses(
+    rt: &Runtime,
+    s3_manager: &s3::Manager,
+    s3_bucket: &Arc<String>,
+    spec: &avalanche_ops::Spec,
+    canaries: &[String],
+    since: u64,
+    expected_bin_sha256: Option<&str>,
+) -> io::Result<BTreeMap<String, canary::Status>> {
+    let heartbeats = crate::fleet::fetch_heartbeats(rt, s3_manager, s3_bucket.clone(), &spec.id)?;
+    Ok(canaries
+        .iter()
+        .map(|id| {
+            let hb = heartbeats.iter().find(|hb| &hb.machine_id == id);
+            (id.clone(), canary::status(hb, since, expected_bin_sha256))
+        })
+        .collect())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("unexpected None duration_since")
+        .as_secs()
+}
@@ -1,11 +1,21 @@
 use clap::Command;
 
+pub mod canary;
+pub mod timeline;
 pub mod update_artifacts;
+pub mod update_chain_upgrades;
+pub mod update_peer_allowlist;
 
 pub const NAME: &str = "events";
 
 pub fn command() -> Command<'static> {
     Command::new(NAME)
-        .about("Events to trigger to the network")
+        .about("Events to trigger to the network, or the timeline of the node events ('events [NODE]')")
+        .args(timeline::args())
+        // "events [NODE]" shows the timeline, while the subcommands trigger the events
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
         .subcommand(update_artifacts::subcommand())
+        .subcommand(update_chain_upgrades::subcommand())
+        .subcommand(update_peer_allowlist::subcommand())
 }
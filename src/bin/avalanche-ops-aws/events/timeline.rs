@@ -0,0 +1,239 @@
+use std::io::{self, stdout, Error, ErrorKind};
+
+use chrono::{Duration, Utc};
+use clap::Arg;
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::heartbeat,
+    aws::{self, cloudformation, cloudwatch, s3},
+    support_bundle, timeline,
+};
+
+/// Max number of the heartbeat versions to read (7 days of 5-min heartbeats).
+const MAX_HEARTBEAT_VERSIONS: usize = 7 * 24 * 12;
+
+/// Max number of the log events to read per log stream.
+const MAX_LOG_EVENTS: usize = 1000;
+
+pub fn args() -> Vec<Arg<'static>> {
+    vec![
+        Arg::new("NODE")
+            .help("Sets the node ID (or the instance ID) to show the timeline of")
+            .required(true)
+            .index(1)
+            .takes_value(true)
+            .allow_invalid_utf8(false),
+        Arg::new("LOG_LEVEL")
+            .long("log-level")
+            .short('l')
+            .help("Sets the log level")
+            .required(false)
+            .takes_value(true)
+            .possible_value("debug")
+            .possible_value("info")
+            .allow_invalid_utf8(false)
+            .default_value("info"),
+        Arg::new("SPEC_FILE_PATH")
+            .long("spec-file-path")
+            .short('s')
+            .help("The spec file to load")
+            .required(true)
+            .takes_value(true)
+            .allow_invalid_utf8(false),
+        Arg::new("SINCE")
+            .long("since")
+            .help("Sets the timeline window (e.g., '30m', '2h', '1d')")
+            .required(false)
+            .takes_value(true)
+            .allow_invalid_utf8(false)
+            .default_value("1d"),
+    ]
+}
+
+/// Prints the node events (launched, bootstrapped, restarts, backups,
+/// upgrades, alarms), aggregated from the heartbeat versions in S3,
+/// the ASG stack events, and the avalanched logs in CloudWatch.
+pub fn execute(log_level: &str, spec_file_path: &str, node: &str, since: &str) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let since_minutes = support_bundle::parse_since(since)?;
+    let since = Utc::now() - Duration::minutes(since_minutes as i64);
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+    // the replaced nodes are no longer in the spec, but still have the heartbeats
+    let (machine_id, node_kind) = match spec
+        .current_nodes
+        .clone()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|n| n.node_id == node || n.machine_id == node)
+    {
+        Some(n) => (n.machine_id, Some(n.kind)),
+        None if node.starts_with("i-") => (node.to_string(), None),
+        None => {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "node '{}' not found in the spec current nodes (use the instance ID)",
+                    node
+                ),
+            ));
+        }
+    };
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+    let cfn_manager = cloudformation::Manager::new(&shared_config);
+    let cw_manager = cloudwatch::Manager::new(&shared_config);
+
+    info!("STEP: reading the heartbeat versions of '{}'", machine_id);
+    let heartbeats = fetch_heartbeat_versions(
+        &rt,
+        &s3_manager,
+        &aws_resources.s3_bucket,
+        &spec.id,
+        &machine_id,
+        since,
+    )
+    .unwrap_or_else(|e| {
+        warn!("skipping the heartbeats ({})", e);
+        Vec::new()
+    });
+    let node_kind = node_kind.or_else(|| heartbeats.first().map(|hb| hb.kind.clone()));
+    let mut sources = vec![timeline::from_heartbeats(&heartbeats)];
+
+    if let Some(kind) = &node_kind {
+        let stack_name = if kind == "anchor" {
+            aws_resources.cloudformation_asg_anchor_nodes.clone()
+        } else if kind == avalanche_ops::icm_relayer::NODE_KIND {
+            aws_resources.cloudformation_asg_icm_relayer.clone()
+        } else {
+            aws_resources.cloudformation_asg_non_anchor_nodes.clone()
+        };
+        if let Some(stack_name) = stack_name {
+            info!("STEP: reading the events of the stack '{}'", stack_name);
+            match rt.block_on(cfn_manager.list_stack_events(&stack_name, since)) {
+                Ok(v) => sources.push(timeline::from_stack_events(&v, &machine_id)),
+                Err(e) => warn!("skipping the stack events ({})", e.message()),
+            }
+        }
+
+        info!("STEP: reading the avalanched logs of '{}'", machine_id);
+        match rt.block_on(cw_manager.filter_log_events(
+            &spec.id,
+            vec![format!("{}-{}-avalanched", machine_id, kind)],
+            &timeline::avalanched_log_filter_pattern(),
+            since,
+            MAX_LOG_EVENTS,
+        )) {
+            Ok(v) => sources.push(timeline::from_avalanched_logs(&v)),
+            Err(e) => warn!("skipping the avalanched logs ({})", e.message()),
+        }
+        if spec.remediation.is_some() {
+            match rt.block_on(cw_manager.filter_log_events(
+                &spec.id,
+                vec![format!("{}-{}-remediation", machine_id, kind)],
+                "",
+                since,
+                MAX_LOG_EVENTS,
+            )) {
+                Ok(v) => sources.push(timeline::from_remediation_logs(&v)),
+                Err(e) => warn!("skipping the remediation logs ({})", e.message()),
+            }
+        }
+
+        // same name as the "ClockOffsetAlarm" in the ASG template
+        let alarm_name = format!("{}-{}-clock-offset", spec.id, kind);
+        match rt.block_on(cw_manager.list_alarm_state_changes(&alarm_name, since)) {
+            Ok(v) => sources.push(timeline::from_alarm_state_changes(&v)),
+            Err(e) => warn!("skipping the alarm history ({})", e.message()),
+        }
+    } else {
+        warn!(
+            "unknown node kind of '{}', showing the heartbeats only",
+            machine_id
+        );
+    }
+
+    let events = timeline::merge(sources, since);
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\n{} events of '{}' ({}) since {}\n\n",
+            events.len(),
+            node,
+            machine_id,
+            since.format("%Y-%m-%dT%H:%M:%SZ")
+        )),
+        ResetColor
+    )?;
+    for ev in events.iter() {
+        println!("{}", ev);
+    }
+    Ok(())
+}
+
+/// Reads the versions of the node heartbeat since the time,
+/// skipping the versions that fail the signature verification.
+fn fetch_heartbeat_versions(
+    rt: &Runtime,
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    id: &str,
+    machine_id: &str,
+    since: chrono::DateTime<Utc>,
+) -> io::Result<Vec<heartbeat::Heartbeat>> {
+    let s3_key =
+        avalanche_ops::StorageNamespace::Heartbeat(id.to_string(), machine_id.to_string()).encode();
+    let versions = rt
+        .block_on(s3_manager.list_object_versions(s3_bucket, &s3_key))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    let mut heartbeats = Vec::new();
+    for v in versions.iter().take(MAX_HEARTBEAT_VERSIONS) {
+        // newest first
+        let modified = v.last_modified().map(|t| t.secs()).unwrap_or_default();
+        if modified < since.timestamp() {
+            break;
+        }
+        let d = match rt
+            .block_on(s3_manager.get_bytes(s3_bucket, &s3_key, v.version_id()))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?
+        {
+            Some(d) => d,
+            None => continue,
+        };
+        // never render the unverified documents, as in "fleet"
+        let verified = serde_json::from_slice::<heartbeat::Signed>(&d)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+            .and_then(|signed| signed.verify());
+        match verified {
+            Ok(hb) => heartbeats.push(hb),
+            Err(e) => warn!(
+                "skipping invalid heartbeat version {:?} ({})",
+                v.version_id(),
+                e
+            ),
+        }
+    }
+    info!("read {} heartbeat versions", heartbeats.len());
+    Ok(heartbeats)
+}
@@ -1,6 +1,6 @@
 use std::{
     fs,
-    io::{self, stdout},
+    io::{self, stdout, Error, ErrorKind},
     sync::Arc,
 };
 
@@ -16,7 +16,7 @@ use tokio::runtime::Runtime;
 use avalanche_ops::{
     self,
     aws::{self, s3},
-    utils::{compress, random},
+    utils::{compress, provenance, random},
 };
 
 pub const NAME: &str = "update-artifacts";
@@ -70,6 +70,16 @@ pub fn subcommand() -> Command<'static> {
                 .takes_value(false)
                 .allow_invalid_utf8(false),
         )
+        .arg(
+            Arg::new("FORCE")
+                .long("force")
+                .help("Runs even outside of the maintenance window in the spec")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(crate::quorum::skip_quorum_check_arg())
+        .arg(super::canary::skip_canary_arg())
 }
 
 pub fn execute(
@@ -78,6 +88,9 @@ pub fn execute(
     install_artifacts_avalanche_bin: &str,
     install_artifacts_plugins_dir: &str,
     skip_prompt: bool,
+    force: bool,
+    skip_quorum_check: bool,
+    skip_canary: bool,
 ) -> io::Result<()> {
     // ref. https://github.com/env-logger-rs/env_logger/issues/47
     env_logger::init_from_env(
@@ -86,6 +99,8 @@ pub fn execute(
 
     let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
     spec.validate()?;
+    spec.check_maintenance_window("events update-artifacts", force)?;
+    crate::quorum::check(&spec, "events update-artifacts", skip_quorum_check)?;
 
     execute!(
         stdout(),
@@ -113,12 +128,24 @@ pub fn execute(
     }
 
     let rt = Runtime::new().unwrap();
-    let aws_resources = spec.aws_resources.expect("unexpected None aws_resources");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
     let shared_config = rt
         .block_on(aws::load_config(Some(aws_resources.region.clone())))
         .expect("failed to aws::load_config");
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
     let s3_manager = s3::Manager::new(&shared_config);
 
+    let avalanche_bin_provenance = spec
+        .install_artifacts
+        .verify_local(install_artifacts_avalanche_bin)?;
+
     // compress as these will be decompressed by "avalanched"
     let tmp_avalanche_bin_compressed_path =
         random::tmp_path(15, Some(compress::Encoder::Zstd(3).ext())).unwrap();
@@ -128,10 +155,11 @@ pub fn execute(
         compress::Encoder::Zstd(3),
     )
     .expect("failed pack_file install_artifacts_avalanche_bin");
-    rt.block_on(s3_manager.put_object(
+    rt.block_on(s3_manager.put_object_with_metadata(
         Arc::new(tmp_avalanche_bin_compressed_path.clone()),
         Arc::new(aws_resources.s3_bucket.clone()),
         Arc::new(avalanche_ops::StorageNamespace::EventsUpdateArtifactsInstallDirAvalancheBinCompressed(spec.id.clone()).encode()),
+        Some(avalanche_bin_provenance.to_metadata()),
     ))
     .expect("failed put_object compressed install_artifacts_avalanche_bin");
     fs::remove_file(tmp_avalanche_bin_compressed_path)?;
@@ -143,6 +171,10 @@ pub fn execute(
             let file_path = entry_path.to_str().unwrap();
             let file_name = entry.file_name();
             let file_name = file_name.as_os_str().to_str().unwrap();
+            if provenance::is_provenance_file(file_name) {
+                continue;
+            }
+            let plugin_provenance = spec.install_artifacts.verify_local(file_path)?;
 
             let tmp_plugin_compressed_path =
                 random::tmp_path(15, Some(compress::Encoder::Zstd(3).ext())).unwrap();
@@ -158,7 +190,7 @@ pub fn execute(
                 tmp_plugin_compressed_path, file_path, install_artifacts_plugins_dir,
             );
             rt.block_on(
-                s3_manager.put_object(
+                s3_manager.put_object_with_metadata(
                     Arc::new(tmp_plugin_compressed_path.clone()),
                         Arc::new(aws_resources.s3_bucket.clone()),
                     Arc::new(format!(
@@ -167,18 +199,22 @@ pub fn execute(
                         file_name,
                         compress::Encoder::Zstd(3).ext()
                     )),
+                    Some(plugin_provenance.to_metadata()),
                 ),
             )
             .expect("failed put_object tmp_plugin_compressed_path");
             fs::remove_file(tmp_plugin_compressed_path)?;
         }
     }
-    rt.block_on(s3_manager.put_object(
-        Arc::new(spec_file_path.to_string()),
-        Arc::new(aws_resources.s3_bucket),
-        Arc::new(avalanche_ops::StorageNamespace::EventsUpdateArtifactsEvent(spec.id).encode()),
-    ))
-    .expect("failed put_object EventsUpdateArtifactsEvent");
+    super::canary::put_event(
+        &rt,
+        &s3_manager,
+        &spec,
+        &avalanche_ops::StorageNamespace::EventsUpdateArtifactsEvent(spec.id.clone()).encode(),
+        0,
+        Some(&avalanche_bin_provenance.sha256),
+        skip_canary,
+    )?;
 
     println!();
     info!("update-artifacts all success!");
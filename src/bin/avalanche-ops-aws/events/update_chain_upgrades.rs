@@ -0,0 +1,216 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::avalanchego::upgrade,
+    aws::{self, s3},
+};
+
+pub const NAME: &str = "update-chain-upgrades";
+
+/// "avalanched" applies the event modified in the last 6-min,
+/// so the canary heartbeats after that reflect the upgrade files.
+const CHAIN_UPGRADES_APPLIED_AFTER_SECONDS: u64 = 360;
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Uploads chain upgrade files and triggers the coordinated restarts based on the spec file")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load (with 'chain_upgrades')")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("MIN_LEAD_SECONDS")
+                .long("min-lead-seconds")
+                .help("Sets the minimum seconds between now and the earliest activation timestamp, for all nodes to restart in time")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("1800"),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("FORCE")
+                .long("force")
+                .help("Runs even outside of the maintenance window in the spec")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(crate::quorum::skip_quorum_check_arg())
+        .arg(super::canary::skip_canary_arg())
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    min_lead_seconds: u64,
+    skip_prompt: bool,
+    force: bool,
+    skip_quorum_check: bool,
+    skip_canary: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    spec.validate()?;
+    spec.check_maintenance_window("events update-chain-upgrades", force)?;
+    crate::quorum::check(&spec, "events update-chain-upgrades", skip_quorum_check)?;
+    let chain_upgrades = match &spec.chain_upgrades {
+        Some(v) if !v.is_empty() => v.clone(),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "no 'chain_upgrades' found in spec",
+            ));
+        }
+    };
+
+    // every node polls the event every 3-min, so the activation must leave
+    // enough time for the whole fleet to pick up the files and restart
+    let now_unix = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("unexpected None duration_since")
+        .as_secs();
+    // the rest of the fleet only restarts after the canaries soak
+    let min_lead_seconds = match &spec.canary {
+        Some(c) if !skip_canary => min_lead_seconds + c.soak_seconds(),
+        _ => min_lead_seconds,
+    };
+    for u in chain_upgrades.iter() {
+        let (_, timestamps) = upgrade::load(&u.file_path)?;
+        info!(
+            "chain {} upgrade activation timestamps {:?}",
+            u.chain_id, timestamps
+        );
+        if let Some(earliest) = timestamps.first() {
+            if *earliest < now_unix + min_lead_seconds {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "chain {} activation {} is within {} seconds from now {} (too soon to restart the fleet)",
+                        u.chain_id, earliest, min_lead_seconds, now_unix
+                    ),
+                ));
+            }
+        }
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!("\nLoaded Spec: '{}'\n", spec_file_path)),
+        ResetColor
+    )?;
+    for u in chain_upgrades.iter() {
+        println!("{}: {}", u.chain_id, u.file_path);
+    }
+    println!();
+
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to update chain upgrades!",
+            "Yes, let's update chain upgrades!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'update-chain-upgrades' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    let rt = Runtime::new().unwrap();
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    let s3_manager = s3::Manager::new(&shared_config);
+
+    for u in chain_upgrades.iter() {
+        info!(
+            "uploading chain upgrade file {} for {}",
+            u.file_path, u.chain_id
+        );
+        rt.block_on(
+            s3_manager.put_object(
+                Arc::new(u.file_path.clone()),
+                Arc::new(aws_resources.s3_bucket.clone()),
+                Arc::new(
+                    avalanche_ops::StorageNamespace::ChainUpgradeFile(
+                        spec.id.clone(),
+                        u.chain_id.clone(),
+                    )
+                    .encode(),
+                ),
+            ),
+        )
+        .expect("failed put_object chain upgrade file");
+    }
+    super::canary::put_event(
+        &rt,
+        &s3_manager,
+        &spec,
+        &avalanche_ops::StorageNamespace::EventsUpdateChainUpgradesEvent(spec.id.clone()).encode(),
+        CHAIN_UPGRADES_APPLIED_AFTER_SECONDS,
+        None,
+        skip_canary,
+    )?;
+
+    println!();
+    info!("update-chain-upgrades all success!");
+    println!();
+
+    Ok(())
+}
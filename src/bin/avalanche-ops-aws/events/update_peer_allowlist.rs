@@ -0,0 +1,190 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    time::SystemTime,
+};
+
+use chrono::Utc;
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, s3},
+    peer_allowlist,
+};
+
+pub const NAME: &str = "update-peer-allowlist";
+
+/// "avalanched" applies the event modified in the last 6-min,
+/// so the canary heartbeats after that reflect the allowlist.
+const PEER_ALLOWLIST_APPLIED_AFTER_SECONDS: u64 = 360;
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Re-generates the peer allowlist from the cluster nodes and the partner node IDs, and pushes it to all nodes")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load (with 'peer_allowlist')")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("FORCE")
+                .long("force")
+                .help("Runs even outside of the maintenance window in the spec")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(crate::quorum::skip_quorum_check_arg())
+        .arg(super::canary::skip_canary_arg())
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    skip_prompt: bool,
+    force: bool,
+    skip_quorum_check: bool,
+    skip_canary: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    spec.validate()?;
+    spec.check_maintenance_window("events update-peer-allowlist", force)?;
+    crate::quorum::check(&spec, "events update-peer-allowlist", skip_quorum_check)?;
+    let cfg = spec
+        .peer_allowlist
+        .clone()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "no 'peer_allowlist' found in spec"))?;
+
+    let rt = Runtime::new().unwrap();
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+
+    let now_unix = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("unexpected None duration_since")
+        .as_secs();
+    let cluster_node_ids = rt.block_on(peer_allowlist::fetch_registry(
+        &s3_manager,
+        &aws_resources.s3_bucket,
+        &spec.id,
+        now_unix,
+    ))?;
+    let allowlist = cfg.generate(&cluster_node_ids, Utc::now());
+    let prev = rt.block_on(peer_allowlist::get(
+        &s3_manager,
+        &aws_resources.s3_bucket,
+        &spec.id,
+    ))?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!("\nLoaded Spec: '{}'\n", spec_file_path)),
+        ResetColor
+    )?;
+    let prev_node_ids = prev.map(|v| v.node_ids).unwrap_or_default();
+    for node_id in allowlist.node_ids.iter() {
+        let mark = if prev_node_ids.contains(node_id) {
+            " "
+        } else {
+            "+"
+        };
+        let source = if cluster_node_ids.contains(node_id) {
+            "cluster"
+        } else {
+            "partner"
+        };
+        println!("{} {} ({})", mark, node_id, source);
+    }
+    for node_id in prev_node_ids.iter() {
+        if !allowlist.contains(node_id) {
+            println!("- {}", node_id);
+        }
+    }
+    println!();
+
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to update the peer allowlist!",
+            "Yes, let's update the peer allowlist!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'update-peer-allowlist' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    rt.block_on(peer_allowlist::put(
+        &s3_manager,
+        &aws_resources.s3_bucket,
+        &spec.id,
+        &allowlist,
+    ))?;
+    super::canary::put_event(
+        &rt,
+        &s3_manager,
+        &spec,
+        &avalanche_ops::StorageNamespace::EventsUpdatePeerAllowlistEvent(spec.id.clone()).encode(),
+        PEER_ALLOWLIST_APPLIED_AFTER_SECONDS,
+        None,
+        skip_canary,
+    )?;
+
+    println!();
+    info!("update-peer-allowlist all success!");
+    println!();
+
+    Ok(())
+}
@@ -0,0 +1,274 @@
+use std::{
+    fs::{self, File},
+    io::{self, stdout, Error, ErrorKind, Write},
+    path::Path,
+    process::Command as ProcessCommand,
+    sync::Arc,
+};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::node,
+    aws::{self, s3},
+    utils::{compress, home_dir, random},
+};
+
+pub const NAME: &str = "export-join-bundle";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Exports a tarball for external nodes to join the network from their own infrastructure")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("OUTPUT_PATH")
+                .long("output-path")
+                .short('o')
+                .help("Sets the output tarball path (default to '$HOME/[ID].join-bundle.tar.gz')")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(log_level: &str, spec_file_path: &str, output_path: &str) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec.aws_resources.clone().unwrap();
+    let current_nodes = spec
+        .current_nodes
+        .clone()
+        .expect("unexpected None current_nodes in spec file (run 'apply' first)");
+
+    let output_path = {
+        if output_path.is_empty() {
+            home_dir::named(
+                &spec.id,
+                Some(format!(".join-bundle{}", compress::DirEncoder::TarGzip.ext()).as_str()),
+            )
+        } else {
+            output_path.to_string()
+        }
+    };
+
+    let bundle_dir = random::tmp_path(10, None)?;
+    let bundle_dir = Path::new(&bundle_dir).join(format!("{}-join-bundle", spec.id));
+    fs::create_dir_all(&bundle_dir)?;
+    let bundle_dir_path = bundle_dir.as_path().to_str().unwrap().to_string();
+
+    // for custom networks, only anchor nodes are the bootstrap nodes
+    // for other networks, any node we run can serve as the bootstrap node
+    let staking_port = spec.avalanchego_config.staking_port;
+    let mut bootstrap_ips: Vec<String> = Vec::new();
+    let mut bootstrap_ids: Vec<String> = Vec::new();
+    for n in current_nodes.iter() {
+        if spec.avalanchego_config.is_custom_network() && n.kind != node::Kind::Anchor.as_str() {
+            continue;
+        }
+        bootstrap_ips.push(format!("{}:{}", n.public_ip, staking_port));
+        bootstrap_ids.push(n.node_id.clone());
+    }
+    if let Some(external_bootstrap_nodes) = &spec.external_bootstrap_nodes {
+        for n in external_bootstrap_nodes.iter() {
+            bootstrap_ips.push(n.ip.clone());
+            bootstrap_ids.push(n.node_id.clone());
+        }
+    }
+    if bootstrap_ids.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "no bootstrap node found in 'current_nodes'",
+        ));
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\n\n\nSTEP: writing join bundle to {}\n",
+            bundle_dir_path
+        )),
+        ResetColor
+    )?;
+
+    // the joining node generates its own staking certs and uses its own public IP
+    let mut avalanchego_config = spec.avalanchego_config.clone();
+    avalanchego_config.public_ip = None;
+    avalanchego_config.bootstrap_ips = Some(bootstrap_ips.join(","));
+    avalanchego_config.bootstrap_ids = Some(bootstrap_ids.join(","));
+    avalanchego_config.sync(Some(
+        bundle_dir
+            .join("avalanche.config.json")
+            .to_str()
+            .unwrap()
+            .to_string(),
+    ))?;
+
+    let chain_config_c_dir = bundle_dir.join("configs").join("chains").join("C");
+    fs::create_dir_all(&chain_config_c_dir)?;
+    spec.coreth_config
+        .sync(chain_config_c_dir.join("config.json").to_str().unwrap())?;
+
+    if spec.avalanchego_config.is_custom_network() {
+        info!("downloading the genesis file with initial stakers from S3");
+        let rt = Runtime::new().unwrap();
+        let shared_config = rt
+            .block_on(aws::load_config(Some(aws_resources.region.clone())))
+            .expect("failed to aws::load_config");
+        let s3_manager = s3::Manager::new(&shared_config);
+        rt.block_on(
+            s3_manager.get_object(
+                Arc::new(aws_resources.s3_bucket.clone()),
+                Arc::new(avalanche_ops::StorageNamespace::GenesisFile(spec.id.clone()).encode()),
+                Arc::new(
+                    bundle_dir
+                        .join("avalanche.genesis.json")
+                        .to_str()
+                        .unwrap()
+                        .to_string(),
+                ),
+            ),
+        )
+        .expect("failed get_object genesis file");
+    }
+    if let Some(subnet_evm_genesis) = &spec.subnet_evm_genesis {
+        subnet_evm_genesis.sync(bundle_dir.join("subnet-evm.genesis.json").to_str().unwrap())?;
+    }
+
+    let avalanchego_version = get_avalanchego_version(&spec.install_artifacts.avalanchego_bin);
+    let mut f = File::create(bundle_dir.join("VERSION"))?;
+    f.write_all(format!("{}\n", avalanchego_version).as_bytes())?;
+
+    let mut f = File::create(bundle_dir.join("README.md"))?;
+    f.write_all(
+        readme(
+            &spec,
+            &avalanchego_version,
+            &bootstrap_ips.join(","),
+            &bootstrap_ids.join(","),
+        )
+        .as_bytes(),
+    )?;
+
+    compress::pack_directory(
+        &bundle_dir_path,
+        &output_path,
+        compress::DirEncoder::TarGzip,
+    )?;
+    fs::remove_dir_all(Path::new(&bundle_dir_path).parent().unwrap())?;
+
+    println!();
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!("\nSaved join bundle: '{}'\n", output_path)),
+        ResetColor
+    )?;
+    info!("'export-join-bundle' all success!");
+    Ok(())
+}
+
+/// Returns the version output of the local avalanchego binary,
+/// assuming it is the same binary uploaded to the remote machines.
+fn get_avalanchego_version(avalanchego_bin: &str) -> String {
+    match ProcessCommand::new(avalanchego_bin)
+        .arg("--version")
+        .output()
+    {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        Err(e) => {
+            warn!("failed to get avalanchego version ({}), skipping...", e);
+            String::from("unknown")
+        }
+    }
+}
+
+fn readme(
+    spec: &avalanche_ops::Spec,
+    avalanchego_version: &str,
+    bootstrap_ips: &str,
+    bootstrap_ids: &str,
+) -> String {
+    let config = &spec.avalanchego_config;
+    let genesis_step = {
+        if config.is_custom_network() {
+            format!(
+                "cp avalanche.genesis.json {}\n",
+                config.genesis.clone().unwrap_or_default()
+            )
+        } else {
+            String::new()
+        }
+    };
+    format!(
+        "# Join bundle for '{}'
+
+- network ID: {}
+- avalanchego version: {}
+- bootstrap IPs: {}
+- bootstrap IDs: {}
+
+## Steps
+
+Install the same avalanchego version, and make sure the staking port {} is reachable
+from the bootstrap nodes. The staking TLS key and certificate are generated on the first
+run at the paths in 'avalanche.config.json' if they do not exist.
+
+```bash
+{}cp avalanche.config.json {}
+mkdir -p {}
+cp -r configs/chains/* {}/
+
+avalanchego --config-file={}
+```
+",
+        spec.id,
+        config.network_id,
+        avalanchego_version,
+        bootstrap_ips,
+        bootstrap_ids,
+        config.staking_port,
+        genesis_step,
+        config
+            .config_file
+            .clone()
+            .unwrap_or_else(|| String::from("avalanche.config.json")),
+        config.chain_config_dir,
+        config.chain_config_dir,
+        config
+            .config_file
+            .clone()
+            .unwrap_or_else(|| String::from("avalanche.config.json")),
+    )
+}
@@ -0,0 +1,216 @@
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, stdout, Error, ErrorKind, Write},
+    os::unix::fs::PermissionsExt,
+    sync::Arc,
+};
+
+use chrono::Utc;
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Password};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, envelope, kms, s3},
+    key_escrow, StorageNamespace,
+};
+
+pub const NAME: &str = "export-keys";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Exports all key material of the cluster (staking, BLS, subnet control keys) into an encrypted archive for offline escrow")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("OUTPUT_PATH")
+                .long("output-path")
+                .short('o')
+                .help("Sets the output archive path (default to '$HOME/[ID].keys.escrow')")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("KMS_KEY_ARN")
+                .long("kms-key-arn")
+                .help("Sets the KMS key ARN to envelope-encrypt with, other than the cluster key (if empty, uses the passphrase)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    output_path: &str,
+    kms_key_arn: Option<&str>,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec.aws_resources.clone().unwrap();
+    let output_path = if output_path.is_empty() {
+        avalanche_ops::utils::home_dir::named(&spec.id, Some(".keys.escrow"))
+    } else {
+        output_path.to_string()
+    };
+
+    // the archive must be recoverable without the cluster KMS key
+    if kms_key_arn.is_some() && kms_key_arn == aws_resources.kms_cmk_arn.as_deref() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "'--kms-key-arn' must not be the cluster KMS key, which 'delete' schedules for deletion",
+        ));
+    }
+    let protection = match kms_key_arn {
+        Some(arn) => key_escrow::Protection::Kms(arn.to_string()),
+        None => {
+            prompt_passphrase(true)?;
+            key_escrow::Protection::Passphrase
+        }
+    };
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+    let envelope = envelope::Envelope::new(
+        Some(kms::Manager::new(&shared_config)),
+        aws_resources.kms_cmk_id.clone(),
+    );
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\n\n\nSTEP: collecting key material from '{}'\n",
+            aws_resources.s3_bucket
+        )),
+        ResetColor
+    )?;
+    let s3_bucket = Arc::new(aws_resources.s3_bucket.clone());
+    let mut entries: Vec<key_escrow::Entry> = Vec::new();
+    for prefix in [
+        StorageNamespace::PkiKeyDir(spec.id.clone()).encode(),
+        format!("{}/subnet-control-keys", spec.id),
+    ] {
+        let objects = rt
+            .block_on(s3_manager.list_objects(s3_bucket.clone(), Some(Arc::new(prefix))))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+        for obj in objects.iter() {
+            let s3_key = obj.key().unwrap_or_default();
+            let (kind, name) = match key_escrow::Kind::parse_s3_key(&spec.id, s3_key) {
+                Some(v) => v,
+                None => continue,
+            };
+            let d = rt
+                .block_on(s3_manager.get_bytes(&s3_bucket, s3_key, None))
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))?
+                .ok_or_else(|| {
+                    Error::new(ErrorKind::NotFound, format!("'{}' not found", s3_key))
+                })?;
+            let plaintext = rt.block_on(kind.open(&envelope, &d))?;
+            info!("collected {:?} '{}'", kind, name);
+            entries.push(key_escrow::Entry {
+                kind,
+                name,
+                data: hex::encode(plaintext),
+            });
+        }
+    }
+    if entries.is_empty() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            "no key material found (run 'apply' first)",
+        ));
+    }
+
+    let archive = key_escrow::Archive {
+        cluster_id: spec.id.clone(),
+        network_id: spec.avalanchego_config.network_id,
+        exported_at: Utc::now(),
+        entries,
+    };
+    let sealed = rt.block_on(key_escrow::seal(&protection, &archive))?;
+
+    let mut f = File::create(&output_path)?;
+    fs::set_permissions(&output_path, PermissionsExt::from_mode(0o600))?;
+    f.write_all(&sealed)?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\n\n\nSTEP: wrote key escrow archive {}\n",
+            output_path
+        )),
+        ResetColor
+    )?;
+    for (kind, n) in archive.counts().iter() {
+        println!("{:?}: {}", kind, n);
+    }
+    if archive.has_instance_keys() {
+        println!(
+            "\nsome node keys are named after the instance IDs, set 'static_node_keys' so 'import-keys' can restore them\n"
+        );
+    }
+    if protection == key_escrow::Protection::Passphrase {
+        println!(
+            "\nkeep the passphrase apart from the archive, 'import-keys' reads it from '{}'\n",
+            key_escrow::PASSPHRASE_ENV
+        );
+    }
+
+    info!("'export-keys' all success!");
+    Ok(())
+}
+
+/// Reads the archive passphrase from the terminal, unless already set in the environment.
+pub fn prompt_passphrase(confirm: bool) -> io::Result<()> {
+    if env::var(key_escrow::PASSPHRASE_ENV).is_ok() {
+        return Ok(());
+    }
+    let theme = ColorfulTheme::default();
+    let mut prompt = Password::with_theme(&theme);
+    prompt.with_prompt("Key escrow passphrase");
+    if confirm {
+        prompt.with_confirmation("Confirm passphrase", "Passphrases do not match");
+    }
+    let passphrase = prompt
+        .interact()
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to read passphrase {}", e)))?;
+    env::set_var(key_escrow::PASSPHRASE_ENV, passphrase);
+    Ok(())
+}
@@ -0,0 +1,520 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    path::Path,
+    sync::Arc,
+};
+
+use chrono::Utc;
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::node,
+    aws::{self, envelope, kms, route53, s3},
+    deployer, failover, key_escrow, network_snapshot, node_keys, StorageNamespace,
+};
+
+pub const NAME: &str = "failover";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Promotes the stand-by cluster in the replica region from the replicated node keys and backups, and points the Route53 records to it (run with '--prepare' ahead of time)")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file of the primary cluster")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("TO_REGION")
+                .long("to-region")
+                .help("Sets the region to fail over to (must be 'aws_resources.replica_s3_region')")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("STANDBY_SPEC_FILE_PATH")
+                .long("standby-spec-file-path")
+                .help("The spec file of the stand-by cluster (written by '--prepare')")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("PREPARE")
+                .long("prepare")
+                .help("Replicates the node keys to the replica bucket and writes the stand-by spec, without promoting (re-run on the node changes)")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("KMS_KEY_ARN")
+                .long("kms-key-arn")
+                .help("Sets the KMS key in the replica region to re-encrypt the node keys with (required with '--prepare')")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("ROUTE53_HOSTED_ZONE_ID")
+                .long("route53-hosted-zone-id")
+                .help("Sets the Route53 hosted zone of the records to point to the stand-by NLB")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("ROUTE53_RECORD_NAMES")
+                .long("route53-record-names")
+                .help("Sets the comma-separated record names to point to the stand-by NLB (e.g., 'rpc.example.com')")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .short('p')
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    to_region: &str,
+    standby_spec_file_path: &str,
+    prepare: bool,
+    kms_key_arn: Option<&str>,
+    route53_hosted_zone_id: Option<&str>,
+    route53_record_names: Option<&str>,
+    skip_prompt: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let primary = avalanche_ops::Spec::load(spec_file_path)?;
+    failover::validate_primary(&primary, to_region)?;
+    let record_names: Vec<String> = route53_record_names
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if !record_names.is_empty() && route53_hosted_zone_id.is_none() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "'--route53-record-names' requires '--route53-hosted-zone-id'",
+        ));
+    }
+
+    if prepare {
+        let kms_key_arn = kms_key_arn.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "'--prepare' requires '--kms-key-arn' in the replica region",
+            )
+        })?;
+        return execute_prepare(
+            &primary,
+            spec_file_path,
+            to_region,
+            standby_spec_file_path,
+            kms_key_arn,
+        );
+    }
+    execute_promote(
+        &primary,
+        to_region,
+        standby_spec_file_path,
+        route53_hosted_zone_id,
+        &record_names,
+        skip_prompt,
+    )
+}
+
+/// Copies the node keys of every slot to the replica bucket, re-encrypted with
+/// the KMS key in the replica region, and writes the stand-by spec.
+/// Runs while the primary region is healthy.
+fn execute_prepare(
+    primary: &avalanche_ops::Spec,
+    spec_file_path: &str,
+    to_region: &str,
+    standby_spec_file_path: &str,
+    kms_key_arn: &str,
+) -> io::Result<()> {
+    if kms_key_arn.split(':').nth(3) != Some(to_region) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "KMS key '{}' is not in '{}' (the primary region key is unusable in the outage)",
+                kms_key_arn, to_region
+            ),
+        ));
+    }
+    let aws_resources = primary.aws_resources.clone().unwrap();
+    let cluster_kms_key_arn = aws_resources
+        .kms_cmk_arn
+        .clone()
+        .expect("unexpected None kms_cmk_arn in spec file (run 'apply' first)");
+    let replica_s3_bucket = Arc::new(aws_resources.replica_s3_bucket.clone().unwrap());
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt.block_on(aws::load_config(Some(aws_resources.region.clone())))?;
+    let replica_config = rt.block_on(aws::load_config(Some(to_region.to_string())))?;
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &primary.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    let s3_manager = s3::Manager::new(&shared_config);
+    let replica_s3_manager = s3::Manager::new(&replica_config);
+    let cluster_envelope = envelope::Envelope::new(
+        Some(kms::Manager::new(&shared_config)),
+        Some(cluster_kms_key_arn),
+    );
+    let failover_envelope = envelope::Envelope::new(
+        Some(kms::Manager::new(&replica_config)),
+        Some(kms_key_arn.to_string()),
+    );
+
+    let s3_bucket = aws_resources.s3_bucket.clone();
+    let failover_dir = failover::dir(&primary.id);
+    let get = |s3_key: &str| -> io::Result<Option<Vec<u8>>> {
+        rt.block_on(s3_manager.get_bytes(&s3_bucket, s3_key, None))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))
+    };
+    let put = |d: Vec<u8>, s3_key: &str| -> io::Result<()> {
+        rt.block_on(replica_s3_manager.put_bytes(
+            d,
+            replica_s3_bucket.clone(),
+            Arc::new(s3_key.to_string()),
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))
+    };
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\n\n\nSTEP: replicating node keys to 's3://{}/{}'\n",
+            replica_s3_bucket, failover_dir
+        )),
+        ResetColor
+    )?;
+    if primary.avalanchego_config.is_custom_network() {
+        let genesis = get(&StorageNamespace::GenesisFile(primary.id.clone()).encode())?
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "genesis file not found"))?;
+        put(genesis, &network_snapshot::genesis_key(&failover_dir))?;
+    }
+
+    let mut slot_ids = node_keys::slot_ids(
+        &primary.id,
+        &node::Kind::Anchor,
+        primary.machine.anchor_nodes.unwrap_or(0),
+    );
+    slot_ids.extend(node_keys::slot_ids(
+        &primary.id,
+        &node::Kind::NonAnchor,
+        primary.machine.non_anchor_nodes,
+    ));
+    let mut slots = Vec::new();
+    for slot_id in slot_ids.iter() {
+        let slot = node_keys::slot_name(slot_id);
+        let cluster_keys = node_keys::S3Keys::new(&primary.id, &slot);
+        let failover_keys = network_snapshot::node_keys(&failover_dir, &slot);
+        let cert = match get(&cluster_keys.tls_cert)? {
+            Some(v) => v,
+            None => {
+                warn!("no node keys backed up for the slot '{}', skipping", slot);
+                continue;
+            }
+        };
+        put(cert, &failover_keys.tls_cert)?;
+        for (kind, src, dst) in [
+            (
+                key_escrow::Kind::TlsKey,
+                &cluster_keys.tls_key_encrypted,
+                &failover_keys.tls_key_encrypted,
+            ),
+            (
+                key_escrow::Kind::SignerKey,
+                &cluster_keys.signer_key_encrypted,
+                &failover_keys.signer_key_encrypted,
+            ),
+        ] {
+            let d = match get(src)? {
+                Some(v) => v,
+                // no BLS signer key configured
+                None => continue,
+            };
+            let plaintext = rt.block_on(kind.open(&cluster_envelope, &d))?;
+            put(
+                rt.block_on(kind.close(&failover_envelope, &plaintext))?,
+                dst,
+            )?;
+        }
+        info!("replicated node keys of the slot '{}'", slot);
+        slots.push(slot);
+    }
+
+    // written last, so the incomplete replication has no manifest
+    let manifest = failover::Manifest {
+        cluster_id: primary.id.clone(),
+        region: to_region.to_string(),
+        created_at: Utc::now(),
+        kms_key_arn: kms_key_arn.to_string(),
+        slots: slots.clone(),
+    };
+    put(
+        manifest.encode_yaml()?.into_bytes(),
+        &network_snapshot::manifest_key(&failover_dir),
+    )?;
+
+    let db_backup_key = rt.block_on(replicated_backup_key(
+        &replica_s3_manager,
+        primary,
+        &replica_s3_bucket,
+    ))?;
+    let standby = failover::standby_spec(
+        primary,
+        to_region,
+        kms_key_arn,
+        db_backup_key.as_deref().unwrap_or_default(),
+    )?;
+    standby.validate()?;
+    standby.sync(standby_spec_file_path)?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nReplicated the node keys of {} slots, and wrote the stand-by spec to '{}'\n",
+            slots.len(),
+            standby_spec_file_path
+        )),
+        ResetColor
+    )?;
+    println!(
+        "avalanche-ops-aws {} --spec-file-path {} --to-region {} --standby-spec-file-path {}\n",
+        NAME, spec_file_path, to_region, standby_spec_file_path
+    );
+    Ok(())
+}
+
+/// Provisions the stand-by cluster from the replicated node keys and backups,
+/// and points the Route53 records to its NLB. Only needs the replica region.
+fn execute_promote(
+    primary: &avalanche_ops::Spec,
+    to_region: &str,
+    standby_spec_file_path: &str,
+    route53_hosted_zone_id: Option<&str>,
+    record_names: &[String],
+    skip_prompt: bool,
+) -> io::Result<()> {
+    if !Path::new(standby_spec_file_path).exists() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "stand-by spec '{}' not found (run '{} --prepare' while the primary region is healthy)",
+                standby_spec_file_path, NAME
+            ),
+        ));
+    }
+    let mut standby = avalanche_ops::Spec::load(standby_spec_file_path)?;
+    let mut standby_resources = standby.aws_resources.clone().unwrap();
+    if standby_resources.region != to_region || standby.id != primary.id {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "stand-by spec '{}' is not of '{}' in '{}'",
+                standby_spec_file_path, primary.id, to_region
+            ),
+        ));
+    }
+    let replica_s3_bucket = primary
+        .aws_resources
+        .clone()
+        .unwrap()
+        .replica_s3_bucket
+        .unwrap();
+
+    let rt = Runtime::new().unwrap();
+    let replica_config = rt.block_on(aws::load_config(Some(to_region.to_string())))?;
+    let replica_s3_manager = s3::Manager::new(&replica_config);
+    let manifest = match rt
+        .block_on(replica_s3_manager.get_bytes(
+            &replica_s3_bucket,
+            &network_snapshot::manifest_key(&failover::dir(&primary.id)),
+            None,
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?
+    {
+        Some(d) => failover::Manifest::decode_yaml(&d)?,
+        None => {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "no node keys replicated to 's3://{}' (run '{} --prepare' while the primary region is healthy)",
+                    replica_s3_bucket, NAME
+                ),
+            ));
+        }
+    };
+
+    // restores the latest backup replicated since "--prepare"
+    match rt.block_on(replicated_backup_key(
+        &replica_s3_manager,
+        primary,
+        &replica_s3_bucket,
+    ))? {
+        Some(key) => standby_resources.db_backup_s3_key = Some(key),
+        None => {
+            warn!("no database backup replicated, the stand-by nodes bootstrap from the network");
+            standby_resources.db_backup_s3_region = None;
+            standby_resources.db_backup_s3_bucket = None;
+            standby_resources.db_backup_s3_key = None;
+        }
+    }
+    standby.aws_resources = Some(standby_resources.clone());
+    standby.sync(standby_spec_file_path)?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Red),
+        Print(format!(
+            "\nPromoting '{}' in '{}' with the node keys of {} slots replicated at {}, and the database backup {:?}\nStop the primary nodes if still running, the same node IDs must not run twice!\n",
+            standby.id,
+            to_region,
+            manifest.slots.len(),
+            manifest.created_at,
+            standby_resources.db_backup_s3_key
+        )),
+        ResetColor
+    )?;
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to fail over!",
+            "Yes, let's fail over to the stand-by region!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'failover' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    // "apply" locks the stand-by cluster, and the primary is not locked
+    // since its region may be down (the outage does not wait for the maintenance window)
+    deployer::apply::run(
+        standby_spec_file_path,
+        &deployer::apply::AutoApprove {
+            allow_destructive_changes: true,
+            force: true,
+        },
+    )?;
+
+    let standby = avalanche_ops::Spec::load(standby_spec_file_path)?;
+    let nlb_dns_name = standby
+        .aws_resources
+        .clone()
+        .unwrap()
+        .cloudformation_asg_nlb_dns_name;
+    if let Some(zone_id) = route53_hosted_zone_id {
+        let nlb_dns_name = nlb_dns_name.ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                "no NLB in the stand-by cluster to point the Route53 records to",
+            )
+        })?;
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print("\n\n\nSTEP: point Route53 records to the stand-by NLB\n"),
+            ResetColor
+        )?;
+        for name in record_names.iter() {
+            route53::upsert_cname(zone_id, name, &nlb_dns_name, route53::DEFAULT_TTL_SECONDS)?;
+            println!("{} -> {}", name, nlb_dns_name);
+        }
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nFailed over '{}' to '{}' (spec '{}')\n",
+            standby.id, to_region, standby_spec_file_path
+        )),
+        ResetColor
+    )?;
+    Ok(())
+}
+
+/// Returns the latest database backup in the replica bucket: the periodic
+/// "avalanched backup upload", or the bootstrap backup that "apply" replicates.
+async fn replicated_backup_key(
+    replica_s3_manager: &s3::Manager,
+    primary: &avalanche_ops::Spec,
+    replica_s3_bucket: &str,
+) -> io::Result<Option<String>> {
+    let mut keys = vec![failover::latest_backup_key(&primary.id)];
+    if let Some(key) = primary
+        .aws_resources
+        .clone()
+        .unwrap()
+        .db_backup_s3_key
+        .clone()
+    {
+        keys.push(key);
+    }
+    for key in keys {
+        let found = replica_s3_manager
+            .head_object_metadata(replica_s3_bucket, &key)
+            .await
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?
+            .is_some();
+        if found {
+            info!(
+                "found replicated backup 's3://{}/{}'",
+                replica_s3_bucket, key
+            );
+            return Ok(Some(key));
+        }
+    }
+    Ok(None)
+}
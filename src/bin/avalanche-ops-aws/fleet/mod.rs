@@ -0,0 +1,213 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use clap::{Arg, Command};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::heartbeat,
+    aws::{self, s3},
+    utils::{humanize, random},
+};
+
+pub const NAME: &str = "fleet";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Renders the node inventory from the signed heartbeats in S3 (no SSH/RPC access required)")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("STALE_SECONDS")
+                .long("stale-seconds")
+                .help("Sets the heartbeat age in seconds to mark the node as stale")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("900"),
+        )
+        .arg(
+            Arg::new("OUTPUT")
+                .long("output")
+                .short('o')
+                .help("Sets the output format")
+                .required(false)
+                .takes_value(true)
+                .possible_value("table")
+                .possible_value("json")
+                .allow_invalid_utf8(false)
+                .default_value("table"),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    stale_seconds: u64,
+    output: &str,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+    let s3_bucket = Arc::new(aws_resources.s3_bucket.clone());
+
+    let mut heartbeats = fetch_heartbeats(&rt, &s3_manager, s3_bucket, &spec.id)?;
+    heartbeats.sort_by(|a, b| (&a.kind, &a.node_id).cmp(&(&b.kind, &b.node_id)));
+
+    if output == "json" {
+        let d = serde_json::to_string_pretty(&heartbeats)
+            .map_err(|e| Error::new(ErrorKind::Other, e.to_string()))?;
+        println!("{}", d);
+        return Ok(());
+    }
+
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    println!(
+        "{:<42} {:<20} {:<11} {:<16} {:<20} {:>22} {:>9} {:>12} {:<12} {:<8}",
+        "NODE ID",
+        "MACHINE ID",
+        "KIND",
+        "PUBLIC IP",
+        "AVALANCHEGO",
+        "DISK AVAIL/TOTAL",
+        "UPTIME",
+        "LAST BACKUP",
+        "CONFIG",
+        "STATUS"
+    );
+    let mut config_hashes: Vec<&str> = Vec::new();
+    for hb in heartbeats.iter() {
+        if !config_hashes.contains(&hb.config_sha256.as_str()) {
+            config_hashes.push(&hb.config_sha256);
+        }
+        let age = now.saturating_sub(hb.timestamp);
+        println!(
+            "{:<42} {:<20} {:<11} {:<16} {:<20} {:>22} {:>8}h {:>12} {:<12} {:<8}",
+            hb.node_id,
+            hb.machine_id,
+            hb.kind,
+            hb.public_ip,
+            hb.avalanchego_version
+                .split_whitespace()
+                .next()
+                .unwrap_or(""),
+            format!(
+                "{}/{}",
+                humanize::bytes(hb.disk_available_bytes as f64),
+                humanize::bytes(hb.disk_total_bytes as f64)
+            ),
+            hb.uptime_seconds / 3600,
+            match hb.last_backup {
+                Some(t) => format!("{}h ago", now.saturating_sub(t) / 3600),
+                None => String::from("never"),
+            },
+            hb.config_sha256.chars().take(12).collect::<String>(),
+            if age > stale_seconds {
+                format!("stale ({}s)", age)
+            } else {
+                String::from("ok")
+            },
+        );
+    }
+    println!();
+
+    let expected = spec.machine.anchor_nodes.unwrap_or(0) + spec.machine.non_anchor_nodes;
+    if (heartbeats.len() as u32) < expected {
+        warn!(
+            "{} heartbeats found (expected {} nodes in spec)",
+            heartbeats.len(),
+            expected
+        );
+    }
+    if config_hashes.len() > 1 {
+        warn!(
+            "{} distinct avalanchego config hashes found in the fleet",
+            config_hashes.len()
+        );
+    }
+    Ok(())
+}
+
+/// Downloads the heartbeats of the spec, skipping the ones that fail the verification.
+pub fn fetch_heartbeats(
+    rt: &Runtime,
+    s3_manager: &s3::Manager,
+    s3_bucket: Arc<String>,
+    id: &str,
+) -> io::Result<Vec<heartbeat::Heartbeat>> {
+    let objects = rt
+        .block_on(s3_manager.list_objects(
+            s3_bucket.clone(),
+            Some(Arc::new(s3::append_slash(
+                &avalanche_ops::StorageNamespace::HeartbeatsDir(id.to_string()).encode(),
+            ))),
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed list_objects {}", e)))?;
+    info!("found {} heartbeats", objects.len());
+
+    let mut heartbeats: Vec<heartbeat::Heartbeat> = Vec::new();
+    for obj in objects.iter() {
+        let s3_key = obj.key().unwrap();
+        let tmp_path = random::tmp_path(10, Some(".json"))?;
+        rt.block_on(s3_manager.get_object(
+            s3_bucket.clone(),
+            Arc::new(s3_key.to_string()),
+            Arc::new(tmp_path.clone()),
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed get_object {}", e)))?;
+        let d = fs::read(&tmp_path)?;
+        fs::remove_file(&tmp_path)?;
+
+        // never render the unverified documents, since anyone with
+        // the bucket write access could have uploaded them
+        let verified = serde_json::from_slice::<heartbeat::Signed>(&d)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+            .and_then(|signed| signed.verify());
+        match verified {
+            Ok(hb) => heartbeats.push(hb),
+            Err(e) => warn!("skipping invalid heartbeat {} ({})", s3_key, e),
+        }
+    }
+    Ok(heartbeats)
+}
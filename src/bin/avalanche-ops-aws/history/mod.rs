@@ -0,0 +1,122 @@
+use std::io::{self, Error, ErrorKind};
+
+use clap::{Arg, Command};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self, audit,
+    aws::{self, s3},
+};
+
+pub const NAME: &str = "history";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Lists the audit log of the operations against the cluster (latest last)")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("LIMIT")
+                .long("limit")
+                .help("Sets the number of the latest records to list (0 to list all)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("20"),
+        )
+        .arg(
+            Arg::new("JSON")
+                .long("json")
+                .help("Prints the records as JSON lines (e.g., for the compliance exports)")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(log_level: &str, spec_file_path: &str, limit: usize, json: bool) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+
+    let records = rt.block_on(audit::list(&s3_manager, &aws_resources.s3_bucket, &spec.id))?;
+    if records.is_empty() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("no audit records for '{}'", spec.id),
+        ));
+    }
+    let skip = if limit == 0 {
+        0
+    } else {
+        records.len().saturating_sub(limit)
+    };
+
+    if json {
+        for r in records.iter().skip(skip) {
+            let line = serde_json::to_string(r).map_err(|e| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("failed to serialize audit record {}", e),
+                )
+            })?;
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    println!(
+        "{:<21} {:<30} {:<16} {:<24} {:<8} {:<14}",
+        "STARTED AT", "OPERATION ID", "OPERATOR", "COMMAND", "RESULT", "INPUTS SHA256"
+    );
+    for r in records.iter().skip(skip) {
+        println!(
+            "{:<21} {:<30} {:<16} {:<24} {:<8} {:<14}",
+            r.started_at,
+            r.operation_id,
+            r.operator,
+            r.command,
+            r.outcome.map(|v| v.to_string()).unwrap_or_default(),
+            &r.inputs_sha256[..12.min(r.inputs_sha256.len())],
+        );
+        if let Some(caller_arn) = &r.caller_arn {
+            println!("    caller: {}", caller_arn);
+        }
+        if let Some(error) = &r.error {
+            println!("    error: {}", error);
+        }
+    }
+    Ok(())
+}
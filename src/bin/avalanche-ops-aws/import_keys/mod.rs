@@ -0,0 +1,235 @@
+use std::{
+    fs,
+    io::{self, stdout, Error, ErrorKind},
+    sync::Arc,
+};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, envelope, kms, s3},
+    key_escrow,
+};
+
+pub const NAME: &str = "import-keys";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Restores the key material from an 'export-keys' archive into an applied cluster, for the replaced instances to boot with")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file of the cluster to restore into")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("ARCHIVE_PATH")
+                .long("archive-path")
+                .short('a')
+                .help("The key escrow archive written by 'export-keys'")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    archive_path: &str,
+    skip_prompt: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec.aws_resources.clone().unwrap();
+    if aws_resources.kms_cmk_id.is_none() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "no KMS key in the spec (run 'apply' first)",
+        ));
+    }
+
+    let d = fs::read(archive_path)?;
+    if key_escrow::is_passphrase_protected(&d) {
+        crate::export_keys::prompt_passphrase(false)?;
+    }
+    let rt = Runtime::new().unwrap();
+    let archive = rt.block_on(key_escrow::unseal(&d))?;
+    info!(
+        "loaded {} entries of '{}' exported at {}",
+        archive.entries.len(),
+        archive.cluster_id,
+        archive.exported_at
+    );
+
+    if archive.network_id != spec.avalanchego_config.network_id {
+        warn!(
+            "archive network ID {} != cluster network ID {}, the subnet control keys may not apply",
+            archive.network_id, spec.avalanchego_config.network_id
+        );
+    }
+    // the replaced instances never match the instance IDs
+    let entries: Vec<&key_escrow::Entry> = archive
+        .entries
+        .iter()
+        .filter(|e| {
+            if e.kind.is_node_key() && e.name.starts_with("i-") {
+                warn!(
+                    "skipping {:?} '{}' named after the instance ID",
+                    e.kind, e.name
+                );
+                return false;
+            }
+            true
+        })
+        .collect();
+
+    // "avalanched" only restores the keys of the node slots
+    let has_node_keys = entries.iter().any(|e| e.kind.is_node_key());
+    if has_node_keys && spec.static_node_keys != Some(true) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "archive has node keys but 'static_node_keys' is not set (set and run 'apply' first)",
+        ));
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nImporting into '{}' (overwrites the existing keys of the same names):\n",
+            spec.id
+        )),
+        ResetColor
+    )?;
+    for e in entries.iter() {
+        println!("{:?} '{}'", e.kind, e.name);
+    }
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to import keys!",
+            "Yes, let's import keys!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'import-keys' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    let s3_manager = s3::Manager::new(&shared_config);
+    let envelope = envelope::Envelope::new(
+        Some(kms::Manager::new(&shared_config)),
+        aws_resources.kms_cmk_id.clone(),
+    );
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print("\n\n\nSTEP: uploading and verifying key material\n"),
+        ResetColor
+    )?;
+    let s3_bucket = Arc::new(aws_resources.s3_bucket.clone());
+    for e in entries.iter() {
+        let plaintext = hex::decode(&e.data).map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid hex of {:?} '{}' {}", e.kind, e.name, err),
+            )
+        })?;
+        let s3_key = e.kind.s3_key(&spec.id, &e.name);
+        let sealed = rt.block_on(e.kind.close(&envelope, &plaintext))?;
+        rt.block_on(s3_manager.put_bytes(sealed, s3_bucket.clone(), Arc::new(s3_key.clone())))
+            .map_err(|err| Error::new(ErrorKind::Other, err.message()))?;
+
+        // reads back the way "avalanched" does, before any instance depends on it
+        let uploaded = rt
+            .block_on(s3_manager.get_bytes(&s3_bucket, &s3_key, None))
+            .map_err(|err| Error::new(ErrorKind::Other, err.message()))?
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("'{}' not found", s3_key)))?;
+        if rt.block_on(e.kind.open(&envelope, &uploaded))? != plaintext {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("restored '{}' does not match the archive", s3_key),
+            ));
+        }
+        info!("restored {:?} '{}' to '{}'", e.kind, e.name, s3_key);
+    }
+
+    if has_node_keys {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print("\n\n\nSTEP: replace the instances to boot with the imported node keys\n"),
+            ResetColor
+        )?;
+        for asg in [
+            aws_resources
+                .cloudformation_asg_anchor_nodes_logical_id
+                .clone(),
+            aws_resources
+                .cloudformation_asg_non_anchor_nodes_logical_id
+                .clone(),
+        ]
+        .iter()
+        .flatten()
+        {
+            println!(
+                "aws autoscaling start-instance-refresh --region {} --auto-scaling-group-name {}",
+                aws_resources.region, asg
+            );
+        }
+    }
+
+    println!();
+    info!("'import-keys' all success!");
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use std::io::{self, Error, ErrorKind};
+
+use clap::{Arg, Command};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{self, avalanche::avalanchego::api::platform, network_params::NetworkParams};
+
+pub const NAME: &str = "list-delegations";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Lists the delegations to the cluster (or any primary network) validators, and the remaining delegation capacity")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("HTTP_RPC")
+                .long("http-rpc")
+                .help("Sets the P-chain API endpoint (defaults to the public API, or 'endpoints.http_rpc' in spec)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("NODE_IDS")
+                .long("node-ids")
+                .help("Sets the comma-separated validator node IDs (default to the cluster nodes)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("PRIVATE_KEY_PATH")
+                .long("private-key-path")
+                .help("Only lists the delegations with the rewards owned by the key")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    http_rpc: &str,
+    node_ids: &str,
+    private_key_path: Option<&str>,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let node_ids = resolve_node_ids(&spec, node_ids)?;
+    let http_rpc = crate::elastic_subnet::get_http_rpc(&spec, http_rpc)?;
+    let owner = match private_key_path {
+        Some(p) => Some(
+            crate::elastic_subnet::load_key(p)?.address("P", spec.avalanchego_config.network_id)?,
+        ),
+        None => None,
+    };
+
+    let rt = Runtime::new().unwrap();
+    let mut validators = rt.block_on(platform::get_delegated_validators(&http_rpc, &node_ids))?;
+    let limits = rt
+        .block_on(NetworkParams::fetch(
+            &http_rpc,
+            spec.avalanchego_config.network_id,
+        ))?
+        .delegation_limits();
+
+    println!(
+        "{:<42} {:>20} {:>8} {:>20} {:>20}",
+        "NODE ID", "WEIGHT", "FEE %", "DELEGATED", "CAPACITY"
+    );
+    for v in validators.iter() {
+        println!(
+            "{:<42} {:>20} {:>8.2} {:>20} {:>20}",
+            v.node_id,
+            v.weight,
+            v.delegation_fee_percent,
+            v.delegated_weight(),
+            limits.capacity(v)
+        );
+    }
+    for node_id in node_ids.iter() {
+        if !validators.iter().any(|v| &v.node_id == node_id) {
+            println!("{:<42} not a current validator", node_id);
+        }
+    }
+
+    if let Some(owner) = &owner {
+        for v in validators.iter_mut() {
+            v.delegations
+                .retain(|d| d.reward_owner_addresses.contains(owner));
+        }
+    }
+    let delegations: Vec<&platform::Delegation> = validators
+        .iter()
+        .flat_map(|v| v.delegations.iter())
+        .collect();
+    println!();
+    println!(
+        "{}",
+        serde_yaml::to_string(&delegations)
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to serialize {}", e)))?
+    );
+    Ok(())
+}
+
+/// Parses the comma-separated node IDs, or returns the cluster nodes if empty.
+pub fn resolve_node_ids(spec: &avalanche_ops::Spec, node_ids: &str) -> io::Result<Vec<String>> {
+    let parsed: Vec<String> = node_ids
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect();
+    if !parsed.is_empty() {
+        return Ok(parsed);
+    }
+    match &spec.current_nodes {
+        Some(nodes) if !nodes.is_empty() => Ok(nodes.iter().map(|n| n.node_id.clone()).collect()),
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "no '--node-ids' and no current nodes for '{}' (not applied yet?)",
+                spec.id
+            ),
+        )),
+    }
+}
@@ -1,11 +1,59 @@
-use clap::Command;
+use std::{
+    panic::{self, AssertUnwindSafe},
+    process,
+};
 
+use clap::{ArgMatches, Command};
+
+use avalanche_ops::{avalanche::platformvm::txs, aws};
+
+mod api_auth;
 mod apply;
+mod audit;
+mod bake_ami;
+mod build_vm;
 mod check_balances;
+mod check_db;
+mod check_node_drift;
+mod clone_node;
+mod collect_logs;
 mod default_spec;
+mod delegate;
 mod delete;
+mod diff_spec;
+mod elastic_subnet;
+mod encrypt_spec;
 mod events;
+mod export_join_bundle;
+mod export_keys;
+mod failover;
+mod fleet;
+mod history;
+mod import_keys;
+mod list_delegations;
+mod quorum;
+mod read_only;
 mod read_spec;
+mod reap;
+mod rebalance;
+mod redact_spec;
+mod renew_validators;
+mod restore_network;
+mod rewards;
+mod rpc_usage;
+mod share;
+mod snapshot_network;
+mod ssh;
+mod start_network;
+mod state;
+mod status;
+mod stop_network;
+mod subnet;
+mod sync_artifacts;
+mod teleporter;
+mod track_release;
+mod tx;
+mod unlock;
 
 const NAME: &str = "avalanche-ops-aws";
 
@@ -14,21 +62,91 @@ const NAME: &str = "avalanche-ops-aws";
 fn main() {
     let matches = Command::new(NAME)
         .about("Avalanche node operations on AWS")
+        .arg(read_only::arg())
         .subcommands(vec![
             default_spec::command(),
             read_spec::command(),
+            redact_spec::command(),
+            diff_spec::command(),
+            encrypt_spec::command(),
+            share::command(),
+            export_join_bundle::command(),
+            export_keys::command(),
+            import_keys::command(),
+            snapshot_network::command(),
+            restore_network::command(),
+            failover::command(),
+            stop_network::command(),
+            start_network::command(),
             check_balances::command(),
+            fleet::command(),
+            check_node_drift::command(),
+            clone_node::command(),
+            collect_logs::command(),
+            check_db::command(),
+            ssh::command(),
+            status::command(),
+            rewards::command(),
+            rpc_usage::command(),
             events::command(),
             apply::command(),
+            elastic_subnet::command(),
+            subnet::command(),
+            teleporter::command(),
+            tx::command(),
+            sync_artifacts::command(),
+            bake_ami::command(),
+            build_vm::command(),
+            track_release::command(),
+            rebalance::command(),
+            renew_validators::command(),
+            delegate::command(),
+            list_delegations::command(),
             delete::command(),
+            reap::command(),
+            unlock::command(),
+            state::command(),
+            history::command(),
         ])
         .get_matches();
 
+    // rejects the commands that may mutate the cluster, before any AWS API call
+    let read_only = read_only::enabled(&matches);
+    if read_only {
+        if let Err(e) = read_only::check(&subcommand_path(&matches)) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+
+    // tags the AWS sessions and the stacks with this invocation
+    let op = aws::operation::init(&subcommand_path(&matches));
+
+    // records the operation in the audit log of the cluster, including the failures
+    // (the read-only mode never writes to the cluster bucket)
+    let recorder = if read_only {
+        None
+    } else {
+        audit::Recorder::start(&matches, &op)
+    };
+
+    // attaches the tokens to the node API calls, if required by the cluster
+    api_auth::authorize(&matches);
+    let ret = panic::catch_unwind(AssertUnwindSafe(|| run(&matches)));
+    if let Some(recorder) = recorder {
+        recorder.finish(ret.as_ref().err().map(|e| e.as_ref()));
+    }
+    if let Err(e) = ret {
+        panic::resume_unwind(e);
+    }
+}
+
+fn run(matches: &ArgMatches) {
     match matches.subcommand() {
         Some((default_spec::NAME, sub_matches)) => {
             let keys_to_generate = sub_matches.value_of("KEYS_TO_GENERATE").unwrap_or("");
             let keys_to_generate = keys_to_generate.parse::<usize>().unwrap();
-            let opt = avalanche_ops::DefaultSpecOption {
+            let mut opt = avalanche_ops::DefaultSpecOption {
                 log_level: sub_matches
                     .value_of("LOG_LEVEL")
                     .unwrap_or("info")
@@ -41,6 +159,15 @@ fn main() {
 
                 region: sub_matches.value_of("REGION").unwrap().to_string(),
 
+                s3_backups_expiration_days: sub_matches
+                    .value_of("S3_BACKUPS_EXPIRATION_DAYS")
+                    .unwrap_or("")
+                    .to_string(),
+                s3_backups_glacier_transition_days: sub_matches
+                    .value_of("S3_BACKUPS_GLACIER_TRANSITION_DAYS")
+                    .unwrap_or("")
+                    .to_string(),
+
                 db_backup_s3_region: sub_matches
                     .value_of("DB_BACKUP_S3_REGION")
                     .unwrap_or("")
@@ -54,6 +181,47 @@ fn main() {
                     .unwrap_or("")
                     .to_string(),
 
+                replica_s3_region: sub_matches
+                    .value_of("REPLICA_S3_REGION")
+                    .unwrap_or("")
+                    .to_string(),
+                replica_s3_bucket: sub_matches
+                    .value_of("REPLICA_S3_BUCKET")
+                    .unwrap_or("")
+                    .to_string(),
+
+                tracked_subnet_ids: sub_matches
+                    .value_of("TRACKED_SUBNET_IDS")
+                    .unwrap_or("")
+                    .to_string(),
+                tracked_subnets_validator_only: sub_matches
+                    .is_present("TRACKED_SUBNETS_VALIDATOR_ONLY"),
+                tracked_subnets_allowed_nodes: sub_matches
+                    .value_of("TRACKED_SUBNETS_ALLOWED_NODES")
+                    .unwrap_or("")
+                    .to_string(),
+
+                external_bootstrap_ips: sub_matches
+                    .value_of("EXTERNAL_BOOTSTRAP_IPS")
+                    .unwrap_or("")
+                    .to_string(),
+                external_bootstrap_ids: sub_matches
+                    .value_of("EXTERNAL_BOOTSTRAP_IDS")
+                    .unwrap_or("")
+                    .to_string(),
+
+                chain_upgrade_files: sub_matches
+                    .value_of("CHAIN_UPGRADE_FILES")
+                    .unwrap_or("")
+                    .to_string(),
+
+                avalanchego_release_constraint: sub_matches
+                    .value_of("AVALANCHEGO_RELEASE_CONSTRAINT")
+                    .unwrap_or("")
+                    .to_string(),
+                avalanchego_release_auto_upgrade: sub_matches
+                    .is_present("AVALANCHEGO_RELEASE_AUTO_UPGRADE"),
+
                 nlb_acm_certificate_arn: sub_matches
                     .value_of("NLB_ACM_CERTIFICATE_ARN")
                     .unwrap_or("")
@@ -61,16 +229,20 @@ fn main() {
 
                 install_artifacts_avalanched_bin: sub_matches
                     .value_of("INSTALL_ARTIFACTS_AVALANCHED_BIN")
-                    .unwrap()
+                    .unwrap_or("")
                     .to_string(),
                 install_artifacts_avalanche_bin: sub_matches
                     .value_of("INSTALL_ARTIFACTS_AVALANCHE_BIN")
-                    .unwrap()
+                    .unwrap_or("")
                     .to_string(),
                 install_artifacts_plugins_dir: sub_matches
                     .value_of("INSTALL_ARTIFACTS_PLUGINS_DIR")
                     .unwrap_or("")
                     .to_string(),
+                install_artifacts_signing_public_key: sub_matches
+                    .value_of("INSTALL_ARTIFACTS_SIGNING_PUBLIC_KEY")
+                    .unwrap_or("")
+                    .to_string(),
 
                 avalanchego_log_level: sub_matches
                     .value_of("AVALANCHEGO_LOG_LEVEL")
@@ -118,8 +290,19 @@ fn main() {
                     .value_of("SPEC_FILE_PATH")
                     .unwrap_or("")
                     .to_string(),
+                id_seed: sub_matches.value_of("ID_SEED").unwrap_or("").to_string(),
             };
-            default_spec::execute(opt).expect("failed to execute 'default-spec'");
+            let profile = default_spec::apply_profile(sub_matches, &mut opt)
+                .expect("failed to apply profile");
+            let ci_preview =
+                default_spec::ci_preview(sub_matches).expect("failed to parse CI preview");
+            default_spec::execute(
+                opt,
+                profile,
+                ci_preview,
+                sub_matches.is_present("INTERACTIVE"),
+            )
+            .expect("failed to execute 'default-spec'");
         }
 
         Some((read_spec::NAME, sub_matches)) => {
@@ -134,6 +317,106 @@ fn main() {
             .expect("failed to execute 'read-spec'");
         }
 
+        Some((share::NAME, sub_matches)) => {
+            let expires_in_hours = sub_matches.value_of("EXPIRES_IN_HOURS").unwrap_or("24");
+            let expires_in_hours = expires_in_hours.parse::<u64>().unwrap();
+            share::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                expires_in_hours,
+                sub_matches.is_present("SPEC"),
+                sub_matches.is_present("GENESIS"),
+                sub_matches.is_present("INSTALL_ARTIFACTS"),
+                sub_matches.is_present("BACKUPS"),
+            )
+            .expect("failed to execute 'share'");
+        }
+
+        Some((export_join_bundle::NAME, sub_matches)) => {
+            export_join_bundle::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("OUTPUT_PATH").unwrap_or(""),
+            )
+            .expect("failed to execute 'export-join-bundle'");
+        }
+
+        Some((export_keys::NAME, sub_matches)) => {
+            export_keys::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("OUTPUT_PATH").unwrap_or(""),
+                sub_matches.value_of("KMS_KEY_ARN"),
+            )
+            .expect("failed to execute 'export-keys'");
+        }
+
+        Some((import_keys::NAME, sub_matches)) => {
+            import_keys::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("ARCHIVE_PATH").unwrap(),
+                sub_matches.is_present("SKIP_PROMPT"),
+            )
+            .expect("failed to execute 'import-keys'");
+        }
+
+        Some((snapshot_network::NAME, sub_matches)) => {
+            snapshot_network::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("KMS_KEY_ARN"),
+                sub_matches.is_present("SKIP_PROMPT"),
+            )
+            .expect("failed to execute 'snapshot-network'");
+        }
+
+        Some((restore_network::NAME, sub_matches)) => {
+            restore_network::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SNAPSHOT_URL").unwrap(),
+                sub_matches.value_of("SNAPSHOT_REGION").unwrap(),
+                sub_matches.value_of("REGION"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+            )
+            .expect("failed to execute 'restore-network'");
+        }
+
+        Some((failover::NAME, sub_matches)) => {
+            failover::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("TO_REGION").unwrap(),
+                sub_matches.value_of("STANDBY_SPEC_FILE_PATH").unwrap(),
+                sub_matches.is_present("PREPARE"),
+                sub_matches.value_of("KMS_KEY_ARN"),
+                sub_matches.value_of("ROUTE53_HOSTED_ZONE_ID"),
+                sub_matches.value_of("ROUTE53_RECORD_NAMES"),
+                sub_matches.is_present("SKIP_PROMPT"),
+            )
+            .expect("failed to execute 'failover'");
+        }
+
+        Some((stop_network::NAME, sub_matches)) => {
+            stop_network::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.is_present("SKIP_BACKUP"),
+                sub_matches.is_present("FORCE"),
+                sub_matches.is_present("SKIP_PROMPT"),
+            )
+            .expect("failed to execute 'stop-network'");
+        }
+
+        Some((start_network::NAME, sub_matches)) => {
+            start_network::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.is_present("SKIP_PROMPT"),
+            )
+            .expect("failed to execute 'start-network'");
+        }
+
         Some((check_balances::NAME, sub_matches)) => {
             check_balances::execute(
                 sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
@@ -154,9 +437,44 @@ fn main() {
                         .value_of("INSTALL_ARTIFACTS_PLUGINS_DIR")
                         .unwrap_or(""),
                     sub_sub_matches.is_present("SKIP_PROMPT"),
+                    sub_sub_matches.is_present("FORCE"),
+                    sub_sub_matches.is_present("SKIP_QUORUM_CHECK"),
+                    sub_sub_matches.is_present("SKIP_CANARY"),
                 )
                 .expect("failed to execute 'events update-artifacts'");
             }
+            Some((events::update_chain_upgrades::NAME, sub_sub_matches)) => {
+                events::update_chain_upgrades::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    parse_value(sub_sub_matches, "MIN_LEAD_SECONDS"),
+                    sub_sub_matches.is_present("SKIP_PROMPT"),
+                    sub_sub_matches.is_present("FORCE"),
+                    sub_sub_matches.is_present("SKIP_QUORUM_CHECK"),
+                    sub_sub_matches.is_present("SKIP_CANARY"),
+                )
+                .expect("failed to execute 'events update-chain-upgrades'");
+            }
+            Some((events::update_peer_allowlist::NAME, sub_sub_matches)) => {
+                events::update_peer_allowlist::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_sub_matches.is_present("SKIP_PROMPT"),
+                    sub_sub_matches.is_present("FORCE"),
+                    sub_sub_matches.is_present("SKIP_QUORUM_CHECK"),
+                    sub_sub_matches.is_present("SKIP_CANARY"),
+                )
+                .expect("failed to execute 'events update-peer-allowlist'");
+            }
+            None => {
+                events::timeline::execute(
+                    sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_matches.value_of("NODE").unwrap(),
+                    sub_matches.value_of("SINCE").unwrap_or("1d"),
+                )
+                .expect("failed to execute 'events'");
+            }
             _ => unreachable!("unknown sub-subcommand"),
         },
 
@@ -165,10 +483,527 @@ fn main() {
                 sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
                 sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
                 sub_matches.is_present("SKIP_PROMPT"),
+                sub_matches.is_present("YES"),
+                sub_matches.is_present("FORCE"),
+                sub_matches.is_present("SMOKE_TEST"),
+                parse_value(sub_matches, "SMOKE_TEST_WAIT_SECONDS"),
             )
             .expect("failed to execute 'apply'");
         }
 
+        Some((elastic_subnet::NAME, sub_matches)) => match sub_matches.subcommand() {
+            Some((elastic_subnet::transform::NAME, sub_sub_matches)) => {
+                let params = txs::TransformSubnetParams {
+                    subnet_id: sub_sub_matches.value_of("SUBNET_ID").unwrap().to_string(),
+                    asset_id: sub_sub_matches.value_of("ASSET_ID").unwrap().to_string(),
+                    initial_supply: parse_value(sub_sub_matches, "INITIAL_SUPPLY"),
+                    maximum_supply: parse_value(sub_sub_matches, "MAXIMUM_SUPPLY"),
+                    min_consumption_rate: parse_value(sub_sub_matches, "MIN_CONSUMPTION_RATE"),
+                    max_consumption_rate: parse_value(sub_sub_matches, "MAX_CONSUMPTION_RATE"),
+                    min_validator_stake: parse_value(sub_sub_matches, "MIN_VALIDATOR_STAKE"),
+                    max_validator_stake: parse_value(sub_sub_matches, "MAX_VALIDATOR_STAKE"),
+                    min_stake_duration: parse_value(sub_sub_matches, "MIN_STAKE_DURATION"),
+                    max_stake_duration: parse_value(sub_sub_matches, "MAX_STAKE_DURATION"),
+                    min_delegation_fee: parse_value(sub_sub_matches, "MIN_DELEGATION_FEE"),
+                    min_delegator_stake: parse_value(sub_sub_matches, "MIN_DELEGATOR_STAKE"),
+                    max_validator_weight_factor: parse_value(
+                        sub_sub_matches,
+                        "MAX_VALIDATOR_WEIGHT_FACTOR",
+                    ),
+                    uptime_requirement: parse_value(sub_sub_matches, "UPTIME_REQUIREMENT"),
+                };
+                elastic_subnet::transform::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("PRIVATE_KEY_PATH").unwrap(),
+                    sub_sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                    params,
+                    sub_sub_matches.is_present("SKIP_PROMPT"),
+                    &tx::MultisigOpts::from_matches(sub_sub_matches),
+                )
+                .expect("failed to execute 'elastic-subnet transform'");
+            }
+            Some((elastic_subnet::add_validator::NAME, sub_sub_matches)) => {
+                elastic_subnet::add_validator::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("PRIVATE_KEY_PATH").unwrap(),
+                    sub_sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                    sub_sub_matches.value_of("NODE_IDS").unwrap_or(""),
+                    parse_value(sub_sub_matches, "STAKE_AMOUNT"),
+                    parse_value(sub_sub_matches, "START_DELAY_SECONDS"),
+                    parse_optional_value(sub_sub_matches, "STAKE_DURATION"),
+                    parse_optional_value(sub_sub_matches, "DELEGATION_FEE"),
+                    sub_sub_matches.is_present("SKIP_PROMPT"),
+                    &tx::MultisigOpts::from_matches(sub_sub_matches),
+                )
+                .expect("failed to execute 'elastic-subnet add-validator'");
+            }
+            Some((elastic_subnet::add_delegator::NAME, sub_sub_matches)) => {
+                elastic_subnet::add_delegator::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("PRIVATE_KEY_PATH").unwrap(),
+                    sub_sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                    sub_sub_matches.value_of("NODE_ID").unwrap(),
+                    parse_value(sub_sub_matches, "STAKE_AMOUNT"),
+                    parse_value(sub_sub_matches, "START_DELAY_SECONDS"),
+                    parse_optional_value(sub_sub_matches, "STAKE_DURATION"),
+                    sub_sub_matches.is_present("SKIP_PROMPT"),
+                    &tx::MultisigOpts::from_matches(sub_sub_matches),
+                )
+                .expect("failed to execute 'elastic-subnet add-delegator'");
+            }
+            _ => unreachable!("unknown sub-subcommand"),
+        },
+
+        Some((subnet::NAME, sub_matches)) => match sub_matches.subcommand() {
+            Some((subnet::create::NAME, sub_sub_matches)) => {
+                subnet::create::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                    sub_sub_matches.value_of("PRIVATE_KEY_PATH").unwrap(),
+                    sub_sub_matches.value_of("CONTROL_KEYS").unwrap_or(""),
+                    parse_value(sub_sub_matches, "THRESHOLD"),
+                    sub_sub_matches.is_present("SKIP_PROMPT"),
+                    &tx::MultisigOpts::from_matches(sub_sub_matches),
+                )
+                .expect("failed to execute 'subnet create'");
+            }
+            Some((subnet::upload_control_key::NAME, sub_sub_matches)) => {
+                subnet::upload_control_key::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("SUBNET_ID").unwrap(),
+                    sub_sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                    sub_sub_matches.value_of("PRIVATE_KEY_PATH").unwrap(),
+                )
+                .expect("failed to execute 'subnet upload-control-key'");
+            }
+            Some((subnet::add_validator::NAME, sub_sub_matches)) => {
+                subnet::add_validator::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("SUBNET_ID").unwrap(),
+                    sub_sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                    sub_sub_matches.value_of("PRIVATE_KEY_PATH").unwrap_or(""),
+                    sub_sub_matches.value_of("NODE_IDS").unwrap_or(""),
+                    parse_value(sub_sub_matches, "WEIGHT"),
+                    parse_value(sub_sub_matches, "START_DELAY_SECONDS"),
+                    parse_optional_value(sub_sub_matches, "VALIDATE_DURATION"),
+                    sub_sub_matches.is_present("SKIP_PROMPT"),
+                    &tx::MultisigOpts::from_matches(sub_sub_matches),
+                )
+                .expect("failed to execute 'subnet add-validator'");
+            }
+            Some((subnet::remove_validator::NAME, sub_sub_matches)) => {
+                subnet::remove_validator::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("SUBNET_ID").unwrap(),
+                    sub_sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                    sub_sub_matches.value_of("PRIVATE_KEY_PATH").unwrap_or(""),
+                    sub_sub_matches.value_of("NODE_IDS").unwrap(),
+                    sub_sub_matches.is_present("SKIP_PROMPT"),
+                    &tx::MultisigOpts::from_matches(sub_sub_matches),
+                )
+                .expect("failed to execute 'subnet remove-validator'");
+            }
+            Some((subnet::list_validators::NAME, sub_sub_matches)) => {
+                subnet::list_validators::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("SUBNET_ID").unwrap(),
+                    sub_sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                )
+                .expect("failed to execute 'subnet list-validators'");
+            }
+            Some((subnet::update_fee_config::NAME, sub_sub_matches)) => {
+                subnet::update_fee_config::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                    sub_sub_matches.value_of("BLOCKCHAIN_ID").unwrap(),
+                    sub_sub_matches.value_of("PRIVATE_KEY_PATH").unwrap(),
+                    &avalanche_ops::avalanche::subnet_evm::genesis::FeeConfig {
+                        gas_limit: parse_optional_value(sub_sub_matches, "GAS_LIMIT"),
+                        target_block_rate: parse_optional_value(
+                            sub_sub_matches,
+                            "TARGET_BLOCK_RATE",
+                        ),
+                        min_base_fee: parse_optional_value(sub_sub_matches, "MIN_BASE_FEE"),
+                        target_gas: parse_optional_value(sub_sub_matches, "TARGET_GAS"),
+                        base_fee_change_denominator: parse_optional_value(
+                            sub_sub_matches,
+                            "BASE_FEE_CHANGE_DENOMINATOR",
+                        ),
+                        min_block_gas_cost: parse_optional_value(
+                            sub_sub_matches,
+                            "MIN_BLOCK_GAS_COST",
+                        ),
+                        max_block_gas_cost: parse_optional_value(
+                            sub_sub_matches,
+                            "MAX_BLOCK_GAS_COST",
+                        ),
+                        block_gas_cost_step: parse_optional_value(
+                            sub_sub_matches,
+                            "BLOCK_GAS_COST_STEP",
+                        ),
+                    },
+                    sub_sub_matches.is_present("SKIP_PROMPT"),
+                )
+                .expect("failed to execute 'subnet update-fee-config'");
+            }
+            _ => unreachable!("unknown sub-subcommand"),
+        },
+
+        Some((teleporter::NAME, sub_matches)) => match sub_matches.subcommand() {
+            Some((teleporter::deploy::NAME, sub_sub_matches)) => {
+                teleporter::deploy::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                    sub_sub_matches.value_of("PRIVATE_KEY_PATH").unwrap(),
+                    sub_sub_matches.value_of("BLOCKCHAIN_IDS").unwrap(),
+                    sub_sub_matches.value_of("DEPLOYMENT_DIR").unwrap(),
+                    sub_sub_matches.is_present("SKIP_PROMPT"),
+                )
+                .expect("failed to execute 'teleporter deploy'");
+            }
+            Some((teleporter::send::NAME, sub_sub_matches)) => {
+                teleporter::send::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                    sub_sub_matches.value_of("PRIVATE_KEY_PATH").unwrap(),
+                    sub_sub_matches.value_of("SOURCE_BLOCKCHAIN_ID").unwrap(),
+                    sub_sub_matches
+                        .value_of("DESTINATION_BLOCKCHAIN_ID")
+                        .unwrap(),
+                    sub_sub_matches.value_of("TELEPORTER_ADDRESS").unwrap(),
+                    sub_sub_matches.value_of("DESTINATION_ADDRESS"),
+                    parse_value(sub_sub_matches, "COUNT"),
+                    std::time::Duration::from_secs(parse_value(sub_sub_matches, "TIMEOUT_SECONDS")),
+                )
+                .expect("failed to execute 'teleporter send'");
+            }
+            _ => unreachable!("unknown sub-subcommand"),
+        },
+
+        Some((tx::NAME, sub_matches)) => match sub_matches.subcommand() {
+            Some((tx::sign::NAME, sub_sub_matches)) => {
+                tx::sign::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("TX_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("PRIVATE_KEY_PATH").unwrap(),
+                )
+                .expect("failed to execute 'tx sign'");
+            }
+            Some((tx::issue::NAME, sub_sub_matches)) => {
+                tx::issue::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("TX_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                )
+                .expect("failed to execute 'tx issue'");
+            }
+            _ => unreachable!("unknown sub-subcommand"),
+        },
+
+        Some((sync_artifacts::NAME, sub_matches)) => {
+            sync_artifacts::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("LOCAL_DIR").unwrap(),
+                sub_matches.is_present("DRY_RUN"),
+                sub_matches.is_present("NOTIFY_AVALANCHED"),
+                sub_matches.is_present("FORCE"),
+                sub_matches.is_present("SKIP_QUORUM_CHECK"),
+            )
+            .expect("failed to execute 'sync-artifacts'");
+        }
+
+        Some((track_release::NAME, sub_matches)) => {
+            track_release::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("ARCH").unwrap_or("amd64"),
+                sub_matches.is_present("SKIP_PROMPT"),
+                sub_matches.is_present("FORCE"),
+                sub_matches.is_present("SKIP_QUORUM_CHECK"),
+            )
+            .expect("failed to execute 'track-release'");
+        }
+
+        Some((rebalance::NAME, sub_matches)) => {
+            rebalance::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("PRIVATE_KEY_PATH").unwrap(),
+                sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                parse_optional_value(sub_matches, "ANCHOR_NODES"),
+                parse_optional_value(sub_matches, "DEFAULT_WEIGHT"),
+                sub_matches.value_of("STAKE_WEIGHTS").unwrap_or(""),
+                parse_value(sub_matches, "START_DELAY_SECONDS"),
+                parse_value(sub_matches, "STAKE_DURATION"),
+                parse_value(sub_matches, "DELEGATION_FEE"),
+                sub_matches.is_present("SKIP_PROMPT"),
+                sub_matches.is_present("FORCE"),
+                sub_matches.is_present("SKIP_QUORUM_CHECK"),
+                &tx::MultisigOpts::from_matches(sub_matches),
+            )
+            .expect("failed to execute 'rebalance'");
+        }
+
+        Some((renew_validators::NAME, sub_matches)) => {
+            renew_validators::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("PRIVATE_KEY_PATH").unwrap(),
+                sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                parse_value(sub_matches, "START_DELAY_SECONDS"),
+                parse_value(sub_matches, "STAKE_DURATION"),
+                parse_value(sub_matches, "DELEGATION_FEE"),
+                sub_matches.is_present("SKIP_PROMPT"),
+                &tx::MultisigOpts::from_matches(sub_matches),
+            )
+            .expect("failed to execute 'renew-validators'");
+        }
+
+        Some((delegate::NAME, sub_matches)) => {
+            delegate::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("PRIVATE_KEY_PATH").unwrap(),
+                sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                sub_matches.value_of("NODE_IDS").unwrap_or(""),
+                parse_value(sub_matches, "STAKE_AMOUNT"),
+                parse_optional_value(sub_matches, "STAKE_DURATION"),
+                parse_value(sub_matches, "START_DELAY_SECONDS"),
+                parse_optional_value(sub_matches, "MAX_DELEGATION_FEE_PERCENT"),
+                sub_matches.is_present("SKIP_PROMPT"),
+                &tx::MultisigOpts::from_matches(sub_matches),
+            )
+            .expect("failed to execute 'delegate'");
+        }
+
+        Some((list_delegations::NAME, sub_matches)) => {
+            list_delegations::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                sub_matches.value_of("NODE_IDS").unwrap_or(""),
+                sub_matches.value_of("PRIVATE_KEY_PATH"),
+            )
+            .expect("failed to execute 'list-delegations'");
+        }
+
+        Some((fleet::NAME, sub_matches)) => {
+            fleet::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                parse_value(sub_matches, "STALE_SECONDS"),
+                sub_matches.value_of("OUTPUT").unwrap_or("table"),
+            )
+            .expect("failed to execute 'fleet'");
+        }
+
+        Some((redact_spec::NAME, sub_matches)) => {
+            redact_spec::execute(
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("OUTPUT_FILE_PATH"),
+            )
+            .expect("failed to execute 'redact-spec'");
+        }
+
+        Some((diff_spec::NAME, sub_matches)) => {
+            diff_spec::execute(
+                sub_matches.value_of("OLD_SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("NEW_SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("FAIL_ON_IMPACT"),
+            )
+            .expect("failed to execute 'diff-spec'");
+        }
+
+        Some((encrypt_spec::NAME, sub_matches)) => {
+            encrypt_spec::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("KMS_KEY_ARN"),
+                sub_matches.is_present("DECRYPT"),
+            )
+            .expect("failed to execute 'encrypt-spec'");
+        }
+
+        Some((check_node_drift::NAME, sub_matches)) => {
+            check_node_drift::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.is_present("FAIL_ON_DRIFT"),
+            )
+            .expect("failed to execute 'check-node-drift'");
+        }
+
+        Some((state::NAME, sub_matches)) => match sub_matches.subcommand() {
+            Some((state::history::NAME, sub_sub_matches)) => {
+                state::history::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                )
+                .expect("failed to execute 'state history'");
+            }
+            Some((state::pull::NAME, sub_sub_matches)) => {
+                state::pull::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("REGION"),
+                    sub_sub_matches.value_of("S3_BUCKET"),
+                    sub_sub_matches.value_of("ID"),
+                )
+                .expect("failed to execute 'state pull'");
+            }
+            Some((state::rollback::NAME, sub_sub_matches)) => {
+                state::rollback::execute(
+                    sub_sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                    sub_sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                    sub_sub_matches.value_of("VERSION_ID").unwrap(),
+                    sub_sub_matches.is_present("SKIP_PROMPT"),
+                )
+                .expect("failed to execute 'state rollback'");
+            }
+            _ => unreachable!("unknown sub-subcommand"),
+        },
+
+        Some((rpc_usage::NAME, sub_matches)) => {
+            rpc_usage::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("SINCE").unwrap_or("1d"),
+                sub_matches
+                    .value_of("TOP")
+                    .unwrap_or("10")
+                    .parse::<usize>()
+                    .expect("invalid '--top'"),
+                sub_matches.is_present("JSON"),
+            )
+            .expect("failed to execute 'rpc-usage'");
+        }
+
+        Some((rewards::NAME, sub_matches)) => {
+            rewards::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("HTTP_RPC").unwrap_or(""),
+                sub_matches
+                    .value_of("UPTIME_THRESHOLD_PERCENT")
+                    .unwrap_or("80")
+                    .parse::<f64>()
+                    .expect("invalid '--uptime-threshold-percent'"),
+                sub_matches.is_present("FAIL_ON_RISK"),
+            )
+            .expect("failed to execute 'rewards'");
+        }
+
+        Some((status::NAME, sub_matches)) => {
+            status::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                parse_optional_value(sub_matches, "EXPIRY_WARN_DAYS"),
+                read_only::enabled(matches),
+            )
+            .expect("failed to execute 'status'");
+        }
+
+        Some((clone_node::NAME, sub_matches)) => {
+            let expires_in_hours = sub_matches.value_of("EXPIRES_IN_HOURS").unwrap_or("24");
+            let expires_in_hours = expires_in_hours.parse::<u64>().unwrap();
+            clone_node::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("NODE_ID").unwrap(),
+                expires_in_hours,
+                sub_matches.value_of("INSTANCE_TYPE"),
+                sub_matches.is_present("SKIP_PROMPT"),
+            )
+            .expect("failed to execute 'clone-node'");
+        }
+
+        Some((bake_ami::NAME, sub_matches)) => {
+            bake_ami::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("BASE_IMAGE_ID"),
+                sub_matches.value_of("INSTANCE_TYPE"),
+                sub_matches.is_present("SKIP_PROMPT"),
+            )
+            .expect("failed to execute 'bake-ami'");
+        }
+
+        Some((build_vm::NAME, sub_matches)) => {
+            build_vm::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                build_vm::Options {
+                    repo: sub_matches.value_of("REPO").unwrap().to_string(),
+                    git_ref: sub_matches.value_of("REF").unwrap().to_string(),
+                    vm_name: sub_matches.value_of("VM_NAME").map(String::from),
+                    vm_id: sub_matches.value_of("VM_ID").map(String::from),
+                    build_command: sub_matches.value_of("BUILD_COMMAND").unwrap().to_string(),
+                    go_version: sub_matches.value_of("GO_VERSION").unwrap().to_string(),
+                    instance_type: sub_matches.value_of("INSTANCE_TYPE").unwrap().to_string(),
+                    notify_avalanched: sub_matches.is_present("NOTIFY_AVALANCHED"),
+                    force: sub_matches.is_present("FORCE"),
+                    skip_quorum_check: sub_matches.is_present("SKIP_QUORUM_CHECK"),
+                    skip_prompt: sub_matches.is_present("SKIP_PROMPT"),
+                },
+            )
+            .expect("failed to execute 'build-vm'");
+        }
+
+        Some((collect_logs::NAME, sub_matches)) => {
+            collect_logs::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("NODES"),
+                sub_matches.value_of("SINCE").unwrap_or("2h"),
+                sub_matches.value_of("OUTPUT_FILE_PATH"),
+            )
+            .expect("failed to execute 'collect-logs'");
+        }
+
+        Some((check_db::NAME, sub_matches)) => {
+            check_db::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("NODE").unwrap(),
+                sub_matches.value_of("DB_DIR"),
+                sub_matches.is_present("STOP_NODE"),
+                sub_matches.is_present("SKIP_PROMPT"),
+            )
+            .expect("failed to execute 'check-db'");
+        }
+
+        Some((ssh::NAME, sub_matches)) => {
+            ssh::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.value_of("NODE").unwrap(),
+                sub_matches.value_of("VIA"),
+                sub_matches.is_present("RECORD"),
+            )
+            .expect("failed to execute 'ssh'");
+        }
+
+        Some((unlock::NAME, sub_matches)) => {
+            unlock::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                sub_matches.is_present("SKIP_PROMPT"),
+            )
+            .expect("failed to execute 'unlock'");
+        }
+
         Some((delete::NAME, sub_matches)) => {
             delete::execute(
                 sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
@@ -181,6 +1016,51 @@ fn main() {
             .expect("failed to execute 'delete'");
         }
 
+        Some((reap::NAME, sub_matches)) => {
+            reap::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("REGION").unwrap(),
+                sub_matches.value_of("CI_JOB_ID"),
+                sub_matches.is_present("DRY_RUN"),
+            )
+            .expect("failed to execute 'reap'");
+        }
+
+        Some((history::NAME, sub_matches)) => {
+            history::execute(
+                sub_matches.value_of("LOG_LEVEL").unwrap_or("info"),
+                sub_matches.value_of("SPEC_FILE_PATH").unwrap(),
+                parse_value(sub_matches, "LIMIT"),
+                sub_matches.is_present("JSON"),
+            )
+            .expect("failed to execute 'history'");
+        }
+
         _ => unreachable!("unknown subcommand"),
     }
 }
+
+fn parse_value<T: std::str::FromStr>(matches: &clap::ArgMatches, name: &str) -> T {
+    match matches.value_of(name).unwrap().parse::<T>() {
+        Ok(v) => v,
+        Err(_) => panic!("invalid value for '{}'", name),
+    }
+}
+
+fn parse_optional_value<T: std::str::FromStr>(matches: &clap::ArgMatches, name: &str) -> Option<T> {
+    matches.value_of(name).map(|s| {
+        s.parse::<T>()
+            .unwrap_or_else(|_| panic!("invalid value for '{}'", name))
+    })
+}
+
+/// Returns the space-separated (nested) subcommand names (e.g., "subnet create").
+fn subcommand_path(matches: &clap::ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut cur = matches;
+    while let Some((name, sub_matches)) = cur.subcommand() {
+        names.push(name);
+        cur = sub_matches;
+    }
+    names.join(" ")
+}
@@ -0,0 +1,65 @@
+use std::io::{self, Error, ErrorKind};
+
+use clap::Arg;
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{self, avalanche::avalanchego::api::platform};
+
+pub fn skip_quorum_check_arg() -> Arg<'static> {
+    Arg::new("SKIP_QUORUM_CHECK")
+        .long("skip-quorum-check")
+        .help("Skips checking the stake to be offline against 'max_offline_stake_percent' in the spec")
+        .required(false)
+        .takes_value(false)
+        .allow_invalid_utf8(false)
+}
+
+/// Refuses the operation that restarts all nodes in the spec at once,
+/// if the nodes hold more stake than the threshold (e.g., >20% of the
+/// custom network stake), so the network does not lose its liveness.
+pub fn check(spec: &avalanche_ops::Spec, op: &str, skip: bool) -> io::Result<()> {
+    let node_ids: Vec<String> = match &spec.current_nodes {
+        Some(nodes) => nodes.iter().map(|n| n.node_id.clone()).collect(),
+        None => return Ok(()),
+    };
+    if skip {
+        warn!("'{}' skipping quorum check", op);
+        return Ok(());
+    }
+    let threshold = spec
+        .max_offline_stake_percent
+        .unwrap_or(avalanche_ops::DEFAULT_MAX_OFFLINE_STAKE_PERCENT);
+
+    let http_rpc = crate::elastic_subnet::get_http_rpc(spec, "")?;
+    let rt = Runtime::new().unwrap();
+    let validators = rt
+        .block_on(platform::get_validators(
+            &http_rpc,
+            platform::PRIMARY_NETWORK_ID,
+        ))
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!(
+                    "failed to get validators for quorum check ({}), use '--skip-quorum-check' to override",
+                    e
+                ),
+            )
+        })?;
+    let offline = platform::stake_percent(&validators, &node_ids);
+    info!(
+        "'{}' would take {:.2}% of stake offline at once (threshold {}%)",
+        op, offline, threshold
+    );
+    if offline > threshold as f64 {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "'{}' would take {:.2}% of stake offline at once (>{}% 'max_offline_stake_percent'), use '--skip-quorum-check' to override",
+                op, offline, threshold
+            ),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use std::{
+    env,
+    io::{self, Error, ErrorKind},
+};
+
+use clap::{Arg, ArgMatches};
+
+/// Non-empty to run in the read-only mode without "--read-only"
+/// (e.g., set in the shells of the auditors and the on-call responders).
+pub const READ_ONLY_ENV: &str = "AVALANCHE_OPS_READ_ONLY";
+
+pub fn arg() -> Arg<'static> {
+    Arg::new("READ_ONLY")
+        .long("read-only")
+        .help("Only permits the commands that never mutate the cluster (status, logs, metrics, and drift)")
+        .required(false)
+        .takes_value(false)
+        .global(true)
+        .allow_invalid_utf8(false)
+}
+
+/// Returns the subcommand paths allowed in the read-only mode.
+/// "events" without the subcommands is the timeline of the node events.
+/// "collect-logs" is not allowed, since it runs the commands on the nodes via SSM.
+fn allowed() -> Vec<String> {
+    vec![
+        crate::status::NAME.to_string(),
+        crate::fleet::NAME.to_string(),
+        crate::check_node_drift::NAME.to_string(),
+        crate::diff_spec::NAME.to_string(),
+        crate::read_spec::NAME.to_string(),
+        crate::history::NAME.to_string(),
+        crate::events::NAME.to_string(),
+        crate::rpc_usage::NAME.to_string(),
+        format!("{} {}", crate::state::NAME, crate::state::history::NAME),
+    ]
+}
+
+/// Returns true if "--read-only" is set at any level of the (nested) subcommand,
+/// or "AVALANCHE_OPS_READ_ONLY" is non-empty.
+pub fn enabled(matches: &ArgMatches) -> bool {
+    if matches!(env::var(READ_ONLY_ENV), Ok(v) if !v.is_empty()) {
+        return true;
+    }
+    let mut cur = matches;
+    loop {
+        if cur.is_present("READ_ONLY") {
+            return true;
+        }
+        match cur.subcommand() {
+            Some((_, sub_matches)) => cur = sub_matches,
+            None => return false,
+        }
+    }
+}
+
+/// Fails the subcommand not allowed in the read-only mode.
+pub fn check(subcommand_path: &str) -> io::Result<()> {
+    let allowed = allowed();
+    if allowed.iter().any(|v| v == subcommand_path) {
+        return Ok(());
+    }
+    Err(Error::new(
+        ErrorKind::PermissionDenied,
+        format!(
+            "'{}' is not allowed in the read-only mode (allowed: {})",
+            subcommand_path,
+            allowed.join(", ")
+        ),
+    ))
+}
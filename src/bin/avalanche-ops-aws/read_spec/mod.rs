@@ -93,7 +93,10 @@ pub fn execute(
     if nlb_endpoint {
         let aws_resources = spec.aws_resources.expect("unexpected None aws_resources");
         let nlb_https_enabled = aws_resources.nlb_acm_certificate_arn.is_some();
-        let dns_name = aws_resources.cloudformation_asg_nlb_dns_name.unwrap();
+        // the CI preview network has no NLB
+        let dns_name = aws_resources
+            .cloudformation_asg_nlb_dns_name
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no NLB in the spec"))?;
         let (scheme_for_dns, port_for_dns) = {
             if nlb_https_enabled {
                 ("https", 443)
@@ -0,0 +1,311 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, stdout, Error, ErrorKind},
+    process::Command as ProcessCommand,
+    sync::Arc,
+    time::Duration,
+};
+
+use aws_sdk_cloudformation::model::StackStatus;
+use chrono::{DateTime, Utc};
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self, auto_delete,
+    aws::{self, cloudformation, s3},
+    utils::home_dir,
+    StackName, StorageNamespace,
+};
+
+/// Represents the cluster found from the stack tags.
+struct Cluster {
+    tagged: auto_delete::Tagged,
+    stack_names: Vec<String>,
+    created_at: Option<DateTime<Utc>>,
+    expires_at: Option<DateTime<Utc>>,
+    spec: Option<Vec<u8>>,
+}
+
+pub const NAME: &str = "reap";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Deletes the clusters past 'auto_delete_after' and the expired CI preview networks (or the ones of the CI job), found by the stack tags (e.g., run on a schedule)")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("REGION")
+                .long("region")
+                .short('r')
+                .help("Sets the AWS region to find the stacks in")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("CI_JOB_ID")
+                .long("ci-job-id")
+                .help("Deletes the networks of the CI job, even if not expired (default to all expired networks)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("DRY_RUN")
+                .long("dry-run")
+                .help("Lists the clusters to delete without deleting")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+// 50-minute
+const MAX_WAIT_SECONDS: u64 = 50 * 60;
+
+pub fn execute(
+    log_level: &str,
+    region: &str,
+    ci_job_id: Option<&str>,
+    dry_run: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(region.to_string())))
+        .expect("failed to aws::load_config");
+    let cloudformation_manager = cloudformation::Manager::new(&shared_config);
+    let s3_manager = s3::Manager::new(&shared_config);
+
+    let now = Utc::now();
+    let stacks = rt
+        .block_on(cloudformation_manager.list_stacks_with_tag(auto_delete::TAG_CLUSTER_ID))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    let mut clusters: BTreeMap<String, Cluster> = BTreeMap::new();
+    for stack in stacks.iter() {
+        let tagged = match auto_delete::Tagged::from_tags(&stack.tags) {
+            Some(v) => v,
+            None => continue,
+        };
+        let cluster = clusters
+            .entry(tagged.cluster_id.clone())
+            .or_insert_with(|| Cluster {
+                tagged,
+                stack_names: Vec::new(),
+                created_at: None,
+                expires_at: None,
+                spec: None,
+            });
+        cluster.stack_names.push(stack.name.clone());
+        // the first stack is created by the first "apply"
+        if let Some(created_at) = stack.created_at {
+            if cluster.created_at.map_or(true, |v| created_at < v) {
+                cluster.created_at = Some(created_at);
+            }
+        }
+    }
+
+    let mut to_reap = Vec::new();
+    for (cluster_id, cluster) in clusters.iter_mut() {
+        // "apply" uploads the spec before creating the stacks
+        cluster.spec = rt
+            .block_on(s3_manager.get_bytes(
+                &Arc::new(cluster.tagged.s3_bucket.clone()),
+                &StorageNamespace::ConfigFile(cluster_id.clone()).encode(),
+                None,
+            ))
+            .unwrap_or_else(|e| {
+                warn!(
+                    "failed to read the spec of '{}' ({})",
+                    cluster_id,
+                    e.message()
+                );
+                None
+            });
+
+        if ci_job_id.is_some() || cluster.tagged.expires_at.is_some() {
+            cluster.expires_at = cluster.tagged.expires_at;
+            if cluster.tagged.should_reap(ci_job_id, now) {
+                to_reap.push(cluster_id.clone());
+            }
+            continue;
+        }
+
+        let auto_delete_after = cluster
+            .spec
+            .as_ref()
+            .and_then(|d| serde_yaml::from_slice::<avalanche_ops::Spec>(d).ok())
+            .and_then(|spec| spec.auto_delete_after);
+        if let (Some(auto_delete_after), Some(created_at)) = (auto_delete_after, cluster.created_at)
+        {
+            match auto_delete::expires_at(created_at, &auto_delete_after) {
+                Ok(expires_at) => {
+                    cluster.expires_at = Some(expires_at);
+                    if now >= expires_at {
+                        to_reap.push(cluster_id.clone());
+                    }
+                }
+                Err(e) => warn!("skipping '{}' ({})", cluster_id, e),
+            }
+        }
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nFound {} clusters, {} to reap\n",
+            clusters.len(),
+            to_reap.len()
+        )),
+        ResetColor
+    )?;
+    for (cluster_id, cluster) in clusters.iter() {
+        println!(
+            "{} (job {}, expires at {}){}: {}",
+            cluster_id,
+            cluster.tagged.job_id.clone().unwrap_or_default(),
+            cluster
+                .expires_at
+                .map(|v| v.to_rfc3339())
+                .unwrap_or_else(|| String::from("never")),
+            if to_reap.contains(cluster_id) {
+                " [REAP]"
+            } else {
+                ""
+            },
+            cluster.stack_names.join(", ")
+        );
+    }
+    if dry_run || to_reap.is_empty() {
+        return Ok(());
+    }
+
+    let exec_path = std::env::current_exe()?;
+    let mut failed = Vec::new();
+    for cluster_id in to_reap.iter() {
+        let cluster = clusters.get(cluster_id).unwrap();
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Red),
+            Print(format!("\n\n\nSTEP: reaping '{}'\n", cluster_id)),
+            ResetColor
+        )?;
+
+        let ret = match &cluster.spec {
+            Some(d) => {
+                // deletes the same way as the "delete" command, including the S3 bucket,
+                // the KMS key, and the log group
+                let spec_file_path = home_dir::named(cluster_id, Some(".reap.yaml"));
+                fs::write(&spec_file_path, d)?;
+                let status = ProcessCommand::new(&exec_path)
+                    .args([
+                        "delete",
+                        "--log-level",
+                        log_level,
+                        "--spec-file-path",
+                        &spec_file_path,
+                        "--delete-cloudwatch-log-group",
+                        "--delete-s3-objects",
+                        "--delete-s3-bucket",
+                        "--skip-prompt",
+                    ])
+                    .status()?;
+                if status.success() {
+                    fs::remove_file(&spec_file_path)?;
+                    Ok(())
+                } else {
+                    Err(Error::new(
+                        ErrorKind::Other,
+                        format!("'delete' failed with {}", status),
+                    ))
+                }
+            }
+            None => {
+                warn!(
+                    "no spec found for '{}', only deleting the stacks",
+                    cluster_id
+                );
+                // held while deleting, so no other operation mutates the cluster
+                match rt.block_on(aws::lock::Guard::acquire(&shared_config, cluster_id)) {
+                    Ok(_lock) => delete_stacks(
+                        &rt,
+                        &cloudformation_manager,
+                        cluster_id,
+                        &cluster.stack_names,
+                    ),
+                    Err(e) => Err(Error::new(ErrorKind::Other, e.message())),
+                }
+            }
+        };
+        match ret {
+            Ok(_) => info!("reaped '{}'", cluster_id),
+            Err(e) => {
+                warn!("failed to reap '{}' ({})", cluster_id, e);
+                failed.push(cluster_id.clone());
+            }
+        }
+    }
+    if !failed.is_empty() {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("failed to reap {}", failed.join(", ")),
+        ));
+    }
+
+    info!("'reap' all success!");
+    Ok(())
+}
+
+/// Deletes the auto scaling group stacks first, which depend on the VPC and the role.
+fn delete_stacks(
+    rt: &Runtime,
+    cloudformation_manager: &cloudformation::Manager,
+    cluster_id: &str,
+    stack_names: &[String],
+) -> io::Result<()> {
+    let asg_stacks = [
+        StackName::AsgBeaconNodes(cluster_id.to_string()).encode(),
+        StackName::AsgNonBeaconNodes(cluster_id.to_string()).encode(),
+        StackName::AsgIcmRelayer(cluster_id.to_string()).encode(),
+    ];
+    let (first, rest): (Vec<&String>, Vec<&String>) =
+        stack_names.iter().partition(|n| asg_stacks.contains(n));
+    for stacks in [first, rest] {
+        for stack_name in stacks.iter() {
+            rt.block_on(cloudformation_manager.delete_stack(stack_name))
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+        }
+        for stack_name in stacks.iter() {
+            rt.block_on(cloudformation_manager.poll_stack(
+                stack_name,
+                StackStatus::DeleteComplete,
+                Duration::from_secs(MAX_WAIT_SECONDS),
+                Duration::from_secs(30),
+            ))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,569 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    io::{self, stdout, Error, ErrorKind},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use aws_sdk_cloudformation::model::{Parameter, StackStatus};
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::{
+        avalanchego::api::platform,
+        constants, ids, node,
+        platformvm::{txs, wallet},
+    },
+    aws::{self, cloudformation, ec2, s3},
+};
+
+pub const NAME: &str = "rebalance";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Changes the anchor node count and stake weights of the running custom network")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load and update")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("PRIVATE_KEY_PATH")
+                .long("private-key-path")
+                .help("Sets the key path to fund the stake, or 'prefunded:N' for the N-th prefunded key in the spec")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("HTTP_RPC")
+                .long("http-rpc")
+                .help("Sets the P-chain API endpoint (default to the spec endpoint)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("ANCHOR_NODES")
+                .long("anchor-nodes")
+                .help("Sets the new anchor node count (default to the current count)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("DEFAULT_WEIGHT")
+                .long("default-weight")
+                .help("Sets the target stake in nano-AVAX for the anchor nodes without a specific weight (default to the spec)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("STAKE_WEIGHTS")
+                .long("stake-weights")
+                .help("Sets comma-separated 'NODE_ID=WEIGHT' target stakes in nano-AVAX, merged into the spec")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("START_DELAY_SECONDS")
+                .long("start-delay-seconds")
+                .help("Sets the delay in seconds before the staking starts")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("60"),
+        )
+        .arg(
+            Arg::new("STAKE_DURATION")
+                .long("stake-duration")
+                .help("Sets the stake duration in seconds for the new validators")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("31536000"),
+        )
+        .arg(
+            Arg::new("DELEGATION_FEE")
+                .long("delegation-fee")
+                .help("Sets the delegation fee of the new validators in units of 1,000,000 (e.g., 20000 for 2%)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("20000"),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("FORCE")
+                .long("force")
+                .help("Removes the anchor nodes even outside of the maintenance window in the spec")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(crate::quorum::skip_quorum_check_arg())
+        .args(crate::tx::multisig_args())
+}
+
+/// Represents the stake transaction to move the node towards its target weight.
+enum Action {
+    AddValidator {
+        node_id: String,
+        weight: u64,
+    },
+    AddDelegator {
+        node_id: String,
+        weight: u64,
+        end: u64,
+    },
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    private_key_path: &str,
+    http_rpc: &str,
+    anchor_nodes: Option<u32>,
+    default_weight: Option<u64>,
+    stake_weights: &str,
+    start_delay_seconds: u64,
+    stake_duration: u64,
+    delegation_fee: u32,
+    skip_prompt: bool,
+    force: bool,
+    skip_quorum_check: bool,
+    multisig_opts: &crate::tx::MultisigOpts,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let mut spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    spec.validate()?;
+    if constants::NETWORK_ID_TO_PUBLIC_API_ENDPOINT
+        .contains_key(&spec.avalanchego_config.network_id)
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "'rebalance' is only supported for the custom network",
+        ));
+    }
+    let current_anchor_nodes = spec.machine.anchor_nodes.unwrap_or(0);
+    if current_anchor_nodes == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "no anchor node found in spec (run 'apply' first)",
+        ));
+    }
+    let target_anchor_nodes = anchor_nodes.unwrap_or(current_anchor_nodes);
+    if target_anchor_nodes == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "'--anchor-nodes' must be >0",
+        ));
+    }
+
+    let mut distribution = match (&spec.stake_distribution, default_weight) {
+        (Some(v), Some(w)) => avalanche_ops::StakeDistribution {
+            default_weight: w,
+            weights: v.weights.clone(),
+        },
+        (Some(v), None) => v.clone(),
+        (None, Some(w)) => avalanche_ops::StakeDistribution {
+            default_weight: w,
+            weights: BTreeMap::new(),
+        },
+        (None, None) => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "no 'stake_distribution' in spec, '--default-weight' is required",
+            ));
+        }
+    };
+    for (node_id, weight) in avalanche_ops::StakeDistribution::parse_weights(stake_weights)? {
+        ids::parse_node_id(&node_id)?;
+        distribution.weights.insert(node_id, weight);
+    }
+
+    let http_rpc = crate::elastic_subnet::get_http_rpc(&spec, http_rpc)?;
+    let rt = Runtime::new().unwrap();
+    let params = crate::tx::load_network_params(&rt, &spec, &http_rpc)?;
+    params.validate_stake_distribution(&distribution)?;
+    params.validate_validator(distribution.default_weight, stake_duration)?;
+    let validators = rt.block_on(platform::get_validators(
+        &http_rpc,
+        platform::PRIMARY_NETWORK_ID,
+    ))?;
+
+    // the auto scaling group picks the instances to terminate,
+    // so assume the worst case of losing the largest anchor stakes
+    if target_anchor_nodes < current_anchor_nodes {
+        spec.check_maintenance_window("rebalance", force)?;
+        let mut anchor_weights: Vec<(String, u64)> = validators
+            .iter()
+            .filter(|v| !v.pending && is_anchor(&spec, &v.node_id))
+            .map(|v| (v.node_id.clone(), v.weight))
+            .collect();
+        anchor_weights.sort_by_key(|(_, weight)| Reverse(*weight));
+        let removed: Vec<String> = anchor_weights
+            .iter()
+            .take((current_anchor_nodes - target_anchor_nodes) as usize)
+            .map(|(node_id, _)| node_id.clone())
+            .collect();
+        let offline = platform::stake_percent(&validators, &removed);
+        let threshold = spec
+            .max_offline_stake_percent
+            .unwrap_or(avalanche_ops::DEFAULT_MAX_OFFLINE_STAKE_PERCENT);
+        if offline > threshold as f64 && !skip_quorum_check {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                format!(
+                    "removing {} anchor nodes may take {:.2}% of stake offline (>{}% 'max_offline_stake_percent'), use '--skip-quorum-check' to override",
+                    removed.len(),
+                    offline,
+                    threshold
+                ),
+            ));
+        }
+        warn!(
+            "primary network validators cannot leave before their end time, removed anchor nodes (up to {:.2}% of stake) will be offline until then",
+            offline
+        );
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nRebalancing anchor nodes {} -> {} (default weight {}, {} specific weights) via {}\n",
+            current_anchor_nodes,
+            target_anchor_nodes,
+            distribution.default_weight,
+            distribution.weights.len(),
+            http_rpc
+        )),
+        ResetColor
+    )?;
+    if !skip_prompt {
+        let options = &["No, I am not ready to rebalance!", "Yes, let's rebalance!"];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'rebalance' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    let s3_manager = s3::Manager::new(&shared_config);
+    let s3_bucket = Arc::new(aws_resources.s3_bucket.clone());
+
+    if target_anchor_nodes != current_anchor_nodes {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print(format!(
+                "\n\n\nSTEP: update ASG for anchor nodes to {}\n",
+                target_anchor_nodes
+            )),
+            ResetColor
+        )?;
+        let cloudformation_manager = cloudformation::Manager::new(&shared_config);
+        let ec2_manager = ec2::Manager::new(&shared_config);
+        let stack_name = aws_resources
+            .cloudformation_asg_anchor_nodes
+            .clone()
+            .expect("unexpected None cloudformation_asg_anchor_nodes");
+        rt.block_on(cloudformation_manager.update_stack_parameters(
+            &stack_name,
+            None,
+            vec![Parameter::builder()
+                .parameter_key("AsgDesiredCapacity")
+                .parameter_value(format!("{}", target_anchor_nodes))
+                .build()],
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed update_stack {}", e)))?;
+        thread::sleep(Duration::from_secs(30));
+        rt.block_on(cloudformation_manager.poll_stack(
+            &stack_name,
+            StackStatus::UpdateComplete,
+            Duration::from_secs(300 + 60 * target_anchor_nodes as u64),
+            Duration::from_secs(30),
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed poll_stack {}", e)))?;
+
+        spec.machine.anchor_nodes = Some(target_anchor_nodes);
+        sync_spec(&rt, &s3_manager, s3_bucket.clone(), &spec, spec_file_path)?;
+
+        // wait for the new anchor nodes to post their node IDs to remote storage,
+        // ignoring the ones from the terminated instances
+        let asg_name = aws_resources
+            .cloudformation_asg_anchor_nodes_logical_id
+            .clone()
+            .expect("unexpected None cloudformation_asg_anchor_nodes_logical_id");
+        let anchor_nodes = loop {
+            thread::sleep(Duration::from_secs(30));
+            let droplets = rt
+                .block_on(ec2_manager.list_asg(&asg_name))
+                .map_err(|e| Error::new(ErrorKind::Other, format!("failed list_asg {}", e)))?;
+            let objects = rt
+                .block_on(
+                    s3_manager.list_objects(
+                        s3_bucket.clone(),
+                        Some(Arc::new(s3::append_slash(
+                            &avalanche_ops::StorageNamespace::DiscoverReadyAnchorNodesDir(
+                                spec.id.clone(),
+                            )
+                            .encode(),
+                        ))),
+                    ),
+                )
+                .expect("failed list_objects DiscoverReadyAnchorNodesDir");
+            let mut nodes: Vec<node::Node> = Vec::new();
+            for obj in objects.iter() {
+                let n = avalanche_ops::StorageNamespace::parse_node_from_path(obj.key().unwrap())?;
+                if droplets
+                    .iter()
+                    .any(|d| d.instance_id == n.machine_id && d.instance_state_name == "running")
+                {
+                    nodes.push(n);
+                }
+            }
+            info!(
+                "{} anchor nodes are ready (expecting {} nodes)",
+                nodes.len(),
+                target_anchor_nodes
+            );
+            if nodes.len() as u32 == target_anchor_nodes {
+                break nodes;
+            }
+        };
+
+        let mut current_nodes: Vec<node::Node> = spec
+            .current_nodes
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|n| n.kind != node::Kind::Anchor.as_str())
+            .collect();
+        current_nodes.extend(anchor_nodes);
+        spec.current_nodes = Some(current_nodes);
+    }
+
+    // drop the weights of the nodes no longer in the network
+    distribution
+        .weights
+        .retain(|node_id, _| is_anchor(&spec, node_id));
+    spec.stake_distribution = Some(distribution.clone());
+    sync_spec(&rt, &s3_manager, s3_bucket.clone(), &spec, spec_file_path)?;
+
+    let validators = rt.block_on(platform::get_validators(
+        &http_rpc,
+        platform::PRIMARY_NETWORK_ID,
+    ))?;
+    let start = wallet::now_unix() + start_delay_seconds;
+    let mut actions: Vec<Action> = Vec::new();
+    for n in spec.current_nodes.clone().unwrap_or_default().iter() {
+        if n.kind != node::Kind::Anchor.as_str() {
+            continue;
+        }
+        let target = distribution.target_weight(&n.node_id);
+        let v = match validators.iter().find(|v| v.node_id == n.node_id) {
+            Some(v) => v,
+            None => {
+                actions.push(Action::AddValidator {
+                    node_id: n.node_id.clone(),
+                    weight: target,
+                });
+                continue;
+            }
+        };
+        if v.weight < target {
+            actions.push(Action::AddDelegator {
+                node_id: n.node_id.clone(),
+                weight: target - v.weight,
+                end: v.end_time,
+            });
+        } else if v.weight > target {
+            warn!(
+                "{} stake {} exceeds target {}, which cannot be reduced until its end time {}",
+                n.node_id, v.weight, target, v.end_time
+            );
+        }
+    }
+    if actions.is_empty() {
+        info!("anchor node stakes already match the distribution");
+        println!();
+        info!("'rebalance' all success!");
+        return Ok(());
+    }
+    for action in actions.iter() {
+        if let Action::AddDelegator {
+            node_id,
+            weight,
+            end,
+        } = action
+        {
+            if *end > start {
+                params
+                    .validate_delegator(*weight, end - start)
+                    .map_err(|e| Error::new(e.kind(), format!("{} {}", node_id, e)))?;
+            }
+        }
+    }
+
+    let k = crate::elastic_subnet::load_funding_key(&spec, private_key_path)?;
+    let mut w = crate::tx::load_wallet(
+        &rt,
+        k,
+        spec.avalanchego_config.network_id,
+        &http_rpc,
+        multisig_opts,
+    )?;
+    w.fees = params.tx_fees.clone();
+    for action in actions.iter() {
+        let (node_id, issued) = match action {
+            Action::AddValidator { node_id, weight } => {
+                execute!(
+                    stdout(),
+                    SetForegroundColor(Color::Green),
+                    Print(format!(
+                        "\n\n\nSTEP: issue AddValidatorTx for {} (weight {})\n",
+                        node_id, weight
+                    )),
+                    ResetColor
+                )?;
+                let issued = rt.block_on(w.add_validator(
+                    txs::Validator {
+                        node_id: ids::parse_node_id(node_id)?,
+                        start,
+                        end: start + stake_duration,
+                        weight: *weight,
+                    },
+                    delegation_fee,
+                ))?;
+                (node_id, issued)
+            }
+            Action::AddDelegator {
+                node_id,
+                weight,
+                end,
+            } => {
+                if *end <= start {
+                    warn!(
+                        "{} validation ends before the delegation, skipping",
+                        node_id
+                    );
+                    continue;
+                }
+                execute!(
+                    stdout(),
+                    SetForegroundColor(Color::Green),
+                    Print(format!(
+                        "\n\n\nSTEP: issue AddDelegatorTx for {} (weight {})\n",
+                        node_id, weight
+                    )),
+                    ResetColor
+                )?;
+                let issued = rt.block_on(w.add_delegator(txs::Validator {
+                    node_id: ids::parse_node_id(node_id)?,
+                    start,
+                    end: *end,
+                    weight: *weight,
+                }))?;
+                (node_id, issued)
+            }
+        };
+        if let Some(tx_id) = crate::tx::handle_issued(
+            issued,
+            &spec.id,
+            &format!("rebalance-{}", node_id),
+            multisig_opts,
+        )? {
+            println!("staked for {} (tx {})", node_id, tx_id);
+        }
+    }
+
+    println!();
+    info!("'rebalance' all success!");
+    Ok(())
+}
+
+fn is_anchor(spec: &avalanche_ops::Spec, node_id: &str) -> bool {
+    spec.current_nodes
+        .as_ref()
+        .map(|nodes| {
+            nodes
+                .iter()
+                .any(|n| n.node_id == node_id && n.kind == node::Kind::Anchor.as_str())
+        })
+        .unwrap_or(false)
+}
+
+fn sync_spec(
+    rt: &Runtime,
+    s3_manager: &s3::Manager,
+    s3_bucket: Arc<String>,
+    spec: &avalanche_ops::Spec,
+    spec_file_path: &str,
+) -> io::Result<()> {
+    spec.sync(spec_file_path)?;
+    rt.block_on(s3_manager.put_bytes(
+        spec.encode_state_yaml()?.into_bytes(),
+        s3_bucket,
+        Arc::new(avalanche_ops::StorageNamespace::ConfigFile(spec.id.clone()).encode()),
+    ))
+    .expect("failed put_object ConfigFile");
+    Ok(())
+}
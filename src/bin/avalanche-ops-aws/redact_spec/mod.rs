@@ -0,0 +1,47 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+};
+
+use clap::{Arg, Command};
+
+pub const NAME: &str = "redact-spec";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Writes a shareable copy of the spec with the private keys and account identifiers redacted (e.g., for support tickets)")
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load (never modified)")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("OUTPUT_FILE_PATH")
+                .long("output-file-path")
+                .short('o')
+                .help("The file to write the redacted spec to (if empty, prints to stdout)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(spec_file_path: &str, output_file_path: Option<&str>) -> io::Result<()> {
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let redacted = spec.redact()?;
+    let d = redacted.encode_yaml()?;
+
+    match output_file_path {
+        Some(p) => {
+            let mut f = File::create(p)?;
+            f.write_all(d.as_bytes())?;
+            eprintln!("wrote the redacted spec to '{}'", p);
+        }
+        None => print!("{}", d),
+    }
+    Ok(())
+}
@@ -0,0 +1,269 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    sync::Arc,
+};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::{
+        avalanchego::api::platform,
+        ids,
+        platformvm::{txs, wallet},
+    },
+    aws::{self, s3},
+    validator_expiry,
+};
+
+pub const NAME: &str = "renew-validators";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Re-adds the cluster validators whose staking ended, with the same stake (e.g., on a schedule)")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load and update")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("PRIVATE_KEY_PATH")
+                .long("private-key-path")
+                .help("Sets the key path to fund the stake, or 'prefunded:N' for the N-th prefunded key in the spec")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("HTTP_RPC")
+                .long("http-rpc")
+                .help("Sets the P-chain API endpoint (defaults to the public API, or 'endpoints.http_rpc' in spec)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("START_DELAY_SECONDS")
+                .long("start-delay-seconds")
+                .help("Sets the delay in seconds before the staking starts")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("60"),
+        )
+        .arg(
+            Arg::new("STAKE_DURATION")
+                .long("stake-duration")
+                .help("Sets the stake duration in seconds for the renewed validators")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("31536000"),
+        )
+        .arg(
+            Arg::new("DELEGATION_FEE")
+                .long("delegation-fee")
+                .help("Sets the delegation fee of the renewed validators in units of 1,000,000 (e.g., 20000 for 2%)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("20000"),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .args(crate::tx::multisig_args())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    private_key_path: &str,
+    http_rpc: &str,
+    start_delay_seconds: u64,
+    stake_duration: u64,
+    delegation_fee: u32,
+    skip_prompt: bool,
+    multisig_opts: &crate::tx::MultisigOpts,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let mut spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let node_ids: Vec<String> = match &spec.current_nodes {
+        Some(nodes) => nodes.iter().map(|n| n.node_id.clone()).collect(),
+        None => {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("no current nodes for '{}' (not applied yet?)", spec.id),
+            ))
+        }
+    };
+    let http_rpc = crate::elastic_subnet::get_http_rpc(&spec, http_rpc)?;
+
+    let rt = Runtime::new().unwrap();
+    let validators = rt.block_on(platform::get_validators(
+        &http_rpc,
+        platform::PRIMARY_NETWORK_ID,
+    ))?;
+    let mut expiry = spec.validator_expiry.clone().unwrap_or_default();
+    expiry.track(&node_ids, &validators);
+
+    // the primary network validator cannot be re-added until its staking ends
+    let now = wallet::now_unix();
+    let ended: Vec<validator_expiry::Expiry> = expiry
+        .expiring(now, 0)
+        .into_iter()
+        .filter(|e| !validators.iter().any(|v| v.node_id == e.node_id))
+        .collect();
+    spec.validator_expiry = Some(expiry.clone());
+    if ended.is_empty() {
+        sync_spec(&rt, &spec, spec_file_path)?;
+        info!("no validator staking has ended");
+        println!();
+        info!("'renew-validators' all success!");
+        return Ok(());
+    }
+
+    let params = crate::tx::load_network_params(&rt, &spec, &http_rpc)?;
+    for e in ended.iter() {
+        params.validate_validator(e.weight, stake_duration)?;
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nRenewing {} validator(s) for {} seconds via {}\n",
+            ended.len(),
+            stake_duration,
+            http_rpc
+        )),
+        ResetColor
+    )?;
+    for e in ended.iter() {
+        println!("{} ({} nAVAX)", e.node_id, e.weight);
+    }
+    if !skip_prompt {
+        let options = &["No, I am not ready to renew!", "Yes, let's renew!"];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'renew-validators' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = match &spec.aws_resources {
+        Some(aws_resources) => {
+            let shared_config =
+                rt.block_on(aws::load_config(Some(aws_resources.region.clone())))?;
+            Some(
+                rt.block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+                    .map_err(|e| Error::new(ErrorKind::Other, e.message()))?,
+            )
+        }
+        None => None,
+    };
+
+    let k = crate::elastic_subnet::load_funding_key(&spec, private_key_path)?;
+    let mut w = crate::tx::load_wallet(
+        &rt,
+        k,
+        spec.avalanchego_config.network_id,
+        &http_rpc,
+        multisig_opts,
+    )?;
+    w.fees = params.tx_fees.clone();
+    let start = wallet::now_unix() + start_delay_seconds;
+    for e in ended.iter() {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print(format!(
+                "\n\n\nSTEP: issue AddValidatorTx for {} (weight {})\n",
+                e.node_id, e.weight
+            )),
+            ResetColor
+        )?;
+        let issued = rt.block_on(w.add_validator(
+            txs::Validator {
+                node_id: ids::parse_node_id(&e.node_id)?,
+                start,
+                end: start + stake_duration,
+                weight: e.weight,
+            },
+            delegation_fee,
+        ))?;
+        if let Some(tx_id) = crate::tx::handle_issued(
+            issued,
+            &spec.id,
+            &format!("renew-validators-{}", e.node_id),
+            multisig_opts,
+        )? {
+            println!("renewed {} (tx {})", e.node_id, tx_id);
+            expiry.validators.insert(
+                e.node_id.clone(),
+                validator_expiry::Staking {
+                    end_time: start + stake_duration,
+                    weight: e.weight,
+                },
+            );
+            spec.validator_expiry = Some(expiry.clone());
+            sync_spec(&rt, &spec, spec_file_path)?;
+        }
+    }
+
+    println!();
+    info!("'renew-validators' all success!");
+    Ok(())
+}
+
+fn sync_spec(rt: &Runtime, spec: &avalanche_ops::Spec, spec_file_path: &str) -> io::Result<()> {
+    spec.sync(spec_file_path)?;
+    if let Some(aws_resources) = &spec.aws_resources {
+        let shared_config = rt.block_on(aws::load_config(Some(aws_resources.region.clone())))?;
+        let s3_manager = s3::Manager::new(&shared_config);
+        rt.block_on(s3_manager.put_bytes(
+            spec.encode_state_yaml()?.into_bytes(),
+            Arc::new(aws_resources.s3_bucket.clone()),
+            Arc::new(avalanche_ops::StorageNamespace::ConfigFile(spec.id.clone()).encode()),
+        ))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    sync::Arc,
+};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, s3},
+    network_snapshot,
+    utils::id,
+};
+
+pub const NAME: &str = "restore-network";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Writes the spec of a new cluster that restores the node keys, the genesis, and the databases of a 'snapshot-network' snapshot")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SNAPSHOT_URL")
+                .long("snapshot-url")
+                .help("Sets the snapshot URL printed by 'snapshot-network' (e.g., s3://bucket/[ID]/snapshots/[SNAPSHOT_ID])")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SNAPSHOT_REGION")
+                .long("snapshot-region")
+                .help("Sets the region of the snapshot bucket")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("REGION")
+                .long("region")
+                .short('r')
+                .help("Sets the region of the restored cluster (default to the snapshot region)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to write for the restored cluster")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    snapshot_url: &str,
+    snapshot_region: &str,
+    region: Option<&str>,
+    spec_file_path: &str,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let (s3_bucket, s3_prefix) = network_snapshot::parse_s3_url(snapshot_url)?;
+    let region = region.unwrap_or(snapshot_region);
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(snapshot_region.to_string())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+    let s3_bucket = Arc::new(s3_bucket);
+
+    let get = |s3_key: &str| -> io::Result<Vec<u8>> {
+        rt.block_on(s3_manager.get_bytes(&s3_bucket, s3_key, None))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("'{}' not found (incomplete snapshot?)", s3_key),
+                )
+            })
+    };
+    let manifest = network_snapshot::Manifest::decode_yaml(&get(
+        &network_snapshot::manifest_key(&s3_prefix),
+    )?)?;
+    let snapshot_spec: avalanche_ops::Spec =
+        serde_yaml::from_slice(&get(&network_snapshot::spec_key(&s3_prefix))?).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid snapshot spec {}", e),
+            )
+        })?;
+    info!(
+        "loaded snapshot '{}' of '{}' with {} nodes",
+        manifest.snapshot_id,
+        manifest.cluster_id,
+        manifest.nodes.len()
+    );
+
+    let source = network_snapshot::Source {
+        region: snapshot_region.to_string(),
+        s3_bucket: s3_bucket.to_string(),
+        s3_prefix: s3_prefix.clone(),
+        kms_key_arn: manifest.kms_key_arn.clone(),
+    };
+    let cluster_id = id::petname(4);
+    let spec = network_snapshot::restored_spec(&snapshot_spec, &cluster_id, region, source);
+    spec.validate()?;
+    spec.sync(spec_file_path)?;
+
+    // the staking periods of the snapshot keep counting down
+    warn!(
+        "validators of the snapshot taken at {} keep their original stake end times",
+        manifest.created_at
+    );
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nWrote the spec of '{}' restoring '{}' to '{}'\n",
+            cluster_id, snapshot_url, spec_file_path
+        )),
+        ResetColor
+    )?;
+    for n in manifest.nodes.iter() {
+        println!("{} ({}, {})", n.node_id, n.kind, n.slot);
+    }
+    println!();
+    println!(
+        "avalanche-ops-aws apply --spec-file-path {}",
+        spec_file_path
+    );
+    Ok(())
+}
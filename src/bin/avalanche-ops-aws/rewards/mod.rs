@@ -0,0 +1,178 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    sync::Arc,
+};
+
+use chrono::Utc;
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::warn;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::avalanchego::api::platform,
+    aws::{self, s3},
+    rewards,
+};
+
+pub const NAME: &str = "rewards";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Reports the uptime and the pending rewards of the cluster validators, and flags the validators trending below the reward uptime")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("HTTP_RPC")
+                .long("http-rpc")
+                .help("Sets the P-chain RPC endpoint (defaults to the public API, or 'endpoints.http_rpc' in spec)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("UPTIME_THRESHOLD_PERCENT")
+                .long("uptime-threshold-percent")
+                .help("Flags the validators with the (projected) uptime below this")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("80"),
+        )
+        .arg(
+            Arg::new("FAIL_ON_RISK")
+                .long("fail-on-risk")
+                .help("Exits with an error if any validator is at risk (e.g., for cron alerts)")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    http_rpc: &str,
+    uptime_threshold_percent: f64,
+    fail_on_risk: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path)?;
+    let node_ids: Vec<String> = match &spec.current_nodes {
+        Some(nodes) => nodes.iter().map(|n| n.node_id.clone()).collect(),
+        None => {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("no current nodes for '{}' (not applied yet?)", spec.id),
+            ))
+        }
+    };
+    let http_rpc = crate::elastic_subnet::get_http_rpc(&spec, http_rpc)?;
+
+    let rt = Runtime::new().unwrap();
+    let uptimes = rt.block_on(platform::get_validator_uptimes(&http_rpc, &node_ids))?;
+
+    // the uptime trend needs the samples of the previous runs
+    let now = Utc::now().timestamp() as u64;
+    let mut history = rewards::History::default();
+    let aws_resources = spec.aws_resources.clone();
+    let s3_manager = match &aws_resources {
+        Some(v) => {
+            let shared_config = rt.block_on(aws::load_config(Some(v.region.clone())))?;
+            Some(s3::Manager::new(&shared_config))
+        }
+        None => None,
+    };
+    let history_key =
+        avalanche_ops::StorageNamespace::RewardsUptimeHistory(spec.id.clone()).encode();
+    if let (Some(s3_manager), Some(aws_resources)) = (&s3_manager, &aws_resources) {
+        match rt.block_on(s3_manager.get_bytes(&aws_resources.s3_bucket, &history_key, None)) {
+            Ok(Some(b)) => history = rewards::History::parse(&b)?,
+            Ok(None) => {}
+            Err(e) => warn!("failed to load uptime history ({})", e.message()),
+        }
+    }
+    history.record(now, &uptimes);
+
+    let report = rewards::report(
+        &spec.id,
+        &node_ids,
+        &uptimes,
+        &history,
+        now,
+        uptime_threshold_percent,
+    );
+    println!("{}", report.encode_yaml()?);
+
+    if let (Some(s3_manager), Some(aws_resources)) = (&s3_manager, &aws_resources) {
+        if let Err(e) = rt.block_on(s3_manager.put_bytes(
+            history.encode_json()?,
+            Arc::new(aws_resources.s3_bucket.clone()),
+            Arc::new(history_key),
+        )) {
+            warn!("failed to save uptime history ({})", e.message());
+        }
+    }
+
+    for node_id in report.not_validating.iter() {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Yellow),
+            Print(format!("NOT VALIDATING {}\n", node_id)),
+            ResetColor
+        )?;
+    }
+    let at_risk = report.at_risk();
+    for v in at_risk.iter() {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Red),
+            Print(format!(
+                "AT RISK {} uptime {:.2}% (projected {}) < {}%, {} nAVAX potential reward\n",
+                v.node_id,
+                v.uptime_percent,
+                v.projected_uptime_percent
+                    .map(|p| format!("{:.2}%", p))
+                    .unwrap_or_else(|| String::from("n/a")),
+                uptime_threshold_percent,
+                v.potential_reward
+            )),
+            ResetColor
+        )?;
+    }
+
+    if fail_on_risk && !at_risk.is_empty() {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("{} validator(s) at risk of losing rewards", at_risk.len()),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    sync::Arc,
+};
+
+use chrono::{Duration, Utc};
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::node,
+    aws::{self, cloudwatch, s3, sts},
+    rpc_usage, support_bundle,
+    utils::compress,
+};
+
+pub const NAME: &str = "rpc-usage";
+
+/// Max number of the "Served" log events to read.
+const MAX_LOG_EVENTS: usize = 100_000;
+
+/// Max number of the log streams per "FilterLogEvents" call.
+const MAX_LOG_STREAMS: usize = 100;
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Reports the public RPC usage over the window (top client IPs from the NLB access logs, top methods and error rates from the API node logs)")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SINCE")
+                .long("since")
+                .help("Sets the report window (e.g., '30m', '2h', '1d')")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("1d"),
+        )
+        .arg(
+            Arg::new("TOP")
+                .long("top")
+                .help("Sets the number of the top clients and methods to report")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("10"),
+        )
+        .arg(
+            Arg::new("JSON")
+                .long("json")
+                .help("Prints the report in JSON")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    since: &str,
+    top: usize,
+    json: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let since_minutes = support_bundle::parse_since(since)?;
+    let now = Utc::now();
+    let since = now - Duration::minutes(since_minutes as i64);
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+    let current_nodes = spec
+        .current_nodes
+        .clone()
+        .expect("unexpected None current_nodes in spec file (run 'apply' first)");
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+    let cw_manager = cloudwatch::Manager::new(&shared_config);
+
+    let mut connections = Vec::new();
+    if aws_resources.nlb_access_logs == Some(true) {
+        info!("STEP: reading the NLB access logs since {}", since);
+        let account_id = match &aws_resources.identity {
+            Some(identity) => identity.account_id.clone(),
+            None => {
+                let sts_manager = sts::Manager::new(&shared_config);
+                rt.block_on(sts_manager.get_identity())
+                    .map_err(|e| Error::new(ErrorKind::Other, e.message()))?
+                    .account_id
+            }
+        };
+        let s3_bucket = Arc::new(aws_resources.s3_bucket.clone());
+        for prefix in rpc_usage::nlb_access_logs_day_prefixes(
+            &spec.id,
+            &account_id,
+            &aws_resources.region,
+            since,
+            now,
+        ) {
+            let objects = rt
+                .block_on(s3_manager.list_objects(s3_bucket.clone(), Some(Arc::new(prefix))))
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+            for obj in objects.iter() {
+                let key = obj.key().unwrap_or_default();
+                // delivered every 5 minutes, after the connections end
+                if let Some(modified) = obj.last_modified() {
+                    if modified.secs() < since.timestamp() {
+                        continue;
+                    }
+                }
+                let b = match rt
+                    .block_on(s3_manager.get_bytes(&s3_bucket, key, None))
+                    .map_err(|e| Error::new(ErrorKind::Other, e.message()))?
+                {
+                    Some(v) => v,
+                    None => continue,
+                };
+                let d = compress::unpack(&b, compress::Decoder::Gzip)?;
+                connections.extend(
+                    String::from_utf8_lossy(&d)
+                        .lines()
+                        .filter_map(rpc_usage::parse_nlb_log_line),
+                );
+            }
+        }
+        info!("read {} NLB connections", connections.len());
+    } else {
+        warn!("'aws_resources.nlb_access_logs' is not enabled, skipping the client IPs");
+    }
+
+    // "avalanched" ships the C-chain logs to the "all-logs" streams
+    if spec.coreth_config.log_level.as_deref() != Some("debug") {
+        warn!("'coreth_config.log_level' is not 'debug', so the API nodes may not log the served calls");
+    }
+    let api_streams: Vec<String> = current_nodes
+        .iter()
+        .filter(|n| match node::Kind::Anchor.from_str(&n.kind) {
+            Ok(kind) => spec.is_api_node(&kind),
+            Err(_) => false,
+        })
+        .map(|n| format!("{}-{}-all-logs", n.machine_id, n.kind))
+        .collect();
+    info!(
+        "STEP: reading the served calls of {} API nodes since {}",
+        api_streams.len(),
+        since
+    );
+    let mut calls = Vec::new();
+    for streams in api_streams.chunks(MAX_LOG_STREAMS) {
+        match rt.block_on(cw_manager.filter_log_events(
+            &spec.id,
+            streams.to_vec(),
+            rpc_usage::SERVED_LOG_FILTER_PATTERN,
+            since,
+            MAX_LOG_EVENTS,
+        )) {
+            Ok(events) => calls.extend(
+                events
+                    .iter()
+                    .filter_map(|ev| rpc_usage::parse_served_line(&ev.message)),
+            ),
+            Err(e) => warn!("skipping the API node logs ({})", e.message()),
+        }
+    }
+    info!("read {} served calls", calls.len());
+
+    let report = rpc_usage::Report::new(since, now, &connections, &calls, top);
+    if json {
+        let s = serde_json::to_string_pretty(&report)
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to encode {}", e)))?;
+        println!("{}", s);
+        return Ok(());
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nRPC usage of '{}' ({} ~ {})\n",
+            spec.id, report.since, report.until
+        )),
+        ResetColor
+    )?;
+    println!(
+        "connections: {} ({} unique clients, TLS error rate {:.2}%)",
+        report.connections,
+        report.unique_clients,
+        report.tls_error_rate * 100.0
+    );
+    println!(
+        "calls: {} (error rate {:.2}%)",
+        report.calls,
+        report.rpc_error_rate * 100.0
+    );
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!("\nTop {} clients by connections\n", top)),
+        ResetColor
+    )?;
+    println!(
+        "{:<40} {:>12} {:>10} {:>14} {:>14}",
+        "CLIENT IP", "CONNECTIONS", "TLS ERRORS", "RECEIVED", "SENT"
+    );
+    for c in report.top_clients.iter() {
+        println!(
+            "{:<40} {:>12} {:>10} {:>14} {:>14}",
+            c.client_ip, c.connections, c.tls_errors, c.received_bytes, c.sent_bytes
+        );
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!("\nTop {} methods by calls\n", top)),
+        ResetColor
+    )?;
+    println!("{:<40} {:>12} {:>10}", "METHOD", "CALLS", "ERRORS");
+    for m in report.top_methods.iter() {
+        println!("{:<40} {:>12} {:>10}", m.method, m.calls, m.errors);
+    }
+    Ok(())
+}
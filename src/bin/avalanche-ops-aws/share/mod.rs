@@ -0,0 +1,237 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    sync::Arc,
+    time::Duration,
+};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, s3},
+};
+
+pub const NAME: &str = "share";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Generates time-limited presigned URLs to share artifacts without AWS credentials")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("EXPIRES_IN_HOURS")
+                .long("expires-in-hours")
+                .short('e')
+                .help("Sets the hours until the URLs expire (up to 168, 7 days)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("24"),
+        )
+        .arg(
+            Arg::new("SPEC")
+                .long("spec")
+                .help("Set to share the spec file (WARN: includes the generated private keys)")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("GENESIS")
+                .long("genesis")
+                .help("Set to share the genesis file (only for custom networks)")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("INSTALL_ARTIFACTS")
+                .long("install-artifacts")
+                .help("Set to share the install artifacts (avalanched, avalanchego, plugins)")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("BACKUPS")
+                .long("backups")
+                .help("Set to share the database backups")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+/// Max expiration for presigned URLs with SigV4.
+/// ref. https://docs.aws.amazon.com/AmazonS3/latest/userguide/ShareObjectPreSignedURL.html
+const MAX_EXPIRES_IN_HOURS: u64 = 7 * 24;
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    expires_in_hours: u64,
+    share_spec: bool,
+    share_genesis: bool,
+    share_install_artifacts: bool,
+    share_backups: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    if expires_in_hours == 0 || expires_in_hours > MAX_EXPIRES_IN_HOURS {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "'--expires-in-hours' must be in [1, {}] (got {})",
+                MAX_EXPIRES_IN_HOURS, expires_in_hours
+            ),
+        ));
+    }
+    let expires_in = Duration::from_secs(expires_in_hours * 3600);
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec.aws_resources.clone().unwrap();
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+
+    // share everything if nothing is selected
+    let share_all = !share_spec && !share_genesis && !share_install_artifacts && !share_backups;
+
+    // (name, region, bucket, key) to presign
+    let mut targets: Vec<(String, String, String, String)> = Vec::new();
+    if share_all || share_spec {
+        if spec.prefunded_keys_on_nodes == Some(true) {
+            warn!("sharing the spec file that includes the prefunded private keys");
+        }
+        targets.push((
+            String::from("spec"),
+            aws_resources.region.clone(),
+            aws_resources.s3_bucket.clone(),
+            avalanche_ops::StorageNamespace::ConfigFile(spec.id.clone()).encode(),
+        ));
+    }
+    if (share_all || share_genesis) && spec.avalanchego_config.is_custom_network() {
+        targets.push((
+            String::from("genesis"),
+            aws_resources.region.clone(),
+            aws_resources.s3_bucket.clone(),
+            avalanche_ops::StorageNamespace::GenesisFile(spec.id.clone()).encode(),
+        ));
+    }
+    if share_all || share_install_artifacts {
+        let objects = rt
+            .block_on(s3_manager.list_objects(
+                Arc::new(aws_resources.s3_bucket.clone()),
+                Some(Arc::new(s3::append_slash(
+                    &avalanche_ops::StorageNamespace::InstallDir(spec.id.clone()).encode(),
+                ))),
+            ))
+            .expect("failed list_objects");
+        for obj in objects.iter() {
+            targets.push((
+                String::from("install artifact"),
+                aws_resources.region.clone(),
+                aws_resources.s3_bucket.clone(),
+                obj.key().unwrap().to_string(),
+            ));
+        }
+    }
+    if share_all || share_backups {
+        let objects = rt
+            .block_on(s3_manager.list_objects(
+                Arc::new(aws_resources.s3_bucket.clone()),
+                Some(Arc::new(s3::append_slash(
+                    &avalanche_ops::StorageNamespace::BackupsDir(spec.id.clone()).encode(),
+                ))),
+            ))
+            .expect("failed list_objects");
+        for obj in objects.iter() {
+            targets.push((
+                String::from("backup"),
+                aws_resources.region.clone(),
+                aws_resources.s3_bucket.clone(),
+                obj.key().unwrap().to_string(),
+            ));
+        }
+        if aws_resources.db_backup_s3_bucket.is_some() {
+            targets.push((
+                String::from("database backup"),
+                aws_resources.db_backup_s3_region.clone().unwrap(),
+                aws_resources.db_backup_s3_bucket.clone().unwrap(),
+                aws_resources.db_backup_s3_key.clone().unwrap(),
+            ));
+        }
+    }
+    if targets.is_empty() {
+        warn!("nothing to share");
+        return Ok(());
+    }
+
+    for (name, region, s3_bucket, s3_key) in targets.iter() {
+        // presigned URLs must be signed against the bucket region
+        let url = if region.eq(&aws_resources.region) {
+            rt.block_on(s3_manager.presign_get(s3_bucket, s3_key, expires_in))
+        } else {
+            let cfg = rt
+                .block_on(aws::load_config(Some(region.clone())))
+                .expect("failed to aws::load_config");
+            rt.block_on(s3::Manager::new(&cfg).presign_get(s3_bucket, s3_key, expires_in))
+        }
+        .expect("failed presign_get");
+
+        println!();
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print(format!("# {} 's3://{}/{}'\n", name, s3_bucket, s3_key)),
+            ResetColor
+        )?;
+        println!("curl -o {} '{}'", file_name(s3_key), url);
+    }
+
+    println!();
+    info!(
+        "'share' all success! (URLs expire in {}-hour)",
+        expires_in_hours
+    );
+    Ok(())
+}
+
+/// returns "hello.zstd" from "a/b/c/hello.zstd"
+fn file_name(s3_key: &str) -> String {
+    match s3_key.rsplit('/').next() {
+        Some(v) => String::from(v),
+        None => String::from(s3_key),
+    }
+}
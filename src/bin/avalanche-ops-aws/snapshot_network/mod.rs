@@ -0,0 +1,433 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    sync::Arc,
+    time::Duration,
+};
+
+use aws_sdk_ssm::model::CommandInvocationStatus;
+use chrono::Utc;
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::node,
+    aws::{self, envelope, kms, s3, ssm},
+    key_escrow, network_snapshot, node_keys, StorageNamespace,
+};
+
+pub const NAME: &str = "snapshot-network";
+
+/// Time for the services to stop on each node.
+const PAUSE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Time for the database upload on each node.
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(3 * 60 * 60);
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Pauses all nodes of the custom network, and backs up their databases with the spec, the genesis, and the node keys")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("KMS_KEY_ARN")
+                .long("kms-key-arn")
+                .help("Sets the KMS key ARN to re-encrypt the node keys with (default to the cluster key, deleted with the cluster)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    kms_key_arn: Option<&str>,
+    skip_prompt: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec.aws_resources.clone().unwrap();
+    if !spec.avalanchego_config.is_custom_network() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "'snapshot-network' is only valid for the custom network (use 'db_backup_s3_key' for the public networks)",
+        ));
+    }
+    // the node slots map the snapshot nodes to the restored instances
+    if spec.static_node_keys != Some(true) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "'snapshot-network' requires 'static_node_keys'",
+        ));
+    }
+    let current_nodes = spec
+        .current_nodes
+        .clone()
+        .expect("unexpected None current_nodes in spec file (run 'apply' first)");
+    let cluster_kms_key_arn = aws_resources.kms_cmk_arn.clone().unwrap();
+    let kms_key_arn = kms_key_arn
+        .map(String::from)
+        .unwrap_or_else(|| cluster_kms_key_arn.clone());
+    if kms_key_arn == cluster_kms_key_arn {
+        warn!("node keys of the snapshot are encrypted with the cluster KMS key, which 'delete' schedules for deletion");
+    }
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+    let ssm_manager = ssm::Manager::new(&shared_config);
+
+    // finds the node slot of each instance
+    let slot_manager = node_keys::slot_manager(&shared_config);
+    let mut slot_ids = node_keys::slot_ids(
+        &spec.id,
+        &node::Kind::Anchor,
+        spec.machine.anchor_nodes.unwrap_or(0),
+    );
+    slot_ids.extend(node_keys::slot_ids(
+        &spec.id,
+        &node::Kind::NonAnchor,
+        spec.machine.non_anchor_nodes,
+    ));
+    let mut nodes: Vec<(node::Node, String)> = Vec::new();
+    for slot_id in slot_ids.iter() {
+        let lease = rt
+            .block_on(slot_manager.get(slot_id))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+        let lease = match lease {
+            Some(v) => v,
+            None => continue,
+        };
+        if let Some(n) = current_nodes
+            .iter()
+            .find(|n| n.machine_id == lease.instance_id)
+        {
+            nodes.push((n.clone(), node_keys::slot_name(slot_id)));
+        }
+    }
+    for n in current_nodes.iter() {
+        if !nodes.iter().any(|(m, _)| m.machine_id == n.machine_id) {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "node '{}' ({}) holds no node slot (run 'apply' to sync 'current_nodes')",
+                    n.node_id, n.machine_id
+                ),
+            ));
+        }
+    }
+
+    let snapshot_id = Utc::now().format("%Y%m%d%H%M%S").to_string();
+    let snapshot_dir = network_snapshot::dir(&spec.id, &snapshot_id);
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nSnapshot 's3://{}/{}' pauses all {} nodes until their databases are uploaded:\n",
+            aws_resources.s3_bucket,
+            snapshot_dir,
+            nodes.len()
+        )),
+        ResetColor
+    )?;
+    for (n, slot) in nodes.iter() {
+        println!("{} ({}, {})", n.node_id, n.machine_id, slot);
+    }
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to pause the network!",
+            "Yes, let's pause the network and take the snapshot!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'snapshot-network' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print("\n\n\nSTEP: pausing all nodes\n"),
+        ResetColor
+    )?;
+    let instance_ids: Vec<String> = nodes.iter().map(|(n, _)| n.machine_id.clone()).collect();
+    let ret = run_all(
+        &rt,
+        &ssm_manager,
+        &instance_ids,
+        &network_snapshot::pause_script(),
+        &format!("{} {} pause", NAME, snapshot_id),
+        PAUSE_TIMEOUT,
+    );
+
+    // all nodes are stopped at the same height, so the databases are consistent
+    let ret = ret.and_then(|_| {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print("\n\n\nSTEP: uploading databases\n"),
+            ResetColor
+        )?;
+        let mut commands = Vec::new();
+        for (n, slot) in nodes.iter() {
+            let script = network_snapshot::upload_db_script(
+                &spec.avalanchego_config.db_dir,
+                &format!(
+                    "s3://{}/{}",
+                    aws_resources.s3_bucket,
+                    network_snapshot::db_key(&snapshot_dir, slot)
+                ),
+            );
+            let command_id = rt
+                .block_on(ssm_manager.send_command(
+                    vec![n.machine_id.clone()],
+                    &script,
+                    &format!("{} {} upload", NAME, snapshot_id),
+                    UPLOAD_TIMEOUT,
+                ))
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+            commands.push((n, command_id));
+        }
+        poll_all(&rt, &ssm_manager, &commands, UPLOAD_TIMEOUT)
+    });
+
+    // resumes the nodes even if the snapshot failed
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print("\n\n\nSTEP: resuming all nodes\n"),
+        ResetColor
+    )?;
+    if let Err(e) = run_all(
+        &rt,
+        &ssm_manager,
+        &instance_ids,
+        &network_snapshot::resume_script(),
+        &format!("{} {} resume", NAME, snapshot_id),
+        PAUSE_TIMEOUT,
+    ) {
+        warn!(
+            "failed to resume some nodes, restart 'avalanched' manually ({})",
+            e
+        );
+    }
+    ret?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print("\n\n\nSTEP: copying spec, genesis, and node keys\n"),
+        ResetColor
+    )?;
+    let s3_bucket = Arc::new(aws_resources.s3_bucket.clone());
+    let copy = |src: &str, dst: &str| -> io::Result<Vec<u8>> {
+        let d = rt
+            .block_on(s3_manager.get_bytes(&s3_bucket, src, None))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("'{}' not found", src)))?;
+        rt.block_on(s3_manager.put_bytes(d.clone(), s3_bucket.clone(), Arc::new(dst.to_string())))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+        Ok(d)
+    };
+    copy(
+        &StorageNamespace::ConfigFile(spec.id.clone()).encode(),
+        &network_snapshot::spec_key(&snapshot_dir),
+    )?;
+    copy(
+        &StorageNamespace::GenesisFile(spec.id.clone()).encode(),
+        &network_snapshot::genesis_key(&snapshot_dir),
+    )?;
+
+    let cluster_envelope = envelope::Envelope::new(
+        Some(kms::Manager::new(&shared_config)),
+        Some(cluster_kms_key_arn.clone()),
+    );
+    let snapshot_envelope = envelope::Envelope::new(
+        Some(kms::Manager::new(&rt.block_on(aws::load_config(
+            kms_key_arn.split(':').nth(3).map(String::from),
+        ))?)),
+        Some(kms_key_arn.clone()),
+    );
+    for (_, slot) in nodes.iter() {
+        let cluster_keys = node_keys::S3Keys::new(&spec.id, slot);
+        let snapshot_keys = network_snapshot::node_keys(&snapshot_dir, slot);
+        copy(&cluster_keys.tls_cert, &snapshot_keys.tls_cert)?;
+        for (kind, src, dst) in [
+            (
+                key_escrow::Kind::TlsKey,
+                &cluster_keys.tls_key_encrypted,
+                &snapshot_keys.tls_key_encrypted,
+            ),
+            (
+                key_escrow::Kind::SignerKey,
+                &cluster_keys.signer_key_encrypted,
+                &snapshot_keys.signer_key_encrypted,
+            ),
+        ] {
+            let d = match rt
+                .block_on(s3_manager.get_bytes(&s3_bucket, src, None))
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))?
+            {
+                Some(v) => v,
+                // no BLS signer key configured
+                None => continue,
+            };
+            let d = if kms_key_arn == cluster_kms_key_arn {
+                d
+            } else {
+                let plaintext = rt.block_on(kind.open(&cluster_envelope, &d))?;
+                rt.block_on(kind.close(&snapshot_envelope, &plaintext))?
+            };
+            rt.block_on(s3_manager.put_bytes(d, s3_bucket.clone(), Arc::new(dst.clone())))
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+        }
+    }
+
+    // written last, so an incomplete snapshot has no manifest
+    let manifest = network_snapshot::Manifest {
+        snapshot_id: snapshot_id.clone(),
+        cluster_id: spec.id.clone(),
+        network_id: spec.avalanchego_config.network_id,
+        created_at: Utc::now(),
+        kms_key_arn,
+        nodes: nodes
+            .iter()
+            .map(|(n, slot)| network_snapshot::Node {
+                slot: slot.clone(),
+                node_id: n.node_id.clone(),
+                kind: n.kind.clone(),
+            })
+            .collect(),
+    };
+    rt.block_on(s3_manager.put_bytes(
+        manifest.encode_yaml()?.into_bytes(),
+        s3_bucket.clone(),
+        Arc::new(network_snapshot::manifest_key(&snapshot_dir)),
+    ))
+    .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\n\n\nSTEP: took snapshot 's3://{}/{}'\n",
+            aws_resources.s3_bucket, snapshot_dir
+        )),
+        ResetColor
+    )?;
+    println!(
+        "avalanche-ops-aws restore-network --snapshot-url s3://{}/{} --region {} --spec-file-path [NEW_SPEC_PATH]",
+        aws_resources.s3_bucket, snapshot_dir, aws_resources.region
+    );
+    info!("'snapshot-network' all success!");
+    Ok(())
+}
+
+/// Runs the script on all instances, and waits for all to succeed.
+pub fn run_all(
+    rt: &Runtime,
+    ssm_manager: &ssm::Manager,
+    instance_ids: &[String],
+    script: &str,
+    comment: &str,
+    timeout: Duration,
+) -> io::Result<()> {
+    let command_id = rt
+        .block_on(ssm_manager.send_command(instance_ids.to_vec(), script, comment, timeout))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    for instance_id in instance_ids.iter() {
+        let (status, stderr) = rt
+            .block_on(ssm_manager.poll_command(&command_id, instance_id, timeout))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+        if status != CommandInvocationStatus::Success {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "'{}' failed on '{}' ({:?}, {})",
+                    comment,
+                    instance_id,
+                    status,
+                    stderr.trim()
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Waits for the per-node commands to succeed.
+pub fn poll_all(
+    rt: &Runtime,
+    ssm_manager: &ssm::Manager,
+    commands: &[(&node::Node, String)],
+    timeout: Duration,
+) -> io::Result<()> {
+    for (n, command_id) in commands.iter() {
+        let (status, stderr) = rt
+            .block_on(ssm_manager.poll_command(command_id, &n.machine_id, timeout))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+        if status != CommandInvocationStatus::Success {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "database upload failed on '{}' ({:?}, {})",
+                    n.node_id,
+                    status,
+                    stderr.trim()
+                ),
+            ));
+        }
+        info!("uploaded database of '{}'", n.node_id);
+    }
+    Ok(())
+}
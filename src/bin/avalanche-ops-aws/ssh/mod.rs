@@ -0,0 +1,243 @@
+use std::{
+    fs,
+    io::{self, stdout, Error, ErrorKind},
+    path::Path,
+    process::Command as ProcessCommand,
+    sync::Arc,
+};
+
+use chrono::Utc;
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, ec2, operation, s3},
+    ssh_session,
+    utils::random,
+};
+
+pub const NAME: &str = "ssh";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Opens a shell on the node via the stored key or SSM, optionally recording the session in the audit log")
+        .arg(
+            Arg::new("NODE")
+                .help("Sets the node ID, instance ID, or IP address of the node")
+                .required(true)
+                .index(1)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("VIA")
+                .long("via")
+                .help("Sets the session method (default 'key' if the key file exists, otherwise 'ssm'), or 'instance-connect' for the ephemeral key")
+                .required(false)
+                .takes_value(true)
+                .possible_value("key")
+                .possible_value("ssm")
+                .possible_value("instance-connect")
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("RECORD")
+                .long("record")
+                .help("Records the session transcript to the audit prefix in the S3 bucket")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    node: &str,
+    via: Option<&str>,
+    record: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let ec2_manager = ec2::Manager::new(&shared_config);
+
+    // the node ID is only known from the spec, while the instances
+    // are looked up by the cluster tag, in case the spec is stale
+    let instance_id = spec
+        .current_nodes
+        .clone()
+        .unwrap_or_default()
+        .iter()
+        .find(|n| n.node_id == node)
+        .map(|n| n.machine_id.clone())
+        .unwrap_or_else(|| node.to_string());
+    let droplets = rt
+        .block_on(
+            ec2_manager.list_instances(&ec2::InstanceFilter::default().tag("ID", &spec.id).alive()),
+        )
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    let droplet = droplets
+        .iter()
+        .find(|d| {
+            d.instance_id == instance_id
+                || (!d.public_ipv4.is_empty() && d.public_ipv4 == node)
+                || (!d.private_ipv4.is_empty() && d.private_ipv4 == node)
+        })
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("no running instance of '{}' found for '{}'", spec.id, node),
+            )
+        })?;
+
+    let key_path = aws_resources
+        .ec2_key_path
+        .clone()
+        .filter(|p| Path::new(p).exists());
+    let via = via.unwrap_or(if key_path.is_some() { "key" } else { "ssm" });
+
+    // private connectivity has no public address
+    let host = if droplet.public_ipv4.is_empty() {
+        droplet.private_ipv4.clone()
+    } else {
+        droplet.public_ipv4.clone()
+    };
+    // removed with the ephemeral key once the session ends
+    let key_dir = tempfile::tempdir()?;
+    let method = match via {
+        "key" => {
+            let key_path = key_path.ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    "no local 'aws_resources.ec2_key_path' (use '--via ssm' or '--via instance-connect')",
+                )
+            })?;
+            ssh_session::Method::Key { key_path, host }
+        }
+        "instance-connect" => {
+            let (key_path, public_key) = ssh_session::generate_ephemeral_key(key_dir.path())?;
+            rt.block_on(ec2_manager.send_ssh_public_key(
+                &droplet.instance_id,
+                &droplet.availability_zone,
+                ssh_session::DEFAULT_USER,
+                &public_key,
+            ))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+            ssh_session::Method::Key { key_path, host }
+        }
+        _ => ssh_session::Method::Ssm {
+            region: aws_resources.region.clone(),
+        },
+    };
+
+    let mut args = method.args(&droplet.instance_id);
+    let transcript_path = if record {
+        let p = random::tmp_path(15, Some(".log"))?;
+        args = ssh_session::record_args(&args, &p);
+        Some(p)
+    } else {
+        None
+    };
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nConnecting to '{}' ({}, {}) via {}{}\n",
+            droplet.instance_id,
+            droplet.availability_zone,
+            droplet.public_ipv4,
+            via,
+            if record { ", recording" } else { "" }
+        )),
+        ResetColor
+    )?;
+    let started_at = Utc::now();
+    let status = ProcessCommand::new(&args[0])
+        .args(&args[1..])
+        .status()
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("failed to run '{}' ({})", args[0], e),
+            )
+        })?;
+    if !status.success() {
+        warn!("session exited with {}", status);
+    }
+
+    if let Some(p) = transcript_path {
+        let s3_key = ssh_session::recording_s3_key(
+            &spec.id,
+            &started_at,
+            &droplet.instance_id,
+            &operation::current()
+                .unwrap_or_else(|| operation::Operation::new(NAME))
+                .operator,
+        );
+        info!("uploading the session transcript to {}", s3_key);
+        let s3_manager = s3::Manager::new(&shared_config);
+        rt.block_on(s3_manager.put_object(
+            Arc::new(p.clone()),
+            Arc::new(aws_resources.s3_bucket.clone()),
+            Arc::new(s3_key.clone()),
+        ))
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!(
+                    "failed to upload the session transcript (kept in {}) ({})",
+                    p,
+                    e.message()
+                ),
+            )
+        })?;
+        fs::remove_file(&p)?;
+        println!(
+            "\nrecorded session in s3://{}/{}\n",
+            aws_resources.s3_bucket, s3_key
+        );
+    }
+
+    Ok(())
+}
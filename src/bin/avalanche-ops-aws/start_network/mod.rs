@@ -0,0 +1,178 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    time::Duration,
+};
+
+use chrono::Utc;
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, ssm},
+    network_shutdown, network_snapshot,
+};
+
+use crate::snapshot_network::run_all;
+
+pub const NAME: &str = "start-network";
+
+/// Time for "avalanched" to start on each node.
+const START_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Time for the nodes of a stage to become healthy.
+const HEALTHY_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Starts the nodes stopped by 'stop-network' in reverse order (anchor nodes, non-anchor nodes, then API nodes), waiting for each stage to become healthy")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(log_level: &str, spec_file_path: &str, skip_prompt: bool) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec.aws_resources.clone().unwrap();
+    if !spec.avalanchego_config.is_custom_network() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "'start-network' is only valid for the custom network",
+        ));
+    }
+    let current_nodes = spec
+        .current_nodes
+        .clone()
+        .expect("unexpected None current_nodes in spec file (run 'apply' first)");
+    let stages = network_shutdown::start_stages(&current_nodes, |kind| spec.is_api_node(kind));
+    let http_scheme = if spec.avalanchego_config.http_tls_enabled == Some(true) {
+        "https"
+    } else {
+        "http"
+    };
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nStarting all {} nodes of '{}' in {} stage(s):\n",
+            current_nodes.len(),
+            spec.id,
+            stages.len()
+        )),
+        ResetColor
+    )?;
+    for (i, stage) in stages.iter().enumerate() {
+        println!("{}. {}", i + 1, stage.name);
+        for n in stage.nodes.iter() {
+            println!("   {} ({})", n.node_id, n.machine_id);
+        }
+    }
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to start the network!",
+            "Yes, let's start the network!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'start-network' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let ssm_manager = ssm::Manager::new(&shared_config);
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    let start_id = Utc::now().format("%Y%m%d%H%M%S").to_string();
+    for stage in stages.iter() {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print(format!("\n\n\nSTEP: starting {} nodes\n", stage.name)),
+            ResetColor
+        )?;
+        let instance_ids: Vec<String> = stage.nodes.iter().map(|n| n.machine_id.clone()).collect();
+        run_all(
+            &rt,
+            &ssm_manager,
+            &instance_ids,
+            &network_snapshot::resume_script(),
+            &format!("{} {} {}", NAME, start_id, stage.name),
+            START_TIMEOUT,
+        )?;
+
+        // the next stage bootstraps from the nodes of this stage
+        info!("waiting for {} nodes to become healthy", stage.name);
+        run_all(
+            &rt,
+            &ssm_manager,
+            &instance_ids,
+            &network_shutdown::wait_healthy_script(
+                http_scheme,
+                spec.avalanchego_config.http_port,
+                HEALTHY_TIMEOUT,
+            ),
+            &format!("{} {} {} healthy", NAME, start_id, stage.name),
+            HEALTHY_TIMEOUT + Duration::from_secs(60),
+        )?;
+        info!("started {} {} nodes", instance_ids.len(), stage.name);
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!("\n\n\nSTEP: started all nodes of '{}'\n", spec.id)),
+        ResetColor
+    )?;
+    info!("'start-network' all success!");
+    Ok(())
+}
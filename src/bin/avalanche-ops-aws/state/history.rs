@@ -0,0 +1,70 @@
+use std::io::{self, Error, ErrorKind};
+
+use clap::{Arg, Command};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, s3},
+    spec_state,
+};
+
+pub const NAME: &str = "history";
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Lists the past spec states of the cluster (latest first)")
+        .arg(super::log_level_arg())
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(log_level: &str, spec_file_path: &str) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+
+    let versions = rt.block_on(spec_state::versions(
+        &s3_manager,
+        &aws_resources.s3_bucket,
+        &spec.id,
+    ))?;
+    if versions.is_empty() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("no spec state for '{}'", spec.id),
+        ));
+    }
+
+    println!("{:<34} {:<22} {:>8}", "VERSION ID", "LAST MODIFIED", "SIZE");
+    for v in versions.iter() {
+        println!(
+            "{:<34} {:<22} {:>8}{}",
+            v.version_id,
+            v.last_modified,
+            v.size,
+            if v.is_latest { "  (latest)" } else { "" }
+        );
+    }
+    Ok(())
+}
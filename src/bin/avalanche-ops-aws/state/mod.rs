@@ -0,0 +1,28 @@
+use clap::{Arg, Command};
+
+pub mod history;
+pub mod pull;
+pub mod rollback;
+
+pub const NAME: &str = "state";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Inspects and restores the spec state stored in the versioned S3 bucket")
+        .subcommand(history::subcommand())
+        .subcommand(pull::subcommand())
+        .subcommand(rollback::subcommand())
+}
+
+pub fn log_level_arg() -> Arg<'static> {
+    Arg::new("LOG_LEVEL")
+        .long("log-level")
+        .short('l')
+        .help("Sets the log level")
+        .required(false)
+        .takes_value(true)
+        .possible_value("debug")
+        .possible_value("info")
+        .allow_invalid_utf8(false)
+        .default_value("info")
+}
@@ -0,0 +1,118 @@
+use std::{
+    io::{self, Error, ErrorKind},
+    path::Path,
+};
+
+use clap::{Arg, Command};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, s3},
+    spec_state,
+};
+
+pub const NAME: &str = "pull";
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Updates the local spec file from the remote spec state (or re-creates a lost spec file)")
+        .arg(super::log_level_arg())
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to update or create")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("REGION")
+                .long("region")
+                .help("Sets the AWS region of the S3 bucket (only if the spec file does not exist)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("S3_BUCKET")
+                .long("s3-bucket")
+                .help("Sets the S3 bucket of the cluster (only if the spec file does not exist)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("ID")
+                .long("id")
+                .help("Sets the cluster ID (only if the spec file does not exist)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    region: Option<&str>,
+    s3_bucket: Option<&str>,
+    id: Option<&str>,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let rt = Runtime::new().unwrap();
+
+    if Path::new(spec_file_path).exists() {
+        let mut spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+        let aws_resources = spec
+            .aws_resources
+            .clone()
+            .expect("unexpected None aws_resources");
+        let shared_config = rt
+            .block_on(aws::load_config(Some(aws_resources.region.clone())))
+            .expect("failed to aws::load_config");
+        let s3_manager = s3::Manager::new(&shared_config);
+
+        let merged = rt.block_on(spec_state::pull(&s3_manager, &mut spec))?;
+        if merged.is_empty() {
+            println!("'{}' is up-to-date", spec_file_path);
+            return Ok(());
+        }
+        spec.sync(spec_file_path)?;
+        println!("updated '{}' fields {:?}", spec_file_path, merged);
+        return Ok(());
+    }
+
+    let (region, s3_bucket, id) = match (region, s3_bucket, id) {
+        (Some(region), Some(s3_bucket), Some(id)) => (region, s3_bucket, id),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'{}' does not exist; '--region', '--s3-bucket', and '--id' are required to re-create",
+                    spec_file_path
+                ),
+            ))
+        }
+    };
+    let shared_config = rt
+        .block_on(aws::load_config(Some(region.to_string())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+    let spec = rt
+        .block_on(spec_state::download(&s3_manager, s3_bucket, id, None))?
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("no spec state for '{}' in '{}'", id, s3_bucket),
+            )
+        })?;
+    spec.sync(spec_file_path)?;
+    println!("re-created '{}' from the spec state", spec_file_path);
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use std::{
+    io::{self, Error, ErrorKind},
+    sync::Arc,
+};
+
+use clap::{Arg, Command};
+use dialoguer::{theme::ColorfulTheme, Select};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, s3},
+    spec_diff, spec_state,
+};
+
+pub const NAME: &str = "rollback";
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Restores the spec state to a past version (see 'state history'); does not change the AWS resources")
+        .arg(super::log_level_arg())
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load and overwrite")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("VERSION_ID")
+                .long("version-id")
+                .help("Sets the version to restore")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    version_id: &str,
+    skip_prompt: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+
+    let mut restored = rt
+        .block_on(spec_state::download(
+            &s3_manager,
+            &aws_resources.s3_bucket,
+            &spec.id,
+            Some(version_id),
+        ))?
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("no spec state version '{}'", version_id),
+            )
+        })?;
+
+    let changes = spec_diff::diff(&spec, &restored)?;
+    if changes.is_empty() {
+        println!("spec already matches version '{}'", version_id);
+        return Ok(());
+    }
+    spec_diff::print_changes(&changes)?;
+
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to roll back the spec state!",
+            "Yes, let's roll back the spec state!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'rollback' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    // keeps the local placeholders for the fields not rolled back
+    restored.interpolations = spec.interpolations.clone();
+    restored.sync(spec_file_path)?;
+    rt.block_on(s3_manager.put_bytes(
+        restored.encode_state_yaml()?.into_bytes(),
+        Arc::new(aws_resources.s3_bucket.clone()),
+        Arc::new(avalanche_ops::StorageNamespace::ConfigFile(spec.id.clone()).encode()),
+    ))
+    .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    println!(
+        "rolled back the spec state to version '{}' (as a new version)",
+        version_id
+    );
+    Ok(())
+}
@@ -0,0 +1,379 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    sync::Arc,
+};
+
+use chrono::{Duration, Utc};
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::warn;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::avalanchego::api::{eth, health, platform},
+    aws::{self, cloudwatch, s3},
+    metric_schema, validator_expiry,
+};
+
+pub const NAME: &str = "status";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Checks the network endpoints through the load balancer (HTTP health and websocket), and shows the fleet metrics")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("EXPIRY_WARN_DAYS")
+                .long("expiry-warn-days")
+                .help("Warns the validators ending staking within the days (default to 'validator_expiry.warn_days' in spec)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    expiry_warn_days: Option<u64>,
+    read_only: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let mut spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let endpoints = match &spec.endpoints {
+        Some(v) => v.clone(),
+        None => {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("no endpoints for '{}' (not applied yet?)", spec.id),
+            ))
+        }
+    };
+
+    let rt = Runtime::new().unwrap();
+    let mut failed = 0;
+
+    if let Some(http_rpc) = &endpoints.http_rpc {
+        let ret = rt.block_on(health::check(Arc::new(http_rpc.clone()), true));
+        let ok = matches!(&ret, Ok(res) if res.healthy == Some(true));
+        if !ok {
+            failed += 1;
+        }
+        print_check(
+            "HTTP liveness",
+            endpoints.liveness.as_deref().unwrap_or(http_rpc),
+            ok,
+            match ret {
+                Ok(res) => format!("healthy {:?}", res.healthy),
+                Err(e) => e.to_string(),
+            },
+        )?;
+    }
+
+    if let Some(ws) = &endpoints.websocket {
+        let ret = rt.block_on(eth::chain_id_via_websocket(ws));
+        if ret.is_err() {
+            failed += 1;
+        }
+        print_check(
+            "C-chain websocket",
+            ws,
+            ret.is_ok(),
+            match ret {
+                Ok(chain_id) => format!("chain ID {}", chain_id),
+                Err(e) => e.to_string(),
+            },
+        )?;
+    }
+
+    if let Some(aws_resources) = &spec.aws_resources {
+        if let Some(namespace) = &aws_resources.cloudwatch_avalanche_metrics_namespace {
+            // the schema version 1 metrics have no dimensions
+            let cluster_id = match aws_resources.cloudwatch_metrics_schema_version {
+                Some(metric_schema::VERSION_2) => Some(spec.id.as_str()),
+                _ => None,
+            };
+            if let Err(e) = print_fleet_metrics(&rt, &aws_resources.region, namespace, cluster_id) {
+                warn!("failed to fetch the fleet metrics ({})", e);
+            }
+        }
+    }
+
+    if spec.indexer.is_some() {
+        if let Err(e) = print_index_backfill(&rt, &spec) {
+            warn!("failed to fetch the index backfill progress ({})", e);
+        }
+    }
+
+    if let Err(e) =
+        check_validator_expiry(&rt, &mut spec, spec_file_path, expiry_warn_days, read_only)
+    {
+        warn!("failed to check the validator expiry ({})", e);
+    }
+
+    if failed > 0 {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("{} endpoint check(s) failed", failed),
+        ));
+    }
+    Ok(())
+}
+
+/// Tracks the staking end times of the cluster nodes in the spec,
+/// and warns (and posts to the webhook) the validators ending soon.
+fn check_validator_expiry(
+    rt: &Runtime,
+    spec: &mut avalanche_ops::Spec,
+    spec_file_path: &str,
+    warn_days: Option<u64>,
+    read_only: bool,
+) -> io::Result<()> {
+    let node_ids: Vec<String> = match &spec.current_nodes {
+        Some(nodes) => nodes.iter().map(|n| n.node_id.clone()).collect(),
+        None => return Ok(()),
+    };
+    let http_rpc = crate::elastic_subnet::get_http_rpc(spec, "")?;
+    let validators = rt.block_on(platform::get_validators(
+        &http_rpc,
+        platform::PRIMARY_NETWORK_ID,
+    ))?;
+
+    let mut expiry = spec.validator_expiry.clone().unwrap_or_default();
+    if expiry.track(&node_ids, &validators) {
+        spec.validator_expiry = Some(expiry.clone());
+        spec.sync(spec_file_path)?;
+        // the read-only mode only updates the local spec
+        if let (Some(aws_resources), false) = (&spec.aws_resources, read_only) {
+            let shared_config =
+                rt.block_on(aws::load_config(Some(aws_resources.region.clone())))?;
+            let s3_manager = s3::Manager::new(&shared_config);
+            // not to overwrite the spec of the running operation,
+            // and tracked again by the next "status"
+            match rt.block_on(aws::lock::Guard::acquire(&shared_config, &spec.id)) {
+                Ok(_lock) => {
+                    rt.block_on(s3_manager.put_bytes(
+                        spec.encode_state_yaml()?.into_bytes(),
+                        Arc::new(aws_resources.s3_bucket.clone()),
+                        Arc::new(
+                            avalanche_ops::StorageNamespace::ConfigFile(spec.id.clone()).encode(),
+                        ),
+                    ))?;
+                }
+                Err(e) => warn!("skipping the spec upload ({})", e.message()),
+            }
+        }
+    }
+
+    let warn_days = warn_days.unwrap_or_else(|| expiry.warn_days());
+    let expiring = expiry.expiring(Utc::now().timestamp() as u64, warn_days);
+    if expiring.is_empty() {
+        return Ok(());
+    }
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nvalidators ending staking within {} days\n",
+            warn_days
+        )),
+        ResetColor
+    )?;
+    for e in expiring.iter() {
+        let detail = if e.expired {
+            String::from("ended (run 'renew-validators')")
+        } else {
+            format!("ends in {} day(s)", e.days_left)
+        };
+        execute!(
+            stdout(),
+            SetForegroundColor(if e.expired { Color::Red } else { Color::Yellow }),
+            Print(format!(
+                "EXPIRY {} {} ({} nAVAX)\n",
+                e.node_id, detail, e.weight
+            )),
+            ResetColor
+        )?;
+    }
+
+    if let Some(webhook_url) = &expiry.webhook_url {
+        let payload = validator_expiry::encode_webhook_message(&spec.id, &expiring)?;
+        rt.block_on(validator_expiry::notify(webhook_url, &payload))?;
+    }
+    Ok(())
+}
+
+/// Prints the index backfill progress that "avalanched" reports
+/// in the heartbeats of the index-enabled nodes.
+fn print_index_backfill(rt: &Runtime, spec: &avalanche_ops::Spec) -> io::Result<()> {
+    let aws_resources = match &spec.aws_resources {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    let shared_config = rt.block_on(aws::load_config(Some(aws_resources.region.clone())))?;
+    let s3_manager = s3::Manager::new(&shared_config);
+    let mut heartbeats = crate::fleet::fetch_heartbeats(
+        rt,
+        &s3_manager,
+        Arc::new(aws_resources.s3_bucket.clone()),
+        &spec.id,
+    )?;
+    heartbeats.sort_by(|a, b| (&a.kind, &a.node_id).cmp(&(&b.kind, &b.node_id)));
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print("\nindex backfill progress\n"),
+        ResetColor
+    )?;
+    for hb in heartbeats.iter() {
+        let backfill = match &hb.index_backfill {
+            Some(v) => v,
+            None => continue,
+        };
+        if backfill.is_empty() {
+            execute!(
+                stdout(),
+                SetForegroundColor(Color::Yellow),
+                Print(format!(
+                    "INDEX {} ({}) not available (still bootstrapping?)\n",
+                    hb.node_id, hb.kind
+                )),
+                ResetColor
+            )?;
+        }
+        for b in backfill.iter() {
+            execute!(
+                stdout(),
+                SetForegroundColor(if b.is_complete() {
+                    Color::Green
+                } else {
+                    Color::Yellow
+                }),
+                Print(format!(
+                    "INDEX {} ({}) {}-chain {}/{} ({:.2}%)\n",
+                    hb.node_id,
+                    hb.kind,
+                    b.chain,
+                    b.indexed,
+                    b.height,
+                    b.progress_percent()
+                )),
+                ResetColor
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints the fleet-level aggregates of the metrics that "avalanched"
+/// publishes from every node, over the last 15 minutes.
+fn print_fleet_metrics(
+    rt: &Runtime,
+    region: &str,
+    namespace: &str,
+    cluster_id: Option<&str>,
+) -> io::Result<()> {
+    let shared_config = rt.block_on(aws::load_config(Some(region.to_string())))?;
+    let cw_manager = cloudwatch::Manager::new(&shared_config);
+
+    let mut queries = vec![
+        cloudwatch::Query::stat(
+            "latency",
+            "avalanche_requests_average_latency_seconds",
+            "p99",
+        ),
+        cloudwatch::Query::stat("peers", "avalanche_network_peers", "Minimum"),
+        cloudwatch::Query::stat("cmin", "avalanche_C_blks_accepted_count", "Minimum"),
+        cloudwatch::Query::stat("cmax", "avalanche_C_blks_accepted_count", "Maximum").hidden(),
+        cloudwatch::Query::expression("cspread", "cmax - cmin", "C-chain height spread"),
+        cloudwatch::Query::stat("pmin", "avalanche_P_blks_accepted_count", "Minimum"),
+    ];
+    if let Some(cluster_id) = cluster_id {
+        // per-cluster rollup
+        queries = queries
+            .into_iter()
+            .map(|q| q.dimension(metric_schema::DIMENSION_CLUSTER_ID, cluster_id))
+            .collect();
+    }
+    let labels = [
+        ("latency", "p99 request latency (seconds)"),
+        ("peers", "min peers"),
+        ("cmin", "min C-chain accepted blocks"),
+        ("cspread", "C-chain accepted blocks spread"),
+        ("pmin", "min P-chain accepted blocks"),
+    ];
+
+    let end = Utc::now();
+    let results = rt
+        .block_on(cw_manager.get_metric_data(
+            namespace,
+            &queries,
+            end - Duration::minutes(15),
+            end,
+            300,
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nfleet metrics in '{}' (last 15 minutes)\n",
+            namespace
+        )),
+        ResetColor
+    )?;
+    for (id, label) in labels {
+        let value = results
+            .iter()
+            .find(|r| r.id == id)
+            .and_then(|r| r.latest())
+            .map(|v| format!("{:.3}", v))
+            .unwrap_or_else(|| String::from("no data"));
+        println!("{:<32} {}", label, value);
+    }
+    Ok(())
+}
+
+fn print_check(name: &str, url: &str, ok: bool, detail: String) -> io::Result<()> {
+    execute!(
+        stdout(),
+        SetForegroundColor(if ok { Color::Green } else { Color::Red }),
+        Print(format!(
+            "{} {:<18} {} ({})\n",
+            if ok { "OK  " } else { "FAIL" },
+            name,
+            url,
+            detail
+        )),
+        ResetColor
+    )
+}
@@ -0,0 +1,225 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    time::Duration,
+};
+
+use chrono::Utc;
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    aws::{self, ssm},
+    network_shutdown, network_snapshot,
+};
+
+use crate::snapshot_network::{poll_all, run_all};
+
+pub const NAME: &str = "stop-network";
+
+/// Time for the services to stop on each node.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Time for the final database backup on each node.
+const BACKUP_TIMEOUT: Duration = Duration::from_secs(3 * 60 * 60);
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Stops avalanchego on all nodes of the custom network in order (API nodes, non-anchor nodes, then anchor nodes), and backs up their databases")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_BACKUP")
+                .long("skip-backup")
+                .help("Skips the final database backups of the stopped nodes")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("FORCE")
+                .long("force")
+                .help("Runs even outside of the maintenance window in the spec")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    skip_backup: bool,
+    force: bool,
+    skip_prompt: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec.aws_resources.clone().unwrap();
+    if !spec.avalanchego_config.is_custom_network() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "'stop-network' is only valid for the custom network",
+        ));
+    }
+    spec.check_maintenance_window(NAME, force)?;
+    let current_nodes = spec
+        .current_nodes
+        .clone()
+        .expect("unexpected None current_nodes in spec file (run 'apply' first)");
+    let stages = network_shutdown::stop_stages(&current_nodes, |kind| spec.is_api_node(kind));
+
+    let stop_id = Utc::now().format("%Y%m%d%H%M%S").to_string();
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nStopping all {} nodes of '{}' in {} stage(s):\n",
+            current_nodes.len(),
+            spec.id,
+            stages.len()
+        )),
+        ResetColor
+    )?;
+    for (i, stage) in stages.iter().enumerate() {
+        println!("{}. {}", i + 1, stage.name);
+        for n in stage.nodes.iter() {
+            println!("   {} ({})", n.node_id, n.machine_id);
+        }
+    }
+    if !skip_backup {
+        println!(
+            "\nFinal database backups: s3://{}/{}",
+            aws_resources.s3_bucket,
+            network_shutdown::backup_key(&spec.id, &stop_id, "[NODE_ID]")
+        );
+    }
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to stop the network!",
+            "Yes, let's stop the network!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'stop-network' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let ssm_manager = ssm::Manager::new(&shared_config);
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    // stops "avalanched" with "avalanchego", so the remediation and
+    // the update events do not restart the stopped nodes
+    for stage in stages.iter() {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print(format!("\n\n\nSTEP: stopping {} nodes\n", stage.name)),
+            ResetColor
+        )?;
+        let instance_ids: Vec<String> = stage.nodes.iter().map(|n| n.machine_id.clone()).collect();
+        run_all(
+            &rt,
+            &ssm_manager,
+            &instance_ids,
+            &network_snapshot::pause_script(),
+            &format!("{} {} {}", NAME, stop_id, stage.name),
+            STOP_TIMEOUT,
+        )?;
+        info!("stopped {} {} nodes", instance_ids.len(), stage.name);
+    }
+
+    // all nodes are stopped, so the databases stay unchanged during the uploads
+    if !skip_backup {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print("\n\n\nSTEP: backing up databases\n"),
+            ResetColor
+        )?;
+        let mut commands = Vec::new();
+        for n in current_nodes.iter() {
+            let script = network_snapshot::upload_db_script(
+                &spec.avalanchego_config.db_dir,
+                &format!(
+                    "s3://{}/{}",
+                    aws_resources.s3_bucket,
+                    network_shutdown::backup_key(&spec.id, &stop_id, &n.node_id)
+                ),
+            );
+            let command_id = rt
+                .block_on(ssm_manager.send_command(
+                    vec![n.machine_id.clone()],
+                    &script,
+                    &format!("{} {} backup", NAME, stop_id),
+                    BACKUP_TIMEOUT,
+                ))
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+            commands.push((n, command_id));
+        }
+        poll_all(&rt, &ssm_manager, &commands, BACKUP_TIMEOUT)?;
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!("\n\n\nSTEP: stopped all nodes of '{}'\n", spec.id)),
+        ResetColor
+    )?;
+    println!(
+        "avalanche-ops-aws start-network --spec-file-path {}",
+        spec_file_path
+    );
+    info!("'stop-network' all success!");
+    Ok(())
+}
@@ -0,0 +1,221 @@
+use std::io::{self, stdout, Error, ErrorKind};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::{
+        avalanchego::api::platform,
+        ids,
+        platformvm::{txs, wallet},
+    },
+};
+
+pub const NAME: &str = "add-validator";
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Adds the nodes to the permissioned subnet validator set")
+        .args(super::common_args())
+        .arg(super::subnet_id_arg())
+        .args(super::tx_args())
+        .arg(
+            Arg::new("NODE_IDS")
+                .long("node-ids")
+                .help("Sets comma-separated node IDs to add (default to all nodes in the spec)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("WEIGHT")
+                .long("weight")
+                .help("Sets the validator weight")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("1000"),
+        )
+        .arg(
+            Arg::new("START_DELAY_SECONDS")
+                .long("start-delay-seconds")
+                .help("Sets the delay in seconds before the validation starts")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("60"),
+        )
+        .arg(
+            Arg::new("VALIDATE_DURATION")
+                .long("validate-duration")
+                .help("Sets the validation duration in seconds (default to the end of the primary network validation)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    subnet_id: &str,
+    http_rpc: &str,
+    private_key_path: &str,
+    node_ids: &str,
+    weight: u64,
+    start_delay_seconds: u64,
+    validate_duration: Option<u64>,
+    skip_prompt: bool,
+    multisig_opts: &crate::tx::MultisigOpts,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let node_ids: Vec<String> = {
+        if node_ids.is_empty() {
+            spec.current_nodes
+                .clone()
+                .expect("unexpected None current_nodes (run 'apply' first)")
+                .iter()
+                .map(|n| n.node_id.clone())
+                .collect()
+        } else {
+            node_ids.split(',').map(String::from).collect()
+        }
+    };
+    for node_id in node_ids.iter() {
+        ids::parse_node_id(node_id)?;
+    }
+
+    let http_rpc = crate::elastic_subnet::get_http_rpc(&spec, http_rpc)?;
+    let rt = Runtime::new().unwrap();
+
+    // the subnet validation period must be within the primary network validation
+    let primary_validators = rt.block_on(platform::get_validators(
+        &http_rpc,
+        platform::PRIMARY_NETWORK_ID,
+    ))?;
+    let subnet_validators = rt.block_on(platform::get_validators(&http_rpc, subnet_id))?;
+    let start = wallet::now_unix() + start_delay_seconds;
+    let mut to_add: Vec<(String, u64)> = Vec::new();
+    for node_id in node_ids.iter() {
+        if subnet_validators.iter().any(|v| &v.node_id == node_id) {
+            warn!(
+                "{} already validates subnet {}, skipping",
+                node_id, subnet_id
+            );
+            continue;
+        }
+        let primary_end = match primary_validators
+            .iter()
+            .find(|v| &v.node_id == node_id && !v.pending)
+        {
+            Some(v) => v.end_time,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("{} is not a current primary network validator", node_id),
+                ));
+            }
+        };
+        let end = match validate_duration {
+            Some(d) => start + d,
+            None => primary_end,
+        };
+        if end > primary_end || end <= start {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} validation period [{}, {}] not within the primary network validation ending at {}",
+                    node_id, start, end, primary_end
+                ),
+            ));
+        }
+        to_add.push((node_id.clone(), end));
+    }
+    if to_add.is_empty() {
+        info!("no validator to add");
+        return Ok(());
+    }
+
+    let k = super::load_control_key(&spec, subnet_id, private_key_path)?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nAdding {} validators to subnet {} via {} (weight {})\n",
+            to_add.len(),
+            subnet_id,
+            http_rpc,
+            weight
+        )),
+        ResetColor
+    )?;
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to add validators!",
+            "Yes, let's add validators!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'add-validator' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    let w = crate::tx::load_wallet(
+        &rt,
+        k,
+        spec.avalanchego_config.network_id,
+        &http_rpc,
+        multisig_opts,
+    )?;
+    for (node_id, end) in to_add.iter() {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print(format!(
+                "\n\n\nSTEP: issue AddSubnetValidatorTx for {}\n",
+                node_id
+            )),
+            ResetColor
+        )?;
+        let issued = rt.block_on(w.add_subnet_validator(
+            subnet_id,
+            txs::Validator {
+                node_id: ids::parse_node_id(node_id)?,
+                start,
+                end: *end,
+                weight,
+            },
+        ))?;
+        if let Some(tx_id) = crate::tx::handle_issued(
+            issued,
+            &spec.id,
+            &format!("add-subnet-validator-{}", node_id),
+            multisig_opts,
+        )? {
+            println!("added validator {} (tx {})", node_id, tx_id);
+        }
+    }
+
+    println!();
+    info!("'subnet add-validator' all success!");
+    Ok(())
+}
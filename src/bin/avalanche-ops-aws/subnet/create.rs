@@ -0,0 +1,129 @@
+use std::io::{self, stdout};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::platformvm::{multisig, txs},
+};
+
+pub const NAME: &str = "create";
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Creates a permissioned subnet owned by the N-of-M control keys")
+        .args(super::common_args())
+        .args(super::tx_args())
+        .mut_arg("PRIVATE_KEY_PATH", |a| {
+            a.help("Sets the key path (CB58 'PrivateKey-' or hex) that pays for the transaction, or 'prefunded:N' for the N-th prefunded key in the spec")
+                .required(true)
+        })
+        .arg(
+            Arg::new("CONTROL_KEYS")
+                .long("control-keys")
+                .help("Sets comma-separated P-chain addresses of the subnet control keys (default to the paying key)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("THRESHOLD")
+                .long("threshold")
+                .help("Sets the number of control key signatures required for the subnet operations")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("1"),
+        )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    http_rpc: &str,
+    private_key_path: &str,
+    control_keys: &str,
+    threshold: u32,
+    skip_prompt: bool,
+    multisig_opts: &crate::tx::MultisigOpts,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let http_rpc = crate::elastic_subnet::get_http_rpc(&spec, http_rpc)?;
+    let k = crate::elastic_subnet::load_funding_key(&spec, private_key_path)?;
+
+    let mut control_keys = multisig::parse_addresses(control_keys)?;
+    if control_keys.is_empty() {
+        let mut addr = [0u8; 20];
+        addr.copy_from_slice(&k.short_address_bytes()?);
+        control_keys.push(addr);
+    }
+    let owner = txs::OutputOwners::new_multisig(threshold, &control_keys)?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nCreating subnet via {} with {}-of-{} control keys\n",
+            http_rpc,
+            owner.threshold,
+            owner.addrs.len()
+        )),
+        ResetColor
+    )?;
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to create the subnet!",
+            "Yes, let's create the subnet!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'create' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print("\n\n\nSTEP: issue CreateSubnetTx\n"),
+        ResetColor
+    )?;
+    let rt = Runtime::new().unwrap();
+    let w = crate::tx::load_wallet(
+        &rt,
+        k,
+        spec.avalanchego_config.network_id,
+        &http_rpc,
+        multisig_opts,
+    )?;
+    let issued = rt.block_on(w.create_subnet(owner))?;
+    if let Some(tx_id) = crate::tx::handle_issued(issued, &spec.id, "create-subnet", multisig_opts)?
+    {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Blue),
+            Print(format!("\nCreated subnet {}\n", tx_id)),
+            ResetColor
+        )?;
+    }
+
+    info!("'subnet create' all success!");
+    Ok(())
+}
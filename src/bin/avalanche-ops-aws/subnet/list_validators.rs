@@ -0,0 +1,61 @@
+use std::io;
+
+use clap::Command;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{self, avalanche::avalanchego::api::platform};
+
+pub const NAME: &str = "list-validators";
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Lists the current and pending validators of the subnet")
+        .args(super::common_args())
+        .arg(super::subnet_id_arg())
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    subnet_id: &str,
+    http_rpc: &str,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let http_rpc = crate::elastic_subnet::get_http_rpc(&spec, http_rpc)?;
+
+    let rt = Runtime::new().unwrap();
+    let validators = rt.block_on(platform::get_validators(&http_rpc, subnet_id))?;
+
+    // mark the nodes that this spec manages
+    let managed: Vec<String> = spec
+        .current_nodes
+        .clone()
+        .unwrap_or_default()
+        .iter()
+        .map(|n| n.node_id.clone())
+        .collect();
+
+    println!();
+    println!("subnet {} ({} validators)", subnet_id, validators.len());
+    for v in validators.iter() {
+        println!(
+            "{} weight={} start={} end={}{}{}",
+            v.node_id,
+            v.weight,
+            v.start_time,
+            v.end_time,
+            if v.pending { " (pending)" } else { "" },
+            if managed.contains(&v.node_id) {
+                " (managed)"
+            } else {
+                ""
+            },
+        );
+    }
+    Ok(())
+}
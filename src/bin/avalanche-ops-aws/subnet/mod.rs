@@ -0,0 +1,179 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind},
+    sync::Arc,
+};
+
+use clap::{Arg, Command};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::{ids, key},
+    aws::{self, envelope, kms, s3},
+    utils::random,
+};
+
+pub mod add_validator;
+pub mod create;
+pub mod list_validators;
+pub mod remove_validator;
+pub mod update_fee_config;
+pub mod upload_control_key;
+
+pub const NAME: &str = "subnet";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Creates a permissioned subnet and manages its validator set and fee config")
+        .subcommands(vec![
+            create::subcommand(),
+            upload_control_key::subcommand(),
+            add_validator::subcommand(),
+            remove_validator::subcommand(),
+            list_validators::subcommand(),
+            update_fee_config::subcommand(),
+        ])
+}
+
+/// Returns the arguments shared by all "subnet" sub-commands.
+fn common_args() -> Vec<Arg<'static>> {
+    vec![
+        Arg::new("LOG_LEVEL")
+            .long("log-level")
+            .short('l')
+            .help("Sets the log level")
+            .required(false)
+            .takes_value(true)
+            .possible_value("debug")
+            .possible_value("info")
+            .allow_invalid_utf8(false)
+            .default_value("info"),
+        Arg::new("SPEC_FILE_PATH")
+            .long("spec-file-path")
+            .short('s')
+            .help("The spec file to load")
+            .required(true)
+            .takes_value(true)
+            .allow_invalid_utf8(false),
+        Arg::new("HTTP_RPC")
+            .long("http-rpc")
+            .help("Sets the P-chain API endpoint (default to the public API for Fuji/mainnet, otherwise the spec endpoint)")
+            .required(false)
+            .takes_value(true)
+            .allow_invalid_utf8(false),
+    ]
+}
+
+/// Returns the JSON-RPC URL of the EVM blockchain (e.g., subnet-evm) on the node API endpoint,
+/// default to the spec endpoint.
+pub fn get_blockchain_rpc(
+    spec: &avalanche_ops::Spec,
+    http_rpc: &str,
+    blockchain_id: &str,
+) -> io::Result<String> {
+    ids::parse_id(blockchain_id)?;
+    let http_rpc = if http_rpc.is_empty() {
+        spec.endpoints
+            .clone()
+            .and_then(|e| e.http_rpc)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    "no '--http-rpc' and no 'endpoints.http_rpc' in spec (run 'apply' first)",
+                )
+            })?
+    } else {
+        http_rpc.to_string()
+    };
+    Ok(format!("{}/ext/bc/{}/rpc", http_rpc, blockchain_id))
+}
+
+fn subnet_id_arg() -> Arg<'static> {
+    Arg::new("SUBNET_ID")
+        .long("subnet-id")
+        .help("Sets the permissioned subnet ID")
+        .required(true)
+        .takes_value(true)
+        .allow_invalid_utf8(false)
+}
+
+/// Returns the arguments for the sub-commands that issue transactions.
+fn tx_args() -> Vec<Arg<'static>> {
+    let mut args = vec![
+        Arg::new("PRIVATE_KEY_PATH")
+            .long("private-key-path")
+            .help(
+                "Sets the control key path (default to the encrypted control key in the S3 bucket)",
+            )
+            .required(false)
+            .takes_value(true)
+            .allow_invalid_utf8(false),
+        Arg::new("SKIP_PROMPT")
+            .long("skip-prompt")
+            .help("Skips prompt mode")
+            .required(false)
+            .takes_value(false)
+            .allow_invalid_utf8(false),
+    ];
+    args.extend(crate::tx::multisig_args());
+    args
+}
+
+/// Loads the subnet control key from the local file if specified.
+/// Otherwise, downloads and decrypts the one uploaded by "subnet upload-control-key".
+fn load_control_key(
+    spec: &avalanche_ops::Spec,
+    subnet_id: &str,
+    private_key_path: &str,
+) -> io::Result<key::Key> {
+    if !private_key_path.is_empty() {
+        return crate::elastic_subnet::load_key(private_key_path);
+    }
+
+    let aws_resources = match &spec.aws_resources {
+        Some(v) if v.kms_cmk_id.is_some() => v.clone(),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "no '--private-key-path' and no KMS key in spec (run 'apply' first)",
+            ));
+        }
+    };
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+    let kms_manager = kms::Manager::new(&shared_config);
+    let envelope = envelope::Envelope::new(Some(kms_manager), aws_resources.kms_cmk_id.clone());
+
+    let s3_key = avalanche_ops::StorageNamespace::SubnetControlKeyEncrypted(
+        spec.id.clone(),
+        subnet_id.to_string(),
+    )
+    .encode();
+    info!("downloading subnet control key from {}", s3_key);
+
+    let tmp_encrypted_path = random::tmp_path(15, Some(".encrypted"))?;
+    rt.block_on(s3_manager.get_object(
+        Arc::new(aws_resources.s3_bucket.clone()),
+        Arc::new(s3_key),
+        Arc::new(tmp_encrypted_path.clone()),
+    ))
+    .expect("failed get_object subnet control key (run 'subnet upload-control-key' first)");
+
+    let tmp_decrypted_path = random::tmp_path(15, None)?;
+    rt.block_on(envelope.unseal_aes_256_file(
+        Arc::new(tmp_encrypted_path.clone()),
+        Arc::new(tmp_decrypted_path.clone()),
+    ))
+    .expect("failed unseal_aes_256_file subnet control key");
+
+    let loaded = crate::elastic_subnet::load_key(&tmp_decrypted_path);
+    fs::remove_file(&tmp_encrypted_path)?;
+    fs::remove_file(&tmp_decrypted_path)?;
+    loaded
+}
@@ -0,0 +1,130 @@
+use std::io::{self, stdout, Error, ErrorKind};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::{avalanchego::api::platform, ids},
+};
+
+pub const NAME: &str = "remove-validator";
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Removes the nodes from the permissioned subnet validator set")
+        .args(super::common_args())
+        .arg(super::subnet_id_arg())
+        .args(super::tx_args())
+        .arg(
+            Arg::new("NODE_IDS")
+                .long("node-ids")
+                .help("Sets comma-separated node IDs to remove")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    subnet_id: &str,
+    http_rpc: &str,
+    private_key_path: &str,
+    node_ids: &str,
+    skip_prompt: bool,
+    multisig_opts: &crate::tx::MultisigOpts,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let node_ids: Vec<String> = node_ids.split(',').map(String::from).collect();
+    for node_id in node_ids.iter() {
+        ids::parse_node_id(node_id)?;
+    }
+
+    let http_rpc = crate::elastic_subnet::get_http_rpc(&spec, http_rpc)?;
+    let rt = Runtime::new().unwrap();
+    let subnet_validators = rt.block_on(platform::get_validators(&http_rpc, subnet_id))?;
+    for node_id in node_ids.iter() {
+        if !subnet_validators.iter().any(|v| &v.node_id == node_id) {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("{} is not a validator of subnet {}", node_id, subnet_id),
+            ));
+        }
+    }
+
+    let k = super::load_control_key(&spec, subnet_id, private_key_path)?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nRemoving {} validators from subnet {} via {}\n",
+            node_ids.len(),
+            subnet_id,
+            http_rpc
+        )),
+        ResetColor
+    )?;
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to remove validators!",
+            "Yes, let's remove validators!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'remove-validator' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    let w = crate::tx::load_wallet(
+        &rt,
+        k,
+        spec.avalanchego_config.network_id,
+        &http_rpc,
+        multisig_opts,
+    )?;
+    for node_id in node_ids.iter() {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print(format!(
+                "\n\n\nSTEP: issue RemoveSubnetValidatorTx for {}\n",
+                node_id
+            )),
+            ResetColor
+        )?;
+        let issued =
+            rt.block_on(w.remove_subnet_validator(subnet_id, ids::parse_node_id(node_id)?))?;
+        if let Some(tx_id) = crate::tx::handle_issued(
+            issued,
+            &spec.id,
+            &format!("remove-subnet-validator-{}", node_id),
+            multisig_opts,
+        )? {
+            println!("removed validator {} (tx {})", node_id, tx_id);
+        }
+    }
+
+    println!();
+    info!("'subnet remove-validator' all success!");
+    Ok(())
+}
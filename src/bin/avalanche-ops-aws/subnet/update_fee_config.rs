@@ -0,0 +1,266 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    thread,
+    time::{Duration, Instant},
+};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::{
+        avalanchego::api::eth,
+        coreth::tx,
+        subnet_evm::{fee_manager, genesis::FeeConfig},
+    },
+};
+
+pub const NAME: &str = "update-fee-config";
+
+/// Flags of the fee config fields, in the "fee_manager" ABI order.
+const FIELDS: [(&str, &str); 8] = [
+    ("GAS_LIMIT", "gas-limit"),
+    ("TARGET_BLOCK_RATE", "target-block-rate"),
+    ("MIN_BASE_FEE", "min-base-fee"),
+    ("TARGET_GAS", "target-gas"),
+    ("BASE_FEE_CHANGE_DENOMINATOR", "base-fee-change-denominator"),
+    ("MIN_BLOCK_GAS_COST", "min-block-gas-cost"),
+    ("MAX_BLOCK_GAS_COST", "max-block-gas-cost"),
+    ("BLOCK_GAS_COST_STEP", "block-gas-cost-step"),
+];
+
+const RECEIPT_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub fn subcommand() -> Command<'static> {
+    let mut cmd = Command::new(NAME)
+        .about("Updates the fee config of the running subnet-evm blockchain via the fee manager precompile")
+        .args(super::common_args())
+        .mut_arg("HTTP_RPC", |a| {
+            a.help("Sets the node API endpoint that serves the blockchain (default to the spec endpoint)")
+        })
+        .arg(
+            Arg::new("BLOCKCHAIN_ID")
+                .long("blockchain-id")
+                .help("Sets the subnet-evm blockchain ID")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("PRIVATE_KEY_PATH")
+                .long("private-key-path")
+                .help("Sets the fee manager admin key path (CB58 'PrivateKey-' or hex), or 'prefunded:N' for the N-th prefunded key in the spec")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        );
+    for (name, long) in FIELDS {
+        cmd = cmd.arg(
+            Arg::new(name)
+                .long(long)
+                .help("Sets the new value (default to the current on-chain value)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        );
+    }
+    cmd
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    http_rpc: &str,
+    blockchain_id: &str,
+    private_key_path: &str,
+    overrides: &FeeConfig,
+    skip_prompt: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    if fee_manager::fee_config_fields(overrides)
+        .iter()
+        .all(|v| v.is_none())
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "no fee config field to update (set any of {:?})",
+                FIELDS.iter().map(|(_, long)| *long).collect::<Vec<_>>()
+            ),
+        ));
+    }
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let rpc_url = super::get_blockchain_rpc(&spec, http_rpc, blockchain_id)?;
+    let k = crate::elastic_subnet::load_funding_key(&spec, private_key_path)?;
+
+    let rt = Runtime::new().unwrap();
+    let chain_id = rt.block_on(eth::chain_id(&rpc_url))?;
+    let role = fee_manager::parse_role(&rt.block_on(eth::call_contract(
+        &rpc_url,
+        fee_manager::ADDRESS,
+        &fee_manager::read_allow_list_calldata(&k.eth_address)?,
+    ))?)?;
+    if role != fee_manager::Role::Admin {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "'{}' is not the fee manager admin ({:?}), see 'fee_manager_config.adminAddresses' in the genesis",
+                k.eth_address, role
+            ),
+        ));
+    }
+    let current = fee_manager::parse_fee_config(&rt.block_on(eth::call_contract(
+        &rpc_url,
+        fee_manager::ADDRESS,
+        &fee_manager::get_fee_config_calldata(),
+    ))?)?;
+    let updated = merge(&current, overrides);
+    updated.validate()?;
+    if updated == current {
+        println!("\nfee config unchanged; skipping...");
+        return Ok(());
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nUpdating fee config of {} (chain ID {}) with '{}'\n",
+            blockchain_id, chain_id, k.eth_address
+        )),
+        ResetColor
+    )?;
+    let before = fee_manager::fee_config_fields(&current);
+    let after = fee_manager::fee_config_fields(&updated);
+    for (i, (_, long)) in FIELDS.iter().enumerate() {
+        if before[i] != after[i] {
+            println!(
+                "{}: {} -> {}",
+                long,
+                before[i].unwrap_or_default(),
+                after[i].unwrap_or_default()
+            );
+        }
+    }
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to update the fee config!",
+            "Yes, let's update the fee config!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'update-fee-config' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print("\n\n\nSTEP: send setFeeConfig transaction\n"),
+        ResetColor
+    )?;
+    let nonce = rt.block_on(eth::get_transaction_count(&rpc_url, &k.eth_address))?;
+    let gas_price = rt.block_on(eth::gas_price(&rpc_url))?;
+    let (raw, _) = tx::LegacyTx {
+        nonce,
+        gas_price,
+        gas: fee_manager::SET_FEE_CONFIG_GAS,
+        to: tx::parse_address(fee_manager::ADDRESS)?,
+        value: 0,
+        data: fee_manager::set_fee_config_calldata(&updated)?,
+    }
+    .sign(&k, chain_id)?;
+    let hash = rt.block_on(eth::send_raw_transaction(&rpc_url, &raw))?;
+    info!("sent setFeeConfig {}", hash);
+
+    let start = Instant::now();
+    loop {
+        match rt.block_on(eth::get_transaction_receipt_status(&rpc_url, &hash))? {
+            Some(true) => break,
+            Some(false) => {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!("setFeeConfig {} reverted", hash),
+                ))
+            }
+            None => {}
+        }
+        if start.elapsed() > RECEIPT_TIMEOUT {
+            return Err(Error::new(
+                ErrorKind::TimedOut,
+                format!(
+                    "setFeeConfig {} not accepted in {:?}",
+                    hash, RECEIPT_TIMEOUT
+                ),
+            ));
+        }
+        thread::sleep(Duration::from_secs(2));
+    }
+
+    let applied = fee_manager::parse_fee_config(&rt.block_on(eth::call_contract(
+        &rpc_url,
+        fee_manager::ADDRESS,
+        &fee_manager::get_fee_config_calldata(),
+    ))?)?;
+    if applied != updated {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "setFeeConfig {} accepted but the fee config is {:?}",
+                hash, applied
+            ),
+        ));
+    }
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!("\nUpdated fee config in {}\n", hash)),
+        ResetColor
+    )?;
+
+    info!("'subnet update-fee-config' all success!");
+    Ok(())
+}
+
+/// Overrides the current on-chain fee config with the specified fields.
+fn merge(current: &FeeConfig, overrides: &FeeConfig) -> FeeConfig {
+    FeeConfig {
+        gas_limit: overrides.gas_limit.or(current.gas_limit),
+        target_block_rate: overrides.target_block_rate.or(current.target_block_rate),
+        min_base_fee: overrides.min_base_fee.or(current.min_base_fee),
+        target_gas: overrides.target_gas.or(current.target_gas),
+        base_fee_change_denominator: overrides
+            .base_fee_change_denominator
+            .or(current.base_fee_change_denominator),
+        min_block_gas_cost: overrides.min_block_gas_cost.or(current.min_block_gas_cost),
+        max_block_gas_cost: overrides.max_block_gas_cost.or(current.max_block_gas_cost),
+        block_gas_cost_step: overrides
+            .block_gas_cost_step
+            .or(current.block_gas_cost_step),
+    }
+}
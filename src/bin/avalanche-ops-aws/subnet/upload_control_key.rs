@@ -0,0 +1,130 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    sync::Arc,
+};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::{avalanchego::api::platform, ids},
+    aws::{self, envelope, kms, s3},
+    utils::random,
+};
+
+pub const NAME: &str = "upload-control-key";
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Encrypts the subnet control key with the KMS key and uploads it to the S3 bucket")
+        .args(super::common_args())
+        .arg(super::subnet_id_arg())
+        .arg(
+            Arg::new("PRIVATE_KEY_PATH")
+                .long("private-key-path")
+                .help("Sets the control key path (CB58 'PrivateKey-' or hex)")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    subnet_id: &str,
+    http_rpc: &str,
+    private_key_path: &str,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources (run 'apply' first)");
+    if aws_resources.kms_cmk_id.is_none() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "no KMS key in spec (run 'apply' first)",
+        ));
+    }
+
+    let k = crate::elastic_subnet::load_key(private_key_path)?;
+    let network_id = spec.avalanchego_config.network_id;
+    let p_address = k.address("P", network_id)?;
+    let short_address = k.short_address_bytes()?;
+
+    // reject the key that cannot sign subnet transactions
+    let http_rpc = crate::elastic_subnet::get_http_rpc(&spec, http_rpc)?;
+    let rt = Runtime::new().unwrap();
+    let (control_keys, _) = rt.block_on(platform::get_subnet_owners(&http_rpc, subnet_id))?;
+    let mut found = false;
+    for ck in control_keys.iter() {
+        if ids::parse_short_address(ck)?.to_vec() == short_address {
+            found = true;
+            break;
+        }
+    }
+    if !found {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} is not a control key of subnet {}", p_address, subnet_id),
+        ));
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\n\n\nSTEP: uploading encrypted control key {} for subnet {}\n",
+            p_address, subnet_id
+        )),
+        ResetColor
+    )?;
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    let s3_manager = s3::Manager::new(&shared_config);
+    let kms_manager = kms::Manager::new(&shared_config);
+    let envelope = envelope::Envelope::new(Some(kms_manager), aws_resources.kms_cmk_id.clone());
+
+    let tmp_encrypted_path = random::tmp_path(15, Some(".encrypted"))?;
+    rt.block_on(envelope.seal_aes_256_file(
+        Arc::new(private_key_path.to_string()),
+        Arc::new(tmp_encrypted_path.clone()),
+    ))
+    .expect("failed seal_aes_256_file");
+    rt.block_on(
+        s3_manager.put_object(
+            Arc::new(tmp_encrypted_path),
+            Arc::new(aws_resources.s3_bucket.clone()),
+            Arc::new(
+                avalanche_ops::StorageNamespace::SubnetControlKeyEncrypted(
+                    spec.id.clone(),
+                    subnet_id.to_string(),
+                )
+                .encode(),
+            ),
+        ),
+    )
+    .expect("failed put_object");
+
+    println!();
+    info!("'subnet upload-control-key' all success!");
+    Ok(())
+}
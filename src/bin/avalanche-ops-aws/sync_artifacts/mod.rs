@@ -0,0 +1,413 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, stdout, Error, ErrorKind},
+    path::Path,
+    sync::Arc,
+};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::info;
+use tokio::runtime::Runtime;
+use walkdir::WalkDir;
+
+use avalanche_ops::{
+    self,
+    aws::{self, s3},
+    utils::{
+        compress, hash,
+        provenance::{self, METADATA_KEY_SHA256},
+        random,
+    },
+};
+
+pub const NAME: &str = "sync-artifacts";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Uploads only the changed artifacts in the local directory (by checksum)")
+        .long_about(
+            "
+Diffs the local directory against the cluster's S3 install prefix by SHA-256
+checksum, and uploads only the changed files. The local directory is expected
+to be laid out as follows:
+
+local-dir
+  ├── avalanched (uploaded as is)
+  ├── avalanchego (compressed)
+  ├── icm-relayer (compressed, for the ICM relayer instances)
+  ├── plugins
+  │   └── evm (compressed)
+  └── ... (other files, uploaded as is)
+
+",
+        )
+        .arg(
+            Arg::new("LOCAL_DIR")
+                .help("The local directory to sync")
+                .required(true)
+                .index(1)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("DRY_RUN")
+                .long("dry-run")
+                .help("Set to only print the changed files without uploading")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("NOTIFY_AVALANCHED")
+                .long("notify-avalanched")
+                .help("Set to notify avalanched to pull the updated avalanchego and plugins")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("FORCE")
+                .long("force")
+                .help("Runs even outside of the maintenance window in the spec")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(crate::quorum::skip_quorum_check_arg())
+}
+
+/// Represents a local file and its remote location.
+struct Artifact {
+    local_path: String,
+    s3_key: String,
+    compress: bool,
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    local_dir: &str,
+    dry_run: bool,
+    notify_avalanched: bool,
+    force: bool,
+    skip_quorum_check: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    if !Path::new(local_dir).is_dir() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("local dir {} is not a directory", local_dir),
+        ));
+    }
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    if notify_avalanched && !dry_run {
+        spec.check_maintenance_window("sync-artifacts --notify-avalanched", force)?;
+        crate::quorum::check(
+            &spec,
+            "sync-artifacts --notify-avalanched",
+            skip_quorum_check,
+        )?;
+    }
+    let aws_resources = spec.aws_resources.clone().unwrap();
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let s3_manager = s3::Manager::new(&shared_config);
+
+    let artifacts = list_artifacts(&spec.id, local_dir)?;
+    info!("found {} artifacts in {}", artifacts.len(), local_dir);
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print("\n\n\nSTEP: diff local artifacts by checksum\n"),
+        ResetColor
+    )?;
+    let mut changed: Vec<(&Artifact, String)> = Vec::new();
+    for artifact in artifacts.iter() {
+        let checksum = hash::sha256_file(&artifact.local_path)?;
+        let metadata = rt
+            .block_on(s3_manager.head_object_metadata(&aws_resources.s3_bucket, &artifact.s3_key))
+            .expect("failed head_object_metadata");
+        let remote_checksum = match metadata {
+            Some(m) => m.get(METADATA_KEY_SHA256).cloned(),
+            None => None,
+        };
+        if remote_checksum.as_deref() == Some(checksum.as_str()) {
+            info!("unchanged {} ({})", artifact.local_path, checksum);
+            continue;
+        }
+        println!(
+            "changed {} -> 's3://{}/{}'",
+            artifact.local_path, aws_resources.s3_bucket, artifact.s3_key
+        );
+        changed.push((artifact, checksum));
+    }
+    if changed.is_empty() {
+        println!("\nnothing changed; skipping...");
+        return Ok(());
+    }
+    if dry_run {
+        println!("\n{} changed artifacts (dry run)", changed.len());
+        return Ok(());
+    }
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\n\n\nSTEP: upload {} changed artifacts\n",
+            changed.len()
+        )),
+        ResetColor
+    )?;
+    for (artifact, checksum) in changed.iter() {
+        // avalanchego and plugins (compressed) are verified by "avalanched"
+        let metadata = {
+            if artifact.compress {
+                spec.install_artifacts
+                    .verify_local(&artifact.local_path)?
+                    .to_metadata()
+            } else {
+                let mut metadata = HashMap::new();
+                metadata.insert(METADATA_KEY_SHA256.to_string(), checksum.clone());
+                metadata
+            }
+        };
+
+        let upload_path = {
+            if artifact.compress {
+                let tmp_compressed_path =
+                    random::tmp_path(15, Some(compress::Encoder::Zstd(3).ext())).unwrap();
+                compress::pack_file(
+                    &artifact.local_path,
+                    &tmp_compressed_path,
+                    compress::Encoder::Zstd(3),
+                )?;
+                tmp_compressed_path
+            } else {
+                artifact.local_path.clone()
+            }
+        };
+        rt.block_on(s3_manager.put_object_with_metadata(
+            Arc::new(upload_path.clone()),
+            Arc::new(aws_resources.s3_bucket.clone()),
+            Arc::new(artifact.s3_key.clone()),
+            Some(metadata),
+        ))
+        .expect("failed put_object_with_metadata");
+        if artifact.compress {
+            fs::remove_file(upload_path)?;
+        }
+    }
+
+    if notify_avalanched {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print("\n\n\nSTEP: notify avalanched to pull updated artifacts\n"),
+            ResetColor
+        )?;
+
+        notify(&rt, &s3_manager, &spec)?;
+    }
+
+    println!();
+    info!("'sync-artifacts' all success!");
+    Ok(())
+}
+
+/// Copies the latest install artifacts to the update events directory,
+/// and posts the event for "avalanched" to pull the updated avalanchego and plugins.
+pub fn notify(
+    rt: &Runtime,
+    s3_manager: &s3::Manager,
+    spec: &avalanche_ops::Spec,
+) -> io::Result<()> {
+    let aws_resources = spec.aws_resources.clone().unwrap();
+
+    // avalanched pulls from the update events directory, so copy the latest
+    // install artifacts (including unchanged ones) before posting the event
+    let avalanche_bin_compressed_key =
+        avalanche_ops::StorageNamespace::AvalancheBinCompressed(spec.id.clone()).encode();
+    rt.block_on(s3_manager.copy_object(
+        Arc::new(aws_resources.s3_bucket.clone()),
+        Arc::new(avalanche_bin_compressed_key),
+        Arc::new(aws_resources.s3_bucket.clone()),
+        Arc::new(
+            avalanche_ops::StorageNamespace::EventsUpdateArtifactsInstallDirAvalancheBinCompressed(
+                spec.id.clone(),
+            )
+            .encode(),
+        ),
+    ))
+    .expect("failed copy_object avalanche binary");
+
+    // only for the relayer instances, if uploaded
+    let icm_relayer_bin_compressed_key =
+        avalanche_ops::StorageNamespace::IcmRelayerBinCompressed(spec.id.clone()).encode();
+    if rt
+        .block_on(
+            s3_manager
+                .head_object_metadata(&aws_resources.s3_bucket, &icm_relayer_bin_compressed_key),
+        )
+        .expect("failed head_object_metadata")
+        .is_some()
+    {
+        rt.block_on(s3_manager.copy_object(
+            Arc::new(aws_resources.s3_bucket.clone()),
+            Arc::new(icm_relayer_bin_compressed_key),
+            Arc::new(aws_resources.s3_bucket.clone()),
+            Arc::new(
+                avalanche_ops::StorageNamespace::EventsUpdateArtifactsInstallDirIcmRelayerBinCompressed(
+                    spec.id.clone(),
+                )
+                .encode(),
+            ),
+        ))
+        .expect("failed copy_object ICM relayer binary");
+    }
+
+    let plugins_dir = avalanche_ops::StorageNamespace::PluginsDir(spec.id.clone()).encode();
+    let objects = rt
+        .block_on(s3_manager.list_objects(
+            Arc::new(aws_resources.s3_bucket.clone()),
+            Some(Arc::new(s3::append_slash(&plugins_dir))),
+        ))
+        .expect("failed list_objects");
+    for obj in objects.iter() {
+        let s3_key = obj.key().unwrap();
+        let file_name = s3_key.rsplit('/').next().unwrap();
+        rt.block_on(s3_manager.copy_object(
+            Arc::new(aws_resources.s3_bucket.clone()),
+            Arc::new(s3_key.to_string()),
+            Arc::new(aws_resources.s3_bucket.clone()),
+            Arc::new(format!(
+                "{}/{}",
+                avalanche_ops::StorageNamespace::EventsUpdateArtifactsInstallDirPluginsDir(
+                    spec.id.clone()
+                )
+                .encode(),
+                file_name
+            )),
+        ))
+        .expect("failed copy_object plugin");
+    }
+
+    rt.block_on(s3_manager.put_bytes(
+        spec.encode_state_yaml()?.into_bytes(),
+        Arc::new(aws_resources.s3_bucket.clone()),
+        Arc::new(
+            avalanche_ops::StorageNamespace::EventsUpdateArtifactsEvent(spec.id.clone()).encode(),
+        ),
+    ))
+    .expect("failed put_object EventsUpdateArtifactsEvent");
+    Ok(())
+}
+
+/// Maps the files in the local directory to the S3 keys that "avalanched" downloads.
+fn list_artifacts(id: &str, local_dir: &str) -> io::Result<Vec<Artifact>> {
+    let mut artifacts = Vec::new();
+    for entry in WalkDir::new(local_dir) {
+        let entry = entry.map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to walk {} ({})", local_dir, e),
+            )
+        })?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let local_path = entry.path().to_str().unwrap().to_string();
+        if provenance::is_provenance_file(&local_path) {
+            continue;
+        }
+        let rel_path = entry
+            .path()
+            .strip_prefix(local_dir)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let (s3_key, compress) = match rel_path.as_str() {
+            "avalanched" => (
+                avalanche_ops::StorageNamespace::AvalanchedBin(id.to_string()).encode(),
+                false,
+            ),
+            "avalanchego" => (
+                avalanche_ops::StorageNamespace::AvalancheBinCompressed(id.to_string()).encode(),
+                true,
+            ),
+            "icm-relayer" => (
+                avalanche_ops::StorageNamespace::IcmRelayerBinCompressed(id.to_string()).encode(),
+                true,
+            ),
+            _ => {
+                if let Some(plugin_name) = rel_path.strip_prefix("plugins/") {
+                    (
+                        format!(
+                            "{}/{}{}",
+                            avalanche_ops::StorageNamespace::PluginsDir(id.to_string()).encode(),
+                            plugin_name,
+                            compress::Encoder::Zstd(3).ext()
+                        ),
+                        true,
+                    )
+                } else {
+                    (
+                        format!(
+                            "{}/{}",
+                            avalanche_ops::StorageNamespace::InstallDir(id.to_string()).encode(),
+                            rel_path
+                        ),
+                        false,
+                    )
+                }
+            }
+        };
+        artifacts.push(Artifact {
+            local_path,
+            s3_key,
+            compress,
+        });
+    }
+    Ok(artifacts)
+}
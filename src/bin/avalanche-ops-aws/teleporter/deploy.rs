@@ -0,0 +1,178 @@
+use std::io::{self, stdout, Error, ErrorKind};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::{avalanchego::api::eth, subnet_evm::teleporter},
+};
+
+pub const NAME: &str = "deploy";
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Deploys the TeleporterMessenger with the pre-signed release transaction to the blockchains")
+        .args(super::common_args())
+        .arg(
+            Arg::new("BLOCKCHAIN_IDS")
+                .long("blockchain-ids")
+                .help("Sets comma-separated subnet-evm blockchain IDs (warp enabled) to deploy to")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("DEPLOYMENT_DIR")
+                .long("deployment-dir")
+                .help("Sets the directory with the Teleporter release files (deployment transaction, deployer address, contract address)")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    http_rpc: &str,
+    private_key_path: &str,
+    blockchain_ids: &str,
+    deployment_dir: &str,
+    skip_prompt: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let deployment = teleporter::Deployment::load(deployment_dir)?;
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let k = crate::elastic_subnet::load_funding_key(&spec, private_key_path)?;
+    let mut rpc_urls = Vec::new();
+    for blockchain_id in blockchain_ids.split(',').map(str::trim) {
+        rpc_urls.push((
+            blockchain_id,
+            crate::subnet::get_blockchain_rpc(&spec, http_rpc, blockchain_id)?,
+        ));
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nDeploying TeleporterMessenger '{}' to {} blockchains (funding the deployer '{}' with '{}')\n",
+            deployment.contract_address,
+            rpc_urls.len(),
+            deployment.deployer_address,
+            k.eth_address
+        )),
+        ResetColor
+    )?;
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to deploy Teleporter!",
+            "Yes, let's deploy Teleporter!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'deploy' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    let rt = Runtime::new().unwrap();
+    for (blockchain_id, rpc_url) in rpc_urls.iter() {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print(format!("\n\n\nSTEP: deploy to {}\n", blockchain_id)),
+            ResetColor
+        )?;
+        if !rt
+            .block_on(eth::get_code(rpc_url, &deployment.contract_address))?
+            .is_empty()
+        {
+            println!("already deployed; skipping...");
+            continue;
+        }
+        // the pre-signed transaction only deploys at the address with the nonce 0
+        let deployer_nonce = rt.block_on(eth::get_transaction_count(
+            rpc_url,
+            &deployment.deployer_address,
+        ))?;
+        if deployer_nonce != 0 {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "deployer '{}' already used on {} (nonce {}) without the contract",
+                    deployment.deployer_address, blockchain_id, deployer_nonce
+                ),
+            ));
+        }
+
+        let balance = rt.block_on(eth::get_balance_wei(rpc_url, &deployment.deployer_address))?;
+        if balance < teleporter::DEPLOYER_FUNDING_WEI {
+            let (hash, _) = super::send_tx(
+                &rt,
+                rpc_url,
+                &k,
+                &deployment.deployer_address,
+                teleporter::DEPLOYER_FUNDING_WEI - balance,
+                Vec::new(),
+                super::TRANSFER_GAS,
+            )?;
+            info!("funded the deployer in {}", hash);
+        }
+
+        let hash = rt.block_on(eth::send_raw_transaction(rpc_url, &deployment.raw_tx))?;
+        rt.block_on(eth::wait_for_receipt_logs(
+            rpc_url,
+            &hash,
+            super::RECEIPT_TIMEOUT,
+        ))?;
+        if rt
+            .block_on(eth::get_code(rpc_url, &deployment.contract_address))?
+            .is_empty()
+        {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "deployment {} accepted but no contract at '{}'",
+                    hash, deployment.contract_address
+                ),
+            ));
+        }
+        println!("deployed in {}", hash);
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nDeployed TeleporterMessenger '{}', test with 'teleporter send'\n",
+            deployment.contract_address
+        )),
+        ResetColor
+    )?;
+    info!("'teleporter deploy' all success!");
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use std::{io, time::Duration};
+
+use clap::{Arg, Command};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::avalanche::{avalanchego::api::eth, coreth::tx, key};
+
+pub mod deploy;
+pub mod send;
+
+pub const NAME: &str = "teleporter";
+
+/// Max time for the transaction to be accepted on the blockchain.
+const RECEIPT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Gas of the plain value transfer.
+const TRANSFER_GAS: u64 = 21_000;
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Deploys and tests the Teleporter (Warp) cross-subnet messaging between the subnet-evm blockchains")
+        .subcommands(vec![deploy::subcommand(), send::subcommand()])
+}
+
+/// Returns the arguments shared by all "teleporter" sub-commands.
+fn common_args() -> Vec<Arg<'static>> {
+    vec![
+        Arg::new("LOG_LEVEL")
+            .long("log-level")
+            .short('l')
+            .help("Sets the log level")
+            .required(false)
+            .takes_value(true)
+            .possible_value("debug")
+            .possible_value("info")
+            .allow_invalid_utf8(false)
+            .default_value("info"),
+        Arg::new("SPEC_FILE_PATH")
+            .long("spec-file-path")
+            .short('s')
+            .help("The spec file to load")
+            .required(true)
+            .takes_value(true)
+            .allow_invalid_utf8(false),
+        Arg::new("HTTP_RPC")
+            .long("http-rpc")
+            .help("Sets the node API endpoint that serves the blockchains (default to the spec endpoint)")
+            .required(false)
+            .takes_value(true)
+            .allow_invalid_utf8(false),
+        Arg::new("PRIVATE_KEY_PATH")
+            .long("private-key-path")
+            .help("Sets the key path (CB58 'PrivateKey-' or hex) that pays for the transactions, or 'prefunded:N' for the N-th prefunded key in the spec")
+            .required(true)
+            .takes_value(true)
+            .allow_invalid_utf8(false),
+    ]
+}
+
+/// Signs and sends the legacy transaction, and waits for the successful receipt.
+/// Returns the transaction hash and the receipt logs.
+fn send_tx(
+    rt: &Runtime,
+    rpc_url: &str,
+    k: &key::Key,
+    to: &str,
+    value: u128,
+    data: Vec<u8>,
+    gas: u64,
+) -> io::Result<(String, Vec<eth::Log>)> {
+    let chain_id = rt.block_on(eth::chain_id(rpc_url))?;
+    let nonce = rt.block_on(eth::get_transaction_count(rpc_url, &k.eth_address))?;
+    let gas_price = rt.block_on(eth::gas_price(rpc_url))?;
+    let (raw, _) = tx::LegacyTx {
+        nonce,
+        gas_price,
+        gas,
+        to: tx::parse_address(to)?,
+        value,
+        data,
+    }
+    .sign(k, chain_id)?;
+    let hash = rt.block_on(eth::send_raw_transaction(rpc_url, &raw))?;
+    info!("sent {} to {}", hash, to);
+    let logs = rt.block_on(eth::wait_for_receipt_logs(rpc_url, &hash, RECEIPT_TIMEOUT))?;
+    Ok((hash, logs))
+}
@@ -0,0 +1,223 @@
+use std::{
+    io::{self, stdout, Error, ErrorKind},
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::{avalanchego::api::eth, coreth::tx, ids, subnet_evm::teleporter},
+};
+
+pub const NAME: &str = "send";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Sends the test messages between the blockchains, and verifies the delivery and the latency")
+        .long_about(
+            "
+Sends the test messages via the TeleporterMessenger on the source blockchain,
+and waits for the events of each message on the destination blockchain.
+The messages are delivered by the relayer (e.g., awm-relayer) running between
+the subnets, which this command does not run.
+
+",
+        )
+        .args(super::common_args())
+        .arg(
+            Arg::new("SOURCE_BLOCKCHAIN_ID")
+                .long("source-blockchain-id")
+                .help("Sets the blockchain ID to send from")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("DESTINATION_BLOCKCHAIN_ID")
+                .long("destination-blockchain-id")
+                .help("Sets the blockchain ID to deliver to")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("TELEPORTER_ADDRESS")
+                .long("teleporter-address")
+                .help("Sets the TeleporterMessenger address (same on both blockchains)")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("DESTINATION_ADDRESS")
+                .long("destination-address")
+                .help("Sets the receiver address on the destination (default to the sending key)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("COUNT")
+                .long("count")
+                .help("Sets the number of test messages to send one by one")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("1"),
+        )
+        .arg(
+            Arg::new("TIMEOUT_SECONDS")
+                .long("timeout-seconds")
+                .help("Sets the max time for each message to be delivered")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("60"),
+        )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    http_rpc: &str,
+    private_key_path: &str,
+    source_blockchain_id: &str,
+    destination_blockchain_id: &str,
+    teleporter_address: &str,
+    destination_address: Option<&str>,
+    count: u64,
+    timeout: Duration,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let k = crate::elastic_subnet::load_funding_key(&spec, private_key_path)?;
+    let source_rpc = crate::subnet::get_blockchain_rpc(&spec, http_rpc, source_blockchain_id)?;
+    let destination_rpc =
+        crate::subnet::get_blockchain_rpc(&spec, http_rpc, destination_blockchain_id)?;
+    let destination_id = ids::parse_id(destination_blockchain_id)?;
+    let destination_address =
+        tx::parse_address(destination_address.unwrap_or(k.eth_address.as_str()))?;
+    tx::parse_address(teleporter_address)?;
+
+    let rt = Runtime::new().unwrap();
+    for rpc_url in [&source_rpc, &destination_rpc] {
+        if rt
+            .block_on(eth::get_code(rpc_url, teleporter_address))?
+            .is_empty()
+        {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "no TeleporterMessenger '{}' on {} (run 'teleporter deploy' first)",
+                    teleporter_address, rpc_url
+                ),
+            ));
+        }
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\n\n\nSTEP: send {} test messages from {} to {}\n",
+            count, source_blockchain_id, destination_blockchain_id
+        )),
+        ResetColor
+    )?;
+    let mut deliveries = Vec::new();
+    for seq in 0..count {
+        let from_block = rt.block_on(eth::block_number(&destination_rpc))?;
+        let start = Instant::now();
+        let (source_tx, logs) = super::send_tx(
+            &rt,
+            &source_rpc,
+            &k,
+            teleporter_address,
+            0,
+            teleporter::send_cross_chain_message_calldata(
+                &destination_id,
+                &destination_address,
+                teleporter::DEFAULT_REQUIRED_GAS_LIMIT,
+                &teleporter::test_message(seq, &Utc::now().to_rfc3339()),
+            ),
+            teleporter::SEND_GAS,
+        )?;
+        let message_id =
+            teleporter::message_id_from_logs(&logs, teleporter_address, &destination_id)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("no SendCrossChainMessage event in {}", source_tx),
+                    )
+                })?;
+        info!("sent message {} in {}", message_id, source_tx);
+
+        let mut latency_ms = None;
+        while start.elapsed() < timeout {
+            let delivered = rt.block_on(eth::get_logs(
+                &destination_rpc,
+                teleporter_address,
+                &teleporter::delivery_topics(&message_id),
+                from_block,
+            ))?;
+            if !delivered.is_empty() {
+                latency_ms = Some(start.elapsed().as_millis() as u64);
+                break;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        println!(
+            "message {} ({}): {}",
+            seq,
+            message_id,
+            match latency_ms {
+                Some(v) => format!("delivered in {} ms", v),
+                None => format!("not delivered in {:?}", timeout),
+            }
+        );
+        deliveries.push(teleporter::Delivery {
+            seq,
+            message_id,
+            source_tx,
+            delivered: latency_ms.is_some(),
+            latency_ms,
+        });
+    }
+
+    let report = teleporter::Report::new(
+        source_blockchain_id,
+        destination_blockchain_id,
+        teleporter_address,
+        deliveries,
+    );
+    println!("\n{}", report.encode_yaml()?);
+    if report.delivered < report.sent {
+        return Err(Error::new(
+            ErrorKind::TimedOut,
+            format!(
+                "{} of {} messages not delivered (is the relayer running between the subnets?)",
+                report.sent - report.delivered,
+                report.sent
+            ),
+        ));
+    }
+
+    info!("'teleporter send' all success!");
+    Ok(())
+}
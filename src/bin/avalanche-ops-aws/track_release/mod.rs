@@ -0,0 +1,356 @@
+use std::{
+    fs,
+    io::{self, stdout, Error, ErrorKind, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use dialoguer::{theme::ColorfulTheme, Select};
+use log::{info, warn};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    self,
+    avalanche::avalanchego::release,
+    aws::{self, s3},
+    utils::{compress, home_dir, http, provenance, random},
+};
+
+pub const NAME: &str = "track-release";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Stages the latest avalanchego release matching the spec constraint (e.g., run with cron)")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load and update")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("ARCH")
+                .long("arch")
+                .help("Sets the release architecture to download")
+                .required(false)
+                .takes_value(true)
+                .possible_value("amd64")
+                .possible_value("arm64")
+                .allow_invalid_utf8(false)
+                .default_value("amd64"),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("FORCE")
+                .long("force")
+                .help("Runs even outside of the maintenance window in the spec")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(crate::quorum::skip_quorum_check_arg())
+}
+
+pub fn execute(
+    log_level: &str,
+    spec_file_path: &str,
+    arch: &str,
+    skip_prompt: bool,
+    force: bool,
+    skip_quorum_check: bool,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let mut spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    spec.validate()?;
+    let mut avalanchego_release = spec.avalanchego_release.clone().ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            "no 'avalanchego_release' found in spec",
+        )
+    })?;
+    let constraint = release::Constraint::parse(&avalanchego_release.version_constraint)?;
+    let staged_version = match &avalanchego_release.staged_version {
+        Some(v) => Some(release::Version::parse(v)?),
+        None => None,
+    };
+
+    let rt = Runtime::new().unwrap();
+    let releases = rt.block_on(release::fetch_releases())?;
+    let (latest, version) =
+        match release::latest_matching(&releases, &constraint, staged_version.as_ref()) {
+            Some(v) => v,
+            None => {
+                info!(
+                    "no new release matching '{}' (staged version {:?})",
+                    avalanchego_release.version_constraint, avalanchego_release.staged_version
+                );
+                return Ok(());
+            }
+        };
+    let asset = latest.linux_asset(arch).ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("no linux-{} asset found for {}", arch, latest.tag_name),
+        )
+    })?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nFound new avalanchego release {} (staged {:?}, auto upgrade {})\n",
+            version, avalanchego_release.staged_version, avalanchego_release.auto_upgrade
+        )),
+        ResetColor
+    )?;
+    if !skip_prompt {
+        let options = &[
+            "No, I am not ready to stage the release!",
+            "Yes, let's stage the release!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'track-release' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "\n\n\nSTEP: downloading {}\n",
+            asset.browser_download_url
+        )),
+        ResetColor
+    )?;
+    let b = rt.block_on(http::get_https_following_redirects(
+        &asset.browser_download_url,
+        std::time::Duration::from_secs(300),
+    ))?;
+    let tmp_archive_path = random::tmp_path(15, Some(".tar.gz"))?;
+    let mut f = fs::File::create(&tmp_archive_path)?;
+    f.write_all(&b)?;
+
+    // e.g., "avalanchego-v1.7.10/avalanchego" and "avalanchego-v1.7.10/plugins/evm"
+    let release_dir = home_dir::named(&spec.id, Some(format!("-avalanchego-{}", version).as_str()));
+    compress::unpack_directory(
+        &tmp_archive_path,
+        &release_dir,
+        compress::DirDecoder::TarGzip,
+    )?;
+    fs::remove_file(&tmp_archive_path)?;
+    let unpacked_dir = Path::new(&release_dir).join(format!("avalanchego-{}", latest.tag_name));
+    let avalanche_bin = unpacked_dir.join("avalanchego");
+    let avalanche_bin = avalanche_bin.to_str().unwrap().to_string();
+    let plugins_dir = unpacked_dir.join("plugins");
+    let plugins_dir = plugins_dir.to_str().unwrap().to_string();
+    if !Path::new(&avalanche_bin).exists() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("avalanchego binary not found in {}", release_dir),
+        ));
+    }
+
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    let s3_manager = s3::Manager::new(&shared_config);
+    let s3_bucket = Arc::new(aws_resources.s3_bucket.clone());
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!("\n\n\nSTEP: staging {} in S3\n", version)),
+        ResetColor
+    )?;
+    let staging_dir =
+        avalanche_ops::StorageNamespace::ReleaseStagingDir(spec.id.clone(), version.to_string())
+            .encode();
+
+    // (staged S3 key, S3 key to copy to for the update event)
+    let mut staged: Vec<(String, String)> = Vec::new();
+    let avalanche_bin_provenance = spec.install_artifacts.verify_local(&avalanche_bin)?;
+    let tmp_compressed_path = random::tmp_path(15, Some(compress::Encoder::Zstd(3).ext()))?;
+    compress::pack_file(
+        &avalanche_bin,
+        &tmp_compressed_path,
+        compress::Encoder::Zstd(3),
+    )?;
+    let s3_key = format!(
+        "{}/avalanche{}",
+        staging_dir,
+        compress::Encoder::Zstd(3).ext()
+    );
+    rt.block_on(s3_manager.put_object_with_metadata(
+        Arc::new(tmp_compressed_path.clone()),
+        s3_bucket.clone(),
+        Arc::new(s3_key.clone()),
+        Some(avalanche_bin_provenance.to_metadata()),
+    ))
+    .expect("failed put_object compressed avalanche_bin");
+    fs::remove_file(&tmp_compressed_path)?;
+    staged.push((
+        s3_key,
+        avalanche_ops::StorageNamespace::EventsUpdateArtifactsInstallDirAvalancheBinCompressed(
+            spec.id.clone(),
+        )
+        .encode(),
+    ));
+    if Path::new(&plugins_dir).exists() {
+        for entry in fs::read_dir(&plugins_dir)? {
+            let entry = entry?;
+            let entry_path = entry.path();
+            let file_path = entry_path.to_str().unwrap();
+            let file_name = entry.file_name();
+            let file_name = file_name.as_os_str().to_str().unwrap();
+            if provenance::is_provenance_file(file_name) {
+                continue;
+            }
+            let plugin_provenance = spec.install_artifacts.verify_local(file_path)?;
+
+            compress::pack_file(file_path, &tmp_compressed_path, compress::Encoder::Zstd(3))?;
+            let s3_key = format!(
+                "{}/plugins/{}{}",
+                staging_dir,
+                file_name,
+                compress::Encoder::Zstd(3).ext()
+            );
+            rt.block_on(s3_manager.put_object_with_metadata(
+                Arc::new(tmp_compressed_path.clone()),
+                s3_bucket.clone(),
+                Arc::new(s3_key.clone()),
+                Some(plugin_provenance.to_metadata()),
+            ))
+            .expect("failed put_object compressed plugin");
+            fs::remove_file(&tmp_compressed_path)?;
+            staged.push((
+                s3_key,
+                format!(
+                    "{}/{}{}",
+                    avalanche_ops::StorageNamespace::EventsUpdateArtifactsInstallDirPluginsDir(
+                        spec.id.clone()
+                    )
+                    .encode(),
+                    file_name,
+                    compress::Encoder::Zstd(3).ext()
+                ),
+            ));
+        }
+    }
+
+    avalanchego_release.staged_version = Some(version.to_string());
+    spec.avalanchego_release = Some(avalanchego_release.clone());
+    spec.sync(spec_file_path)?;
+    rt.block_on(s3_manager.put_bytes(
+        spec.encode_state_yaml()?.into_bytes(),
+        s3_bucket.clone(),
+        Arc::new(avalanche_ops::StorageNamespace::ConfigFile(spec.id.clone()).encode()),
+    ))
+    .expect("failed put_object ConfigFile");
+
+    // staging is always allowed, but restarting the nodes is not
+    let auto_upgrade = avalanchego_release.auto_upgrade
+        && match spec
+            .check_maintenance_window("track-release", force)
+            .and_then(|_| crate::quorum::check(&spec, "track-release", skip_quorum_check))
+        {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("{}, skipping auto upgrade", e);
+                false
+            }
+        };
+    if auto_upgrade {
+        execute!(
+            stdout(),
+            SetForegroundColor(Color::Green),
+            Print(format!(
+                "\n\n\nSTEP: auto-upgrading the network to {}\n",
+                version
+            )),
+            ResetColor
+        )?;
+        for (src, dst) in staged.iter() {
+            rt.block_on(s3_manager.copy_object(
+                s3_bucket.clone(),
+                Arc::new(src.clone()),
+                s3_bucket.clone(),
+                Arc::new(dst.clone()),
+            ))
+            .expect("failed copy_object staged release");
+        }
+        rt.block_on(
+            s3_manager.put_bytes(
+                spec.encode_state_yaml()?.into_bytes(),
+                s3_bucket,
+                Arc::new(
+                    avalanche_ops::StorageNamespace::EventsUpdateArtifactsEvent(spec.id.clone())
+                        .encode(),
+                ),
+            ),
+        )
+        .expect("failed put_object EventsUpdateArtifactsEvent");
+    } else {
+        let exec_path = std::env::current_exe().expect("unexpected None current_exe");
+        println!("\n# upgrade plan for {}", version);
+        println!(
+            "# staged in 's3://{}/{}'",
+            aws_resources.s3_bucket, staging_dir
+        );
+        println!(
+            "{} events update-artifacts --spec-file-path {} --install-artifacts-avalanche-bin {} --install-artifacts-plugins-dir {}\n",
+            exec_path.display(),
+            spec_file_path,
+            avalanche_bin,
+            plugins_dir,
+        );
+    }
+
+    println!();
+    info!("'track-release' all success!");
+    Ok(())
+}
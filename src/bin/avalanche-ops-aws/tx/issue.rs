@@ -0,0 +1,95 @@
+use std::io::{self, stdout, Error, ErrorKind};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::info;
+use tokio::runtime::Runtime;
+
+use avalanche_ops::avalanche::{
+    constants,
+    platformvm::{multisig, wallet},
+};
+
+pub const NAME: &str = "issue";
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Issues the fully signed transaction")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("TX_FILE_PATH")
+                .long("tx-file-path")
+                .help("Sets the signed transaction file to issue")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("HTTP_RPC")
+                .long("http-rpc")
+                .help("Sets the P-chain API endpoint (default to the public API for Fuji/mainnet)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(log_level: &str, tx_file_path: &str, http_rpc: &str) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let tx = multisig::PartiallySignedTx::load(tx_file_path)?;
+    if !tx.is_complete() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "tx still requires signatures from {:?}",
+                tx.missing_signers()
+            ),
+        ));
+    }
+
+    let http_rpc = {
+        if !http_rpc.is_empty() {
+            http_rpc.to_string()
+        } else {
+            match constants::NETWORK_ID_TO_PUBLIC_API_ENDPOINT.get(&tx.network_id) {
+                Some(v) => v.to_string(),
+                None => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("no '--http-rpc' for network ID {}", tx.network_id),
+                    ));
+                }
+            }
+        }
+    };
+
+    let rt = Runtime::new().unwrap();
+    let tx_id = rt.block_on(wallet::issue_signed(&http_rpc, &tx))?;
+
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!("\nIssued tx {} via {}\n", tx_id, http_rpc)),
+        ResetColor
+    )?;
+    info!("'tx issue' all success!");
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use std::io::{self, stdout};
+
+use clap::{Arg, ArgMatches, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{
+    avalanche::{
+        key,
+        platformvm::{
+            multisig,
+            wallet::{self, Issued},
+        },
+    },
+    network_params::NetworkParams,
+    utils::home_dir,
+};
+
+pub mod issue;
+pub mod sign;
+
+pub const NAME: &str = "tx";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about(
+            "Signs and issues the partially signed P-chain transactions for multi-signature keys",
+        )
+        .subcommands(vec![sign::subcommand(), issue::subcommand()])
+}
+
+/// Returns the multi-signature arguments for the sub-commands that issue transactions.
+pub fn multisig_args() -> Vec<Arg<'static>> {
+    vec![
+        Arg::new("SIGNERS")
+            .long("signers")
+            .help("Sets comma-separated P-chain addresses of the co-signers (e.g., N-of-M subnet control keys)")
+            .required(false)
+            .takes_value(true)
+            .allow_invalid_utf8(false),
+        Arg::new("FUNDING_THRESHOLD")
+            .long("funding-threshold")
+            .help("Sets to spend the N-of-M UTXOs owned by the signers, with this threshold")
+            .required(false)
+            .takes_value(true)
+            .allow_invalid_utf8(false),
+        Arg::new("PARTIAL_TX_DIR")
+            .long("partial-tx-dir")
+            .help("Sets the directory to export the partially signed transactions (default to '$HOME')")
+            .required(false)
+            .takes_value(true)
+            .allow_invalid_utf8(false),
+    ]
+}
+
+/// Represents the multi-signature options for the commands that issue transactions.
+pub struct MultisigOpts {
+    pub signers: String,
+    pub funding_threshold: Option<u32>,
+    pub partial_tx_dir: String,
+}
+
+impl MultisigOpts {
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        Self {
+            signers: matches.value_of("SIGNERS").unwrap_or("").to_string(),
+            funding_threshold: matches.value_of("FUNDING_THRESHOLD").map(|s| {
+                s.parse::<u32>()
+                    .expect("invalid value for 'FUNDING_THRESHOLD'")
+            }),
+            partial_tx_dir: matches.value_of("PARTIAL_TX_DIR").unwrap_or("").to_string(),
+        }
+    }
+}
+
+/// Loads the wallet with the co-signers if any.
+pub fn load_wallet(
+    rt: &Runtime,
+    k: key::Key,
+    network_id: u32,
+    http_rpc: &str,
+    opts: &MultisigOpts,
+) -> io::Result<wallet::Wallet> {
+    let w = rt.block_on(wallet::Wallet::new(k, network_id, http_rpc))?;
+    if opts.signers.is_empty() && opts.funding_threshold.is_none() {
+        return Ok(w);
+    }
+    let signers = multisig::parse_addresses(&opts.signers)?;
+    w.with_multisig(&signers, opts.funding_threshold)
+}
+
+/// Fetches the live staking parameters of the network, and validates the
+/// staking amounts and durations in the spec before any transaction.
+pub fn load_network_params(
+    rt: &Runtime,
+    spec: &avalanche_ops::Spec,
+    http_rpc: &str,
+) -> io::Result<NetworkParams> {
+    let params = rt.block_on(NetworkParams::fetch(
+        http_rpc,
+        spec.avalanchego_config.network_id,
+    ))?;
+    params.validate_spec(spec)?;
+    Ok(params)
+}
+
+/// Returns the transaction ID if committed. Otherwise, exports the partially
+/// signed transaction as "[SPEC ID]-[NAME].partial-tx.json" for the co-signers.
+pub fn handle_issued(
+    issued: Issued,
+    spec_id: &str,
+    name: &str,
+    opts: &MultisigOpts,
+) -> io::Result<Option<String>> {
+    let partial = match issued {
+        Issued::Committed(tx_id) => return Ok(Some(tx_id)),
+        Issued::PartiallySigned(v) => v,
+    };
+
+    let file_name = format!("{}-{}", spec_id, name);
+    let file_path = {
+        if opts.partial_tx_dir.is_empty() {
+            home_dir::named(&file_name, Some(".partial-tx.json"))
+        } else {
+            std::path::Path::new(&opts.partial_tx_dir)
+                .join(format!("{}.partial-tx.json", file_name))
+                .to_str()
+                .unwrap()
+                .to_string()
+        }
+    };
+    partial.sync(&file_path)?;
+
+    let exec_path = std::env::current_exe().expect("unexpected None current_exe");
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Magenta),
+        Print(format!(
+            "\nExported partially signed tx to '{}'\nsend to the co-signers {:?}, and run:\n",
+            file_path,
+            partial.missing_signers()
+        )),
+        ResetColor
+    )?;
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Green),
+        Print(format!(
+            "{} tx sign \\\n--tx-file-path {} \\\n--private-key-path [CO_SIGNER_KEY_PATH]\n\n{} tx issue \\\n--tx-file-path {}\n",
+            exec_path.display(),
+            file_path,
+            exec_path.display(),
+            file_path,
+        )),
+        ResetColor
+    )?;
+    Ok(None)
+}
@@ -0,0 +1,78 @@
+use std::io::{self, stdout};
+
+use clap::{Arg, Command};
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use log::info;
+
+use avalanche_ops::avalanche::platformvm::multisig;
+
+pub const NAME: &str = "sign";
+
+pub fn subcommand() -> Command<'static> {
+    Command::new(NAME)
+        .about("Adds the signatures of the key to the partially signed transaction file")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("TX_FILE_PATH")
+                .long("tx-file-path")
+                .help("Sets the partially signed transaction file to load and update")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("PRIVATE_KEY_PATH")
+                .long("private-key-path")
+                .help("Sets the file path of the private key (CB58 'PrivateKey-' or hex) to sign with")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(log_level: &str, tx_file_path: &str, private_key_path: &str) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let mut tx = multisig::PartiallySignedTx::load(tx_file_path)?;
+    let k = crate::elastic_subnet::load_key(private_key_path)?;
+    let added = tx.sign(&k)?;
+    tx.sync(tx_file_path)?;
+
+    let missing = tx.missing_signers();
+    execute!(
+        stdout(),
+        SetForegroundColor(Color::Blue),
+        Print(format!(
+            "\nAdded {} signatures from {} to '{}'\n",
+            added,
+            k.address("P", tx.network_id)?,
+            tx_file_path
+        )),
+        ResetColor
+    )?;
+    if missing.is_empty() {
+        println!("all signatures collected, ready to issue");
+    } else {
+        println!("still requires signatures from {:?}", missing);
+    }
+
+    info!("'tx sign' all success!");
+    Ok(())
+}
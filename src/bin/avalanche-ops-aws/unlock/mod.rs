@@ -0,0 +1,95 @@
+use std::io::{self, Error, ErrorKind};
+
+use clap::{Arg, Command};
+use dialoguer::{theme::ColorfulTheme, Select};
+use tokio::runtime::Runtime;
+
+use avalanche_ops::{self, aws};
+
+pub const NAME: &str = "unlock";
+
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Shows and force-releases the cluster lock (e.g., left by a crashed 'apply')")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("SPEC_FILE_PATH")
+                .long("spec-file-path")
+                .short('s')
+                .help("The spec file to load")
+                .required(true)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("SKIP_PROMPT")
+                .long("skip-prompt")
+                .short('p')
+                .help("Skips prompt mode")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub fn execute(log_level: &str, spec_file_path: &str, skip_prompt: bool) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let spec = avalanche_ops::Spec::load(spec_file_path).expect("failed to load spec");
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+
+    let rt = Runtime::new().unwrap();
+    let shared_config = rt
+        .block_on(aws::load_config(Some(aws_resources.region.clone())))
+        .expect("failed to aws::load_config");
+    let lock_manager = aws::lock::Manager::new(&shared_config);
+
+    let lock = rt
+        .block_on(lock_manager.get(&spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    match lock {
+        Some(lock) => println!("{}", lock),
+        None => {
+            println!("cluster '{}' is not locked", spec.id);
+            return Ok(());
+        }
+    }
+
+    if !skip_prompt {
+        let options = &[
+            "No, the operation may still be running!",
+            "Yes, let's release the lock!",
+        ];
+        let selected = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select your 'unlock' option")
+            .items(&options[..])
+            .default(0)
+            .interact()
+            .unwrap();
+        if selected == 0 {
+            return Ok(());
+        }
+    }
+
+    rt.block_on(lock_manager.force_release(&spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    println!("released the lock of cluster '{}'", spec.id);
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use std::{fs, io, path::Path, sync::Arc};
+use std::{fs, io, path::Path};
 
 use clap::{Arg, Command};
 use log::info;
@@ -77,6 +77,15 @@ pub fn subcommand() -> Command<'static> {
                 .takes_value(true)
                 .allow_invalid_utf8(false),
         )
+        .arg(
+            Arg::new("MAX_BYTES_PER_SECOND")
+                .long("max-bytes-per-second")
+                .help("Sets the average download throughput limit in bytes per second (0 for no limit)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("0"),
+        )
 }
 
 pub fn execute(
@@ -86,6 +95,7 @@ pub fn execute(
     s3_bucket: &str,
     s3_key: &str,
     unpack_dir: &str,
+    max_bytes_per_second: u64,
 ) -> io::Result<()> {
     // ref. https://github.com/env-logger-rs/env_logger/issues/47
     env_logger::init_from_env(
@@ -103,7 +113,13 @@ pub fn execute(
     let shared_config = rt
         .block_on(aws::load_config(Some(reg.to_string())))
         .unwrap();
-    let s3_manager = s3::Manager::new(&shared_config);
+    let mut s3_manager = s3::Manager::new(&shared_config);
+    if max_bytes_per_second > 0 {
+        s3_manager.set_transfer_limit(s3::TransferLimit {
+            upload_bytes_per_second: None,
+            download_bytes_per_second: Some(max_bytes_per_second),
+        });
+    }
 
     let dec = compress::DirDecoder::new(decompression_unarchive_method)?;
 
@@ -116,12 +132,13 @@ pub fn execute(
         "STEP: downloading from S3 {} {} to {}",
         s3_bucket, s3_key, tmp_file_path
     );
-    rt.block_on(s3_manager.get_object(
-        Arc::new(s3_bucket.to_string()),
-        Arc::new(s3_key.to_string()),
-        Arc::new(tmp_file_path.to_string()),
+    rt.block_on(s3_manager.get_object_resumable(
+        s3_bucket,
+        s3_key,
+        tmp_file_path,
+        s3::DEFAULT_DOWNLOAD_MAX_ATTEMPTS,
     ))
-    .expect("failed get_object backup");
+    .expect("failed get_object_resumable backup");
 
     info!(
         "STEP: unpack backup {} to {} with {}",
@@ -1,4 +1,4 @@
-use std::{fs, io, path::Path, sync::Arc};
+use std::{fs, io, path::Path, sync::Arc, time::SystemTime};
 
 use clap::{Arg, Command};
 use log::info;
@@ -6,6 +6,7 @@ use tokio::runtime::Runtime;
 
 use avalanche_ops::{
     self,
+    avalanche::heartbeat,
     aws::{self, s3},
     utils::{compress, random},
 };
@@ -82,8 +83,36 @@ pub fn subcommand() -> Command<'static> {
                 .takes_value(true)
                 .allow_invalid_utf8(false),
         )
+        .arg(
+            Arg::new("REPLICA_REGION")
+                .long("replica-region")
+                .help("Sets the AWS region of the replica bucket to also upload to")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("REPLICA_S3_BUCKET")
+                .long("replica-s3-bucket")
+                .help("Sets the S3 bucket name in the replica region to also upload to")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("MAX_BYTES_PER_SECOND")
+                .long("max-bytes-per-second")
+                .help(
+                    "Sets the average upload throughput limit in bytes per second (0 for no limit)",
+                )
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false)
+                .default_value("0"),
+        )
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute(
     reg: &str,
     log_level: &str,
@@ -91,6 +120,9 @@ pub fn execute(
     pack_dir: &str,
     s3_bucket: &str,
     s3_key: &str,
+    replica_reg: &str,
+    replica_s3_bucket: &str,
+    max_bytes_per_second: u64,
 ) -> io::Result<()> {
     // ref. https://github.com/env-logger-rs/env_logger/issues/47
     env_logger::init_from_env(
@@ -108,7 +140,16 @@ pub fn execute(
     let shared_config = rt
         .block_on(aws::load_config(Some(reg.to_string())))
         .unwrap();
-    let s3_manager = s3::Manager::new(&shared_config);
+    let transfer_limit = s3::TransferLimit {
+        upload_bytes_per_second: if max_bytes_per_second > 0 {
+            Some(max_bytes_per_second)
+        } else {
+            None
+        },
+        download_bytes_per_second: None,
+    };
+    let mut s3_manager = s3::Manager::new(&shared_config);
+    s3_manager.set_transfer_limit(transfer_limit.clone());
 
     let enc = compress::DirEncoder::new(archive_compression_method)?;
     info!("STEP: backup {} with {}", pack_dir, enc.to_string());
@@ -126,8 +167,36 @@ pub fn execute(
         Arc::new(s3_key.to_string()),
     ))
     .unwrap();
+
+    if !replica_reg.is_empty() && !replica_s3_bucket.is_empty() {
+        info!(
+            "STEP: upload output {} to S3 in replica region {}",
+            tmp_file_path, replica_reg
+        );
+        let replica_shared_config = rt
+            .block_on(aws::load_config(Some(replica_reg.to_string())))
+            .unwrap();
+        let mut replica_s3_manager = s3::Manager::new(&replica_shared_config);
+        replica_s3_manager.set_transfer_limit(transfer_limit);
+        rt.block_on(replica_s3_manager.put_object(
+            Arc::new(tmp_file_path.to_string()),
+            Arc::new(replica_s3_bucket.to_string()),
+            Arc::new(s3_key.to_string()),
+        ))
+        .unwrap();
+    }
     fs::remove_file(tmp_file_path)?;
 
+    // reported in the heartbeat
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    if let Some(dir) = Path::new(heartbeat::LAST_BACKUP_FILE_PATH).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(heartbeat::LAST_BACKUP_FILE_PATH, format!("{}", now))?;
+
     info!("'avalanched backup upload' all success!");
     Ok(())
 }
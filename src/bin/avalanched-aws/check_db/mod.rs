@@ -0,0 +1,185 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind},
+    path::Path,
+    sync::Arc,
+    time::Instant,
+};
+
+use chrono::Utc;
+use clap::{Arg, Command};
+use log::{info, warn};
+
+use avalanche_ops::{
+    self,
+    aws::{ec2, s3},
+    db_check,
+    utils::{bash, random},
+};
+
+use crate::install;
+
+pub const NAME: &str = "check-db";
+
+/// Verifies the avalanchego database offline, either the live database
+/// with avalanchego stopped, or the database on a cloned volume,
+/// and uploads the report to S3.
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Verifies the Avalanche database offline and reports the corruptions to S3")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("DB_DIR")
+                .long("db-dir")
+                .help("Sets the database directory to check (default the live 'avalanchego_config.db_dir', e.g., the mounted cloned volume)")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("STOP_NODE")
+                .long("stop-node")
+                .help("Stops avalanchego during the check of the live database, and starts it afterwards")
+                .required(false)
+                .takes_value(false)
+                .allow_invalid_utf8(false),
+        )
+        .arg(
+            Arg::new("REPORT_NAME")
+                .long("report-name")
+                .help("Sets the report name in S3 (default '[TIMESTAMP]-[INSTANCE_ID]')")
+                .required(false)
+                .takes_value(true)
+                .allow_invalid_utf8(false),
+        )
+}
+
+pub async fn execute(
+    log_level: &str,
+    db_dir: Option<&str>,
+    stop_node: bool,
+    report_name: Option<&str>,
+) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let (shared_config, tags) = install::load_local_instance().await;
+    let id = install::required_tag(&tags, "ID");
+    let s3_bucket = install::required_tag(&tags, "S3_BUCKET_NAME");
+    let machine_id = ec2::fetch_instance_id()
+        .await
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    let s3_manager = s3::Manager::new(&shared_config);
+
+    info!("STEP: downloading avalanche-ops::Spec from S3");
+    let tmp_spec_file_path = random::tmp_path(15, Some(".yaml"))?;
+    s3::spawn_get_object(
+        s3_manager.clone(),
+        &s3_bucket,
+        &avalanche_ops::StorageNamespace::ConfigFile(id.clone()).encode(),
+        &tmp_spec_file_path,
+    )
+    .await
+    .map_err(|e| Error::new(ErrorKind::Other, format!("failed spawn_get_object {}", e)))?;
+    let spec = avalanche_ops::Spec::load(&tmp_spec_file_path)?;
+    fs::remove_file(&tmp_spec_file_path)?;
+
+    let live_db_dir = spec.avalanchego_config.db_dir.clone();
+    let db_dir = db_dir.unwrap_or(&live_db_dir).to_string();
+    let source = if Path::new(&db_dir) == Path::new(&live_db_dir) {
+        db_check::Source::Stopped
+    } else {
+        db_check::Source::Cloned
+    };
+    // LevelDB files change under the running node, so the live database
+    // is only checked with avalanchego stopped
+    if source == db_check::Source::Stopped
+        && !stop_node
+        && bash::run("systemctl is-active --quiet avalanche.service").is_ok()
+    {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "database '{}' is in use by avalanche.service (use '--stop-node', or '--db-dir' of the cloned volume)",
+                db_dir
+            ),
+        ));
+    }
+
+    let started_at = Utc::now();
+    let stopped = source == db_check::Source::Stopped && stop_node;
+    if stopped {
+        warn!("STEP: stopping avalanche.service for the check");
+        fs::create_dir_all(Path::new(db_check::IN_PROGRESS_FILE_PATH).parent().unwrap())?;
+        fs::write(db_check::IN_PROGRESS_FILE_PATH, started_at.to_rfc3339())?;
+        bash::run("sudo systemctl stop avalanche.service")?;
+    }
+
+    info!("STEP: checking the database '{}' ({:?})", db_dir, source);
+    let start = Instant::now();
+    let scanned = db_check::scan(&db_dir);
+
+    // start the node even if the check failed
+    if stopped {
+        info!("STEP: starting avalanche.service");
+        bash::run("sudo systemctl start --no-block avalanche.service")?;
+        fs::remove_file(db_check::IN_PROGRESS_FILE_PATH)?;
+    }
+    let scanned = scanned?;
+
+    let report = db_check::Report {
+        id: id.clone(),
+        machine_id: machine_id.clone(),
+        db_dir,
+        source,
+        started_at: started_at.to_rfc3339(),
+        elapsed_seconds: start.elapsed().as_secs(),
+        scan: scanned,
+    };
+    let report_name = match report_name {
+        Some(v) => v.to_string(),
+        None => format!("{}-{}", started_at.format("%Y%m%d%H%M%S"), machine_id),
+    };
+    let s3_key = db_check::Report::s3_key(&id, &report_name);
+    info!("STEP: uploading the report to {}", s3_key);
+    s3_manager
+        .put_bytes(
+            report.encode_json()?.into_bytes(),
+            Arc::new(s3_bucket),
+            Arc::new(s3_key),
+        )
+        .await
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed put_bytes {}", e)))?;
+
+    info!(
+        "checked {} databases, {} files ({} bytes) in {} seconds",
+        report.scan.databases, report.scan.files, report.scan.bytes, report.elapsed_seconds
+    );
+    if !report.is_healthy() {
+        for f in report.scan.findings.iter() {
+            warn!("{:?} '{}': {}", f.kind, f.path, f.detail);
+        }
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "found {} corruptions in '{}'",
+                report.scan.findings.len(),
+                report.db_dir
+            ),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,373 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Error, ErrorKind},
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    sync::Arc,
+    time::SystemTime,
+};
+
+use aws_types::SdkConfig as AwsSdkConfig;
+use clap::{Arg, Command};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use avalanche_ops::{
+    self,
+    aws::{self, ec2, s3},
+    network_tuning,
+    utils::{bash, compress, provenance, random},
+};
+
+pub const NAME: &str = "install";
+
+/// Written once the install phase completes, so that "run" on the baked
+/// AMI finds the artifacts and the tunings already in place.
+pub const INSTALLED_FILE_PATH: &str = "/var/lib/avalanched/installed";
+
+/// Runs the one-time setup (downloads and verifies the artifacts,
+/// applies the network tunings) without starting any service,
+/// so the instance can be imaged or handed over to "run".
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about(
+            "Installs the Avalanche artifacts and system tunings on AWS, without running the agent",
+        )
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+}
+
+/// Represents the install marker file.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Installed {
+    pub id: String,
+    pub avalanched_version: String,
+    pub artifacts: Vec<String>,
+    /// Unix time in seconds.
+    pub timestamp: u64,
+}
+
+pub async fn execute(log_level: &str) {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let (shared_config, tags) = load_local_instance().await;
+    let id = required_tag(&tags, "ID");
+    let s3_bucket = required_tag(&tags, "S3_BUCKET_NAME");
+    let avalanche_bin_path = required_tag(&tags, "AVALANCHE_BIN_PATH");
+    let s3_manager = s3::Manager::new(&shared_config);
+
+    let downloaded_artifacts =
+        download_artifacts(&s3_manager, &s3_bucket, &id, &avalanche_bin_path).await;
+
+    info!("STEP: downloading avalanche-ops::Spec from S3");
+    let tmp_spec_file_path = random::tmp_path(15, Some(".yaml")).unwrap();
+    s3::spawn_get_object(
+        s3_manager.clone(),
+        &s3_bucket,
+        &avalanche_ops::StorageNamespace::ConfigFile(id.clone()).encode(),
+        &tmp_spec_file_path,
+    )
+    .await
+    .expect("failed s3::spawn_get_object");
+    let spec = avalanche_ops::Spec::load(&tmp_spec_file_path).unwrap();
+    fs::remove_file(&tmp_spec_file_path).expect("failed fs::remove_file");
+
+    verify_artifacts(
+        &s3_manager,
+        &s3_bucket,
+        &downloaded_artifacts,
+        &get_plugins_dir(&avalanche_bin_path),
+        &spec.install_artifacts,
+    )
+    .await;
+    apply_network_tuning(spec.machine.network_tuning.as_ref());
+
+    info!("STEP: writing install marker '{}'", INSTALLED_FILE_PATH);
+    let installed = Installed {
+        id,
+        avalanched_version: env!("CARGO_PKG_VERSION").to_string(),
+        artifacts: downloaded_artifacts.into_iter().map(|(_, p)| p).collect(),
+        timestamp: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("unexpected None duration_since")
+            .as_secs(),
+    };
+    write_installed(&installed).expect("failed write_installed");
+    info!("installed {} artifacts", installed.artifacts.len());
+}
+
+/// Loads the AWS config for the local instance region, and returns the instance tags.
+pub async fn load_local_instance() -> (AwsSdkConfig, HashMap<String, String>) {
+    info!("STEP: fetching intance metadata using IMDSv2");
+    let reg = ec2::fetch_region().await.expect("failed ec2::fetch_region");
+    let instance_id = ec2::fetch_instance_id()
+        .await
+        .expect("failed ec2::fetch_instance_id");
+    info!("fetched region {}, instance ID {}", reg, instance_id);
+
+    let shared_config = aws::load_config(Some(reg))
+        .await
+        .expect("failed aws::load_config");
+
+    info!("STEP: fetching tags from the local instance");
+    let tags = Arc::new(ec2::Manager::new(&shared_config))
+        .fetch_tags(Arc::new(instance_id))
+        .await
+        .expect("failed ec2_manager.fetch_tags");
+    let tags = tags
+        .iter()
+        .map(|c| {
+            (
+                c.key().unwrap_or_default().to_string(),
+                c.value().unwrap_or_default().to_string(),
+            )
+        })
+        .collect();
+    (shared_config, tags)
+}
+
+pub fn required_tag(tags: &HashMap<String, String>, k: &str) -> String {
+    match tags.get(k) {
+        Some(v) if !v.is_empty() => v.clone(),
+        _ => panic!("'{}' tag not found", k),
+    }
+}
+
+fn write_installed(installed: &Installed) -> io::Result<()> {
+    let d = serde_json::to_vec(installed)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to serialize {}", e)))?;
+    if let Some(parent_dir) = Path::new(INSTALLED_FILE_PATH).parent() {
+        fs::create_dir_all(parent_dir)?;
+    }
+    fs::write(INSTALLED_FILE_PATH, d)
+}
+
+/// Downloads the avalanche binary and the plugins, unless already installed
+/// (e.g., baked in the AMI). Returns the (S3 key, file path) of each download
+/// to verify once the spec is loaded.
+pub async fn download_artifacts(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    id: &str,
+    avalanche_bin_path: &str,
+) -> Vec<(String, String)> {
+    let mut downloaded_artifacts: Vec<(String, String)> = Vec::new();
+    if !Path::new(avalanche_bin_path).exists() {
+        info!("STEP: downloading avalanche binary from S3");
+        let s3_key =
+            avalanche_ops::StorageNamespace::AvalancheBinCompressed(id.to_string()).encode();
+        let tmp_avalanche_bin_compressed_path = random::tmp_path(15, Some(".zstd")).unwrap();
+        s3::spawn_get_object_resumable(
+            s3_manager.clone(),
+            s3_bucket,
+            &s3_key,
+            &tmp_avalanche_bin_compressed_path,
+        )
+        .await
+        .expect("failed s3::spawn_get_object_resumable");
+
+        compress::unpack_file(
+            &tmp_avalanche_bin_compressed_path,
+            avalanche_bin_path,
+            compress::Decoder::Zstd,
+        )
+        .expect("failed unpack_file avalanche_bin_compressed_path");
+
+        let f = File::open(avalanche_bin_path).expect("failed to open avalanche_bin");
+        f.set_permissions(PermissionsExt::from_mode(0o777))
+            .expect("failed to set file permission for avalanche_bin");
+        fs::remove_file(&tmp_avalanche_bin_compressed_path).expect("failed fs::remove_file");
+        downloaded_artifacts.push((s3_key, avalanche_bin_path.to_string()));
+    }
+
+    let plugins_dir = get_plugins_dir(avalanche_bin_path);
+    if !Path::new(&plugins_dir).exists() {
+        info!("STEP: creating '{}' for plugins", plugins_dir);
+        fs::create_dir_all(plugins_dir.clone()).unwrap();
+
+        info!("STEP: downloading plugins from S3 (if any)");
+        let objects = s3::spawn_list_objects(
+            s3_manager.clone(),
+            s3_bucket,
+            Some(s3::append_slash(
+                &avalanche_ops::StorageNamespace::PluginsDir(id.to_string()).encode(),
+            )),
+        )
+        .await
+        .expect("failed s3::spawn_list_objects");
+        info!("listed {} plugins from S3", objects.len());
+        for obj in objects.iter() {
+            let s3_key = obj.key().expect("unexpected None s3 object");
+            let tmp_path = random::tmp_path(15, None).unwrap();
+            s3::spawn_get_object_resumable(s3_manager.clone(), s3_bucket, s3_key, &tmp_path)
+                .await
+                .expect("failed s3::spawn_get_object_resumable");
+
+            let file_name = extract_filename(s3_key);
+            let file_path = format!("{}/{}", plugins_dir, file_name);
+            compress::unpack_file(&tmp_path, &file_path, compress::Decoder::Zstd).unwrap();
+
+            let f = File::open(&file_path).expect("failed to open plugin file");
+            f.set_permissions(PermissionsExt::from_mode(0o777))
+                .expect("failed to set file permission");
+            fs::remove_file(&tmp_path).expect("failed fs::remove_file");
+            downloaded_artifacts.push((s3_key.to_string(), file_path));
+        }
+    }
+
+    downloaded_artifacts
+}
+
+/// Verifies the downloaded artifacts against the provenance, and
+/// removes them and panics on the failure, so that the next restart
+/// downloads again.
+pub async fn verify_artifacts(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    downloaded_artifacts: &[(String, String)],
+    plugins_dir: &str,
+    install_artifacts: &avalanche_ops::InstallArtifacts,
+) {
+    info!(
+        "STEP: verifying {} downloaded artifacts",
+        downloaded_artifacts.len()
+    );
+    for (s3_key, file_path) in downloaded_artifacts.iter() {
+        if let Err(e) =
+            verify_artifact(s3_manager, s3_bucket, s3_key, file_path, install_artifacts).await
+        {
+            // remove so that the next restart downloads again
+            for (_, file_path) in downloaded_artifacts.iter() {
+                fs::remove_file(file_path).expect("failed fs::remove_file");
+            }
+            fs::remove_dir_all(plugins_dir).expect("failed fs::remove_dir_all");
+            panic!("refusing to install '{}' ({})", file_path, e);
+        }
+    }
+}
+
+/// Applies the network tunings (if any), and returns
+/// the open files limit for the avalanche service.
+pub fn apply_network_tuning(network_tuning: Option<&network_tuning::NetworkTuning>) -> u64 {
+    match network_tuning {
+        Some(tuning) => {
+            info!(
+                "STEP: applying network tunings to {}",
+                network_tuning::SYSCTL_FILE_PATH
+            );
+            fs::write(network_tuning::SYSCTL_FILE_PATH, tuning.sysctl_conf())
+                .expect("failed to write sysctl file");
+            // "-e" to skip the unknown keys (e.g., conntrack module not loaded)
+            if let Err(e) = bash::run(
+                format!("sudo sysctl -e -p {}", network_tuning::SYSCTL_FILE_PATH).as_str(),
+            ) {
+                warn!("failed to apply network tunings ({})", e);
+            }
+
+            let checks = tuning.verify();
+            let failed: Vec<&network_tuning::Check> =
+                checks.iter().filter(|c| !c.is_ok()).collect();
+            for c in checks.iter() {
+                info!(
+                    "network tuning {}: expected '{}', actual '{}' ({})",
+                    c.key,
+                    c.expected,
+                    c.actual.clone().unwrap_or_else(|| String::from("<none>")),
+                    if c.is_ok() { "ok" } else { "MISMATCH" }
+                );
+            }
+            if failed.is_empty() {
+                info!("all {} network tunings applied", checks.len());
+            } else {
+                warn!(
+                    "{} out of {} network tunings not applied",
+                    failed.len(),
+                    checks.len()
+                );
+            }
+            tuning.nofile_limit()
+        }
+        None => network_tuning::UNTUNED_NOFILE_LIMIT,
+    }
+}
+
+///  build
+///    ├── avalanchego (the binary from compiling the app directory)
+///    └── plugins
+///        └── evm
+/// Verifies the downloaded (and unpacked) artifact against the provenance
+/// in the S3 object metadata, uploaded by "avalanche-ops-aws".
+pub async fn verify_artifact(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    s3_key: &str,
+    file_path: &str,
+    install_artifacts: &avalanche_ops::InstallArtifacts,
+) -> io::Result<()> {
+    let metadata = s3_manager
+        .head_object_metadata(s3_bucket, s3_key)
+        .await
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed head_object_metadata {}", e),
+            )
+        })?;
+    match metadata
+        .as_ref()
+        .and_then(provenance::Provenance::from_metadata)
+    {
+        Some(p) => p.verify(
+            file_path,
+            install_artifacts.signing_public_key.as_deref(),
+            install_artifacts.require_signed_artifacts,
+        ),
+        None => {
+            if install_artifacts.require_signed_artifacts {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("refusing artifact '{}' without provenance", s3_key),
+                ));
+            }
+            warn!(
+                "no provenance found for '{}', skipping verification",
+                s3_key
+            );
+            Ok(())
+        }
+    }
+}
+
+pub fn get_plugins_dir(avalanche_bin: &str) -> String {
+    let path = Path::new(avalanche_bin);
+    let parent_dir = path.parent().unwrap();
+    String::from(
+        parent_dir
+            .join(Path::new("plugins"))
+            .as_path()
+            .to_str()
+            .unwrap(),
+    )
+}
+
+/// returns "hello" from "a/b/c/hello.zstd"
+pub fn extract_filename(p: &str) -> String {
+    let path = Path::new(p);
+    let file_stemp = path.file_stem().unwrap();
+    String::from(file_stemp.to_str().unwrap())
+}
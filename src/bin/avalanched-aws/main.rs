@@ -1,7 +1,10 @@
 use clap::Command;
 
 mod backup;
+mod check_db;
+mod install;
 mod run;
+mod upgrade;
 
 const NAME: &str = "avalanched-aws";
 
@@ -10,10 +13,21 @@ async fn main() {
     let matches = Command::new(NAME)
         .about("avalanched on AWS")
         .long_about("Avalanche agent (daemon) on AWS")
-        .subcommands(vec![run::command(), backup::command()])
+        .subcommands(vec![
+            install::command(),
+            run::command(),
+            backup::command(),
+            upgrade::command(),
+            check_db::command(),
+        ])
         .get_matches();
 
     match matches.subcommand() {
+        Some((install::NAME, sub_matches)) => {
+            let log_lvl = sub_matches.value_of("LOG_LEVEL").unwrap_or("info");
+            install::execute(log_lvl).await;
+        }
+
         Some((run::NAME, sub_matches)) => {
             let log_lvl = sub_matches.value_of("LOG_LEVEL").unwrap_or("info");
             run::execute(log_lvl).await;
@@ -31,6 +45,11 @@ async fn main() {
                     sub_sub_matches.value_of("S3_BUCKET").unwrap(),
                     sub_sub_matches.value_of("S3_KEY").unwrap(),
                     sub_sub_matches.value_of("UNPACK_DIR").unwrap(),
+                    sub_sub_matches
+                        .value_of("MAX_BYTES_PER_SECOND")
+                        .unwrap_or("0")
+                        .parse::<u64>()
+                        .expect("invalid --max-bytes-per-second"),
                 )
                 .unwrap();
             }
@@ -46,6 +65,13 @@ async fn main() {
                     sub_sub_matches.value_of("PACK_DIR").unwrap(),
                     sub_sub_matches.value_of("S3_BUCKET").unwrap(),
                     sub_sub_matches.value_of("S3_KEY").unwrap(),
+                    sub_sub_matches.value_of("REPLICA_REGION").unwrap_or(""),
+                    sub_sub_matches.value_of("REPLICA_S3_BUCKET").unwrap_or(""),
+                    sub_sub_matches
+                        .value_of("MAX_BYTES_PER_SECOND")
+                        .unwrap_or("0")
+                        .parse::<u64>()
+                        .expect("invalid --max-bytes-per-second"),
                 )
                 .unwrap();
             }
@@ -53,6 +79,25 @@ async fn main() {
             _ => unreachable!("unknown sub-subcommand"),
         },
 
+        Some((upgrade::NAME, sub_matches)) => {
+            let log_lvl = sub_matches.value_of("LOG_LEVEL").unwrap_or("info");
+            upgrade::execute(log_lvl)
+                .await
+                .expect("failed to execute 'upgrade'");
+        }
+
+        Some((check_db::NAME, sub_matches)) => {
+            let log_lvl = sub_matches.value_of("LOG_LEVEL").unwrap_or("info");
+            check_db::execute(
+                log_lvl,
+                sub_matches.value_of("DB_DIR"),
+                sub_matches.is_present("STOP_NODE"),
+                sub_matches.value_of("REPORT_NAME"),
+            )
+            .await
+            .expect("failed to execute 'check-db'");
+        }
+
         _ => unreachable!("unknown subcommand"),
     }
 }
@@ -1,6 +1,7 @@
 use std::{
+    collections::BTreeMap,
     fs::{self, File},
-    io::Write,
+    io::{self, Error, ErrorKind, Read, Seek, SeekFrom, Write},
     os::unix::fs::PermissionsExt,
     path::Path,
     sync::Arc,
@@ -13,18 +14,26 @@ use log::{info, warn};
 use tokio::time::sleep;
 
 use avalanche_ops::{
-    self,
+    self, agent_metrics, api_auth,
     avalanche::{
         avalanchego::{
-            api::{health, metrics},
-            genesis as avalanchego_genesis,
+            api::{auth, health, index, info as info_api, metrics},
+            config as avalanchego_config, genesis as avalanchego_genesis, subnet_config, upgrade,
         },
-        constants, node,
+        bls, constants, heartbeat, node,
     },
-    aws::{self, cloudwatch, ec2, envelope, kms, s3},
-    utils::{bash, cert, compress, random},
+    aws::{self, cloudwatch, ec2, eip, elbv2, envelope, kms, s3},
+    canary, db_check, indexer, metric_schema, network_snapshot, node_keys, otlp, remediation,
+    time_sync, usage,
+    utils::{bash, cert, compress, hash, random},
 };
 
+use crate::install;
+
+mod peer_allowlist;
+mod public_snapshot;
+mod relayer;
+
 pub const NAME: &str = "run";
 
 /// Should be able to run with idempotency
@@ -53,6 +62,13 @@ pub async fn execute(log_level: &str) {
         env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
     );
 
+    let agent_registry = Arc::new(agent_metrics::Registry::new(
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    ));
+
     info!("STEP: fetching intance metadata using IMDSv2");
 
     let az = tokio::spawn(ec2::fetch_availability_zone())
@@ -73,12 +89,6 @@ pub async fn execute(log_level: &str) {
         .expect("failed ec2::fetch_instance_id");
     info!("fetched instance ID {}", instance_id);
 
-    let public_ipv4 = tokio::spawn(ec2::fetch_public_ipv4())
-        .await
-        .expect("failed spawn await")
-        .expect("failed ec2::fetch_public_ipv4");
-    info!("fetched public ipv4 {}", public_ipv4);
-
     info!("STEP: loading AWS config");
     let shared_config = tokio::spawn(aws::load_config(Some(reg.clone())))
         .await
@@ -86,19 +96,24 @@ pub async fn execute(log_level: &str) {
         .expect("failed aws::load_config");
 
     let ec2_manager = ec2::Manager::new(&shared_config);
+
+    info!("STEP: discovering public IPv4 (IMDSv2, or EC2 API as fallback)");
+    let mut public_ipv4 = ec2_manager
+        .discover_public_ipv4(&instance_id)
+        .await
+        .expect("failed ec2::Manager::discover_public_ipv4");
+    info!("discovered public ipv4 {}", public_ipv4);
     let kms_manager = kms::Manager::new(&shared_config);
     let s3_manager = s3::Manager::new(&shared_config);
     let cw_manager = cloudwatch::Manager::new(&shared_config);
 
     info!("STEP: fetching tags from the local instance");
     let instance_id_arc = Arc::new(instance_id.clone());
-    let tags = tokio::spawn(async move {
-        let ec2_manager_arc = Arc::new(ec2_manager);
-        ec2_manager_arc.fetch_tags(instance_id_arc).await
-    })
-    .await
-    .expect("failed spawn await")
-    .expect("failed ec2_manager.fetch_tags");
+    let ec2_manager_arc = Arc::new(ec2_manager.clone());
+    let tags = tokio::spawn(async move { ec2_manager_arc.fetch_tags(instance_id_arc).await })
+        .await
+        .expect("failed spawn await")
+        .expect("failed ec2_manager.fetch_tags");
 
     let mut id: String = String::new();
     let mut _node_kind: String = String::new();
@@ -108,6 +123,7 @@ pub async fn execute(log_level: &str) {
     let mut avalanched_bin_path: String = String::new();
     let mut avalanche_bin_path: String = String::new();
     let mut avalanche_data_volume_path: String = String::new();
+    let mut nlb_target_group_arn: String = String::new();
     for c in tags {
         let k = c.key().unwrap();
         let v = c.value().unwrap();
@@ -137,6 +153,9 @@ pub async fn execute(log_level: &str) {
             "AVALANCHE_DATA_VOLUME_PATH" => {
                 avalanche_data_volume_path = v.to_string();
             }
+            "NLB_TARGET_GROUP_ARN" => {
+                nlb_target_group_arn = v.to_string();
+            }
             _ => {}
         }
     }
@@ -146,6 +165,7 @@ pub async fn execute(log_level: &str) {
     if _node_kind.is_empty() {
         panic!("'NODE_KIND' tag not found")
     }
+    let is_icm_relayer = _node_kind.eq(avalanche_ops::icm_relayer::NODE_KIND);
     let node_kind = {
         if _node_kind.eq("anchor") {
             node::Kind::Anchor
@@ -172,67 +192,37 @@ pub async fn execute(log_level: &str) {
         panic!("'AVALANCHE_DATA_VOLUME_PATH' tag not found")
     }
 
-    let envelope = envelope::Envelope::new(Some(kms_manager), Some(kms_cmk_arn));
-
-    if !Path::new(&avalanche_bin_path).exists() {
-        info!("STEP: downloading avalanche binary from S3");
-        let s3_key = avalanche_ops::StorageNamespace::AvalancheBinCompressed(id.clone()).encode();
-        let tmp_avalanche_bin_compressed_path = random::tmp_path(15, Some(".zstd")).unwrap();
-        s3::spawn_get_object(
-            s3_manager.clone(),
-            &s3_bucket,
-            &s3_key,
-            &tmp_avalanche_bin_compressed_path,
-        )
-        .await
-        .expect("failed s3::spawn_get_object");
-
-        compress::unpack_file(
-            &tmp_avalanche_bin_compressed_path,
-            &avalanche_bin_path,
-            compress::Decoder::Zstd,
+    // the artifacts sealed by "apply" share the data keys
+    let envelope = envelope::Envelope::new(Some(kms_manager), Some(kms_cmk_arn))
+        .with_data_key_cache(envelope::DEFAULT_DATA_KEY_CACHE_CAPACITY);
+
+    if is_icm_relayer {
+        info!("STEP: running ICM relayer instead of avalanche node");
+        relayer::execute(
+            s3_manager,
+            cw_manager,
+            envelope,
+            id,
+            s3_bucket,
+            instance_id,
+            az,
+            cloudwatch_config_file_path,
+            avalanche_data_volume_path,
         )
-        .expect("failed unpack_file avalanche_bin_compressed_path");
-
-        let f = File::open(&avalanche_bin_path).expect("failed to open avalanche_bin");
-        f.set_permissions(PermissionsExt::from_mode(0o777))
-            .expect("failed to set file permission for avalanche_bin");
-        fs::remove_file(&tmp_avalanche_bin_compressed_path).expect("failed fs::remove_file");
+        .await;
+        return;
     }
 
-    let plugins_dir = get_plugins_dir(&avalanche_bin_path);
-    if !Path::new(&plugins_dir).exists() {
-        info!("STEP: creating '{}' for plugins", plugins_dir);
-        fs::create_dir_all(plugins_dir.clone()).unwrap();
-
-        info!("STEP: downloading plugins from S3 (if any)");
-        let objects = s3::spawn_list_objects(
-            s3_manager.clone(),
-            &s3_bucket,
-            Some(s3::append_slash(
-                &avalanche_ops::StorageNamespace::PluginsDir(id.clone()).encode(),
-            )),
-        )
-        .await
-        .expect("failed s3::spawn_list_objects");
-        info!("listed {} plugins from S3", objects.len());
-        for obj in objects.iter() {
-            let s3_key = obj.key().expect("unexpected None s3 object");
-            let tmp_path = random::tmp_path(15, None).unwrap();
-            s3::spawn_get_object(s3_manager.clone(), &s3_bucket, s3_key, &tmp_path)
-                .await
-                .expect("failed s3::spawn_get_object");
-
-            let file_name = extract_filename(s3_key);
-            let file_path = format!("{}/{}", plugins_dir, file_name);
-            compress::unpack_file(&tmp_path, &file_path, compress::Decoder::Zstd).unwrap();
-
-            let f = File::open(file_path).expect("failed to open plugin file");
-            f.set_permissions(PermissionsExt::from_mode(0o777))
-                .expect("failed to set file permission");
-            fs::remove_file(&tmp_path).expect("failed fs::remove_file");
-        }
+    if Path::new(install::INSTALLED_FILE_PATH).exists() {
+        info!(
+            "STEP: found '{}', skipping the installed artifacts",
+            install::INSTALLED_FILE_PATH
+        );
     }
+    // (S3 key, file path) to verify once the spec is loaded
+    let downloaded_artifacts =
+        install::download_artifacts(&s3_manager, &s3_bucket, &id, &avalanche_bin_path).await;
+    let plugins_dir = install::get_plugins_dir(&avalanche_bin_path);
 
     info!("STEP: downloading avalanche-ops::Spec from S3");
     let tmp_spec_file_path = random::tmp_path(15, Some(".yaml")).unwrap();
@@ -246,7 +236,83 @@ pub async fn execute(log_level: &str) {
     .expect("failed s3::spawn_get_object");
 
     let mut spec = avalanche_ops::Spec::load(&tmp_spec_file_path).unwrap();
+
+    // serves before the database backup download, which can take hours
+    if let Some(v) = &spec.agent_metrics {
+        let port = v.port();
+        let registry = agent_registry.clone();
+        tokio::spawn(async move {
+            if let Err(e) = agent_metrics::serve(port, registry).await {
+                warn!("agent metrics server stopped ({})", e);
+            }
+        });
+    }
+
+    let elastic_ip_pool_addresses = spec
+        .aws_resources
+        .as_ref()
+        .and_then(|r| r.elastic_ip_pool_addresses.clone())
+        .unwrap_or_default();
+    if matches!(node_kind, node::Kind::NonAnchor) && !elastic_ip_pool_addresses.is_empty() {
+        info!("STEP: leasing Elastic IP from the pool");
+        let leased = eip::Manager::new(&shared_config)
+            .acquire(&id, &elastic_ip_pool_addresses, &instance_id)
+            .await
+            .expect("failed eip::Manager::acquire");
+        match leased {
+            Some(address) => {
+                info!(
+                    "leased Elastic IP {} (was {})",
+                    address.public_ip, public_ipv4
+                );
+                public_ipv4 = address.public_ip;
+            }
+            None => warn!(
+                "no free Elastic IP in the pool, keeping the auto-assigned {}",
+                public_ipv4
+            ),
+        }
+    }
     spec.avalanchego_config.public_ip = Some(public_ipv4.clone());
+
+    // per node class APIs (if any)
+    let (node_avalanchego_config, node_coreth_config) = spec.node_configs(&node_kind);
+    spec.avalanchego_config = node_avalanchego_config;
+    spec.coreth_config = node_coreth_config;
+
+    // the API nodes compete for the publisher lease, so only one stops at a time
+    let public_snapshot_cfg = spec
+        .public_snapshot
+        .clone()
+        .filter(|_| spec.is_api_node(&node_kind));
+
+    // the database backups and snapshots are not indexed
+    let restores_db = spec
+        .indexer
+        .as_ref()
+        .map(|v| v.restores_db(&node_kind))
+        .unwrap_or(true);
+
+    let mut api_auth_password: Option<String> = None;
+    if spec.avalanchego_config.api_auth_required == Some(true) {
+        info!("STEP: downloading API auth password from S3");
+        let password = api_auth::get_password(&s3_manager, &envelope, &s3_bucket, &id)
+            .await
+            .expect("failed api_auth::get_password")
+            .expect("API auth password not found (run 'apply' first)");
+        let password_file = spec
+            .avalanchego_config
+            .api_auth_password_file
+            .clone()
+            .expect("unexpected None api_auth_password_file");
+        if let Some(parent_dir) = Path::new(&password_file).parent() {
+            fs::create_dir_all(parent_dir).expect("failed to create password file dir");
+        }
+        fs::write(&password_file, &password).expect("failed to write API auth password file");
+        fs::set_permissions(&password_file, PermissionsExt::from_mode(0o600))
+            .expect("failed to set file permission for API auth password file");
+        api_auth_password = Some(password);
+    }
     spec.avalanchego_config
         .sync(None)
         .expect("failed to sync avalanchego config_file");
@@ -254,6 +320,15 @@ pub async fn execute(log_level: &str) {
     // "avalanched" never updates "spec" file, runs in read-only mode
     fs::remove_file(&tmp_spec_file_path).expect("failed fs::remove_file");
 
+    install::verify_artifacts(
+        &s3_manager,
+        &s3_bucket,
+        &downloaded_artifacts,
+        &plugins_dir,
+        &spec.install_artifacts,
+    )
+    .await;
+
     // ref. https://docs.aws.amazon.com/AmazonCloudWatch/latest/monitoring/CloudWatch-Agent-Configuration-File-Details.html
     info!("STEP: writing CloudWatch configuration JSON file");
     let aws_resources = spec.aws_resources.clone().unwrap();
@@ -279,6 +354,16 @@ pub async fn execute(log_level: &str) {
             ..cloudwatch::Collect::default()
         },
     ];
+    if spec.remediation.is_some() {
+        log_collect_list.push(cloudwatch::Collect {
+            log_group_name: id.clone(),
+            log_stream_name: format!("{{instance_id}}-{}-remediation", node_kind.as_str()),
+            file_path: String::from(remediation::ACTION_LOG_FILE_PATH),
+            auto_removal: Some(false),
+            retention_in_days: Some(30),
+            ..cloudwatch::Collect::default()
+        });
+    }
     if aws_resources.instance_system_logs.is_some() && aws_resources.instance_system_logs.unwrap() {
         // to check OOMs via "oom-kill" or "Out of memory: Killed process 8266 (srEXiWaHuhNyGwP)"
         log_collect_list.push(cloudwatch::Collect {
@@ -299,15 +384,28 @@ pub async fn execute(log_level: &str) {
             ..cloudwatch::Collect::default()
         });
     }
+    // same files for the OTLP log export
+    let otlp_log_file_paths: Vec<String> = log_collect_list
+        .iter()
+        .map(|c| c.file_path.clone())
+        .collect();
     let mut cloudwatch_config = cloudwatch::Config::default();
-    cloudwatch_config.logs = Some(cloudwatch::Logs {
-        force_flush_interval: Some(60),
-        logs_collected: Some(cloudwatch::LogsCollected {
-            files: Some(cloudwatch::Files {
-                collect_list: Some(log_collect_list),
+    if spec
+        .otlp
+        .as_ref()
+        .map_or(false, |v| v.skips_cloudwatch_logs())
+    {
+        info!("skipping CloudWatch logs for OTLP export");
+    } else {
+        cloudwatch_config.logs = Some(cloudwatch::Logs {
+            force_flush_interval: Some(60),
+            logs_collected: Some(cloudwatch::LogsCollected {
+                files: Some(cloudwatch::Files {
+                    collect_list: Some(log_collect_list),
+                }),
             }),
-        }),
-    });
+        });
+    }
     if aws_resources.instance_system_metrics.is_some()
         && aws_resources.instance_system_metrics.unwrap()
     {
@@ -323,21 +421,165 @@ pub async fn execute(log_level: &str) {
         .sync(&cloudwatch_config_file_path)
         .unwrap();
 
+    // the node slot keeps the keys across the instance replacements
+    let mut keys_name = instance_id.clone();
+    if spec.static_node_keys == Some(true) {
+        info!("STEP: leasing node slot for the static node keys");
+        let nodes = match node_kind {
+            node::Kind::Anchor => spec.machine.anchor_nodes.unwrap_or(0),
+            node::Kind::NonAnchor => spec.machine.non_anchor_nodes,
+        };
+        let slot = node_keys::slot_manager(&shared_config)
+            .acquire(
+                &id,
+                &node_keys::slot_ids(&id, &node_kind, nodes),
+                &instance_id,
+            )
+            .await
+            .expect("failed to lease node slot");
+        match slot {
+            Some(slot_id) => keys_name = node_keys::slot_name(&slot_id),
+            None => warn!("no free node slot, generating the keys for the instance"),
+        }
+    }
+    let keys_s3 = node_keys::S3Keys::new(&id, &keys_name);
+    let restore_keys = keys_name != instance_id;
+
     info!("checking TLS certs for node ID");
-    // TODO: reuse TLS certs for static node IDs
     let tls_key_path = spec
         .avalanchego_config
         .clone()
         .staking_tls_key_file
         .unwrap();
-    let tls_key_exists = Path::new(&tls_key_path).exists();
     let tls_cert_path = spec
         .avalanchego_config
         .clone()
         .staking_tls_cert_file
         .unwrap();
+
+    // the restored node slot takes over the keys of the snapshot node
+    let snapshot_source = match spec.restore_from_snapshot.clone() {
+        Some(source) if restore_keys => {
+            let source_config = tokio::spawn(aws::load_config(Some(source.region.clone())))
+                .await
+                .expect("failed spawn await")
+                .expect("failed aws::load_config");
+            let source_s3_manager = s3::Manager::new(&source_config);
+            let source_envelope = envelope::Envelope::new(
+                Some(kms::Manager::new(&source_config)),
+                Some(source.kms_key_arn.clone()),
+            );
+            Some((source, source_s3_manager, source_envelope))
+        }
+        _ => None,
+    };
+    if let Some((source, source_s3_manager, source_envelope)) = &snapshot_source {
+        let backed_up = s3_manager
+            .head_object_metadata(&s3_bucket, &keys_s3.tls_cert)
+            .await
+            .expect("failed s3::Manager::head_object_metadata")
+            .is_some();
+        if !backed_up && !Path::new(&tls_key_path).exists() {
+            info!(
+                "STEP: restoring node keys of the slot '{}' from snapshot {}",
+                keys_name,
+                source.s3_url()
+            );
+            let snapshot_keys = network_snapshot::node_keys(&source.s3_prefix, &keys_name);
+            s3::spawn_get_object(
+                source_s3_manager.clone(),
+                &source.s3_bucket,
+                &snapshot_keys.tls_cert,
+                &tls_cert_path,
+            )
+            .await
+            .expect("failed s3::spawn_get_object");
+            restore_key(
+                source_s3_manager.clone(),
+                source_envelope.clone(),
+                &source.s3_bucket,
+                &snapshot_keys.tls_key_encrypted,
+                &tls_key_path,
+            )
+            .await;
+
+            // backed up under the slot for the later replacements
+            s3::spawn_put_object(
+                s3_manager.clone(),
+                &tls_cert_path,
+                &s3_bucket,
+                &keys_s3.tls_cert,
+            )
+            .await
+            .expect("failed s3::spawn_put_object");
+            backup_key(
+                s3_manager.clone(),
+                envelope.clone(),
+                &tls_key_path,
+                &s3_bucket,
+                &keys_s3.tls_key_encrypted,
+            )
+            .await;
+
+            if let Some(signer_key_path) = spec.avalanchego_config.staking_signer_key_file.clone() {
+                let signer_key_in_snapshot = source_s3_manager
+                    .head_object_metadata(&source.s3_bucket, &snapshot_keys.signer_key_encrypted)
+                    .await
+                    .expect("failed s3::Manager::head_object_metadata")
+                    .is_some();
+                if signer_key_in_snapshot {
+                    restore_key(
+                        source_s3_manager.clone(),
+                        source_envelope.clone(),
+                        &source.s3_bucket,
+                        &snapshot_keys.signer_key_encrypted,
+                        &signer_key_path,
+                    )
+                    .await;
+                    backup_key(
+                        s3_manager.clone(),
+                        envelope.clone(),
+                        &signer_key_path,
+                        &s3_bucket,
+                        &keys_s3.signer_key_encrypted,
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+
+    let tls_key_exists = Path::new(&tls_key_path).exists();
     let tls_cert_exists = Path::new(&tls_cert_path).exists();
-    if !tls_key_exists || !tls_cert_exists {
+    let mut tls_restored = false;
+    if restore_keys && (!tls_key_exists || !tls_cert_exists) {
+        let cert_backed_up = s3_manager
+            .head_object_metadata(&s3_bucket, &keys_s3.tls_cert)
+            .await
+            .expect("failed s3::Manager::head_object_metadata")
+            .is_some();
+        if cert_backed_up {
+            info!("STEP: restoring TLS certs of the node slot '{}'", keys_name);
+            s3::spawn_get_object(
+                s3_manager.clone(),
+                &s3_bucket,
+                &keys_s3.tls_cert,
+                &tls_cert_path,
+            )
+            .await
+            .expect("failed s3::spawn_get_object");
+            restore_key(
+                s3_manager.clone(),
+                envelope.clone(),
+                &s3_bucket,
+                &keys_s3.tls_key_encrypted,
+                &tls_key_path,
+            )
+            .await;
+            tls_restored = true;
+        }
+    }
+    if !tls_restored && (!tls_key_exists || !tls_cert_exists) {
         info!(
             "STEP: generating TLS certs (key exists {}, cert exists {})",
             tls_key_exists, tls_cert_exists
@@ -345,49 +587,59 @@ pub async fn execute(log_level: &str) {
         cert::generate(&tls_key_path, &tls_cert_path).unwrap();
 
         info!("uploading generated TLS certs to S3");
-        let s3_key = format!(
-            "{}/{}.crt",
-            avalanche_ops::StorageNamespace::PkiKeyDir(id.clone()).encode(),
-            instance_id
-        );
-        s3::spawn_put_object(s3_manager.clone(), &tls_cert_path, &s3_bucket, &s3_key)
-            .await
-            .expect("failed s3::spawn_put_object");
-
-        let tmp_compressed_path = random::tmp_path(15, Some(".zstd")).unwrap();
-        let tmp_encrypted_path = random::tmp_path(15, Some(".zstd.encrypted")).unwrap();
-
-        compress::pack_file(
-            &tls_key_path,
-            &tmp_compressed_path,
-            compress::Encoder::Zstd(3),
-        )
-        .expect("failed pack_file tls_key_path");
-
-        envelope::spawn_seal_aes_256_file(
-            envelope.clone(),
-            &tmp_compressed_path,
-            &tmp_encrypted_path,
-        )
-        .await
-        .expect("failed envelope::spawn_seal_aes_256_file");
-
         s3::spawn_put_object(
             s3_manager.clone(),
-            &tmp_encrypted_path,
+            &tls_cert_path,
             &s3_bucket,
-            format!(
-                "{}/{}.key.zstd.seal_aes_256.encrypted",
-                avalanche_ops::StorageNamespace::PkiKeyDir(id.clone()).encode(),
-                instance_id
-            )
-            .as_str(),
+            &keys_s3.tls_cert,
         )
         .await
         .expect("failed s3::spawn_put_object");
+        backup_key(
+            s3_manager.clone(),
+            envelope.clone(),
+            &tls_key_path,
+            &s3_bucket,
+            &keys_s3.tls_key_encrypted,
+        )
+        .await;
+    }
 
-        fs::remove_file(tmp_compressed_path).expect("failed fs::remove_file");
-        fs::remove_file(tmp_encrypted_path).expect("failed fs::remove_file");
+    if let Some(signer_key_path) = spec.avalanchego_config.staking_signer_key_file.clone() {
+        info!("checking BLS signer key");
+        if !Path::new(&signer_key_path).exists() {
+            let signer_key_backed_up = restore_keys
+                && s3_manager
+                    .head_object_metadata(&s3_bucket, &keys_s3.signer_key_encrypted)
+                    .await
+                    .expect("failed s3::Manager::head_object_metadata")
+                    .is_some();
+            if signer_key_backed_up {
+                info!(
+                    "STEP: restoring BLS signer key of the node slot '{}'",
+                    keys_name
+                );
+                restore_key(
+                    s3_manager.clone(),
+                    envelope.clone(),
+                    &s3_bucket,
+                    &keys_s3.signer_key_encrypted,
+                    &signer_key_path,
+                )
+                .await;
+            } else {
+                info!("STEP: generating BLS signer key");
+                bls::generate(&signer_key_path).expect("failed bls::generate");
+                backup_key(
+                    s3_manager.clone(),
+                    envelope.clone(),
+                    &signer_key_path,
+                    &s3_bucket,
+                    &keys_s3.signer_key_encrypted,
+                )
+                .await;
+            }
+        }
     }
 
     // loads the node ID from generated/existing certs
@@ -421,12 +673,81 @@ pub async fn execute(log_level: &str) {
             .expect("failed to encode node Info")
     );
 
+    if let Some((source, source_s3_manager, _)) = &snapshot_source {
+        // the public networks (e.g., "failover" of a mainnet cluster) have no genesis file
+        let genesis_path = spec.avalanchego_config.clone().genesis;
+        if let Some(genesis_path) = genesis_path.filter(|p| !Path::new(p).exists()) {
+            info!(
+                "STEP: downloading genesis file from snapshot {}",
+                source.s3_url()
+            );
+            s3::spawn_get_object(
+                source_s3_manager.clone(),
+                &source.s3_bucket,
+                &network_snapshot::genesis_key(&source.s3_prefix),
+                &genesis_path,
+            )
+            .await
+            .expect("failed s3::spawn_get_object");
+
+            // shared with the nodes that have no snapshot slot
+            s3::spawn_put_object(
+                s3_manager.clone(),
+                &genesis_path,
+                &s3_bucket,
+                &avalanche_ops::StorageNamespace::GenesisFile(spec.id.clone()).encode(),
+            )
+            .await
+            .expect("failed s3::spawn_put_object");
+        }
+
+        let db_dir = spec.avalanchego_config.db_dir.clone();
+        fs::create_dir_all(&db_dir).expect("failed fs::create_dir_all");
+        let db_empty = fs::read_dir(&db_dir)
+            .map(|mut d| d.next().is_none())
+            .unwrap_or(true);
+        let db_s3_key = network_snapshot::db_key(&source.s3_prefix, &keys_name);
+        let db_in_snapshot = source_s3_manager
+            .head_object_metadata(&source.s3_bucket, &db_s3_key)
+            .await
+            .expect("failed s3::Manager::head_object_metadata")
+            .is_some();
+        if !restores_db {
+            info!("STEP: skipping database snapshot download for the index backfill from genesis");
+        } else if db_empty && db_in_snapshot {
+            info!(
+                "STEP: downloading database of the slot '{}' from snapshot {}",
+                keys_name,
+                source.s3_url()
+            );
+            // do not store in "tmp", will run out of space
+            let download_path = format!(
+                "{}/.snapshot-download{}",
+                db_dir,
+                compress::DirDecoder::TarGzip.ext()
+            );
+            s3::spawn_get_object(
+                source_s3_manager.clone(),
+                &source.s3_bucket,
+                &db_s3_key,
+                &download_path,
+            )
+            .await
+            .expect("failed s3::spawn_get_object");
+            compress::unpack_directory(&download_path, &db_dir, compress::DirDecoder::TarGzip)
+                .unwrap();
+            fs::remove_file(download_path).expect("failed fs::remove_file");
+        }
+    }
+
     // "63.65 GB" .tar.gz download  takes about 45-min
     // "63.65 GB" .tar.gz unpack    takes about 7-min
     // "75.47 GB" .tar    unarchive takes about 5-min
     if spec.aws_resources.is_some() {
         let aws_resources = spec.aws_resources.unwrap();
-        if aws_resources.db_backup_s3_region.is_some()
+        if !restores_db {
+            info!("STEP: skipping database backup download for the index backfill from genesis")
+        } else if aws_resources.db_backup_s3_region.is_some()
             && aws_resources.db_backup_s3_bucket.is_some()
             && aws_resources.db_backup_s3_key.is_some()
         {
@@ -459,6 +780,11 @@ pub async fn execute(log_level: &str) {
             fs::remove_file(tmp_path).expect("failed fs::remove_file");
 
             sleep(Duration::from_secs(1)).await;
+            let transfer_limit = aws_resources
+                .s3_transfer_limits
+                .clone()
+                .unwrap_or_default()
+                .node_limit(&node_kind);
             let db_backup_s3_region = aws_resources.db_backup_s3_region.clone().unwrap();
             let db_backup_s3_bucket = aws_resources.db_backup_s3_bucket.clone().unwrap();
             let db_backup_s3_key = aws_resources.db_backup_s3_key.unwrap();
@@ -475,24 +801,41 @@ pub async fn execute(log_level: &str) {
                 .await
                 .expect("failed spawn await")
                 .expect("failed aws::load_config");
-            let db_backup_s3_manager = s3::Manager::new(&db_backup_s3_config);
+            let mut db_backup_s3_manager = s3::Manager::new(&db_backup_s3_config);
+            db_backup_s3_manager.set_transfer_limit(transfer_limit.clone());
 
             // do not store in "tmp", will run out of space
+            // fixed path to resume the partial download after the restarts
             let download_path = format!(
-                "{}/{}{}",
+                "{}/.db-backup-download{}",
                 spec.avalanchego_config.db_dir,
-                random::string(10),
                 dec.ext()
             );
 
-            s3::spawn_get_object(
-                db_backup_s3_manager.clone(),
-                &db_backup_s3_bucket,
-                &db_backup_s3_key,
-                &download_path,
-            )
-            .await
-            .expect("failed s3::spawn_get_object");
+            // primary region may be unavailable, fail over to the replica and the mirrors
+            let mut sources = vec![(db_backup_s3_manager, db_backup_s3_bucket)];
+            let mut fallbacks = Vec::new();
+            if let (Some(region), Some(bucket)) = (
+                aws_resources.replica_s3_region.clone(),
+                aws_resources.replica_s3_bucket.clone(),
+            ) {
+                fallbacks.push((region, bucket));
+            }
+            for m in aws_resources.db_backup_s3_mirrors.unwrap_or_default() {
+                fallbacks.push((m.region, m.bucket));
+            }
+            for (region, bucket) in fallbacks {
+                let cfg = tokio::spawn(aws::load_config(Some(region)))
+                    .await
+                    .expect("failed spawn await")
+                    .expect("failed aws::load_config");
+                let mut s3_manager = s3::Manager::new(&cfg);
+                s3_manager.set_transfer_limit(transfer_limit.clone());
+                sources.push((s3_manager, bucket));
+            }
+            s3::spawn_get_object_from_mirrors(sources, &db_backup_s3_key, &download_path)
+                .await
+                .expect("failed s3::spawn_get_object_from_mirrors");
 
             compress::unpack_directory(&download_path, &spec.avalanchego_config.db_dir, dec)
                 .unwrap();
@@ -703,6 +1046,23 @@ pub async fn execute(log_level: &str) {
         spec.avalanchego_config.bootstrap_ids = Some(bootstrap_ids.join(","));
     }
 
+    // e.g., joining a partner-operated custom network
+    if let Some(external_bootstrap_nodes) = &spec.external_bootstrap_nodes {
+        info!(
+            "STEP: merging {} external bootstrap nodes into bootstrap IPs/IDs",
+            external_bootstrap_nodes.len()
+        );
+        let ips: Vec<String> = external_bootstrap_nodes
+            .iter()
+            .map(|n| n.ip.clone())
+            .collect();
+        let ids: Vec<String> = external_bootstrap_nodes
+            .iter()
+            .map(|n| n.node_id.clone())
+            .collect();
+        spec.avalanchego_config.merge_bootstrap_nodes(&ips, &ids);
+    }
+
     let log_dir = spec.avalanchego_config.clone().log_dir;
     fs::create_dir_all(&log_dir).expect("failed to create log_dir");
 
@@ -727,6 +1087,12 @@ pub async fn execute(log_level: &str) {
     fs::copy(&tmp_coreth_config_path, chain_config_c_path).expect("failed fs::copy");
     fs::remove_file(&tmp_coreth_config_path).expect("failed fs::remove_file");
 
+    if spec.chain_upgrades.is_some() {
+        info!("STEP: downloading chain upgrade files from S3");
+        let upgrades = download_chain_upgrades(s3_manager.clone(), &s3_bucket, &id).await;
+        place_chain_upgrades(&upgrades, &chain_config_dir);
+    }
+
     if spec.avalanchego_config.subnet_config_dir.is_some() {
         let subnet_config_dir = spec
             .avalanchego_config
@@ -758,6 +1124,78 @@ pub async fn execute(log_level: &str) {
             .expect("failed to create continuous_profiler_dir");
     };
 
+    if let Some(tracked_subnets) = &spec.tracked_subnets {
+        info!(
+            "STEP: setting up {} tracked subnets with peer allowlists",
+            tracked_subnets.len()
+        );
+        let mut whitelisted_subnets: Vec<String> =
+            match &spec.avalanchego_config.whitelisted_subnets {
+                Some(v) => v
+                    .split(',')
+                    .filter(|x| !x.is_empty())
+                    .map(String::from)
+                    .collect(),
+                None => Vec::new(),
+            };
+        let subnet_config_dir = spec
+            .avalanchego_config
+            .clone()
+            .subnet_config_dir
+            .expect("unexpected None subnet_config_dir for tracked subnets");
+        for subnet in tracked_subnets.iter() {
+            if !whitelisted_subnets.contains(&subnet.subnet_id) {
+                whitelisted_subnets.push(subnet.subnet_id.clone());
+            }
+
+            // ref. "avalanchego/config.getSubnetConfigsFromDir"
+            let cfg = subnet_config::Config {
+                validator_only: Some(subnet.validator_only),
+                allowed_nodes: subnet.allowed_nodes.clone(),
+            };
+            cfg.sync(
+                Path::new(&subnet_config_dir)
+                    .join(format!("{}.json", subnet.subnet_id))
+                    .to_str()
+                    .unwrap(),
+            )
+            .expect("failed to sync subnet config");
+        }
+        spec.avalanchego_config.whitelisted_subnets = Some(whitelisted_subnets.join(","));
+    }
+
+    // applied before the first start, so the node never accepts other peers
+    let peer_allowlist_cfg = match (
+        &spec.peer_allowlist,
+        &spec.avalanchego_config.subnet_config_dir,
+    ) {
+        (Some(cfg), Some(subnet_config_dir)) => {
+            let subnets = peer_allowlist::Subnets {
+                subnet_config_dir: subnet_config_dir.clone(),
+                whitelisted_subnets: spec
+                    .avalanchego_config
+                    .whitelisted_subnets
+                    .clone()
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter(|s| !s.is_empty())
+                    .map(String::from)
+                    .collect(),
+                tracked_subnets: spec.tracked_subnets.clone().unwrap_or_default(),
+            };
+            info!("STEP: applying peer allowlist");
+            peer_allowlist::apply_current(&s3_manager, &s3_bucket, &id, cfg, &subnets)
+                .await
+                .expect("failed to apply peer allowlist");
+            Some((cfg.clone(), subnets))
+        }
+        _ => None,
+    };
+
+    let nofile_limit = install::apply_network_tuning(spec.machine.network_tuning.as_ref());
+
+    check_indexed_db(&spec.avalanchego_config);
+
     // persist before starting the service
     spec.avalanchego_config
         .sync(None)
@@ -783,13 +1221,14 @@ Type=exec
 TimeoutStartSec=300
 Restart=always
 RestartSec=5s
-LimitNOFILE=40000
+LimitNOFILE={}
 ExecStart={} --config-file={}
 StandardOutput=append:/var/log/avalanche/avalanche.log
 StandardError=append:/var/log/avalanche/avalanche.log
 
 [Install]
 WantedBy=multi-user.target",
+        nofile_limit,
         avalanche_bin_path,
         spec.avalanchego_config.clone().config_file.unwrap(),
     );
@@ -813,6 +1252,11 @@ WantedBy=multi-user.target",
     // this can take awhile if loaded from backups or syncing from peers
     info!("'avalanched run' all success -- now waiting for local node liveness check");
     loop {
+        if let Some(password) = &api_auth_password {
+            if let Err(e) = auth::authorize(&local_node.http_endpoint, password).await {
+                warn!("failed to issue API auth token ({})", e);
+            }
+        }
         let ret = health::spawn_check(&local_node.http_endpoint, true).await;
         let (res, err) = match ret {
             Ok(res) => (res, None),
@@ -835,6 +1279,27 @@ WantedBy=multi-user.target",
         sleep(Duration::from_secs(30)).await;
     }
 
+    if spec.avalanchego_config.staking_signer_key_file.is_some() {
+        info!("STEP: publishing BLS proof of possession for the validator registration");
+        match info_api::get_node_id(&local_node.http_endpoint).await {
+            Ok(resp) => match resp.result.and_then(|r| r.node_pop) {
+                Some(pop) => {
+                    info!("BLS proof of possession {:?}", pop);
+                    s3_manager
+                        .put_bytes(
+                            serde_json::to_vec(&pop).expect("failed serde_json::to_vec"),
+                            Arc::new(s3_bucket.clone()),
+                            Arc::new(node_keys::pop_s3_key(&id, &node_id)),
+                        )
+                        .await
+                        .expect("failed s3::Manager::put_bytes");
+                }
+                None => warn!("no BLS proof of possession (avalanchego older than v1.9.0?)"),
+            },
+            Err(e) => warn!("failed info.getNodeID ({})", e),
+        }
+    }
+
     info!("spawning async routines...");
     let node_info_ready_s3_key = {
         if matches!(node_kind, node::Kind::Anchor) {
@@ -851,16 +1316,41 @@ WantedBy=multi-user.target",
             .encode()
         }
     };
+    let dimensions = metric_schema::Dimensions {
+        cluster_id: id.clone(),
+        node_kind: node_kind.as_str().to_string(),
+        node_id: local_node.node_id.clone(),
+        availability_zone: az.clone(),
+    };
+    let otlp_exporter = spec
+        .otlp
+        .clone()
+        .map(|v| otlp::Exporter::new(v, &dimensions, &instance_id));
+    let metrics_publisher = Arc::new(metric_schema::Publisher {
+        cw_manager: cw_manager.clone(),
+        namespace: aws_resources
+            .clone()
+            .cloudwatch_avalanche_metrics_namespace
+            .unwrap(),
+        version: aws_resources
+            .cloudwatch_metrics_schema_version
+            .unwrap_or(metric_schema::VERSION_1),
+        dimensions,
+        legacy_namespace: aws_resources.cloudwatch_metrics_legacy_namespace.clone(),
+        otlp: otlp_exporter
+            .clone()
+            .filter(|v| v.cfg.metrics)
+            .map(Arc::new),
+    });
     let mut handles = vec![
         tokio::spawn(fetch_metrics(
-            cw_manager.clone(),
-            Arc::new(
-                aws_resources
-                    .clone()
-                    .cloudwatch_avalanche_metrics_namespace
-                    .unwrap(),
-            ),
+            metrics_publisher.clone(),
             Arc::new(local_node.http_endpoint.clone()),
+            Arc::new(api_auth_password.clone()),
+        )),
+        tokio::spawn(publish_clock_offset(
+            metrics_publisher.clone(),
+            Arc::new(node_kind.as_str().to_string()),
         )),
         tokio::spawn(publish_node_info_ready(
             s3_manager.clone(),
@@ -872,42 +1362,196 @@ WantedBy=multi-user.target",
                 spec.coreth_config.clone(),
             )),
         )),
+        tokio::spawn(publish_heartbeat(
+            s3_manager.clone(),
+            Arc::new(s3_bucket.clone()),
+            Arc::new(
+                avalanche_ops::StorageNamespace::Heartbeat(id.clone(), instance_id.clone())
+                    .encode(),
+            ),
+            Arc::new(local_node.clone()),
+            Arc::new(avalanche_bin_path.clone()),
+            Arc::new(spec.avalanchego_config.clone()),
+            agent_registry.clone(),
+        )),
         tokio::spawn(check_node_update(
             s3_manager.clone(),
             Arc::new(s3_bucket.clone()),
             Arc::new(id.clone()),
+            Arc::new(instance_id.clone()),
             Arc::new(avalanche_bin_path),
+            Arc::new(spec.install_artifacts.clone()),
+        )),
+        tokio::spawn(check_chain_upgrades(
+            s3_manager.clone(),
+            Arc::new(s3_bucket.clone()),
+            Arc::new(id.clone()),
+            Arc::new(instance_id.clone()),
+            Arc::new(chain_config_dir.clone()),
         )),
     ];
+    if let Some((cfg, subnets)) = peer_allowlist_cfg {
+        handles.push(tokio::spawn(peer_allowlist::check_update(
+            s3_manager.clone(),
+            Arc::new(s3_bucket.clone()),
+            Arc::new(id.clone()),
+            Arc::new(instance_id.clone()),
+            Arc::new(local_node.node_id.clone()),
+            Arc::new(cfg),
+            Arc::new(subnets),
+        )));
+    }
+    if let Some(exporter) = otlp_exporter.filter(|v| v.cfg.logs) {
+        handles.push(tokio::spawn(otlp::export_logs_loop(
+            exporter,
+            otlp_log_file_paths,
+        )));
+    }
     if aws_resources.db_backup_s3_bucket.is_some() {
+        let transfer_limit = aws_resources
+            .s3_transfer_limits
+            .clone()
+            .unwrap_or_default()
+            .node_limit(&node_kind);
         handles.push(tokio::spawn(print_backup_commands(
+            Arc::new(transfer_limit),
             Arc::new(aws_resources.db_backup_s3_region.clone().unwrap()),
             Arc::new(aws_resources.db_backup_s3_bucket.clone().unwrap()),
+            Arc::new(aws_resources.replica_s3_region.clone()),
+            Arc::new(aws_resources.replica_s3_bucket.clone()),
             Arc::new(id.clone()),
             Arc::new(spec.avalanchego_config.network_id),
-            Arc::new(spec.avalanchego_config.db_dir),
+            Arc::new(spec.avalanchego_config.db_dir.clone()),
         )));
     }
 
-    info!("STEP: blocking on handles via JoinHandle");
-    for handle in handles {
-        handle.await.expect("failed handle");
+    // only tagged with "register_on_readiness" for the NLB target node classes
+    let mut nlb_target: Option<public_snapshot::NlbTarget> = None;
+    if !nlb_target_group_arn.is_empty() {
+        let weighted_ports = match &aws_resources.nlb_target_group {
+            Some(tg) if !tg.weights.is_empty() => {
+                let instance_type = ec2::fetch_instance_type()
+                    .await
+                    .expect("failed ec2::fetch_instance_type");
+                let ports = tg.weighted_ports(&instance_type);
+                info!(
+                    "NLB target weight {} for instance type {}",
+                    ports.len() + 1,
+                    instance_type
+                );
+                ports
+            }
+            _ => Vec::new(),
+        };
+        nlb_target = Some(public_snapshot::NlbTarget {
+            region: aws_resources.region.clone(),
+            target_group_arn: nlb_target_group_arn.clone(),
+            weighted_ports: weighted_ports.clone(),
+            deregistration_delay_seconds: aws_resources
+                .nlb_target_group
+                .as_ref()
+                .and_then(|tg| tg.deregistration_delay_seconds)
+                .unwrap_or(60),
+        });
+        handles.push(tokio::spawn(manage_nlb_target(
+            Arc::new(aws_resources.region.clone()),
+            Arc::new(nlb_target_group_arn),
+            Arc::new(instance_id.clone()),
+            Arc::new(weighted_ports),
+            Arc::new(spec.avalanchego_config.http_port),
+            Arc::new(local_node.http_endpoint.clone()),
+            Arc::new(api_auth_password.clone()),
+        )));
     }
-}
 
-async fn fetch_metrics(
-    cw_manager: cloudwatch::Manager,
-    cw_namespace: Arc<String>,
-    metrics_ep: Arc<String>,
-) {
-    info!("STEP: starting 'fetch_metrics' in 2-minute");
-    sleep(Duration::from_secs(120)).await;
+    if let Some(cfg) = public_snapshot_cfg {
+        let mut subnet_ids: Vec<String> = spec
+            .avalanchego_config
+            .whitelisted_subnets
+            .clone()
+            .unwrap_or_default()
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect();
+        for s in spec.tracked_subnets.clone().unwrap_or_default() {
+            if !subnet_ids.contains(&s.subnet_id) {
+                subnet_ids.push(s.subnet_id);
+            }
+        }
+        handles.push(tokio::spawn(public_snapshot::publish(
+            s3_manager.clone(),
+            avalanche_ops::public_snapshot::lease_manager(&shared_config),
+            Arc::new(cfg),
+            Arc::new(id.clone()),
+            Arc::new(instance_id.clone()),
+            Arc::new(nlb_target),
+            Arc::new(spec.avalanchego_config.network_id),
+            Arc::new(subnet_ids),
+            Arc::new(spec.avalanchego_config.db_dir.clone()),
+            Arc::new(local_node.http_endpoint.clone()),
+            Arc::new(api_auth_password.clone()),
+        )));
+    }
+
+    if spec.usage_report == Some(true) {
+        handles.push(tokio::spawn(publish_usage_report(
+            ec2_manager.clone(),
+            cw_manager.clone(),
+            s3_manager.clone(),
+            Arc::new(s3_bucket.clone()),
+            Arc::new(usage::NodeUsage {
+                cluster_id: id.clone(),
+                instance_id: instance_id.clone(),
+                node_kind: node_kind.as_str().to_string(),
+                node_id: local_node.node_id.clone(),
+                availability_zone: az.clone(),
+                ..Default::default()
+            }),
+        )));
+    }
+
+    if let Some(policy) = spec.remediation.clone() {
+        if policy.is_enabled() {
+            handles.push(tokio::spawn(remediate(
+                metrics_publisher.clone(),
+                Arc::new(node_kind.as_str().to_string()),
+                Arc::new(policy),
+                Arc::new(local_node.http_endpoint.clone()),
+                Arc::new(api_auth_password),
+                Arc::new(format!("{}/main.log", spec.avalanchego_config.log_dir)),
+                Arc::new(spec.avalanchego_config.db_dir.clone()),
+                agent_registry.clone(),
+            )));
+        }
+    }
+
+    info!("STEP: blocking on handles via JoinHandle");
+    for handle in handles {
+        handle.await.expect("failed handle");
+    }
+}
+
+async fn fetch_metrics(
+    metrics_publisher: Arc<metric_schema::Publisher>,
+    metrics_ep: Arc<String>,
+    api_auth_password: Arc<Option<String>>,
+) {
+    info!("STEP: starting 'fetch_metrics' in 2-minute");
+    sleep(Duration::from_secs(120)).await;
 
     let mut prev_metrics: Option<metrics::Metrics> = None;
     loop {
         info!("STEP: fetching metrics in 1-min");
         sleep(Duration::from_secs(60)).await;
 
+        // re-issues since the node restarts invalidate the tokens
+        if let Some(password) = api_auth_password.as_ref() {
+            if let Err(e) = auth::authorize(metrics_ep.as_str(), password).await {
+                warn!("failed to issue API auth token {}, retrying...", e);
+                continue;
+            }
+        }
         let cur_metrics = match metrics::spawn_get(metrics_ep.as_str()).await {
             Ok(v) => v,
             Err(e) => {
@@ -916,12 +1560,9 @@ async fn fetch_metrics(
             }
         };
 
-        match cloudwatch::spawn_put_metric_data(
-            cw_manager.clone(),
-            cw_namespace.as_str(),
-            cur_metrics.to_cw_metric_data(prev_metrics.clone()),
-        )
-        .await
+        match metrics_publisher
+            .publish(cur_metrics.to_cw_metric_data(prev_metrics.clone()))
+            .await
         {
             Ok(_) => {}
             Err(e) => {
@@ -936,6 +1577,294 @@ async fn fetch_metrics(
 
 /// if run in anchor nodes, the uploaded file will be downloaded
 /// in bootstrapping non-anchor nodes for custom networks
+/// Publishes the chrony clock offset for the clock offset alarm.
+async fn publish_clock_offset(
+    metrics_publisher: Arc<metric_schema::Publisher>,
+    node_kind: Arc<String>,
+) {
+    info!("STEP: starting 'publish_clock_offset'");
+    loop {
+        sleep(Duration::from_secs(60)).await;
+
+        let tracking = match time_sync::Tracking::fetch() {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("failed to fetch chrony tracking {}, retrying...", e);
+                continue;
+            }
+        };
+        if !tracking.is_synchronized() {
+            warn!(
+                "clock not synchronized (leap status '{}', stratum {})",
+                tracking.leap_status, tracking.stratum
+            );
+        }
+        if let Err(e) = metrics_publisher
+            .publish(tracking.to_cw_metric_data(node_kind.as_str()))
+            .await
+        {
+            warn!("failed to put clock offset metric data {}, retrying...", e);
+        }
+    }
+}
+
+/// Compresses and envelope-encrypts the key file, and uploads to S3.
+async fn backup_key(
+    s3_manager: s3::Manager,
+    envelope: envelope::Envelope,
+    key_path: &str,
+    s3_bucket: &str,
+    s3_key: &str,
+) {
+    let tmp_compressed_path = random::tmp_path(15, Some(".zstd")).unwrap();
+    let tmp_encrypted_path = random::tmp_path(15, Some(".zstd.encrypted")).unwrap();
+
+    compress::pack_file(key_path, &tmp_compressed_path, compress::Encoder::Zstd(3))
+        .expect("failed pack_file key_path");
+    envelope::spawn_seal_aes_256_file(envelope, &tmp_compressed_path, &tmp_encrypted_path)
+        .await
+        .expect("failed envelope::spawn_seal_aes_256_file");
+    s3::spawn_put_object(s3_manager, &tmp_encrypted_path, s3_bucket, s3_key)
+        .await
+        .expect("failed s3::spawn_put_object");
+
+    fs::remove_file(tmp_compressed_path).expect("failed fs::remove_file");
+    fs::remove_file(tmp_encrypted_path).expect("failed fs::remove_file");
+}
+
+/// Downloads the key file backed up by "backup_key", and decrypts.
+async fn restore_key(
+    s3_manager: s3::Manager,
+    envelope: envelope::Envelope,
+    s3_bucket: &str,
+    s3_key: &str,
+    key_path: &str,
+) {
+    let tmp_encrypted_path = random::tmp_path(15, Some(".zstd.encrypted")).unwrap();
+    let tmp_compressed_path = random::tmp_path(15, Some(".zstd")).unwrap();
+
+    s3::spawn_get_object(s3_manager, s3_bucket, s3_key, &tmp_encrypted_path)
+        .await
+        .expect("failed s3::spawn_get_object");
+    envelope::spawn_unseal_aes_256_file(envelope, &tmp_encrypted_path, &tmp_compressed_path)
+        .await
+        .expect("failed envelope::spawn_unseal_aes_256_file");
+    compress::unpack_file(&tmp_compressed_path, key_path, compress::Decoder::Zstd)
+        .expect("failed unpack_file key_path");
+
+    fs::remove_file(tmp_compressed_path).expect("failed fs::remove_file");
+    fs::remove_file(tmp_encrypted_path).expect("failed fs::remove_file");
+}
+
+/// Registers the instance in the NLB target group while the node is
+/// bootstrapped and healthy, and deregisters it otherwise (e.g., re-syncing),
+/// so that the clients are only routed to the ready nodes.
+/// The node is not ready while the public snapshot is in progress.
+/// The weighted instance is registered with the extra ports as well,
+/// redirected to the HTTP port.
+async fn manage_nlb_target(
+    region: Arc<String>,
+    target_group_arn: Arc<String>,
+    instance_id: Arc<String>,
+    weighted_ports: Arc<Vec<u32>>,
+    http_port: Arc<u32>,
+    http_endpoint: Arc<String>,
+    api_auth_password: Arc<Option<String>>,
+) {
+    info!(
+        "STEP: starting 'manage_nlb_target' for {}",
+        target_group_arn
+    );
+    for port in weighted_ports.iter() {
+        bash::run(&elbv2::port_redirect_cmd(*port, *http_port))
+            .expect("failed to redirect the weighted NLB target port");
+    }
+
+    let mut registered: Option<bool> = None;
+    loop {
+        if let Some(password) = api_auth_password.as_ref() {
+            if let Err(e) = auth::authorize(http_endpoint.as_str(), password).await {
+                warn!("failed to issue API auth token ({})", e);
+            }
+        }
+        // "ext/health" (not liveness) fails until the chains are bootstrapped
+        let ready = match health::spawn_check(http_endpoint.as_str(), false).await {
+            Ok(res) => res.healthy == Some(true),
+            Err(e) => {
+                warn!("health check failed ({})", e);
+                false
+            }
+        };
+        // still healthy while the public snapshot drains the connections
+        let ready =
+            ready && !Path::new(avalanche_ops::public_snapshot::IN_PROGRESS_FILE_PATH).exists();
+        if registered != Some(ready) {
+            info!(
+                "{} instance {} in the NLB target group (ready {})",
+                if ready {
+                    "registering"
+                } else {
+                    "deregistering"
+                },
+                instance_id,
+                ready
+            );
+            match bash::run(&elbv2::target_registration_cmd(
+                region.as_str(),
+                target_group_arn.as_str(),
+                instance_id.as_str(),
+                weighted_ports.as_slice(),
+                ready,
+            )) {
+                Ok(_) => registered = Some(ready),
+                Err(e) => warn!("failed to update the NLB target {}", e),
+            }
+        }
+
+        sleep(Duration::from_secs(30)).await;
+    }
+}
+
+/// Applies the remediation policies, once the node was healthy.
+/// Re-bootstrapping removes the database and exits non-zero, so systemd
+/// restarts "avalanched" which downloads the database backup again.
+#[allow(clippy::too_many_arguments)]
+async fn remediate(
+    metrics_publisher: Arc<metric_schema::Publisher>,
+    node_kind: Arc<String>,
+    policy: Arc<remediation::Remediation>,
+    http_endpoint: Arc<String>,
+    api_auth_password: Arc<Option<String>>,
+    avalanchego_log_path: Arc<String>,
+    db_dir: Arc<String>,
+    agent_registry: Arc<agent_metrics::Registry>,
+) {
+    info!("STEP: starting 'remediate' with {:?}", policy);
+
+    let started = SystemTime::now();
+    let mut tracker = remediation::Tracker::new(policy.as_ref().clone());
+    // only scan the logs written from now on
+    let mut log_offset = fs::metadata(avalanchego_log_path.as_str())
+        .map(|m| m.len())
+        .unwrap_or(0);
+    let mut became_healthy = false;
+    loop {
+        sleep(Duration::from_secs(60)).await;
+
+        if let Some(password) = api_auth_password.as_ref() {
+            if let Err(e) = auth::authorize(http_endpoint.as_str(), password).await {
+                warn!("failed to issue API auth token ({})", e);
+            }
+        }
+        let healthy = match health::spawn_check(http_endpoint.as_str(), true).await {
+            Ok(res) => res.healthy == Some(true),
+            Err(e) => {
+                warn!("health/liveness check failed ({})", e);
+                false
+            }
+        };
+        // do not restart the node that is still bootstrapping
+        became_healthy = became_healthy || healthy;
+        let db_corrupted = policy.rebootstrap_on_db_corruption
+            && scan_db_corruption(avalanchego_log_path.as_str(), &mut log_offset);
+        let uptime_secs = fs::read_to_string("/proc/uptime")
+            .ok()
+            .and_then(|s| s.split_whitespace().next().map(String::from))
+            .and_then(|s| s.parse::<f64>().ok())
+            .unwrap_or(0_f64) as u64;
+
+        let now = started.elapsed().unwrap_or_default().as_secs();
+        if !became_healthy && !db_corrupted {
+            continue;
+        }
+        // avalanchego is stopped on purpose
+        if Path::new(db_check::IN_PROGRESS_FILE_PATH).exists() {
+            info!("skipping remediation while 'check-db' is in progress");
+            continue;
+        }
+        if Path::new(avalanche_ops::public_snapshot::IN_PROGRESS_FILE_PATH).exists() {
+            info!("skipping remediation while the public snapshot is in progress");
+            continue;
+        }
+        let action = match tracker.observe(now, healthy, db_corrupted, uptime_secs) {
+            Some(v) => v,
+            None => continue,
+        };
+        warn!("remediation action '{}'", action.as_str());
+        log_remediation(&action, healthy, db_corrupted);
+        agent_registry.remediation_action(&action);
+        if let Err(e) = metrics_publisher
+            .publish(action.to_cw_metric_data(node_kind.as_str()))
+            .await
+        {
+            warn!("failed to put remediation metric data {}", e);
+        }
+
+        match action {
+            remediation::Action::RestartNode => {
+                if let Err(e) = bash::run("sudo systemctl restart --no-block avalanche.service") {
+                    warn!("failed systemctl restart command {}", e);
+                }
+            }
+            remediation::Action::Rebootstrap => {
+                bash::run("sudo systemctl stop avalanche.service")
+                    .expect("failed systemctl stop command");
+                fs::remove_dir_all(db_dir.as_str()).expect("failed fs::remove_dir_all");
+                fs::create_dir_all(db_dir.as_str()).expect("failed fs::create_dir_all");
+                panic!(
+                    "removed corrupted database '{}', restarting avalanched to re-bootstrap",
+                    db_dir
+                );
+            }
+            remediation::Action::RebootInstance => {
+                if let Err(e) = bash::run("sudo reboot") {
+                    warn!("failed reboot command {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Returns true if the log lines appended since "offset" report
+/// the database corruption, and moves "offset" to the end of the file.
+fn scan_db_corruption(log_path: &str, offset: &mut u64) -> bool {
+    let mut f = match File::open(log_path) {
+        Ok(f) => f,
+        Err(_) => return false,
+    };
+    let len = f.metadata().map(|m| m.len()).unwrap_or(0);
+    // rotated
+    if len < *offset {
+        *offset = 0;
+    }
+    let mut appended = Vec::new();
+    if f.seek(SeekFrom::Start(*offset)).is_err() || f.read_to_end(&mut appended).is_err() {
+        return false;
+    }
+    *offset += appended.len() as u64;
+    String::from_utf8_lossy(&appended)
+        .lines()
+        .any(remediation::is_db_corruption)
+}
+
+/// Appends the action to the remediation log (shipped to CloudWatch Logs).
+fn log_remediation(action: &remediation::Action, healthy: bool, db_corrupted: bool) {
+    let line = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "action": action.as_str(),
+        "healthy": healthy,
+        "db_corrupted": db_corrupted,
+    });
+    let ret = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(remediation::ACTION_LOG_FILE_PATH)
+        .and_then(|mut f| writeln!(f, "{}", line));
+    if let Err(e) = ret {
+        warn!("failed to write remediation log {}", e);
+    }
+}
+
 async fn publish_node_info_ready(
     s3_manager: s3::Manager,
     s3_bucket: Arc<String>,
@@ -968,11 +1897,386 @@ async fn publish_node_info_ready(
     }
 }
 
+/// Writes the usage of the node for the previous UTC day, once the day ends
+/// (plus the per-node delay), and rebuilds the cluster usage report
+/// from the usages of all nodes reported so far.
+/// Checks every hour, so the restarted node still reports the missed day.
+async fn publish_usage_report(
+    ec2_manager: ec2::Manager,
+    cw_manager: cloudwatch::Manager,
+    s3_manager: s3::Manager,
+    s3_bucket: Arc<String>,
+    node: Arc<usage::NodeUsage>,
+) {
+    info!("STEP: starting 'publish_usage_report'");
+    let delay = chrono::Duration::minutes(usage::report_delay_minutes(&node.instance_id) as i64);
+
+    loop {
+        let date = usage::previous_day(chrono::Utc::now() - delay);
+        let s3_key = avalanche_ops::StorageNamespace::UsageNode(
+            node.cluster_id.clone(),
+            date.clone(),
+            node.instance_id.clone(),
+        )
+        .encode();
+        let reported = match s3::spawn_list_objects(
+            s3_manager.clone(),
+            s3_bucket.as_str(),
+            Some(s3_key.clone()),
+        )
+        .await
+        {
+            Ok(objects) => objects.iter().any(|o| o.key() == Some(s3_key.as_str())),
+            Err(e) => {
+                warn!("failed to list usage {}, retrying...", e);
+                sleep(Duration::from_secs(300)).await;
+                continue;
+            }
+        };
+        if !reported {
+            match collect_node_usage(&ec2_manager, &cw_manager, &node, &date).await {
+                Ok(v) => {
+                    if let Err(e) = put_usage_report(&s3_manager, &s3_bucket, &v, &s3_key).await {
+                        warn!("failed to publish usage report {}, retrying...", e);
+                        sleep(Duration::from_secs(300)).await;
+                        continue;
+                    }
+                    info!("published {} usage to {}", date, s3_key);
+                }
+                Err(e) => {
+                    warn!("failed to collect usage {}, retrying...", e);
+                    sleep(Duration::from_secs(300)).await;
+                    continue;
+                }
+            }
+        }
+
+        info!("sleeping 1-hour for 'publish_usage_report'");
+        sleep(Duration::from_secs(3600)).await;
+    }
+}
+
+async fn collect_node_usage(
+    ec2_manager: &ec2::Manager,
+    cw_manager: &cloudwatch::Manager,
+    node: &usage::NodeUsage,
+    date: &str,
+) -> io::Result<usage::NodeUsage> {
+    let (start, end) = usage::day_range(date)?;
+    let inst = ec2_manager
+        .describe_instance(&node.instance_id)
+        .await
+        .map_err(|e| Error::new(ErrorKind::Other, e.to_string()))?;
+    let instance_hours = usage::running_hours(ec2::Droplet::new(&inst).launched_at_utc, start, end);
+
+    let volume_ids: Vec<String> = inst
+        .block_device_mappings()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|m| m.ebs().and_then(|ebs| ebs.volume_id()).map(String::from))
+        .collect();
+    let volume_gb: i32 = ec2_manager
+        .list_volume_sizes(&volume_ids)
+        .await
+        .map_err(|e| Error::new(ErrorKind::Other, e.to_string()))?
+        .iter()
+        .map(|(_, size)| size)
+        .sum();
+
+    let queries = vec![
+        cloudwatch::Query::stat("network_in", "NetworkIn", "Sum")
+            .dimension("InstanceId", &node.instance_id),
+        cloudwatch::Query::stat("network_out", "NetworkOut", "Sum")
+            .dimension("InstanceId", &node.instance_id),
+    ];
+    let results = cw_manager
+        .get_metric_data("AWS/EC2", &queries, start, end, 86400)
+        .await
+        .map_err(|e| Error::new(ErrorKind::Other, e.to_string()))?;
+    let sum = |id: &str| -> f64 {
+        results
+            .iter()
+            .find(|r| r.id == id)
+            .map(|r| r.values.iter().sum())
+            .unwrap_or_default()
+    };
+
+    Ok(usage::NodeUsage {
+        date: date.to_string(),
+        instance_type: inst
+            .instance_type()
+            .map(|v| v.as_str().to_string())
+            .unwrap_or_default(),
+        instance_hours,
+        ebs_gb_hours: volume_gb as f64 * instance_hours,
+        network_in_bytes: sum("network_in"),
+        network_out_bytes: sum("network_out"),
+        ..node.clone()
+    })
+}
+
+/// Writes the node usage, and rebuilds the cluster report of the day
+/// from the node usages in S3.
+async fn put_usage_report(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    node_usage: &usage::NodeUsage,
+    s3_key: &str,
+) -> io::Result<()> {
+    let to_io = |e: avalanche_ops::errors::Error| Error::new(ErrorKind::Other, e.to_string());
+    let tmp_path = random::tmp_path(10, Some(".json"))?;
+    fs::write(&tmp_path, serde_json::to_vec(node_usage)?)?;
+    s3::spawn_put_object(s3_manager.clone(), &tmp_path, s3_bucket, s3_key)
+        .await
+        .map_err(to_io)?;
+
+    let nodes_dir = avalanche_ops::StorageNamespace::UsageNodesDir(
+        node_usage.cluster_id.clone(),
+        node_usage.date.clone(),
+    )
+    .encode();
+    let objects = s3::spawn_list_objects(s3_manager.clone(), s3_bucket, Some(nodes_dir))
+        .await
+        .map_err(to_io)?;
+    let mut nodes = Vec::new();
+    for o in objects.iter() {
+        let key = o.key().unwrap_or_default();
+        s3::spawn_get_object(s3_manager.clone(), s3_bucket, key, &tmp_path)
+            .await
+            .map_err(to_io)?;
+        match serde_json::from_slice::<usage::NodeUsage>(&fs::read(&tmp_path)?) {
+            Ok(v) => nodes.push(v),
+            Err(e) => warn!("skipping invalid usage {} ({})", key, e),
+        }
+    }
+    let report = usage::Report::new(
+        &node_usage.cluster_id,
+        &node_usage.date,
+        nodes,
+        chrono::Utc::now(),
+    );
+
+    for (format, d) in [
+        ("json", serde_json::to_vec_pretty(&report)?),
+        ("csv", report.to_csv().into_bytes()),
+    ] {
+        fs::write(&tmp_path, d)?;
+        let key = avalanche_ops::StorageNamespace::UsageReport(
+            report.cluster_id.clone(),
+            report.date.clone(),
+            format.to_string(),
+        )
+        .encode();
+        s3::spawn_put_object(s3_manager.clone(), &tmp_path, s3_bucket, &key)
+            .await
+            .map_err(to_io)?;
+    }
+    fs::remove_file(&tmp_path)?;
+    Ok(())
+}
+
+/// avalanchego refuses to enable the index on the database bootstrapped
+/// without the index, unless "index-allow-incomplete". Removes such database
+/// to backfill the full index from genesis, and marks the indexed database.
+fn check_indexed_db(avalanchego_config: &avalanchego_config::Config) {
+    let marker = Path::new(indexer::INDEXED_DB_FILE_PATH);
+    if avalanchego_config.index_enabled != Some(true) {
+        if marker.exists() {
+            fs::remove_file(marker).expect("failed fs::remove_file");
+        }
+        return;
+    }
+
+    let db_dir = &avalanchego_config.db_dir;
+    let db_empty = fs::read_dir(db_dir)
+        .map(|mut d| d.next().is_none())
+        .unwrap_or(true);
+    if !db_empty && !marker.exists() && avalanchego_config.index_allow_incomplete != Some(true) {
+        warn!(
+            "STEP: removing database '{}' bootstrapped without the index, to backfill from genesis",
+            db_dir
+        );
+        fs::remove_dir_all(db_dir).expect("failed fs::remove_dir_all");
+        fs::create_dir_all(db_dir).expect("failed fs::create_dir_all");
+    }
+    fs::create_dir_all(marker.parent().unwrap()).expect("failed fs::create_dir_all");
+    fs::write(marker, b"").expect("failed fs::write");
+}
+
+/// Periodically uploads the signed node status, so the operator
+/// can see the fleet inventory without SSH or RPC access to the nodes.
+async fn publish_heartbeat(
+    s3_manager: s3::Manager,
+    s3_bucket: Arc<String>,
+    s3_key: Arc<String>,
+    local_node: Arc<node::Node>,
+    avalanche_bin_path: Arc<String>,
+    avalanchego_config: Arc<avalanchego_config::Config>,
+    agent_registry: Arc<agent_metrics::Registry>,
+) {
+    info!("STEP: starting 'publish_heartbeat'");
+
+    loop {
+        let index_backfill = if avalanchego_config.index_enabled == Some(true) {
+            let backfill = index::get_backfill(&local_node.http_endpoint).await;
+            for b in backfill.iter() {
+                info!(
+                    "{}-chain index backfill {}/{} ({:.2}%)",
+                    b.chain,
+                    b.indexed,
+                    b.height,
+                    b.progress_percent()
+                );
+            }
+            Some(backfill)
+        } else {
+            None
+        };
+        let healthy = health::spawn_check(&local_node.http_endpoint, false)
+            .await
+            .ok()
+            .and_then(|r| r.healthy);
+        let signed = match collect_heartbeat(&local_node, &avalanche_bin_path, &avalanchego_config)
+            .and_then(|mut hb| {
+                hb.index_backfill = index_backfill.clone();
+                hb.healthy = healthy;
+                agent_registry.set_heartbeat(&hb);
+                heartbeat::Signed::sign(
+                    &hb,
+                    avalanchego_config.staking_tls_key_file.as_ref().unwrap(),
+                    avalanchego_config.staking_tls_cert_file.as_ref().unwrap(),
+                )
+            }) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("failed to collect heartbeat {}, retrying...", e);
+                sleep(Duration::from_secs(60)).await;
+                continue;
+            }
+        };
+
+        let tmp_path = random::tmp_path(10, Some(".json")).expect("unexpected tmp_path failure");
+        let d = serde_json::to_vec(&signed).expect("failed to serialize heartbeat");
+        fs::write(&tmp_path, d).expect("failed fs::write");
+        match s3::spawn_put_object(
+            s3_manager.clone(),
+            &tmp_path,
+            s3_bucket.as_str(),
+            s3_key.as_str(),
+        )
+        .await
+        {
+            Ok(_) => {
+                info!("published heartbeat to {}", s3_key);
+                agent_registry.heartbeat_published(
+                    SystemTime::now()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs(),
+                );
+            }
+            Err(e) => warn!("failed to publish heartbeat {}, retrying...", e),
+        }
+        fs::remove_file(&tmp_path).expect("failed fs::remove_file");
+
+        info!("sleeping 5-min for 'publish_heartbeat'");
+        sleep(Duration::from_secs(300)).await;
+    }
+}
+
+fn collect_heartbeat(
+    local_node: &node::Node,
+    avalanche_bin_path: &str,
+    avalanchego_config: &avalanchego_config::Config,
+) -> io::Result<heartbeat::Heartbeat> {
+    // e.g., "avalanche/1.7.10 [database=v1.4.5, commit=...]"
+    let (avalanchego_version, _) = bash::run(&format!("{} --version", avalanche_bin_path))?;
+
+    // e.g., "  8319852544 5287432192"
+    let (df, _) = bash::run(&format!(
+        "df -B1 --output=size,avail {} | tail -1",
+        avalanchego_config.db_dir
+    ))?;
+    let df: Vec<u64> = df
+        .split_whitespace()
+        .filter_map(|v| v.parse::<u64>().ok())
+        .collect();
+    if df.len() != 2 {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("unexpected df output {:?}", df),
+        ));
+    }
+
+    // e.g., "350735.47 4949721.17"
+    let uptime = fs::read_to_string("/proc/uptime")?;
+    let uptime_seconds = uptime
+        .split_whitespace()
+        .next()
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(0_f64) as u64;
+
+    let last_backup = fs::read_to_string(heartbeat::LAST_BACKUP_FILE_PATH)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok());
+
+    let (config_sha256, config) = match &avalanchego_config.config_file {
+        Some(v) => (
+            hash::sha256_file(v)?,
+            serde_json::from_slice(&fs::read(v)?).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("failed to parse avalanchego config {}", e),
+                )
+            })?,
+        ),
+        None => (String::new(), BTreeMap::new()),
+    };
+
+    let mut plugins = BTreeMap::new();
+    let plugins_dir = install::get_plugins_dir(avalanche_bin_path);
+    if Path::new(&plugins_dir).exists() {
+        for entry in fs::read_dir(&plugins_dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name().to_str().unwrap().to_string();
+            plugins.insert(
+                file_name,
+                hash::sha256_file(entry.path().to_str().unwrap())?,
+            );
+        }
+    }
+
+    Ok(heartbeat::Heartbeat {
+        node_id: local_node.node_id.clone(),
+        machine_id: local_node.machine_id.clone(),
+        kind: local_node.kind.clone(),
+        public_ip: local_node.public_ip.clone(),
+        avalanched_version: env!("CARGO_PKG_VERSION").to_string(),
+        avalanchego_version: avalanchego_version.trim().to_string(),
+        disk_total_bytes: df[0],
+        disk_available_bytes: df[1],
+        uptime_seconds,
+        last_backup,
+        config_sha256,
+        avalanchego_config: config,
+        avalanchego_bin_sha256: hash::sha256_file(avalanche_bin_path)?,
+        plugins,
+        index_backfill: None,
+        healthy: None,
+        timestamp: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    })
+}
+
 async fn check_node_update(
     s3_manager: s3::Manager,
     s3_bucket: Arc<String>,
     id: Arc<String>,
+    machine_id: Arc<String>,
     avalanche_bin_path: Arc<String>,
+    install_artifacts: Arc<avalanche_ops::InstallArtifacts>,
 ) {
     info!("STEP: starting 'check_node_update'");
 
@@ -1028,95 +2332,246 @@ async fn check_node_update(
             );
             continue;
         }
+        let event_s3_key =
+            avalanche_ops::StorageNamespace::EventsUpdateArtifactsEvent(id.to_string()).encode();
+        if !is_rollout_target(&s3_manager, &s3_bucket, &event_s3_key, &machine_id).await {
+            info!("update artifacts event does not target this node, skipping");
+            sleep(Duration::from_secs(240)).await; // sleep to prevent duplicate checks
+            continue;
+        }
 
-        info!("STEP: downloading avalanched binary from S3");
+        if let Err(e) = crate::upgrade::install_update_artifacts(
+            &s3_manager,
+            &s3_bucket,
+            &id,
+            &avalanche_bin_path,
+            &install_artifacts,
+        )
+        .await
+        {
+            warn!("refusing to install the updated artifacts ({})", e);
+            sleep(Duration::from_secs(240)).await; // sleep to prevent duplicate checks
+            continue;
+        }
 
-        // TODO: replace "avalanched" itself?
-        // TODO: fs::copy fails with 'Os { code: 26, kind: ExecutableFileBusy, message: "Text file busy" }'
-        // can't replace the process itself...
+        // updated the avalanched itself, so sleep for cloudwatch logs and restart
+        warn!("artifacts have been updated... will trigger avalanched restart by panic here...");
+        sleep(Duration::from_secs(240)).await; // sleep to prevent duplicate updates
+        panic!("panic avalanched to trigger restarts via systemd service!!!")
+    }
+}
 
-        info!("STEP: downloading avalanche binary from S3");
-        let tmp_avalanche_bin_compressed_path = random::tmp_path(15, Some(".zstd")).unwrap();
-        s3::spawn_get_object(
-                    s3_manager.clone(),
-                    &s3_bucket,
-                    &avalanche_ops::StorageNamespace::EventsUpdateArtifactsInstallDirAvalancheBinCompressed(id.to_string()).encode(),
-                    &tmp_avalanche_bin_compressed_path,
-                )
-                .await
-                .expect("failed s3::spawn_get_object");
+/// Checks the chain upgrade event, and places the new upgrade files
+/// only when all activation timestamps are still in the future,
+/// so that every node in the fleet restarts before the activation.
+async fn check_chain_upgrades(
+    s3_manager: s3::Manager,
+    s3_bucket: Arc<String>,
+    id: Arc<String>,
+    machine_id: Arc<String>,
+    chain_config_dir: Arc<String>,
+) {
+    info!("STEP: starting 'check_chain_upgrades'");
 
-        warn!("stopping avalanche.service before unpack...");
-        bash::run("sudo systemctl stop avalanche.service").expect("failed systemctl stop command");
-        warn!("stopped avalanche.service before unpack...");
-        sleep(Duration::from_secs(10)).await;
+    let mut last_applied_unix = 0_f64;
+    loop {
+        info!("sleeping 3-min for 'check_chain_upgrades'");
+        sleep(Duration::from_secs(180)).await;
 
-        compress::unpack_file(
-            &tmp_avalanche_bin_compressed_path,
-            avalanche_bin_path.as_str(),
-            compress::Decoder::Zstd,
+        info!("STEP: checking update chain upgrades event key");
+        let objects = match s3::spawn_list_objects(
+            s3_manager.clone(),
+            s3_bucket.as_str(),
+            Some(
+                avalanche_ops::StorageNamespace::EventsUpdateChainUpgradesEvent(id.to_string())
+                    .encode(),
+            ),
         )
-        .expect("failed unpack_file avalanche_bin_compressed_path");
+        .await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("failed s3::spawn_list_objects {}, retrying...", e);
+                continue;
+            }
+        };
+        if objects.is_empty() {
+            continue;
+        }
 
-        let f = File::open(avalanche_bin_path.as_str()).expect("failed to open avalanche_bin");
-        f.set_permissions(PermissionsExt::from_mode(0o777))
-            .expect("failed to set file permission for avalanche_bin");
-        fs::remove_file(&tmp_avalanche_bin_compressed_path).expect("failed fs::remove_file");
+        let last_modified_unix = objects[0].last_modified.unwrap().as_secs_f64();
+        let now_unix = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("unexpected None duration_since")
+            .as_secs();
 
-        let plugins_dir = get_plugins_dir(avalanche_bin_path.as_str());
-        if !Path::new(&plugins_dir).exists() {
-            info!("STEP: creating '{}' for plugins", plugins_dir);
-            fs::create_dir_all(plugins_dir.clone()).unwrap();
+        // requested for the last 6-min
+        let needs_update = (now_unix as f64 - last_modified_unix) < 360_f64
+            && last_modified_unix > last_applied_unix;
+        if !needs_update {
+            continue;
+        }
+        last_applied_unix = last_modified_unix;
+        let event_s3_key =
+            avalanche_ops::StorageNamespace::EventsUpdateChainUpgradesEvent(id.to_string())
+                .encode();
+        if !is_rollout_target(&s3_manager, &s3_bucket, &event_s3_key, &machine_id).await {
+            info!("update chain upgrades event does not target this node, skipping");
+            continue;
         }
 
-        info!("STEP: downloading plugins from S3 (if any) to overwrite");
-        let objects = s3::spawn_list_objects(
-            s3_manager.clone(),
-            &s3_bucket,
-            Some(s3::append_slash(
-                &avalanche_ops::StorageNamespace::EventsUpdateArtifactsInstallDirPluginsDir(
-                    id.to_string(),
-                )
-                .encode(),
-            )),
-        )
-        .await
-        .expect("failed s3::spawn_list_objects");
+        info!("STEP: downloading chain upgrade files from S3");
+        let upgrades = download_chain_upgrades(s3_manager.clone(), &s3_bucket, &id).await;
+
+        let mut passed = false;
+        for (chain_id, file_path) in upgrades.iter() {
+            let d = fs::read(file_path).expect("failed fs::read");
+            let timestamps = match upgrade::activation_timestamps(&d) {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!("invalid upgrade file for {} ({})", chain_id, e);
+                    passed = true;
+                    break;
+                }
+            };
+            if let Some(earliest) = timestamps.first() {
+                if *earliest <= now_unix {
+                    warn!(
+                        "activation {} for {} has already passed (now {})",
+                        earliest, chain_id, now_unix
+                    );
+                    passed = true;
+                }
+            }
+        }
+        if passed {
+            warn!("refusing to apply chain upgrades... skipping restart");
+            for (_, file_path) in upgrades.iter() {
+                fs::remove_file(file_path).expect("failed fs::remove_file");
+            }
+            continue;
+        }
 
-        info!("listed {} plugins from S3", objects.len());
-        for obj in objects.iter() {
-            let s3_key = obj.key().expect("unexpected None s3 object");
-            let tmp_path = random::tmp_path(15, None).unwrap();
-            s3::spawn_get_object(s3_manager.clone(), &s3_bucket, s3_key, &tmp_path)
-                .await
-                .expect("failed s3::spawn_get_object");
+        place_chain_upgrades(&upgrades, &chain_config_dir);
 
-            let file_name = extract_filename(s3_key);
-            let file_path = format!("{}/{}", plugins_dir, file_name);
-            compress::unpack_file(&tmp_path, &file_path, compress::Decoder::Zstd).unwrap();
+        warn!("chain upgrades have been updated... restarting avalanche.service");
+        bash::run("sudo systemctl restart avalanche.service")
+            .expect("failed systemctl restart command");
+    }
+}
+
+/// Returns true if the update event targets this node (see "canary").
+/// If the event cannot be read, updates as before the staged rollouts.
+async fn is_rollout_target(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    event_s3_key: &str,
+    machine_id: &str,
+) -> bool {
+    let tmp_path = random::tmp_path(10, None).expect("unexpected tmp_path failure");
+    if let Err(e) =
+        s3::spawn_get_object(s3_manager.clone(), s3_bucket, event_s3_key, &tmp_path).await
+    {
+        warn!("failed to download event {} ({})", event_s3_key, e);
+        return true;
+    }
+    let d = fs::read(&tmp_path).expect("failed fs::read");
+    fs::remove_file(&tmp_path).expect("failed fs::remove_file");
+
+    let rollout = canary::Rollout::decode(&d);
+    info!(
+        "event {} rollout stage {:?} with {} target(s)",
+        event_s3_key,
+        rollout.stage,
+        rollout.machine_ids.len()
+    );
+    rollout.includes(machine_id)
+}
 
-            let f = File::open(file_path).expect("failed to open plugin file");
-            f.set_permissions(PermissionsExt::from_mode(0o777))
-                .expect("failed to set file permission");
-            fs::remove_file(&tmp_path).expect("failed fs::remove_file");
+/// Downloads the chain upgrade files from S3, and returns the chain IDs
+/// with their temporary file paths.
+async fn download_chain_upgrades(
+    s3_manager: s3::Manager,
+    s3_bucket: &str,
+    id: &str,
+) -> Vec<(String, String)> {
+    let s3_dir = s3::append_slash(
+        &avalanche_ops::StorageNamespace::ChainUpgradesDir(id.to_string()).encode(),
+    );
+    let objects = s3::spawn_list_objects(s3_manager.clone(), s3_bucket, Some(s3_dir.clone()))
+        .await
+        .expect("failed s3::spawn_list_objects");
+    info!("listed {} chain upgrade files from S3", objects.len());
+
+    let mut upgrades = Vec::new();
+    for obj in objects.iter() {
+        let s3_key = obj.key().expect("unexpected None s3 object");
+
+        // e.g., "[ID]/chain-upgrades/[CHAIN_ID]/upgrade.json"
+        let chain_id = s3_key
+            .trim_start_matches(&s3_dir)
+            .split('/')
+            .next()
+            .unwrap_or("");
+        if chain_id.is_empty() {
+            continue;
         }
 
-        // updated the avalanched itself, so sleep for cloudwatch logs and restart
-        warn!("artifacts have been updated... will trigger avalanched restart by panic here...");
-        sleep(Duration::from_secs(240)).await; // sleep to prevent duplicate updates
-        panic!("panic avalanched to trigger restarts via systemd service!!!")
+        let tmp_path = random::tmp_path(15, Some(".json")).unwrap();
+        s3::spawn_get_object(s3_manager.clone(), s3_bucket, s3_key, &tmp_path)
+            .await
+            .expect("failed s3::spawn_get_object");
+        upgrades.push((chain_id.to_string(), tmp_path));
+    }
+    upgrades
+}
+
+/// Moves the downloaded upgrade files to "[chain_config_dir]/[CHAIN_ID]/upgrade.json".
+fn place_chain_upgrades(upgrades: &[(String, String)], chain_config_dir: &str) {
+    for (chain_id, tmp_path) in upgrades.iter() {
+        let dir = Path::new(chain_config_dir).join(chain_id);
+        fs::create_dir_all(&dir).expect("failed to create dir for chain upgrade");
+        let file_path = dir.join(upgrade::FILE_NAME);
+        info!("saving chain upgrade file to {:?}", file_path.as_os_str());
+        fs::copy(tmp_path, file_path).expect("failed fs::copy");
+        fs::remove_file(tmp_path).expect("failed fs::remove_file");
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn print_backup_commands(
+    transfer_limit: Arc<s3::TransferLimit>,
     s3_region: Arc<String>,
     s3_bucket: Arc<String>,
+    replica_s3_region: Arc<Option<String>>,
+    replica_s3_bucket: Arc<Option<String>>,
     id: Arc<String>,
     network_id: Arc<u32>,
     db_dir: Arc<String>,
 ) {
     info!("STEP: starting 'print_backup_commands'");
 
+    let replica_flags = {
+        if replica_s3_region.is_some() && replica_s3_bucket.is_some() {
+            format!(
+                " --replica-region {} --replica-s3-bucket {}",
+                replica_s3_region.as_ref().clone().unwrap(),
+                replica_s3_bucket.as_ref().clone().unwrap()
+            )
+        } else {
+            String::new()
+        }
+    };
+
+    let upload_limit_flags = match transfer_limit.upload_bytes_per_second {
+        Some(v) => format!(" --max-bytes-per-second {}", v),
+        None => String::new(),
+    };
+    let download_limit_flags = match transfer_limit.download_bytes_per_second {
+        Some(v) => format!(" --max-bytes-per-second {}", v),
+        None => String::new(),
+    };
+
     loop {
         // e.g., "--pack-dir /avalanche-data/network-1000000/v1.4.5"
         let db_dir_network = match constants::NETWORK_ID_TO_NETWORK_NAME.get(network_id.as_ref()) {
@@ -1124,7 +2579,7 @@ async fn print_backup_commands(
             None => format!("network-{}", network_id),
         };
 
-        println!("[TO BACK UP DATA] /usr/local/bin/avalanched backup upload --region {} --archive-compression-method {} --pack-dir {}/{} --s3-bucket {} --s3-key {}/backup{}", 
+        println!("[TO BACK UP DATA] /usr/local/bin/avalanched backup upload --region {} --archive-compression-method {} --pack-dir {}/{} --s3-bucket {} --s3-key {}/backup{}{}{}", 
             s3_region,
             compress::DirEncoder::TarGzip.id(),
             db_dir,
@@ -1132,41 +2587,21 @@ async fn print_backup_commands(
             &s3_bucket,
             avalanche_ops::StorageNamespace::BackupsDir(id.to_string()).encode(),
             compress::DirEncoder::TarGzip.ext(),
+            replica_flags,
+            upload_limit_flags,
         );
 
-        println!("[TO DOWNLOAD DATA] /usr/local/bin/avalanched backup download --region {} --unarchive-decompression-method {} --s3-bucket {} --s3-key {}/backup{} --unpack-dir {}",
+        println!("[TO DOWNLOAD DATA] /usr/local/bin/avalanched backup download --region {} --unarchive-decompression-method {} --s3-bucket {} --s3-key {}/backup{} --unpack-dir {}{}",
             s3_region,
             compress::DirDecoder::TarGzip.id(),
             s3_bucket,
             avalanche_ops::StorageNamespace::BackupsDir(id.to_string()).encode(),
             compress::DirDecoder::TarGzip.ext(),
             db_dir,
+            download_limit_flags,
         );
 
         info!("sleeping 5-hour 'print_backup_commands'");
         sleep(Duration::from_secs(5 * 3600)).await;
     }
 }
-
-///  build
-///    ├── avalanchego (the binary from compiling the app directory)
-///    └── plugins
-///        └── evm
-fn get_plugins_dir(avalanche_bin: &str) -> String {
-    let path = Path::new(avalanche_bin);
-    let parent_dir = path.parent().unwrap();
-    String::from(
-        parent_dir
-            .join(Path::new("plugins"))
-            .as_path()
-            .to_str()
-            .unwrap(),
-    )
-}
-
-/// returns "hello" from "a/b/c/hello.zstd"
-fn extract_filename(p: &str) -> String {
-    let path = Path::new(p);
-    let file_stemp = path.file_stem().unwrap();
-    String::from(file_stemp.to_str().unwrap())
-}
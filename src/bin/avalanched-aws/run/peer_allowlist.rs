@@ -0,0 +1,203 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Error, ErrorKind},
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use chrono::Utc;
+use log::{info, warn};
+use tokio::time::sleep;
+
+use avalanche_ops::{
+    avalanche::avalanchego::subnet_config,
+    aws::s3,
+    peer_allowlist::{self, PeerAllowlist},
+    utils::{bash, random},
+    TrackedSubnet,
+};
+
+/// Represents the subnets of this node that the allowlist restricts.
+#[derive(Debug, Clone)]
+pub struct Subnets {
+    pub subnet_config_dir: String,
+    pub whitelisted_subnets: Vec<String>,
+    pub tracked_subnets: Vec<TrackedSubnet>,
+}
+
+/// Writes the subnet configs from the allowlist in S3, if any.
+/// Returns true if any subnet config has changed.
+pub async fn apply_current(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    id: &str,
+    cfg: &PeerAllowlist,
+    subnets: &Subnets,
+) -> io::Result<bool> {
+    let allowlist = match peer_allowlist::get(s3_manager, s3_bucket, id).await? {
+        Some(v) => v,
+        None => {
+            info!("no peer allowlist generated yet, skipping");
+            return Ok(false);
+        }
+    };
+    info!(
+        "applying peer allowlist with {} node IDs generated at {}",
+        allowlist.node_ids.len(),
+        allowlist.generated_at
+    );
+    write_subnet_configs(
+        &subnets.subnet_config_dir,
+        &cfg.subnet_configs(
+            &allowlist,
+            &subnets.whitelisted_subnets,
+            &subnets.tracked_subnets,
+        ),
+    )
+}
+
+/// Writes "[SUBNET_ID].json" in the subnet config directory,
+/// and returns true if any file has changed.
+fn write_subnet_configs(
+    subnet_config_dir: &str,
+    configs: &BTreeMap<String, subnet_config::Config>,
+) -> io::Result<bool> {
+    let mut changed = false;
+    for (subnet_id, cfg) in configs.iter() {
+        let file_path = Path::new(subnet_config_dir).join(format!("{}.json", subnet_id));
+        let prev = fs::read(&file_path)
+            .ok()
+            .and_then(|d| serde_json::from_slice::<subnet_config::Config>(&d).ok());
+        if prev.as_ref() == Some(cfg) {
+            continue;
+        }
+        cfg.sync(file_path.to_str().unwrap())?;
+        changed = true;
+    }
+    Ok(changed)
+}
+
+/// Applies the "update-peer-allowlist" events, and re-generates the allowlist
+/// if this node is missing (e.g., the replaced instance with a new node ID),
+/// so the other nodes accept its connections.
+pub async fn check_update(
+    s3_manager: s3::Manager,
+    s3_bucket: Arc<String>,
+    id: Arc<String>,
+    machine_id: Arc<String>,
+    node_id: Arc<String>,
+    cfg: Arc<PeerAllowlist>,
+    subnets: Arc<Subnets>,
+) {
+    info!("STEP: starting 'check_peer_allowlist_update'");
+
+    let event_s3_key =
+        avalanche_ops::StorageNamespace::EventsUpdatePeerAllowlistEvent(id.to_string()).encode();
+    let mut last_applied_unix = 0_f64;
+    loop {
+        info!("sleeping 3-min for 'check_peer_allowlist_update'");
+        sleep(Duration::from_secs(180)).await;
+
+        if let Err(e) = register(&s3_manager, &s3_bucket, &id, &node_id, &cfg).await {
+            warn!("failed to register in the peer allowlist {}", e);
+        }
+
+        let objects = match s3::spawn_list_objects(
+            s3_manager.clone(),
+            s3_bucket.as_str(),
+            Some(event_s3_key.clone()),
+        )
+        .await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("failed s3::spawn_list_objects {}, retrying...", e);
+                continue;
+            }
+        };
+        if objects.is_empty() {
+            continue;
+        }
+
+        let last_modified_unix = objects[0].last_modified.unwrap().as_secs_f64();
+        let now_unix = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("unexpected None duration_since")
+            .as_secs();
+
+        // requested for the last 6-min
+        let needs_update = (now_unix as f64 - last_modified_unix) < 360_f64
+            && last_modified_unix > last_applied_unix;
+        if !needs_update {
+            continue;
+        }
+        last_applied_unix = last_modified_unix;
+        if !super::is_rollout_target(&s3_manager, &s3_bucket, &event_s3_key, &machine_id).await {
+            info!("update peer allowlist event does not target this node, skipping");
+            continue;
+        }
+
+        let changed = match apply_current(&s3_manager, &s3_bucket, &id, &cfg, &subnets).await {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("failed to apply peer allowlist {}", e);
+                continue;
+            }
+        };
+        if !changed {
+            info!("subnet configs have not changed, skipping restart");
+            continue;
+        }
+
+        // the nodes pick up the event within 3-min of each other,
+        // so spread the restarts not to stop all validators at once
+        let jitter = u16::from_be_bytes([random::bytes(1)[0], random::bytes(1)[0]]) % 120;
+        info!("restarting avalanche.service in {} seconds", jitter);
+        sleep(Duration::from_secs(jitter as u64)).await;
+        warn!("peer allowlist has been updated... restarting avalanche.service");
+        bash::run("sudo systemctl restart avalanche.service")
+            .expect("failed systemctl restart command");
+    }
+}
+
+/// Pushes the re-generated allowlist if this node is missing from the
+/// allowlist that the cluster already uses. The subnet peers may not accept
+/// this node yet, so only waits for its own verified heartbeat.
+async fn register(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    id: &str,
+    node_id: &str,
+    cfg: &PeerAllowlist,
+) -> io::Result<()> {
+    // "apply" generates the first allowlist once all nodes are up
+    let current = match peer_allowlist::get(s3_manager, s3_bucket, id).await? {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    if current.contains(node_id) {
+        return Ok(());
+    }
+
+    let now_unix = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("unexpected None duration_since")
+        .as_secs();
+    let cluster_node_ids =
+        peer_allowlist::fetch_registry(s3_manager, s3_bucket, id, now_unix).await?;
+    if !cluster_node_ids.iter().any(|v| v == node_id) {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("no verified heartbeat of {} found yet", node_id),
+        ));
+    }
+    let allowlist = cfg.generate(&cluster_node_ids, Utc::now());
+    warn!(
+        "node {} not in the peer allowlist, pushing {} node IDs",
+        node_id,
+        allowlist.node_ids.len()
+    );
+    peer_allowlist::push(s3_manager, s3_bucket, id, &allowlist).await
+}
@@ -0,0 +1,301 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::Utc;
+use log::{info, warn};
+use tokio::time::sleep;
+
+use avalanche_ops::{
+    avalanche::avalanchego::api::{auth, health},
+    aws::{elbv2, lease, s3},
+    public_snapshot::{self, PublicSnapshot},
+    utils::{bash, compress, hash, random},
+};
+
+/// Represents the NLB target of this node, deregistered while avalanchego
+/// is stopped for the snapshot, so the clients are not routed to it.
+#[derive(Debug, Clone)]
+pub struct NlbTarget {
+    pub region: String,
+    pub target_group_arn: String,
+    pub weighted_ports: Vec<u32>,
+    /// Seconds to drain the deregistering target.
+    pub deregistration_delay_seconds: u32,
+}
+
+impl NlbTarget {
+    fn deregistration_cmd(&self, instance_id: &str) -> String {
+        elbv2::target_registration_cmd(
+            &self.region,
+            &self.target_group_arn,
+            instance_id,
+            &self.weighted_ports,
+            false,
+        )
+    }
+}
+
+/// Publishes the database snapshot every "interval_hours" while this node
+/// holds the publisher lease. The lease is taken over from the terminated
+/// instance, and the schedule follows the published "latest.json", so the
+/// replaced publisher does not re-publish right away.
+#[allow(clippy::too_many_arguments)]
+pub async fn publish(
+    s3_manager: s3::Manager,
+    lease_manager: lease::Manager,
+    cfg: Arc<PublicSnapshot>,
+    id: Arc<String>,
+    instance_id: Arc<String>,
+    nlb_target: Arc<Option<NlbTarget>>,
+    network_id: Arc<u32>,
+    subnet_ids: Arc<Vec<String>>,
+    db_dir: Arc<String>,
+    http_endpoint: Arc<String>,
+    api_auth_password: Arc<Option<String>>,
+) {
+    info!("STEP: starting 'public_snapshot::publish' in 10-minute");
+    sleep(Duration::from_secs(600)).await;
+
+    let lease_keys = vec![public_snapshot::lease_key(&id)];
+    loop {
+        match lease_manager.acquire(&id, &lease_keys, &instance_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                info!("public snapshot published by another node, retrying in 1-hour");
+                sleep(Duration::from_secs(3600)).await;
+                continue;
+            }
+            Err(e) => {
+                warn!("failed to lease the public snapshot publisher {}", e);
+                sleep(Duration::from_secs(600)).await;
+                continue;
+            }
+        }
+
+        let latest = match s3_manager
+            .get_bytes(&cfg.s3_bucket, &cfg.latest_key(), None)
+            .await
+        {
+            Ok(Some(b)) => match public_snapshot::Manifest::decode_json(&b) {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    warn!("ignoring the latest public snapshot manifest ({})", e);
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(e) => {
+                warn!("failed to read the latest public snapshot {}", e);
+                sleep(Duration::from_secs(600)).await;
+                continue;
+            }
+        };
+        if let Some(m) = &latest {
+            if !m.is_due(Utc::now(), cfg.interval_hours()) {
+                info!(
+                    "latest public snapshot '{}' is not due yet, checking in 1-hour",
+                    m.snapshot_id
+                );
+                sleep(Duration::from_secs(3600)).await;
+                continue;
+            }
+        }
+
+        // only snapshot the database that is fully bootstrapped
+        if let Some(password) = api_auth_password.as_ref() {
+            if let Err(e) = auth::authorize(http_endpoint.as_str(), password).await {
+                warn!("failed to issue API auth token ({})", e);
+            }
+        }
+        match health::spawn_check(http_endpoint.as_str(), false).await {
+            Ok(res) if res.healthy == Some(true) => {}
+            Ok(_) => {
+                warn!("node is not healthy yet, retrying public snapshot in 10-minute");
+                sleep(Duration::from_secs(600)).await;
+                continue;
+            }
+            Err(e) => {
+                warn!("health check failed ({}), retrying in 10-minute", e);
+                sleep(Duration::from_secs(600)).await;
+                continue;
+            }
+        }
+
+        match publish_once(
+            &s3_manager,
+            &cfg,
+            &instance_id,
+            nlb_target.as_ref().as_ref(),
+            &network_id,
+            &subnet_ids,
+            &db_dir,
+        )
+        .await
+        {
+            Ok(manifest) => info!(
+                "published public snapshot '{}' ({} bytes)",
+                manifest.snapshot_id, manifest.archive.size_bytes
+            ),
+            Err(e) => {
+                warn!("failed to publish public snapshot {}", e);
+                sleep(Duration::from_secs(3600)).await;
+                continue;
+            }
+        }
+
+        if let Err(e) = delete_expired(&s3_manager, &cfg).await {
+            warn!("failed to delete the expired public snapshots {}", e);
+        }
+    }
+}
+
+/// Archives the database with avalanchego stopped, and uploads the archive,
+/// the checksums, and the manifest, then the manifest as "latest.json" last.
+/// The node is out of the NLB target group while avalanchego is stopped,
+/// and "manage_nlb_target" registers it again once healthy.
+async fn publish_once(
+    s3_manager: &s3::Manager,
+    cfg: &PublicSnapshot,
+    instance_id: &str,
+    nlb_target: Option<&NlbTarget>,
+    network_id: &u32,
+    subnet_ids: &[String],
+    db_dir: &str,
+) -> io::Result<public_snapshot::Manifest> {
+    let created_at = Utc::now();
+    let snapshot_id = public_snapshot::snapshot_id(created_at);
+    let dir = cfg.dir(&snapshot_id);
+
+    let parent_dir = Path::new(db_dir)
+        .parent()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "unexpected None parent dir"))?;
+    let tmp_file_path = parent_dir.join(random::string(10));
+    let tmp_file_path = tmp_file_path.as_path().as_os_str().to_str().unwrap();
+
+    let marker_dir = Path::new(public_snapshot::IN_PROGRESS_FILE_PATH)
+        .parent()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "unexpected None parent dir"))?;
+
+    // the marker also keeps "manage_nlb_target" from registering the node,
+    // until avalanchego is back and healthy
+    let packed = fs::create_dir_all(marker_dir).and_then(|_| {
+        fs::write(
+            public_snapshot::IN_PROGRESS_FILE_PATH,
+            created_at.to_rfc3339(),
+        )
+    });
+
+    // drain the client connections before the node stops serving them
+    let packed = match (packed, nlb_target) {
+        (Ok(_), Some(t)) => {
+            info!(
+                "STEP: deregistering {} from the NLB target group for public snapshot",
+                instance_id
+            );
+            match bash::run(&t.deregistration_cmd(instance_id)) {
+                Ok(_) => {
+                    sleep(Duration::from_secs(t.deregistration_delay_seconds as u64)).await;
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            }
+        }
+        (ret, _) => ret,
+    };
+
+    // LevelDB files change under the running node
+    warn!(
+        "STEP: stopping avalanche.service for public snapshot '{}'",
+        snapshot_id
+    );
+    let packed = packed
+        .and_then(|_| bash::run("sudo systemctl stop avalanche.service").map(|_| ()))
+        .and_then(|_| compress::pack_directory(db_dir, tmp_file_path, public_snapshot::ENCODER));
+
+    // start the node and resume remediation even if the archive failed,
+    // and only then return the first error
+    info!("STEP: starting avalanche.service");
+    let started = bash::run("sudo systemctl start --no-block avalanche.service").map(|_| ());
+    let removed = match fs::remove_file(public_snapshot::IN_PROGRESS_FILE_PATH) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    };
+    if let Err(e) = packed.and(started).and(removed) {
+        let _ = fs::remove_file(tmp_file_path);
+        return Err(e);
+    }
+
+    let archive = public_snapshot::Archive {
+        s3_key: public_snapshot::archive_key(&dir),
+        size_bytes: fs::metadata(tmp_file_path)?.len(),
+        sha256: hash::sha256_file(tmp_file_path)?,
+        decompression_method: compress::DirDecoder::TarZstd.id().to_string(),
+    };
+    let manifest = public_snapshot::Manifest {
+        snapshot_id,
+        network_id: *network_id,
+        subnet_ids: subnet_ids.to_vec(),
+        created_at,
+        requester_pays: cfg.requester_pays(),
+        archive,
+    };
+
+    // the AWS CLI uploads the large archive in parts
+    info!(
+        "STEP: uploading public snapshot archive {}",
+        manifest.archive.s3_key
+    );
+    let uploaded = bash::run(&format!(
+        "aws s3 cp --only-show-errors {} s3://{}/{}",
+        tmp_file_path, cfg.s3_bucket, manifest.archive.s3_key
+    ));
+    fs::remove_file(tmp_file_path)?;
+    uploaded?;
+
+    let encoded = manifest.encode_json()?;
+    for (key, contents) in [
+        (public_snapshot::checksums_key(&dir), manifest.checksums()),
+        (public_snapshot::manifest_key(&dir), encoded.clone()),
+        (cfg.latest_key(), encoded),
+    ] {
+        s3_manager
+            .put_bytes(
+                contents.into_bytes(),
+                Arc::new(cfg.s3_bucket.clone()),
+                Arc::new(key),
+            )
+            .await
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed put_bytes {}", e)))?;
+    }
+    Ok(manifest)
+}
+
+async fn delete_expired(s3_manager: &s3::Manager, cfg: &PublicSnapshot) -> io::Result<()> {
+    let objects = s3_manager
+        .list_objects(
+            Arc::new(cfg.s3_bucket.clone()),
+            Some(Arc::new(s3::append_slash(&cfg.s3_prefix()))),
+        )
+        .await
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed list_objects {}", e)))?;
+    let keys: Vec<String> = objects
+        .iter()
+        .filter_map(|o| o.key().map(String::from))
+        .collect();
+    for snapshot_id in cfg.expired_snapshot_ids(&keys) {
+        info!("deleting expired public snapshot '{}'", snapshot_id);
+        s3_manager
+            .delete_objects(
+                Arc::new(cfg.s3_bucket.clone()),
+                Some(Arc::new(s3::append_slash(&cfg.dir(&snapshot_id)))),
+            )
+            .await
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed delete_objects {}", e)))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,398 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use log::{info, warn};
+use tokio::time::sleep;
+
+use avalanche_ops::{
+    self,
+    aws::{cloudwatch, envelope, s3},
+    icm_relayer, metric_schema,
+    utils::{bash, compress, random},
+};
+
+use crate::install;
+
+/// Runs the ICM relayer on the "icm-relayer" node kind instead of avalanchego,
+/// with the same CloudWatch logs, clock offset alarm, and update events.
+#[allow(clippy::too_many_arguments)]
+pub async fn execute(
+    s3_manager: s3::Manager,
+    cw_manager: cloudwatch::Manager,
+    envelope: envelope::Envelope,
+    id: String,
+    s3_bucket: String,
+    instance_id: String,
+    az: String,
+    cloudwatch_config_file_path: String,
+    data_volume_path: String,
+) {
+    let spec = download_spec(&s3_manager, &s3_bucket, &id)
+        .await
+        .expect("failed to download spec");
+    let aws_resources = spec.aws_resources.clone().unwrap();
+    let relayer = spec
+        .icm_relayer
+        .clone()
+        .expect("unexpected None icm_relayer in spec");
+
+    // ref. https://docs.aws.amazon.com/AmazonCloudWatch/latest/monitoring/CloudWatch-Agent-Configuration-File-Details.html
+    info!("STEP: writing CloudWatch configuration JSON file");
+    let mut log_collect_list = vec![
+        cloudwatch::Collect {
+            log_group_name: id.clone(),
+            log_stream_name: format!("{{instance_id}}-{}-avalanched", icm_relayer::NODE_KIND),
+            file_path: String::from("/var/log/avalanched/avalanched.log"),
+            auto_removal: Some(true),
+            retention_in_days: Some(7),
+            ..cloudwatch::Collect::default()
+        },
+        cloudwatch::Collect {
+            log_group_name: id.clone(),
+            log_stream_name: format!("{{instance_id}}-{}", icm_relayer::NODE_KIND),
+            file_path: String::from(icm_relayer::LOG_FILE_PATH),
+            auto_removal: Some(false),
+            retention_in_days: Some(7),
+            ..cloudwatch::Collect::default()
+        },
+    ];
+    if aws_resources.instance_system_logs == Some(true) {
+        log_collect_list.push(cloudwatch::Collect {
+            log_group_name: id.clone(),
+            log_stream_name: format!("{{instance_id}}-{}-syslog", icm_relayer::NODE_KIND),
+            file_path: String::from("/var/log/syslog"),
+            auto_removal: Some(true),
+            retention_in_days: Some(7),
+            ..cloudwatch::Collect::default()
+        });
+    }
+    let mut cloudwatch_config = cloudwatch::Config::default();
+    cloudwatch_config.logs = Some(cloudwatch::Logs {
+        force_flush_interval: Some(60),
+        logs_collected: Some(cloudwatch::LogsCollected {
+            files: Some(cloudwatch::Files {
+                collect_list: Some(log_collect_list),
+            }),
+        }),
+    });
+    if aws_resources.instance_system_metrics == Some(true) {
+        let mut cw_metrics = cloudwatch::Metrics {
+            namespace: id.clone(),
+            ..Default::default()
+        };
+        cw_metrics.metrics_collected.disk =
+            Some(cloudwatch::Disk::new(vec![data_volume_path.clone()]));
+        cloudwatch_config.metrics = Some(cw_metrics);
+    }
+    cloudwatch_config
+        .sync(&cloudwatch_config_file_path)
+        .unwrap();
+
+    if !Path::new(icm_relayer::BIN_PATH).exists() {
+        info!("STEP: downloading ICM relayer binary from S3");
+        let s3_key = avalanche_ops::StorageNamespace::IcmRelayerBinCompressed(id.clone()).encode();
+        let tmp_compressed_path = random::tmp_path(15, Some(".zstd")).unwrap();
+        s3::spawn_get_object_resumable(
+            s3_manager.clone(),
+            &s3_bucket,
+            &s3_key,
+            &tmp_compressed_path,
+        )
+        .await
+        .expect("failed s3::spawn_get_object_resumable");
+        compress::unpack_file(
+            &tmp_compressed_path,
+            icm_relayer::BIN_PATH,
+            compress::Decoder::Zstd,
+        )
+        .expect("failed unpack_file icm_relayer_bin_compressed_path");
+        fs::remove_file(&tmp_compressed_path).expect("failed fs::remove_file");
+        if let Err(e) = install::verify_artifact(
+            &s3_manager,
+            &s3_bucket,
+            &s3_key,
+            icm_relayer::BIN_PATH,
+            &spec.install_artifacts,
+        )
+        .await
+        {
+            // remove so that the next restart downloads again
+            fs::remove_file(icm_relayer::BIN_PATH).expect("failed fs::remove_file");
+            panic!("refusing to install '{}' ({})", icm_relayer::BIN_PATH, e);
+        }
+        let f = File::open(icm_relayer::BIN_PATH).expect("failed to open icm_relayer_bin");
+        f.set_permissions(PermissionsExt::from_mode(0o777))
+            .expect("failed to set file permission for icm_relayer_bin");
+    }
+
+    info!("STEP: downloading ICM relayer key from S3");
+    let relayer_key = icm_relayer::get_key(&s3_manager, &envelope, &s3_bucket, &id)
+        .await
+        .expect("failed icm_relayer::get_key (run 'apply' first)");
+    let storage_location = format!("{}/icm-relayer", data_volume_path);
+    write_config(&spec, &relayer_key, &storage_location);
+
+    info!("STEP: setting up ICM relayer systemd service file");
+    fs::create_dir_all(&storage_location).expect("failed to create relayer storage dir");
+    if let Some(parent_dir) = Path::new(icm_relayer::LOG_FILE_PATH).parent() {
+        fs::create_dir_all(parent_dir).expect("failed to create relayer log dir");
+    }
+    let service_file_contents = format!(
+        "[Unit]
+Description=icm relayer
+
+[Service]
+Type=exec
+TimeoutStartSec=300
+Restart=always
+RestartSec=5s
+ExecStart={} --config-file={}
+StandardOutput=append:{}
+StandardError=append:{}
+
+[Install]
+WantedBy=multi-user.target",
+        icm_relayer::BIN_PATH,
+        icm_relayer::CONFIG_FILE_PATH,
+        icm_relayer::LOG_FILE_PATH,
+        icm_relayer::LOG_FILE_PATH,
+    );
+    let mut service_file = tempfile::NamedTempFile::new().unwrap();
+    service_file
+        .write_all(service_file_contents.as_bytes())
+        .expect("failed write_all icm relayer service_file");
+    fs::copy(
+        service_file.path().to_str().unwrap(),
+        "/etc/systemd/system/icm-relayer.service",
+    )
+    .expect("failed to copy /etc/systemd/system/icm-relayer.service");
+    bash::run("sudo systemctl daemon-reload").expect("failed systemctl daemon-reload command");
+    bash::run("sudo systemctl enable icm-relayer.service")
+        .expect("failed systemctl enable command");
+    bash::run("sudo systemctl restart --no-block icm-relayer.service")
+        .expect("failed systemctl restart command");
+    info!(
+        "'avalanched run' all success -- relaying {} to {} blockchains with '{}'",
+        relayer.source_blockchains.len(),
+        relayer.destination_blockchains.len(),
+        relayer_key.eth_address
+    );
+
+    info!("spawning async routines...");
+    let metrics_publisher = Arc::new(metric_schema::Publisher {
+        cw_manager,
+        namespace: aws_resources
+            .cloudwatch_avalanche_metrics_namespace
+            .clone()
+            .unwrap(),
+        version: aws_resources
+            .cloudwatch_metrics_schema_version
+            .unwrap_or(metric_schema::VERSION_1),
+        dimensions: metric_schema::Dimensions {
+            cluster_id: id.clone(),
+            node_kind: icm_relayer::NODE_KIND.to_string(),
+            node_id: instance_id.clone(),
+            availability_zone: az,
+        },
+        legacy_namespace: aws_resources.cloudwatch_metrics_legacy_namespace.clone(),
+        otlp: None,
+    });
+    let handles = vec![
+        tokio::spawn(super::publish_clock_offset(
+            metrics_publisher,
+            Arc::new(icm_relayer::NODE_KIND.to_string()),
+        )),
+        tokio::spawn(check_config_update(
+            s3_manager.clone(),
+            Arc::new(s3_bucket.clone()),
+            Arc::new(id.clone()),
+            Arc::new(relayer_key),
+            Arc::new(storage_location),
+        )),
+        tokio::spawn(check_relayer_update(
+            s3_manager,
+            Arc::new(s3_bucket),
+            Arc::new(id),
+            Arc::new(instance_id),
+            Arc::new(spec.install_artifacts.clone()),
+        )),
+    ];
+    for handle in handles {
+        handle.await.expect("failed handle");
+    }
+}
+
+async fn download_spec(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    id: &str,
+) -> std::io::Result<avalanche_ops::Spec> {
+    let tmp_spec_file_path = random::tmp_path(15, Some(".yaml"))?;
+    s3::spawn_get_object(
+        s3_manager.clone(),
+        s3_bucket,
+        &avalanche_ops::StorageNamespace::ConfigFile(id.to_string()).encode(),
+        &tmp_spec_file_path,
+    )
+    .await
+    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+    let spec = avalanche_ops::Spec::load(&tmp_spec_file_path)?;
+    fs::remove_file(&tmp_spec_file_path)?;
+    Ok(spec)
+}
+
+/// Writes the relayer config from the spec, and returns "true" if changed.
+fn write_config(
+    spec: &avalanche_ops::Spec,
+    relayer_key: &avalanche_ops::avalanche::key::Key,
+    storage_location: &str,
+) -> bool {
+    let relayer = spec
+        .icm_relayer
+        .as_ref()
+        .expect("unexpected None icm_relayer in spec");
+    let http_rpc = spec
+        .endpoints
+        .as_ref()
+        .and_then(|e| e.http_rpc.clone())
+        .expect("unexpected None endpoints.http_rpc in spec");
+    let contents = relayer
+        .config(
+            &http_rpc,
+            relayer_key.private_key_hex.expose_str(),
+            &relayer_key.eth_address,
+            storage_location,
+        )
+        .encode_json()
+        .expect("failed to encode relayer config");
+    if fs::read_to_string(icm_relayer::CONFIG_FILE_PATH).ok() == Some(contents.clone()) {
+        return false;
+    }
+
+    info!(
+        "STEP: writing ICM relayer config '{}'",
+        icm_relayer::CONFIG_FILE_PATH
+    );
+    if let Some(parent_dir) = Path::new(icm_relayer::CONFIG_FILE_PATH).parent() {
+        fs::create_dir_all(parent_dir).expect("failed to create relayer config dir");
+    }
+    fs::write(icm_relayer::CONFIG_FILE_PATH, contents).expect("failed to write relayer config");
+    // has the private key
+    fs::set_permissions(
+        icm_relayer::CONFIG_FILE_PATH,
+        PermissionsExt::from_mode(0o600),
+    )
+    .expect("failed to set file permission for relayer config");
+    true
+}
+
+/// Reloads the relayer config from the spec uploaded by "apply",
+/// and restarts the relayer on the changes (e.g., new blockchains).
+async fn check_config_update(
+    s3_manager: s3::Manager,
+    s3_bucket: Arc<String>,
+    id: Arc<String>,
+    relayer_key: Arc<avalanche_ops::avalanche::key::Key>,
+    storage_location: Arc<String>,
+) {
+    info!("STEP: starting 'check_config_update'");
+    loop {
+        sleep(Duration::from_secs(180)).await;
+
+        let spec = match download_spec(&s3_manager, &s3_bucket, &id).await {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("failed to download spec {}, retrying...", e);
+                continue;
+            }
+        };
+        if spec.icm_relayer.is_none() {
+            warn!("no 'icm_relayer' in spec, keeping the current config");
+            continue;
+        }
+        if write_config(&spec, &relayer_key, &storage_location) {
+            warn!("ICM relayer config has been updated... restarting icm-relayer.service");
+            if let Err(e) = bash::run("sudo systemctl restart icm-relayer.service") {
+                warn!("failed systemctl restart command {}", e);
+            }
+        }
+    }
+}
+
+async fn check_relayer_update(
+    s3_manager: s3::Manager,
+    s3_bucket: Arc<String>,
+    id: Arc<String>,
+    machine_id: Arc<String>,
+    install_artifacts: Arc<avalanche_ops::InstallArtifacts>,
+) {
+    info!("STEP: starting 'check_relayer_update'");
+    let event_s3_key =
+        avalanche_ops::StorageNamespace::EventsUpdateArtifactsEvent(id.to_string()).encode();
+    loop {
+        info!("sleeping 3-min for 'check_relayer_update'");
+        sleep(Duration::from_secs(180)).await;
+
+        let objects = match s3::spawn_list_objects(
+            s3_manager.clone(),
+            s3_bucket.as_str(),
+            Some(event_s3_key.clone()),
+        )
+        .await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("failed s3::spawn_list_objects {}, retrying...", e);
+                continue;
+            }
+        };
+        if objects.is_empty() {
+            continue;
+        }
+        let last_modified_unix = objects[0].last_modified.unwrap().as_secs_f64();
+        let now_unix = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("unexpected None duration_since")
+            .as_secs() as f64;
+        // requested for the last 6-min, same as "check_node_update"
+        if (now_unix - last_modified_unix) >= 360_f64 {
+            continue;
+        }
+        if !super::is_rollout_target(&s3_manager, &s3_bucket, &event_s3_key, &machine_id).await {
+            info!("update artifacts event does not target this relayer, skipping");
+            sleep(Duration::from_secs(240)).await; // sleep to prevent duplicate checks
+            continue;
+        }
+
+        match crate::upgrade::install_update_icm_relayer(
+            &s3_manager,
+            &s3_bucket,
+            &id,
+            &install_artifacts,
+        )
+        .await
+        {
+            Ok(true) => {}
+            Ok(false) => {
+                info!("no ICM relayer binary in the update artifacts, skipping");
+                sleep(Duration::from_secs(240)).await; // sleep to prevent duplicate checks
+                continue;
+            }
+            Err(e) => {
+                warn!("refusing to install the updated ICM relayer ({})", e);
+                sleep(Duration::from_secs(240)).await; // sleep to prevent duplicate checks
+                continue;
+            }
+        }
+
+        warn!("ICM relayer has been updated... will trigger avalanched restart by panic here...");
+        sleep(Duration::from_secs(240)).await; // sleep to prevent duplicate updates
+        panic!("panic avalanched to trigger restarts via systemd service!!!")
+    }
+}
@@ -0,0 +1,267 @@
+use std::{
+    fs::{self, File},
+    io::{self, Error, ErrorKind},
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    time::Duration,
+};
+
+use clap::{Arg, Command};
+use log::{info, warn};
+use tokio::time::sleep;
+
+use avalanche_ops::{
+    self,
+    aws::s3,
+    icm_relayer,
+    utils::{bash, compress, random},
+};
+
+use crate::install;
+
+pub const NAME: &str = "upgrade";
+
+/// Installs the artifacts of the latest "events update-artifacts" once,
+/// and restarts the agent, without waiting for the event polling in "run".
+pub fn command() -> Command<'static> {
+    Command::new(NAME)
+        .about("Installs the updated Avalanche artifacts from S3 and restarts the agent on AWS")
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .short('l')
+                .help("Sets the log level")
+                .required(false)
+                .takes_value(true)
+                .possible_value("debug")
+                .possible_value("info")
+                .allow_invalid_utf8(false)
+                .default_value("info"),
+        )
+}
+
+pub async fn execute(log_level: &str) -> io::Result<()> {
+    // ref. https://github.com/env-logger-rs/env_logger/issues/47
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, log_level),
+    );
+
+    let (shared_config, tags) = install::load_local_instance().await;
+    let id = install::required_tag(&tags, "ID");
+    let s3_bucket = install::required_tag(&tags, "S3_BUCKET_NAME");
+    let avalanche_bin_path = install::required_tag(&tags, "AVALANCHE_BIN_PATH");
+    let s3_manager = s3::Manager::new(&shared_config);
+
+    info!("STEP: downloading avalanche-ops::Spec from S3");
+    let tmp_spec_file_path = random::tmp_path(15, Some(".yaml"))?;
+    s3::spawn_get_object(
+        s3_manager.clone(),
+        &s3_bucket,
+        &avalanche_ops::StorageNamespace::ConfigFile(id.clone()).encode(),
+        &tmp_spec_file_path,
+    )
+    .await
+    .map_err(|e| Error::new(ErrorKind::Other, format!("failed spawn_get_object {}", e)))?;
+    let spec = avalanche_ops::Spec::load(&tmp_spec_file_path)?;
+    fs::remove_file(&tmp_spec_file_path)?;
+
+    if tags.get("NODE_KIND").map(String::as_str) == Some(icm_relayer::NODE_KIND) {
+        install_update_icm_relayer(&s3_manager, &s3_bucket, &id, &spec.install_artifacts).await?;
+    } else {
+        install_update_artifacts(
+            &s3_manager,
+            &s3_bucket,
+            &id,
+            &avalanche_bin_path,
+            &spec.install_artifacts,
+        )
+        .await?;
+    }
+
+    // avalanched restarts avalanche.service with the new binary
+    warn!("restarting avalanched.service with the updated artifacts");
+    bash::run("sudo systemctl restart avalanched.service")?;
+    Ok(())
+}
+
+/// Downloads and verifies the artifacts uploaded by "events update-artifacts",
+/// and replaces the installed ones with avalanche.service stopped.
+/// Returns an error without stopping the service if any artifact fails
+/// the verification.
+pub async fn install_update_artifacts(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    id: &str,
+    avalanche_bin_path: &str,
+    install_artifacts: &avalanche_ops::InstallArtifacts,
+) -> io::Result<()> {
+    info!("STEP: downloading avalanched binary from S3");
+
+    // TODO: replace "avalanched" itself?
+    // TODO: fs::copy fails with 'Os { code: 26, kind: ExecutableFileBusy, message: "Text file busy" }'
+    // can't replace the process itself...
+
+    info!("STEP: downloading avalanche binary from S3");
+    let avalanche_bin_s3_key =
+        avalanche_ops::StorageNamespace::EventsUpdateArtifactsInstallDirAvalancheBinCompressed(
+            id.to_string(),
+        )
+        .encode();
+    let tmp_avalanche_bin_compressed_path = random::tmp_path(15, Some(".zstd")).unwrap();
+    s3::spawn_get_object_resumable(
+        s3_manager.clone(),
+        s3_bucket,
+        &avalanche_bin_s3_key,
+        &tmp_avalanche_bin_compressed_path,
+    )
+    .await
+    .expect("failed s3::spawn_get_object_resumable");
+    let tmp_avalanche_bin_path = random::tmp_path(15, None).unwrap();
+    compress::unpack_file(
+        &tmp_avalanche_bin_compressed_path,
+        &tmp_avalanche_bin_path,
+        compress::Decoder::Zstd,
+    )
+    .expect("failed unpack_file avalanche_bin_compressed_path");
+    fs::remove_file(&tmp_avalanche_bin_compressed_path).expect("failed fs::remove_file");
+
+    // (unpacked path, path to install)
+    let mut downloaded = vec![(tmp_avalanche_bin_path, avalanche_bin_path.to_string())];
+    let mut verified = install::verify_artifact(
+        s3_manager,
+        s3_bucket,
+        &avalanche_bin_s3_key,
+        &downloaded[0].0,
+        install_artifacts,
+    )
+    .await;
+
+    info!("STEP: downloading plugins from S3 (if any) to overwrite");
+    let plugins_dir = install::get_plugins_dir(avalanche_bin_path);
+    let objects = s3::spawn_list_objects(
+        s3_manager.clone(),
+        s3_bucket,
+        Some(s3::append_slash(
+            &avalanche_ops::StorageNamespace::EventsUpdateArtifactsInstallDirPluginsDir(
+                id.to_string(),
+            )
+            .encode(),
+        )),
+    )
+    .await
+    .expect("failed s3::spawn_list_objects");
+    info!("listed {} plugins from S3", objects.len());
+    for obj in objects.iter() {
+        let s3_key = obj.key().expect("unexpected None s3 object");
+        let tmp_path = random::tmp_path(15, None).unwrap();
+        s3::spawn_get_object_resumable(s3_manager.clone(), s3_bucket, s3_key, &tmp_path)
+            .await
+            .expect("failed s3::spawn_get_object_resumable");
+
+        let tmp_plugin_path = random::tmp_path(15, None).unwrap();
+        compress::unpack_file(&tmp_path, &tmp_plugin_path, compress::Decoder::Zstd).unwrap();
+        fs::remove_file(&tmp_path).expect("failed fs::remove_file");
+
+        if verified.is_ok() {
+            verified = install::verify_artifact(
+                s3_manager,
+                s3_bucket,
+                s3_key,
+                &tmp_plugin_path,
+                install_artifacts,
+            )
+            .await;
+        }
+        let file_name = install::extract_filename(s3_key);
+        downloaded.push((tmp_plugin_path, format!("{}/{}", plugins_dir, file_name)));
+    }
+
+    if let Err(e) = verified {
+        for (tmp_path, _) in downloaded.iter() {
+            fs::remove_file(tmp_path).expect("failed fs::remove_file");
+        }
+        return Err(e);
+    }
+
+    warn!("stopping avalanche.service before install...");
+    bash::run("sudo systemctl stop avalanche.service").expect("failed systemctl stop command");
+    warn!("stopped avalanche.service before install...");
+    sleep(Duration::from_secs(10)).await;
+
+    if !Path::new(&plugins_dir).exists() {
+        info!("STEP: creating '{}' for plugins", plugins_dir);
+        fs::create_dir_all(plugins_dir.clone()).unwrap();
+    }
+    for (tmp_path, file_path) in downloaded.iter() {
+        fs::copy(tmp_path, file_path).expect("failed fs::copy");
+        let f = File::open(file_path).expect("failed to open artifact file");
+        f.set_permissions(PermissionsExt::from_mode(0o777))
+            .expect("failed to set file permission");
+        fs::remove_file(tmp_path).expect("failed fs::remove_file");
+    }
+
+    Ok(())
+}
+
+/// Downloads and verifies the ICM relayer binary uploaded by "sync-artifacts",
+/// and replaces the installed one with icm-relayer.service stopped.
+/// Returns "false" if the update artifacts have no relayer binary.
+pub async fn install_update_icm_relayer(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    id: &str,
+    install_artifacts: &avalanche_ops::InstallArtifacts,
+) -> io::Result<bool> {
+    let s3_key =
+        avalanche_ops::StorageNamespace::EventsUpdateArtifactsInstallDirIcmRelayerBinCompressed(
+            id.to_string(),
+        )
+        .encode();
+    let exists = s3_manager
+        .head_object_metadata(s3_bucket, &s3_key)
+        .await
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed head_object_metadata {}", e),
+            )
+        })?
+        .is_some();
+    if !exists {
+        return Ok(false);
+    }
+
+    info!("STEP: downloading ICM relayer binary from S3");
+    let tmp_compressed_path = random::tmp_path(15, Some(".zstd"))?;
+    s3::spawn_get_object_resumable(s3_manager.clone(), s3_bucket, &s3_key, &tmp_compressed_path)
+        .await
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed spawn_get_object_resumable {}", e),
+            )
+        })?;
+    let tmp_bin_path = random::tmp_path(15, None)?;
+    compress::unpack_file(&tmp_compressed_path, &tmp_bin_path, compress::Decoder::Zstd)?;
+    fs::remove_file(&tmp_compressed_path)?;
+    if let Err(e) = install::verify_artifact(
+        s3_manager,
+        s3_bucket,
+        &s3_key,
+        &tmp_bin_path,
+        install_artifacts,
+    )
+    .await
+    {
+        fs::remove_file(&tmp_bin_path)?;
+        return Err(e);
+    }
+
+    warn!("stopping icm-relayer.service before install...");
+    bash::run("sudo systemctl stop icm-relayer.service")?;
+    fs::copy(&tmp_bin_path, icm_relayer::BIN_PATH)?;
+    let f = File::open(icm_relayer::BIN_PATH)?;
+    f.set_permissions(PermissionsExt::from_mode(0o777))?;
+    fs::remove_file(&tmp_bin_path)?;
+    Ok(true)
+}
@@ -94,7 +94,7 @@ pub fn execute(log_level: &str, spec_file_path: &str, skip_prompt: bool) -> io::
     match aws_resources.clone().identity {
         Some(identity) => {
             // AWS calls must be made from the same caller
-            if identity != current_identity {
+            if !identity.is_same_caller(&current_identity) {
                 return Err(Error::new(
                     ErrorKind::Other,
                     format!(
@@ -291,10 +291,16 @@ pub fn execute(log_level: &str, spec_file_path: &str, skip_prompt: bool) -> io::
                 Some(vec![Capability::CapabilityNamedIam]),
                 OnFailure::Delete,
                 ec2_instance_role_tmpl,
-                Some(Vec::from([Tag::builder()
-                    .key("KIND")
-                    .value("avalanche-ops/dev-machine")
-                    .build()])),
+                Some(
+                    [
+                        vec![Tag::builder()
+                            .key("KIND")
+                            .value("avalanche-ops/dev-machine")
+                            .build()],
+                        aws::operation::current_stack_tags(),
+                    ]
+                    .concat(),
+                ),
                 Some(Vec::from([
                     build_param("Id", &spec.id),
                     build_param("KmsCmkArn", &aws_resources.kms_cmk_arn.clone().unwrap()),
@@ -366,10 +372,16 @@ pub fn execute(log_level: &str, spec_file_path: &str, skip_prompt: bool) -> io::
                 None,
                 OnFailure::Delete,
                 vpc_tmpl,
-                Some(Vec::from([Tag::builder()
-                    .key("KIND")
-                    .value("avalanche-ops/dev-machine")
-                    .build()])),
+                Some(
+                    [
+                        vec![Tag::builder()
+                            .key("KIND")
+                            .value("avalanche-ops/dev-machine")
+                            .build()],
+                        aws::operation::current_stack_tags(),
+                    ]
+                    .concat(),
+                ),
                 Some(parameters),
             ),
         )
@@ -499,10 +511,16 @@ pub fn execute(log_level: &str, spec_file_path: &str, skip_prompt: bool) -> io::
                 None,
                 OnFailure::Delete,
                 cloudformation_asg_tmpl,
-                Some(Vec::from([Tag::builder()
-                    .key("KIND")
-                    .value("avalanche-ops/dev-machine")
-                    .build()])),
+                Some(
+                    [
+                        vec![Tag::builder()
+                            .key("KIND")
+                            .value("avalanche-ops/dev-machine")
+                            .build()],
+                        aws::operation::current_stack_tags(),
+                    ]
+                    .concat(),
+                ),
                 Some(parameters),
             ),
         )
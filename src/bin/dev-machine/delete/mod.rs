@@ -98,7 +98,7 @@ pub fn execute(
     match aws_resources.identity {
         Some(identity) => {
             // AWS calls must be made from the same caller
-            if identity != current_identity {
+            if !identity.is_same_caller(&current_identity) {
                 return Err(Error::new(
                     ErrorKind::Other,
                     format!(
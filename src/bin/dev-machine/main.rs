@@ -1,5 +1,7 @@
 use clap::Command;
 
+use avalanche_ops::aws;
+
 mod apply;
 mod default_spec;
 mod delete;
@@ -18,6 +20,9 @@ fn main() {
         ])
         .get_matches();
 
+    // tags the AWS sessions and the stacks with this invocation
+    aws::operation::init(&subcommand_path(&matches));
+
     match matches.subcommand() {
         Some((default_spec::NAME, sub_matches)) => {
             let opt = default_spec::Option {
@@ -53,3 +58,14 @@ fn main() {
         _ => unreachable!("unknown subcommand"),
     }
 }
+
+/// Returns the space-separated (nested) subcommand names (e.g., "subnet create").
+fn subcommand_path(matches: &clap::ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut cur = matches;
+    while let Some((name, sub_matches)) = cur.subcommand() {
+        names.push(name);
+        cur = sub_matches;
+    }
+    names.join(" ")
+}
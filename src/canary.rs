@@ -0,0 +1,238 @@
+use std::io::{self, Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+use crate::avalanche::{heartbeat, node};
+
+/// Default number of the canary nodes.
+pub const DEFAULT_NODES: u32 = 1;
+/// Default time that the canary nodes must stay healthy
+/// before the rest of the fleet is updated.
+pub const DEFAULT_SOAK_SECONDS: u64 = 1800;
+/// The CLI blocks and holds the cluster lock (renewed by the heartbeat)
+/// while soaking, so a longer soak would keep the other operations out and
+/// the fleet on mixed versions for too long.
+pub const MAX_SOAK_SECONDS: u64 = 3600;
+
+/// Represents the canary node group that receives the update events
+/// ("events update-artifacts", "events update-chain-upgrades") first.
+/// The rest of the fleet is only updated once the canaries stay healthy
+/// for the soak time.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Canary {
+    /// Number of the canary nodes, picked from the non-anchor nodes first
+    /// (sorted by the node ID). Default "1".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nodes: Option<u32>,
+    /// Default "1800".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soak_seconds: Option<u64>,
+}
+
+impl Canary {
+    pub fn nodes(&self) -> u32 {
+        self.nodes.unwrap_or(DEFAULT_NODES)
+    }
+
+    pub fn soak_seconds(&self) -> u64 {
+        self.soak_seconds.unwrap_or(DEFAULT_SOAK_SECONDS)
+    }
+
+    /// Validates that the canaries leave at least one node
+    /// for the second stage.
+    pub fn validate(&self, total_nodes: u32) -> io::Result<()> {
+        let n = self.nodes();
+        if n == 0 || n >= total_nodes {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'canary.nodes' {} must be between 1 and {} (total {} nodes)",
+                    n,
+                    total_nodes.saturating_sub(1),
+                    total_nodes
+                ),
+            ));
+        }
+        if self.soak_seconds() > MAX_SOAK_SECONDS {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'canary.soak_seconds' {} exceeds {}",
+                    self.soak_seconds(),
+                    MAX_SOAK_SECONDS
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the machine IDs of the canary nodes. The non-anchor nodes
+    /// come first, so the canaries do not hold the initial stakes.
+    pub fn select(&self, heartbeats: &[heartbeat::Heartbeat]) -> Vec<String> {
+        let mut sorted: Vec<&heartbeat::Heartbeat> = heartbeats.iter().collect();
+        sorted.sort_by_key(|hb| {
+            (
+                hb.kind != node::Kind::NonAnchor.as_str(),
+                hb.node_id.clone(),
+            )
+        });
+        sorted
+            .iter()
+            .take(self.nodes() as usize)
+            .map(|hb| hb.machine_id.clone())
+            .collect()
+    }
+}
+
+/// Stage of the staged rollout.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Canary,
+    Fleet,
+}
+
+/// Represents the contents of the update event file,
+/// with the nodes that must react to the event.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Rollout {
+    pub stage: Stage,
+    /// Empty to update every node.
+    #[serde(default)]
+    pub machine_ids: Vec<String>,
+}
+
+impl Rollout {
+    pub fn fleet(machine_ids: Vec<String>) -> Self {
+        Self {
+            stage: Stage::Fleet,
+            machine_ids,
+        }
+    }
+
+    pub fn encode_json(&self) -> io::Result<String> {
+        serde_json::to_string(self)
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to serialize {}", e)))
+    }
+
+    /// Decodes the event file. The events written before the staged
+    /// rollouts (the spec YAML) target every node.
+    pub fn decode(d: &[u8]) -> Self {
+        serde_json::from_slice(d).unwrap_or_else(|_| Self::fleet(Vec::new()))
+    }
+
+    pub fn includes(&self, machine_id: &str) -> bool {
+        self.machine_ids.is_empty() || self.machine_ids.iter().any(|v| v == machine_id)
+    }
+}
+
+/// Represents the canary status from its latest heartbeat.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Status {
+    /// No heartbeat since the given time, or not running the expected binary yet.
+    Pending,
+    Healthy,
+    Unhealthy(String),
+}
+
+/// Returns the status of the canary from the heartbeat published after "since"
+/// (unix time in seconds). The binary checksum is only compared if expected.
+pub fn status(
+    hb: Option<&heartbeat::Heartbeat>,
+    since: u64,
+    expected_bin_sha256: Option<&str>,
+) -> Status {
+    let hb = match hb {
+        Some(v) if v.timestamp >= since => v,
+        _ => return Status::Pending,
+    };
+    if let Some(e) = expected_bin_sha256 {
+        if e != hb.avalanchego_bin_sha256 {
+            return Status::Pending;
+        }
+    }
+    match hb.healthy {
+        Some(true) => Status::Healthy,
+        Some(false) => Status::Unhealthy(format!("node {} reports unhealthy", hb.node_id)),
+        None => Status::Unhealthy(format!("node {} reports no health", hb.node_id)),
+    }
+}
+
+#[test]
+fn test_canary() {
+    let canary = Canary::default();
+    assert!(canary.validate(2).is_ok());
+    assert!(canary.validate(1).is_err());
+    assert!(Canary {
+        nodes: Some(0),
+        ..Default::default()
+    }
+    .validate(5)
+    .is_err());
+    assert!(Canary {
+        soak_seconds: Some(MAX_SOAK_SECONDS + 1),
+        ..Default::default()
+    }
+    .validate(5)
+    .is_err());
+
+    let hb = |node_id: &str, kind: &node::Kind| heartbeat::Heartbeat {
+        node_id: node_id.to_string(),
+        machine_id: format!("i-{}", node_id),
+        kind: kind.as_str().to_string(),
+        public_ip: String::new(),
+        avalanched_version: String::new(),
+        avalanchego_version: String::new(),
+        disk_total_bytes: 0,
+        disk_available_bytes: 0,
+        uptime_seconds: 0,
+        last_backup: None,
+        config_sha256: String::new(),
+        avalanchego_config: Default::default(),
+        avalanchego_bin_sha256: String::from("abc"),
+        plugins: Default::default(),
+        index_backfill: None,
+        healthy: Some(true),
+        timestamp: 100,
+    };
+    let heartbeats = vec![
+        hb("a", &node::Kind::Anchor),
+        hb("c", &node::Kind::NonAnchor),
+        hb("b", &node::Kind::NonAnchor),
+    ];
+    let two = Canary {
+        nodes: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(two.select(&heartbeats), vec!["i-b", "i-c"]);
+
+    assert_eq!(status(None, 0, None), Status::Pending);
+    assert_eq!(status(Some(&heartbeats[0]), 101, None), Status::Pending);
+    assert_eq!(
+        status(Some(&heartbeats[0]), 100, Some("def")),
+        Status::Pending
+    );
+    assert_eq!(
+        status(Some(&heartbeats[0]), 100, Some("abc")),
+        Status::Healthy
+    );
+    let mut unhealthy = hb("d", &node::Kind::NonAnchor);
+    unhealthy.healthy = Some(false);
+    assert!(matches!(
+        status(Some(&unhealthy), 0, None),
+        Status::Unhealthy(_)
+    ));
+
+    let rollout = Rollout {
+        stage: Stage::Canary,
+        machine_ids: vec![String::from("i-b")],
+    };
+    let decoded = Rollout::decode(rollout.encode_json().unwrap().as_bytes());
+    assert_eq!(decoded, rollout);
+    assert!(decoded.includes("i-b"));
+    assert!(!decoded.includes("i-c"));
+    // the legacy event contents
+    assert!(Rollout::decode(b"id: test\n").includes("i-c"));
+}
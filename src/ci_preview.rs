@@ -0,0 +1,131 @@
+use std::{
+    io::{self, Error, ErrorKind},
+    time::Duration,
+};
+
+use aws_sdk_cloudformation::model::Tag;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{auto_delete, utils::rfc3339};
+
+/// Stack tag of the CI job that created the preview network.
+pub const TAG_CI_JOB_ID: &str = "avalanche-ops:ci-job-id";
+
+pub const MAX_TTL_MINUTES: u32 = 24 * 60;
+
+/// Default time for all nodes to become ready, before "apply" fails.
+pub const DEFAULT_READY_TIMEOUT_MINUTES: u32 = 20;
+pub const MIN_READY_TIMEOUT_MINUTES: u32 = 5;
+
+/// Represents the ephemeral preview network created by the CI job
+/// (e.g., GitHub Actions), to test the subnet changes end to end.
+/// The network has no NLB, fails "apply" fast if the nodes are not ready,
+/// and tags all stacks with the job ID and the expiry for "reap".
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct CiPreview {
+    /// CI job ID (e.g., "${{ github.run_id }}").
+    pub job_id: String,
+    #[serde(with = "rfc3339::serde_format")]
+    pub expires_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready_timeout_minutes: Option<u32>,
+}
+
+impl CiPreview {
+    pub fn new(job_id: &str, ttl_minutes: u32) -> Self {
+        Self {
+            job_id: job_id.to_string(),
+            expires_at: Utc::now() + chrono::Duration::minutes(ttl_minutes as i64),
+            ready_timeout_minutes: None,
+        }
+    }
+
+    pub fn validate(&self) -> io::Result<()> {
+        // the stack tag value limits
+        if self.job_id.is_empty()
+            || self.job_id.len() > 128
+            || !self
+                .job_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_.:/=+@-".contains(c))
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid 'ci_preview.job_id' '{}'", self.job_id),
+            ));
+        }
+        if let Some(v) = self.ready_timeout_minutes {
+            if v < MIN_READY_TIMEOUT_MINUTES {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "'ci_preview.ready_timeout_minutes' {} <minimum {}",
+                        v, MIN_READY_TIMEOUT_MINUTES
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now >= self.expires_at
+    }
+
+    pub fn ready_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.ready_timeout_minutes
+                .unwrap_or(DEFAULT_READY_TIMEOUT_MINUTES) as u64
+                * 60,
+        )
+    }
+
+    /// Returns the CloudFormation stack tags for "reap", in addition to
+    /// the cluster tags of "auto_delete::stack_tags".
+    pub fn stack_tags(&self) -> Vec<Tag> {
+        vec![
+            Tag::builder()
+                .key(TAG_CI_JOB_ID)
+                .value(&self.job_id)
+                .build(),
+            Tag::builder()
+                .key(auto_delete::TAG_EXPIRES_AT)
+                .value(self.expires_at.to_rfc3339_opts(SecondsFormat::Secs, true))
+                .build(),
+        ]
+    }
+}
+
+#[test]
+fn test_ci_preview() {
+    use std::collections::HashMap;
+
+    let mut preview = CiPreview::new("1234567890", 60);
+    assert!(preview.validate().is_ok());
+    assert!(!preview.is_expired(Utc::now()));
+    assert!(preview.is_expired(Utc::now() + chrono::Duration::minutes(61)));
+    assert_eq!(preview.ready_timeout(), Duration::from_secs(20 * 60));
+
+    preview.ready_timeout_minutes = Some(1);
+    assert!(preview.validate().is_err());
+    preview.ready_timeout_minutes = None;
+    preview.job_id = String::from("run 1");
+    assert!(preview.validate().is_err());
+    preview.job_id = String::from("1234567890");
+
+    let tags: HashMap<String, String> = [
+        auto_delete::stack_tags("aops-custom-test", "bucket"),
+        preview.stack_tags(),
+    ]
+    .concat()
+    .into_iter()
+    .map(|t| (t.key.unwrap(), t.value.unwrap()))
+    .collect();
+    let tagged = auto_delete::Tagged::from_tags(&tags).unwrap();
+    assert_eq!(tagged.job_id.as_deref(), Some("1234567890"));
+    assert!(tagged.should_reap(Some("1234567890"), Utc::now()));
+    assert!(!tagged.should_reap(None, Utc::now()));
+    assert!(tagged.should_reap(None, Utc::now() + chrono::Duration::minutes(61)));
+}
@@ -0,0 +1,173 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Error, ErrorKind},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::avalanche::{avalanchego::config as avalanchego_config, heartbeat, node};
+
+/// avalanchego flags that "avalanche-ops" manages for the whole cluster,
+/// which must not differ by node group.
+pub const CLUSTER_MANAGED_FLAGS: [&str; 12] = [
+    "config-file",
+    "genesis",
+    "network-id",
+    "db-type",
+    "db-dir",
+    "log-dir",
+    "http-port",
+    "staking-port",
+    "staking-tls-key-file",
+    "staking-tls-cert-file",
+    "staking-signer-key-file",
+    "chain-config-dir",
+];
+
+/// Represents the avalanchego flags per node group, overriding
+/// "avalanchego_config" in the config files that the nodes of each ASG write.
+/// The flags are in the avalanchego config file format
+/// (e.g., "snow-mixed-query-num-push-vdr: 10").
+/// ref. https://docs.avax.network/nodes/maintain/avalanchego-config-flags
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct ConfigOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor_nodes: Option<BTreeMap<String, serde_json::Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub non_anchor_nodes: Option<BTreeMap<String, serde_json::Value>>,
+    /// The node classes in the public NLB target group (see "api_exposure").
+    /// The anchor or non-anchor flags above take precedence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_nodes: Option<BTreeMap<String, serde_json::Value>>,
+}
+
+impl ConfigOverrides {
+    /// Returns the flags of the node class, with the class flags
+    /// taking precedence over the API node flags.
+    pub fn flags(&self, kind: &node::Kind, is_api: bool) -> BTreeMap<String, serde_json::Value> {
+        let mut flags = BTreeMap::new();
+        if is_api {
+            flags.extend(self.api_nodes.clone().unwrap_or_default());
+        }
+        let class = match kind {
+            node::Kind::Anchor => &self.anchor_nodes,
+            node::Kind::NonAnchor => &self.non_anchor_nodes,
+        };
+        flags.extend(class.clone().unwrap_or_default());
+        flags
+    }
+
+    /// Overwrites the configuration with the flags of the node class.
+    pub fn apply(
+        &self,
+        kind: &node::Kind,
+        is_api: bool,
+        avalanchego_config: &avalanchego_config::Config,
+    ) -> io::Result<avalanchego_config::Config> {
+        let flags = self.flags(kind, is_api);
+        if flags.is_empty() {
+            return Ok(avalanchego_config.clone());
+        }
+
+        let mut v = serde_json::to_value(avalanchego_config)
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to serialize {}", e)))?;
+        let m = v.as_object_mut().unwrap();
+        for (k, flag) in flags.into_iter() {
+            if flag.is_null() {
+                m.remove(&k);
+            } else {
+                m.insert(k, flag);
+            }
+        }
+        serde_json::from_value(v).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "invalid 'config_overrides' for {} nodes ({})",
+                    kind.as_str(),
+                    e
+                ),
+            )
+        })
+    }
+
+    /// Validates that no group overrides the flags managed for the whole
+    /// cluster or set per node, and that the flags have the valid types.
+    pub fn validate(
+        &self,
+        avalanchego_config: &avalanchego_config::Config,
+        is_api: impl Fn(&node::Kind) -> bool,
+    ) -> io::Result<()> {
+        let groups = [
+            ("anchor_nodes", &self.anchor_nodes),
+            ("non_anchor_nodes", &self.non_anchor_nodes),
+            ("api_nodes", &self.api_nodes),
+        ];
+        for (name, flags) in groups.iter() {
+            for k in flags.as_ref().map(|v| v.keys()).into_iter().flatten() {
+                if CLUSTER_MANAGED_FLAGS.contains(&k.as_str())
+                    || heartbeat::NODE_SPECIFIC_CONFIG_KEYS.contains(&k.as_str())
+                {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("'config_overrides.{}' cannot override '{}'", name, k),
+                    ));
+                }
+            }
+        }
+        for kind in [node::Kind::Anchor, node::Kind::NonAnchor] {
+            self.apply(&kind, is_api(&kind), avalanchego_config)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_config_overrides() {
+    let config = avalanchego_config::Config::default();
+    let overrides: ConfigOverrides = serde_yaml::from_str(
+        "
+anchor_nodes:
+  snow-mixed-query-num-push-vdr: 10
+non_anchor_nodes:
+  api-admin-enabled: false
+api_nodes:
+  api-admin-enabled: true
+  api-keystore-enabled: false
+  throttler-inbound-at-large-alloc-size: 1048576
+",
+    )
+    .unwrap();
+    assert!(overrides.validate(&config, |_| true).is_ok());
+
+    let anchor = overrides
+        .apply(&node::Kind::Anchor, false, &config)
+        .unwrap();
+    assert_eq!(
+        anchor.extra_flags.get("snow-mixed-query-num-push-vdr"),
+        Some(&serde_json::json!(10))
+    );
+    assert_eq!(anchor.throttler_inbound_at_large_alloc_size, None);
+    assert_eq!(anchor.api_keystore_enabled, Some(true));
+
+    // the class flags take precedence over the API node flags
+    let non_anchor = overrides
+        .apply(&node::Kind::NonAnchor, true, &config)
+        .unwrap();
+    assert_eq!(non_anchor.api_admin_enabled, Some(false));
+    assert_eq!(non_anchor.api_keystore_enabled, Some(false));
+    assert_eq!(
+        non_anchor.throttler_inbound_at_large_alloc_size,
+        Some(1048576)
+    );
+    assert!(non_anchor.extra_flags.is_empty());
+
+    let managed: ConfigOverrides = serde_yaml::from_str("api_nodes:\n  db-dir: /data\n").unwrap();
+    assert!(managed.validate(&config, |_| true).is_err());
+    let node_specific: ConfigOverrides =
+        serde_yaml::from_str("anchor_nodes:\n  public-ip: 1.2.3.4\n").unwrap();
+    assert!(node_specific.validate(&config, |_| false).is_err());
+    let invalid: ConfigOverrides = serde_yaml::from_str("anchor_nodes:\n  http-host: 1\n").unwrap();
+    assert!(invalid.validate(&config, |_| false).is_err());
+}
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+
+pub const HOURS_PER_MONTH: f64 = 730.0;
+
+/// Approximate on-demand hourly prices in USD (us-west-2, Linux),
+/// only to hint the cost of the choices.
+pub const ON_DEMAND_HOURLY_USD: [(&str, f64); 12] = [
+    ("c6a.large", 0.0765),
+    ("m6a.large", 0.0864),
+    ("m5.large", 0.096),
+    ("c5.large", 0.085),
+    ("c6a.xlarge", 0.153),
+    ("m6a.xlarge", 0.1728),
+    ("m5.xlarge", 0.192),
+    ("c5.xlarge", 0.17),
+    ("c6a.2xlarge", 0.306),
+    ("m6a.2xlarge", 0.3456),
+    ("m5.2xlarge", 0.384),
+    ("c5.2xlarge", 0.34),
+];
+
+pub fn on_demand_hourly(instance_type: &str) -> Option<f64> {
+    ON_DEMAND_HOURLY_USD
+        .iter()
+        .find(|(t, _)| *t == instance_type)
+        .map(|(_, price)| *price)
+}
+
+/// Represents the active reserved instances of the instance type in the region.
+/// ref. https://docs.aws.amazon.com/AWSEC2/latest/APIReference/API_DescribeReservedInstances.html
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Reservation {
+    pub reserved_instances_id: String,
+    pub instance_type: String,
+    pub count: u32,
+    /// Upfront price amortized over the term, plus the hourly charges.
+    pub effective_hourly_usd: f64,
+}
+
+impl Reservation {
+    /// Returns the effective hourly price of the reservation.
+    pub fn effective_hourly(
+        fixed_price: f64,
+        duration_seconds: i64,
+        usage_price: f64,
+        recurring_hourly: f64,
+    ) -> f64 {
+        let hours = duration_seconds as f64 / 3600.0;
+        let amortized = if hours > 0.0 {
+            fixed_price / hours
+        } else {
+            0.0
+        };
+        amortized + usage_price + recurring_hourly
+    }
+}
+
+/// Represents the rate of the instance type under an active savings plan.
+/// ref. https://docs.aws.amazon.com/savingsplans/latest/APIReference/API_DescribeSavingsPlanRates.html
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct SavingsPlanRate {
+    pub savings_plan_id: String,
+    /// Hourly commitment of the plan in USD.
+    pub commitment_hourly_usd: f64,
+    /// Hourly rate of the instance type under the plan in USD.
+    pub rate_hourly_usd: f64,
+}
+
+/// Represents the projected cost of the nodes, applying the active
+/// reserved instances first, then the savings plans, then the on-demand price,
+/// the same order AWS applies the discounts.
+/// The reservations already used by the running instances of the same type
+/// are excluded. The savings plan commitments are assumed to be unused by
+/// the other workloads, so the projection is a lower bound with savings plans.
+/// ref. https://docs.aws.amazon.com/savingsplans/latest/userguide/sp-applying.html
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Estimate {
+    pub instance_type: String,
+    pub nodes: u32,
+    pub on_demand_hourly_usd: f64,
+    pub reserved_nodes: u32,
+    /// Fractional, if the commitment partially covers a node.
+    pub savings_plan_nodes: f64,
+    pub projected_hourly_usd: f64,
+}
+
+impl Estimate {
+    pub fn new(
+        instance_type: &str,
+        nodes: u32,
+        on_demand_hourly_usd: f64,
+        reservations: &[Reservation],
+        running: u32,
+        savings_plans: &[SavingsPlanRate],
+    ) -> Self {
+        let mut reservations: Vec<&Reservation> = reservations
+            .iter()
+            .filter(|r| r.instance_type == instance_type)
+            .collect();
+        reservations.sort_by(|a, b| a.effective_hourly_usd.total_cmp(&b.effective_hourly_usd));
+
+        // the running instances already use the cheapest reservations
+        let mut used = running;
+        let mut remaining = nodes;
+        let mut reserved_nodes = 0;
+        let mut projected_hourly_usd = 0.0;
+        for r in reservations {
+            let available = r.count.saturating_sub(used);
+            used = used.saturating_sub(r.count);
+            let covered = available.min(remaining);
+            reserved_nodes += covered;
+            remaining -= covered;
+            projected_hourly_usd += covered as f64 * r.effective_hourly_usd;
+        }
+
+        let mut savings_plans: Vec<&SavingsPlanRate> = savings_plans
+            .iter()
+            .filter(|p| p.rate_hourly_usd > 0.0)
+            .collect();
+        savings_plans.sort_by(|a, b| a.rate_hourly_usd.total_cmp(&b.rate_hourly_usd));
+        let mut uncovered = remaining as f64;
+        let mut savings_plan_nodes = 0.0;
+        for p in savings_plans {
+            let covered = (p.commitment_hourly_usd / p.rate_hourly_usd).min(uncovered);
+            savings_plan_nodes += covered;
+            uncovered -= covered;
+            projected_hourly_usd += covered * p.rate_hourly_usd;
+        }
+        projected_hourly_usd += uncovered * on_demand_hourly_usd;
+
+        Self {
+            instance_type: instance_type.to_string(),
+            nodes,
+            on_demand_hourly_usd,
+            reserved_nodes,
+            savings_plan_nodes,
+            projected_hourly_usd,
+        }
+    }
+
+    pub fn on_demand_monthly_usd(&self) -> f64 {
+        self.on_demand_hourly_usd * HOURS_PER_MONTH * self.nodes as f64
+    }
+
+    pub fn projected_monthly_usd(&self) -> f64 {
+        self.projected_hourly_usd * HOURS_PER_MONTH
+    }
+}
+
+#[test]
+fn test_estimate() {
+    let hourly = on_demand_hourly("c6a.large").unwrap();
+    assert!(on_demand_hourly("t2.micro").is_none());
+
+    let e = Estimate::new("c6a.large", 4, hourly, &[], 0, &[]);
+    assert_eq!(e.reserved_nodes, 0);
+    assert!((e.projected_monthly_usd() - e.on_demand_monthly_usd()).abs() < 1e-9);
+
+    // 1-year all upfront
+    let effective = Reservation::effective_hourly(438.0, 365 * 24 * 3600, 0.0, 0.0);
+    assert!((effective - 0.05).abs() < 1e-9);
+
+    let reservations = vec![
+        Reservation {
+            reserved_instances_id: String::from("a"),
+            instance_type: String::from("c6a.large"),
+            count: 2,
+            effective_hourly_usd: 0.05,
+        },
+        Reservation {
+            reserved_instances_id: String::from("b"),
+            instance_type: String::from("m5.large"),
+            count: 10,
+            effective_hourly_usd: 0.06,
+        },
+    ];
+    let e = Estimate::new("c6a.large", 4, hourly, &reservations, 0, &[]);
+    assert_eq!(e.reserved_nodes, 2);
+    assert!((e.projected_hourly_usd - (2.0 * 0.05 + 2.0 * hourly)).abs() < 1e-9);
+
+    // one reservation is used by the running instance
+    let e = Estimate::new("c6a.large", 4, hourly, &reservations, 1, &[]);
+    assert_eq!(e.reserved_nodes, 1);
+
+    let savings_plans = vec![SavingsPlanRate {
+        savings_plan_id: String::from("sp"),
+        commitment_hourly_usd: 0.05,
+        rate_hourly_usd: 0.05,
+    }];
+    let e = Estimate::new("c6a.large", 4, hourly, &reservations, 0, &savings_plans);
+    assert_eq!(e.reserved_nodes, 2);
+    assert!((e.savings_plan_nodes - 1.0).abs() < 1e-9);
+    assert!((e.projected_hourly_usd - (3.0 * 0.05 + hourly)).abs() < 1e-9);
+}
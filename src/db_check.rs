@@ -0,0 +1,322 @@
+use std::{
+    fs::{self, File},
+    io::{self, Error, ErrorKind, Read},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::StorageNamespace;
+
+/// Written while "avalanched check-db" has avalanchego stopped,
+/// so the remediation in "avalanched run" does not restart the node.
+pub const IN_PROGRESS_FILE_PATH: &str = "/var/lib/avalanched/db-check-in-progress";
+
+/// LevelDB table footer ("metaindex" and "index" handles, and the magic number).
+/// ref. https://github.com/google/leveldb/blob/main/doc/table_format.md
+const TABLE_FOOTER_LEN: u64 = 48;
+
+/// ref. https://github.com/google/leveldb/blob/main/table/format.h
+const TABLE_MAGIC: u64 = 0xdb47_7524_8b80_fb57;
+
+const READ_BUFFER_LEN: usize = 1 << 20;
+
+/// Represents where the checked database comes from.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// The live database, with avalanchego stopped during the check.
+    Stopped,
+    /// The database on a cloned (e.g., restored from the snapshot) volume,
+    /// while avalanchego keeps running.
+    Cloned,
+}
+
+/// Represents the kind of corruption found in the database.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// No LevelDB directory (with "CURRENT") under the database directory.
+    NoDatabase,
+    /// "CURRENT" is empty or names a missing "MANIFEST".
+    MissingManifest,
+    /// The file cannot be read to the end (e.g., I/O error on the bad disk).
+    Unreadable,
+    /// The file is shorter than its format allows.
+    Truncated,
+    /// The table file does not end with the LevelDB magic number.
+    BadTableMagic,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Finding {
+    pub path: String,
+    pub kind: FindingKind,
+    pub detail: String,
+}
+
+/// Represents the result of reading every file of the database.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Scan {
+    pub databases: u64,
+    pub files: u64,
+    pub bytes: u64,
+    #[serde(default)]
+    pub findings: Vec<Finding>,
+}
+
+/// Represents the report of "avalanched check-db", uploaded to
+/// "[ID]/db-checks/[NAME].json" in the S3 bucket.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Report {
+    pub id: String,
+    pub machine_id: String,
+    pub db_dir: String,
+    pub source: Source,
+    /// RFC3339 time when the check started.
+    pub started_at: String,
+    pub elapsed_seconds: u64,
+    pub scan: Scan,
+}
+
+impl Report {
+    pub fn is_healthy(&self) -> bool {
+        self.scan.findings.is_empty()
+    }
+
+    pub fn s3_key(id: &str, name: &str) -> String {
+        StorageNamespace::DbCheckReport(id.to_string(), name.to_string()).encode()
+    }
+
+    pub fn encode_json(&self) -> io::Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("failed to serialize db check report to JSON {}", e),
+            )
+        })
+    }
+
+    pub fn decode_json(d: &[u8]) -> io::Result<Self> {
+        serde_json::from_slice(d).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("failed to parse db check report {}", e),
+            )
+        })
+    }
+}
+
+/// Reads every file of the LevelDB databases under the directory
+/// (e.g., "[DB_DIR]/[NETWORK]/v1.4.5"), without opening the databases,
+/// and returns the corruptions found. The database must not be in use.
+pub fn scan(db_dir: &str) -> io::Result<Scan> {
+    if !Path::new(db_dir).is_dir() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("database directory '{}' not found", db_dir),
+        ));
+    }
+
+    let mut scan = Scan::default();
+    for entry in WalkDir::new(db_dir).into_iter() {
+        let entry = match entry {
+            Ok(v) => v,
+            Err(e) => {
+                scan.findings.push(Finding {
+                    path: e
+                        .path()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default(),
+                    kind: FindingKind::Unreadable,
+                    detail: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let p = entry.path();
+        if entry.file_type().is_dir() {
+            if p.join("CURRENT").is_file() {
+                scan.databases += 1;
+                check_current(p, &mut scan.findings);
+            }
+            continue;
+        }
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        scan.files += 1;
+        let path = p.display().to_string();
+        let (size, footer) = match read_to_end(p) {
+            Ok(v) => v,
+            Err(e) => {
+                scan.findings.push(Finding {
+                    path,
+                    kind: FindingKind::Unreadable,
+                    detail: e.to_string(),
+                });
+                continue;
+            }
+        };
+        scan.bytes += size;
+
+        let is_table = matches!(
+            p.extension().and_then(|e| e.to_str()),
+            Some("ldb") | Some("sst")
+        );
+        if !is_table {
+            continue;
+        }
+        if size < TABLE_FOOTER_LEN {
+            scan.findings.push(Finding {
+                path,
+                kind: FindingKind::Truncated,
+                detail: format!(
+                    "table is {} bytes, shorter than the footer ({} bytes)",
+                    size, TABLE_FOOTER_LEN
+                ),
+            });
+        } else if footer != TABLE_MAGIC {
+            scan.findings.push(Finding {
+                path,
+                kind: FindingKind::BadTableMagic,
+                detail: format!("table ends with 0x{:016x}", footer),
+            });
+        }
+    }
+
+    if scan.databases == 0 {
+        scan.findings.push(Finding {
+            path: db_dir.to_string(),
+            kind: FindingKind::NoDatabase,
+            detail: String::from("no LevelDB directory (with 'CURRENT') found"),
+        });
+    }
+    Ok(scan)
+}
+
+/// Checks "CURRENT" names the existing non-empty "MANIFEST".
+fn check_current(dir: &Path, findings: &mut Vec<Finding>) {
+    let current = dir.join("CURRENT");
+    let manifest = match fs::read_to_string(&current) {
+        Ok(v) => v.trim().to_string(),
+        // reported as unreadable when the file is read
+        Err(_) => return,
+    };
+    if !manifest.starts_with("MANIFEST-") {
+        findings.push(Finding {
+            path: current.display().to_string(),
+            kind: FindingKind::MissingManifest,
+            detail: format!("unexpected 'CURRENT' contents '{}'", manifest),
+        });
+        return;
+    }
+    let manifest_path = dir.join(&manifest);
+    match fs::metadata(&manifest_path) {
+        Ok(m) if m.len() > 0 => {}
+        Ok(_) => findings.push(Finding {
+            path: manifest_path.display().to_string(),
+            kind: FindingKind::Truncated,
+            detail: String::from("empty manifest"),
+        }),
+        Err(e) => findings.push(Finding {
+            path: manifest_path.display().to_string(),
+            kind: FindingKind::MissingManifest,
+            detail: format!("'CURRENT' names missing '{}' ({})", manifest, e),
+        }),
+    }
+}
+
+/// Reads the whole file, so the bad sectors surface as I/O errors,
+/// and returns the size and the last 8 bytes (little-endian).
+fn read_to_end(p: &Path) -> io::Result<(u64, u64)> {
+    let mut f = File::open(p)?;
+    let mut buf = vec![0u8; READ_BUFFER_LEN];
+    let mut tail = [0u8; 8];
+    let mut size = 0_u64;
+    loop {
+        let n = f.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        size += n as u64;
+        if n >= 8 {
+            tail.copy_from_slice(&buf[n - 8..n]);
+        } else {
+            tail.rotate_left(n);
+            tail[8 - n..].copy_from_slice(&buf[..n]);
+        }
+    }
+    Ok((size, u64::from_le_bytes(tail)))
+}
+
+/// Generates the bash script for SSM "AWS-RunShellScript", which runs
+/// "avalanched check-db" on the node and uploads the report.
+pub fn check_script(report_name: &str, db_dir: Option<&str>, stop_node: bool) -> String {
+    let mut cmd = format!(
+        "sudo /usr/local/bin/avalanched check-db --report-name {}",
+        report_name
+    );
+    if let Some(d) = db_dir {
+        cmd.push_str(&format!(" --db-dir {}", d));
+    }
+    if stop_node {
+        cmd.push_str(" --stop-node");
+    }
+    cmd
+}
+
+#[test]
+fn test_db_check() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("mainnet").join("v1.4.5");
+    fs::create_dir_all(&db).unwrap();
+    fs::write(db.join("CURRENT"), "MANIFEST-000002\n").unwrap();
+    fs::write(db.join("MANIFEST-000002"), [1u8; 16]).unwrap();
+
+    let mut table = vec![7u8; 100];
+    table.extend_from_slice(&TABLE_MAGIC.to_le_bytes());
+    fs::write(db.join("000005.ldb"), &table).unwrap();
+
+    let scanned = scan(dir.path().to_str().unwrap()).unwrap();
+    assert_eq!(scanned.databases, 1);
+    assert_eq!(scanned.files, 3);
+    assert_eq!(scanned.bytes, 16 + 16 + 108);
+    assert!(scanned.findings.is_empty());
+
+    // flipped footer, truncated table, missing manifest
+    let mut bad = table.clone();
+    *bad.last_mut().unwrap() ^= 0xff;
+    fs::write(db.join("000006.ldb"), &bad).unwrap();
+    fs::write(db.join("000007.ldb"), [0u8; 10]).unwrap();
+    fs::remove_file(db.join("MANIFEST-000002")).unwrap();
+    let kinds: Vec<FindingKind> = scan(dir.path().to_str().unwrap())
+        .unwrap()
+        .findings
+        .iter()
+        .map(|f| f.kind)
+        .collect();
+    assert_eq!(kinds.len(), 3);
+    assert!(kinds.contains(&FindingKind::MissingManifest));
+    assert!(kinds.contains(&FindingKind::BadTableMagic));
+    assert!(kinds.contains(&FindingKind::Truncated));
+
+    let empty = tempfile::tempdir().unwrap();
+    let scanned = scan(empty.path().to_str().unwrap()).unwrap();
+    assert_eq!(scanned.findings[0].kind, FindingKind::NoDatabase);
+    assert!(scan("/tmp/not-exists-db-dir").is_err());
+
+    assert_eq!(
+        check_script("20220501-i-123", None, true),
+        "sudo /usr/local/bin/avalanched check-db --report-name 20220501-i-123 --stop-node"
+    );
+    assert_eq!(
+        Report::s3_key("my-id", "20220501-i-123"),
+        "my-id/db-checks/20220501-i-123.json"
+    );
+}
@@ -0,0 +1,140 @@
+use std::io::{self, Error, ErrorKind};
+
+use crate::avalanche::{avalanchego::api::platform::DelegatedValidator, platformvm::txs::AVAX};
+
+/// The validator stake and its delegations combined cannot exceed
+/// this multiple of the validator's own stake.
+/// ref. https://github.com/ava-labs/avalanchego/blob/v1.9.0/genesis/genesis_mainnet.go
+pub const MAX_VALIDATOR_WEIGHT_FACTOR: u64 = 5;
+
+/// The validator stake and its delegations combined cannot exceed this.
+/// ref. https://github.com/ava-labs/avalanchego/blob/v1.9.0/genesis/genesis_mainnet.go
+pub const MAX_VALIDATOR_STAKE: u64 = 3_000_000 * AVAX;
+
+/// Delegation limits of the network.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Limits {
+    /// From "platform.getMinStake".
+    pub min_delegator_stake: u64,
+    pub max_validator_stake: u64,
+    pub max_validator_weight_factor: u64,
+}
+
+impl Limits {
+    pub fn new(min_delegator_stake: u64) -> Self {
+        Self {
+            min_delegator_stake,
+            max_validator_stake: MAX_VALIDATOR_STAKE,
+            max_validator_weight_factor: MAX_VALIDATOR_WEIGHT_FACTOR,
+        }
+    }
+
+    /// Returns the stake the validator still accepts from the delegators.
+    pub fn capacity(&self, v: &DelegatedValidator) -> u64 {
+        let max = (v.weight.saturating_mul(self.max_validator_weight_factor))
+            .min(self.max_validator_stake);
+        max.saturating_sub(v.weight + v.delegated_weight())
+    }
+
+    /// Validates the delegation to the validator before issuing the transaction,
+    /// which the network rejects otherwise (after the transaction fee is burned).
+    pub fn validate(
+        &self,
+        v: &DelegatedValidator,
+        stake_amount: u64,
+        start: u64,
+        end: u64,
+        max_delegation_fee_percent: Option<f64>,
+    ) -> io::Result<()> {
+        if stake_amount < self.min_delegator_stake {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "stake amount {} < min delegator stake {}",
+                    stake_amount, self.min_delegator_stake
+                ),
+            ));
+        }
+        let capacity = self.capacity(v);
+        if stake_amount > capacity {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "stake amount {} exceeds the remaining delegation capacity {} of {}",
+                    stake_amount, capacity, v.node_id
+                ),
+            ));
+        }
+        if start >= end {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("start time {} >= end time {}", start, end),
+            ));
+        }
+        if end > v.end_time {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "end time {} is after the validation end time {} of {}",
+                    end, v.end_time, v.node_id
+                ),
+            ));
+        }
+        if let Some(max) = max_delegation_fee_percent {
+            if v.delegation_fee_percent > max {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "delegation fee {}% of {} exceeds {}%",
+                        v.delegation_fee_percent, v.node_id, max
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_limits() {
+    use crate::avalanche::avalanchego::api::platform::Delegation;
+
+    let mut v = DelegatedValidator {
+        node_id: String::from("NodeID-A"),
+        start_time: 0,
+        end_time: 1000,
+        weight: 2000 * AVAX,
+        delegation_fee_percent: 2_f64,
+        delegations: vec![Delegation {
+            tx_id: String::from("tx"),
+            node_id: String::from("NodeID-A"),
+            start_time: 0,
+            end_time: 500,
+            stake_amount: 3000 * AVAX,
+            reward_owner_addresses: Vec::new(),
+            potential_reward: 0,
+        }],
+    };
+    let limits = Limits::new(25 * AVAX);
+    assert_eq!(limits.capacity(&v), 5000 * AVAX);
+
+    assert!(limits.validate(&v, 25 * AVAX, 10, 1000, None).is_ok());
+    assert!(limits
+        .validate(&v, 5000 * AVAX, 10, 1000, Some(2_f64))
+        .is_ok());
+    // below the min stake
+    assert!(limits.validate(&v, 24 * AVAX, 10, 1000, None).is_err());
+    // over the capacity
+    assert!(limits.validate(&v, 5001 * AVAX, 10, 1000, None).is_err());
+    // after the validation ends
+    assert!(limits.validate(&v, 25 * AVAX, 10, 1001, None).is_err());
+    // fee too high
+    assert!(limits
+        .validate(&v, 25 * AVAX, 10, 1000, Some(1_f64))
+        .is_err());
+
+    // capped by the max validator stake
+    v.weight = 1_000_000 * AVAX;
+    v.delegations.clear();
+    assert_eq!(limits.capacity(&v), 2_000_000 * AVAX);
+}
@@ -0,0 +1,1994 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Error, ErrorKind},
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use aws_sdk_cloudformation::model::{Capability, OnFailure, Parameter, StackStatus, Tag};
+use aws_sdk_s3::model::Object;
+use chrono::Utc;
+use log::{info, warn};
+use rust_embed::RustEmbed;
+use tokio::runtime::Runtime;
+
+use super::required;
+use crate::{
+    api_auth, auto_delete,
+    avalanche::{
+        avalanchego::api::{auth, eth, health},
+        node,
+    },
+    aws::{self, cloudformation, ec2, envelope, kms, s3, sts},
+    fips, icm_relayer, join_validation, metric_schema, peer_allowlist, preflight,
+    provider::{self, CloudProvider, NetworkSpec, NodeGroup, NodeGroupSpec},
+    rpc_usage, spec_diff, spec_state, time_sync,
+    utils::{compress, provenance, random},
+    Spec,
+};
+
+#[derive(RustEmbed)]
+#[folder = "src/aws/cfn-templates/avalanche-node/"]
+#[prefix = "src/aws/cfn-templates/avalanche-node/"]
+struct Asset;
+
+/// Decides whether "run" creates the resources, once the spec is loaded
+/// and the preflight checks pass, so the prompts stay in the caller.
+pub trait Approver {
+    /// Called with the changes from the applied spec, or None on the
+    /// first "apply". Returns false to stop without changing the cluster.
+    fn approve(&self, spec: &Spec, changes: Option<&[spec_diff::Change]>) -> io::Result<bool>;
+
+    /// Returns true to apply the destructive changes even outside of
+    /// the maintenance window in the spec.
+    fn force(&self) -> bool {
+        false
+    }
+}
+
+/// Approves without prompts, and the destructive changes (replacement or
+/// node ID changes) only if allowed, otherwise fails on them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AutoApprove {
+    pub allow_destructive_changes: bool,
+    /// Ignores the maintenance window for the destructive changes.
+    pub force: bool,
+}
+
+impl Approver for AutoApprove {
+    fn approve(&self, _spec: &Spec, changes: Option<&[spec_diff::Change]>) -> io::Result<bool> {
+        let changes = changes.unwrap_or_default();
+        if !spec_diff::is_destructive(changes) {
+            return Ok(true);
+        }
+        if !self.allow_destructive_changes {
+            return Err(Error::new(
+                ErrorKind::Other,
+                "destructive changes (replacement or node ID changes) are not allowed",
+            ));
+        }
+        warn!(
+            "applying {} change(s), highest impact: {}",
+            changes.len(),
+            spec_diff::max_impact(changes)
+        );
+        Ok(true)
+    }
+
+    fn force(&self) -> bool {
+        self.force
+    }
+}
+
+/// Creates the resources of the spec, or applies the spec changes to the
+/// existing cluster, and updates the spec file in place.
+/// Returns false if the approver stopped it before any change.
+pub fn run(spec_file_path: &str, approver: &dyn Approver) -> io::Result<bool> {
+    let mut spec = Spec::load(spec_file_path)?;
+    spec.validate()?;
+    join_validation::validate(&spec)?;
+    if let Some(v) = &spec.ci_preview {
+        if v.is_expired(Utc::now()) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("'ci_preview' expired at {} (run 'reap')", v.expires_at),
+            ));
+        }
+    }
+
+    let rt = Runtime::new()?;
+
+    let mut aws_resources = super::aws_resources(&spec)?;
+    // for "reap" to find the cluster without the spec file
+    let mut reap_stack_tags = auto_delete::stack_tags(&spec.id, &aws_resources.s3_bucket);
+    if let Some(v) = &spec.ci_preview {
+        reap_stack_tags.extend(v.stack_tags());
+    }
+    let nlb_enabled = spec.ci_preview.is_none();
+    let shared_config = rt.block_on(aws::load_config(Some(aws_resources.region.clone())))?;
+
+    // the remote state is authoritative for the generated fields
+    // (e.g., stale or lost local spec file)
+    let s3_manager = s3::Manager::new(&shared_config);
+    if !rt
+        .block_on(spec_state::pull(&s3_manager, &mut spec))?
+        .is_empty()
+    {
+        aws_resources = super::aws_resources(&spec)?;
+        fips::validate_recorded(aws_resources.fips)?;
+    }
+    aws_resources.fips = Some(fips::enabled());
+
+    let sts_manager = sts::Manager::new(&shared_config);
+    let current_identity = rt.block_on(sts_manager.get_identity())?;
+
+    // validate identity
+    match aws_resources.clone().identity {
+        Some(identity) => {
+            // AWS calls must be made from the same caller
+            if !identity.is_same_caller(&current_identity) {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!(
+                        "config identity {:?} != currently loaded identity {:?}",
+                        identity, current_identity
+                    ),
+                ));
+            }
+        }
+        None => {
+            aws_resources.identity = Some(current_identity);
+        }
+    }
+
+    // set defaults based on ID
+    if aws_resources.ec2_key_name.is_none() {
+        aws_resources.ec2_key_name = Some(format!("{}-ec2-key", spec.id));
+    }
+    if aws_resources.cloudformation_ec2_instance_role.is_none() {
+        aws_resources.cloudformation_ec2_instance_role =
+            Some(crate::StackName::Ec2InstanceRole(spec.id.clone()).encode());
+    }
+    if aws_resources.cloudformation_vpc.is_none() {
+        aws_resources.cloudformation_vpc = Some(crate::StackName::Vpc(spec.id.clone()).encode());
+    }
+    if spec.avalanchego_config.is_custom_network()
+        && aws_resources.cloudformation_asg_anchor_nodes.is_none()
+    {
+        aws_resources.cloudformation_asg_anchor_nodes =
+            Some(crate::StackName::AsgBeaconNodes(spec.id.clone()).encode());
+    }
+    if aws_resources.cloudformation_asg_non_anchor_nodes.is_none() {
+        aws_resources.cloudformation_asg_non_anchor_nodes =
+            Some(crate::StackName::AsgNonBeaconNodes(spec.id.clone()).encode());
+    }
+    if spec.icm_relayer.is_some() && aws_resources.cloudformation_asg_icm_relayer.is_none() {
+        aws_resources.cloudformation_asg_icm_relayer =
+            Some(crate::StackName::AsgIcmRelayer(spec.id.clone()).encode());
+    }
+    if aws_resources
+        .cloudwatch_avalanche_metrics_namespace
+        .is_none()
+    {
+        aws_resources.cloudwatch_avalanche_metrics_namespace =
+            Some(metric_schema::namespace(&spec.id));
+        aws_resources.cloudwatch_metrics_schema_version = Some(metric_schema::CURRENT_VERSION);
+    }
+    // migrating an existing cluster, keep the old namespace until the dashboards are updated
+    let legacy_namespace = metric_schema::legacy_namespace(&spec.id);
+    if aws_resources.cloudwatch_metrics_schema_version == Some(metric_schema::VERSION_2)
+        && aws_resources
+            .cloudwatch_avalanche_metrics_namespace
+            .as_deref()
+            == Some(legacy_namespace.as_str())
+    {
+        info!(
+            "migrating metrics namespace '{}' to the schema version {}",
+            legacy_namespace,
+            metric_schema::VERSION_2
+        );
+        aws_resources.cloudwatch_avalanche_metrics_namespace =
+            Some(metric_schema::namespace(&spec.id));
+        aws_resources.cloudwatch_metrics_legacy_namespace = Some(legacy_namespace);
+    }
+    spec.aws_resources = Some(aws_resources.clone());
+    spec.sync(spec_file_path)?;
+
+    // fail before any resource is created, rather than in the middle of the stacks
+    rt.block_on(preflight::run(&shared_config, &spec, nlb_enabled))
+        .into_result()?;
+
+    // the KMS key is created on the first "apply"
+    // so the cluster exists with the previously applied spec in S3
+    let changes = if aws_resources.kms_cmk_arn.is_some() {
+        Some(applied_changes(&rt, &s3_manager, &spec)?)
+    } else {
+        None
+    };
+    // the replacement and the node ID changes refresh the instances
+    if spec_diff::is_destructive(changes.as_deref().unwrap_or_default()) {
+        spec.check_maintenance_window("apply", approver.force())?;
+    }
+    if !approver.approve(&spec, changes.as_deref())? {
+        info!("'apply' not approved, not creating resources");
+        return Ok(false);
+    }
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    info!("creating resources (with spec path {})", spec_file_path);
+    let kms_manager = kms::Manager::new(&shared_config);
+    let ec2_manager = ec2::Manager::new(&shared_config);
+    let cloudformation_manager = cloudformation::Manager::new(&shared_config);
+    // the bucket, the key, the VPC, and the node groups go through the provider,
+    // the AWS-only resources (e.g., IAM roles, budgets) through the managers
+    let mut provider = provider::aws::Provider::new(&shared_config);
+    provider.stack_tags = reap_stack_tags.clone();
+
+    info!("STEP: create S3 buckets");
+    rt.block_on(provider.create_bucket(&aws_resources.s3_bucket))?;
+    // keeps the past spec states
+    rt.block_on(s3_manager.put_bucket_versioning(&aws_resources.s3_bucket))?;
+    if let Some(s3_lifecycle) = &aws_resources.s3_lifecycle {
+        rt.block_on(s3_manager.put_bucket_lifecycle(
+            &aws_resources.s3_bucket,
+            &spec.id,
+            s3_lifecycle,
+        ))?;
+    }
+    // the NLB checks the delivery permission when created
+    if aws_resources.nlb_access_logs == Some(true) {
+        rt.block_on(s3_manager.put_bucket_policy(
+            &aws_resources.s3_bucket,
+            &rpc_usage::nlb_access_logs_bucket_policy(
+                &aws_resources.s3_bucket,
+                &rpc_usage::nlb_access_logs_prefix(&spec.id),
+                &required(&aws_resources.identity, "aws_resources.identity")?.account_id,
+            ),
+        ))?;
+    }
+    if aws_resources.db_backup_s3_bucket.is_some() {
+        rt.block_on(s3_manager.create_bucket(&required(
+            &aws_resources.db_backup_s3_bucket,
+            "aws_resources.db_backup_s3_bucket",
+        )?))?;
+    }
+    // only the snapshot prefix is public, the rest of the bucket stays private
+    if let Some(public_snapshot) = &spec.public_snapshot {
+        rt.block_on(s3_manager.create_bucket(&public_snapshot.s3_bucket))?;
+        rt.block_on(s3_manager.allow_public_policy(&public_snapshot.s3_bucket))?;
+        let identity = required(&aws_resources.identity, "aws_resources.identity")?;
+        rt.block_on(s3_manager.put_bucket_policy(
+            &public_snapshot.s3_bucket,
+            &public_snapshot.bucket_policy(identity.partition()),
+        ))?;
+        rt.block_on(s3_manager.put_bucket_request_payment(
+            &public_snapshot.s3_bucket,
+            public_snapshot.requester_pays(),
+        ))?;
+    }
+
+    // replica bucket lives in a different region, so it needs its own client
+    let replica_s3_manager = {
+        if aws_resources.replica_s3_region.is_some() {
+            let replica_shared_config =
+                rt.block_on(aws::load_config(aws_resources.replica_s3_region.clone()))?;
+            Some(s3::Manager::new(&replica_shared_config))
+        } else {
+            None
+        }
+    };
+    if let Some(replica_s3_manager) = &replica_s3_manager {
+        rt.block_on(replica_s3_manager.create_bucket(&required(
+            &aws_resources.replica_s3_bucket,
+            "aws_resources.replica_s3_bucket",
+        )?))?;
+    }
+
+    thread::sleep(Duration::from_secs(2));
+    info!("STEP: upload artifacts to S3 bucket");
+
+    // don't compress since we need to download this in user data
+    // while instance bootstrapping
+    rt.block_on(s3_manager.put_object(
+        Arc::new(spec.install_artifacts.avalanched_bin.clone()),
+        Arc::new(aws_resources.s3_bucket.clone()),
+        Arc::new(crate::StorageNamespace::AvalanchedBin(spec.id.clone()).encode()),
+    ))?;
+
+    // verify before upload, and share the provenance via metadata
+    // so that "avalanched" verifies again after download
+    let avalanche_bin_provenance = spec
+        .install_artifacts
+        .verify_local(&spec.install_artifacts.avalanchego_bin)?;
+
+    // compress as these will be decompressed by "avalanched"
+    let tmp_avalanche_bin_compressed_path =
+        random::tmp_path(15, Some(compress::Encoder::Zstd(3).ext()))?;
+    compress::pack_file(
+        &spec.install_artifacts.avalanchego_bin,
+        &tmp_avalanche_bin_compressed_path,
+        compress::Encoder::Zstd(3),
+    )?;
+    rt.block_on(s3_manager.put_object_with_metadata(
+        Arc::new(tmp_avalanche_bin_compressed_path.clone()),
+        Arc::new(aws_resources.s3_bucket.clone()),
+        Arc::new(crate::StorageNamespace::AvalancheBinCompressed(spec.id.clone()).encode()),
+        Some(avalanche_bin_provenance.to_metadata()),
+    ))?;
+    fs::remove_file(tmp_avalanche_bin_compressed_path)?;
+    if let Some(plugins_dir) = &spec.install_artifacts.plugins_dir {
+        for entry in fs::read_dir(plugins_dir.as_str())? {
+            let entry = entry?;
+            let entry_path = entry.path();
+
+            let file_path = utf8(entry_path.as_os_str())?;
+            let file_name = entry.file_name();
+            let file_name = utf8(&file_name)?;
+            if provenance::is_provenance_file(file_name) {
+                continue;
+            }
+            let plugin_provenance = spec.install_artifacts.verify_local(file_path)?;
+
+            let tmp_plugin_compressed_path =
+                random::tmp_path(15, Some(compress::Encoder::Zstd(3).ext()))?;
+            compress::pack_file(
+                file_path,
+                &tmp_plugin_compressed_path,
+                compress::Encoder::Zstd(3),
+            )?;
+
+            info!(
+                "uploading {} (compressed from {}) from plugins directory {}",
+                tmp_plugin_compressed_path, file_path, plugins_dir,
+            );
+            rt.block_on(s3_manager.put_object_with_metadata(
+                Arc::new(tmp_plugin_compressed_path.clone()),
+                Arc::new(aws_resources.s3_bucket.clone()),
+                Arc::new(format!(
+                    "{}/{}{}",
+                    &crate::StorageNamespace::PluginsDir(spec.id.clone()).encode(),
+                    file_name,
+                    compress::Encoder::Zstd(3).ext()
+                )),
+                Some(plugin_provenance.to_metadata()),
+            ))?;
+            fs::remove_file(tmp_plugin_compressed_path)?;
+        }
+    }
+    // only downloaded by the relayer instances
+    if let Some(icm_relayer_bin) = &spec.install_artifacts.icm_relayer_bin {
+        let icm_relayer_provenance = spec.install_artifacts.verify_local(icm_relayer_bin)?;
+        let tmp_icm_relayer_compressed_path =
+            random::tmp_path(15, Some(compress::Encoder::Zstd(3).ext()))?;
+        compress::pack_file(
+            icm_relayer_bin,
+            &tmp_icm_relayer_compressed_path,
+            compress::Encoder::Zstd(3),
+        )?;
+        rt.block_on(s3_manager.put_object_with_metadata(
+            Arc::new(tmp_icm_relayer_compressed_path.clone()),
+            Arc::new(aws_resources.s3_bucket.clone()),
+            Arc::new(crate::StorageNamespace::IcmRelayerBinCompressed(spec.id.clone()).encode()),
+            Some(icm_relayer_provenance.to_metadata()),
+        ))?;
+        fs::remove_file(tmp_icm_relayer_compressed_path)?;
+    }
+    if let Some(chain_upgrades) = &spec.chain_upgrades {
+        for u in chain_upgrades.iter() {
+            info!(
+                "uploading chain upgrade file {} for {}",
+                u.file_path, u.chain_id
+            );
+            rt.block_on(
+                s3_manager.put_object(
+                    Arc::new(u.file_path.clone()),
+                    Arc::new(aws_resources.s3_bucket.clone()),
+                    Arc::new(
+                        crate::StorageNamespace::ChainUpgradeFile(
+                            spec.id.clone(),
+                            u.chain_id.clone(),
+                        )
+                        .encode(),
+                    ),
+                ),
+            )?;
+        }
+    }
+    rt.block_on(put_state(&provider, &spec))?;
+
+    if let Some(replica_s3_manager) = &replica_s3_manager {
+        thread::sleep(Duration::from_secs(1));
+        info!("STEP: replicate artifacts to S3 bucket in replica region");
+        let replica_s3_bucket = required(
+            &aws_resources.replica_s3_bucket,
+            "aws_resources.replica_s3_bucket",
+        )?;
+
+        let mut s3_keys = vec![crate::StorageNamespace::ConfigFile(spec.id.clone()).encode()];
+        let install_objects = rt.block_on(s3_manager.list_objects(
+            Arc::new(aws_resources.s3_bucket.clone()),
+            Some(Arc::new(s3::append_slash(
+                &crate::StorageNamespace::InstallDir(spec.id.clone()).encode(),
+            ))),
+        ))?;
+        for obj in install_objects.iter() {
+            s3_keys.push(obj.key().unwrap_or_default().to_string());
+        }
+        // the source objects are read in the primary region, and only
+        // the copy runs in the replica region
+        for s3_key in s3_keys.iter() {
+            let size =
+                rt.block_on(s3_manager.head_object_size(&aws_resources.s3_bucket, s3_key))?;
+            rt.block_on(replica_s3_manager.copy_object_with_size(
+                Arc::new(aws_resources.s3_bucket.clone()),
+                Arc::new(s3_key.clone()),
+                size,
+                Arc::new(replica_s3_bucket.clone()),
+                Arc::new(s3_key.clone()),
+            ))?;
+        }
+
+        // keep the same key, so "avalanched" can fail over with the same spec
+        if aws_resources.db_backup_s3_bucket.is_some() {
+            let db_backup_s3_key = required(
+                &aws_resources.db_backup_s3_key,
+                "aws_resources.db_backup_s3_key",
+            )?;
+            let db_backup_s3_bucket = required(
+                &aws_resources.db_backup_s3_bucket,
+                "aws_resources.db_backup_s3_bucket",
+            )?;
+            let size =
+                rt.block_on(s3_manager.head_object_size(&db_backup_s3_bucket, &db_backup_s3_key))?;
+            rt.block_on(replica_s3_manager.copy_object_with_size(
+                Arc::new(db_backup_s3_bucket),
+                Arc::new(db_backup_s3_key.clone()),
+                size,
+                Arc::new(replica_s3_bucket),
+                Arc::new(db_backup_s3_key),
+            ))?;
+        }
+    }
+
+    if aws_resources.kms_cmk_id.is_none() && aws_resources.kms_cmk_arn.is_none() {
+        thread::sleep(Duration::from_secs(2));
+        info!("STEP: create KMS key");
+        let key = rt.block_on(provider.create_key(&format!("{}-cmk", spec.id)))?;
+
+        aws_resources.kms_cmk_id = Some(key.id);
+        aws_resources.kms_cmk_arn = Some(key.arn);
+        spec.aws_resources = Some(aws_resources.clone());
+        spec.sync(spec_file_path)?;
+
+        rt.block_on(put_state(&provider, &spec))?;
+    }
+    // seals the node keys with the shared data keys, so the nodes unseal with fewer KMS calls
+    let envelope = envelope::Envelope::new(Some(kms_manager), aws_resources.kms_cmk_id.clone())
+        .with_data_key_cache(envelope::DEFAULT_DATA_KEY_CACHE_CAPACITY);
+
+    // generated once, the nodes download the password before starting
+    let mut api_auth_password: Option<String> = None;
+    if spec.avalanchego_config.api_auth_required == Some(true) {
+        api_auth_password = rt.block_on(api_auth::get_password(
+            &s3_manager,
+            &envelope,
+            &aws_resources.s3_bucket,
+            &spec.id,
+        ))?;
+        if api_auth_password.is_none() {
+            info!("STEP: generate API auth password");
+            let password = api_auth::generate_password();
+            rt.block_on(api_auth::put_password(
+                &s3_manager,
+                &envelope,
+                &aws_resources.s3_bucket,
+                &spec.id,
+                &password,
+            ))?;
+            api_auth_password = Some(password);
+        }
+        if aws_resources
+            .nlb_target_group
+            .as_ref()
+            .and_then(|tg| tg.stickiness)
+            != Some(true)
+        {
+            warn!("the API auth tokens are per node, so the calls through the NLB may fail without 'nlb_target_group.stickiness'");
+        }
+    }
+
+    if let Some(public_key) = &aws_resources.ec2_key_import_public_key {
+        info!("STEP: import EC2 key pair");
+        rt.block_on(ec2_manager.import_key_pair(
+            required(&aws_resources.ec2_key_name, "aws_resources.ec2_key_name")?.as_str(),
+            public_key,
+        ))?;
+    } else if aws_resources.ec2_key_path.is_none() {
+        info!("STEP: create EC2 key pair");
+        let ec2_key_path = get_ec2_key_path(spec_file_path)?;
+        rt.block_on(ec2_manager.create_key_pair(
+            required(&aws_resources.ec2_key_name, "aws_resources.ec2_key_name")?.as_str(),
+            ec2_key_path.as_str(),
+        ))?;
+
+        let tmp_compressed_path = random::tmp_path(15, Some(compress::Encoder::Zstd(3).ext()))?;
+        compress::pack_file(
+            ec2_key_path.as_str(),
+            &tmp_compressed_path,
+            compress::Encoder::Zstd(3),
+        )?;
+
+        let tmp_encrypted_path = random::tmp_path(15, Some(".zstd.encrypted"))?;
+        rt.block_on(envelope.seal_aes_256_file(
+            Arc::new(tmp_compressed_path),
+            Arc::new(tmp_encrypted_path.clone()),
+        ))?;
+        rt.block_on(s3_manager.put_object(
+            Arc::new(tmp_encrypted_path),
+            Arc::new(aws_resources.s3_bucket.clone()),
+            Arc::new(
+                crate::StorageNamespace::Ec2AccessKeyCompressedEncrypted(spec.id.clone()).encode(),
+            ),
+        ))?;
+
+        aws_resources.ec2_key_path = Some(ec2_key_path);
+        spec.aws_resources = Some(aws_resources.clone());
+        spec.sync(spec_file_path)?;
+
+        rt.block_on(put_state(&provider, &spec))?;
+    }
+
+    if aws_resources
+        .cloudformation_ec2_instance_profile_arn
+        .is_none()
+    {
+        info!("STEP: create EC2 instance role");
+
+        let ec2_instance_role_tmpl = template("ec2_instance_role.yaml")?;
+        let ec2_instance_role_stack_name = required(
+            &aws_resources.cloudformation_ec2_instance_role,
+            "aws_resources.cloudformation_ec2_instance_role",
+        )?;
+
+        let mut role_params = Vec::from([
+            build_param("Id", &spec.id),
+            build_param(
+                "KmsCmkArn",
+                &required(&aws_resources.kms_cmk_arn, "aws_resources.kms_cmk_arn")?,
+            ),
+            build_param("S3BucketName", &aws_resources.s3_bucket),
+        ]);
+        if aws_resources.db_backup_s3_bucket.is_some() {
+            let param = build_param(
+                "S3BucketDbBackupName",
+                &required(
+                    &aws_resources.db_backup_s3_bucket,
+                    "aws_resources.db_backup_s3_bucket",
+                )?,
+            );
+            role_params.push(param);
+        }
+        if aws_resources.replica_s3_bucket.is_some() {
+            let param = build_param(
+                "S3BucketReplicaName",
+                &required(
+                    &aws_resources.replica_s3_bucket,
+                    "aws_resources.replica_s3_bucket",
+                )?,
+            );
+            role_params.push(param);
+        }
+        if let Some(v) = &aws_resources.nlb_target_group {
+            if v.is_register_on_readiness() {
+                role_params.push(build_param("NlbRegisterOnReadiness", "true"));
+            }
+        }
+        if aws_resources.elastic_ip_pool.is_some() {
+            role_params.push(build_param("ElasticIpPool", "true"));
+        }
+        if spec.static_node_keys == Some(true) {
+            role_params.push(build_param("StaticNodeKeys", "true"));
+        }
+        if let Some(source) = &spec.restore_from_snapshot {
+            role_params.push(build_param("RestoreS3BucketName", &source.s3_bucket));
+            role_params.push(build_param("RestoreKmsKeyArn", &source.kms_key_arn));
+        }
+        if let Some(public_snapshot) = &spec.public_snapshot {
+            role_params.push(build_param(
+                "PublicSnapshotS3BucketName",
+                &public_snapshot.s3_bucket,
+            ));
+        }
+        rt.block_on(
+            cloudformation_manager.create_stack(
+                ec2_instance_role_stack_name.as_str(),
+                Some(vec![Capability::CapabilityNamedIam]),
+                OnFailure::Delete,
+                &ec2_instance_role_tmpl,
+                Some(
+                    [
+                        vec![Tag::builder().key("KIND").value("avalanche-ops").build()],
+                        aws::operation::current_stack_tags(),
+                        reap_stack_tags.clone(),
+                    ]
+                    .concat(),
+                ),
+                Some(role_params),
+            ),
+        )?;
+
+        thread::sleep(Duration::from_secs(10));
+        let stack = rt.block_on(cloudformation_manager.poll_stack(
+            ec2_instance_role_stack_name.as_str(),
+            StackStatus::CreateComplete,
+            Duration::from_secs(500),
+            Duration::from_secs(30),
+        ))?;
+
+        for o in stack.outputs.unwrap_or_default() {
+            let k = o.output_key.unwrap_or_default();
+            let v = o.output_value.unwrap_or_default();
+            info!("stack output key=[{}], value=[{}]", k, v,);
+            if k.eq("InstanceProfileArn") {
+                aws_resources.cloudformation_ec2_instance_profile_arn = Some(v)
+            }
+        }
+        spec.aws_resources = Some(aws_resources.clone());
+        spec.sync(spec_file_path)?;
+
+        rt.block_on(put_state(&provider, &spec))?;
+    }
+
+    if spec.static_node_keys == Some(true) {
+        // the nodes only lease the slots
+        rt.block_on(crate::node_keys::slot_manager(&shared_config).create_table_if_not_exists())
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    }
+    if spec.public_snapshot.is_some() {
+        rt.block_on(
+            crate::public_snapshot::lease_manager(&shared_config).create_table_if_not_exists(),
+        )
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    }
+
+    if aws_resources.cloudformation_vpc_id.is_none()
+        && aws_resources.cloudformation_vpc_security_group_id.is_none()
+        && aws_resources.cloudformation_vpc_public_subnet_ids.is_none()
+    {
+        info!("STEP: create VPC");
+        rt.block_on(create_network(&provider, &spec, &mut aws_resources))?;
+        spec.aws_resources = Some(aws_resources.clone());
+        spec.sync(spec_file_path)?;
+
+        rt.block_on(put_state(&provider, &spec))?;
+    }
+
+    // the provider sets the cluster ID, the node kind, the desired capacity,
+    // and the VPC parameters
+    let mut asg_parameters = Vec::from([
+        build_param(
+            "NetworkId",
+            format!("{}", &spec.avalanchego_config.network_id).as_str(),
+        ),
+        build_param(
+            "KmsCmkArn",
+            &required(&aws_resources.kms_cmk_arn, "aws_resources.kms_cmk_arn")?,
+        ),
+        build_param("S3BucketName", &aws_resources.s3_bucket),
+        build_param(
+            "Ec2KeyPairName",
+            &required(&aws_resources.ec2_key_name, "aws_resources.ec2_key_name")?,
+        ),
+        build_param(
+            "InstanceProfileArn",
+            &required(
+                &aws_resources.cloudformation_ec2_instance_profile_arn,
+                "aws_resources.cloudformation_ec2_instance_profile_arn",
+            )?,
+        ),
+        build_param(
+            "NlbHttpPort",
+            format!("{}", spec.avalanchego_config.http_port).as_str(),
+        ),
+    ]);
+
+    // only used by the stack that creates the NLB
+    if let Some(nlb_target_group) = &aws_resources.nlb_target_group {
+        for (k, v) in nlb_target_group.parameters() {
+            asg_parameters.push(build_param(k, &v));
+        }
+    }
+    asg_parameters.push(build_param(
+        "CloudWatchMetricsNamespace",
+        &required(
+            &aws_resources.cloudwatch_avalanche_metrics_namespace,
+            "aws_resources.cloudwatch_avalanche_metrics_namespace",
+        )?,
+    ));
+    asg_parameters.push(build_param(
+        "CloudWatchMetricsSchemaVersion",
+        &aws_resources
+            .cloudwatch_metrics_schema_version
+            .unwrap_or(metric_schema::VERSION_1)
+            .to_string(),
+    ));
+    asg_parameters.push(build_param(
+        "ClockOffsetAlarmThresholdMs",
+        aws_resources
+            .clock_offset_alarm_threshold_ms
+            .unwrap_or(time_sync::DEFAULT_CLOCK_OFFSET_ALARM_THRESHOLD_MS)
+            .to_string()
+            .as_str(),
+    ));
+    if let Some(v) = aws_resources.nlb_tcp_idle_timeout_seconds {
+        asg_parameters.push(build_param(
+            "NlbTcpIdleTimeoutSeconds",
+            v.to_string().as_str(),
+        ));
+    }
+    if aws_resources.nlb_access_logs == Some(true) {
+        asg_parameters.push(build_param("NlbAccessLogs", "true"));
+    }
+    if let Some(v) = aws_resources.nlb_cross_zone_load_balancing {
+        asg_parameters.push(build_param(
+            "NlbCrossZoneLoadBalancing",
+            v.to_string().as_str(),
+        ));
+    }
+    if !nlb_enabled {
+        asg_parameters.push(build_param("NlbEnabled", "false"));
+    }
+
+    if let Some(v) = &spec.machine.os_hardening {
+        if v.is_enabled() {
+            asg_parameters.push(build_param(
+                "OsHardeningScript",
+                &v.script(
+                    spec.avalanchego_config.http_port,
+                    spec.avalanchego_config.staking_port,
+                ),
+            ));
+        }
+    }
+    if let Some(instance_types) = &spec.machine.instance_types {
+        asg_parameters.push(build_param("InstanceTypes", &instance_types.join(",")));
+        asg_parameters.push(build_param(
+            "InstanceTypesCount",
+            format!("{}", instance_types.len()).as_str(),
+        ));
+    }
+    if let Some(v) = &spec.machine.image_id {
+        asg_parameters.push(build_param("ImageId", v));
+    }
+    if aws_resources.fips == Some(true) {
+        asg_parameters.push(build_param("Fips", "true"));
+    }
+
+    // TODO: support bootstrap from existing DB for anchor nodes
+    let mut current_nodes: Vec<node::Node> = Vec::new();
+    if spec.machine.anchor_nodes.unwrap_or(0) > 0
+        && aws_resources
+            .cloudformation_asg_anchor_nodes_logical_id
+            .is_none()
+    {
+        info!("STEP: create ASG for anchor nodes");
+
+        let cloudformation_asg_anchor_nodes_stack_name = required(
+            &aws_resources.cloudformation_asg_anchor_nodes,
+            "aws_resources.cloudformation_asg_anchor_nodes",
+        )?;
+
+        let desired_capacity = spec.machine.anchor_nodes.unwrap_or(0);
+
+        // must deep-copy as shared with other node kind
+        let mut asg_anchor_params = asg_parameters.clone();
+        // index-enabled nodes need the extra disk
+        if let Some(v) = spec.volume_size_in_gb(&node::Kind::Anchor) {
+            asg_anchor_params.push(build_param("VolumeSize", format!("{}", v).as_str()));
+        }
+        if let Some(v) = &spec.api_exposure {
+            if !v.node_apis(&node::Kind::Anchor).is_nlb_target() {
+                asg_anchor_params.push(build_param("NlbRegisterTargets", "false"));
+            }
+        }
+        if aws_resources.nlb_acm_certificate_arn.is_some() {
+            asg_anchor_params.push(build_param(
+                "NlbAcmCertificateArn",
+                &required(
+                    &aws_resources.nlb_acm_certificate_arn,
+                    "aws_resources.nlb_acm_certificate_arn",
+                )?,
+            ));
+        };
+
+        // TODO: support other platforms
+        let group = rt.block_on(create_node_group(
+            &provider,
+            &spec,
+            &aws_resources,
+            NodeGroupSpec {
+                name: cloudformation_asg_anchor_nodes_stack_name,
+                cluster_id: spec.id.clone(),
+                kind: node::Kind::Anchor,
+                desired_capacity,
+                parameters: parameters_map(&asg_anchor_params),
+            },
+        ))?;
+        aws_resources.cloudformation_asg_anchor_nodes_logical_id = Some(group.id.clone());
+        aws_resources.cloudformation_asg_nlb_arn = group.outputs.get("NlbArn").cloned();
+        aws_resources.cloudformation_asg_nlb_target_group_arn =
+            group.outputs.get("NlbTargetGroupArn").cloned();
+        aws_resources.cloudformation_asg_nlb_dns_name = group.outputs.get("NlbDnsName").cloned();
+        if aws_resources
+            .cloudformation_asg_anchor_nodes_logical_id
+            .is_none()
+        {
+            return Err(Error::new(
+                ErrorKind::Other,
+                "aws_resources.cloudformation_asg_anchor_nodes_logical_id not found",
+            ));
+        }
+        if nlb_enabled {
+            if aws_resources.cloudformation_asg_nlb_arn.is_none() {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    "aws_resources.cloudformation_asg_nlb_arn not found",
+                ));
+            }
+            if aws_resources
+                .cloudformation_asg_nlb_target_group_arn
+                .is_none()
+            {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    "aws_resources.cloudformation_asg_nlb_target_group_arn not found",
+                ));
+            }
+            if aws_resources.cloudformation_asg_nlb_dns_name.is_none() {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    "aws_resources.cloudformation_asg_nlb_dns_name not found",
+                ));
+            }
+        }
+
+        let mut droplets = rt.block_on(provider.list_instances(&group))?;
+        let target_nodes = spec.machine.anchor_nodes.unwrap_or(0);
+        if (droplets.len() as u32) < target_nodes {
+            // TODO: better retries
+            thread::sleep(Duration::from_secs(30));
+            droplets = rt.block_on(provider.list_instances(&group))?;
+        }
+        let ec2_key_path = get_ec2_key_path_for_ssh(&aws_resources)?;
+        for d in droplets {
+            // ssh -o "StrictHostKeyChecking no" -i [ec2_key_path] [user name]@[public IPv4/DNS name]
+            // aws ssm start-session --region [region] --target [instance ID]
+            info!(
+                "instance '{}' ({}, {})\nssh -o \"StrictHostKeyChecking no\" -i {} ubuntu@{}\naws ssm start-session --region {} --target {}",
+                d.id,
+                d.state,
+                d.availability_zone,
+                ec2_key_path,
+                d.public_ipv4,
+                aws_resources.region,
+                d.id,
+            );
+        }
+
+        // wait for anchor nodes to generate certs and node ID and post to remote storage
+        // TODO: set timeouts for the non-preview networks
+        let start = Instant::now();
+        let mut objects: Vec<Object>;
+        loop {
+            thread::sleep(Duration::from_secs(30));
+            if let Some(v) = &spec.ci_preview {
+                if start.elapsed() > v.ready_timeout() {
+                    return Err(Error::new(
+                        ErrorKind::TimedOut,
+                        format!("anchor nodes not ready in {:?}", v.ready_timeout()),
+                    ));
+                }
+            }
+            objects = rt.block_on(s3_manager.list_objects(
+                Arc::new(aws_resources.s3_bucket.clone()),
+                Some(Arc::new(s3::append_slash(
+                    &crate::StorageNamespace::DiscoverReadyAnchorNodesDir(spec.id.clone()).encode(),
+                ))),
+            ))?;
+            info!(
+                "{} anchor nodes are bootstrapped and ready (expecting {} nodes)",
+                objects.len(),
+                target_nodes
+            );
+            if objects.len() as u32 >= target_nodes {
+                break;
+            }
+        }
+
+        for obj in objects.iter() {
+            let s3_key = obj.key().unwrap_or_default();
+            let anchor_node = crate::StorageNamespace::parse_node_from_path(s3_key)?;
+            current_nodes.push(anchor_node.clone());
+        }
+
+        spec.aws_resources = Some(aws_resources.clone());
+        spec.sync(spec_file_path)?;
+
+        rt.block_on(put_state(&provider, &spec))?;
+
+        info!("waiting for anchor nodes bootstrap and ready (to be safe)");
+        thread::sleep(Duration::from_secs(15));
+    }
+
+    if let Some(pool) = &aws_resources.elastic_ip_pool {
+        let mut addresses = aws_resources
+            .elastic_ip_pool_addresses
+            .clone()
+            .unwrap_or_default();
+        let size = pool.size(spec.machine.non_anchor_nodes) as usize;
+        if addresses.len() < size {
+            info!("STEP: allocate Elastic IP pool for non-anchor nodes");
+            let eip_manager = aws::eip::Manager::new(&shared_config);
+            rt.block_on(eip_manager.create_table_if_not_exists())
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+            while addresses.len() < size {
+                let (allocation_id, public_ip) = rt
+                    .block_on(ec2_manager.allocate_address(vec![
+                        aws_sdk_ec2::model::Tag::builder()
+                            .key("Name")
+                            .value(format!("{}-non-anchor-{}", spec.id, addresses.len()))
+                            .build(),
+                        aws_sdk_ec2::model::Tag::builder()
+                            .key("ID")
+                            .value(&spec.id)
+                            .build(),
+                    ]))
+                    .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+                addresses.push(aws::eip::Address {
+                    allocation_id,
+                    public_ip,
+                });
+
+                // sync each, so the retried "apply" does not leak the addresses
+                aws_resources.elastic_ip_pool_addresses = Some(addresses.clone());
+                spec.aws_resources = Some(aws_resources.clone());
+                spec.sync(spec_file_path)?;
+            }
+            rt.block_on(put_state(&provider, &spec))?;
+        }
+        for a in addresses.iter() {
+            info!("elastic IP: {} ({})", a.public_ip, a.allocation_id);
+        }
+    }
+
+    if aws_resources
+        .cloudformation_asg_non_anchor_nodes_logical_id
+        .is_none()
+    {
+        info!("STEP: create ASG for non-anchor nodes");
+
+        let cloudformation_asg_non_anchor_nodes_stack_name = required(
+            &aws_resources.cloudformation_asg_non_anchor_nodes,
+            "aws_resources.cloudformation_asg_non_anchor_nodes",
+        )?;
+
+        let desired_capacity = spec.machine.non_anchor_nodes;
+
+        // we did not create anchor nodes for mainnet/* nodes
+        // so no nlb creation before
+        // we create here for non-anchor nodes
+        let need_to_create_nlb = nlb_enabled
+            && aws_resources
+                .cloudformation_asg_nlb_target_group_arn
+                .is_none();
+
+        // must deep-copy as shared with other node kind
+        let mut asg_non_anchor_params = asg_parameters.clone();
+        if let Some(v) = spec.volume_size_in_gb(&node::Kind::NonAnchor) {
+            asg_non_anchor_params.push(build_param("VolumeSize", format!("{}", v).as_str()));
+        }
+        if let Some(v) = &spec.api_exposure {
+            if !v.node_apis(&node::Kind::NonAnchor).is_nlb_target() {
+                asg_non_anchor_params.push(build_param("NlbRegisterTargets", "false"));
+            }
+        }
+        if need_to_create_nlb {
+            if aws_resources.nlb_acm_certificate_arn.is_some() {
+                asg_non_anchor_params.push(build_param(
+                    "NlbAcmCertificateArn",
+                    &required(
+                        &aws_resources.nlb_acm_certificate_arn,
+                        "aws_resources.nlb_acm_certificate_arn",
+                    )?,
+                ));
+            };
+        } else if nlb_enabled {
+            // already created for anchor nodes
+            asg_non_anchor_params.push(build_param(
+                "NlbTargetGroupArn",
+                &required(
+                    &aws_resources.cloudformation_asg_nlb_target_group_arn,
+                    "aws_resources.cloudformation_asg_nlb_target_group_arn",
+                )?,
+            ));
+        }
+
+        let group = rt.block_on(create_node_group(
+            &provider,
+            &spec,
+            &aws_resources,
+            NodeGroupSpec {
+                name: cloudformation_asg_non_anchor_nodes_stack_name,
+                cluster_id: spec.id.clone(),
+                kind: node::Kind::NonAnchor,
+                desired_capacity,
+                parameters: parameters_map(&asg_non_anchor_params),
+            },
+        ))?;
+        aws_resources.cloudformation_asg_non_anchor_nodes_logical_id = Some(group.id.clone());
+        if need_to_create_nlb {
+            aws_resources.cloudformation_asg_nlb_arn = group.outputs.get("NlbArn").cloned();
+            aws_resources.cloudformation_asg_nlb_target_group_arn =
+                group.outputs.get("NlbTargetGroupArn").cloned();
+            aws_resources.cloudformation_asg_nlb_dns_name =
+                group.outputs.get("NlbDnsName").cloned();
+        }
+        if aws_resources
+            .cloudformation_asg_non_anchor_nodes_logical_id
+            .is_none()
+        {
+            return Err(Error::new(
+                ErrorKind::Other,
+                "aws_resources.cloudformation_asg_non_anchor_nodes_logical_id not found",
+            ));
+        }
+        if need_to_create_nlb {
+            if aws_resources.cloudformation_asg_nlb_arn.is_none() {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    "aws_resources.cloudformation_asg_nlb_arn not found",
+                ));
+            }
+            if aws_resources
+                .cloudformation_asg_nlb_target_group_arn
+                .is_none()
+            {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    "aws_resources.cloudformation_asg_nlb_target_group_arn not found",
+                ));
+            }
+            if aws_resources.cloudformation_asg_nlb_dns_name.is_none() {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    "aws_resources.cloudformation_asg_nlb_dns_name not found",
+                ));
+            }
+        }
+        spec.aws_resources = Some(aws_resources.clone());
+        spec.sync(spec_file_path)?;
+
+        let mut droplets = rt.block_on(provider.list_instances(&group))?;
+        let target_nodes = spec.machine.non_anchor_nodes;
+        if (droplets.len() as u32) < target_nodes {
+            // TODO: better retries
+            thread::sleep(Duration::from_secs(30));
+            droplets = rt.block_on(provider.list_instances(&group))?;
+        }
+        let ec2_key_path = get_ec2_key_path_for_ssh(&aws_resources)?;
+        for d in droplets {
+            // ssh -o "StrictHostKeyChecking no" -i [ec2_key_path] [user name]@[public IPv4/DNS name]
+            // aws ssm start-session --region [region] --target [instance ID]
+            info!(
+                "instance '{}' ({}, {})\nssh -o \"StrictHostKeyChecking no\" -i {} ubuntu@{}\naws ssm start-session --region {} --target {}",
+                d.id,
+                d.state,
+                d.availability_zone,
+                ec2_key_path,
+                d.public_ipv4,
+                aws_resources.region,
+                d.id,
+            );
+        }
+
+        let require_db_download = aws_resources.db_backup_s3_bucket.is_some();
+        let s3_dir = {
+            if require_db_download {
+                crate::StorageNamespace::DiscoverProvisioningNonAnchorNodesDir(spec.id.clone())
+            } else {
+                crate::StorageNamespace::DiscoverReadyNonAnchorNodesDir(spec.id.clone())
+            }
+        };
+        // wait for non-anchor nodes to generate certs and node ID and post to remote storage
+        // TODO: set timeouts for the non-preview networks
+        let start = Instant::now();
+        let mut objects: Vec<Object>;
+        loop {
+            thread::sleep(Duration::from_secs(30));
+            if let Some(v) = &spec.ci_preview {
+                if start.elapsed() > v.ready_timeout() {
+                    return Err(Error::new(
+                        ErrorKind::TimedOut,
+                        format!("non-anchor nodes not ready in {:?}", v.ready_timeout()),
+                    ));
+                }
+            }
+            objects = rt.block_on(s3_manager.list_objects(
+                Arc::new(aws_resources.s3_bucket.clone()),
+                Some(Arc::new(s3::append_slash(&s3_dir.encode()))),
+            ))?;
+            info!(
+                "{} non-anchor nodes are ready (expecting {} nodes)",
+                objects.len(),
+                target_nodes
+            );
+            if objects.len() as u32 >= target_nodes {
+                break;
+            }
+        }
+        for obj in objects.iter() {
+            let s3_key = obj.key().unwrap_or_default();
+            let non_anchor_node = crate::StorageNamespace::parse_node_from_path(s3_key)?;
+            current_nodes.push(non_anchor_node.clone());
+        }
+        spec.current_nodes = Some(current_nodes.clone());
+        spec.aws_resources = Some(aws_resources.clone());
+        spec.sync(spec_file_path)?;
+
+        rt.block_on(put_state(&provider, &spec))?;
+
+        // TODO: if downloading mainnet db, it will take a while
+        // TODO: better handle this
+        if require_db_download {
+            spec.current_nodes = Some(current_nodes.clone());
+            spec.aws_resources = Some(aws_resources);
+            spec.sync(spec_file_path)?;
+            warn!(
+                "non-anchor nodes are downloading db backups, can take awhile, check back later..."
+            );
+            return Ok(true);
+        }
+
+        info!("waiting for non-anchor nodes bootstrap and ready (to be safe)");
+        thread::sleep(Duration::from_secs(20));
+    }
+    spec.current_nodes = Some(current_nodes.clone());
+    spec.sync(spec_file_path)?;
+
+    if aws_resources.budget.is_some() || aws_resources.cloudformation_budget.is_some() {
+        info!("STEP: sync budget");
+        let budget_tmpl = template("budget.yaml")?;
+        sync_budget(
+            &rt,
+            &cloudformation_manager,
+            &spec.id,
+            &budget_tmpl,
+            [
+                vec![Tag::builder().key("KIND").value("avalanche-ops").build()],
+                aws::operation::current_stack_tags(),
+                reap_stack_tags.clone(),
+            ]
+            .concat(),
+            &mut aws_resources,
+        )?;
+        spec.aws_resources = Some(aws_resources.clone());
+        spec.sync(spec_file_path)?;
+    }
+
+    if aws_resources.auditor.is_some() || aws_resources.cloudformation_auditor_role.is_some() {
+        info!("STEP: sync auditor role");
+        let auditor_role_tmpl = template("auditor_role.yaml")?;
+        sync_auditor_role(
+            &rt,
+            &cloudformation_manager,
+            &spec.id,
+            &auditor_role_tmpl,
+            [
+                vec![Tag::builder().key("KIND").value("avalanche-ops").build()],
+                aws::operation::current_stack_tags(),
+                reap_stack_tags.clone(),
+            ]
+            .concat(),
+            &mut aws_resources,
+        )?;
+        spec.aws_resources = Some(aws_resources.clone());
+        spec.sync(spec_file_path)?;
+    }
+
+    if aws_resources.nlb_endpoint_service.is_some()
+        || aws_resources.nlb_endpoint_service_id.is_some()
+    {
+        info!("STEP: sync NLB endpoint service (PrivateLink)");
+        match aws_resources.nlb_endpoint_service.clone() {
+            Some(endpoint_service) => {
+                if aws_resources.nlb_endpoint_service_id.is_none() {
+                    let (service_id, service_name) = rt
+                        .block_on(ec2_manager.create_endpoint_service(
+                            &format!("{}-nlb-endpoint-service", spec.id),
+                            &required(
+                                &aws_resources.cloudformation_asg_nlb_arn,
+                                "aws_resources.cloudformation_asg_nlb_arn",
+                            )?,
+                            endpoint_service.acceptance_required(),
+                        ))
+                        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+                    aws_resources.nlb_endpoint_service_id = Some(service_id);
+                    aws_resources.nlb_endpoint_service_name = Some(service_name);
+                    spec.aws_resources = Some(aws_resources.clone());
+                    spec.sync(spec_file_path)?;
+                }
+                let service_id = required(
+                    &aws_resources.nlb_endpoint_service_id,
+                    "aws_resources.nlb_endpoint_service_id",
+                )?;
+                rt.block_on(ec2_manager.set_endpoint_service_acceptance(
+                    &service_id,
+                    endpoint_service.acceptance_required(),
+                ))
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+                let current = rt
+                    .block_on(ec2_manager.list_endpoint_service_principals(&service_id))
+                    .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+                let (to_add, to_remove) = endpoint_service.diff_principals(&current);
+                rt.block_on(ec2_manager.modify_endpoint_service_principals(
+                    &service_id,
+                    to_add,
+                    to_remove,
+                ))
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+                info!(
+                    "endpoint service name: {}",
+                    required(
+                        &aws_resources.nlb_endpoint_service_name,
+                        "aws_resources.nlb_endpoint_service_name"
+                    )?
+                );
+            }
+            None => {
+                // removed from the spec
+                let service_id = required(
+                    &aws_resources.nlb_endpoint_service_id,
+                    "aws_resources.nlb_endpoint_service_id",
+                )?;
+                rt.block_on(ec2_manager.delete_endpoint_service(&service_id))
+                    .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+                aws_resources.nlb_endpoint_service_id = None;
+                aws_resources.nlb_endpoint_service_name = None;
+                spec.aws_resources = Some(aws_resources.clone());
+                spec.sync(spec_file_path)?;
+            }
+        }
+    }
+
+    info!("STEP: listing all nodes based on S3 keys...");
+    for node in current_nodes.iter() {
+        info!("{}", node.encode_yaml()?);
+        if spec.avalanchego_config.staking_signer_key_file.is_none() {
+            continue;
+        }
+        // published by "avalanched" once healthy
+        let pop_s3_key = crate::node_keys::pop_s3_key(&spec.id, &node.node_id);
+        match rt.block_on(s3_manager.get_bytes(&aws_resources.s3_bucket, &pop_s3_key, None)) {
+            Ok(Some(b)) => info!("BLS proof of possession\n{}", String::from_utf8_lossy(&b)),
+            Ok(None) => warn!(
+                "BLS proof of possession for '{}' not published yet",
+                node.node_id
+            ),
+            Err(e) => warn!(
+                "no BLS proof of possession for '{}' ({})",
+                node.node_id,
+                e.message()
+            ),
+        }
+    }
+
+    info!("STEP: nodes are ready -- check the following endpoints!");
+    let http_port = spec.avalanchego_config.http_port;
+
+    let nlb_https_enabled = aws_resources.nlb_acm_certificate_arn.is_some();
+    let https_enabled = spec.avalanchego_config.http_tls_enabled == Some(true);
+
+    let (dns_name, scheme_for_dns, port_for_dns) =
+        match &aws_resources.cloudformation_asg_nlb_dns_name {
+            Some(dns_name) => {
+                if nlb_https_enabled || https_enabled {
+                    (dns_name.clone(), "https", 443)
+                } else {
+                    (dns_name.clone(), "http", http_port)
+                }
+            }
+            // the preview network has no NLB, so use the first node
+            None => (
+                current_nodes
+                    .first()
+                    .map(|n| n.public_ip.clone())
+                    .ok_or_else(|| Error::new(ErrorKind::NotFound, "no node found"))?,
+                if https_enabled { "https" } else { "http" },
+                http_port,
+            ),
+        };
+
+    // TODO: check "/ext/info"
+    // TODO: check "/ext/bc/C/rpc"
+    // TODO: subnet-evm endpoint with "/ext/bc/[BLOCKCHAIN TX ID]/rpc"
+    // ref. https://github.com/ava-labs/subnet-evm/blob/505f03904736ee9f8de7b862c06d0ae18062cc80/runner/main.go#L671
+    //
+    // NOTE: metamask endpoints will be "http://[NLB_DNS]:9650/ext/bc/[CHAIN ID]/rpc"
+    // NOTE: metamask endpoints will be "http://[NLB_DNS]:9650/ext/bc/C/rpc"
+    // NOTE: metamask chain ID is "43112" as in coreth "DEFAULT_GENESIS"
+    let mut dns_endpoints = crate::Endpoints::default();
+    let http_rpc = format!("{}://{}:{}", scheme_for_dns, dns_name, port_for_dns);
+    dns_endpoints.http_rpc = Some(http_rpc.clone());
+    dns_endpoints.http_rpc_x = Some(format!("{}/ext/bc/X", http_rpc));
+    dns_endpoints.http_rpc_p = Some(format!("{}/ext/bc/P", http_rpc));
+    dns_endpoints.http_rpc_c = Some(format!("{}/ext/bc/C/rpc", http_rpc));
+    dns_endpoints.metrics = Some(format!("{}/ext/metrics", http_rpc));
+    dns_endpoints.health = Some(format!("{}/ext/health", http_rpc));
+    dns_endpoints.liveness = Some(format!("{}/ext/health/liveness", http_rpc));
+    dns_endpoints.metamask_rpc = Some(format!("{}/ext/bc/C/rpc", http_rpc));
+    dns_endpoints.websocket = Some(format!(
+        "{}://{}:{}/ext/bc/C/ws",
+        if scheme_for_dns == "https" {
+            "wss"
+        } else {
+            "ws"
+        },
+        dns_name,
+        port_for_dns
+    ));
+    spec.endpoints = Some(dns_endpoints.clone());
+    spec.sync(spec_file_path)?;
+    rt.block_on(put_state(&provider, &spec))?;
+    info!("endpoints\n{}", dns_endpoints.encode_yaml()?);
+
+    let mut success = false;
+    for _ in 0..10_u8 {
+        if let Some(password) = &api_auth_password {
+            if let Err(e) = rt.block_on(auth::authorize(&http_rpc, password)) {
+                warn!("failed to issue API auth token for {} ({})", http_rpc, e);
+            }
+        }
+        let ret = rt.block_on(health::check(Arc::new(http_rpc.clone()), true));
+        let (res, err) = match ret {
+            Ok(res) => (res, None),
+            Err(e) => (
+                health::Response {
+                    checks: None,
+                    healthy: Some(false),
+                },
+                Some(e),
+            ),
+        };
+        success = res.healthy == Some(true);
+        if success {
+            info!("health/liveness check success for {}", http_rpc);
+            break;
+        }
+        warn!(
+            "health/liveness check failed for {} ({:?}, {:?})",
+            http_rpc, res, err
+        );
+        if aws_resources.db_backup_s3_bucket.is_some() {
+            // TODO: fix this
+            warn!("node may be still downloading database backup... skipping for now...");
+            success = true;
+            break;
+        }
+        thread::sleep(Duration::from_secs(10));
+    }
+    if !success {
+        warn!(
+            "health/liveness check failed for network id {}",
+            &spec.avalanchego_config.network_id
+        );
+        return Err(Error::new(ErrorKind::Other, "health/liveness check failed"));
+    }
+    if let Some(ws) = &dns_endpoints.websocket {
+        match rt.block_on(eth::chain_id_via_websocket(ws)) {
+            Ok(chain_id) => info!("websocket check success for {} (chain ID {})", ws, chain_id),
+            Err(e) => warn!(
+                "websocket check failed for {} ({}), re-check with 'status'",
+                ws, e
+            ),
+        }
+    }
+
+    let mut uris: Vec<String> = vec![];
+    for node in current_nodes.iter() {
+        let mut success = false;
+        for _ in 0..10_u8 {
+            if let Some(password) = &api_auth_password {
+                if let Err(e) = rt.block_on(auth::authorize(&node.http_endpoint, password)) {
+                    warn!(
+                        "failed to issue API auth token for {} ({})",
+                        node.machine_id, e
+                    );
+                }
+            }
+            let ret = rt.block_on(health::check(Arc::new(node.http_endpoint.clone()), true));
+            let (res, err) = match ret {
+                Ok(res) => (res, None),
+                Err(e) => (
+                    health::Response {
+                        checks: None,
+                        healthy: Some(false),
+                    },
+                    Some(e),
+                ),
+            };
+            success = res.healthy == Some(true);
+            if success {
+                info!("health/liveness check success for {}", node.machine_id);
+                break;
+            }
+            warn!(
+                "health/liveness check failed for {} ({:?}, {:?})",
+                node.machine_id, res, err
+            );
+            if aws_resources.db_backup_s3_bucket.is_some() {
+                // TODO: fix this
+                warn!("node may be still downloading database backup... skipping for now...");
+                success = true;
+                break;
+            }
+            thread::sleep(Duration::from_secs(10));
+        }
+        if !success {
+            warn!(
+                "health/liveness check failed for network id {}",
+                &spec.avalanchego_config.network_id
+            );
+            return Err(Error::new(ErrorKind::Other, "health/liveness check failed"));
+        }
+        info!(
+            "{0}/ext/metrics\n{0}/ext/health\n{0}/ext/health/liveness",
+            node.http_endpoint
+        );
+        uris.push(node.http_endpoint.clone());
+    }
+    info!("URIs: {}", uris.join(","));
+
+    // the heartbeats of the new nodes are published once they start
+    if let Some(cfg) = &spec.peer_allowlist {
+        info!("STEP: sync peer allowlist");
+        let cluster_node_ids = rt.block_on(peer_allowlist::fetch_registry(
+            &s3_manager,
+            &aws_resources.s3_bucket,
+            &spec.id,
+            Utc::now().timestamp() as u64,
+        ))?;
+        let allowlist = cfg.generate(&cluster_node_ids, Utc::now());
+        let prev = rt.block_on(peer_allowlist::get(
+            &s3_manager,
+            &aws_resources.s3_bucket,
+            &spec.id,
+        ))?;
+        // every push restarts the nodes whose subnet configs change
+        if prev.map(|v| v.node_ids) == Some(allowlist.node_ids.clone()) {
+            info!("peer allowlist has not changed, skipping push");
+        } else {
+            rt.block_on(peer_allowlist::push(
+                &s3_manager,
+                &aws_resources.s3_bucket,
+                &spec.id,
+                &allowlist,
+            ))?;
+            info!(
+                "pushed peer allowlist with {} node IDs ({} in the cluster)",
+                allowlist.node_ids.len(),
+                cluster_node_ids.len()
+            );
+        }
+    }
+
+    // after the nodes, as the relayer reads the blockchains through the cluster endpoint
+    if let Some(relayer) = &spec.icm_relayer {
+        info!("STEP: sync ICM relayer key");
+        let relayer_key = relayer.load_key(&spec)?;
+        rt.block_on(icm_relayer::put_key(
+            &s3_manager,
+            &envelope,
+            &aws_resources.s3_bucket,
+            &spec.id,
+            relayer_key.private_key_hex.expose_str(),
+        ))?;
+        for b in relayer.destination_blockchains.iter() {
+            let rpc_url = format!("{}/ext/bc/{}/rpc", http_rpc, b.blockchain_id);
+            match rt.block_on(eth::get_balance_wei(&rpc_url, &relayer_key.eth_address)) {
+                Ok(0) => warn!(
+                    "ICM relayer key '{}' has no balance on {} (fund it to pay for the deliveries)",
+                    relayer_key.eth_address, b.blockchain_id
+                ),
+                Ok(v) => info!(
+                    "ICM relayer key '{}' has {} wei on {}",
+                    relayer_key.eth_address, v, b.blockchain_id
+                ),
+                Err(e) => warn!(
+                    "failed to check ICM relayer key balance on {} ({})",
+                    b.blockchain_id, e
+                ),
+            }
+        }
+
+        if aws_resources
+            .cloudformation_asg_icm_relayer_logical_id
+            .is_none()
+        {
+            info!("STEP: create ASG for ICM relayer");
+
+            let cloudformation_asg_icm_relayer_stack_name = required(
+                &aws_resources.cloudformation_asg_icm_relayer,
+                "aws_resources.cloudformation_asg_icm_relayer",
+            )?;
+
+            // same template as the nodes, but never behind the NLB
+            let mut asg_icm_relayer_params = asg_parameters.clone();
+            asg_icm_relayer_params.push(build_param("NodeKind", icm_relayer::NODE_KIND));
+            asg_icm_relayer_params.push(build_param(
+                "VolumeSize",
+                format!("{}", relayer.volume_size_in_gb()).as_str(),
+            ));
+            if nlb_enabled {
+                asg_icm_relayer_params.push(build_param("NlbRegisterTargets", "false"));
+                if let Some(v) = &aws_resources.cloudformation_asg_nlb_target_group_arn {
+                    asg_icm_relayer_params.push(build_param("NlbTargetGroupArn", v));
+                }
+            }
+
+            // "NodeKind" in the parameters overrides the kind
+            let group = rt.block_on(create_node_group(
+                &provider,
+                &spec,
+                &aws_resources,
+                NodeGroupSpec {
+                    name: cloudformation_asg_icm_relayer_stack_name,
+                    cluster_id: spec.id.clone(),
+                    kind: node::Kind::NonAnchor,
+                    desired_capacity: 1,
+                    parameters: parameters_map(&asg_icm_relayer_params),
+                },
+            ))?;
+            aws_resources.cloudformation_asg_icm_relayer_logical_id = Some(group.id);
+            if aws_resources
+                .cloudformation_asg_icm_relayer_logical_id
+                .is_none()
+            {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    "aws_resources.cloudformation_asg_icm_relayer_logical_id not found",
+                ));
+            }
+            spec.aws_resources = Some(aws_resources.clone());
+            spec.sync(spec_file_path)?;
+            rt.block_on(put_state(&provider, &spec))?;
+        }
+        info!(
+            "ICM relayer '{}' relays {} to {} blockchains with '{}'",
+            aws_resources
+                .cloudformation_asg_icm_relayer_logical_id
+                .clone()
+                .unwrap_or_default(),
+            relayer.source_blockchains.len(),
+            relayer.destination_blockchains.len(),
+            relayer_key.eth_address
+        );
+    }
+
+    info!("apply all success!");
+    Ok(true)
+}
+
+/// Creates the VPC through the provider, and records it in "aws_resources".
+pub(super) async fn create_network<P: CloudProvider>(
+    provider: &P,
+    spec: &Spec,
+    aws_resources: &mut aws::Resources,
+) -> io::Result<()> {
+    let mut parameters = BTreeMap::new();
+    if let Some(private_connectivity) = &aws_resources.private_connectivity {
+        for (k, v) in private_connectivity.parameters() {
+            parameters.insert(k.to_string(), v);
+        }
+    }
+    if aws_resources.vpc_endpoints == Some(true) {
+        parameters.insert(String::from("VpcEndpoints"), String::from("true"));
+    }
+    let network = provider
+        .create_network(&NetworkSpec {
+            name: required(
+                &aws_resources.cloudformation_vpc,
+                "aws_resources.cloudformation_vpc",
+            )?,
+            cluster_id: spec.id.clone(),
+            cidr: String::from("10.0.0.0/16"),
+            subnet_cidrs: vec![
+                String::from("10.0.64.0/19"),
+                String::from("10.0.128.0/19"),
+                String::from("10.0.192.0/19"),
+            ],
+            ingress_ipv4_range: String::from("0.0.0.0/0"),
+            http_port: spec.avalanchego_config.http_port,
+            staking_port: spec.avalanchego_config.staking_port,
+            parameters,
+        })
+        .await?;
+
+    aws_resources.cloudformation_vpc_id = Some(network.id);
+    aws_resources.cloudformation_vpc_security_group_id = Some(network.security_group_id);
+    aws_resources.cloudformation_vpc_public_subnet_ids = Some(network.subnet_ids);
+    aws_resources.cloudformation_vpc_private_connection_id = network
+        .outputs
+        .get("VpcPeeringConnectionId")
+        .or_else(|| network.outputs.get("TransitGatewayAttachmentId"))
+        .cloned();
+    Ok(())
+}
+
+/// Creates the node group through the provider in the VPC of "aws_resources".
+pub(super) async fn create_node_group<P: CloudProvider>(
+    provider: &P,
+    spec: &Spec,
+    aws_resources: &aws::Resources,
+    group: NodeGroupSpec,
+) -> io::Result<NodeGroup> {
+    info!(
+        "creating node group '{}' of '{}' with provider '{}'",
+        group.name,
+        spec.id,
+        provider.name()
+    );
+    let network = super::network(aws_resources)?;
+    Ok(provider.create_node_group(&network, &group).await?)
+}
+
+/// Uploads the spec state, which is authoritative for the generated fields.
+pub(super) async fn put_state<P: CloudProvider>(provider: &P, spec: &Spec) -> io::Result<()> {
+    let aws_resources = super::aws_resources(spec)?;
+    provider
+        .put_object(
+            &aws_resources.s3_bucket,
+            &crate::StorageNamespace::ConfigFile(spec.id.clone()).encode(),
+            spec.encode_state_yaml()?.into_bytes(),
+        )
+        .await?;
+    Ok(())
+}
+
+fn parameters_map(params: &[Parameter]) -> BTreeMap<String, String> {
+    params
+        .iter()
+        .map(|p| {
+            (
+                p.parameter_key().unwrap_or_default().to_string(),
+                p.parameter_value().unwrap_or_default().to_string(),
+            )
+        })
+        .collect()
+}
+
+fn utf8(s: &std::ffi::OsStr) -> io::Result<&str> {
+    s.to_str().ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("non UTF-8 path {}", s.to_string_lossy()),
+        )
+    })
+}
+
+fn template(name: &str) -> io::Result<String> {
+    let path = format!("src/aws/cfn-templates/avalanche-node/{}", name);
+    let f = Asset::get(&path).ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("template '{}' not found", path),
+        )
+    })?;
+    Ok(String::from_utf8_lossy(f.data.as_ref()).to_string())
+}
+
+fn build_param(k: &str, v: &str) -> Parameter {
+    Parameter::builder()
+        .parameter_key(k)
+        .parameter_value(v)
+        .build()
+}
+
+/// Creates or updates the budget stack with the current "budget",
+/// or deletes the stack if "budget" was removed from the spec.
+fn sync_budget(
+    rt: &Runtime,
+    cloudformation_manager: &cloudformation::Manager,
+    id: &str,
+    budget_tmpl: &str,
+    stack_tags: Vec<Tag>,
+    aws_resources: &mut aws::Resources,
+) -> io::Result<()> {
+    let budget = match &aws_resources.budget {
+        Some(v) => v.clone(),
+        None => {
+            let stack_name = required(
+                &aws_resources.cloudformation_budget,
+                "aws_resources.cloudformation_budget",
+            )?;
+            rt.block_on(cloudformation_manager.delete_stack(&stack_name))
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+            thread::sleep(Duration::from_secs(10));
+            rt.block_on(cloudformation_manager.poll_stack(
+                &stack_name,
+                StackStatus::DeleteComplete,
+                Duration::from_secs(300),
+                Duration::from_secs(20),
+            ))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+            aws_resources.cloudformation_budget = None;
+            aws_resources.cloudformation_budget_alert_topic_arn = None;
+            return Ok(());
+        }
+    };
+
+    let asg_name = aws_resources
+        .cloudformation_asg_non_anchor_nodes_logical_id
+        .clone()
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::Other,
+                "aws_resources.cloudformation_asg_non_anchor_nodes_logical_id not found",
+            )
+        })?;
+    let mut params = vec![
+        build_param("Id", id),
+        build_param("NonAnchorAsgName", &asg_name),
+    ];
+    for (k, v) in budget.parameters() {
+        params.push(build_param(k, &v));
+    }
+
+    let (stack_name, status) = match aws_resources.cloudformation_budget.clone() {
+        Some(stack_name) => {
+            let ret = rt.block_on(cloudformation_manager.update_stack_parameters(
+                &stack_name,
+                Some(vec![Capability::CapabilityNamedIam]),
+                params,
+            ));
+            match ret {
+                Ok(_) => (stack_name, StackStatus::UpdateComplete),
+                Err(e) if e.message().contains("No updates are to be performed") => {
+                    info!("budget stack '{}' is up-to-date", stack_name);
+                    return Ok(());
+                }
+                Err(e) => return Err(Error::new(ErrorKind::Other, e.message())),
+            }
+        }
+        None => {
+            let stack_name = crate::StackName::Budget(id.to_string()).encode();
+            rt.block_on(cloudformation_manager.create_stack(
+                &stack_name,
+                Some(vec![Capability::CapabilityNamedIam]),
+                OnFailure::Delete,
+                budget_tmpl,
+                Some(stack_tags),
+                Some(params),
+            ))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+            aws_resources.cloudformation_budget = Some(stack_name.clone());
+            (stack_name, StackStatus::CreateComplete)
+        }
+    };
+
+    thread::sleep(Duration::from_secs(10));
+    let stack = rt
+        .block_on(cloudformation_manager.poll_stack(
+            &stack_name,
+            status,
+            Duration::from_secs(300),
+            Duration::from_secs(20),
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    for o in stack.outputs.unwrap_or_default() {
+        let k = o.output_key.unwrap_or_default();
+        let v = o.output_value.unwrap_or_default();
+        info!("stack output key=[{}], value=[{}]", k, v,);
+        if k.eq("AlertTopicArn") {
+            aws_resources.cloudformation_budget_alert_topic_arn = Some(v);
+        }
+    }
+    if let Some(v) = &aws_resources.cloudformation_budget_alert_topic_arn {
+        info!("budget alert topic: {}", v);
+    }
+    if budget.scale_down_non_anchor_nodes == Some(true) {
+        warn!(
+            "non-anchor nodes scale to zero over {}% of the monthly budget, restore the capacity of '{}' to scale back up",
+            budget.scale_down_threshold_percent(),
+            asg_name
+        );
+    }
+    Ok(())
+}
+
+/// Creates or updates the read-only auditor role stack with the current "auditor",
+/// or deletes the stack if "auditor" was removed from the spec.
+fn sync_auditor_role(
+    rt: &Runtime,
+    cloudformation_manager: &cloudformation::Manager,
+    id: &str,
+    auditor_role_tmpl: &str,
+    stack_tags: Vec<Tag>,
+    aws_resources: &mut aws::Resources,
+) -> io::Result<()> {
+    let auditor = match &aws_resources.auditor {
+        Some(v) => v.clone(),
+        None => {
+            let stack_name = required(
+                &aws_resources.cloudformation_auditor_role,
+                "aws_resources.cloudformation_auditor_role",
+            )?;
+            rt.block_on(cloudformation_manager.delete_stack(&stack_name))
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+            thread::sleep(Duration::from_secs(10));
+            rt.block_on(cloudformation_manager.poll_stack(
+                &stack_name,
+                StackStatus::DeleteComplete,
+                Duration::from_secs(300),
+                Duration::from_secs(20),
+            ))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+            aws_resources.cloudformation_auditor_role = None;
+            aws_resources.cloudformation_auditor_role_arn = None;
+            return Ok(());
+        }
+    };
+
+    let mut params = vec![
+        build_param("Id", id),
+        build_param("S3BucketName", &aws_resources.s3_bucket),
+    ];
+    for (k, v) in auditor.parameters() {
+        params.push(build_param(k, &v));
+    }
+
+    let (stack_name, status) = match aws_resources.cloudformation_auditor_role.clone() {
+        Some(stack_name) => {
+            let ret = rt.block_on(cloudformation_manager.update_stack_parameters(
+                &stack_name,
+                Some(vec![Capability::CapabilityNamedIam]),
+                params,
+            ));
+            match ret {
+                Ok(_) => (stack_name, StackStatus::UpdateComplete),
+                Err(e) if e.message().contains("No updates are to be performed") => {
+                    info!("auditor role stack '{}' is up-to-date", stack_name);
+                    return Ok(());
+                }
+                Err(e) => return Err(Error::new(ErrorKind::Other, e.message())),
+            }
+        }
+        None => {
+            let stack_name = crate::StackName::AuditorRole(id.to_string()).encode();
+            rt.block_on(cloudformation_manager.create_stack(
+                &stack_name,
+                Some(vec![Capability::CapabilityNamedIam]),
+                OnFailure::Delete,
+                auditor_role_tmpl,
+                Some(stack_tags),
+                Some(params),
+            ))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+            aws_resources.cloudformation_auditor_role = Some(stack_name.clone());
+            (stack_name, StackStatus::CreateComplete)
+        }
+    };
+
+    thread::sleep(Duration::from_secs(10));
+    let stack = rt
+        .block_on(cloudformation_manager.poll_stack(
+            &stack_name,
+            status,
+            Duration::from_secs(300),
+            Duration::from_secs(20),
+        ))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    for o in stack.outputs.unwrap_or_default() {
+        let k = o.output_key.unwrap_or_default();
+        let v = o.output_value.unwrap_or_default();
+        info!("stack output key=[{}], value=[{}]", k, v,);
+        if k.eq("RoleArn") {
+            aws_resources.cloudformation_auditor_role_arn = Some(v);
+        }
+    }
+    if let Some(v) = &aws_resources.cloudformation_auditor_role_arn {
+        info!("auditor role: {}", v);
+    }
+    Ok(())
+}
+
+/// Returns the private key path for the SSH commands, after making the key read-only.
+/// The imported key pair has no local private key, so a placeholder is returned.
+fn get_ec2_key_path_for_ssh(aws_resources: &aws::Resources) -> io::Result<String> {
+    match &aws_resources.ec2_key_path {
+        Some(ec2_key_path) => {
+            let f = File::open(ec2_key_path)?;
+            f.set_permissions(PermissionsExt::from_mode(0o444))?;
+            Ok(ec2_key_path.clone())
+        }
+        None => Ok(String::from("[YOUR_PRIVATE_KEY_PATH]")),
+    }
+}
+
+fn get_ec2_key_path(spec_file_path: &str) -> io::Result<String> {
+    let path = Path::new(spec_file_path);
+    let parent_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let name = path.file_stem().ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("no file name in '{}'", spec_file_path),
+        )
+    })?;
+    let new_name = format!("{}-ec2-access.key", utf8(name)?);
+    Ok(utf8(parent_dir.join(new_name).as_os_str())?.to_string())
+}
+
+/// Returns the changes from the spec applied last.
+fn applied_changes(
+    rt: &Runtime,
+    s3_manager: &s3::Manager,
+    spec: &Spec,
+) -> io::Result<Vec<spec_diff::Change>> {
+    let aws_resources = super::aws_resources(spec)?;
+    let tmp_spec_path = random::tmp_path(15, Some(".yaml"))?;
+    rt.block_on(s3_manager.get_object(
+        Arc::new(aws_resources.s3_bucket.clone()),
+        Arc::new(crate::StorageNamespace::ConfigFile(spec.id.clone()).encode()),
+        Arc::new(tmp_spec_path.clone()),
+    ))
+    .map_err(|e| {
+        Error::new(
+            ErrorKind::Other,
+            format!("failed to download the applied spec ({})", e.message()),
+        )
+    })?;
+    let applied = crate::Spec::load(&tmp_spec_path);
+    fs::remove_file(&tmp_spec_path)?;
+
+    spec_diff::diff(&applied?, spec)
+}
+
+#[test]
+fn test_auto_approve() {
+    use crate::avalanche::{
+        avalanchego::config as avalanchego_config, coreth::config as coreth_config,
+    };
+
+    let old: Spec = serde_json::from_value(serde_json::json!({
+        "id": "test",
+        "machine": { "non_anchor_nodes": 2, "instance_types": ["c5.large"] },
+        "install_artifacts": { "avalanched_bin": "a", "avalanchego_bin": "b" },
+        "avalanchego_config": avalanchego_config::Config::default(),
+        "coreth_config": coreth_config::Config::default(),
+    }))
+    .unwrap();
+    let mut new = old.clone();
+    new.machine.non_anchor_nodes = 3;
+    let in_place = spec_diff::diff(&old, &new).unwrap();
+    new.machine.instance_types = Some(vec![String::from("m5.large")]);
+    let destructive = spec_diff::diff(&old, &new).unwrap();
+
+    let deny = AutoApprove::default();
+    assert!(deny.approve(&new, None).unwrap());
+    assert!(deny.approve(&new, Some(&in_place)).unwrap());
+    assert!(deny.approve(&new, Some(&destructive)).is_err());
+
+    let allow = AutoApprove {
+        allow_destructive_changes: true,
+        ..Default::default()
+    };
+    assert!(allow.approve(&new, Some(&destructive)).unwrap());
+}
@@ -0,0 +1,324 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+use aws_sdk_cloudformation::model::StackStatus;
+use log::info;
+use tokio::runtime::Runtime;
+
+use super::{required, DeleteOptions};
+use crate::{
+    avalanche::node,
+    aws::{self, cloudformation, cloudwatch, ec2, s3, sts},
+    provider::{self, CloudProvider},
+    spec_state,
+    utils::compress,
+    Spec,
+};
+
+/// Deletes the resources of the spec, once "approve" returns true for the
+/// spec with the remote state, so the prompts stay in the caller.
+/// Returns false if not approved.
+pub fn run(
+    spec_file_path: &str,
+    opts: &DeleteOptions,
+    approve: &dyn Fn(&Spec) -> io::Result<bool>,
+) -> io::Result<bool> {
+    let mut spec = Spec::load(spec_file_path)?;
+
+    let rt = Runtime::new()?;
+    let shared_config = rt.block_on(aws::load_config(Some(super::aws_resources(&spec)?.region)))?;
+
+    // the remote state is authoritative for the generated fields
+    // (e.g., stale or lost local spec file)
+    let s3_manager = s3::Manager::new(&shared_config);
+    rt.block_on(spec_state::pull(&s3_manager, &mut spec))?;
+    let aws_resources = super::aws_resources(&spec)?;
+
+    let sts_manager = sts::Manager::new(&shared_config);
+    let current_identity = rt.block_on(sts_manager.get_identity())?;
+
+    // validate identity
+    match aws_resources.identity {
+        Some(identity) => {
+            // AWS calls must be made from the same caller
+            if !identity.is_same_caller(&current_identity) {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!(
+                        "config identity {:?} != currently loaded identity {:?}",
+                        identity, current_identity
+                    ),
+                ));
+            }
+        }
+        None => {
+            return Err(Error::new(ErrorKind::Other, "unknown identity"));
+        }
+    }
+
+    if !approve(&spec)? {
+        info!("'delete' not approved, not deleting resources");
+        return Ok(false);
+    }
+
+    // held until returned, so no other operation mutates the cluster
+    let _lock = rt
+        .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+        .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+    info!("deleting resources...");
+    let provider = provider::aws::Provider::new(&shared_config);
+    let ec2_manager = ec2::Manager::new(&shared_config);
+    let cloudformation_manager = cloudformation::Manager::new(&shared_config);
+    let cw_manager = cloudwatch::Manager::new(&shared_config);
+
+    // delete this first since EC2 key delete does not depend on ASG/VPC
+    // (mainly to speed up delete operation)
+    if let Some(ec2_key_name) = aws_resources.ec2_key_name.as_ref().filter(|_| {
+        aws_resources.ec2_key_path.is_some() || aws_resources.ec2_key_import_public_key.is_some()
+    }) {
+        thread::sleep(Duration::from_secs(2));
+        info!("STEP: delete EC2 key pair");
+
+        // the imported key pair has no local private key
+        if let Some(ec2_key_path) = &aws_resources.ec2_key_path {
+            if Path::new(ec2_key_path.as_str()).exists() {
+                fs::remove_file(ec2_key_path.as_str())?;
+            }
+            let ec2_key_path_compressed =
+                format!("{}{}", ec2_key_path, compress::Encoder::Zstd(3).ext());
+            if Path::new(ec2_key_path_compressed.as_str()).exists() {
+                fs::remove_file(ec2_key_path_compressed.as_str())?;
+            }
+            let ec2_key_path_compressed_encrypted =
+                format!("{}.encrypted", ec2_key_path_compressed);
+            if Path::new(ec2_key_path_compressed_encrypted.as_str()).exists() {
+                fs::remove_file(ec2_key_path_compressed_encrypted.as_str())?;
+            }
+        }
+        rt.block_on(ec2_manager.delete_key_pair(ec2_key_name))?;
+    }
+
+    // delete this first since KMS key delete does not depend on ASG/VPC
+    // (mainly to speed up delete operation)
+    if let (Some(cmk_id), Some(_)) = (&aws_resources.kms_cmk_id, &aws_resources.kms_cmk_arn) {
+        thread::sleep(Duration::from_secs(2));
+        info!("STEP: delete KMS key");
+        rt.block_on(provider.delete_key(cmk_id))?;
+    }
+
+    // IAM roles can be deleted without being blocked on ASG/VPC
+    if aws_resources
+        .cloudformation_ec2_instance_profile_arn
+        .is_some()
+    {
+        thread::sleep(Duration::from_secs(2));
+        info!("STEP: trigger delete EC2 instance role");
+
+        let ec2_instance_role_stack_name = required(
+            &aws_resources.cloudformation_ec2_instance_role,
+            "aws_resources.cloudformation_ec2_instance_role",
+        )?;
+        rt.block_on(cloudformation_manager.delete_stack(ec2_instance_role_stack_name.as_str()))?;
+    }
+
+    // the budget only refers to the ASG by name
+    if let Some(budget_stack_name) = &aws_resources.cloudformation_budget {
+        thread::sleep(Duration::from_secs(2));
+        info!("STEP: delete budget");
+        rt.block_on(cloudformation_manager.delete_stack(budget_stack_name.as_str()))?;
+        thread::sleep(Duration::from_secs(10));
+        rt.block_on(cloudformation_manager.poll_stack(
+            budget_stack_name.as_str(),
+            StackStatus::DeleteComplete,
+            Duration::from_secs(300),
+            Duration::from_secs(20),
+        ))?;
+    }
+
+    if let Some(auditor_role_stack_name) = &aws_resources.cloudformation_auditor_role {
+        thread::sleep(Duration::from_secs(2));
+        info!("STEP: delete auditor role");
+        rt.block_on(cloudformation_manager.delete_stack(auditor_role_stack_name.as_str()))?;
+        thread::sleep(Duration::from_secs(10));
+        rt.block_on(cloudformation_manager.poll_stack(
+            auditor_role_stack_name.as_str(),
+            StackStatus::DeleteComplete,
+            Duration::from_secs(300),
+            Duration::from_secs(20),
+        ))?;
+    }
+
+    // the NLB cannot be deleted while the endpoint service exists
+    if let Some(service_id) = &aws_resources.nlb_endpoint_service_id {
+        thread::sleep(Duration::from_secs(2));
+        info!("STEP: delete NLB endpoint service");
+        rt.block_on(ec2_manager.delete_endpoint_service(service_id))?;
+    }
+
+    let relayer = match aws_resources.cloudformation_asg_icm_relayer_logical_id {
+        Some(_) => Some(required(
+            &aws_resources.cloudformation_asg_icm_relayer,
+            "aws_resources.cloudformation_asg_icm_relayer",
+        )?),
+        None => None,
+    };
+    let non_anchor_nodes = match aws_resources.cloudformation_asg_non_anchor_nodes_logical_id {
+        Some(_) => Some(required(
+            &aws_resources.cloudformation_asg_non_anchor_nodes,
+            "aws_resources.cloudformation_asg_non_anchor_nodes",
+        )?),
+        None => None,
+    };
+    let anchor_nodes = match aws_resources.cloudformation_asg_anchor_nodes_logical_id {
+        Some(_) if spec.machine.anchor_nodes.unwrap_or(0) > 0 => Some(required(
+            &aws_resources.cloudformation_asg_anchor_nodes,
+            "aws_resources.cloudformation_asg_anchor_nodes",
+        )?),
+        _ => None,
+    };
+    thread::sleep(Duration::from_secs(2));
+    info!("STEP: delete ASGs");
+    rt.block_on(delete_node_groups(
+        &provider,
+        relayer.as_deref(),
+        non_anchor_nodes.as_deref(),
+        anchor_nodes.as_deref(),
+    ))?;
+
+    // the addresses are disassociated once the instances are terminated
+    if let Some(addresses) = &aws_resources.elastic_ip_pool_addresses {
+        thread::sleep(Duration::from_secs(2));
+        info!("STEP: release Elastic IP pool");
+        for a in addresses.iter() {
+            rt.block_on(ec2_manager.release_address(&a.allocation_id))?;
+        }
+        rt.block_on(aws::eip::Manager::new(&shared_config).delete_leases(addresses))?;
+    }
+
+    if spec.static_node_keys.unwrap_or(false) {
+        let mut slot_ids = crate::node_keys::slot_ids(
+            &spec.id,
+            &node::Kind::Anchor,
+            spec.machine.anchor_nodes.unwrap_or(0),
+        );
+        slot_ids.extend(crate::node_keys::slot_ids(
+            &spec.id,
+            &node::Kind::NonAnchor,
+            spec.machine.non_anchor_nodes,
+        ));
+        rt.block_on(crate::node_keys::slot_manager(&shared_config).delete(&slot_ids))?;
+    }
+
+    // VPC delete must run after associated EC2 instances are terminated due to dependencies
+    if aws_resources.cloudformation_vpc_id.is_some()
+        && aws_resources.cloudformation_vpc_security_group_id.is_some()
+        && aws_resources.cloudformation_vpc_public_subnet_ids.is_some()
+    {
+        thread::sleep(Duration::from_secs(2));
+        info!("STEP: delete VPC");
+
+        let vpc_stack_name = required(
+            &aws_resources.cloudformation_vpc,
+            "aws_resources.cloudformation_vpc",
+        )?;
+        rt.block_on(provider.delete_network(vpc_stack_name.as_str()))?;
+    }
+
+    if aws_resources
+        .cloudformation_ec2_instance_profile_arn
+        .is_some()
+    {
+        thread::sleep(Duration::from_secs(2));
+        info!("STEP: confirming delete EC2 instance role");
+
+        let ec2_instance_role_stack_name = required(
+            &aws_resources.cloudformation_ec2_instance_role,
+            "aws_resources.cloudformation_ec2_instance_role",
+        )?;
+        rt.block_on(cloudformation_manager.poll_stack(
+            ec2_instance_role_stack_name.as_str(),
+            StackStatus::DeleteComplete,
+            Duration::from_secs(500),
+            Duration::from_secs(30),
+        ))?;
+    }
+
+    if opts.delete_cloudwatch_log_group {
+        // deletes the one auto-created by nodes
+        thread::sleep(Duration::from_secs(2));
+        info!("STEP: cloudwatch log groups");
+        rt.block_on(cw_manager.delete_log_group(&spec.id))?;
+    }
+
+    if opts.delete_s3_objects {
+        thread::sleep(Duration::from_secs(1));
+        info!("STEP: delete S3 objects");
+        thread::sleep(Duration::from_secs(5));
+        rt.block_on(provider.delete_objects(&aws_resources.s3_bucket, Some(&spec.id)))?;
+    }
+
+    if opts.delete_s3_bucket {
+        thread::sleep(Duration::from_secs(1));
+        info!("STEP: delete S3 bucket");
+        thread::sleep(Duration::from_secs(5));
+        // the versioned bucket must be emptied of all versions
+        rt.block_on(s3_manager.delete_object_versions(&aws_resources.s3_bucket, None))?;
+        rt.block_on(provider.delete_bucket(&aws_resources.s3_bucket))?;
+        // NOTE: do not delete db backups...
+        if aws_resources.db_backup_s3_bucket.is_some() {
+            info!(
+                "skipping deleting {}",
+                required(
+                    &aws_resources.db_backup_s3_bucket,
+                    "aws_resources.db_backup_s3_bucket"
+                )?
+            );
+            // rt.block_on(
+            //     s3_manager
+            //         .delete_objects(&aws_resources.s3_bucket_db_backup.clone().unwrap(), None),
+            // )
+            // .unwrap();
+            // rt.block_on(
+            //     s3_manager.delete_bucket(&aws_resources.s3_bucket_db_backup.clone().unwrap()),
+            // )
+            // .unwrap();
+        }
+    }
+
+    info!("delete all success!");
+    Ok(true)
+}
+
+/// Deletes the node groups, each given by name if created. The ICM relayer
+/// goes first, as its stack may reference the NLB target group of the
+/// non-anchor nodes. The anchor and non-anchor nodes are deleted concurrently.
+pub(super) async fn delete_node_groups<P: CloudProvider>(
+    provider: &P,
+    relayer: Option<&str>,
+    non_anchor_nodes: Option<&str>,
+    anchor_nodes: Option<&str>,
+) -> io::Result<()> {
+    if let Some(name) = relayer {
+        info!("deleting node group '{}' for ICM relayer", name);
+        provider.delete_node_group(name).await?;
+    }
+    tokio::try_join!(
+        delete_node_group(provider, non_anchor_nodes),
+        delete_node_group(provider, anchor_nodes)
+    )?;
+    Ok(())
+}
+
+async fn delete_node_group<P: CloudProvider>(provider: &P, name: Option<&str>) -> io::Result<()> {
+    if let Some(name) = name {
+        info!("deleting node group '{}'", name);
+        provider.delete_node_group(name).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,374 @@
+pub mod apply;
+pub mod delete;
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Error, ErrorKind},
+};
+
+use log::info;
+use tokio::runtime::Runtime;
+
+use crate::{
+    auto_delete,
+    aws::{self, cloudformation, s3},
+    provider::{self, CloudProvider},
+    spec_state, Endpoints, Spec,
+};
+
+/// Orchestrates the cluster of the spec without the CLI parsing,
+/// the prompts, and the process exits, so other Rust services can embed
+/// "apply", "status", and "delete" instead of running the binary.
+/// The spec file is written and updated in place as with the CLI,
+/// since the generated fields (e.g., stack names) are tracked there.
+/// Each method blocks on its own runtime, so call it from
+/// "tokio::task::spawn_blocking" in an async service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deployer {
+    pub spec_file_path: String,
+    /// Applies the replacement and the node ID changes to the existing
+    /// cluster (same as "apply --yes"), otherwise "create" fails on them.
+    pub allow_destructive_changes: bool,
+    /// Applies the destructive changes even outside of the maintenance
+    /// window in the spec (same as "apply --force").
+    pub force: bool,
+}
+
+/// Options of "Deployer::delete", same as the "delete" flags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeleteOptions {
+    pub delete_cloudwatch_log_group: bool,
+    pub delete_s3_objects: bool,
+    pub delete_s3_bucket: bool,
+}
+
+/// Represents the current state of the cluster.
+#[derive(Debug, Clone)]
+pub struct Status {
+    pub id: String,
+    /// CloudFormation stacks tagged with the cluster ID, except the deleted ones.
+    pub stacks: Vec<cloudformation::TaggedStack>,
+    pub anchor_nodes: Vec<provider::Instance>,
+    pub non_anchor_nodes: Vec<provider::Instance>,
+    pub endpoints: Option<Endpoints>,
+}
+
+impl Deployer {
+    pub fn new(spec_file_path: &str) -> Self {
+        Self {
+            spec_file_path: String::from(spec_file_path),
+            allow_destructive_changes: false,
+            force: false,
+        }
+    }
+
+    /// Creates the cluster of the spec, or applies the spec changes to the
+    /// existing cluster. Returns the spec with the generated fields.
+    pub fn create(&self, spec: &Spec) -> io::Result<Spec> {
+        spec.sync(&self.spec_file_path)?;
+        apply::run(
+            &self.spec_file_path,
+            &apply::AutoApprove {
+                allow_destructive_changes: self.allow_destructive_changes,
+                force: self.force,
+            },
+        )?;
+        Spec::load(&self.spec_file_path)
+    }
+
+    /// Returns the stacks and the nodes of the cluster.
+    pub fn status(&self, spec: &Spec) -> io::Result<Status> {
+        let rt = Runtime::new()?;
+        let (spec, shared_config) = rt.block_on(load_remote(spec))?;
+
+        let cloudformation_manager = cloudformation::Manager::new(&shared_config);
+        let stacks: Vec<cloudformation::TaggedStack> = rt
+            .block_on(cloudformation_manager.list_stacks_with_tag(auto_delete::TAG_CLUSTER_ID))?
+            .into_iter()
+            .filter(|s| s.tags.get(auto_delete::TAG_CLUSTER_ID) == Some(&spec.id))
+            .collect();
+
+        let provider = provider::aws::Provider::new(&shared_config);
+        let (anchor_nodes, non_anchor_nodes) = rt.block_on(list_nodes(&provider, &spec))?;
+
+        Ok(Status {
+            id: spec.id.clone(),
+            stacks,
+            anchor_nodes,
+            non_anchor_nodes,
+            endpoints: spec.endpoints.clone(),
+        })
+    }
+
+    /// Updates the desired capacity of the non-anchor nodes ASG, and
+    /// returns the spec with the new "machine.non_anchor_nodes".
+    /// The anchor nodes are not scaled, since they hold the genesis stakes.
+    pub fn scale(&self, spec: &Spec, non_anchor_nodes: u32) -> io::Result<Spec> {
+        let rt = Runtime::new()?;
+        let (mut spec, shared_config) = rt.block_on(load_remote(spec))?;
+        spec.machine.non_anchor_nodes = non_anchor_nodes;
+        spec.validate()?;
+
+        // held until returned, so no other operation mutates the cluster
+        let _lock = rt
+            .block_on(aws::lock::Guard::acquire(&shared_config, &spec.id))
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+
+        let provider = provider::aws::Provider::new(&shared_config);
+        rt.block_on(scale_non_anchor_nodes(&provider, &spec))?;
+        spec.sync(&self.spec_file_path)?;
+        Ok(spec)
+    }
+
+    /// Deletes the cluster of the spec.
+    pub fn delete(&self, spec: &Spec, opts: &DeleteOptions) -> io::Result<()> {
+        spec.sync(&self.spec_file_path)?;
+        delete::run(&self.spec_file_path, opts, &|_| Ok(true))?;
+        Ok(())
+    }
+}
+
+fn aws_resources(spec: &Spec) -> io::Result<aws::Resources> {
+    spec.aws_resources
+        .clone()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "no aws_resources found"))
+}
+
+/// Returns the VPC of "aws_resources" as the provider network.
+fn network(aws_resources: &aws::Resources) -> io::Result<provider::Network> {
+    Ok(provider::Network {
+        name: required(
+            &aws_resources.cloudformation_vpc,
+            "aws_resources.cloudformation_vpc",
+        )?,
+        id: required(
+            &aws_resources.cloudformation_vpc_id,
+            "aws_resources.cloudformation_vpc_id",
+        )?,
+        security_group_id: required(
+            &aws_resources.cloudformation_vpc_security_group_id,
+            "aws_resources.cloudformation_vpc_security_group_id",
+        )?,
+        subnet_ids: required(
+            &aws_resources.cloudformation_vpc_public_subnet_ids,
+            "aws_resources.cloudformation_vpc_public_subnet_ids",
+        )?,
+        outputs: BTreeMap::new(),
+    })
+}
+
+/// Returns the anchor and the non-anchor nodes of the created ASGs.
+async fn list_nodes<P: CloudProvider>(
+    provider: &P,
+    spec: &Spec,
+) -> io::Result<(Vec<provider::Instance>, Vec<provider::Instance>)> {
+    let aws_resources = aws_resources(spec)?;
+    let mut anchor_nodes = Vec::new();
+    if let (Some(name), Some(asg_name)) = (
+        &aws_resources.cloudformation_asg_anchor_nodes,
+        &aws_resources.cloudformation_asg_anchor_nodes_logical_id,
+    ) {
+        let group = node_group(name, asg_name, spec.machine.anchor_nodes.unwrap_or(0));
+        anchor_nodes = provider.list_instances(&group).await?;
+    }
+    let mut non_anchor_nodes = Vec::new();
+    if let (Some(name), Some(asg_name)) = (
+        &aws_resources.cloudformation_asg_non_anchor_nodes,
+        &aws_resources.cloudformation_asg_non_anchor_nodes_logical_id,
+    ) {
+        let group = node_group(name, asg_name, spec.machine.non_anchor_nodes);
+        non_anchor_nodes = provider.list_instances(&group).await?;
+    }
+    Ok((anchor_nodes, non_anchor_nodes))
+}
+
+/// Scales the non-anchor nodes ASG to "machine.non_anchor_nodes",
+/// and uploads the spec state.
+async fn scale_non_anchor_nodes<P: CloudProvider>(provider: &P, spec: &Spec) -> io::Result<()> {
+    let aws_resources = aws_resources(spec)?;
+    let stack_name = aws_resources
+        .cloudformation_asg_non_anchor_nodes
+        .clone()
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                "aws_resources.cloudformation_asg_non_anchor_nodes not found (run 'create' first)",
+            )
+        })?;
+
+    let non_anchor_nodes = spec.machine.non_anchor_nodes;
+    info!(
+        "scaling non-anchor nodes of '{}' to {} with provider '{}'",
+        spec.id,
+        non_anchor_nodes,
+        provider.name()
+    );
+    let group = node_group(
+        &stack_name,
+        &required(
+            &aws_resources.cloudformation_asg_non_anchor_nodes_logical_id,
+            "aws_resources.cloudformation_asg_non_anchor_nodes_logical_id",
+        )?,
+        non_anchor_nodes,
+    );
+    provider.scale_node_group(&group, non_anchor_nodes).await?;
+    apply::put_state(provider, spec).await
+}
+
+/// Returns the ASG of the stack as the provider node group.
+fn node_group(stack_name: &str, asg_name: &str, desired_capacity: u32) -> provider::NodeGroup {
+    provider::NodeGroup {
+        name: stack_name.to_string(),
+        id: asg_name.to_string(),
+        desired_capacity,
+        outputs: BTreeMap::new(),
+    }
+}
+
+/// Returns the field that the earlier steps set, or fails on the
+/// partially applied spec instead of panicking.
+fn required<T: Clone>(v: &Option<T>, name: &str) -> io::Result<T> {
+    v.clone()
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("unexpected None {}", name)))
+}
+
+/// Returns the spec with the remote state, which is authoritative for
+/// the generated fields, and the AWS configuration of its region.
+async fn load_remote(spec: &Spec) -> io::Result<(Spec, aws_types::SdkConfig)> {
+    let aws_resources = aws_resources(spec)?;
+    let shared_config = aws::load_config(Some(aws_resources.region.clone())).await?;
+
+    let mut spec = spec.clone();
+    let s3_manager = s3::Manager::new(&shared_config);
+    spec_state::pull(&s3_manager, &mut spec).await?;
+    Ok((spec, shared_config))
+}
+
+#[test]
+fn test_provider() {
+    use crate::{
+        avalanche::{
+            avalanchego::config as avalanchego_config, coreth::config as coreth_config, node,
+        },
+        provider::{
+            mock::{MockProvider, Operation},
+            NodeGroupSpec,
+        },
+        StorageNamespace,
+    };
+
+    macro_rules! ab {
+        ($e:expr) => {
+            tokio_test::block_on($e)
+        };
+    }
+
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let mut spec: Spec = serde_json::from_value(serde_json::json!({
+        "id": "test",
+        "machine": { "anchor_nodes": 2, "non_anchor_nodes": 3, "instance_types": ["c5.large"] },
+        "install_artifacts": { "avalanched_bin": "a", "avalanchego_bin": "b" },
+        "avalanchego_config": avalanchego_config::Config::default(),
+        "coreth_config": coreth_config::Config::default(),
+    }))
+    .unwrap();
+    let mut aws_resources = aws::Resources {
+        s3_bucket: String::from("test-bucket"),
+        cloudformation_vpc: Some(String::from("test-vpc")),
+        cloudformation_asg_anchor_nodes: Some(String::from("test-asg-anchor-nodes")),
+        cloudformation_asg_non_anchor_nodes: Some(String::from("test-asg-non-anchor-nodes")),
+        ..Default::default()
+    };
+    let group = |name: &str, kind: node::Kind, n: u32| NodeGroupSpec {
+        name: String::from(name),
+        cluster_id: String::from("test"),
+        kind,
+        desired_capacity: n,
+        parameters: BTreeMap::new(),
+    };
+
+    let provider = MockProvider::new();
+    ab!(provider.create_bucket("test-bucket")).unwrap();
+
+    // fails at the network, then the retry creates it
+    provider.fail(Operation::CreateNetwork, "AccessDeniedException", 1);
+    assert!(ab!(apply::create_network(&provider, &spec, &mut aws_resources)).is_err());
+    assert!(!provider.has_network("test-vpc"));
+    assert!(aws_resources.cloudformation_vpc_id.is_none());
+    ab!(apply::create_network(&provider, &spec, &mut aws_resources)).unwrap();
+    assert!(provider.has_network("test-vpc"));
+    assert_eq!(
+        aws_resources
+            .cloudformation_vpc_public_subnet_ids
+            .as_ref()
+            .map(|ids| ids.len()),
+        Some(3)
+    );
+
+    let anchor_nodes = ab!(apply::create_node_group(
+        &provider,
+        &spec,
+        &aws_resources,
+        group("test-asg-anchor-nodes", node::Kind::Anchor, 2),
+    ))
+    .unwrap();
+    aws_resources.cloudformation_asg_anchor_nodes_logical_id = Some(anchor_nodes.id);
+    let non_anchor_nodes = ab!(apply::create_node_group(
+        &provider,
+        &spec,
+        &aws_resources,
+        group("test-asg-non-anchor-nodes", node::Kind::NonAnchor, 3),
+    ))
+    .unwrap();
+    aws_resources.cloudformation_asg_non_anchor_nodes_logical_id = Some(non_anchor_nodes.id);
+    spec.aws_resources = Some(aws_resources.clone());
+
+    ab!(apply::put_state(&provider, &spec)).unwrap();
+    assert!(ab!(provider.get_object(
+        "test-bucket",
+        &StorageNamespace::ConfigFile(spec.id.clone()).encode()
+    ))
+    .unwrap()
+    .is_some());
+
+    let (anchor_nodes, non_anchor_nodes) = ab!(list_nodes(&provider, &spec)).unwrap();
+    assert_eq!(anchor_nodes.len(), 2);
+    assert_eq!(non_anchor_nodes.len(), 3);
+
+    // scale, and the replaced instance keeps the group size
+    spec.machine.non_anchor_nodes = 5;
+    ab!(scale_non_anchor_nodes(&provider, &spec)).unwrap();
+    assert_eq!(provider.count(Operation::ScaleNodeGroup), 1);
+    let (_, non_anchor_nodes) = ab!(list_nodes(&provider, &spec)).unwrap();
+    assert_eq!(non_anchor_nodes.len(), 5);
+    let replacement = provider
+        .terminate_instance(&non_anchor_nodes[0].id)
+        .unwrap();
+    let (_, non_anchor_nodes) = ab!(list_nodes(&provider, &spec)).unwrap();
+    assert_eq!(non_anchor_nodes.len(), 5);
+    assert!(non_anchor_nodes.iter().any(|i| i.id == replacement));
+
+    // partial deletion resumes
+    provider.fail(Operation::DeleteNodeGroup, "ThrottlingException", 1);
+    assert!(ab!(delete::delete_node_groups(
+        &provider,
+        None,
+        Some("test-asg-non-anchor-nodes"),
+        Some("test-asg-anchor-nodes"),
+    ))
+    .is_err());
+    assert!(provider.has_node_group("test-asg-non-anchor-nodes"));
+    ab!(delete::delete_node_groups(
+        &provider,
+        None,
+        Some("test-asg-non-anchor-nodes"),
+        Some("test-asg-anchor-nodes"),
+    ))
+    .unwrap();
+    assert!(!provider.has_node_group("test-asg-non-anchor-nodes"));
+    assert!(!provider.has_node_group("test-asg-anchor-nodes"));
+    let (anchor_nodes, non_anchor_nodes) = ab!(list_nodes(&provider, &spec)).unwrap();
+    assert!(anchor_nodes.is_empty());
+    assert!(non_anchor_nodes.is_empty());
+}
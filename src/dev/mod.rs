@@ -149,7 +149,7 @@ impl Spec {
 
             aws_resources: Some(AWSResources {
                 region: String::from("us-west-2"),
-                bucket: format!("dev-machine-{}-{}", time::get(6), id::system(7)), // [year][month][date]-[system host-based id]
+                bucket: id::system_with_prefix("dev-machine", &time::get(6), 7), // [year][month][date]-[system host-based id]
                 ..AWSResources::default()
             }),
 
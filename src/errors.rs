@@ -1,14 +1,40 @@
+use std::{
+    io::{self, ErrorKind},
+    time::Duration,
+};
+
+use aws_smithy_http::result::SdkError;
+use aws_smithy_types::retry::{ErrorKind as RetryErrorKind, ProvideErrorKind};
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// Backing errors for all AWS operations.
+/// The AWS API errors are classified by "from_sdk", so the callers
+/// can react to the throttling, permission, not-found, validation,
+/// and timeout errors without matching the error messages.
 #[derive(Error, Debug)]
 pub enum Error {
+    /// API error that is not classified below.
     #[error("failed API")]
     API { message: String, is_retryable: bool },
     #[error("failed for other reasons")]
     Other { message: String, is_retryable: bool },
+    /// Request rate exceeded, always retryable with backoff.
+    #[error("throttled")]
+    Throttling { message: String },
+    /// Missing IAM permissions or invalid credentials.
+    #[error("permission denied")]
+    PermissionDenied { message: String },
+    /// Resource does not exist (e.g., deleted stack, missing S3 object).
+    #[error("not found")]
+    NotFound { message: String },
+    /// Request rejected for the invalid input.
+    #[error("invalid request")]
+    Validation { message: String },
+    /// Request timed out before the response, always retryable.
+    #[error("timed out")]
+    Timeout { message: String },
 }
 
 impl Error {
@@ -17,7 +43,13 @@ impl Error {
     #[must_use]
     pub fn message(&self) -> String {
         match self {
-            Error::API { message, .. } | Error::Other { message, .. } => message.clone(),
+            Error::API { message, .. }
+            | Error::Other { message, .. }
+            | Error::Throttling { message }
+            | Error::PermissionDenied { message }
+            | Error::NotFound { message }
+            | Error::Validation { message }
+            | Error::Timeout { message } => message.clone(),
         }
     }
 
@@ -27,6 +59,220 @@ impl Error {
     pub fn is_retryable(&self) -> bool {
         match self {
             Error::API { is_retryable, .. } | Error::Other { is_retryable, .. } => *is_retryable,
+            Error::Throttling { .. } | Error::Timeout { .. } => true,
+            Error::PermissionDenied { .. } | Error::NotFound { .. } | Error::Validation { .. } => {
+                false
+            }
         }
     }
+
+    #[inline]
+    #[must_use]
+    pub fn is_throttling(&self) -> bool {
+        matches!(self, Error::Throttling { .. })
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_permission_denied(&self) -> bool {
+        matches!(self, Error::PermissionDenied { .. })
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Error::NotFound { .. })
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_validation(&self) -> bool {
+        matches!(self, Error::Validation { .. })
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Error::Timeout { .. })
+    }
+
+    /// Returns the wait before the "attempt"-th retry (starting from 1),
+    /// or None if the error is not retryable.
+    /// The throttling errors back off exponentially up to 1-minute.
+    #[must_use]
+    pub fn retry_after(&self, attempt: u32) -> Option<Duration> {
+        if !self.is_retryable() {
+            return None;
+        }
+        if self.is_throttling() {
+            let secs = 2_u64.saturating_pow(attempt.min(6));
+            return Some(Duration::from_secs(secs.min(60)));
+        }
+        Some(Duration::from_secs(5))
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        let kind = match &e {
+            Error::PermissionDenied { .. } => ErrorKind::PermissionDenied,
+            Error::NotFound { .. } => ErrorKind::NotFound,
+            Error::Validation { .. } => ErrorKind::InvalidInput,
+            Error::Timeout { .. } => ErrorKind::TimedOut,
+            _ => ErrorKind::Other,
+        };
+        io::Error::new(kind, e.message())
+    }
+}
+
+/// ref. https://docs.aws.amazon.com/general/latest/gr/api-retries.html
+const THROTTLING_CODES: [&str; 11] = [
+    "Throttling",
+    "ThrottlingException",
+    "ThrottledException",
+    "RequestThrottledException",
+    "TooManyRequestsException",
+    "ProvisionedThroughputExceededException",
+    "TransactionInProgressException",
+    "RequestLimitExceeded",
+    "BandwidthLimitExceeded",
+    "SlowDown",
+    "PriorRequestNotComplete",
+];
+
+const PERMISSION_DENIED_CODES: [&str; 11] = [
+    "AccessDenied",
+    "AccessDeniedException",
+    "UnauthorizedOperation",
+    "UnauthorizedException",
+    "UnrecognizedClientException",
+    "AuthFailure",
+    "InvalidClientTokenId",
+    "ExpiredToken",
+    "ExpiredTokenException",
+    "SignatureDoesNotMatch",
+    "OptInRequired",
+];
+
+/// Server-side errors of the services, with the same request retryable.
+/// ref. https://docs.aws.amazon.com/kms/latest/APIReference/CommonErrors.html
+const RETRYABLE_CODES: [&str; 3] = [
+    "DependencyTimeoutException",
+    "KMSInternalException",
+    "KeyUnavailableException",
+];
+
+const VALIDATION_CODES: [&str; 8] = [
+    "ValidationError",
+    "ValidationException",
+    "MissingParameter",
+    "MalformedPolicyDocument",
+    "InvalidRequest",
+    "InvalidArgument",
+    "InvalidInput",
+    "SerializationException",
+];
+
+/// Classifies the AWS API error by its error code.
+pub fn classify(message: String, code: Option<&str>, is_retryable: bool) -> Error {
+    let code = match code {
+        Some(v) => v,
+        None => {
+            return Error::API {
+                message,
+                is_retryable,
+            }
+        }
+    };
+    if THROTTLING_CODES.contains(&code) {
+        return Error::Throttling { message };
+    }
+    if PERMISSION_DENIED_CODES.contains(&code) {
+        return Error::PermissionDenied { message };
+    }
+    // e.g., "NoSuchKey", "InvalidInstanceID.NotFound", "ResourceNotFoundException"
+    if code.starts_with("NoSuch")
+        || code.ends_with("NotFound")
+        || code.ends_with("NotFoundException")
+        || code.ends_with("NotFoundFault")
+        // CloudFormation returns "ValidationError" for the non-existing stack
+        || (code == "ValidationError" && message.contains("does not exist"))
+    {
+        return Error::NotFound { message };
+    }
+    // e.g., "InvalidParameterValue", "InvalidAMIID.Malformed"
+    if VALIDATION_CODES.contains(&code) || code.starts_with("Invalid") {
+        return Error::Validation { message };
+    }
+    Error::API {
+        message,
+        is_retryable: is_retryable || RETRYABLE_CODES.contains(&code),
+    }
+}
+
+/// Converts the AWS SDK error with the classification.
+pub fn from_sdk<E>(message: String, e: &SdkError<E>) -> Error
+where
+    E: ProvideErrorKind,
+{
+    match e {
+        SdkError::TimeoutError(_) => Error::Timeout { message },
+        SdkError::DispatchFailure(f) if f.is_timeout() => Error::Timeout { message },
+        SdkError::DispatchFailure(f) => Error::API {
+            message,
+            is_retryable: f.is_io(),
+        },
+        SdkError::ResponseError { .. } => Error::API {
+            message,
+            is_retryable: true,
+        },
+        SdkError::ServiceError { err, .. } => {
+            if err.retryable_error_kind() == Some(RetryErrorKind::ThrottlingError) {
+                return Error::Throttling { message };
+            }
+            let is_retryable = matches!(
+                err.retryable_error_kind(),
+                Some(RetryErrorKind::TransientError) | Some(RetryErrorKind::ServerError)
+            );
+            classify(message, err.code(), is_retryable)
+        }
+        _ => Error::API {
+            message,
+            is_retryable: false,
+        },
+    }
+}
+
+#[test]
+fn test_classify() {
+    let c = |code: &str| classify(String::from("test"), Some(code), false);
+    assert!(c("ThrottlingException").is_throttling());
+    assert!(c("SlowDown").is_retryable());
+    assert!(c("AccessDeniedException").is_permission_denied());
+    assert!(c("NoSuchKey").is_not_found());
+    assert!(c("InvalidInstanceID.NotFound").is_not_found());
+    assert!(c("ResourceNotFoundException").is_not_found());
+    assert!(c("InvalidParameterValue").is_validation());
+    assert!(!c("InvalidParameterValue").is_retryable());
+    assert!(c("ValidationError").is_validation());
+    assert!(classify(
+        String::from("Stack with id test does not exist"),
+        Some("ValidationError"),
+        false
+    )
+    .is_not_found());
+    assert!(matches!(c("InternalFailure"), Error::API { .. }));
+    assert!(c("KMSInternalException").is_retryable());
+    assert!(!c("InternalFailure").is_retryable());
+    assert!(classify(String::from("test"), None, true).is_retryable());
+
+    assert_eq!(c("Throttling").retry_after(1), Some(Duration::from_secs(2)));
+    assert_eq!(
+        c("Throttling").retry_after(10),
+        Some(Duration::from_secs(60))
+    );
+    assert_eq!(c("AccessDenied").retry_after(1), None);
+
+    let e: io::Error = c("NoSuchBucket").into();
+    assert_eq!(e.kind(), ErrorKind::NotFound);
 }
@@ -0,0 +1,183 @@
+use std::io::{self, Error, ErrorKind};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    network_snapshot,
+    utils::{compress, rfc3339},
+    Spec, StorageNamespace,
+};
+
+/// Represents the node keys replicated by "failover --prepare"
+/// to the replica bucket, re-encrypted with the KMS key in the replica region,
+/// so the stand-by cluster restores them without the primary region.
+/// Written last once all node slots are copied.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Manifest {
+    pub cluster_id: String,
+    pub region: String,
+    #[serde(with = "rfc3339::serde_format")]
+    pub created_at: DateTime<Utc>,
+    /// KMS key in the replica region that encrypted the node keys.
+    pub kms_key_arn: String,
+    /// Node slot names (e.g., "anchor-0", "non-anchor-1").
+    pub slots: Vec<String>,
+}
+
+impl Manifest {
+    pub fn encode_yaml(&self) -> io::Result<String> {
+        serde_yaml::to_string(self).map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to serialize failover manifest to YAML {}", e),
+            )
+        })
+    }
+
+    pub fn decode_yaml(d: &[u8]) -> io::Result<Self> {
+        serde_yaml::from_slice(d).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid failover manifest {}", e),
+            )
+        })
+    }
+}
+
+pub fn dir(cluster_id: &str) -> String {
+    StorageNamespace::FailoverDir(cluster_id.to_string()).encode()
+}
+
+/// Returns the S3 key of the latest database backup of "avalanched backup upload",
+/// which uploads to the replica bucket with the same key.
+pub fn latest_backup_key(cluster_id: &str) -> String {
+    format!(
+        "{}/backup{}",
+        StorageNamespace::BackupsDir(cluster_id.to_string()).encode(),
+        compress::DirEncoder::TarGzip.ext()
+    )
+}
+
+/// Checks the primary spec replicates to the region to fail over to.
+pub fn validate_primary(primary: &Spec, to_region: &str) -> io::Result<()> {
+    let aws_resources = primary.aws_resources.clone().unwrap_or_default();
+    if aws_resources.region == to_region {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("'{}' is the primary region", to_region),
+        ));
+    }
+    if aws_resources.replica_s3_region.as_deref() != Some(to_region)
+        || aws_resources.replica_s3_bucket.is_none()
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "no backups replicated to '{}' (set 'aws_resources.replica_s3_region' and 'replica_s3_bucket', and 'apply')",
+                to_region
+            ),
+        ));
+    }
+    // the validators keep the node IDs only with the node keys by slot
+    if primary.static_node_keys != Some(true) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "'failover' requires 'static_node_keys'",
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the spec of the stand-by cluster in the replica region,
+/// with the same cluster ID (and node slots), restoring the node keys of
+/// "failover --prepare" and the database from the replica bucket.
+/// "db_backup_key" is the replicated backup to restore (see "latest_backup_key").
+pub fn standby_spec(
+    primary: &Spec,
+    to_region: &str,
+    kms_key_arn: &str,
+    db_backup_key: &str,
+) -> io::Result<Spec> {
+    validate_primary(primary, to_region)?;
+    let replica_s3_bucket = primary
+        .aws_resources
+        .clone()
+        .unwrap_or_default()
+        .replica_s3_bucket
+        .unwrap();
+
+    let source = network_snapshot::Source {
+        region: to_region.to_string(),
+        s3_bucket: replica_s3_bucket.clone(),
+        s3_prefix: dir(&primary.id),
+        kms_key_arn: kms_key_arn.to_string(),
+    };
+    // the new bucket, since the replica bucket has the primary spec state
+    let mut spec = network_snapshot::restored_spec(primary, &primary.id, to_region, source);
+    let mut aws_resources = spec.aws_resources.clone().unwrap();
+    aws_resources.db_backup_s3_region = Some(to_region.to_string());
+    aws_resources.db_backup_s3_bucket = Some(replica_s3_bucket);
+    aws_resources.db_backup_s3_key = Some(db_backup_key.to_string());
+    aws_resources.db_backup_s3_mirrors = None;
+    spec.aws_resources = Some(aws_resources);
+    Ok(spec)
+}
+
+#[test]
+fn test_failover() {
+    use crate::avalanche::{
+        avalanchego::config as avalanchego_config, coreth::config as coreth_config,
+    };
+
+    let mut avalanchego_config = avalanchego_config::Config::default();
+    avalanchego_config.network_id = 1;
+    avalanchego_config.genesis = None;
+    let mut primary: Spec = serde_json::from_value(serde_json::json!({
+        "id": "my-cluster",
+        "aws_resources": { "region": "us-west-2", "s3_bucket": "primary-bucket" },
+        "machine": { "non_anchor_nodes": 2 },
+        "install_artifacts": { "avalanched_bin": "a", "avalanchego_bin": "b" },
+        "avalanchego_config": avalanchego_config,
+        "coreth_config": coreth_config::Config::default(),
+    }))
+    .unwrap();
+    let mut aws_resources = primary.aws_resources.clone().unwrap();
+
+    assert!(validate_primary(&primary, "us-east-1").is_err());
+    aws_resources.replica_s3_region = Some(String::from("us-east-1"));
+    aws_resources.replica_s3_bucket = Some(String::from("replica-bucket"));
+    primary.aws_resources = Some(aws_resources);
+    assert!(validate_primary(&primary, "us-east-1").is_err());
+    primary.static_node_keys = Some(true);
+    assert!(validate_primary(&primary, "us-east-1").is_ok());
+    assert!(validate_primary(&primary, "us-west-2").is_err());
+    assert!(validate_primary(&primary, "eu-west-1").is_err());
+
+    assert_eq!(
+        latest_backup_key("my-cluster"),
+        "my-cluster/backups/backup.tar.gz"
+    );
+    let standby = standby_spec(
+        &primary,
+        "us-east-1",
+        "arn:aws:kms:us-east-1:123:key/abc",
+        &latest_backup_key("my-cluster"),
+    )
+    .unwrap();
+    assert_eq!(standby.id, "my-cluster");
+    let standby_resources = standby.aws_resources.clone().unwrap();
+    assert_eq!(standby_resources.region, "us-east-1");
+    assert_ne!(standby_resources.s3_bucket, "replica-bucket");
+    assert_eq!(
+        standby_resources.db_backup_s3_bucket.as_deref(),
+        Some("replica-bucket")
+    );
+    let source = standby.restore_from_snapshot.unwrap();
+    assert_eq!(source.s3_url(), "s3://replica-bucket/my-cluster/failover");
+    assert_eq!(
+        network_snapshot::node_keys(&source.s3_prefix, "anchor-0").tls_cert,
+        "my-cluster/failover/pki/anchor-0.crt"
+    );
+}
@@ -0,0 +1,121 @@
+use std::{
+    env,
+    io::{self, Error, ErrorKind},
+};
+
+/// Environment variable to enable the FIPS mode at runtime,
+/// in addition to the "fips" build feature.
+pub const ENV_FIPS: &str = "AVALANCHE_OPS_FIPS";
+
+/// Regions with the FIPS endpoints for both KMS and STS.
+/// ref. https://aws.amazon.com/compliance/fips/
+pub const REGIONS: [&str; 6] = [
+    "us-east-1",
+    "us-east-2",
+    "us-west-1",
+    "us-west-2",
+    "us-gov-east-1",
+    "us-gov-west-1",
+];
+
+/// Returns true if built with the "fips" feature,
+/// or "AVALANCHE_OPS_FIPS" is set to "true" (or "1").
+/// In the FIPS mode, KMS and STS calls go to the FIPS endpoints,
+/// and the envelope encryption only uses the KMS-generated data keys
+/// (no passphrase-derived keys).
+pub fn enabled() -> bool {
+    if cfg!(feature = "fips") {
+        return true;
+    }
+    match env::var(ENV_FIPS) {
+        Ok(v) => is_true(&v),
+        Err(_) => false,
+    }
+}
+
+fn is_true(v: &str) -> bool {
+    matches!(v.trim().to_lowercase().as_str(), "true" | "1")
+}
+
+/// Fails if the region has no FIPS endpoints.
+pub fn validate_region(region: &str) -> io::Result<()> {
+    if !REGIONS.contains(&region) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "region '{}' has no FIPS endpoints (expected one of {:?})",
+                region, REGIONS
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Fails if the mode recorded in "aws_resources.fips" differs from the
+/// current mode, so a cluster created in the FIPS mode is never updated
+/// with the standard endpoints (or vice versa).
+/// None if the cluster has not been applied yet.
+pub fn validate_recorded(recorded: Option<bool>) -> io::Result<()> {
+    validate_mode(recorded, enabled())
+}
+
+fn validate_mode(recorded: Option<bool>, current: bool) -> io::Result<()> {
+    match recorded {
+        Some(v) if v != current => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "cluster was created with FIPS mode {} but currently {} (set '{}={}' or build with the matching 'fips' feature)",
+                on_off(v),
+                on_off(current),
+                ENV_FIPS,
+                v
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn on_off(v: bool) -> &'static str {
+    if v {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// ref. https://docs.aws.amazon.com/general/latest/gr/kms.html
+pub fn kms_endpoint(region: &str) -> String {
+    format!("https://kms-fips.{}.amazonaws.com", region)
+}
+
+/// ref. https://docs.aws.amazon.com/general/latest/gr/sts.html
+pub fn sts_endpoint(region: &str) -> String {
+    format!("https://sts-fips.{}.amazonaws.com", region)
+}
+
+#[test]
+fn test_fips() {
+    assert!(is_true("true"));
+    assert!(is_true(" TRUE "));
+    assert!(is_true("1"));
+    assert!(!is_true("false"));
+    assert!(!is_true(""));
+
+    assert!(validate_region("us-gov-west-1").is_ok());
+    assert!(validate_region("eu-west-1").is_err());
+
+    assert!(validate_mode(None, true).is_ok());
+    assert!(validate_mode(Some(true), true).is_ok());
+    assert!(validate_mode(Some(false), false).is_ok());
+    assert!(validate_mode(Some(true), false).is_err());
+    assert!(validate_mode(Some(false), true).is_err());
+
+    assert_eq!(
+        kms_endpoint("us-gov-west-1"),
+        "https://kms-fips.us-gov-west-1.amazonaws.com"
+    );
+    assert_eq!(
+        sts_endpoint("us-east-1"),
+        "https://sts-fips.us-east-1.amazonaws.com"
+    );
+}
@@ -0,0 +1,427 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Error, ErrorKind},
+    sync::Arc,
+};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    avalanche::{coreth::tx, ids, key},
+    aws::{envelope, s3},
+    Spec, StorageNamespace, PREFUNDED_KEY_PATH_PREFIX,
+};
+
+/// "NodeKind" of the relayer ASG, and the "NODE_KIND" tag that "avalanched" runs the relayer on.
+pub const NODE_KIND: &str = "icm-relayer";
+
+pub const BIN_PATH: &str = "/usr/local/bin/icm-relayer";
+pub const CONFIG_FILE_PATH: &str = "/etc/icm-relayer/config.json";
+pub const LOG_FILE_PATH: &str = "/var/log/icm-relayer/icm-relayer.log";
+
+/// Default port of the relayer "/health" API.
+pub const DEFAULT_API_PORT: u16 = 8080;
+/// Default port of the relayer Prometheus "/metrics".
+pub const DEFAULT_METRICS_PORT: u16 = 9090;
+/// The relayer only keeps the processed block heights.
+pub const DEFAULT_VOLUME_SIZE_IN_GB: u32 = 20;
+
+const LOG_LEVELS: [&str; 4] = ["debug", "info", "warn", "error"];
+
+/// Represents the node group that runs the ICM (Teleporter) relayer between
+/// the subnet-evm blockchains, with the same AMI, artifact upgrades, and
+/// CloudWatch logs and alarms as the avalanche nodes.
+/// The relayer reads the blockchains through the cluster endpoint
+/// ("endpoints.http_rpc"), so "apply" creates it after the nodes.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct IcmRelayer {
+    /// Blockchains to relay the messages from.
+    pub source_blockchains: Vec<Blockchain>,
+    /// Blockchains to deliver the messages to, where the relayer key pays the gas.
+    pub destination_blockchains: Vec<Blockchain>,
+    /// TeleporterMessenger address (same on all blockchains, see "teleporter deploy").
+    pub teleporter_address: String,
+    /// "prefunded:N" for the N-th prefunded key in the local spec (funded in the
+    /// subnet-evm genesis), otherwise the key file (CB58 "PrivateKey-" or hex)
+    /// funded on the destination blockchains. Only the sealed copy in S3
+    /// reaches the relayer instances.
+    pub private_key_path: String,
+    /// Address for the delivery rewards. None to the relayer key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reward_address: Option<String>,
+    /// None for "info".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<String>,
+    /// None for "DEFAULT_API_PORT".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_port: Option<u16>,
+    /// None for "DEFAULT_METRICS_PORT".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics_port: Option<u16>,
+    /// None for "DEFAULT_VOLUME_SIZE_IN_GB".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume_size_in_gb: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Blockchain {
+    pub subnet_id: String,
+    pub blockchain_id: String,
+}
+
+impl IcmRelayer {
+    pub fn api_port(&self) -> u16 {
+        self.api_port.unwrap_or(DEFAULT_API_PORT)
+    }
+
+    pub fn metrics_port(&self) -> u16 {
+        self.metrics_port.unwrap_or(DEFAULT_METRICS_PORT)
+    }
+
+    pub fn volume_size_in_gb(&self) -> u32 {
+        self.volume_size_in_gb.unwrap_or(DEFAULT_VOLUME_SIZE_IN_GB)
+    }
+
+    pub fn validate(&self) -> io::Result<()> {
+        if self.source_blockchains.is_empty() || self.destination_blockchains.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'icm_relayer' requires both source and destination blockchains",
+            ));
+        }
+        for b in self
+            .source_blockchains
+            .iter()
+            .chain(self.destination_blockchains.iter())
+        {
+            ids::parse_id(&b.subnet_id)?;
+            ids::parse_id(&b.blockchain_id)?;
+        }
+        tx::parse_address(&self.teleporter_address)?;
+        if let Some(v) = &self.reward_address {
+            tx::parse_address(v)?;
+        }
+        if self.private_key_path.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "empty 'icm_relayer.private_key_path'",
+            ));
+        }
+        if let Some(v) = &self.log_level {
+            if !LOG_LEVELS.contains(&v.as_str()) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "unknown 'icm_relayer.log_level' '{}' (expected {:?})",
+                        v, LOG_LEVELS
+                    ),
+                ));
+            }
+        }
+        if self.api_port() == self.metrics_port() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'icm_relayer.api_port' conflicts with 'metrics_port' ({})",
+                    self.api_port()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Loads the relayer key on the CLI, where the local spec has the prefunded keys.
+    pub fn load_key(&self, spec: &Spec) -> io::Result<key::Key> {
+        if let Some(index) = self
+            .private_key_path
+            .strip_prefix(PREFUNDED_KEY_PATH_PREFIX)
+        {
+            let index = index.parse::<usize>().map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid prefunded key index '{}' ({})", index, e),
+                )
+            })?;
+            return spec.prefunded_key(index);
+        }
+        let contents = fs::read_to_string(&self.private_key_path)?;
+        let contents = contents.trim();
+        if contents.starts_with(key::PRIVATE_KEY_ENCODE_PREFIX) {
+            key::Key::from_private_key(contents)
+        } else {
+            key::Key::from_private_key_hex(contents)
+        }
+    }
+
+    /// Returns the relayer configuration against the cluster endpoint
+    /// (e.g., "http://[NLB_DNS]:9650").
+    pub fn config(
+        &self,
+        http_rpc: &str,
+        private_key_hex: &str,
+        eth_address: &str,
+        storage_location: &str,
+    ) -> Config {
+        let ws_rpc = if let Some(v) = http_rpc.strip_prefix("https://") {
+            format!("wss://{}", v)
+        } else {
+            http_rpc.replacen("http://", "ws://", 1)
+        };
+        let reward_address = self
+            .reward_address
+            .clone()
+            .unwrap_or_else(|| eth_address.to_string());
+
+        let mut source_blockchains = Vec::new();
+        for b in self.source_blockchains.iter() {
+            let mut message_contracts = BTreeMap::new();
+            message_contracts.insert(
+                self.teleporter_address.clone(),
+                MessageContract {
+                    message_format: String::from("teleporter"),
+                    settings: MessageContractSettings {
+                        reward_address: reward_address.clone(),
+                    },
+                },
+            );
+            source_blockchains.push(SourceBlockchain {
+                subnet_id: b.subnet_id.clone(),
+                blockchain_id: b.blockchain_id.clone(),
+                vm: String::from("evm"),
+                rpc_endpoint: Endpoint {
+                    base_url: format!("{}/ext/bc/{}/rpc", http_rpc, b.blockchain_id),
+                },
+                ws_endpoint: Endpoint {
+                    base_url: format!("{}/ext/bc/{}/ws", ws_rpc, b.blockchain_id),
+                },
+                message_contracts,
+            });
+        }
+        let destination_blockchains = self
+            .destination_blockchains
+            .iter()
+            .map(|b| DestinationBlockchain {
+                subnet_id: b.subnet_id.clone(),
+                blockchain_id: b.blockchain_id.clone(),
+                vm: String::from("evm"),
+                rpc_endpoint: Endpoint {
+                    base_url: format!("{}/ext/bc/{}/rpc", http_rpc, b.blockchain_id),
+                },
+                account_private_key: private_key_hex.to_string(),
+            })
+            .collect();
+
+        Config {
+            log_level: self
+                .log_level
+                .clone()
+                .unwrap_or_else(|| String::from("info")),
+            storage_location: storage_location.to_string(),
+            p_chain_api: Endpoint {
+                base_url: http_rpc.to_string(),
+            },
+            info_api: Endpoint {
+                base_url: http_rpc.to_string(),
+            },
+            source_blockchains,
+            destination_blockchains,
+            api_port: self.api_port(),
+            metrics_port: self.metrics_port(),
+        }
+    }
+}
+
+/// Represents the relayer configuration file.
+/// No "Debug" as it has the private key.
+/// ref. https://github.com/ava-labs/icm-services/blob/main/relayer/README.md
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    pub log_level: String,
+    pub storage_location: String,
+    pub p_chain_api: Endpoint,
+    pub info_api: Endpoint,
+    pub source_blockchains: Vec<SourceBlockchain>,
+    pub destination_blockchains: Vec<DestinationBlockchain>,
+    pub api_port: u16,
+    pub metrics_port: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Endpoint {
+    pub base_url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct SourceBlockchain {
+    pub subnet_id: String,
+    pub blockchain_id: String,
+    pub vm: String,
+    pub rpc_endpoint: Endpoint,
+    pub ws_endpoint: Endpoint,
+    pub message_contracts: BTreeMap<String, MessageContract>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct MessageContract {
+    pub message_format: String,
+    pub settings: MessageContractSettings,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct MessageContractSettings {
+    pub reward_address: String,
+}
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct DestinationBlockchain {
+    pub subnet_id: String,
+    pub blockchain_id: String,
+    pub vm: String,
+    pub rpc_endpoint: Endpoint,
+    pub account_private_key: String,
+}
+
+impl Config {
+    pub fn encode_json(&self) -> io::Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to serialize relayer config {}", e),
+            )
+        })
+    }
+}
+
+/// Envelope-encrypts the relayer private key (hex) and uploads
+/// to "StorageNamespace::IcmRelayerKeyEncrypted".
+pub async fn put_key(
+    s3_manager: &s3::Manager,
+    envelope: &envelope::Envelope,
+    s3_bucket: &str,
+    id: &str,
+    private_key_hex: &str,
+) -> io::Result<()> {
+    let sealed = envelope
+        .seal_aes_256(private_key_hex.as_bytes())
+        .await
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to seal ICM relayer key ({})", e.message()),
+            )
+        })?;
+    info!("uploading encrypted ICM relayer key");
+    s3_manager
+        .put_bytes(
+            sealed,
+            Arc::new(s3_bucket.to_string()),
+            Arc::new(StorageNamespace::IcmRelayerKeyEncrypted(id.to_string()).encode()),
+        )
+        .await
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to upload ICM relayer key ({})", e.message()),
+            )
+        })
+}
+
+/// Downloads and decrypts the relayer key.
+pub async fn get_key(
+    s3_manager: &s3::Manager,
+    envelope: &envelope::Envelope,
+    s3_bucket: &str,
+    id: &str,
+) -> io::Result<key::Key> {
+    let sealed = s3_manager
+        .get_bytes(
+            s3_bucket,
+            &StorageNamespace::IcmRelayerKeyEncrypted(id.to_string()).encode(),
+            None,
+        )
+        .await
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to download ICM relayer key ({})", e.message()),
+            )
+        })?
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, "ICM relayer key not found"))?;
+    let d = envelope.unseal_aes_256(&sealed).await.map_err(|e| {
+        Error::new(
+            ErrorKind::Other,
+            format!("failed to unseal ICM relayer key ({})", e.message()),
+        )
+    })?;
+    let private_key_hex = String::from_utf8(d).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid ICM relayer key {}", e),
+        )
+    })?;
+    key::Key::from_private_key_hex(&private_key_hex)
+}
+
+#[test]
+fn test_config() {
+    let b1 = Blockchain {
+        subnet_id: String::from("fChyujj4uLUnXo18BhCmBQSFRBEYbvZRaRzMJRtWfsGroLHS6"),
+        blockchain_id: String::from("gKKjQpdeyzP4c6BK2Mpg3ogJRXNat9osZhWFXL2AgjH3Gqnzr"),
+    };
+    let b2 = Blockchain {
+        subnet_id: String::from("2E9Y6n7aC1ppSSdKa9h1w9h3LVdkt6VQzkxVDLUJPVKRDhUC3o"),
+        blockchain_id: String::from("axczPtsqZ6u2G6hBdNiXmkVKpfZZ9r4oP9KZWapCy8gAotBn6"),
+    };
+    let mut relayer = IcmRelayer {
+        source_blockchains: vec![b1.clone(), b2.clone()],
+        destination_blockchains: vec![b1, b2],
+        teleporter_address: String::from("0x253b2784c75e510dD0fF1da844684a1aC0aa5fcf"),
+        private_key_path: String::from("prefunded:0"),
+        reward_address: None,
+        log_level: None,
+        api_port: None,
+        metrics_port: None,
+        volume_size_in_gb: None,
+    };
+    relayer.validate().expect("unexpected validate failure");
+
+    let cfg = relayer.config(
+        "https://my-nlb.elb.amazonaws.com:443",
+        "56289e99c94b6912bfc12adc093c9b51124f0dc54ac7a766b2bc5ccf558d8027",
+        "0x8db97C7cEcE249c2b98bDC0226Cc4C2A57BF52FC",
+        "/avalanche-data/icm-relayer",
+    );
+    assert_eq!(cfg.source_blockchains.len(), 2);
+    assert_eq!(
+        cfg.source_blockchains[0].ws_endpoint.base_url,
+        "wss://my-nlb.elb.amazonaws.com:443/ext/bc/gKKjQpdeyzP4c6BK2Mpg3ogJRXNat9osZhWFXL2AgjH3Gqnzr/ws"
+    );
+    assert_eq!(
+        cfg.destination_blockchains[1].rpc_endpoint.base_url,
+        "https://my-nlb.elb.amazonaws.com:443/ext/bc/axczPtsqZ6u2G6hBdNiXmkVKpfZZ9r4oP9KZWapCy8gAotBn6/rpc"
+    );
+    let encoded = cfg.encode_json().unwrap();
+    assert!(encoded.contains("\"message-format\": \"teleporter\""));
+    assert!(encoded.contains("\"reward-address\": \"0x8db97C7cEcE249c2b98bDC0226Cc4C2A57BF52FC\""));
+    assert!(encoded.contains("\"account-private-key\""));
+
+    relayer.metrics_port = Some(DEFAULT_API_PORT);
+    assert!(relayer.validate().is_err());
+    relayer.metrics_port = None;
+    relayer.destination_blockchains = Vec::new();
+    assert!(relayer.validate().is_err());
+    assert_eq!(
+        StorageNamespace::IcmRelayerKeyEncrypted(String::from("test")).encode(),
+        "test/icm-relayer-key.seal_aes_256.encrypted"
+    );
+}
@@ -0,0 +1,130 @@
+use std::io::{self, Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+use crate::avalanche::{avalanchego::config as avalanchego_config, node};
+
+/// Local file that "avalanched" creates once avalanchego runs with the index,
+/// to tell the database bootstrapped with the index from the one without.
+pub const INDEXED_DB_FILE_PATH: &str = "/var/lib/avalanched/indexed-db";
+
+/// Default extra disk for the index-enabled nodes on the mainnet, in GiB.
+pub const DEFAULT_MAINNET_EXTRA_VOLUME_SIZE_IN_GB: u32 = 400;
+/// Default extra disk for the index-enabled nodes on the other networks, in GiB.
+pub const DEFAULT_EXTRA_VOLUME_SIZE_IN_GB: u32 = 100;
+
+/// Max size of the root disk with the extra disk for the index, in GiB.
+/// ref. "VolumeSize" in "cfn-templates/avalanche-node/asg_amd64_ubuntu.yaml"
+pub const MAX_VOLUME_SIZE_IN_GB: u32 = 16384;
+
+/// Represents the avalanchego index API ("index-enabled") on the node classes.
+/// By default, only the API nodes (non-anchor nodes) enable the index.
+/// The index must be backfilled from the genesis, so the index-enabled nodes
+/// do not restore the database backups or snapshots taken without the index.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Indexer {
+    /// Default "false".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor_nodes: Option<bool>,
+    /// Default "true".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub non_anchor_nodes: Option<bool>,
+    /// "index-allow-incomplete", to enable the index on the database without
+    /// (e.g., the database backup), where the index misses the earlier blocks.
+    #[serde(default)]
+    pub allow_incomplete: bool,
+    /// Extra disk for the index-enabled nodes, added to "machine.volume_size_in_gb".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_volume_size_in_gb: Option<u32>,
+}
+
+impl Indexer {
+    pub fn is_enabled(&self, kind: &node::Kind) -> bool {
+        match kind {
+            node::Kind::Anchor => self.anchor_nodes.unwrap_or(false),
+            node::Kind::NonAnchor => self.non_anchor_nodes.unwrap_or(true),
+        }
+    }
+
+    /// Overwrites the index configuration of the node class.
+    pub fn apply(&self, kind: &node::Kind, avalanchego_config: &mut avalanchego_config::Config) {
+        let enabled = self.is_enabled(kind);
+        avalanchego_config.index_enabled = Some(enabled);
+        avalanchego_config.index_allow_incomplete = Some(enabled && self.allow_incomplete);
+    }
+
+    /// Returns true if the node class may start from the database backup
+    /// or snapshot, which is not indexed.
+    pub fn restores_db(&self, kind: &node::Kind) -> bool {
+        !self.is_enabled(kind) || self.allow_incomplete
+    }
+
+    pub fn extra_volume_size_in_gb(&self, is_mainnet: bool) -> u32 {
+        self.extra_volume_size_in_gb.unwrap_or(if is_mainnet {
+            DEFAULT_MAINNET_EXTRA_VOLUME_SIZE_IN_GB
+        } else {
+            DEFAULT_EXTRA_VOLUME_SIZE_IN_GB
+        })
+    }
+
+    /// Returns the root disk size of the node class, in GiB.
+    pub fn volume_size_in_gb(&self, kind: &node::Kind, base: u32, is_mainnet: bool) -> u32 {
+        if self.is_enabled(kind) {
+            base + self.extra_volume_size_in_gb(is_mainnet)
+        } else {
+            base
+        }
+    }
+
+    pub fn validate(&self, has_anchor_nodes: bool) -> io::Result<()> {
+        let enabled = self.is_enabled(&node::Kind::NonAnchor)
+            || has_anchor_nodes && self.is_enabled(&node::Kind::Anchor);
+        if !enabled {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'indexer' enables the index on no node class",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_indexer() {
+    let indexer = Indexer::default();
+    assert!(!indexer.is_enabled(&node::Kind::Anchor));
+    assert!(indexer.is_enabled(&node::Kind::NonAnchor));
+    assert!(indexer.restores_db(&node::Kind::Anchor));
+    assert!(!indexer.restores_db(&node::Kind::NonAnchor));
+    assert_eq!(
+        indexer.volume_size_in_gb(&node::Kind::NonAnchor, 800, true),
+        1200
+    );
+    assert_eq!(
+        indexer.volume_size_in_gb(&node::Kind::Anchor, 800, true),
+        800
+    );
+    assert!(indexer.validate(true).is_ok());
+
+    let mut config = avalanchego_config::Config::default();
+    indexer.apply(&node::Kind::NonAnchor, &mut config);
+    assert_eq!(config.index_enabled, Some(true));
+    assert_eq!(config.index_allow_incomplete, Some(false));
+    indexer.apply(&node::Kind::Anchor, &mut config);
+    assert_eq!(config.index_enabled, Some(false));
+
+    let incomplete = Indexer {
+        allow_incomplete: true,
+        ..Default::default()
+    };
+    assert!(incomplete.restores_db(&node::Kind::NonAnchor));
+    incomplete.apply(&node::Kind::NonAnchor, &mut config);
+    assert_eq!(config.index_allow_incomplete, Some(true));
+
+    let none = Indexer {
+        non_anchor_nodes: Some(false),
+        ..Default::default()
+    };
+    assert!(none.validate(true).is_err());
+}
@@ -0,0 +1,214 @@
+use std::io::{self, Error, ErrorKind};
+
+use crate::{avalanche::constants, Spec};
+
+/// Minimum volume size to join mainnet with the state sync or
+/// the pruned database backup, with headroom for the growth.
+pub const MIN_MAINNET_VOLUME_SIZE_IN_GB: u32 = 500;
+/// Minimum volume size to join Fuji.
+pub const MIN_FUJI_VOLUME_SIZE_IN_GB: u32 = 200;
+/// ref. "VolumeSize" in "cfn-templates/avalanche-node/asg_amd64_ubuntu.yaml"
+pub const DEFAULT_VOLUME_SIZE_IN_GB: u32 = 400;
+
+/// Represents a spec problem that would only surface during the bootstrap.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Finding {
+    pub field: &'static str,
+    pub problem: String,
+    pub fix: String,
+}
+
+/// Checks the spec that joins mainnet or Fuji (no anchor nodes), so the bad
+/// configurations fail before "apply" instead of an hour into the bootstrap.
+/// Returns no finding for the other networks.
+pub fn check(spec: &Spec) -> Vec<Finding> {
+    let network_id = spec.avalanchego_config.network_id;
+    let min_volume_size_in_gb = match network_id {
+        1 => MIN_MAINNET_VOLUME_SIZE_IN_GB,
+        5 => MIN_FUJI_VOLUME_SIZE_IN_GB,
+        _ => return Vec::new(),
+    };
+    let network_name = constants::NETWORK_ID_TO_NETWORK_NAME
+        .get(&network_id)
+        .copied()
+        .unwrap_or_default();
+
+    let mut findings = Vec::new();
+    if spec.machine.anchor_nodes.unwrap_or(0) > 0 {
+        findings.push(Finding {
+            field: "machine.anchor_nodes",
+            problem: format!(
+                "{} anchor nodes would bootstrap a separate network",
+                spec.machine.anchor_nodes.unwrap_or(0)
+            ),
+            fix: format!(
+                "remove 'machine.anchor_nodes', the {} nodes bootstrap from the built-in beacons",
+                network_name
+            ),
+        });
+    }
+    if spec.avalanchego_genesis_template.is_some() || spec.avalanchego_config.genesis.is_some() {
+        findings.push(Finding {
+            field: "avalanchego_genesis_template",
+            problem: String::from("custom genesis is ignored by the public networks"),
+            fix: String::from(
+                "remove 'avalanchego_genesis_template' and 'avalanchego_config.genesis'",
+            ),
+        });
+    }
+    if spec.avalanchego_config.bootstrap_ips.is_some()
+        || spec.avalanchego_config.bootstrap_ids.is_some()
+    {
+        findings.push(Finding {
+            field: "avalanchego_config.bootstrap-ips",
+            problem: String::from(
+                "fixed bootstrap nodes replace the built-in beacons, and stall the bootstrap once they go away",
+            ),
+            fix: String::from(
+                "remove 'avalanchego_config.bootstrap-ips' and 'avalanchego_config.bootstrap-ids'",
+            ),
+        });
+    }
+    if spec.external_bootstrap_nodes.is_some() {
+        findings.push(Finding {
+            field: "external_bootstrap_nodes",
+            problem: String::from("external bootstrap nodes replace the built-in beacons"),
+            fix: String::from("remove 'external_bootstrap_nodes'"),
+        });
+    }
+    if spec.avalanchego_config.state_sync_ids.is_some()
+        || spec.avalanchego_config.state_sync_ips.is_some()
+    {
+        findings.push(Finding {
+            field: "avalanchego_config.state-sync-ips",
+            problem: String::from(
+                "pinned state sync peers fail the state sync once they go away or fall behind",
+            ),
+            fix: String::from(
+                "remove 'avalanchego_config.state-sync-ips' and 'avalanchego_config.state-sync-ids' to sync from the validators",
+            ),
+        });
+    }
+
+    let has_db_backup = spec
+        .aws_resources
+        .as_ref()
+        .map(|r| r.db_backup_s3_bucket.is_some())
+        .unwrap_or(false);
+    // "state-sync-enabled: false" explicitly opts into the bootstrap from genesis
+    if spec.coreth_config.state_sync_enabled.is_none() && !has_db_backup {
+        findings.push(Finding {
+            field: "coreth_config.state-sync-enabled",
+            problem: format!(
+                "neither the state sync nor the database backup is configured, bootstrapping the {} C-chain from genesis takes days",
+                network_name
+            ),
+            fix: String::from(
+                "set 'coreth_config.state-sync-enabled: true', or 'aws_resources.db_backup_s3_*' to download a database backup (or 'state-sync-enabled: false' to bootstrap from genesis anyway)",
+            ),
+        });
+    }
+
+    let volume_size_in_gb = spec
+        .machine
+        .volume_size_in_gb
+        .unwrap_or(DEFAULT_VOLUME_SIZE_IN_GB);
+    if volume_size_in_gb < min_volume_size_in_gb {
+        findings.push(Finding {
+            field: "machine.volume_size_in_gb",
+            problem: format!(
+                "{} GiB volume fills up before the {} bootstrap completes",
+                volume_size_in_gb, network_name
+            ),
+            fix: format!(
+                "set 'machine.volume_size_in_gb' to at least {}",
+                min_volume_size_in_gb
+            ),
+        });
+    }
+
+    findings
+}
+
+/// Fails with all the findings of "check".
+pub fn validate(spec: &Spec) -> io::Result<()> {
+    let findings = check(spec);
+    if findings.is_empty() {
+        return Ok(());
+    }
+    let lines: Vec<String> = findings
+        .iter()
+        .map(|f| format!("- '{}': {} (fix: {})", f.field, f.problem, f.fix))
+        .collect();
+    Err(Error::new(
+        ErrorKind::InvalidInput,
+        format!(
+            "spec cannot join network_id {} ({} problems):\n{}",
+            spec.avalanchego_config.network_id,
+            findings.len(),
+            lines.join("\n")
+        ),
+    ))
+}
+
+#[test]
+fn test_join_validation() {
+    use crate::avalanche::{
+        avalanchego::config as avalanchego_config, coreth::config as coreth_config,
+    };
+
+    let load = |network_id: u32| -> Spec {
+        let mut avalanchego_config = avalanchego_config::Config::default();
+        avalanchego_config.network_id = network_id;
+        avalanchego_config.genesis = None;
+        serde_json::from_value(serde_json::json!({
+            "id": "test",
+            "aws_resources": {"region": "us-west-2", "s3_bucket": "test"},
+            "machine": {"non_anchor_nodes": 1},
+            "install_artifacts": {
+                "avalanched_bin": "/tmp/avalanched",
+                "avalanchego_bin": "/tmp/avalanchego",
+            },
+            "avalanchego_config": avalanchego_config,
+            "coreth_config": coreth_config::Config::default(),
+        }))
+        .unwrap()
+    };
+
+    // no state sync, no database backup, 400 GiB default volume
+    let spec = load(1);
+    let fields: Vec<&str> = check(&spec).iter().map(|f| f.field).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "coreth_config.state-sync-enabled",
+            "machine.volume_size_in_gb"
+        ]
+    );
+    assert!(validate(&spec).is_err());
+
+    let mut spec = load(5);
+    spec.coreth_config.state_sync_enabled = Some(true);
+    assert!(check(&spec).is_empty());
+    assert!(validate(&spec).is_ok());
+
+    let mut spec = load(1);
+    spec.coreth_config.state_sync_enabled = Some(false);
+    spec.machine.volume_size_in_gb = Some(1000);
+    spec.machine.anchor_nodes = Some(1);
+    spec.avalanchego_config.bootstrap_ips = Some(String::from("1.2.3.4:9651"));
+    spec.avalanchego_config.state_sync_ips = Some(String::from("1.2.3.4:9651"));
+    let fields: Vec<&str> = check(&spec).iter().map(|f| f.field).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "machine.anchor_nodes",
+            "avalanchego_config.bootstrap-ips",
+            "avalanchego_config.state-sync-ips",
+        ]
+    );
+
+    // custom networks are not checked
+    let spec = load(1000);
+    assert!(check(&spec).is_empty());
+}
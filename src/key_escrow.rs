@@ -0,0 +1,368 @@
+use std::{
+    env,
+    io::{self, Error, ErrorKind},
+};
+
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aws::{self, envelope, kms},
+    node_keys,
+    utils::{compress, rfc3339},
+    StorageNamespace,
+};
+
+/// First line of the key escrow archive, followed by the protection mode.
+/// e.g., "# avalanche-ops key escrow v1 kms arn:aws:kms:us-east-1:123:key/abc"
+/// e.g., "# avalanche-ops key escrow v1 passphrase"
+pub const HEADER: &str = "# avalanche-ops key escrow v1";
+
+/// Environment variable to read the archive passphrase from.
+pub const PASSPHRASE_ENV: &str = "AVALANCHE_OPS_ESCROW_PASSPHRASE";
+
+const SEALED_KEY_EXT: &str = ".key.zstd.seal_aes_256.encrypted";
+const SEALED_SIGNER_KEY_EXT: &str = ".signer.key.zstd.seal_aes_256.encrypted";
+const SEALED_CONTROL_KEY_EXT: &str = ".seal_aes_256.encrypted";
+
+/// Defines how the archive is protected offline.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Protection {
+    /// Envelope-encrypted with the data key from the KMS key (ARN),
+    /// ideally in the other account or region than the cluster.
+    Kms(String),
+    /// Encrypted with the key derived from the "AVALANCHE_OPS_ESCROW_PASSPHRASE".
+    Passphrase,
+}
+
+impl Protection {
+    fn encode(&self) -> String {
+        match self {
+            Protection::Kms(key_arn) => format!("{} kms {}", HEADER, key_arn),
+            Protection::Passphrase => format!("{} passphrase", HEADER),
+        }
+    }
+
+    fn decode(header: &str) -> io::Result<Self> {
+        let mode = header
+            .strip_prefix(HEADER)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid key escrow header '{}'", header),
+                )
+            })?
+            .trim();
+        match mode.split_once(' ') {
+            Some(("kms", key_arn)) => Ok(Protection::Kms(key_arn.to_string())),
+            None if mode == "passphrase" => Ok(Protection::Passphrase),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown key escrow protection '{}'", mode),
+            )),
+        }
+    }
+}
+
+/// Defines the kind of the key material in the archive.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    /// Staking TLS certificate, stored in plaintext.
+    TlsCert,
+    /// Staking TLS key, compressed and envelope-encrypted in S3.
+    TlsKey,
+    /// BLS signer key, compressed and envelope-encrypted in S3.
+    SignerKey,
+    /// Subnet control key, envelope-encrypted in S3.
+    SubnetControlKey,
+}
+
+impl Kind {
+    /// Returns the kind and the name (e.g., "anchor-0", subnet ID) of the S3 key,
+    /// or "None" if the object is not the key material.
+    pub fn parse_s3_key(cluster_id: &str, s3_key: &str) -> Option<(Self, String)> {
+        let pki_dir = format!(
+            "{}/",
+            StorageNamespace::PkiKeyDir(cluster_id.to_string()).encode()
+        );
+        let control_keys_dir = format!("{}/subnet-control-keys/", cluster_id);
+        if let Some(file_name) = s3_key.strip_prefix(&pki_dir) {
+            // check the signer key first, since it shares the TLS key suffix
+            if let Some(name) = file_name.strip_suffix(SEALED_SIGNER_KEY_EXT) {
+                return Some((Kind::SignerKey, name.to_string()));
+            }
+            if let Some(name) = file_name.strip_suffix(SEALED_KEY_EXT) {
+                return Some((Kind::TlsKey, name.to_string()));
+            }
+            if let Some(name) = file_name.strip_suffix(".crt") {
+                return Some((Kind::TlsCert, name.to_string()));
+            }
+            return None;
+        }
+        if let Some(file_name) = s3_key.strip_prefix(&control_keys_dir) {
+            if let Some(name) = file_name.strip_suffix(SEALED_CONTROL_KEY_EXT) {
+                return Some((Kind::SubnetControlKey, name.to_string()));
+            }
+        }
+        None
+    }
+
+    /// Returns the S3 key of the key material in the cluster.
+    pub fn s3_key(&self, cluster_id: &str, name: &str) -> String {
+        let keys = node_keys::S3Keys::new(cluster_id, name);
+        match self {
+            Kind::TlsCert => keys.tls_cert,
+            Kind::TlsKey => keys.tls_key_encrypted,
+            Kind::SignerKey => keys.signer_key_encrypted,
+            Kind::SubnetControlKey => StorageNamespace::SubnetControlKeyEncrypted(
+                cluster_id.to_string(),
+                name.to_string(),
+            )
+            .encode(),
+        }
+    }
+
+    /// Returns "true" if the key material is per node (vs. per cluster).
+    pub fn is_node_key(&self) -> bool {
+        !matches!(self, Kind::SubnetControlKey)
+    }
+
+    /// Decrypts the S3 object into the plaintext key material.
+    pub async fn open(&self, envelope: &envelope::Envelope, d: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Kind::TlsCert => Ok(d.to_vec()),
+            Kind::TlsKey | Kind::SignerKey => {
+                let compressed = envelope
+                    .unseal_aes_256(d)
+                    .await
+                    .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+                compress::unpack(&compressed, compress::Decoder::Zstd)
+            }
+            Kind::SubnetControlKey => envelope
+                .unseal_aes_256(d)
+                .await
+                .map_err(|e| Error::new(ErrorKind::Other, e.message())),
+        }
+    }
+
+    /// Encrypts the plaintext key material into the S3 object,
+    /// in the same format as "avalanched" and "subnet upload-control-key".
+    pub async fn close(&self, envelope: &envelope::Envelope, d: &[u8]) -> io::Result<Vec<u8>> {
+        let d = match self {
+            Kind::TlsCert => return Ok(d.to_vec()),
+            Kind::TlsKey | Kind::SignerKey => compress::pack(d, compress::Encoder::Zstd(3))?,
+            Kind::SubnetControlKey => d.to_vec(),
+        };
+        envelope
+            .seal_aes_256(&d)
+            .await
+            .map_err(|e| Error::new(ErrorKind::Other, e.message()))
+    }
+}
+
+/// Represents the key material of a node or a subnet.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Entry {
+    pub kind: Kind,
+    /// Node slot (e.g., "anchor-0"), instance ID, or subnet ID.
+    pub name: String,
+    /// Hex-encoded plaintext.
+    pub data: String,
+}
+
+/// Represents all key material of a cluster, for the offline escrow.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Archive {
+    pub cluster_id: String,
+    pub network_id: u32,
+    #[serde(with = "rfc3339::serde_format")]
+    pub exported_at: DateTime<Utc>,
+    pub entries: Vec<Entry>,
+}
+
+impl Archive {
+    /// Returns the number of the entries by kind, in the order of the kinds.
+    pub fn counts(&self) -> Vec<(Kind, usize)> {
+        [
+            Kind::TlsCert,
+            Kind::TlsKey,
+            Kind::SignerKey,
+            Kind::SubnetControlKey,
+        ]
+        .iter()
+        .map(|k| (*k, self.entries.iter().filter(|e| e.kind == *k).count()))
+        .filter(|(_, n)| *n > 0)
+        .collect()
+    }
+
+    /// Returns "true" if any node key is named after the instance ID,
+    /// which the replaced instances never restore.
+    pub fn has_instance_keys(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|e| e.kind.is_node_key() && e.name.starts_with("i-"))
+    }
+}
+
+/// Encrypts the archive into the file contents, with the header line.
+pub async fn seal(protection: &Protection, archive: &Archive) -> io::Result<Vec<u8>> {
+    info!("sealing key escrow archive ({})", protection.encode());
+    let d = serde_yaml::to_string(archive)
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to encode {}", e)))?;
+    let d = compress::pack(d.as_bytes(), compress::Encoder::Zstd(3))?;
+    let ciphertext = match protection {
+        Protection::Kms(key_arn) => kms_envelope(key_arn).await?.seal_aes_256(&d).await,
+        Protection::Passphrase => envelope::seal_aes_256_passphrase(passphrase()?.as_bytes(), &d),
+    }
+    .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    Ok(format!("{}\n{}\n", protection.encode(), hex::encode(ciphertext)).into_bytes())
+}
+
+/// Decrypts the file contents into the archive.
+pub async fn unseal(d: &[u8]) -> io::Result<Archive> {
+    let d = String::from_utf8_lossy(d);
+    let mut lines = d.lines();
+    let protection = Protection::decode(lines.next().unwrap_or_default())?;
+    let ciphertext = hex::decode(lines.next().unwrap_or_default().trim()).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid key escrow archive hex {}", e),
+        )
+    })?;
+
+    info!("unsealing key escrow archive ({})", protection.encode());
+    let d = match &protection {
+        Protection::Kms(key_arn) => {
+            kms_envelope(key_arn)
+                .await?
+                .unseal_aes_256(&ciphertext)
+                .await
+        }
+        Protection::Passphrase => {
+            envelope::unseal_aes_256_passphrase(passphrase()?.as_bytes(), &ciphertext)
+        }
+    }
+    .map_err(|e| Error::new(ErrorKind::Other, e.message()))?;
+    let d = compress::unpack(&d, compress::Decoder::Zstd)?;
+    serde_yaml::from_slice(&d)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("failed to decode {}", e)))
+}
+
+/// Returns "true" if the protection of the archive is the passphrase.
+pub fn is_passphrase_protected(d: &[u8]) -> bool {
+    let d = String::from_utf8_lossy(d);
+    matches!(
+        Protection::decode(d.lines().next().unwrap_or_default()),
+        Ok(Protection::Passphrase)
+    )
+}
+
+async fn kms_envelope(key_arn: &str) -> io::Result<envelope::Envelope> {
+    // e.g., "arn:aws:kms:us-east-1:123:key/abc"
+    let region = key_arn.split(':').nth(3).map(String::from);
+    let shared_config = aws::load_config(region).await?;
+    Ok(envelope::Envelope::new(
+        Some(kms::Manager::new(&shared_config)),
+        Some(key_arn.to_string()),
+    ))
+}
+
+fn passphrase() -> io::Result<String> {
+    match env::var(PASSPHRASE_ENV) {
+        Ok(v) if !v.is_empty() => Ok(v),
+        _ => Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("'{}' is not set", PASSPHRASE_ENV),
+        )),
+    }
+}
+
+#[test]
+fn test_key_escrow() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    assert_eq!(
+        Kind::parse_s3_key("test", "test/pki/anchor-0.crt"),
+        Some((Kind::TlsCert, String::from("anchor-0")))
+    );
+    assert_eq!(
+        Kind::parse_s3_key(
+            "test",
+            "test/pki/anchor-0.signer.key.zstd.seal_aes_256.encrypted"
+        ),
+        Some((Kind::SignerKey, String::from("anchor-0")))
+    );
+    assert_eq!(
+        Kind::parse_s3_key("test", "test/pki/i-123.key.zstd.seal_aes_256.encrypted"),
+        Some((Kind::TlsKey, String::from("i-123")))
+    );
+    assert_eq!(
+        Kind::parse_s3_key(
+            "test",
+            "test/subnet-control-keys/subnet-1.seal_aes_256.encrypted"
+        ),
+        Some((Kind::SubnetControlKey, String::from("subnet-1")))
+    );
+    assert_eq!(
+        Kind::parse_s3_key("test", "test/pki/NodeID-1.pop.json"),
+        None
+    );
+    assert_eq!(Kind::parse_s3_key("test", "other/pki/anchor-0.crt"), None);
+
+    // restored into the other cluster under the same names
+    for (kind, name) in [
+        (Kind::TlsCert, "anchor-0"),
+        (Kind::TlsKey, "anchor-0"),
+        (Kind::SignerKey, "anchor-0"),
+        (Kind::SubnetControlKey, "subnet-1"),
+    ] {
+        let s3_key = kind.s3_key("restored", name);
+        assert_eq!(
+            Kind::parse_s3_key("restored", &s3_key),
+            Some((kind, name.to_string()))
+        );
+    }
+
+    let archive = Archive {
+        cluster_id: String::from("test"),
+        network_id: 1000,
+        exported_at: Utc::now(),
+        entries: vec![
+            Entry {
+                kind: Kind::TlsCert,
+                name: String::from("anchor-0"),
+                data: hex::encode(b"cert"),
+            },
+            Entry {
+                kind: Kind::TlsKey,
+                name: String::from("i-123"),
+                data: hex::encode(b"key"),
+            },
+        ],
+    };
+    assert_eq!(
+        archive.counts(),
+        vec![(Kind::TlsCert, 1), (Kind::TlsKey, 1)]
+    );
+    assert!(archive.has_instance_keys());
+
+    env::set_var(PASSPHRASE_ENV, "secret");
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let sealed = rt
+        .block_on(seal(&Protection::Passphrase, &archive))
+        .unwrap();
+    assert!(sealed.starts_with(HEADER.as_bytes()));
+    assert!(is_passphrase_protected(&sealed));
+
+    // "exported_at" is encoded in milliseconds
+    let unsealed = rt.block_on(unseal(&sealed)).unwrap();
+    assert_eq!(unsealed.entries, archive.entries);
+    assert_eq!(unsealed.cluster_id, archive.cluster_id);
+
+    env::set_var(PASSPHRASE_ENV, "wrong");
+    assert!(rt.block_on(unseal(&sealed)).is_err());
+}
@@ -2,37 +2,180 @@ use std::{
     collections::BTreeMap,
     fs::{self, File},
     io::{self, Error, ErrorKind, Write},
+    net::SocketAddr,
     path::Path,
     string::String,
 };
 
-use log::info;
+use chrono::{DateTime, FixedOffset, Timelike, Utc};
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
 
 pub mod errors;
 
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod agent_metrics;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod api_auth;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod api_exposure;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod audit;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod auto_delete;
+
 /// ref. https://doc.rust-lang.org/reference/items/modules.html
 pub mod aws;
 
 /// ref. https://doc.rust-lang.org/reference/items/modules.html
 pub mod utils;
-use crate::utils::{id, prefix, random, time};
+use crate::utils::{cron, id, prefix, provenance, random, time};
 
 /// ref. https://doc.rust-lang.org/reference/items/modules.html
 pub mod avalanche;
 use crate::avalanche::{
-    avalanchego::{config as avalanchego_config, genesis as avalanchego_genesis},
+    avalanchego::{config as avalanchego_config, genesis as avalanchego_genesis, release, upgrade},
     constants,
     coreth::config as coreth_config,
     key, node,
+    platformvm::txs,
     subnet_evm::genesis as subnet_evm_genesis,
 };
 
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod canary;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod ci_preview;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod config_overrides;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod cost;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod db_check;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod delegation;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod deployer;
+pub use deployer::Deployer;
+
 /// ref. https://doc.rust-lang.org/reference/items/modules.html
 pub mod dev;
 
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod failover;
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod fips;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod icm_relayer;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod indexer;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod join_validation;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod key_escrow;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod metric_schema;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod network_params;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod network_tuning;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod network_snapshot;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod network_shutdown;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod node_keys;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod os_hardening;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod otlp;
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod peer_allowlist;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod preflight;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod provider;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod public_snapshot;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod remediation;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod rewards;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod rpc_usage;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod smoke_test;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod spec_diff;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod spec_encryption;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod spec_profile;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod spec_resolver;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod spec_state;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod ssh_session;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod support_bundle;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod time_sync;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod timeline;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod usage;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod validator_expiry;
+
+/// ref. https://doc.rust-lang.org/reference/items/modules.html
+pub mod vm_build;
+
 pub const DEFAULT_KEYS_TO_GENERATE: usize = 5;
 
+/// Refuses to restart the nodes holding more than 20% of the stake at once.
+pub const DEFAULT_MAX_OFFLINE_STAKE_PERCENT: u32 = 20;
+
 /// Default machine anchor nodes size.
 /// only required for custom networks
 pub const DEFAULT_MACHINE_ANCHOR_NODES: u32 = 2;
@@ -44,6 +187,11 @@ pub const DEFAULT_MACHINE_NON_ANCHOR_NODES: u32 = 2;
 pub const MIN_MACHINE_NON_ANCHOR_NODES: u32 = 1;
 pub const MAX_MACHINE_NON_ANCHOR_NODES: u32 = 200; // TODO: allow higher number?
 
+/// ref. "VolumeSize" in "cfn-templates/avalanche-node/asg_amd64_ubuntu.yaml"
+pub const DEFAULT_MACHINE_VOLUME_SIZE_IN_GB: u32 = 400;
+pub const MIN_MACHINE_VOLUME_SIZE_IN_GB: u32 = 40;
+pub const MAX_MACHINE_VOLUME_SIZE_IN_GB: u32 = 1024;
+
 /// Represents network-level configuration shared among all nodes.
 /// The node-level configuration is generated during each
 /// bootstrap process (e.g., certificates) and not defined
@@ -89,6 +237,123 @@ pub struct Spec {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub subnet_evm_genesis: Option<subnet_evm_genesis::Genesis>,
 
+    /// Subnets to track, with their peer allowlists.
+    /// Useful to validate a private subnet on the existing public
+    /// networks (e.g., Fuji, mainnet).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracked_subnets: Option<Vec<TrackedSubnet>>,
+
+    /// Elastic (permissionless) subnet parameters and its staking state.
+    /// Updated via "elastic-subnet" commands.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elastic_subnet: Option<ElasticSubnet>,
+
+    /// Bootstrap nodes that are not managed by this tool
+    /// (e.g., partner-operated nodes in a custom network).
+    /// Merged into the bootstrap IPs/IDs that "avalanched" generates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_bootstrap_nodes: Option<Vec<BootstrapNode>>,
+
+    /// Chain upgrade files (e.g., subnet-evm precompile activations)
+    /// to be placed in "[chain_config_dir]/[CHAIN_ID]/upgrade.json".
+    /// Distributed via "events update-chain-upgrades" for the running nodes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_upgrades: Option<Vec<ChainUpgrade>>,
+
+    /// Tracks the avalanchego GitHub releases, to stage the new binaries
+    /// via "track-release" command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avalanchego_release: Option<AvalanchegoRelease>,
+
+    /// Disruptive operations that restart the nodes (e.g., artifacts updates)
+    /// are only allowed during the window, unless forced.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maintenance_window: Option<MaintenanceWindow>,
+
+    /// Maximum percentage of the primary network stake that can be
+    /// offline at the same time, from the operations that restart nodes.
+    /// Defaults to "DEFAULT_MAX_OFFLINE_STAKE_PERCENT" if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_offline_stake_percent: Option<u32>,
+    /// Target stake of the anchor nodes, maintained by "rebalance".
+    /// Only valid for the custom network.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stake_distribution: Option<StakeDistribution>,
+    /// Staking end times of the validators, to warn in "status"
+    /// and renew with "renew-validators".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validator_expiry: Option<validator_expiry::ValidatorExpiry>,
+    /// avalanchego APIs per node class, and whether the class is
+    /// in the public NLB target group.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_exposure: Option<api_exposure::ApiExposure>,
+    /// avalanchego index API per node class, and the extra disk
+    /// for the index-enabled nodes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexer: Option<indexer::Indexer>,
+    /// avalanchego flags per node group (anchor, non-anchor, and API nodes),
+    /// applied after "api_exposure" and "indexer".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_overrides: Option<config_overrides::ConfigOverrides>,
+    /// Nodes that receive the update events first, before the rest of the fleet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canary: Option<canary::Canary>,
+    /// Health-based remediation policies of "avalanched" on each node.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<remediation::Remediation>,
+    /// Prometheus "/metrics" endpoint of "avalanched" on each node.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_metrics: Option<agent_metrics::AgentMetrics>,
+    /// OTLP export of the node logs and metrics from "avalanched",
+    /// as an alternative to CloudWatch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub otlp: Option<otlp::Otlp>,
+    /// Optional node group that relays the cross-chain (Teleporter) messages
+    /// between the subnet-evm blockchains, managed by "avalanched".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icm_relayer: Option<icm_relayer::IcmRelayer>,
+    /// Periodic pruned database snapshot published by one API node,
+    /// for the community to bootstrap the subnet nodes from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_snapshot: Option<public_snapshot::PublicSnapshot>,
+    /// NodeID-based peer allowlist of the private custom network,
+    /// pushed to all nodes when the cluster membership changes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer_allowlist: Option<peer_allowlist::PeerAllowlist>,
+    /// Set "true" to back up the TLS staking and BLS signer keys by node slot,
+    /// and restore them on the instance replacement, so the replaced nodes keep
+    /// the node IDs (and the validator registrations).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub static_node_keys: Option<bool>,
+    /// Set "true" to keep the private keys of the prefunded keys in the spec
+    /// state that the nodes read. Not recommended: any node (e.g., the public
+    /// API nodes) can then move the funds. None to keep the private keys
+    /// in the local spec only, and sign the funding transactions on the CLI
+    /// (e.g., "--private-key-path prefunded:0").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefunded_keys_on_nodes: Option<bool>,
+    /// Set "true" for "avalanched" to write the daily usage report of the cluster
+    /// (instance hours, EBS GB-hours, data transfer estimate) into the S3 bucket,
+    /// in JSON and CSV (see "StorageNamespace::UsageReport").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage_report: Option<bool>,
+    /// Network snapshot to restore the nodes from on the first boot,
+    /// set by "restore-network" (or "failover"). Requires "static_node_keys".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restore_from_snapshot: Option<network_snapshot::Source>,
+    /// Ephemeral preview network for CI, without the NLB,
+    /// reaped by the job ID or after the expiry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ci_preview: Option<ci_preview::CiPreview>,
+    /// Deletes the cluster after the duration (e.g., "72h", "14d") since
+    /// the first "apply", once "reap" runs (e.g., on a schedule).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_delete_after: Option<String>,
+    /// Values resolved from the "${env:VAR}" and "${ssm:/path/param}"
+    /// placeholders at load time, restored to the placeholders on sync.
+    #[serde(skip)]
+    pub interpolations: Vec<spec_resolver::Interpolation>,
+
     /// Generated key info with locked P-chain balance with
     /// initial stake duration in genesis.
     /// Only valid for custom networks.
@@ -166,6 +431,185 @@ impl Endpoints {
     }
 }
 
+/// Represents a subnet to track.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct TrackedSubnet {
+    pub subnet_id: String,
+    /// If true, only connects to the subnet validators
+    /// (and the allowed nodes) for the subnet messages.
+    #[serde(default)]
+    pub validator_only: bool,
+    /// Non-validator node IDs that are allowed to connect
+    /// when "validator_only" is true (e.g., trusted partner nodes).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_nodes: Option<Vec<String>>,
+}
+
+/// Represents the elastic subnet transformed from a permissioned subnet.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct ElasticSubnet {
+    #[serde(flatten)]
+    pub params: txs::TransformSubnetParams,
+
+    /// READ ONLY -- DO NOT SET.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transform_subnet_tx_id: Option<String>,
+    /// READ ONLY -- DO NOT SET.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validators: Option<Vec<PermissionlessStaker>>,
+    /// READ ONLY -- DO NOT SET.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delegators: Option<Vec<PermissionlessStaker>>,
+}
+
+/// Represents a permissionless validator or delegator registration.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct PermissionlessStaker {
+    pub node_id: String,
+    pub tx_id: String,
+    pub weight: u64,
+    /// Unix time in seconds.
+    pub start_time: u64,
+    /// Unix time in seconds.
+    pub end_time: u64,
+}
+
+/// Represents the recurring maintenance window.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct MaintenanceWindow {
+    /// 5-field cron for the window start (e.g., "0 2 * * SAT" for every Saturday 2 AM).
+    pub cron: String,
+    /// Window duration in minutes from each start.
+    pub duration_minutes: u64,
+    /// Fixed UTC offset for the cron (e.g., "UTC", "+09:00").
+    #[serde(default = "MaintenanceWindow::default_timezone")]
+    pub timezone: String,
+}
+
+impl MaintenanceWindow {
+    fn default_timezone() -> String {
+        String::from("UTC")
+    }
+
+    /// Returns true if the time is within any window.
+    pub fn is_open(&self, now: DateTime<Utc>) -> io::Result<bool> {
+        let schedule = cron::Schedule::parse(&self.cron)?;
+        let now = now.with_timezone(&cron::parse_utc_offset(&self.timezone)?);
+        for m in 0..self.duration_minutes {
+            if schedule.matches(&(now - chrono::Duration::minutes(m as i64))) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns the next window start within a year, if any.
+    pub fn next_start(&self, now: DateTime<Utc>) -> io::Result<Option<DateTime<FixedOffset>>> {
+        let schedule = cron::Schedule::parse(&self.cron)?;
+        let now = now.with_timezone(&cron::parse_utc_offset(&self.timezone)?);
+        for m in 1..=(366 * 24 * 60) {
+            let t = now + chrono::Duration::minutes(m);
+            if schedule.matches(&t) {
+                return Ok(Some(t.with_second(0).unwrap()));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Replaces the secrets in the redacted spec.
+pub const REDACTED: &str = "REDACTED";
+
+/// Prefix of the "--private-key-path" to sign with the prefunded key
+/// in the local spec, with the index (e.g., "prefunded:0").
+pub const PREFUNDED_KEY_PATH_PREFIX: &str = "prefunded:";
+
+/// Returns the short hash of the identifier for the redacted spec.
+pub(crate) fn redact_hash(v: &str) -> String {
+    let d = ring::digest::digest(&ring::digest::SHA256, v.as_bytes());
+    format!("redacted-{}", &hex::encode(d.as_ref())[..12])
+}
+
+/// Represents the target primary network stake of the anchor nodes.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct StakeDistribution {
+    /// Target stake in nano-AVAX for the anchor nodes not in "weights".
+    pub default_weight: u64,
+    /// Target stake in nano-AVAX per node ID.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub weights: BTreeMap<String, u64>,
+}
+
+impl StakeDistribution {
+    /// Returns the target stake of the node.
+    pub fn target_weight(&self, node_id: &str) -> u64 {
+        *self.weights.get(node_id).unwrap_or(&self.default_weight)
+    }
+
+    /// Parses the comma-separated "NODE_ID=WEIGHT" pairs.
+    pub fn parse_weights(s: &str) -> io::Result<BTreeMap<String, u64>> {
+        let mut weights = BTreeMap::new();
+        for pair in s.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            let (node_id, weight) = pair.split_once('=').ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "invalid stake weight '{}' (expected 'NODE_ID=WEIGHT')",
+                        pair
+                    ),
+                )
+            })?;
+            let weight = weight.trim().parse::<u64>().map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid stake weight '{}' ({})", pair, e),
+                )
+            })?;
+            weights.insert(node_id.trim().to_string(), weight);
+        }
+        Ok(weights)
+    }
+}
+
+/// Represents the avalanchego release channel to track.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct AvalanchegoRelease {
+    /// Semver constraint for the releases (e.g., "^1.7", "~1.7.10", ">=1.7.0, <1.8.0").
+    pub version_constraint: String,
+    /// Set "true" to trigger the "update-artifacts" event for the staged release.
+    /// Not allowed for the mainnet.
+    #[serde(default)]
+    pub auto_upgrade: bool,
+    /// The latest release version that has been staged in S3.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub staged_version: Option<String>,
+}
+
+/// Represents a chain upgrade file.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct ChainUpgrade {
+    /// Blockchain ID (or alias) that names the chain config directory.
+    pub chain_id: String,
+    /// Upgrade file path in the local machine.
+    pub file_path: String,
+}
+
+/// Represents a bootstrap node.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct BootstrapNode {
+    /// Staking IP and port (e.g., "1.2.3.4:9651").
+    pub ip: String,
+    pub node_id: String,
+}
+
 /// Defines how the underlying infrastructure is set up.
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
 #[serde(rename_all = "snake_case")]
@@ -176,6 +620,21 @@ pub struct Machine {
     pub non_anchor_nodes: u32,
     #[serde(default)]
     pub instance_types: Option<Vec<String>>,
+    /// Size of the root disk for the EC2 instances, in GiB.
+    /// If empty, 800 for mainnet, 400 for the other networks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume_size_in_gb: Option<u32>,
+    /// Optional OS hardening of the node hosts, run in the user-data.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub os_hardening: Option<os_hardening::OsHardening>,
+    /// Optional kernel network tunings that "avalanched" applies on boot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_tuning: Option<network_tuning::NetworkTuning>,
+    /// Custom AMI ID for the node instances, with the artifacts and
+    /// the tunings already installed (written by "bake-ami").
+    /// If empty, the latest Ubuntu image in the ASG template.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_id: Option<String>,
 }
 
 /// Represents artifacts for installation, to be shared with
@@ -204,6 +663,32 @@ pub struct InstallArtifacts {
     /// with remote machiens.
     #[serde(default)]
     pub plugins_dir: Option<String>,
+    /// ICM relayer binary path in the local environment, required for
+    /// "icm_relayer". The file is "compressed" and uploaded to remote storage
+    /// for the relayer instances, with the same provenance checks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icm_relayer_bin: Option<String>,
+    /// Set "true" to refuse the avalanchego and plugin binaries without
+    /// a valid signature ("[FILE].sig" in the local environment),
+    /// both before upload and before install in the remote machines.
+    #[serde(default)]
+    pub require_signed_artifacts: bool,
+    /// Hex-encoded ed25519 public key to verify the artifact signatures.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signing_public_key: Option<String>,
+}
+
+impl InstallArtifacts {
+    /// Loads and verifies the provenance of the local artifact to upload.
+    pub fn verify_local(&self, file_path: &str) -> io::Result<provenance::Provenance> {
+        let p = provenance::Provenance::load(file_path)?;
+        p.verify(
+            file_path,
+            self.signing_public_key.as_deref(),
+            self.require_signed_artifacts,
+        )?;
+        Ok(p)
+    }
 }
 
 /// Represents the CloudFormation stack name.
@@ -212,6 +697,9 @@ pub enum StackName {
     Vpc(String),
     AsgBeaconNodes(String),
     AsgNonBeaconNodes(String),
+    AsgIcmRelayer(String),
+    Budget(String),
+    AuditorRole(String),
 }
 
 impl StackName {
@@ -221,6 +709,9 @@ impl StackName {
             StackName::Vpc(id) => format!("{}-vpc", id),
             StackName::AsgBeaconNodes(id) => format!("{}-asg-anchor-nodes", id),
             StackName::AsgNonBeaconNodes(id) => format!("{}-asg-non-anchor-nodes", id),
+            StackName::AsgIcmRelayer(id) => format!("{}-asg-icm-relayer", id),
+            StackName::Budget(id) => format!("{}-budget", id),
+            StackName::AuditorRole(id) => format!("{}-auditor-role", id),
         }
     }
 }
@@ -234,15 +725,35 @@ pub struct DefaultSpecOption {
 
     pub region: String,
 
+    pub s3_backups_expiration_days: String,
+    pub s3_backups_glacier_transition_days: String,
+
     pub db_backup_s3_region: String,
     pub db_backup_s3_bucket: String,
     pub db_backup_s3_key: String,
 
+    pub replica_s3_region: String,
+    pub replica_s3_bucket: String,
+
+    pub tracked_subnet_ids: String,
+    pub tracked_subnets_validator_only: bool,
+    pub tracked_subnets_allowed_nodes: String,
+
+    pub external_bootstrap_ips: String,
+    pub external_bootstrap_ids: String,
+
+    /// Comma-separated "[CHAIN_ID]=[FILE_PATH]".
+    pub chain_upgrade_files: String,
+
+    pub avalanchego_release_constraint: String,
+    pub avalanchego_release_auto_upgrade: bool,
+
     pub nlb_acm_certificate_arn: String,
 
     pub install_artifacts_avalanched_bin: String,
     pub install_artifacts_avalanche_bin: String,
     pub install_artifacts_plugins_dir: String,
+    pub install_artifacts_signing_public_key: String,
 
     pub avalanchego_log_level: String,
     pub avalanchego_whitelisted_subnets: String,
@@ -263,6 +774,9 @@ pub struct DefaultSpecOption {
     pub disable_instance_system_metrics: bool,
 
     pub spec_file_path: String,
+    /// Seeds the cluster ID and the S3 bucket name (e.g., CI job ID),
+    /// so the re-runs generate the same names. Random if empty.
+    pub id_seed: String,
 }
 
 impl Spec {
@@ -322,9 +836,11 @@ impl Spec {
                 let spec_file_stem = Path::new(&opt.spec_file_path).file_stem().unwrap();
                 spec_file_stem.to_str().unwrap().to_string()
             } else {
-                match constants::NETWORK_ID_TO_NETWORK_NAME.get(&network_id) {
-                    Some(v) => id::with_time(format!("aops-{}", *v).as_str()),
-                    None => id::with_time("aops-custom"),
+                // e.g., "brave-otter-3fa2"
+                if opt.id_seed.is_empty() {
+                    id::petname(4)
+                } else {
+                    id::petname_from_seed(&opt.id_seed, 4)
                 }
             }
         };
@@ -345,6 +861,10 @@ impl Spec {
                 String::from("m5.large"),
                 String::from("c5.large"),
             ]),
+            volume_size_in_gb: None,
+            os_hardening: None,
+            network_tuning: None,
+            image_id: None,
         };
 
         let (avalanchego_genesis_template, generated_seed_keys) = {
@@ -393,7 +913,9 @@ impl Spec {
                     allow_list_admins: Some(admin_addresses),
                     ..subnet_evm_genesis::ContractDeployerAllowListConfig::default()
                 };
-                chain_config.contract_deployer_allow_list_config = Some(allow_list);
+                chain_config.contract_deployer_allow_list_config = Some(allow_list.clone());
+                // the seed keys update the fees with "subnet update-fee-config"
+                chain_config.fee_manager_config = Some(allow_list);
                 genesis.config = Some(chain_config);
 
                 Some(genesis)
@@ -402,11 +924,101 @@ impl Spec {
             }
         };
 
+        let tracked_subnets = {
+            if opt.tracked_subnet_ids.is_empty() {
+                None
+            } else {
+                let allowed_nodes = {
+                    if opt.tracked_subnets_allowed_nodes.is_empty() {
+                        None
+                    } else {
+                        Some(
+                            opt.tracked_subnets_allowed_nodes
+                                .split(',')
+                                .map(String::from)
+                                .collect::<Vec<String>>(),
+                        )
+                    }
+                };
+                let mut subnets = Vec::new();
+                for subnet_id in opt.tracked_subnet_ids.split(',') {
+                    subnets.push(TrackedSubnet {
+                        subnet_id: subnet_id.to_string(),
+                        validator_only: opt.tracked_subnets_validator_only,
+                        allowed_nodes: allowed_nodes.clone(),
+                    });
+                }
+                Some(subnets)
+            }
+        };
+
+        let external_bootstrap_nodes = {
+            if opt.external_bootstrap_ips.is_empty() {
+                None
+            } else {
+                let ips: Vec<&str> = opt.external_bootstrap_ips.split(',').collect();
+                let ids: Vec<&str> = opt.external_bootstrap_ids.split(',').collect();
+                let mut nodes = Vec::new();
+                for (i, ip) in ips.iter().enumerate() {
+                    nodes.push(BootstrapNode {
+                        ip: ip.to_string(),
+                        node_id: ids.get(i).unwrap_or(&"").to_string(),
+                    });
+                }
+                Some(nodes)
+            }
+        };
+
+        let chain_upgrades = {
+            if opt.chain_upgrade_files.is_empty() {
+                None
+            } else {
+                let mut upgrades = Vec::new();
+                for kv in opt.chain_upgrade_files.split(',') {
+                    let (chain_id, file_path) = kv.split_once('=').unwrap_or((kv, ""));
+                    upgrades.push(ChainUpgrade {
+                        chain_id: chain_id.to_string(),
+                        file_path: file_path.to_string(),
+                    });
+                }
+                Some(upgrades)
+            }
+        };
+
+        let avalanchego_release = {
+            if opt.avalanchego_release_constraint.is_empty() {
+                None
+            } else {
+                Some(AvalanchegoRelease {
+                    version_constraint: opt.avalanchego_release_constraint.clone(),
+                    auto_upgrade: opt.avalanchego_release_auto_upgrade,
+                    staged_version: None,
+                })
+            }
+        };
+
         let mut aws_resources = aws::Resources {
             region: opt.region,
-            s3_bucket: format!("avalanche-ops-{}-{}", time::get(6), id::system(10)), // [year][month][date]-[system host-based id]
+            s3_bucket: if opt.id_seed.is_empty() {
+                id::system_with_prefix("avalanche-ops", &time::get(6), 10) // [year][month][date]-[system host-based id]
+            } else {
+                id::deterministic_with_prefix("avalanche-ops", &opt.id_seed, 10)
+            },
             ..aws::Resources::default()
         };
+        let mut s3_lifecycle = aws::s3::Lifecycle::default();
+        if !opt.s3_backups_expiration_days.is_empty() {
+            let days = opt.s3_backups_expiration_days.parse::<i32>().unwrap();
+            s3_lifecycle.backups_expiration_days = Some(days);
+        }
+        if !opt.s3_backups_glacier_transition_days.is_empty() {
+            let days = opt
+                .s3_backups_glacier_transition_days
+                .parse::<i32>()
+                .unwrap();
+            s3_lifecycle.backups_glacier_transition_days = Some(days);
+        }
+        aws_resources.s3_lifecycle = Some(s3_lifecycle);
         if !opt.db_backup_s3_region.is_empty() {
             aws_resources.db_backup_s3_region = Some(opt.db_backup_s3_region);
         }
@@ -416,6 +1028,12 @@ impl Spec {
         if !opt.db_backup_s3_key.is_empty() {
             aws_resources.db_backup_s3_key = Some(opt.db_backup_s3_key);
         }
+        if !opt.replica_s3_region.is_empty() {
+            aws_resources.replica_s3_region = Some(opt.replica_s3_region);
+        }
+        if !opt.replica_s3_bucket.is_empty() {
+            aws_resources.replica_s3_bucket = Some(opt.replica_s3_bucket);
+        }
         if !opt.nlb_acm_certificate_arn.is_empty() {
             aws_resources.nlb_acm_certificate_arn = Some(opt.nlb_acm_certificate_arn);
         }
@@ -431,7 +1049,14 @@ impl Spec {
             avalanched_bin: opt.install_artifacts_avalanched_bin,
             avalanchego_bin: opt.install_artifacts_avalanche_bin,
             plugins_dir: None,
+            icm_relayer_bin: None,
+            require_signed_artifacts: false,
+            signing_public_key: None,
         };
+        if !opt.install_artifacts_signing_public_key.is_empty() {
+            install_artifacts.require_signed_artifacts = true;
+            install_artifacts.signing_public_key = Some(opt.install_artifacts_signing_public_key);
+        }
         if !opt.install_artifacts_plugins_dir.is_empty() {
             install_artifacts.plugins_dir = Some(opt.install_artifacts_plugins_dir);
         }
@@ -451,6 +1076,10 @@ impl Spec {
         if opt.coreth_offline_pruning_enabled {
             coreth_config.offline_pruning_enabled = Some(true);
         }
+        // bootstrapping the public networks from genesis takes days
+        if !avalanchego_config.is_custom_network() {
+            coreth_config.state_sync_enabled = Some(true);
+        }
 
         Self {
             id,
@@ -464,6 +1093,32 @@ impl Spec {
             avalanchego_genesis_template,
 
             subnet_evm_genesis,
+            tracked_subnets,
+            elastic_subnet: None,
+            external_bootstrap_nodes,
+            chain_upgrades,
+            avalanchego_release,
+            maintenance_window: None,
+            max_offline_stake_percent: None,
+            stake_distribution: None,
+            validator_expiry: None,
+            api_exposure: None,
+            indexer: None,
+            config_overrides: None,
+            canary: None,
+            remediation: None,
+            agent_metrics: None,
+            otlp: None,
+            icm_relayer: None,
+            public_snapshot: None,
+            peer_allowlist: None,
+            static_node_keys: None,
+            prefunded_keys_on_nodes: None,
+            usage_report: None,
+            restore_from_snapshot: None,
+            ci_preview: None,
+            auto_delete_after: None,
+            interpolations: Vec::new(),
 
             generated_seed_private_key_with_locked_p_chain_balance,
             generated_seed_private_keys,
@@ -484,114 +1139,802 @@ impl Spec {
                 ));
             }
         }
-    }
-
-    /// Saves the current spec to disk
-    /// and overwrites the file.
-    pub fn sync(&self, file_path: &str) -> io::Result<()> {
-        info!("syncing Spec to '{}'", file_path);
-        let path = Path::new(file_path);
-        let parent_dir = path.parent().expect("unexpected None parent");
-        fs::create_dir_all(parent_dir)?;
+    }
+
+    /// Returns the spec state to store in S3, which every node reads.
+    /// Removes the private keys of the prefunded keys (the addresses remain),
+    /// unless "prefunded_keys_on_nodes" is set.
+    pub fn state(&self) -> Self {
+        let mut state = self.clone();
+        if self.prefunded_keys_on_nodes == Some(true) {
+            return state;
+        }
+        let strip_key = |k: &mut key::PrivateKeyInfo| {
+            k.private_key = REDACTED.into();
+            k.private_key_hex = REDACTED.into();
+        };
+        if let Some(k) = state
+            .generated_seed_private_key_with_locked_p_chain_balance
+            .as_mut()
+        {
+            strip_key(k);
+        }
+        if let Some(keys) = state.generated_seed_private_keys.as_mut() {
+            keys.iter_mut().for_each(strip_key);
+        }
+        state
+    }
+
+    /// Converts the spec state (see "state") to string in YAML format.
+    pub fn encode_state_yaml(&self) -> io::Result<String> {
+        self.state().encode_yaml()
+    }
+
+    /// Returns true if the prefunded keys have the private keys
+    /// (false for the spec state downloaded from S3).
+    pub fn has_prefunded_private_keys(&self) -> bool {
+        self.generated_seed_private_keys
+            .as_ref()
+            .map(|keys| keys.iter().all(|k| k.private_key.expose_str() != REDACTED))
+            .unwrap_or(false)
+    }
+
+    /// Returns the prefunded key at the index to sign on the CLI.
+    pub fn prefunded_key(&self, index: usize) -> io::Result<key::Key> {
+        let keys = self.generated_seed_private_keys.as_ref().ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                "no prefunded keys in spec (only for custom networks)",
+            )
+        })?;
+        let k = keys.get(index).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("prefunded key {} not found ({} keys)", index, keys.len()),
+            )
+        })?;
+        if k.private_key.expose_str() == REDACTED {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "prefunded key {} ({}) has no private key in spec (use the spec that 'default-spec' generated)",
+                    index, k.p_address
+                ),
+            ));
+        }
+        key::Key::from_private_key(k.private_key.expose_str())
+    }
+
+    /// Saves the current spec to disk
+    /// and overwrites the file.
+    /// Keeps the existing file encrypted, if it was encrypted
+    /// (see "spec_encryption").
+    /// Writes back the placeholders of the resolved values
+    /// (see "spec_resolver").
+    pub fn sync(&self, file_path: &str) -> io::Result<()> {
+        info!("syncing Spec to '{}'", file_path);
+        let path = Path::new(file_path);
+        let parent_dir = path.parent().expect("unexpected None parent");
+        fs::create_dir_all(parent_dir)?;
+
+        let mut doc = serde_yaml::to_value(self).map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to serialize Spec to YAML {}", e),
+            )
+        })?;
+        spec_resolver::restore(&mut doc, &self.interpolations);
+        let ret = serde_yaml::to_vec(&doc);
+        let d = match ret {
+            Ok(d) => d,
+            Err(e) => {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!("failed to serialize Spec to YAML {}", e),
+                ));
+            }
+        };
+        let d = match spec_encryption::read(file_path)? {
+            Some(encryption) => spec_encryption::seal(&encryption, &d)?,
+            None => d,
+        };
+        let mut f = File::create(file_path)?;
+        f.write_all(&d)?;
+
+        Ok(())
+    }
+
+    pub fn load(file_path: &str) -> io::Result<Self> {
+        info!("loading Spec from {}", file_path);
+
+        if !Path::new(file_path).exists() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("file {} does not exists", file_path),
+            ));
+        }
+
+        let d = fs::read(&file_path).map_err(|e| {
+            return Error::new(
+                ErrorKind::Other,
+                format!("failed to open {} ({})", file_path, e),
+            );
+        })?;
+        // transparently decrypts, if encrypted
+        let d = spec_encryption::unseal(&d)?;
+        let mut doc: serde_yaml::Value = serde_yaml::from_slice(&d).map_err(|e| {
+            return Error::new(ErrorKind::InvalidInput, format!("invalid YAML: {}", e));
+        })?;
+
+        // resolves "${env:VAR}" and "${ssm:/path/param}" placeholders
+        let interpolations = spec_resolver::resolve(&mut doc)?;
+        let mut spec: Self = serde_yaml::from_value(doc)
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("invalid YAML: {}", e)))?;
+        spec.interpolations = interpolations;
+        Ok(spec)
+    }
+
+    /// Returns a shareable copy of the spec, with the private keys
+    /// and key material references removed, and the account identifiers hashed.
+    /// The same identifier is hashed to the same value, so the references
+    /// within the spec remain consistent.
+    pub fn redact(&self) -> io::Result<Self> {
+        let mut redacted = self.clone();
+
+        let redact_key = |k: &mut key::PrivateKeyInfo| {
+            k.private_key = REDACTED.into();
+            k.private_key_hex = REDACTED.into();
+        };
+        if let Some(k) = redacted
+            .generated_seed_private_key_with_locked_p_chain_balance
+            .as_mut()
+        {
+            redact_key(k);
+        }
+        if let Some(keys) = redacted.generated_seed_private_keys.as_mut() {
+            keys.iter_mut().for_each(redact_key);
+        }
+
+        // account identifiers may appear in any ARN, so replace all occurrences
+        let identifiers = self.redaction_identifiers();
+        if let Some(aws_resources) = redacted.aws_resources.as_mut() {
+            if aws_resources.ec2_key_path.is_some() {
+                aws_resources.ec2_key_path = Some(String::from(REDACTED));
+            }
+        }
+
+        let mut encoded = redacted.encode_yaml()?;
+        for v in identifiers.iter() {
+            encoded = encoded.replace(v.as_str(), &redact_hash(v));
+        }
+        serde_yaml::from_str(&encoded).map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to parse redacted Spec {}", e),
+            )
+        })
+    }
+
+    /// Returns the account identifiers to hash in the shared outputs
+    /// (e.g., redacted spec, log bundles), the longest first,
+    /// in case one contains another.
+    pub fn redaction_identifiers(&self) -> Vec<String> {
+        let mut identifiers: Vec<String> = Vec::new();
+        if let Some(aws_resources) = self.aws_resources.as_ref() {
+            if let Some(identity) = aws_resources.identity.as_ref() {
+                identifiers.push(identity.account_id.clone());
+                identifiers.push(identity.role_arn.clone());
+                identifiers.push(identity.user_id.clone());
+            }
+            if let Some(v) = aws_resources.kms_cmk_id.as_ref() {
+                identifiers.push(v.clone());
+            }
+            if let Some(v) = aws_resources.ec2_key_name.as_ref() {
+                identifiers.push(v.clone());
+            }
+        }
+        // local paths may contain the user name
+        if let Some(home) = dirs::home_dir() {
+            identifiers.push(home.to_str().unwrap_or_default().to_string());
+        }
+        identifiers.retain(|v| !v.is_empty());
+        identifiers.sort_by_key(|v| std::cmp::Reverse(v.len()));
+        identifiers
+    }
+
+    /// Validates the spec.
+    /// Returns an error if the disruptive operation is requested
+    /// outside of the maintenance window (if any), unless forced.
+    pub fn check_maintenance_window(&self, op: &str, force: bool) -> io::Result<()> {
+        let maintenance_window = match &self.maintenance_window {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        let now = Utc::now();
+        if maintenance_window.is_open(now)? {
+            info!("'{}' within the maintenance window", op);
+            return Ok(());
+        }
+        if force {
+            warn!("'{}' outside of the maintenance window (forced)", op);
+            return Ok(());
+        }
+        let next = match maintenance_window.next_start(now)? {
+            Some(t) => t.to_rfc3339(),
+            None => String::from("none"),
+        };
+        Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "'{}' is not allowed outside of the maintenance window '{}' for {} minutes (next {}), use '--force' to override",
+                op, maintenance_window.cron, maintenance_window.duration_minutes, next
+            ),
+        ))
+    }
+
+    /// Returns the avalanchego and coreth configurations of the node class,
+    /// as "avalanchego" writes them on the node.
+    pub fn node_configs(
+        &self,
+        kind: &node::Kind,
+    ) -> (avalanchego_config::Config, coreth_config::Config) {
+        let mut avalanchego_config = self.avalanchego_config.clone();
+        let mut coreth_config = self.coreth_config.clone();
+        if let Some(v) = &self.api_exposure {
+            v.node_apis(kind)
+                .apply(&mut avalanchego_config, &mut coreth_config);
+        }
+        if let Some(v) = &self.indexer {
+            v.apply(kind, &mut avalanchego_config);
+        }
+        if let Some(v) = &self.config_overrides {
+            // validated in "validate"
+            avalanchego_config = v
+                .apply(kind, self.is_api_node(kind), &avalanchego_config)
+                .expect("invalid 'config_overrides'");
+        }
+        if avalanchego_config.api_auth_required == Some(true)
+            && avalanchego_config.api_auth_password_file.is_none()
+        {
+            avalanchego_config.api_auth_password_file = Some(String::from(
+                avalanchego_config::DEFAULT_API_AUTH_PASSWORD_FILE,
+            ));
+        }
+        (avalanchego_config, coreth_config)
+    }
+
+    /// Returns true if the node class is in the public NLB target group.
+    pub fn is_api_node(&self, kind: &node::Kind) -> bool {
+        match &self.api_exposure {
+            Some(v) => v.node_apis(kind).is_nlb_target(),
+            None => true,
+        }
+    }
+
+    /// Returns the root disk size of the node class in GiB,
+    /// or None to use the default in the ASG template.
+    pub fn volume_size_in_gb(&self, kind: &node::Kind) -> Option<u32> {
+        // mainnet/* requires higher volume size
+        let base = match self.machine.volume_size_in_gb {
+            Some(v) => Some(v),
+            None if self.avalanchego_config.is_mainnet() => Some(800),
+            None if !self.avalanchego_config.is_custom_network() => Some(400),
+            None => None,
+        };
+        match &self.indexer {
+            Some(v) => Some(v.volume_size_in_gb(
+                kind,
+                base.unwrap_or(DEFAULT_MACHINE_VOLUME_SIZE_IN_GB),
+                self.avalanchego_config.is_mainnet(),
+            )),
+            None => base,
+        }
+    }
+
+    pub fn validate(&self) -> io::Result<()> {
+        info!("validating Spec");
+
+        if self.id.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "'id' cannot be empty"));
+        }
+
+        // some AWS resources have tag limit of 32-character
+        if self.id.len() > 28 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("'id' length cannot be >28 (got {})", self.id.len()),
+            ));
+        }
+        // names derived from the ID, ref. "cfn-templates/avalanche-node"
+        for stack_name in [
+            StackName::Ec2InstanceRole(self.id.clone()),
+            StackName::Vpc(self.id.clone()),
+            StackName::AsgBeaconNodes(self.id.clone()),
+            StackName::AsgNonBeaconNodes(self.id.clone()),
+            StackName::AsgIcmRelayer(self.id.clone()),
+            StackName::Budget(self.id.clone()),
+            StackName::AuditorRole(self.id.clone()),
+        ] {
+            id::check_len(id::Resource::CloudFormationStack, &stack_name.encode())?;
+        }
+        id::check_len(id::Resource::Elb, &format!("{}-nlb", self.id))?;
+        id::check_len(id::Resource::Elb, &format!("{}-tg", self.id))?;
+        id::check_len(
+            id::Resource::IamRole,
+            &format!("{}-budget-scale-down-role", self.id),
+        )?;
+        id::check_len(id::Resource::IamRole, &format!("{}-auditor-role", self.id))?;
+        id::check_len(
+            id::Resource::AutoScalingGroup,
+            &format!("{}-non-anchor-amd64", self.id),
+        )?;
+
+        if self.aws_resources.is_some() {
+            let aws_resources = self.aws_resources.clone().unwrap();
+            if aws_resources.region.is_empty() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'machine.region' cannot be empty",
+                ));
+            }
+            id::check_len(id::Resource::S3Bucket, &aws_resources.s3_bucket)?;
+            if let Some(ec2_key_name) = &aws_resources.ec2_key_name {
+                id::check_len(id::Resource::Ec2KeyPair, ec2_key_name)?;
+            }
+            if aws_resources.db_backup_s3_region.is_some()
+                && aws_resources.db_backup_s3_bucket.is_none()
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "{} missing corresponding bucket",
+                        aws_resources
+                            .db_backup_s3_bucket
+                            .expect("unexpected aws_resources.db_backup_s3_bucket")
+                    ),
+                ));
+            }
+            if aws_resources.db_backup_s3_bucket.is_some()
+                && aws_resources.db_backup_s3_key.is_none()
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "{} missing corresponding key",
+                        aws_resources
+                            .db_backup_s3_bucket
+                            .expect("unexpected aws_resources.db_backup_s3_bucket")
+                    ),
+                ));
+            }
+            if aws_resources.db_backup_s3_bucket.is_some()
+                && aws_resources.db_backup_s3_region.is_none()
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "{} missing corresponding region",
+                        aws_resources
+                            .db_backup_s3_bucket
+                            .expect("unexpected aws_resources.db_backup_s3_bucket")
+                    ),
+                ));
+            }
+            if let Some(mirrors) = &aws_resources.db_backup_s3_mirrors {
+                if aws_resources.db_backup_s3_bucket.is_none() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "'db_backup_s3_mirrors' requires 'db_backup_s3_bucket'",
+                    ));
+                }
+                for m in mirrors.iter() {
+                    if m.region.is_empty() || m.bucket.is_empty() {
+                        return Err(Error::new(
+                            ErrorKind::InvalidInput,
+                            "'db_backup_s3_mirrors' has an empty region or bucket",
+                        ));
+                    }
+                }
+            }
+            fips::validate_recorded(aws_resources.fips)?;
+            if fips::enabled() {
+                fips::validate_region(&aws_resources.region)?;
+            }
+            if let Some(s3_transfer_limits) = &aws_resources.s3_transfer_limits {
+                s3_transfer_limits.validate()?;
+            }
+            if let Some(elastic_ip_pool) = &aws_resources.elastic_ip_pool {
+                elastic_ip_pool.validate(self.machine.non_anchor_nodes)?;
+            }
+            if let Some(budget) = &aws_resources.budget {
+                budget.validate()?;
+            }
+            if let Some(auditor) = &aws_resources.auditor {
+                auditor.validate()?;
+            }
+            if let Some(version) = aws_resources.cloudwatch_metrics_schema_version {
+                metric_schema::validate_version(version)?;
+            }
+            if let Some(s3_lifecycle) = &aws_resources.s3_lifecycle {
+                s3_lifecycle.validate()?;
+            }
+            if let Some(private_connectivity) = &aws_resources.private_connectivity {
+                private_connectivity.validate()?;
+            }
+            if let Some(nlb_endpoint_service) = &aws_resources.nlb_endpoint_service {
+                nlb_endpoint_service.validate()?;
+            }
+            if aws_resources.clock_offset_alarm_threshold_ms == Some(0) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'clock_offset_alarm_threshold_ms' must be positive",
+                ));
+            }
+            if let Some(v) = aws_resources.nlb_tcp_idle_timeout_seconds {
+                if !(60..=6000).contains(&v) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("'nlb_tcp_idle_timeout_seconds' must be 60-6000 (got {})", v),
+                    ));
+                }
+            }
+            if aws_resources.nlb_access_logs == Some(true)
+                && aws_resources.nlb_acm_certificate_arn.is_none()
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'nlb_access_logs' requires 'nlb_acm_certificate_arn' (NLB only logs TLS listeners)",
+                ));
+            }
+            if let Some(nlb_target_group) = &aws_resources.nlb_target_group {
+                nlb_target_group.validate()?;
+                if nlb_target_group.stickiness == Some(true)
+                    && aws_resources.nlb_acm_certificate_arn.is_some()
+                {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "'nlb_target_group.stickiness' is not supported with 'nlb_acm_certificate_arn' (TLS listener)",
+                    ));
+                }
+                // the HTTP health checks have no token
+                if self.avalanchego_config.api_auth_required == Some(true)
+                    && nlb_target_group
+                        .health_check_protocol
+                        .as_deref()
+                        .unwrap_or("TCP")
+                        != "TCP"
+                {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "'avalanchego_config.api-auth-required' requires the 'TCP' 'nlb_target_group.health_check_protocol'",
+                    ));
+                }
+                if !nlb_target_group.weights.is_empty() {
+                    let (from, to) = aws::elbv2::weighted_port_range();
+                    for port in [
+                        self.avalanchego_config.http_port,
+                        self.avalanchego_config.staking_port,
+                    ] {
+                        if (from..=to).contains(&port) {
+                            return Err(Error::new(
+                                ErrorKind::InvalidInput,
+                                format!(
+                                    "port {} conflicts with the 'nlb_target_group.weights' ports {}-{}",
+                                    port, from, to
+                                ),
+                            ));
+                        }
+                    }
+                }
+            }
+            if let Some(public_key) = &aws_resources.ec2_key_import_public_key {
+                if !aws::ec2::is_valid_public_key(public_key) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "'ec2_key_import_public_key' must be an OpenSSH 'ssh-rsa' or 'ssh-ed25519' public key",
+                    ));
+                }
+            }
+            if aws_resources.replica_s3_region.is_some()
+                != aws_resources.replica_s3_bucket.is_some()
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'replica_s3_region' and 'replica_s3_bucket' must be set together",
+                ));
+            }
+            if let Some(replica_s3_region) = &aws_resources.replica_s3_region {
+                if replica_s3_region.eq(&aws_resources.region) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "'replica_s3_region' {} must be different from 'region'",
+                            replica_s3_region
+                        ),
+                    ));
+                }
+            }
+        }
+
+        if let Some(tracked_subnets) = &self.tracked_subnets {
+            for subnet in tracked_subnets.iter() {
+                if subnet.subnet_id.is_empty() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "'tracked_subnets' subnet_id cannot be empty",
+                    ));
+                }
+                if let Some(allowed_nodes) = &subnet.allowed_nodes {
+                    if !subnet.validator_only {
+                        return Err(Error::new(
+                            ErrorKind::InvalidInput,
+                            format!(
+                                "'tracked_subnets' {} allowed_nodes requires validator_only",
+                                subnet.subnet_id
+                            ),
+                        ));
+                    }
+                    for node_id in allowed_nodes.iter() {
+                        if !node_id.starts_with("NodeID-") {
+                            return Err(Error::new(
+                                ErrorKind::InvalidInput,
+                                format!(
+                                    "'tracked_subnets' allowed node '{}' is not a valid node ID",
+                                    node_id
+                                ),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(elastic_subnet) = &self.elastic_subnet {
+            elastic_subnet.params.verify()?;
+        }
+
+        if let Some(external_bootstrap_nodes) = &self.external_bootstrap_nodes {
+            for n in external_bootstrap_nodes.iter() {
+                if n.ip.parse::<SocketAddr>().is_err() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "'external_bootstrap_nodes' ip '{}' is not a valid IP:PORT",
+                            n.ip
+                        ),
+                    ));
+                }
+                if !n.node_id.starts_with("NodeID-") {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "'external_bootstrap_nodes' node_id '{}' is not a valid node ID",
+                            n.node_id
+                        ),
+                    ));
+                }
+            }
+        }
+
+        if let Some(chain_upgrades) = &self.chain_upgrades {
+            for u in chain_upgrades.iter() {
+                if u.chain_id.is_empty() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "'chain_upgrades' chain_id cannot be empty",
+                    ));
+                }
+                if !Path::new(&u.file_path).exists() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "'chain_upgrades' file_path '{}' does not exist",
+                            u.file_path
+                        ),
+                    ));
+                }
+                upgrade::load(&u.file_path)?;
+            }
+        }
+
+        if let Some(maintenance_window) = &self.maintenance_window {
+            cron::Schedule::parse(&maintenance_window.cron)?;
+            cron::parse_utc_offset(&maintenance_window.timezone)?;
+            if maintenance_window.duration_minutes == 0 {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'maintenance_window.duration_minutes' must be >0",
+                ));
+            }
+        }
+
+        if let Some(v) = self.max_offline_stake_percent {
+            if v > 100 {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("'max_offline_stake_percent' {} must be <=100", v),
+                ));
+            }
+        }
 
-        let ret = serde_yaml::to_vec(self);
-        let d = match ret {
-            Ok(d) => d,
-            Err(e) => {
+        if let Some(v) = &self.stake_distribution {
+            if self.machine.anchor_nodes.unwrap_or(0) == 0 {
                 return Err(Error::new(
-                    ErrorKind::Other,
-                    format!("failed to serialize Spec to YAML {}", e),
+                    ErrorKind::InvalidInput,
+                    "'stake_distribution' requires anchor nodes (custom network)",
+                ));
+            }
+            if v.default_weight == 0 || v.weights.values().any(|w| *w == 0) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'stake_distribution' weights must be >0",
                 ));
             }
-        };
-        let mut f = File::create(file_path)?;
-        f.write_all(&d)?;
-
-        Ok(())
-    }
-
-    pub fn load(file_path: &str) -> io::Result<Self> {
-        info!("loading Spec from {}", file_path);
-
-        if !Path::new(file_path).exists() {
-            return Err(Error::new(
-                ErrorKind::NotFound,
-                format!("file {} does not exists", file_path),
-            ));
         }
 
-        let f = File::open(&file_path).map_err(|e| {
-            return Error::new(
-                ErrorKind::Other,
-                format!("failed to open {} ({})", file_path, e),
-            );
-        })?;
-        serde_yaml::from_reader(f).map_err(|e| {
-            return Error::new(ErrorKind::InvalidInput, format!("invalid YAML: {}", e));
-        })
-    }
-
-    /// Validates the spec.
-    pub fn validate(&self) -> io::Result<()> {
-        info!("validating Spec");
-
-        if self.id.is_empty() {
-            return Err(Error::new(ErrorKind::InvalidInput, "'id' cannot be empty"));
+        if let Some(v) = &self.api_exposure {
+            v.validate(
+                &self.avalanchego_config,
+                &self.coreth_config,
+                self.machine.anchor_nodes.unwrap_or(0) > 0,
+            )?;
         }
-
-        // some AWS resources have tag limit of 32-character
-        if self.id.len() > 28 {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                format!("'id' length cannot be >28 (got {})", self.id.len()),
-            ));
+        if let Some(v) = &self.indexer {
+            let has_anchor_nodes = self.machine.anchor_nodes.unwrap_or(0) > 0;
+            v.validate(has_anchor_nodes)?;
+            if let Some(exposure) = &self.api_exposure {
+                if exposure.node_apis(&node::Kind::Anchor).index.is_some()
+                    || exposure.node_apis(&node::Kind::NonAnchor).index.is_some()
+                {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "'indexer' conflicts with 'api_exposure' index (set only one)",
+                    ));
+                }
+            }
+            for kind in [node::Kind::Anchor, node::Kind::NonAnchor] {
+                if let Some(size) = self.volume_size_in_gb(&kind) {
+                    if size > indexer::MAX_VOLUME_SIZE_IN_GB {
+                        return Err(Error::new(
+                            ErrorKind::InvalidInput,
+                            format!(
+                                "{} nodes volume size {} GiB with 'indexer' exceeds {} GiB",
+                                kind.as_str(),
+                                size,
+                                indexer::MAX_VOLUME_SIZE_IN_GB
+                            ),
+                        ));
+                    }
+                }
+            }
         }
-
-        if self.aws_resources.is_some() {
-            let aws_resources = self.aws_resources.clone().unwrap();
-            if aws_resources.region.is_empty() {
+        if let Some(v) = &self.config_overrides {
+            v.validate(&self.avalanchego_config, |kind| self.is_api_node(kind))?;
+            // the overrides must not expose the APIs that "api_exposure" locks
+            if self.api_exposure.is_some() {
+                for kind in [node::Kind::Anchor, node::Kind::NonAnchor] {
+                    if !self.is_api_node(&kind) {
+                        continue;
+                    }
+                    let (a, c) = self.node_configs(&kind);
+                    let dangerous = api_exposure::NodeApis::default().dangerous_apis(&a, &c);
+                    if !dangerous.is_empty() {
+                        return Err(Error::new(
+                            ErrorKind::InvalidInput,
+                            format!(
+                                "'config_overrides' exposes the {:?} APIs of {} nodes on the public NLB target group",
+                                dangerous,
+                                kind.as_str()
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+        if let Some(v) = &self.canary {
+            v.validate(self.machine.anchor_nodes.unwrap_or(0) + self.machine.non_anchor_nodes)?;
+        }
+        if let Some(v) = &self.remediation {
+            v.validate()?;
+        }
+        if let Some(v) = &self.agent_metrics {
+            v.validate(
+                self.avalanchego_config.http_port,
+                self.avalanchego_config.staking_port,
+            )?;
+        }
+        if let Some(v) = &self.otlp {
+            v.validate()?;
+        }
+        if let Some(v) = &self.icm_relayer {
+            v.validate()?;
+            if self.install_artifacts.icm_relayer_bin.is_none() {
                 return Err(Error::new(
                     ErrorKind::InvalidInput,
-                    "'machine.region' cannot be empty",
+                    "'icm_relayer' requires 'install_artifacts.icm_relayer_bin'",
                 ));
             }
-            if aws_resources.db_backup_s3_region.is_some()
-                && aws_resources.db_backup_s3_bucket.is_none()
-            {
+            // the relayer calls the node APIs through the NLB without the tokens
+            if self.avalanchego_config.api_auth_required == Some(true) {
                 return Err(Error::new(
                     ErrorKind::InvalidInput,
-                    format!(
-                        "{} missing corresponding bucket",
-                        aws_resources
-                            .db_backup_s3_bucket
-                            .expect("unexpected aws_resources.db_backup_s3_bucket")
-                    ),
+                    "'icm_relayer' does not support 'avalanchego_config.api_auth_required'",
                 ));
             }
-            if aws_resources.db_backup_s3_bucket.is_some()
-                && aws_resources.db_backup_s3_key.is_none()
-            {
+        }
+        if let Some(v) = &self.public_snapshot {
+            v.validate(self.aws_resources.as_ref().map(|r| r.s3_bucket.as_str()))?;
+            if !self.is_api_node(&node::Kind::Anchor) && !self.is_api_node(&node::Kind::NonAnchor) {
                 return Err(Error::new(
                     ErrorKind::InvalidInput,
-                    format!(
-                        "{} missing corresponding key",
-                        aws_resources
-                            .db_backup_s3_bucket
-                            .expect("unexpected aws_resources.db_backup_s3_bucket")
-                    ),
+                    "'public_snapshot' requires the API nodes (see 'api_exposure')",
                 ));
             }
-            if aws_resources.db_backup_s3_bucket.is_some()
-                && aws_resources.db_backup_s3_region.is_none()
-            {
+            // the archival database is too large to download
+            if self.coreth_config.pruning_enabled == Some(false) {
                 return Err(Error::new(
                     ErrorKind::InvalidInput,
-                    format!(
-                        "{} missing corresponding region",
-                        aws_resources
-                            .db_backup_s3_bucket
-                            .expect("unexpected aws_resources.db_backup_s3_bucket")
-                    ),
+                    "'public_snapshot' requires 'coreth_config.pruning_enabled'",
+                ));
+            }
+        }
+        if let Some(v) = &self.peer_allowlist {
+            v.validate()?;
+            // the public networks do not restrict the subnet peers by the cluster
+            if !self.avalanchego_config.is_custom_network() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'peer_allowlist' requires the custom network (use 'tracked_subnets' allowed_nodes)",
+                ));
+            }
+        }
+        if self.restore_from_snapshot.is_some() {
+            if self.static_node_keys != Some(true) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'restore_from_snapshot' requires 'static_node_keys'",
+                ));
+            }
+        }
+        if let Some(v) = &self.auto_delete_after {
+            auto_delete::parse_duration(v)?;
+        }
+        if let Some(v) = &self.ci_preview {
+            v.validate()?;
+            if !self.avalanchego_config.is_custom_network() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'ci_preview' is only valid for the custom network",
+                ));
+            }
+            // the preview network has no NLB
+            if let Some(aws_resources) = &self.aws_resources {
+                if aws_resources.nlb_acm_certificate_arn.is_some()
+                    || aws_resources.nlb_endpoint_service.is_some()
+                {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "'ci_preview' does not support 'nlb_acm_certificate_arn' or 'nlb_endpoint_service'",
+                    ));
+                }
+            }
+        }
+
+        if self.install_artifacts.require_signed_artifacts
+            && self.install_artifacts.signing_public_key.is_none()
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'install_artifacts.require_signed_artifacts' requires 'signing_public_key'",
+            ));
+        }
+
+        if let Some(avalanchego_release) = &self.avalanchego_release {
+            release::Constraint::parse(&avalanchego_release.version_constraint)?;
+            if avalanchego_release.auto_upgrade && self.avalanchego_config.is_mainnet() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'avalanchego_release.auto_upgrade' not allowed for mainnet",
                 ));
             }
         }
@@ -615,6 +1958,45 @@ impl Spec {
             ));
         }
 
+        if let Some(v) = self.machine.volume_size_in_gb {
+            if !(MIN_MACHINE_VOLUME_SIZE_IN_GB..=MAX_MACHINE_VOLUME_SIZE_IN_GB).contains(&v) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "'machine.volume_size_in_gb' {} not in [{}, {}]",
+                        v, MIN_MACHINE_VOLUME_SIZE_IN_GB, MAX_MACHINE_VOLUME_SIZE_IN_GB
+                    ),
+                ));
+            }
+        }
+        if let Some(v) = &self.machine.network_tuning {
+            v.validate()?;
+        }
+        if let Some(v) = &self.machine.image_id {
+            if !v.starts_with("ami-") {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("'machine.image_id' {} is not an AMI ID", v),
+                ));
+            }
+        }
+        if let Some(v) = &self.machine.os_hardening {
+            let script = v.script(
+                self.avalanchego_config.http_port,
+                self.avalanchego_config.staking_port,
+            );
+            if script.len() > os_hardening::MAX_SCRIPT_LEN {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "'machine.os_hardening' script {}-byte exceeds {}-byte",
+                        script.len(),
+                        os_hardening::MAX_SCRIPT_LEN
+                    ),
+                ));
+            }
+        }
+
         if !Path::new(&self.install_artifacts.avalanched_bin).exists() {
             return Err(Error::new(
                 ErrorKind::InvalidInput,
@@ -655,6 +2037,10 @@ impl Spec {
             ));
         }
 
+        if let Some(subnet_evm_genesis) = &self.subnet_evm_genesis {
+            subnet_evm_genesis.validate()?;
+        }
+
         if !self.avalanchego_config.is_custom_network() {
             if self.avalanchego_genesis_template.is_some() {
                 return Err(Error::new(
@@ -716,6 +2102,35 @@ impl Spec {
     }
 }
 
+#[test]
+fn test_maintenance_window() {
+    let t = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+
+    let w = MaintenanceWindow {
+        cron: String::from("0 2 * * SAT"),
+        duration_minutes: 120,
+        timezone: String::from("+09:00"),
+    };
+    // 2022-04-02 (Saturday) 02:30 in +09:00
+    assert!(w.is_open(t("2022-04-01T17:30:00Z")).unwrap());
+    assert!(!w.is_open(t("2022-04-01T19:00:00Z")).unwrap());
+    let next = w.next_start(t("2022-04-01T19:00:00Z")).unwrap().unwrap();
+    assert_eq!(next.to_rfc3339(), "2022-04-09T02:00:00+09:00");
+}
+
+#[test]
+fn test_stake_distribution() {
+    let weights = StakeDistribution::parse_weights("NodeID-a=3000, NodeID-b=1000").unwrap();
+    let d = StakeDistribution {
+        default_weight: 2000,
+        weights,
+    };
+    assert_eq!(d.target_weight("NodeID-a"), 3000);
+    assert_eq!(d.target_weight("NodeID-c"), 2000);
+    assert!(StakeDistribution::parse_weights("NodeID-a").is_err());
+    assert!(StakeDistribution::parse_weights("NodeID-a=x").is_err());
+}
+
 #[test]
 fn test_spec() {
     use std::fs;
@@ -843,12 +2258,19 @@ coreth_config:
                 String::from("r5.large"),
                 String::from("t3.large"),
             ]),
+            volume_size_in_gb: None,
+            os_hardening: None,
+            network_tuning: None,
+            image_id: None,
         },
 
         install_artifacts: InstallArtifacts {
             avalanched_bin: avalanched_bin.to_string(),
             avalanchego_bin: avalanchego_bin.to_string(),
             plugins_dir: Some(plugins_dir.to_string()),
+            icm_relayer_bin: None,
+            require_signed_artifacts: false,
+            signing_public_key: None,
         },
 
         avalanchego_config,
@@ -856,6 +2278,32 @@ coreth_config:
         avalanchego_genesis_template: None,
 
         subnet_evm_genesis: None,
+        tracked_subnets: None,
+        elastic_subnet: None,
+        external_bootstrap_nodes: None,
+        chain_upgrades: None,
+        avalanchego_release: None,
+        maintenance_window: None,
+        max_offline_stake_percent: None,
+        stake_distribution: None,
+        validator_expiry: None,
+        api_exposure: None,
+        indexer: None,
+        config_overrides: None,
+        canary: None,
+        remediation: None,
+        agent_metrics: None,
+        otlp: None,
+        icm_relayer: None,
+        public_snapshot: None,
+        peer_allowlist: None,
+        static_node_keys: None,
+        prefunded_keys_on_nodes: None,
+        usage_report: None,
+        restore_from_snapshot: None,
+        ci_preview: None,
+        auto_delete_after: None,
+        interpolations: Vec::new(),
 
         generated_seed_private_key_with_locked_p_chain_balance: None,
         generated_seed_private_keys: None,
@@ -928,14 +2376,27 @@ pub enum StorageNamespace {
     ConfigFile(String),
     DevMachineConfigFile(String),
     Ec2AccessKeyCompressedEncrypted(String),
+    /// Subnet control key, envelope-encrypted with the KMS CMK.
+    SubnetControlKeyEncrypted(String, String),
+    /// Node API auth password, envelope-encrypted with the KMS CMK.
+    ApiAuthPasswordEncrypted(String),
+    /// ICM relayer key, envelope-encrypted with the KMS CMK.
+    IcmRelayerKeyEncrypted(String),
 
     /// Valid genesis file with initial stakers.
     /// Only updated after anchor nodes become active.
     GenesisFile(String),
 
+    InstallDir(String),
     AvalanchedBin(String),
     AvalancheBinCompressed(String),
     PluginsDir(String),
+    /// ICM relayer binary, only downloaded by the relayer instances.
+    IcmRelayerBinCompressed(String),
+    /// VM plugins built by "build-vm", outside "PluginsDir" so the nodes never download it.
+    PluginsManifest(String),
+    /// Outputs of the "build-vm" builder, with the build ID.
+    VmBuildDir(String, String),
 
     PkiKeyDir(String),
 
@@ -961,6 +2422,61 @@ pub enum StorageNamespace {
     EventsUpdateArtifactsEvent(String),
     EventsUpdateArtifactsInstallDirAvalancheBinCompressed(String),
     EventsUpdateArtifactsInstallDirPluginsDir(String),
+    EventsUpdateArtifactsInstallDirIcmRelayerBinCompressed(String),
+
+    /// Upgrade files for the chains, with the chain ID.
+    ChainUpgradesDir(String),
+    ChainUpgradeFile(String, String),
+    /// If this "event" file has been modified for the last x-min,
+    /// avalanched downloads the files in "ChainUpgradesDir"
+    /// and restarts the node if no activation has passed.
+    EventsUpdateChainUpgradesEvent(String),
+
+    /// Generated NodeID-based peer allowlist (see "peer_allowlist").
+    PeerAllowlist(String),
+    /// If this "event" file has been modified for the last x-min,
+    /// avalanched downloads "PeerAllowlist" and restarts the node
+    /// if its subnet configs change.
+    EventsUpdatePeerAllowlistEvent(String),
+
+    /// Release artifacts staged by "track-release", with the version.
+    ReleaseStagingDir(String, String),
+
+    /// Signed heartbeats from "avalanched", with the machine ID.
+    HeartbeatsDir(String),
+    Heartbeat(String, String),
+
+    /// Append-only audit records of the CLI operations, with the record name.
+    AuditDir(String),
+    AuditRecord(String, String),
+    /// Transcripts of the "ssh --record" sessions, with the session name.
+    SshSessionRecording(String, String),
+
+    /// Per-node log archives uploaded by "collect-logs", with the bundle ID
+    /// and the machine ID. Deleted once merged into the local bundle.
+    SupportBundleNode(String, String, String),
+
+    /// Reports of "avalanched check-db", with the report name.
+    DbCheckReport(String, String),
+
+    /// Network snapshots taken by "snapshot-network", with the snapshot ID.
+    NetworkSnapshotDir(String, String),
+
+    /// Node keys replicated by "failover --prepare" for the stand-by cluster,
+    /// in the same layout as the network snapshots.
+    FailoverDir(String),
+
+    /// Validator uptime samples of the "rewards" runs.
+    RewardsUptimeHistory(String),
+
+    /// Access logs delivered by the NLB ("aws_resources.nlb_access_logs").
+    NlbAccessLogsDir(String),
+
+    /// Daily usage of each node, with the UTC date ("YYYY-MM-DD") and the machine ID.
+    UsageNodesDir(String, String),
+    UsageNode(String, String, String),
+    /// Daily usage report of the cluster, with the UTC date and the format ("json" or "csv").
+    UsageReport(String, String, String),
 }
 
 impl StorageNamespace {
@@ -971,14 +2487,34 @@ impl StorageNamespace {
             StorageNamespace::Ec2AccessKeyCompressedEncrypted(id) => {
                 format!("{}/ec2-access-key.zstd.seal_aes_256.encrypted", id)
             }
+            StorageNamespace::SubnetControlKeyEncrypted(id, subnet_id) => {
+                format!(
+                    "{}/subnet-control-keys/{}.seal_aes_256.encrypted",
+                    id, subnet_id
+                )
+            }
+            StorageNamespace::ApiAuthPasswordEncrypted(id) => {
+                format!("{}/api-auth-password.seal_aes_256.encrypted", id)
+            }
+            StorageNamespace::IcmRelayerKeyEncrypted(id) => {
+                format!("{}/icm-relayer-key.seal_aes_256.encrypted", id)
+            }
 
             StorageNamespace::GenesisFile(id) => format!("{}/genesis.json", id),
 
+            StorageNamespace::InstallDir(id) => format!("{}/install", id),
             StorageNamespace::AvalanchedBin(id) => format!("{}/install/avalanched", id),
             StorageNamespace::AvalancheBinCompressed(id) => {
                 format!("{}/install/avalanche.zstd", id)
             }
             StorageNamespace::PluginsDir(id) => format!("{}/install/plugins", id),
+            StorageNamespace::IcmRelayerBinCompressed(id) => {
+                format!("{}/install/icm-relayer.zstd", id)
+            }
+            StorageNamespace::PluginsManifest(id) => {
+                format!("{}/install/plugins-manifest.yaml", id)
+            }
+            StorageNamespace::VmBuildDir(id, build_id) => format!("{}/vm-builds/{}", id, build_id),
 
             StorageNamespace::PkiKeyDir(id) => {
                 format!("{}/pki", id)
@@ -1050,6 +2586,61 @@ impl StorageNamespace {
             StorageNamespace::EventsUpdateArtifactsInstallDirPluginsDir(id) => {
                 format!("{}/events/update-artifacts/install/plugins", id)
             }
+            StorageNamespace::EventsUpdateArtifactsInstallDirIcmRelayerBinCompressed(id) => {
+                format!("{}/events/update-artifacts/install/icm-relayer.zstd", id)
+            }
+
+            StorageNamespace::ChainUpgradesDir(id) => format!("{}/chain-upgrades", id),
+            StorageNamespace::ChainUpgradeFile(id, chain_id) => {
+                format!("{}/chain-upgrades/{}/{}", id, chain_id, upgrade::FILE_NAME)
+            }
+            StorageNamespace::EventsUpdateChainUpgradesEvent(id) => {
+                format!("{}/events/update-chain-upgrades/event", id)
+            }
+
+            StorageNamespace::PeerAllowlist(id) => format!("{}/peer-allowlist.json", id),
+            StorageNamespace::EventsUpdatePeerAllowlistEvent(id) => {
+                format!("{}/events/update-peer-allowlist/event", id)
+            }
+
+            StorageNamespace::ReleaseStagingDir(id, version) => {
+                format!("{}/releases/{}", id, version)
+            }
+
+            StorageNamespace::HeartbeatsDir(id) => format!("{}/heartbeats", id),
+            StorageNamespace::Heartbeat(id, machine_id) => {
+                format!("{}/heartbeats/{}.json", id, machine_id)
+            }
+
+            StorageNamespace::AuditDir(id) => format!("{}/audit", id),
+            StorageNamespace::AuditRecord(id, name) => {
+                format!("{}/audit/{}.json", id, name)
+            }
+            StorageNamespace::SshSessionRecording(id, name) => {
+                format!("{}/audit/ssh-sessions/{}.log", id, name)
+            }
+
+            StorageNamespace::SupportBundleNode(id, bundle_id, machine_id) => {
+                format!("{}/support-bundles/{}/{}.tar.gz", id, bundle_id, machine_id)
+            }
+            StorageNamespace::DbCheckReport(id, name) => {
+                format!("{}/db-checks/{}.json", id, name)
+            }
+            StorageNamespace::NetworkSnapshotDir(id, snapshot_id) => {
+                format!("{}/snapshots/{}", id, snapshot_id)
+            }
+            StorageNamespace::FailoverDir(id) => format!("{}/failover", id),
+            StorageNamespace::RewardsUptimeHistory(id) => {
+                format!("{}/rewards/uptime-history.json", id)
+            }
+            StorageNamespace::NlbAccessLogsDir(id) => format!("{}/nlb-access-logs", id),
+            StorageNamespace::UsageNodesDir(id, date) => format!("{}/usage/{}/nodes", id, date),
+            StorageNamespace::UsageNode(id, date, machine_id) => {
+                format!("{}/usage/{}/nodes/{}.json", id, date, machine_id)
+            }
+            StorageNamespace::UsageReport(id, date, format) => {
+                format!("{}/usage/{}/usage.{}", id, date, format)
+            }
         }
     }
 
@@ -1125,3 +2716,94 @@ fn test_storage_path() {
     let node_parsed = StorageNamespace::parse_node_from_path(&storage_path).unwrap();
     assert_eq!(node, node_parsed);
 }
+
+#[test]
+fn test_redact() {
+    let contents = r#"
+id: test
+
+aws_resources:
+  region: us-west-2
+  s3_bucket: test
+  identity:
+    account_id: "123456789012"
+    role_arn: "arn:aws:iam::123456789012:role/admin"
+    user_id: AIDAEXAMPLE
+  kms_cmk_arn: "arn:aws:kms:us-west-2:123456789012:key/abc"
+  ec2_key_path: /tmp/ec2.key
+
+machine:
+  non_anchor_nodes: 1
+  instance_types:
+  - m5.large
+
+install_artifacts:
+  avalanched_bin: /tmp/avalanched
+  avalanchego_bin: /tmp/avalanchego
+
+avalanchego_config:
+  config-file: /etc/avalanche.config.json
+  network-id: 1
+  db-type: leveldb
+  db-dir: /avalanche-data
+  log-dir: /var/log/avalanche
+  log-level: INFO
+  http-port: 9650
+  http-host: 0.0.0.0
+  http-tls-enabled: false
+  staking-enabled: true
+  staking-port: 9651
+  staking-tls-key-file: "/etc/pki/tls/certs/avalanched.pki.key"
+  staking-tls-cert-file: "/etc/pki/tls/certs/avalanched.pki.crt"
+  snow-sample-size: 20
+  snow-quorum-size: 15
+  index-enabled: false
+  index-allow-incomplete: false
+  api-admin-enabled: true
+  api-info-enabled: true
+  api-keystore-enabled: true
+  api-metrics-enabled: true
+  api-health-enabled: true
+  api-ipcs-enabled: true
+  chain-config-dir: /etc/avalanche/configs/chains
+  subnet-config-dir: /etc/avalanche/configs/subnets
+  profile-dir: /var/log/avalanche-profile/avalanche
+
+coreth_config:
+  coreth-admin-api-enabled: true
+  metrics-enabled: true
+  log-level: "info"
+
+generated_seed_private_keys:
+- private_key: PrivateKey-secret
+  private_key_hex: "0xsecret"
+  x_address: X-avax1test
+  p_address: P-avax1test
+  c_address: C-avax1test
+  short_address: short-test
+  eth_address: "0xeth"
+"#;
+    let spec: Spec = serde_yaml::from_str(contents).unwrap();
+    let redacted = spec.redact().unwrap();
+    let encoded = redacted.encode_yaml().unwrap();
+    assert!(!encoded.contains("123456789012"));
+    assert!(!encoded.contains("AIDAEXAMPLE"));
+    assert!(!encoded.contains("/tmp/ec2.key"));
+    assert!(!encoded.contains("secret"));
+    // public addresses are kept
+    assert!(encoded.contains("P-avax1test"));
+
+    // consistently hashed
+    let aws_resources = redacted.aws_resources.unwrap();
+    assert_eq!(
+        aws_resources.identity.unwrap().account_id,
+        redact_hash("123456789012")
+    );
+    assert_eq!(
+        aws_resources.kms_cmk_arn.unwrap(),
+        format!(
+            "arn:aws:kms:us-west-2:{}:key/abc",
+            redact_hash("123456789012")
+        )
+    );
+}
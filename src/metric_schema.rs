@@ -0,0 +1,248 @@
+use std::{
+    io::{self, Error, ErrorKind},
+    sync::Arc,
+};
+
+use aws_sdk_cloudwatch::model::{Dimension, MetricDatum};
+use log::warn;
+
+use crate::{aws::cloudwatch, otlp};
+
+/// Metric schema of the clusters created before the dimension contract.
+/// The node metrics have no dimensions, and the clock offset and
+/// the remediation metrics have the "NodeKind" dimension only,
+/// so the nodes of a kind are indistinguishable.
+pub const VERSION_1: u32 = 1;
+
+/// Every metric is published three times, with the per-node dimensions
+/// ("ClusterId", "NodeKind", "NodeId", "AvailabilityZone"),
+/// the per-kind dimensions ("ClusterId", "NodeKind"), and the per-cluster
+/// dimensions ("ClusterId"), plus the metric-specific dimensions
+/// (e.g., "Action" of the remediation).
+/// The dashboards and the alarms query the per-kind or the per-cluster
+/// metrics, which survive the node replacement. "NodeId" is the avalanche node ID, not the instance ID,
+/// so the per-node metrics survive the instance replacement with "static_node_keys".
+/// "ClusterId" keeps the metrics self-describing when streamed or shared
+/// across accounts.
+pub const VERSION_2: u32 = 2;
+
+pub const CURRENT_VERSION: u32 = VERSION_2;
+
+pub const DIMENSION_CLUSTER_ID: &str = "ClusterId";
+pub const DIMENSION_NODE_KIND: &str = "NodeKind";
+pub const DIMENSION_NODE_ID: &str = "NodeId";
+pub const DIMENSION_AVAILABILITY_ZONE: &str = "AvailabilityZone";
+
+/// Returns the namespace of the cluster, so that the clusters in the same
+/// account do not collide and the instance role is scoped to its own
+/// namespace (e.g., "avalanche-ops/aops-custom-202208-2vNYM").
+pub fn namespace(cluster_id: &str) -> String {
+    format!("avalanche-ops/{}", cluster_id)
+}
+
+/// Returns the namespace of the "VERSION_1" clusters.
+pub fn legacy_namespace(cluster_id: &str) -> String {
+    format!("{}-avalanche", cluster_id)
+}
+
+pub fn validate_version(version: u32) -> io::Result<()> {
+    if version != VERSION_1 && version != VERSION_2 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "unknown 'cloudwatch_metrics_schema_version' {} (expected {} or {})",
+                version, VERSION_1, VERSION_2
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Represents the schema dimensions of the node.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Dimensions {
+    pub cluster_id: String,
+    pub node_kind: String,
+    pub node_id: String,
+    pub availability_zone: String,
+}
+
+impl Dimensions {
+    pub fn per_node(&self) -> Vec<Dimension> {
+        vec![
+            dimension(DIMENSION_CLUSTER_ID, &self.cluster_id),
+            dimension(DIMENSION_NODE_KIND, &self.node_kind),
+            dimension(DIMENSION_NODE_ID, &self.node_id),
+            dimension(DIMENSION_AVAILABILITY_ZONE, &self.availability_zone),
+        ]
+    }
+
+    pub fn per_kind(&self) -> Vec<Dimension> {
+        vec![
+            dimension(DIMENSION_CLUSTER_ID, &self.cluster_id),
+            dimension(DIMENSION_NODE_KIND, &self.node_kind),
+        ]
+    }
+
+    pub fn per_cluster(&self) -> Vec<Dimension> {
+        vec![dimension(DIMENSION_CLUSTER_ID, &self.cluster_id)]
+    }
+}
+
+fn dimension(name: &str, value: &str) -> Dimension {
+    Dimension::builder().name(name).value(value).build()
+}
+
+/// Converts the "VERSION_1" metrics to the "VERSION_2" schema.
+/// The schema dimensions replace the ones of the same names,
+/// and the metric-specific dimensions are kept.
+pub fn to_version_2(dims: &Dimensions, data: &[MetricDatum]) -> Vec<MetricDatum> {
+    let schema = [
+        DIMENSION_CLUSTER_ID,
+        DIMENSION_NODE_KIND,
+        DIMENSION_NODE_ID,
+        DIMENSION_AVAILABILITY_ZONE,
+    ];
+    let mut converted = Vec::with_capacity(data.len() * 3);
+    for d in data.iter() {
+        let extra: Vec<Dimension> = d
+            .dimensions
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|v| !schema.contains(&v.name.as_deref().unwrap_or_default()))
+            .collect();
+        for schema_dims in [dims.per_node(), dims.per_kind(), dims.per_cluster()] {
+            let mut datum = d.clone();
+            datum.dimensions = Some([schema_dims, extra.clone()].concat());
+            converted.push(datum);
+        }
+    }
+    converted
+}
+
+/// Publishes the node metrics in the schema of the cluster.
+/// With "legacy_namespace", also publishes the "VERSION_1" metrics there,
+/// so the existing dashboards and alarms keep working during the migration.
+#[derive(Debug, Clone)]
+pub struct Publisher {
+    pub cw_manager: cloudwatch::Manager,
+    pub namespace: String,
+    pub version: u32,
+    pub dimensions: Dimensions,
+    pub legacy_namespace: Option<String>,
+    /// Exports the same data to the OTLP endpoint, if "otlp.metrics" is set.
+    pub otlp: Option<Arc<otlp::Exporter>>,
+}
+
+impl Publisher {
+    /// Publishes the "VERSION_1" metrics of the producers.
+    pub async fn publish(&self, data: Vec<MetricDatum>) -> crate::errors::Result<()> {
+        if let Some(exporter) = &self.otlp {
+            if let Err(e) = exporter.export_metrics(&data).await {
+                warn!("failed to export OTLP metrics {}", e);
+            }
+            if exporter.cfg.skips_cloudwatch_metrics() {
+                return Ok(());
+            }
+        }
+
+        if self.version == VERSION_1 {
+            return cloudwatch::spawn_put_metric_data(
+                self.cw_manager.clone(),
+                &self.namespace,
+                data,
+            )
+            .await;
+        }
+
+        if let Some(legacy_namespace) = &self.legacy_namespace {
+            if let Err(e) = cloudwatch::spawn_put_metric_data(
+                self.cw_manager.clone(),
+                legacy_namespace,
+                data.clone(),
+            )
+            .await
+            {
+                warn!("failed to put legacy metric data {}", e);
+            }
+        }
+        cloudwatch::spawn_put_metric_data(
+            self.cw_manager.clone(),
+            &self.namespace,
+            to_version_2(&self.dimensions, &data),
+        )
+        .await
+    }
+}
+
+#[test]
+fn test_metric_schema() {
+    use aws_sdk_cloudwatch::model::StandardUnit;
+
+    assert_eq!(
+        namespace("aops-custom-test"),
+        "avalanche-ops/aops-custom-test"
+    );
+    assert_eq!(
+        legacy_namespace("aops-custom-test"),
+        "aops-custom-test-avalanche"
+    );
+    assert!(validate_version(VERSION_1).is_ok());
+    assert!(validate_version(VERSION_2).is_ok());
+    assert!(validate_version(3).is_err());
+
+    let dims = Dimensions {
+        cluster_id: String::from("aops-custom-test"),
+        node_kind: String::from("anchor"),
+        node_id: String::from("NodeID-test"),
+        availability_zone: String::from("us-west-2a"),
+    };
+    let data = vec![
+        MetricDatum::builder()
+            .metric_name("avalanche_network_peers")
+            .value(5_f64)
+            .unit(StandardUnit::Count)
+            .build(),
+        MetricDatum::builder()
+            .metric_name("remediation_actions")
+            .dimensions(dimension(DIMENSION_NODE_KIND, "anchor"))
+            .dimensions(dimension("Action", "restart-node"))
+            .value(1_f64)
+            .unit(StandardUnit::Count)
+            .build(),
+    ];
+    let converted = to_version_2(&dims, &data);
+    assert_eq!(converted.len(), 6);
+
+    let names = |d: &MetricDatum| -> Vec<String> {
+        d.dimensions
+            .clone()
+            .unwrap()
+            .into_iter()
+            .map(|v| v.name.unwrap())
+            .collect()
+    };
+    assert_eq!(
+        names(&converted[0]),
+        vec!["ClusterId", "NodeKind", "NodeId", "AvailabilityZone"]
+    );
+    assert_eq!(names(&converted[1]), vec!["ClusterId", "NodeKind"]);
+    assert_eq!(names(&converted[2]), vec!["ClusterId"]);
+    assert_eq!(
+        names(&converted[3]),
+        vec![
+            "ClusterId",
+            "NodeKind",
+            "NodeId",
+            "AvailabilityZone",
+            "Action"
+        ]
+    );
+    assert_eq!(
+        names(&converted[4]),
+        vec!["ClusterId", "NodeKind", "Action"]
+    );
+    assert_eq!(names(&converted[5]), vec!["ClusterId", "Action"]);
+    assert_eq!(converted[5].value, Some(1_f64));
+}
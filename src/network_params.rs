@@ -0,0 +1,203 @@
+use std::io::{self, Error, ErrorKind};
+
+use crate::{
+    avalanche::{
+        avalanchego::api::{info, platform},
+        platformvm::txs::{self, TxFees, AVAX},
+    },
+    delegation, Spec, StakeDistribution,
+};
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// Staking parameters of the primary network, to validate the stakes
+/// before issuing the transactions that the network would reject.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct NetworkParams {
+    pub network_id: u32,
+    pub min_validator_stake: u64,
+    pub min_delegator_stake: u64,
+    pub max_validator_stake: u64,
+    pub max_validator_weight_factor: u64,
+    /// In seconds, for both the validators and the delegators.
+    pub min_stake_duration: u64,
+    /// In seconds, for both the validators and the delegators.
+    pub max_stake_duration: u64,
+    pub tx_fees: TxFees,
+}
+
+impl NetworkParams {
+    /// Returns the parameters hard-coded in avalanchego genesis parameters.
+    /// Custom networks use the local network parameters.
+    /// ref. https://github.com/ava-labs/avalanchego/blob/v1.9.0/genesis/params.go
+    pub fn new(network_id: u32) -> Self {
+        let (min_validator_stake, min_delegator_stake) = match network_id {
+            5 => (AVAX, AVAX),
+            _ => (2_000 * AVAX, 25 * AVAX),
+        };
+        let min_stake_duration = match network_id {
+            1 => 14 * DAY,
+            _ => DAY,
+        };
+        Self {
+            network_id,
+            min_validator_stake,
+            min_delegator_stake,
+            max_validator_stake: delegation::MAX_VALIDATOR_STAKE,
+            max_validator_weight_factor: delegation::MAX_VALIDATOR_WEIGHT_FACTOR,
+            min_stake_duration,
+            max_stake_duration: 365 * DAY,
+            tx_fees: TxFees::new(network_id),
+        }
+    }
+
+    /// Fetches the min stakes and the transaction fees from the network.
+    /// The APIs do not expose the max stake and the staking durations,
+    /// so those remain the genesis parameters of the network.
+    pub async fn fetch(http_rpc: &str, network_id: u32) -> io::Result<Self> {
+        let mut params = Self::new(network_id);
+        let (min_validator_stake, min_delegator_stake) = platform::get_min_stake(http_rpc).await?;
+        params.min_validator_stake = min_validator_stake;
+        params.min_delegator_stake = min_delegator_stake;
+
+        let fee = info::get_tx_fee(http_rpc).await?;
+        params.tx_fees = TxFees {
+            tx_fee: fee.tx_fee,
+            create_subnet_tx_fee: fee.create_subnet_tx_fee,
+            transform_subnet_tx_fee: fee.transform_subnet_tx_fee,
+            add_subnet_validator_fee: fee.add_subnet_validator_fee,
+            add_subnet_delegator_fee: fee.add_subnet_delegator_fee,
+            add_primary_network_validator_fee: fee.add_primary_network_validator_fee,
+            add_primary_network_delegator_fee: fee.add_primary_network_delegator_fee,
+        };
+        Ok(params)
+    }
+
+    pub fn delegation_limits(&self) -> delegation::Limits {
+        delegation::Limits {
+            min_delegator_stake: self.min_delegator_stake,
+            max_validator_stake: self.max_validator_stake,
+            max_validator_weight_factor: self.max_validator_weight_factor,
+        }
+    }
+
+    /// Validates the primary network validator stake and duration in seconds.
+    pub fn validate_validator(&self, weight: u64, duration: u64) -> io::Result<()> {
+        if weight < self.min_validator_stake || weight > self.max_validator_stake {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "validator stake {} not in [{}, {}] of network {}",
+                    weight, self.min_validator_stake, self.max_validator_stake, self.network_id
+                ),
+            ));
+        }
+        self.validate_duration(duration)
+    }
+
+    /// Validates the primary network delegator stake and duration in seconds.
+    /// The validator-specific limits are checked by "delegation::Limits".
+    pub fn validate_delegator(&self, weight: u64, duration: u64) -> io::Result<()> {
+        if weight < self.min_delegator_stake {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "delegator stake {} < min delegator stake {} of network {}",
+                    weight, self.min_delegator_stake, self.network_id
+                ),
+            ));
+        }
+        self.validate_duration(duration)
+    }
+
+    fn validate_duration(&self, duration: u64) -> io::Result<()> {
+        if duration < self.min_stake_duration || duration > self.max_stake_duration {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "stake duration {} seconds not in [{}, {}] of network {}",
+                    duration, self.min_stake_duration, self.max_stake_duration, self.network_id
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validates the elastic subnet staking against the primary network,
+    /// which the subnet validators must also validate.
+    pub fn validate_transform_subnet(&self, params: &txs::TransformSubnetParams) -> io::Result<()> {
+        if params.max_stake_duration as u64 > self.max_stake_duration {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "subnet 'max_stake_duration' {} > primary network max stake duration {}",
+                    params.max_stake_duration, self.max_stake_duration
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validates the target stakes of "rebalance".
+    pub fn validate_stake_distribution(&self, distribution: &StakeDistribution) -> io::Result<()> {
+        let weights = std::iter::once(("default_weight", &distribution.default_weight))
+            .chain(distribution.weights.iter().map(|(k, v)| (k.as_str(), v)));
+        for (name, weight) in weights {
+            if *weight < self.min_validator_stake || *weight > self.max_validator_stake {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "'stake_distribution' {} {} not in [{}, {}] of network {}",
+                        name,
+                        weight,
+                        self.min_validator_stake,
+                        self.max_validator_stake,
+                        self.network_id
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates the staking amounts and durations in the spec.
+    pub fn validate_spec(&self, spec: &Spec) -> io::Result<()> {
+        if let Some(distribution) = &spec.stake_distribution {
+            self.validate_stake_distribution(distribution)?;
+        }
+        if let Some(elastic_subnet) = &spec.elastic_subnet {
+            self.validate_transform_subnet(&elastic_subnet.params)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_network_params() {
+    let mainnet = NetworkParams::new(1);
+    assert!(mainnet.validate_validator(2_000 * AVAX, 14 * DAY).is_ok());
+    assert!(mainnet.validate_validator(1_999 * AVAX, 14 * DAY).is_err());
+    assert!(mainnet.validate_validator(2_000 * AVAX, 13 * DAY).is_err());
+    assert!(mainnet.validate_validator(2_000 * AVAX, 366 * DAY).is_err());
+    assert!(mainnet
+        .validate_validator(3_000_001 * AVAX, 14 * DAY)
+        .is_err());
+    assert!(mainnet.validate_delegator(25 * AVAX, 14 * DAY).is_ok());
+    assert!(mainnet.validate_delegator(24 * AVAX, 14 * DAY).is_err());
+
+    let fuji = NetworkParams::new(5);
+    assert!(fuji.validate_validator(AVAX, DAY).is_ok());
+    assert!(fuji.validate_delegator(AVAX, DAY).is_ok());
+    assert_eq!(fuji.delegation_limits().min_delegator_stake, AVAX);
+
+    let mut distribution = StakeDistribution {
+        default_weight: 2_000 * AVAX,
+        weights: std::collections::BTreeMap::new(),
+    };
+    assert!(mainnet.validate_stake_distribution(&distribution).is_ok());
+    distribution
+        .weights
+        .insert(String::from("NodeID-A"), 1_000 * AVAX);
+    assert!(mainnet.validate_stake_distribution(&distribution).is_err());
+    assert!(fuji.validate_stake_distribution(&distribution).is_ok());
+}
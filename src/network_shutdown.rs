@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use crate::{avalanche::node, StorageNamespace};
+
+/// Represents a group of nodes that "stop-network" stops together,
+/// after the groups before it.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Stage {
+    pub name: &'static str,
+    pub nodes: Vec<node::Node>,
+}
+
+/// Returns the stop order of the nodes: the API nodes (the non-anchor nodes
+/// behind the NLB) first, so the clients fail over before the network stalls,
+/// then the rest of the non-anchor nodes, and the anchor nodes last,
+/// so the other nodes never lose their bootstrap beacons while running.
+/// Skips the empty stages.
+pub fn stop_stages(nodes: &[node::Node], is_api_node: impl Fn(&node::Kind) -> bool) -> Vec<Stage> {
+    let anchor = node::Kind::Anchor.as_str();
+    let api_non_anchor = is_api_node(&node::Kind::NonAnchor);
+    let mut api = Vec::new();
+    let mut non_anchors = Vec::new();
+    let mut anchors = Vec::new();
+    for n in nodes.iter() {
+        if n.kind == anchor {
+            anchors.push(n.clone());
+        } else if api_non_anchor {
+            api.push(n.clone());
+        } else {
+            non_anchors.push(n.clone());
+        }
+    }
+    vec![
+        Stage {
+            name: "api",
+            nodes: api,
+        },
+        Stage {
+            name: "non-anchor",
+            nodes: non_anchors,
+        },
+        Stage {
+            name: "anchor",
+            nodes: anchors,
+        },
+    ]
+    .into_iter()
+    .filter(|s| !s.nodes.is_empty())
+    .collect()
+}
+
+/// Returns the start order, the reverse of the stop order,
+/// so the anchor nodes are healthy before the others bootstrap from them.
+pub fn start_stages(nodes: &[node::Node], is_api_node: impl Fn(&node::Kind) -> bool) -> Vec<Stage> {
+    let mut stages = stop_stages(nodes, is_api_node);
+    stages.reverse();
+    stages
+}
+
+/// Returns the S3 key of the final database backup of the node.
+pub fn backup_key(cluster_id: &str, stop_id: &str, node_id: &str) -> String {
+    format!(
+        "{}/stop-network/{}/{}.tar.gz",
+        StorageNamespace::BackupsDir(cluster_id.to_string()).encode(),
+        stop_id,
+        node_id
+    )
+}
+
+/// Generates the bash script that waits for the local node to report healthy,
+/// which fails after the timeout.
+pub fn wait_healthy_script(http_scheme: &str, http_port: u32, timeout: Duration) -> String {
+    let lines = [
+        String::from("set -uo pipefail"),
+        format!("for _ in $(seq 1 {}); do", (timeout.as_secs() / 10).max(1)),
+        format!(
+            "  if curl -sk {}://127.0.0.1:{}/ext/health | grep -q '\"healthy\":true'; then exit 0; fi",
+            http_scheme, http_port
+        ),
+        String::from("  sleep 10"),
+        String::from("done"),
+        String::from("echo 'node is not healthy' >&2"),
+        String::from("exit 1"),
+    ];
+    lines.join("\n")
+}
+
+#[test]
+fn test_network_shutdown() {
+    let node = |kind: node::Kind, machine_id: &str| {
+        node::Node::new(kind, machine_id, machine_id, "1.2.3.4", "http", 9650)
+    };
+    let nodes = vec![
+        node(node::Kind::Anchor, "i-a1"),
+        node(node::Kind::NonAnchor, "i-n1"),
+        node(node::Kind::Anchor, "i-a2"),
+        node(node::Kind::NonAnchor, "i-n2"),
+    ];
+
+    let stages = stop_stages(&nodes, |_| true);
+    let names: Vec<&str> = stages.iter().map(|s| s.name).collect();
+    assert_eq!(names, vec!["api", "anchor"]);
+    assert_eq!(stages[0].nodes.len(), 2);
+    assert!(stages[1].nodes.iter().all(|n| n.kind == "anchor"));
+
+    let stages = stop_stages(&nodes, |kind| *kind == node::Kind::Anchor);
+    let names: Vec<&str> = stages.iter().map(|s| s.name).collect();
+    assert_eq!(names, vec!["non-anchor", "anchor"]);
+
+    let stages = start_stages(&nodes, |_| false);
+    let names: Vec<&str> = stages.iter().map(|s| s.name).collect();
+    assert_eq!(names, vec!["anchor", "non-anchor"]);
+
+    assert_eq!(
+        backup_key("aops-custom-abc", "20220501000000", "NodeID-abc"),
+        "aops-custom-abc/backups/stop-network/20220501000000/NodeID-abc.tar.gz"
+    );
+
+    let script = wait_healthy_script("https", 9650, Duration::from_secs(600));
+    assert!(script.contains("seq 1 60"));
+    assert!(script.contains("https://127.0.0.1:9650/ext/health"));
+}
@@ -0,0 +1,239 @@
+use std::io::{self, Error, ErrorKind};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aws, node_keys,
+    utils::{id, rfc3339, time},
+    Spec, StorageNamespace,
+};
+
+/// Represents the network snapshot to restore the new cluster from.
+/// Set by "restore-network", and read by "avalanched" on the first boot
+/// of each node slot, so the restored nodes keep the node IDs, the genesis,
+/// and the database of the snapshot.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Source {
+    pub region: String,
+    pub s3_bucket: String,
+    /// Snapshot directory (e.g., "[ID]/snapshots/[SNAPSHOT_ID]").
+    pub s3_prefix: String,
+    /// KMS key that encrypted the node keys of the snapshot.
+    pub kms_key_arn: String,
+}
+
+impl Source {
+    pub fn s3_url(&self) -> String {
+        format!("s3://{}/{}", self.s3_bucket, self.s3_prefix)
+    }
+}
+
+/// Represents a node captured in the snapshot, by the node slot.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Node {
+    /// Node slot name (e.g., "anchor-0").
+    pub slot: String,
+    pub node_id: String,
+    pub kind: String,
+}
+
+/// Represents the manifest of the network snapshot,
+/// written last once all nodes are captured.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Manifest {
+    pub snapshot_id: String,
+    pub cluster_id: String,
+    pub network_id: u32,
+    #[serde(with = "rfc3339::serde_format")]
+    pub created_at: DateTime<Utc>,
+    /// KMS key that encrypted the node keys in the snapshot.
+    pub kms_key_arn: String,
+    pub nodes: Vec<Node>,
+}
+
+impl Manifest {
+    pub fn encode_yaml(&self) -> io::Result<String> {
+        serde_yaml::to_string(self)
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to encode {}", e)))
+    }
+
+    pub fn decode_yaml(d: &[u8]) -> io::Result<Self> {
+        serde_yaml::from_slice(d).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid snapshot manifest {}", e),
+            )
+        })
+    }
+}
+
+/// Returns the snapshot directory of the cluster.
+pub fn dir(cluster_id: &str, snapshot_id: &str) -> String {
+    StorageNamespace::NetworkSnapshotDir(cluster_id.to_string(), snapshot_id.to_string()).encode()
+}
+
+pub fn manifest_key(dir: &str) -> String {
+    format!("{}/manifest.yaml", dir)
+}
+
+pub fn spec_key(dir: &str) -> String {
+    format!("{}/spec.yaml", dir)
+}
+
+pub fn genesis_key(dir: &str) -> String {
+    format!("{}/genesis.json", dir)
+}
+
+/// Returns the S3 key of the database archive of the node slot.
+pub fn db_key(dir: &str, slot: &str) -> String {
+    format!("{}/db/{}.tar.gz", dir, slot)
+}
+
+/// Returns the S3 keys of the node keys of the slot, in the same format as
+/// the cluster "pki" directory.
+pub fn node_keys(dir: &str, slot: &str) -> node_keys::S3Keys {
+    node_keys::S3Keys::in_dir(&format!("{}/pki", dir), slot)
+}
+
+/// Parses the snapshot URL (e.g., "s3://bucket/[ID]/snapshots/[SNAPSHOT_ID]")
+/// into the bucket and the snapshot directory.
+pub fn parse_s3_url(url: &str) -> io::Result<(String, String)> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "invalid snapshot URL '{}' (e.g., 's3://bucket/[ID]/snapshots/[SNAPSHOT_ID]')",
+                url
+            ),
+        )
+    };
+    let (bucket, prefix) = url
+        .strip_prefix("s3://")
+        .and_then(|s| s.split_once('/'))
+        .ok_or_else(invalid)?;
+    let prefix = prefix.trim_end_matches('/');
+    if bucket.is_empty() || prefix.is_empty() {
+        return Err(invalid());
+    }
+    Ok((bucket.to_string(), prefix.to_string()))
+}
+
+/// Generates the bash script for SSM "AWS-RunShellScript", which stops
+/// "avalanched" first (so it does not restart the node) and "avalanchego".
+pub fn pause_script() -> String {
+    String::from("sudo systemctl stop avalanched.service avalanche.service")
+}
+
+/// Generates the bash script that streams the database archive of the paused
+/// node to "s3_url" with the instance role.
+pub fn upload_db_script(db_dir: &str, s3_url: &str) -> String {
+    let lines = [
+        String::from("set -euo pipefail"),
+        format!("SIZE=$(sudo du -sb {} | cut -f1)", db_dir),
+        // streamed, so the archive does not need the free disk space
+        format!(
+            "sudo tar -czf - -C {} . | aws s3 cp --only-show-errors --expected-size $SIZE - {}",
+            db_dir, s3_url
+        ),
+    ];
+    lines.join("\n")
+}
+
+/// Generates the bash script that restarts "avalanched", which restarts "avalanchego".
+pub fn resume_script() -> String {
+    String::from("sudo systemctl start --no-block avalanched.service")
+}
+
+/// Returns the spec of the new cluster to restore the snapshot into.
+/// Keeps the network configuration (e.g., genesis, prefunded keys, node counts),
+/// and resets the cluster resources so "apply" creates them from scratch.
+pub fn restored_spec(snapshot_spec: &Spec, cluster_id: &str, region: &str, source: Source) -> Spec {
+    let mut spec = snapshot_spec.clone();
+    spec.id = cluster_id.to_string();
+
+    let prev = snapshot_spec.aws_resources.clone().unwrap_or_default();
+    let same_region = prev.region == region;
+    spec.aws_resources = Some(aws::Resources {
+        region: region.to_string(),
+        s3_bucket: id::system_with_prefix("avalanche-ops", &time::get(6), 10),
+        s3_lifecycle: prev.s3_lifecycle,
+        s3_transfer_limits: prev.s3_transfer_limits,
+        instance_system_logs: prev.instance_system_logs,
+        instance_system_metrics: prev.instance_system_metrics,
+        // ACM certificates are regional
+        nlb_acm_certificate_arn: if same_region {
+            prev.nlb_acm_certificate_arn
+        } else {
+            None
+        },
+        nlb_target_group: prev.nlb_target_group,
+        nlb_cross_zone_load_balancing: prev.nlb_cross_zone_load_balancing,
+        nlb_tcp_idle_timeout_seconds: prev.nlb_tcp_idle_timeout_seconds,
+        nlb_access_logs: if same_region {
+            prev.nlb_access_logs
+        } else {
+            None
+        },
+        clock_offset_alarm_threshold_ms: prev.clock_offset_alarm_threshold_ms,
+        vpc_endpoints: prev.vpc_endpoints,
+        elastic_ip_pool: prev.elastic_ip_pool,
+        ec2_key_import_public_key: prev.ec2_key_import_public_key,
+        ..aws::Resources::default()
+    });
+
+    // the node slots map the snapshot nodes to the restored instances
+    spec.static_node_keys = Some(true);
+    spec.restore_from_snapshot = Some(source);
+
+    spec.current_nodes = None;
+    spec.endpoints = None;
+    spec
+}
+
+#[test]
+fn test_network_snapshot() {
+    let d = dir("test", "20221001");
+    assert_eq!(d, "test/snapshots/20221001");
+    assert_eq!(manifest_key(&d), "test/snapshots/20221001/manifest.yaml");
+    assert_eq!(
+        db_key(&d, "anchor-0"),
+        "test/snapshots/20221001/db/anchor-0.tar.gz"
+    );
+    assert_eq!(
+        node_keys(&d, "anchor-0").tls_cert,
+        "test/snapshots/20221001/pki/anchor-0.crt"
+    );
+
+    assert_eq!(
+        parse_s3_url("s3://bucket/test/snapshots/20221001/").unwrap(),
+        (
+            String::from("bucket"),
+            String::from("test/snapshots/20221001")
+        )
+    );
+    assert!(parse_s3_url("bucket/test").is_err());
+    assert!(parse_s3_url("s3://bucket").is_err());
+
+    let script = upload_db_script("/data", "s3://bucket/db.tar.gz");
+    assert!(script.contains("tar -czf - -C /data ."));
+    assert!(script.contains("- s3://bucket/db.tar.gz"));
+
+    let manifest = Manifest {
+        snapshot_id: String::from("20221001"),
+        cluster_id: String::from("test"),
+        network_id: 1000,
+        created_at: Utc::now(),
+        kms_key_arn: String::from("arn:aws:kms:us-west-2:123:key/abc"),
+        nodes: vec![Node {
+            slot: String::from("anchor-0"),
+            node_id: String::from("NodeID-1"),
+            kind: String::from("anchor"),
+        }],
+    };
+    let decoded = Manifest::decode_yaml(manifest.encode_yaml().unwrap().as_bytes()).unwrap();
+    assert_eq!(decoded.nodes, manifest.nodes);
+}
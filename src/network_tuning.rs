@@ -0,0 +1,203 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Default "net.core.somaxconn", for the bursts of the peer connections.
+pub const DEFAULT_SOMAXCONN: u32 = 4096;
+/// Default "net.core.rmem_max" and "net.core.wmem_max" (16 MiB).
+pub const DEFAULT_SOCKET_BUFFER_MAX_BYTES: u64 = 16 * 1024 * 1024;
+/// Default "net.netfilter.nf_conntrack_max".
+pub const DEFAULT_CONNTRACK_MAX: u64 = 262144;
+/// Default "LimitNOFILE" of the avalanche node systemd service.
+pub const DEFAULT_NOFILE_LIMIT: u64 = 65536;
+/// "LimitNOFILE" of the avalanche node systemd service if the tuning is not set.
+pub const UNTUNED_NOFILE_LIMIT: u64 = 40000;
+
+pub const MIN_NOFILE_LIMIT: u64 = 4096;
+pub const MAX_NOFILE_LIMIT: u64 = 1048576;
+
+/// Path to the sysctl file that "avalanched" writes on boot.
+pub const SYSCTL_FILE_PATH: &str = "/etc/sysctl.d/99-avalanche-ops.conf";
+
+/// Represents the kernel network tunings that "avalanched" applies on boot,
+/// before starting the avalanche node service. The unset fields use
+/// the defaults for the high peer count nodes.
+/// ref. https://www.kernel.org/doc/Documentation/networking/ip-sysctl.txt
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct NetworkTuning {
+    /// "net.core.somaxconn", the accept queue length of the listeners.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub somaxconn: Option<u32>,
+    /// "net.core.rmem_max", and the max of "net.ipv4.tcp_rmem".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rmem_max_bytes: Option<u64>,
+    /// "net.core.wmem_max", and the max of "net.ipv4.tcp_wmem".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wmem_max_bytes: Option<u64>,
+    /// "net.netfilter.nf_conntrack_max" (only if the conntrack module is loaded).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conntrack_max: Option<u64>,
+    /// "LimitNOFILE" of the avalanche node systemd service.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nofile_limit: Option<u64>,
+}
+
+/// Represents the result of verifying a sysctl parameter on the node.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Check {
+    pub key: String,
+    pub expected: String,
+    /// "None" if the parameter does not exist (e.g., the module is not loaded).
+    pub actual: Option<String>,
+}
+
+impl Check {
+    pub fn is_ok(&self) -> bool {
+        match &self.actual {
+            // e.g., "4096\t87380\t16777216" for "tcp_rmem"
+            Some(v) => v.split_whitespace().eq(self.expected.split_whitespace()),
+            None => false,
+        }
+    }
+}
+
+impl NetworkTuning {
+    pub fn nofile_limit(&self) -> u64 {
+        self.nofile_limit.unwrap_or(DEFAULT_NOFILE_LIMIT)
+    }
+
+    /// Returns the sysctl parameters to apply, in order.
+    pub fn params(&self) -> Vec<(String, String)> {
+        let rmem = self
+            .rmem_max_bytes
+            .unwrap_or(DEFAULT_SOCKET_BUFFER_MAX_BYTES);
+        let wmem = self
+            .wmem_max_bytes
+            .unwrap_or(DEFAULT_SOCKET_BUFFER_MAX_BYTES);
+        vec![
+            (
+                String::from("net.core.somaxconn"),
+                self.somaxconn.unwrap_or(DEFAULT_SOMAXCONN).to_string(),
+            ),
+            (
+                String::from("net.ipv4.tcp_max_syn_backlog"),
+                self.somaxconn.unwrap_or(DEFAULT_SOMAXCONN).to_string(),
+            ),
+            (String::from("net.core.rmem_max"), rmem.to_string()),
+            (String::from("net.core.wmem_max"), wmem.to_string()),
+            (
+                String::from("net.ipv4.tcp_rmem"),
+                format!("4096 87380 {}", rmem),
+            ),
+            (
+                String::from("net.ipv4.tcp_wmem"),
+                format!("4096 65536 {}", wmem),
+            ),
+            (
+                String::from("net.netfilter.nf_conntrack_max"),
+                self.conntrack_max
+                    .unwrap_or(DEFAULT_CONNTRACK_MAX)
+                    .to_string(),
+            ),
+        ]
+    }
+
+    /// Returns the contents of "SYSCTL_FILE_PATH".
+    pub fn sysctl_conf(&self) -> String {
+        let mut lines = vec![String::from("# generated by avalanched")];
+        for (k, v) in self.params().iter() {
+            lines.push(format!("{} = {}", k, v));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    pub fn validate(&self) -> io::Result<()> {
+        if self.somaxconn == Some(0) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'machine.network_tuning.somaxconn' must be positive",
+            ));
+        }
+        for (name, v) in [
+            ("rmem_max_bytes", self.rmem_max_bytes),
+            ("wmem_max_bytes", self.wmem_max_bytes),
+        ] {
+            if let Some(v) = v {
+                if v < 87380 {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "'machine.network_tuning.{}' {} must be >=87380 (the default TCP buffer)",
+                            name, v
+                        ),
+                    ));
+                }
+            }
+        }
+        let nofile = self.nofile_limit();
+        if !(MIN_NOFILE_LIMIT..=MAX_NOFILE_LIMIT).contains(&nofile) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'machine.network_tuning.nofile_limit' {} not in [{}, {}]",
+                    nofile, MIN_NOFILE_LIMIT, MAX_NOFILE_LIMIT
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reads back the sysctl parameters from "/proc/sys",
+    /// to report the tunings that the kernel did not take.
+    pub fn verify(&self) -> Vec<Check> {
+        self.params()
+            .into_iter()
+            .map(|(key, expected)| {
+                let p = Path::new("/proc/sys").join(key.replace('.', "/"));
+                let actual = fs::read_to_string(p).ok().map(|v| v.trim().to_string());
+                Check {
+                    key,
+                    expected,
+                    actual,
+                }
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_network_tuning() {
+    let t = NetworkTuning::default();
+    assert!(t.validate().is_ok());
+    assert_eq!(t.nofile_limit(), DEFAULT_NOFILE_LIMIT);
+
+    let conf = t.sysctl_conf();
+    assert!(conf.contains("net.core.somaxconn = 4096"));
+    assert!(conf.contains("net.ipv4.tcp_rmem = 4096 87380 16777216"));
+    assert!(conf.contains("net.netfilter.nf_conntrack_max = 262144"));
+
+    let t = NetworkTuning {
+        rmem_max_bytes: Some(1024),
+        ..NetworkTuning::default()
+    };
+    assert!(t.validate().is_err());
+    let t = NetworkTuning {
+        nofile_limit: Some(100),
+        ..NetworkTuning::default()
+    };
+    assert!(t.validate().is_err());
+
+    let c = Check {
+        key: String::from("net.ipv4.tcp_rmem"),
+        expected: String::from("4096 87380 16777216"),
+        actual: Some(String::from("4096\t87380\t16777216")),
+    };
+    assert!(c.is_ok());
+    let c = Check { actual: None, ..c };
+    assert!(!c.is_ok());
+}
@@ -0,0 +1,85 @@
+use aws_types::SdkConfig as AwsSdkConfig;
+
+use crate::{avalanche::node, aws::lease, StorageNamespace};
+
+/// Table of the node slot leases, shared by all clusters in the region.
+/// Created by "apply" with "static_node_keys", and never deleted by "delete".
+pub const SLOT_TABLE_NAME: &str = "avalanche-ops-node-slots";
+const SLOT_HASH_KEY: &str = "slot_id";
+
+/// Returns the lease manager of the node slots.
+pub fn slot_manager(shared_config: &AwsSdkConfig) -> lease::Manager {
+    lease::Manager::new(shared_config, SLOT_TABLE_NAME, SLOT_HASH_KEY)
+}
+
+/// Returns the slot IDs of the node kind (e.g., "my-cluster/non-anchor/0"),
+/// one for each node in the spec.
+pub fn slot_ids(cluster_id: &str, kind: &node::Kind, nodes: u32) -> Vec<String> {
+    (0..nodes)
+        .map(|i| format!("{}/{}/{}", cluster_id, kind.as_str(), i))
+        .collect()
+}
+
+/// Returns the name of the keys in the slot (e.g., "non-anchor-0").
+pub fn slot_name(slot_id: &str) -> String {
+    let mut splits = slot_id.rsplit('/');
+    let index = splits.next().unwrap_or_default();
+    let kind = splits.next().unwrap_or_default();
+    format!("{}-{}", kind, index)
+}
+
+/// Represents the S3 keys of the node keys, named after the instance ID
+/// or the node slot.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct S3Keys {
+    pub tls_cert: String,
+    pub tls_key_encrypted: String,
+    pub signer_key_encrypted: String,
+}
+
+impl S3Keys {
+    pub fn new(cluster_id: &str, name: &str) -> Self {
+        Self::in_dir(
+            &StorageNamespace::PkiKeyDir(cluster_id.to_string()).encode(),
+            name,
+        )
+    }
+
+    /// Returns the S3 keys in the other directory (e.g., network snapshot).
+    pub fn in_dir(dir: &str, name: &str) -> Self {
+        Self {
+            tls_cert: format!("{}/{}.crt", dir, name),
+            tls_key_encrypted: format!("{}/{}.key.zstd.seal_aes_256.encrypted", dir, name),
+            signer_key_encrypted: format!(
+                "{}/{}.signer.key.zstd.seal_aes_256.encrypted",
+                dir, name
+            ),
+        }
+    }
+}
+
+/// Returns the S3 key of the BLS proof of possession of the node,
+/// to register the node as a validator.
+pub fn pop_s3_key(cluster_id: &str, node_id: &str) -> String {
+    format!(
+        "{}/{}.pop.json",
+        StorageNamespace::PkiKeyDir(cluster_id.to_string()).encode(),
+        node_id
+    )
+}
+
+#[test]
+fn test_slots() {
+    let ids = slot_ids("test", &node::Kind::NonAnchor, 2);
+    assert_eq!(ids, vec!["test/non-anchor/0", "test/non-anchor/1"]);
+    assert_eq!(slot_name(&ids[1]), "non-anchor-1");
+
+    // same names as the instance-keyed ones
+    let keys = S3Keys::new("test", "i-123");
+    assert_eq!(keys.tls_cert, "test/pki/i-123.crt");
+    assert_eq!(
+        keys.tls_key_encrypted,
+        "test/pki/i-123.key.zstd.seal_aes_256.encrypted"
+    );
+    assert_eq!(pop_s3_key("test", "NodeID-1"), "test/pki/NodeID-1.pop.json");
+}
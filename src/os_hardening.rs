@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+/// Maximum length of a CloudFormation parameter value.
+/// ref. https://docs.aws.amazon.com/AWSCloudFormation/latest/UserGuide/cloudformation-limits.html
+pub const MAX_SCRIPT_LEN: usize = 4096;
+
+/// Represents the optional baseline hardening of the node hosts
+/// (e.g., CIS Ubuntu benchmark), run in the user-data before "avalanched" starts.
+/// ref. https://www.cisecurity.org/benchmark/ubuntu_linux
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct OsHardening {
+    /// Disables the root and password logins, and the agent/X11 forwarding.
+    #[serde(default)]
+    pub sshd_lockdown: bool,
+    /// Installs the security updates daily with "unattended-upgrades".
+    #[serde(default)]
+    pub automatic_security_updates: bool,
+    /// Records the changes to the identity, sudoers, and sshd files with "auditd".
+    #[serde(default)]
+    pub auditd: bool,
+    /// Denies all incoming traffic with "ufw", except the node HTTP and staking ports.
+    #[serde(default)]
+    pub firewall: bool,
+    /// Set "false" to also deny SSH with the firewall (e.g., SSM access only).
+    /// Default "true".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub firewall_allow_ssh: Option<bool>,
+}
+
+impl OsHardening {
+    pub fn is_enabled(&self) -> bool {
+        self.sshd_lockdown || self.automatic_security_updates || self.auditd || self.firewall
+    }
+
+    /// Generates the bash script for the enabled steps, to be embedded in the user-data.
+    pub fn script(&self, http_port: u32, staking_port: u32) -> String {
+        let mut lines: Vec<String> = vec![
+            String::from("set -xeu"),
+            String::from("export DEBIAN_FRONTEND=noninteractive"),
+        ];
+        if self.sshd_lockdown {
+            lines.push(String::from(
+                "cat > /etc/ssh/sshd_config.d/99-avalanche-ops.conf <<'SSHD_EOF'",
+            ));
+            for v in [
+                "PermitRootLogin no",
+                "PasswordAuthentication no",
+                "PermitEmptyPasswords no",
+                "ChallengeResponseAuthentication no",
+                "X11Forwarding no",
+                "AllowAgentForwarding no",
+                "MaxAuthTries 3",
+                "ClientAliveInterval 300",
+                "ClientAliveCountMax 2",
+            ] {
+                lines.push(v.to_string());
+            }
+            lines.push(String::from("SSHD_EOF"));
+            lines.push(String::from("sshd -t && systemctl restart ssh"));
+        }
+        if self.automatic_security_updates {
+            lines.push(String::from("apt-get install -yq unattended-upgrades"));
+            lines.push(String::from(
+                "cat > /etc/apt/apt.conf.d/20auto-upgrades <<'APT_EOF'",
+            ));
+            lines.push(String::from("APT::Periodic::Update-Package-Lists \"1\";"));
+            lines.push(String::from("APT::Periodic::Unattended-Upgrade \"1\";"));
+            lines.push(String::from("APT_EOF"));
+            lines.push(String::from("systemctl enable --now unattended-upgrades"));
+        }
+        if self.auditd {
+            lines.push(String::from("apt-get install -yq auditd"));
+            lines.push(String::from(
+                "cat > /etc/audit/rules.d/99-avalanche-ops.rules <<'AUDIT_EOF'",
+            ));
+            for v in [
+                "-w /etc/passwd -p wa -k identity",
+                "-w /etc/group -p wa -k identity",
+                "-w /etc/shadow -p wa -k identity",
+                "-w /etc/sudoers -p wa -k scope",
+                "-w /etc/sudoers.d/ -p wa -k scope",
+                "-w /etc/ssh/sshd_config -p wa -k sshd",
+                "-w /var/log/sudo.log -p wa -k actions",
+            ] {
+                lines.push(v.to_string());
+            }
+            lines.push(String::from("AUDIT_EOF"));
+            lines.push(String::from("systemctl enable auditd"));
+            lines.push(String::from("augenrules --load"));
+        }
+        if self.firewall {
+            lines.push(String::from("apt-get install -yq ufw"));
+            lines.push(String::from("ufw default deny incoming"));
+            lines.push(String::from("ufw default allow outgoing"));
+            if self.firewall_allow_ssh.unwrap_or(true) {
+                lines.push(String::from("ufw allow 22/tcp"));
+            }
+            lines.push(format!("ufw allow {}/tcp", http_port));
+            lines.push(format!("ufw allow {}/tcp", staking_port));
+            lines.push(String::from("ufw --force enable"));
+        }
+        lines.join("\n")
+    }
+}
+
+#[test]
+fn test_script() {
+    let h = OsHardening::default();
+    assert!(!h.is_enabled());
+
+    let h = OsHardening {
+        firewall: true,
+        firewall_allow_ssh: Some(false),
+        ..OsHardening::default()
+    };
+    assert!(h.is_enabled());
+    let s = h.script(9650, 9651);
+    assert!(s.contains("ufw default deny incoming"));
+    assert!(s.contains("ufw allow 9650/tcp"));
+    assert!(s.contains("ufw allow 9651/tcp"));
+    assert!(!s.contains("22/tcp"));
+    assert!(!s.contains("sshd_config.d"));
+
+    let h = OsHardening {
+        sshd_lockdown: true,
+        automatic_security_updates: true,
+        auditd: true,
+        firewall: true,
+        firewall_allow_ssh: None,
+    };
+    let s = h.script(9650, 9651);
+    assert!(s.contains("PasswordAuthentication no"));
+    assert!(s.contains("unattended-upgrades"));
+    assert!(s.contains("augenrules --load"));
+    assert!(s.contains("ufw allow 22/tcp"));
+    // embedded as-is in the user-data "!Sub"
+    assert!(!s.contains("${"));
+    assert!(s.len() <= MAX_SCRIPT_LEN);
+}
@@ -0,0 +1,539 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::{self, Error, ErrorKind, Read, Seek, SeekFrom},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use aws_sdk_cloudwatch::model::MetricDatum;
+use hyper::{client::HttpConnector, header, Body, Client, Method, Request};
+use hyper_tls::HttpsConnector;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::time::timeout;
+use walkdir::WalkDir;
+
+use crate::metric_schema;
+
+pub const LOGS_PATH: &str = "/v1/logs";
+pub const METRICS_PATH: &str = "/v1/metrics";
+
+/// Maximum log records per export request.
+pub const MAX_LOG_RECORDS_PER_REQUEST: usize = 1000;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Represents the OTLP/HTTP export of the node logs and metrics from "avalanched",
+/// as an alternative to CloudWatch (e.g., Datadog, Grafana Cloud),
+/// using the JSON encoding of the protocol.
+/// The logs are the same files that the CloudWatch agent collects,
+/// and the metrics are the same data that "avalanched" puts to CloudWatch.
+/// ref. https://opentelemetry.io/docs/specs/otlp/#otlphttp
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Otlp {
+    /// OTLP/HTTP base endpoint, appended with "/v1/logs" and "/v1/metrics"
+    /// (e.g., "https://otlp-gateway-prod-us-east-0.grafana.net/otlp").
+    pub endpoint: String,
+    /// Headers of every export request (e.g., "Authorization", "DD-API-KEY").
+    /// Use the "${ssm:/path/param}" placeholders for the secrets.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<Tls>,
+    /// Set "true" to export the node logs.
+    #[serde(default)]
+    pub logs: bool,
+    /// Set "true" to export the node metrics.
+    #[serde(default)]
+    pub metrics: bool,
+    /// Set "true" to stop sending the exported signals to CloudWatch.
+    /// The CloudWatch alarms (e.g., remediation, clock offset) then have no data.
+    #[serde(default)]
+    pub disable_cloudwatch: bool,
+}
+
+/// Represents the TLS options of the OTLP endpoint.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Tls {
+    /// PEM-encoded CA certificate to trust, in addition to the system roots
+    /// (e.g., the self-hosted collector).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert_pem: Option<String>,
+    /// Set "true" to skip the certificate verification (only for testing).
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+}
+
+impl Otlp {
+    pub fn validate(&self) -> io::Result<()> {
+        if !self.endpoint.starts_with("http://") && !self.endpoint.starts_with("https://") {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "'otlp.endpoint' must be a HTTP(S) URL (got '{}')",
+                    self.endpoint
+                ),
+            ));
+        }
+        if !self.logs && !self.metrics {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'otlp' requires 'logs' or 'metrics'",
+            ));
+        }
+        for (k, v) in self.headers.iter() {
+            if header::HeaderName::from_bytes(k.as_bytes()).is_err()
+                || header::HeaderValue::from_str(v).is_err()
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid 'otlp.headers' '{}'", k),
+                ));
+            }
+        }
+        if let Some(tls) = &self.tls {
+            if let Some(pem) = &tls.ca_cert_pem {
+                native_tls::Certificate::from_pem(pem.as_bytes()).map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("invalid 'otlp.tls.ca_cert_pem' ({})", e),
+                    )
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if the metrics skip CloudWatch.
+    pub fn skips_cloudwatch_metrics(&self) -> bool {
+        self.metrics && self.disable_cloudwatch
+    }
+
+    /// Returns true if the logs skip CloudWatch.
+    pub fn skips_cloudwatch_logs(&self) -> bool {
+        self.logs && self.disable_cloudwatch
+    }
+}
+
+/// Represents a log line to export.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct LogRecord {
+    /// Unix time in nanoseconds when the line was read.
+    pub observed_at_nanos: u128,
+    pub file_path: String,
+    pub body: String,
+}
+
+/// Exports the logs and metrics of the node to the OTLP endpoint.
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    pub cfg: Otlp,
+    /// Resource attributes of the node (e.g., "avalanche_ops.cluster_id").
+    pub resource: Vec<(String, String)>,
+}
+
+impl Exporter {
+    pub fn new(cfg: Otlp, dims: &metric_schema::Dimensions, instance_id: &str) -> Self {
+        Self {
+            cfg,
+            resource: resource_attributes(dims, instance_id),
+        }
+    }
+
+    pub async fn export_metrics(&self, data: &[MetricDatum]) -> io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.post(
+            METRICS_PATH,
+            encode_metrics(&self.resource, data, now_nanos()),
+        )
+        .await
+    }
+
+    pub async fn export_logs(&self, records: &[LogRecord]) -> io::Result<()> {
+        for chunk in records.chunks(MAX_LOG_RECORDS_PER_REQUEST) {
+            self.post(LOGS_PATH, encode_logs(&self.resource, chunk))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn post(&self, path: &str, body: Value) -> io::Result<()> {
+        let url = format!("{}{}", self.cfg.endpoint.trim_end_matches('/'), path);
+        let mut builder = Request::builder()
+            .method(Method::POST)
+            .uri(url.as_str())
+            .header(header::CONTENT_TYPE, "application/json");
+        for (k, v) in self.cfg.headers.iter() {
+            builder = builder.header(k.as_str(), v.as_str());
+        }
+        let req = builder
+            .body(Body::from(body.to_string()))
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("invalid request {}", e)))?;
+
+        let mut http = HttpConnector::new();
+        http.set_connect_timeout(Some(Duration::from_secs(5)));
+        http.enforce_http(false);
+        let task = Client::builder()
+            .build::<_, Body>(HttpsConnector::from((http, self.tls_connector()?)))
+            .request(req);
+        let resp = timeout(REQUEST_TIMEOUT, task).await?.map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to export {} ({})", url, e),
+            )
+        })?;
+        if !resp.status().is_success() {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("failed to export {} (status {})", url, resp.status()),
+            ));
+        }
+        Ok(())
+    }
+
+    fn tls_connector(&self) -> io::Result<tokio_native_tls::TlsConnector> {
+        let mut builder = native_tls::TlsConnector::builder();
+        if let Some(tls) = &self.cfg.tls {
+            if let Some(pem) = &tls.ca_cert_pem {
+                let cert = native_tls::Certificate::from_pem(pem.as_bytes())
+                    .map_err(|e| Error::new(ErrorKind::InvalidInput, e.to_string()))?;
+                builder.add_root_certificate(cert);
+            }
+            if tls.insecure_skip_verify {
+                builder.danger_accept_invalid_certs(true);
+            }
+        }
+        let connector = builder
+            .build()
+            .map_err(|e| Error::new(ErrorKind::Other, e.to_string()))?;
+        Ok(tokio_native_tls::TlsConnector::from(connector))
+    }
+}
+
+fn resource_attributes(
+    dims: &metric_schema::Dimensions,
+    instance_id: &str,
+) -> Vec<(String, String)> {
+    vec![
+        (String::from("service.name"), String::from("avalanched")),
+        (
+            String::from("service.version"),
+            env!("CARGO_PKG_VERSION").to_string(),
+        ),
+        (String::from("host.id"), instance_id.to_string()),
+        (
+            String::from("cloud.availability_zone"),
+            dims.availability_zone.clone(),
+        ),
+        (
+            String::from("avalanche_ops.cluster_id"),
+            dims.cluster_id.clone(),
+        ),
+        (
+            String::from("avalanche_ops.node_kind"),
+            dims.node_kind.clone(),
+        ),
+        (String::from("avalanche_ops.node_id"), dims.node_id.clone()),
+    ]
+}
+
+fn attributes(kvs: &[(String, String)]) -> Value {
+    Value::Array(
+        kvs.iter()
+            .map(|(k, v)| json!({"key": k, "value": {"stringValue": v}}))
+            .collect(),
+    )
+}
+
+fn scope() -> Value {
+    json!({"name": "avalanche-ops", "version": env!("CARGO_PKG_VERSION")})
+}
+
+/// Encodes the CloudWatch metric data as the OTLP gauges,
+/// with the CloudWatch dimensions as the data point attributes.
+/// The 64-bit integers are strings in the OTLP JSON encoding.
+pub fn encode_metrics(
+    resource: &[(String, String)],
+    data: &[MetricDatum],
+    now_nanos: u128,
+) -> Value {
+    let metrics: Vec<Value> = data
+        .iter()
+        .filter_map(|d| {
+            let name = d.metric_name.clone()?;
+            let value = d.value?;
+            let ts = d
+                .timestamp
+                .map(|t| t.secs() as u128 * 1_000_000_000 + t.subsec_nanos() as u128)
+                .unwrap_or(now_nanos);
+            let dims: Vec<(String, String)> = d
+                .dimensions
+                .clone()
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|dim| Some((dim.name?, dim.value?)))
+                .collect();
+            Some(json!({
+                "name": name,
+                "unit": d.unit.as_ref().map(|u| u.as_str().to_string()).unwrap_or_default(),
+                "gauge": {
+                    "dataPoints": [{
+                        "timeUnixNano": ts.to_string(),
+                        "asDouble": value,
+                        "attributes": attributes(&dims),
+                    }],
+                },
+            }))
+        })
+        .collect();
+    json!({
+        "resourceMetrics": [{
+            "resource": {"attributes": attributes(resource)},
+            "scopeMetrics": [{"scope": scope(), "metrics": metrics}],
+        }],
+    })
+}
+
+pub fn encode_logs(resource: &[(String, String)], records: &[LogRecord]) -> Value {
+    let records: Vec<Value> = records
+        .iter()
+        .map(|r| {
+            json!({
+                "observedTimeUnixNano": r.observed_at_nanos.to_string(),
+                "body": {"stringValue": r.body},
+                "attributes": attributes(&[(String::from("log.file.path"), r.file_path.clone())]),
+            })
+        })
+        .collect();
+    json!({
+        "resourceLogs": [{
+            "resource": {"attributes": attributes(resource)},
+            "scopeLogs": [{"scope": scope(), "logRecords": records}],
+        }],
+    })
+}
+
+/// Reads the lines appended to the log files since the last read.
+/// The existing lines are skipped on the first read of each file,
+/// so the restarts do not export the same lines again.
+#[derive(Debug, Default)]
+pub struct Tailer {
+    /// File paths or "[DIR]/**.log" patterns (same as the CloudWatch agent).
+    pub patterns: Vec<String>,
+    offsets: HashMap<String, u64>,
+}
+
+impl Tailer {
+    pub fn new(patterns: Vec<String>) -> Self {
+        let mut tailer = Self {
+            patterns,
+            offsets: HashMap::new(),
+        };
+        for f in tailer.files() {
+            let len = fs::metadata(&f).map(|m| m.len()).unwrap_or(0);
+            tailer.offsets.insert(f, len);
+        }
+        tailer
+    }
+
+    /// Returns the files matching the patterns, re-scanned on every read
+    /// for the new log files (e.g., new chains).
+    fn files(&self) -> Vec<String> {
+        let mut files = Vec::new();
+        for p in self.patterns.iter() {
+            match p.split_once("/**") {
+                Some((dir, suffix)) => {
+                    for entry in WalkDir::new(dir).into_iter().flatten() {
+                        let path = entry.path().display().to_string();
+                        if entry.file_type().is_file() && path.ends_with(suffix) {
+                            files.push(path);
+                        }
+                    }
+                }
+                None => {
+                    if Path::new(p).exists() {
+                        files.push(p.clone());
+                    }
+                }
+            }
+        }
+        files.sort();
+        files
+    }
+
+    /// Returns the complete lines appended since the last read,
+    /// starting from the beginning of the new or the rotated files.
+    pub fn read(&mut self, observed_at_nanos: u128) -> Vec<LogRecord> {
+        let mut records = Vec::new();
+        for f in self.files() {
+            let offset = self.offsets.get(&f).copied().unwrap_or(0);
+            let (lines, next) = match read_lines(&f, offset) {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!("failed to read '{}' ({})", f, e);
+                    continue;
+                }
+            };
+            for body in lines {
+                records.push(LogRecord {
+                    observed_at_nanos,
+                    file_path: f.clone(),
+                    body,
+                });
+            }
+            self.offsets.insert(f, next);
+        }
+        records
+    }
+}
+
+/// Returns the complete lines from the offset, and the offset after the last line.
+fn read_lines(file_path: &str, offset: u64) -> io::Result<(Vec<String>, u64)> {
+    let mut f = File::open(file_path)?;
+    let len = f.metadata()?.len();
+    // rotated
+    let offset = if len < offset { 0 } else { offset };
+    f.seek(SeekFrom::Start(offset))?;
+    let mut appended = Vec::new();
+    f.read_to_end(&mut appended)?;
+    // the partial line is read on the next call
+    let complete = match appended.iter().rposition(|b| *b == b'\n') {
+        Some(i) => i + 1,
+        None => 0,
+    };
+    let lines = String::from_utf8_lossy(&appended[..complete])
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect();
+    Ok((lines, offset + complete as u64))
+}
+
+pub fn now_nanos() -> u128 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos()
+}
+
+/// Exports the lines appended to the log files every 10 seconds, until the process exits.
+pub async fn export_logs_loop(exporter: Exporter, patterns: Vec<String>) {
+    info!("STEP: starting OTLP log export of {:?}", patterns);
+    let mut tailer = Tailer::new(patterns);
+    let mut pending: Vec<LogRecord> = Vec::new();
+    loop {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        pending.extend(tailer.read(now_nanos()));
+        if pending.is_empty() {
+            continue;
+        }
+        match exporter.export_logs(&pending).await {
+            Ok(_) => pending.clear(),
+            Err(e) => {
+                warn!("failed to export {} log records ({})", pending.len(), e);
+                // keeps the recent lines for the retries, bounded by the memory
+                let max = MAX_LOG_RECORDS_PER_REQUEST * 10;
+                if pending.len() > max {
+                    pending.drain(..pending.len() - max);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_otlp() {
+    use std::io::Write;
+
+    use aws_sdk_cloudwatch::model::{Dimension, StandardUnit};
+    use aws_smithy_types::DateTime as SmithyDateTime;
+
+    let mut cfg = Otlp {
+        endpoint: String::from("https://otlp.example.com/otlp"),
+        metrics: true,
+        ..Default::default()
+    };
+    assert!(cfg.validate().is_ok());
+    assert!(!cfg.skips_cloudwatch_metrics());
+    cfg.disable_cloudwatch = true;
+    assert!(cfg.skips_cloudwatch_metrics());
+    assert!(!cfg.skips_cloudwatch_logs());
+    cfg.headers
+        .insert(String::from("bad header"), String::from("v"));
+    assert!(cfg.validate().is_err());
+    assert!(Otlp {
+        endpoint: String::from("otlp.example.com"),
+        logs: true,
+        ..Default::default()
+    }
+    .validate()
+    .is_err());
+    assert!(Otlp {
+        endpoint: String::from("http://localhost:4318"),
+        ..Default::default()
+    }
+    .validate()
+    .is_err());
+
+    let resource = vec![(String::from("service.name"), String::from("avalanched"))];
+    let data = vec![
+        MetricDatum::builder()
+            .metric_name("avalanche_health_healthy")
+            .value(1.0)
+            .unit(StandardUnit::Count)
+            .timestamp(SmithyDateTime::from_secs(1_600_000_000))
+            .dimensions(
+                Dimension::builder()
+                    .name("node_kind")
+                    .value("anchor")
+                    .build(),
+            )
+            .build(),
+        // no value
+        MetricDatum::builder().metric_name("skipped").build(),
+    ];
+    let encoded = encode_metrics(&resource, &data, 0);
+    let metrics = &encoded["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+    assert_eq!(metrics.as_array().unwrap().len(), 1);
+    assert_eq!(metrics[0]["name"], "avalanche_health_healthy");
+    assert_eq!(metrics[0]["unit"], "Count");
+    let point = &metrics[0]["gauge"]["dataPoints"][0];
+    assert_eq!(point["timeUnixNano"], "1600000000000000000");
+    assert_eq!(point["asDouble"], 1.0);
+    assert_eq!(point["attributes"][0]["key"], "node_kind");
+    assert_eq!(
+        encoded["resourceMetrics"][0]["resource"]["attributes"][0]["value"]["stringValue"],
+        "avalanched"
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let log_dir = dir.path().join("logs");
+    fs::create_dir_all(&log_dir).unwrap();
+    let main_log = log_dir.join("main.log");
+    fs::write(&main_log, "existing\n").unwrap();
+    let mut tailer = Tailer::new(vec![format!("{}/**.log", log_dir.display())]);
+    assert!(tailer.read(1).is_empty());
+
+    let mut f = fs::OpenOptions::new().append(true).open(&main_log).unwrap();
+    write!(f, "first\npartial").unwrap();
+    fs::write(log_dir.join("C.log"), "new chain\n").unwrap();
+    let records = tailer.read(2);
+    let bodies: Vec<&str> = records.iter().map(|r| r.body.as_str()).collect();
+    assert_eq!(bodies, vec!["new chain", "first"]);
+
+    write!(f, " line\n").unwrap();
+    let records = tailer.read(3);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].body, "partial line");
+
+    let encoded = encode_logs(&resource, &records);
+    let record = &encoded["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0];
+    assert_eq!(record["body"]["stringValue"], "partial line");
+    assert_eq!(record["observedTimeUnixNano"], "3");
+}
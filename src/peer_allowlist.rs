@@ -0,0 +1,312 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Error, ErrorKind},
+    sync::Arc,
+};
+
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    avalanche::{avalanchego::subnet_config, heartbeat, ids},
+    aws::s3,
+    canary,
+    utils::rfc3339,
+    StorageNamespace, TrackedSubnet,
+};
+
+/// Heartbeats are published every 5-min, so the nodes without a heartbeat
+/// for a day are dropped from the cluster registry (e.g., terminated).
+pub const REGISTRY_MAX_AGE_SECONDS: u64 = 24 * 3600;
+
+/// Represents the NodeID-based peer allowlist of the private custom network.
+/// The subnets become "validatorOnly", and only allow the non-validator
+/// connections from the cluster nodes (found by their signed heartbeats)
+/// and the partner nodes. The generated allowlist is pushed to all nodes
+/// with the "update-peer-allowlist" event, and re-generated when the
+/// membership changes (e.g., the replaced node with a new node ID).
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct PeerAllowlist {
+    /// Node IDs outside of the cluster (e.g., partner nodes) allowed to connect.
+    #[serde(default)]
+    pub partner_node_ids: Vec<String>,
+    /// Subnets to restrict. Empty for all subnets the nodes track
+    /// ("avalanchego_config.whitelisted_subnets" and "tracked_subnets").
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subnet_ids: Vec<String>,
+}
+
+impl PeerAllowlist {
+    pub fn validate(&self) -> io::Result<()> {
+        for node_id in self.partner_node_ids.iter() {
+            ids::parse_node_id(node_id).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "invalid 'peer_allowlist.partner_node_ids' '{}' ({})",
+                        node_id, e
+                    ),
+                )
+            })?;
+        }
+        for subnet_id in self.subnet_ids.iter() {
+            ids::parse_id(subnet_id)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the allowlist of the cluster nodes and the partner nodes.
+    pub fn generate(&self, cluster_node_ids: &[String], now: DateTime<Utc>) -> Allowlist {
+        let node_ids: BTreeSet<String> = cluster_node_ids
+            .iter()
+            .chain(self.partner_node_ids.iter())
+            .cloned()
+            .collect();
+        Allowlist {
+            node_ids: node_ids.into_iter().collect(),
+            generated_at: now,
+        }
+    }
+
+    /// Returns the subnet configs by subnet ID for the node that tracks
+    /// "whitelisted_subnets". The allowed nodes of "tracked_subnets" are kept.
+    pub fn subnet_configs(
+        &self,
+        allowlist: &Allowlist,
+        whitelisted_subnets: &[String],
+        tracked_subnets: &[TrackedSubnet],
+    ) -> BTreeMap<String, subnet_config::Config> {
+        let subnet_ids: Vec<&String> = if self.subnet_ids.is_empty() {
+            whitelisted_subnets.iter().collect()
+        } else {
+            self.subnet_ids
+                .iter()
+                .filter(|s| whitelisted_subnets.contains(s))
+                .collect()
+        };
+
+        let mut configs = BTreeMap::new();
+        for subnet_id in subnet_ids {
+            let mut allowed: BTreeSet<String> = allowlist.node_ids.iter().cloned().collect();
+            if let Some(tracked) = tracked_subnets.iter().find(|t| &t.subnet_id == subnet_id) {
+                allowed.extend(tracked.allowed_nodes.clone().unwrap_or_default());
+            }
+            configs.insert(
+                subnet_id.clone(),
+                subnet_config::Config {
+                    validator_only: Some(true),
+                    allowed_nodes: Some(allowed.into_iter().collect()),
+                },
+            );
+        }
+        configs
+    }
+}
+
+/// Represents the generated allowlist, uploaded to "[ID]/peer-allowlist.json".
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Allowlist {
+    /// Sorted node IDs.
+    pub node_ids: Vec<String>,
+    #[serde(with = "rfc3339::serde_format")]
+    pub generated_at: DateTime<Utc>,
+}
+
+impl Allowlist {
+    pub fn encode_json(&self) -> io::Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to encode {}", e)))
+    }
+
+    pub fn decode_json(d: &[u8]) -> io::Result<Self> {
+        serde_json::from_slice(d).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid peer allowlist {}", e),
+            )
+        })
+    }
+
+    pub fn contains(&self, node_id: &str) -> bool {
+        self.node_ids.iter().any(|v| v == node_id)
+    }
+}
+
+/// Returns the node IDs of the heartbeats published in the last "max_age_seconds".
+/// The heartbeats must be verified, so only the nodes with the staking keys are listed.
+pub fn registry_node_ids(
+    heartbeats: &[heartbeat::Heartbeat],
+    now_unix: u64,
+    max_age_seconds: u64,
+) -> Vec<String> {
+    let node_ids: BTreeSet<String> = heartbeats
+        .iter()
+        .filter(|hb| hb.timestamp + max_age_seconds >= now_unix)
+        .map(|hb| hb.node_id.clone())
+        .collect();
+    node_ids.into_iter().collect()
+}
+
+/// Downloads and verifies the heartbeats of the cluster, and returns
+/// the node IDs of the cluster registry.
+pub async fn fetch_registry(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    id: &str,
+    now_unix: u64,
+) -> io::Result<Vec<String>> {
+    let objects = s3_manager
+        .list_objects(
+            Arc::new(s3_bucket.to_string()),
+            Some(Arc::new(s3::append_slash(
+                &StorageNamespace::HeartbeatsDir(id.to_string()).encode(),
+            ))),
+        )
+        .await
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed list_objects {}", e)))?;
+
+    let mut heartbeats = Vec::new();
+    for obj in objects.iter() {
+        let s3_key = obj.key().unwrap_or_default();
+        let d = match s3_manager
+            .get_bytes(s3_bucket, s3_key, None)
+            .await
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed get_bytes {}", e)))?
+        {
+            Some(v) => v,
+            None => continue,
+        };
+        // anyone with the bucket write access could have uploaded the heartbeat
+        let verified = serde_json::from_slice::<heartbeat::Signed>(&d)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+            .and_then(|signed| signed.verify());
+        match verified {
+            Ok(hb) => heartbeats.push(hb),
+            Err(e) => warn!("skipping invalid heartbeat {} ({})", s3_key, e),
+        }
+    }
+    Ok(registry_node_ids(
+        &heartbeats,
+        now_unix,
+        REGISTRY_MAX_AGE_SECONDS,
+    ))
+}
+
+/// Returns the allowlist in S3, or None if not generated yet.
+pub async fn get(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    id: &str,
+) -> io::Result<Option<Allowlist>> {
+    let d = s3_manager
+        .get_bytes(
+            s3_bucket,
+            &StorageNamespace::PeerAllowlist(id.to_string()).encode(),
+            None,
+        )
+        .await
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed get_bytes {}", e)))?;
+    match d {
+        Some(d) => Ok(Some(Allowlist::decode_json(&d)?)),
+        None => Ok(None),
+    }
+}
+
+pub async fn put(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    id: &str,
+    allowlist: &Allowlist,
+) -> io::Result<()> {
+    info!(
+        "uploading peer allowlist with {} node IDs",
+        allowlist.node_ids.len()
+    );
+    s3_manager
+        .put_bytes(
+            allowlist.encode_json()?.into_bytes(),
+            Arc::new(s3_bucket.to_string()),
+            Arc::new(StorageNamespace::PeerAllowlist(id.to_string()).encode()),
+        )
+        .await
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed put_bytes {}", e)))
+}
+
+/// Uploads the allowlist and the event for every node at once,
+/// for the membership changes that cannot wait for the canaries.
+pub async fn push(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    id: &str,
+    allowlist: &Allowlist,
+) -> io::Result<()> {
+    put(s3_manager, s3_bucket, id, allowlist).await?;
+    s3_manager
+        .put_bytes(
+            canary::Rollout::fleet(Vec::new())
+                .encode_json()?
+                .into_bytes(),
+            Arc::new(s3_bucket.to_string()),
+            Arc::new(StorageNamespace::EventsUpdatePeerAllowlistEvent(id.to_string()).encode()),
+        )
+        .await
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed put_bytes {}", e)))
+}
+
+#[test]
+fn test_peer_allowlist() {
+    use chrono::TimeZone;
+
+    let a = String::from("NodeID-KUCzSr49wPWckUDouJLybJuRYtVkVeLoL");
+    let b = String::from("NodeID-6fZuj9x4tozLd6CAQ7AhTLd9RYXLnSzKB");
+    let partner = String::from("NodeID-5Ep2vW3TjZVpdvmFFvw3XrEAALpRHBTqi");
+
+    let cfg = PeerAllowlist {
+        partner_node_ids: vec![partner.clone()],
+        subnet_ids: vec![],
+    };
+    assert!(cfg.validate().is_ok());
+    let invalid = PeerAllowlist {
+        partner_node_ids: vec![String::from("NodeID-invalid")],
+        subnet_ids: vec![],
+    };
+    assert!(invalid.validate().is_err());
+
+    let now = Utc.with_ymd_and_hms(2022, 10, 1, 0, 0, 0).unwrap();
+    let allowlist = cfg.generate(&[b.clone(), a.clone(), a.clone()], now);
+    let mut expected = vec![a.clone(), b.clone(), partner.clone()];
+    expected.sort();
+    assert_eq!(allowlist.node_ids, expected);
+    assert!(allowlist.contains(&partner));
+    let decoded = Allowlist::decode_json(allowlist.encode_json().unwrap().as_bytes()).unwrap();
+    assert_eq!(decoded, allowlist);
+
+    let subnet_a = String::from("2E9Y6n7aC1ppSSdKa9h1w9h3LVdkt6VQzkxVDLUJPVKRDhUC3o");
+    let subnet_b = String::from("axczPtsqZ6u2G6hBdNiXmkVKpfZZ9r4oP9KZWapCy8gAotBn6");
+    let trusted = String::from("NodeID-7Xhw2mDxuDS44j42TCB6U5579esbSt3Lg");
+    let tracked = vec![TrackedSubnet {
+        subnet_id: subnet_b.clone(),
+        validator_only: true,
+        allowed_nodes: Some(vec![trusted.clone()]),
+    }];
+    let configs = cfg.subnet_configs(&allowlist, &[subnet_a.clone(), subnet_b.clone()], &tracked);
+    assert_eq!(configs.len(), 2);
+    assert_eq!(configs[&subnet_a].validator_only, Some(true));
+    assert_eq!(configs[&subnet_a].allowed_nodes, Some(expected.clone()));
+    assert!(configs[&subnet_b]
+        .allowed_nodes
+        .as_ref()
+        .unwrap()
+        .contains(&trusted));
+
+    // only the listed subnets the node tracks
+    let scoped = PeerAllowlist {
+        partner_node_ids: vec![],
+        subnet_ids: vec![subnet_b.clone()],
+    };
+    let configs = scoped.subnet_configs(&allowlist, &[subnet_a], &tracked);
+    assert!(configs.is_empty());
+}
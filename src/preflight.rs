@@ -0,0 +1,299 @@
+use std::{
+    fmt,
+    io::{self, Error, ErrorKind},
+};
+
+use aws_sdk_ec2::model::{ArchitectureValues, ImageState};
+use aws_types::SdkConfig as AwsSdkConfig;
+use log::{info, warn};
+
+use crate::{
+    aws::{
+        self, ec2, kms,
+        s3::{self, BucketOwnership},
+        servicequotas, ssm,
+    },
+    Spec,
+};
+
+/// Number of the zones with the public subnets ("Fn::Select" 0 to 2 of "Fn::GetAZs")
+/// in the VPC template.
+pub const VPC_ZONES: usize = 3;
+
+/// Same as the "ImageIdSsmParameter" default in the ASG template.
+pub const DEFAULT_IMAGE_SSM_PARAMETER: &str =
+    "/aws/service/canonical/ubuntu/server/20.04/stable/current/amd64/hvm/ebs-gp2/ami-id";
+
+/// Represents the pre-flight check of "apply".
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Check {
+    ServiceQuota,
+    InstanceType,
+    Image,
+    Kms,
+    S3Bucket,
+}
+
+impl Check {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Check::ServiceQuota => "service-quota",
+            Check::InstanceType => "instance-type",
+            Check::Image => "image",
+            Check::Kms => "kms",
+            Check::S3Bucket => "s3-bucket",
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Finding {
+    pub check: Check,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] {}", self.check.as_str(), self.message)
+    }
+}
+
+/// Represents the result of all pre-flight checks, so the problems
+/// are fixed at once rather than one "apply" at a time.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct Report {
+    pub findings: Vec<Finding>,
+    /// Checks that could not run (e.g., no permission to describe),
+    /// which do not fail "apply".
+    pub skipped: Vec<Finding>,
+}
+
+impl Report {
+    pub fn push(&mut self, check: Check, message: String) {
+        self.findings.push(Finding { check, message });
+    }
+
+    pub fn skip(&mut self, check: Check, message: String) {
+        self.skipped.push(Finding { check, message });
+    }
+
+    /// Returns the error with every finding, if any.
+    pub fn into_result(self) -> io::Result<()> {
+        for s in self.skipped.iter() {
+            warn!("skipped pre-flight check {}", s);
+        }
+        if self.findings.is_empty() {
+            info!("pre-flight checks passed");
+            return Ok(());
+        }
+        let lines: Vec<String> = self.findings.iter().map(|f| f.to_string()).collect();
+        Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "{} pre-flight check findings:\n{}",
+                self.findings.len(),
+                lines.join("\n")
+            ),
+        ))
+    }
+}
+
+/// Runs the pre-flight checks of the spec before "apply" creates any resource:
+/// the service quotas, the instance types in every target zone, the image,
+/// the KMS key, and the S3 bucket name.
+pub async fn run(shared_config: &AwsSdkConfig, spec: &Spec, nlb_enabled: bool) -> Report {
+    let aws_resources = spec
+        .aws_resources
+        .clone()
+        .expect("unexpected None aws_resources");
+    let mut report = Report::default();
+
+    let ec2_manager = ec2::Manager::new(shared_config);
+    let needs = quota_needs(spec, &aws_resources, nlb_enabled);
+    match servicequotas::check(&ec2_manager, &aws_resources.region, &spec.id, &needs).await {
+        Ok(shortfalls) => {
+            for s in shortfalls.iter() {
+                report.push(Check::ServiceQuota, s.to_string());
+            }
+        }
+        Err(e) => report.skip(Check::ServiceQuota, e.to_string()),
+    }
+
+    match ec2_manager.list_availability_zones().await {
+        Ok(zones) => {
+            let zones: Vec<String> = zones.into_iter().take(VPC_ZONES).collect();
+            for instance_type in instance_types(spec).iter() {
+                match ec2_manager.list_instance_type_zones(instance_type).await {
+                    Ok(offered) => {
+                        let missing = missing_zones(&zones, &offered);
+                        if !missing.is_empty() {
+                            report.push(
+                                Check::InstanceType,
+                                format!(
+                                    "'{}' is not offered in {:?} (remove it from 'machine.instance_types')",
+                                    instance_type, missing
+                                ),
+                            );
+                        }
+                    }
+                    Err(e) => report.skip(Check::InstanceType, e.message()),
+                }
+            }
+        }
+        Err(e) => report.skip(Check::InstanceType, e.message()),
+    }
+
+    let image_id = match &spec.machine.image_id {
+        Some(v) => Some(v.clone()),
+        None => {
+            let ssm_manager = ssm::Manager::new(shared_config);
+            match ssm_manager.get_parameter(DEFAULT_IMAGE_SSM_PARAMETER).await {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    report.skip(Check::Image, e.message());
+                    None
+                }
+            }
+        }
+    };
+    if let Some(image_id) = image_id {
+        match ec2_manager.describe_image(&image_id).await {
+            Ok(image) => {
+                if image.state != Some(ImageState::Available) {
+                    report.push(
+                        Check::Image,
+                        format!("'{}' is in the state {:?}", image_id, image.state),
+                    );
+                }
+                // the ASG template only runs amd64
+                if image.architecture != Some(ArchitectureValues::X8664) {
+                    report.push(
+                        Check::Image,
+                        format!(
+                            "'{}' is of the architecture {:?}, not x86_64",
+                            image_id, image.architecture
+                        ),
+                    );
+                }
+            }
+            Err(e) if e.is_not_found() || e.is_validation() => report.push(
+                Check::Image,
+                format!(
+                    "'{}' not found in '{}' (copy the image to the region, or share it with the account)",
+                    image_id, aws_resources.region
+                ),
+            ),
+            Err(e) => report.skip(Check::Image, e.message()),
+        }
+    }
+
+    // the KMS key is used for every secret, so any failure is a finding
+    let kms_manager = kms::Manager::new(shared_config);
+    match &aws_resources.kms_cmk_id {
+        Some(key_id) => match kms_manager.describe_key_state(key_id).await {
+            Ok(state) if state == "Enabled" => {}
+            Ok(state) => report.push(
+                Check::Kms,
+                format!(
+                    "key '{}' is in the state '{}', not 'Enabled'",
+                    key_id, state
+                ),
+            ),
+            Err(e) => report.push(
+                Check::Kms,
+                format!("key '{}' not usable ({})", key_id, e.message()),
+            ),
+        },
+        None => {
+            if let Err(e) = kms_manager.check_access().await {
+                report.push(Check::Kms, format!("not usable ({})", e.message()));
+            }
+        }
+    }
+
+    let s3_manager = s3::Manager::new(shared_config);
+    match s3_manager.bucket_ownership(&aws_resources.s3_bucket).await {
+        Ok(BucketOwnership::OwnedByOther) => report.push(
+            Check::S3Bucket,
+            format!(
+                "'{}' exists in another account, or is not accessible by the caller (change 's3_bucket')",
+                aws_resources.s3_bucket
+            ),
+        ),
+        Ok(_) => {}
+        Err(e) => report.skip(Check::S3Bucket, e.message()),
+    }
+
+    report
+}
+
+/// Returns the instance types of the ASGs in the priority order.
+fn instance_types(spec: &Spec) -> Vec<String> {
+    spec.machine
+        .instance_types
+        .clone()
+        .unwrap_or_else(|| vec![String::from(servicequotas::DEFAULT_INSTANCE_TYPE)])
+}
+
+/// Returns the resources that "apply" creates for the spec,
+/// excluding the ones created by the previous runs.
+fn quota_needs(
+    spec: &Spec,
+    aws_resources: &aws::Resources,
+    nlb_enabled: bool,
+) -> servicequotas::Needs {
+    let elastic_ips = match &aws_resources.elastic_ip_pool {
+        Some(pool) => pool.size(spec.machine.non_anchor_nodes).saturating_sub(
+            aws_resources
+                .elastic_ip_pool_addresses
+                .as_ref()
+                .map(|v| v.len() as u32)
+                .unwrap_or(0),
+        ),
+        None => 0,
+    };
+    servicequotas::Needs {
+        instance_type: instance_types(spec)[0].clone(),
+        nodes: spec.machine.anchor_nodes.unwrap_or(0) + spec.machine.non_anchor_nodes,
+        elastic_ips,
+        nlbs: u32::from(nlb_enabled && aws_resources.cloudformation_asg_nlb_arn.is_none()),
+        vpcs: u32::from(aws_resources.cloudformation_vpc_id.is_none()),
+    }
+}
+
+fn missing_zones(zones: &[String], offered: &[String]) -> Vec<String> {
+    zones
+        .iter()
+        .filter(|z| !offered.contains(z))
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn test_preflight() {
+    let zones = vec![
+        String::from("us-west-2a"),
+        String::from("us-west-2b"),
+        String::from("us-west-2c"),
+    ];
+    assert!(missing_zones(&zones, &zones).is_empty());
+    assert_eq!(
+        missing_zones(
+            &zones,
+            &[String::from("us-west-2a"), String::from("us-west-2d")]
+        ),
+        vec![String::from("us-west-2b"), String::from("us-west-2c")]
+    );
+
+    let mut report = Report::default();
+    report.skip(Check::ServiceQuota, String::from("no aws CLI"));
+    assert!(report.clone().into_result().is_ok());
+
+    report.push(Check::Image, String::from("'ami-123' not found"));
+    report.push(Check::Kms, String::from("not usable"));
+    let err = report.into_result().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "2 pre-flight check findings:\n[image] 'ami-123' not found\n[kms] not usable"
+    );
+}
@@ -0,0 +1,336 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use aws_sdk_cloudformation::model::{OnFailure, Output, Parameter, StackStatus, Tag};
+use aws_types::SdkConfig as AwsSdkConfig;
+use log::info;
+use rust_embed::RustEmbed;
+
+use crate::{
+    aws::{cloudformation, ec2, kms, operation, s3},
+    errors::{Error::Other, Result},
+    provider::{CloudProvider, Instance, Key, Network, NetworkSpec, NodeGroup, NodeGroupSpec},
+};
+
+#[derive(RustEmbed)]
+#[folder = "src/aws/cfn-templates/avalanche-node/"]
+#[prefix = "src/aws/cfn-templates/avalanche-node/"]
+struct Asset;
+
+// 50-minute
+const MAX_WAIT_SECONDS: u64 = 50 * 60;
+
+/// Implements the cloud operations with CloudFormation (network and
+/// node groups), S3 (object storage), and KMS (key management).
+#[derive(Debug, Clone)]
+pub struct Provider {
+    pub cloudformation_manager: cloudformation::Manager,
+    pub ec2_manager: ec2::Manager,
+    pub s3_manager: s3::Manager,
+    pub kms_manager: kms::Manager,
+    /// Tags of all the stacks (e.g., "auto_delete::stack_tags").
+    pub stack_tags: Vec<Tag>,
+    pub poll_interval: Duration,
+}
+
+impl Provider {
+    pub fn new(shared_config: &AwsSdkConfig) -> Self {
+        Self {
+            cloudformation_manager: cloudformation::Manager::new(shared_config),
+            ec2_manager: ec2::Manager::new(shared_config),
+            s3_manager: s3::Manager::new(shared_config),
+            kms_manager: kms::Manager::new(shared_config),
+            stack_tags: Vec::new(),
+            poll_interval: Duration::from_secs(30),
+        }
+    }
+
+    fn tags(&self) -> Vec<Tag> {
+        [
+            vec![Tag::builder().key("KIND").value("avalanche-ops").build()],
+            operation::current_stack_tags(),
+            self.stack_tags.clone(),
+        ]
+        .concat()
+    }
+}
+
+impl CloudProvider for Provider {
+    fn name(&self) -> &'static str {
+        "aws"
+    }
+
+    async fn create_network(&self, spec: &NetworkSpec) -> Result<Network> {
+        let tmpl = template("vpc.yaml")?;
+        let mut params = BTreeMap::from([
+            (String::from("Id"), spec.cluster_id.clone()),
+            (String::from("VpcCidr"), spec.cidr.clone()),
+            (
+                String::from("IngressIpv4Range"),
+                spec.ingress_ipv4_range.clone(),
+            ),
+            (String::from("HttpPort"), spec.http_port.to_string()),
+            (String::from("StakingPort"), spec.staking_port.to_string()),
+        ]);
+        for (i, cidr) in spec.subnet_cidrs.iter().enumerate() {
+            params.insert(format!("PublicSubnetCidr{}", i + 1), cidr.clone());
+        }
+        params.extend(spec.parameters.clone());
+
+        self.cloudformation_manager
+            .create_stack(
+                &spec.name,
+                None,
+                OnFailure::Delete,
+                &tmpl,
+                Some(self.tags()),
+                Some(build_params(params)),
+            )
+            .await?;
+        let stack = self
+            .cloudformation_manager
+            .poll_stack(
+                &spec.name,
+                StackStatus::CreateComplete,
+                Duration::from_secs(300),
+                self.poll_interval,
+            )
+            .await?;
+
+        let mut network = Network {
+            name: spec.name.clone(),
+            id: String::new(),
+            security_group_id: String::new(),
+            subnet_ids: Vec::new(),
+            outputs: outputs(stack.outputs),
+        };
+        for (k, v) in network.outputs.iter() {
+            match k.as_str() {
+                "VpcId" => network.id = v.clone(),
+                "SecurityGroupId" => network.security_group_id = v.clone(),
+                "PublicSubnetIds" => network.subnet_ids = v.split(',').map(String::from).collect(),
+                _ => {}
+            }
+        }
+        info!("created network {:?}", network);
+        Ok(network)
+    }
+
+    async fn delete_network(&self, name: &str) -> Result<()> {
+        self.delete_stack(name, Duration::from_secs(600)).await
+    }
+
+    async fn create_node_group(
+        &self,
+        network: &Network,
+        spec: &NodeGroupSpec,
+    ) -> Result<NodeGroup> {
+        let tmpl = template("asg_amd64_ubuntu.yaml")?;
+        let mut params = BTreeMap::from([
+            (String::from("Id"), spec.cluster_id.clone()),
+            (String::from("NodeKind"), spec.kind.as_str().to_string()),
+            (
+                String::from("PublicSubnetIds"),
+                network.subnet_ids.join(","),
+            ),
+            (
+                String::from("SecurityGroupId"),
+                network.security_group_id.clone(),
+            ),
+            (String::from("NlbVpcId"), network.id.clone()),
+            (
+                String::from("AsgDesiredCapacity"),
+                spec.desired_capacity.to_string(),
+            ),
+        ]);
+        params.extend(spec.parameters.clone());
+
+        self.cloudformation_manager
+            .create_stack(
+                &spec.name,
+                None,
+                OnFailure::Delete,
+                &tmpl,
+                Some(self.tags()),
+                Some(build_params(params)),
+            )
+            .await?;
+        let stack = self
+            .cloudformation_manager
+            .poll_stack(
+                &spec.name,
+                StackStatus::CreateComplete,
+                wait_for(spec.desired_capacity),
+                self.poll_interval,
+            )
+            .await?;
+
+        let outputs = outputs(stack.outputs);
+        let asg_name = outputs.get("AsgLogicalId").cloned().ok_or_else(|| Other {
+            message: format!("stack '{}' has no 'AsgLogicalId' output", spec.name),
+            is_retryable: false,
+        })?;
+        Ok(NodeGroup {
+            name: spec.name.clone(),
+            id: asg_name,
+            desired_capacity: spec.desired_capacity,
+            outputs,
+        })
+    }
+
+    async fn scale_node_group(
+        &self,
+        group: &NodeGroup,
+        desired_capacity: u32,
+    ) -> Result<NodeGroup> {
+        let ret = self
+            .cloudformation_manager
+            .update_stack_parameters(
+                &group.name,
+                None,
+                vec![build_param(
+                    "AsgDesiredCapacity",
+                    &desired_capacity.to_string(),
+                )],
+            )
+            .await;
+        match ret {
+            Ok(_) => {
+                self.cloudformation_manager
+                    .poll_stack(
+                        &group.name,
+                        StackStatus::UpdateComplete,
+                        wait_for(desired_capacity),
+                        self.poll_interval,
+                    )
+                    .await?;
+            }
+            Err(e) if e.message().contains("No updates are to be performed") => {
+                info!("node group '{}' is up-to-date", group.name);
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(NodeGroup {
+            desired_capacity,
+            ..group.clone()
+        })
+    }
+
+    async fn list_instances(&self, group: &NodeGroup) -> Result<Vec<Instance>> {
+        let droplets = self.ec2_manager.list_asg(&group.id).await?;
+        Ok(droplets
+            .into_iter()
+            .map(|d| Instance {
+                id: d.instance_id,
+                state: d.instance_state_name,
+                availability_zone: d.availability_zone,
+                public_ipv4: d.public_ipv4,
+            })
+            .collect())
+    }
+
+    async fn delete_node_group(&self, name: &str) -> Result<()> {
+        self.delete_stack(name, Duration::from_secs(MAX_WAIT_SECONDS))
+            .await
+    }
+
+    async fn create_bucket(&self, bucket: &str) -> Result<()> {
+        self.s3_manager.create_bucket(bucket).await
+    }
+
+    async fn delete_bucket(&self, bucket: &str) -> Result<()> {
+        self.s3_manager.delete_bucket(bucket).await
+    }
+
+    async fn put_object(&self, bucket: &str, key: &str, data: Vec<u8>) -> Result<()> {
+        self.s3_manager
+            .put_bytes(
+                data,
+                Arc::new(bucket.to_string()),
+                Arc::new(key.to_string()),
+            )
+            .await
+    }
+
+    async fn get_object(&self, bucket: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        self.s3_manager.get_bytes(bucket, key, None).await
+    }
+
+    async fn delete_objects(&self, bucket: &str, prefix: Option<&str>) -> Result<()> {
+        self.s3_manager
+            .delete_objects(
+                Arc::new(bucket.to_string()),
+                prefix.map(|p| Arc::new(p.to_string())),
+            )
+            .await
+    }
+
+    async fn create_key(&self, description: &str) -> Result<Key> {
+        let key = self.kms_manager.create_key(description).await?;
+        Ok(Key {
+            id: key.id,
+            arn: key.arn,
+        })
+    }
+
+    async fn encrypt(&self, key_id: &str, plaintext: Vec<u8>) -> Result<Vec<u8>> {
+        self.kms_manager.encrypt(key_id, None, plaintext).await
+    }
+
+    async fn decrypt(&self, key_id: &str, ciphertext: Vec<u8>) -> Result<Vec<u8>> {
+        self.kms_manager.decrypt(key_id, None, ciphertext).await
+    }
+
+    async fn delete_key(&self, key_id: &str) -> Result<()> {
+        self.kms_manager.schedule_to_delete(key_id).await
+    }
+}
+
+impl Provider {
+    async fn delete_stack(&self, name: &str, timeout: Duration) -> Result<()> {
+        self.cloudformation_manager.delete_stack(name).await?;
+        self.cloudformation_manager
+            .poll_stack(
+                name,
+                StackStatus::DeleteComplete,
+                timeout,
+                self.poll_interval,
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+fn template(name: &str) -> Result<String> {
+    let path = format!("src/aws/cfn-templates/avalanche-node/{}", name);
+    let f = Asset::get(&path).ok_or_else(|| Other {
+        message: format!("template '{}' not found", path),
+        is_retryable: false,
+    })?;
+    Ok(String::from_utf8_lossy(f.data.as_ref()).to_string())
+}
+
+fn build_param(k: &str, v: &str) -> Parameter {
+    Parameter::builder()
+        .parameter_key(k)
+        .parameter_value(v)
+        .build()
+}
+
+fn build_params(params: BTreeMap<String, String>) -> Vec<Parameter> {
+    params.iter().map(|(k, v)| build_param(k, v)).collect()
+}
+
+fn outputs(outputs: Option<Vec<Output>>) -> BTreeMap<String, String> {
+    let mut m = BTreeMap::new();
+    for o in outputs.unwrap_or_default() {
+        let k = o.output_key.unwrap_or_default();
+        let v = o.output_value.unwrap_or_default();
+        info!("stack output key=[{}], value=[{}]", k, v);
+        m.insert(k, v);
+    }
+    m
+}
+
+fn wait_for(desired_capacity: u32) -> Duration {
+    Duration::from_secs((300 + 60 * desired_capacity as u64).min(MAX_WAIT_SECONDS))
+}
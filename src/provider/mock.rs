@@ -0,0 +1,395 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    errors::{self, Result},
+    provider::{CloudProvider, Instance, Key, Network, NetworkSpec, NodeGroup, NodeGroupSpec},
+};
+
+const AVAILABILITY_ZONES: [&str; 3] = ["us-west-2a", "us-west-2b", "us-west-2c"];
+
+/// Defines the provider operations, to inject the failures.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Operation {
+    CreateNetwork,
+    DeleteNetwork,
+    CreateNodeGroup,
+    ScaleNodeGroup,
+    ListInstances,
+    DeleteNodeGroup,
+    CreateBucket,
+    DeleteBucket,
+    PutObject,
+    GetObject,
+    DeleteObjects,
+    CreateKey,
+    Encrypt,
+    Decrypt,
+    DeleteKey,
+}
+
+/// Simulates the cloud resources in memory, so the orchestration is
+/// tested without the cloud account. The node groups launch and
+/// terminate the instances like ASGs, and the injected failures are
+/// classified with the AWS error codes (e.g., "ThrottlingException").
+/// "Clone" shares the state, to inspect it after moving the provider.
+#[derive(Debug, Clone, Default)]
+pub struct MockProvider {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    networks: BTreeMap<String, Network>,
+    node_groups: BTreeMap<String, (NodeGroup, Vec<Instance>)>,
+    buckets: BTreeMap<String, BTreeMap<String, Vec<u8>>>,
+    /// Key ID to the deleted flag.
+    keys: BTreeMap<String, bool>,
+    /// Operation to the error codes of the next calls.
+    failures: BTreeMap<Operation, Vec<String>>,
+    calls: Vec<Operation>,
+    next_id: u64,
+}
+
+impl State {
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}-mock{:08x}", prefix, self.next_id)
+    }
+
+    fn launch(&mut self, group: &str, n: usize) -> Vec<Instance> {
+        (0..n)
+            .map(|_| {
+                let id = self.next_id("i");
+                let az = AVAILABILITY_ZONES[(self.next_id % 3) as usize];
+                Instance {
+                    id,
+                    state: String::from("running"),
+                    availability_zone: String::from(az),
+                    public_ipv4: format!("10.0.{}.{}", group.len() % 256, self.next_id % 256),
+                }
+            })
+            .collect()
+    }
+
+    /// Records the call, and returns the injected failure if any.
+    fn call(&mut self, op: Operation) -> Result<()> {
+        self.calls.push(op);
+        if let Some(codes) = self.failures.get_mut(&op) {
+            if !codes.is_empty() {
+                let code = codes.remove(0);
+                return Err(errors::classify(
+                    format!("injected {:?} failure", op),
+                    Some(&code),
+                    false,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fails the next "times" calls of the operation with the AWS error code.
+    pub fn fail(&self, op: Operation, code: &str, times: usize) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .failures
+            .entry(op)
+            .or_default()
+            .extend(std::iter::repeat_n(String::from(code), times));
+    }
+
+    /// Returns the operations called so far, in order.
+    pub fn calls(&self) -> Vec<Operation> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    /// Returns the number of calls of the operation.
+    pub fn count(&self, op: Operation) -> usize {
+        self.calls().iter().filter(|c| **c == op).count()
+    }
+
+    pub fn has_network(&self, name: &str) -> bool {
+        self.state.lock().unwrap().networks.contains_key(name)
+    }
+
+    pub fn has_node_group(&self, name: &str) -> bool {
+        self.state.lock().unwrap().node_groups.contains_key(name)
+    }
+
+    pub fn has_bucket(&self, bucket: &str) -> bool {
+        self.state.lock().unwrap().buckets.contains_key(bucket)
+    }
+
+    /// Returns the number of the keys not deleted.
+    pub fn active_keys(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .keys
+            .values()
+            .filter(|deleted| !**deleted)
+            .count()
+    }
+
+    /// Terminates the instance, and launches its replacement like ASGs.
+    /// Returns the replacement instance ID.
+    pub fn terminate_instance(&self, instance_id: &str) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        let group = state
+            .node_groups
+            .iter()
+            .find(|(_, (_, instances))| instances.iter().any(|i| i.id == instance_id))
+            .map(|(name, _)| name.clone())?;
+        let replacement = state.launch(&group, 1).remove(0);
+        let replacement_id = replacement.id.clone();
+        let (_, instances) = state.node_groups.get_mut(&group).unwrap();
+        instances.retain(|i| i.id != instance_id);
+        instances.push(replacement);
+        Some(replacement_id)
+    }
+}
+
+impl CloudProvider for MockProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn create_network(&self, spec: &NetworkSpec) -> Result<Network> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::CreateNetwork)?;
+        if state.networks.contains_key(&spec.name) {
+            return Err(errors::classify(
+                format!("network '{}' already exists", spec.name),
+                Some("AlreadyExistsException"),
+                false,
+            ));
+        }
+        let network = Network {
+            name: spec.name.clone(),
+            id: state.next_id("vpc"),
+            security_group_id: state.next_id("sg"),
+            subnet_ids: spec
+                .subnet_cidrs
+                .iter()
+                .map(|_| state.next_id("subnet"))
+                .collect(),
+            outputs: BTreeMap::new(),
+        };
+        state.networks.insert(spec.name.clone(), network.clone());
+        Ok(network)
+    }
+
+    async fn delete_network(&self, name: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::DeleteNetwork)?;
+        state.networks.remove(name);
+        Ok(())
+    }
+
+    async fn create_node_group(
+        &self,
+        network: &Network,
+        spec: &NodeGroupSpec,
+    ) -> Result<NodeGroup> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::CreateNodeGroup)?;
+        if !state.networks.contains_key(&network.name) {
+            return Err(errors::classify(
+                format!("network '{}' does not exist", network.name),
+                Some("InvalidVpcID.NotFound"),
+                false,
+            ));
+        }
+        if state.node_groups.contains_key(&spec.name) {
+            return Err(errors::classify(
+                format!("node group '{}' already exists", spec.name),
+                Some("AlreadyExistsException"),
+                false,
+            ));
+        }
+        let group = NodeGroup {
+            name: spec.name.clone(),
+            id: state.next_id("asg"),
+            desired_capacity: spec.desired_capacity,
+            outputs: BTreeMap::new(),
+        };
+        let instances = state.launch(&spec.name, spec.desired_capacity as usize);
+        state
+            .node_groups
+            .insert(spec.name.clone(), (group.clone(), instances));
+        Ok(group)
+    }
+
+    async fn scale_node_group(
+        &self,
+        group: &NodeGroup,
+        desired_capacity: u32,
+    ) -> Result<NodeGroup> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::ScaleNodeGroup)?;
+        let current = match state.node_groups.get(&group.name) {
+            Some((_, instances)) => instances.len(),
+            None => {
+                return Err(errors::classify(
+                    format!("node group '{}' does not exist", group.name),
+                    Some("ValidationError"),
+                    false,
+                ))
+            }
+        };
+        let launched = state.launch(
+            &group.name,
+            (desired_capacity as usize).saturating_sub(current),
+        );
+        let (g, instances) = state.node_groups.get_mut(&group.name).unwrap();
+        instances.extend(launched);
+        // ASGs terminate the newest instances first by default
+        instances.truncate(desired_capacity as usize);
+        g.desired_capacity = desired_capacity;
+        Ok(g.clone())
+    }
+
+    async fn list_instances(&self, group: &NodeGroup) -> Result<Vec<Instance>> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::ListInstances)?;
+        Ok(state
+            .node_groups
+            .get(&group.name)
+            .map(|(_, instances)| instances.clone())
+            .unwrap_or_default())
+    }
+
+    async fn delete_node_group(&self, name: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::DeleteNodeGroup)?;
+        state.node_groups.remove(name);
+        Ok(())
+    }
+
+    async fn create_bucket(&self, bucket: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::CreateBucket)?;
+        state.buckets.entry(bucket.to_string()).or_default();
+        Ok(())
+    }
+
+    async fn delete_bucket(&self, bucket: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::DeleteBucket)?;
+        if state.buckets.get(bucket).map(|b| !b.is_empty()) == Some(true) {
+            return Err(errors::classify(
+                format!("bucket '{}' is not empty", bucket),
+                Some("BucketNotEmpty"),
+                false,
+            ));
+        }
+        state.buckets.remove(bucket);
+        Ok(())
+    }
+
+    async fn put_object(&self, bucket: &str, key: &str, data: Vec<u8>) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::PutObject)?;
+        match state.buckets.get_mut(bucket) {
+            Some(objects) => {
+                objects.insert(key.to_string(), data);
+                Ok(())
+            }
+            None => Err(no_such_bucket(bucket)),
+        }
+    }
+
+    async fn get_object(&self, bucket: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::GetObject)?;
+        match state.buckets.get(bucket) {
+            Some(objects) => Ok(objects.get(key).cloned()),
+            None => Err(no_such_bucket(bucket)),
+        }
+    }
+
+    async fn delete_objects(&self, bucket: &str, prefix: Option<&str>) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::DeleteObjects)?;
+        if let Some(objects) = state.buckets.get_mut(bucket) {
+            objects.retain(|k, _| !k.starts_with(prefix.unwrap_or_default()));
+        }
+        Ok(())
+    }
+
+    async fn create_key(&self, description: &str) -> Result<Key> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::CreateKey)?;
+        let id = state.next_id("key");
+        state.keys.insert(id.clone(), false);
+        Ok(Key {
+            arn: format!(
+                "arn:aws:kms:us-west-2:123456789012:key/{}/{}",
+                id, description
+            ),
+            id,
+        })
+    }
+
+    async fn encrypt(&self, key_id: &str, plaintext: Vec<u8>) -> Result<Vec<u8>> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::Encrypt)?;
+        check_key(&state, key_id)?;
+        Ok([format!("{}:", key_id).into_bytes(), plaintext].concat())
+    }
+
+    async fn decrypt(&self, key_id: &str, ciphertext: Vec<u8>) -> Result<Vec<u8>> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::Decrypt)?;
+        check_key(&state, key_id)?;
+        let prefix = format!("{}:", key_id).into_bytes();
+        if !ciphertext.starts_with(&prefix) {
+            return Err(errors::classify(
+                String::from("ciphertext was not encrypted with the key"),
+                Some("InvalidCiphertextException"),
+                false,
+            ));
+        }
+        Ok(ciphertext[prefix.len()..].to_vec())
+    }
+
+    async fn delete_key(&self, key_id: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.call(Operation::DeleteKey)?;
+        check_key(&state, key_id)?;
+        state.keys.insert(key_id.to_string(), true);
+        Ok(())
+    }
+}
+
+fn no_such_bucket(bucket: &str) -> errors::Error {
+    errors::classify(
+        format!("bucket '{}' does not exist", bucket),
+        Some("NoSuchBucket"),
+        false,
+    )
+}
+
+fn check_key(state: &State, key_id: &str) -> Result<()> {
+    match state.keys.get(key_id) {
+        Some(false) => Ok(()),
+        Some(true) => Err(errors::classify(
+            format!("key '{}' is pending deletion", key_id),
+            Some("KMSInvalidStateException"),
+            false,
+        )),
+        None => Err(errors::classify(
+            format!("key '{}' does not exist", key_id),
+            Some("NotFoundException"),
+            false,
+        )),
+    }
+}
@@ -0,0 +1,147 @@
+pub mod aws;
+pub mod mock;
+
+use std::{collections::BTreeMap, future::Future};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{avalanche::node, errors::Result};
+
+/// Represents the network of the cluster (e.g., AWS VPC).
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct Network {
+    pub name: String,
+    pub id: String,
+    pub security_group_id: String,
+    pub subnet_ids: Vec<String>,
+    /// Provider-specific outputs (e.g., the VPC peering connection ID).
+    #[serde(default)]
+    pub outputs: BTreeMap<String, String>,
+}
+
+/// Defines the network to create.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NetworkSpec {
+    pub name: String,
+    pub cluster_id: String,
+    pub cidr: String,
+    /// One subnet per availability zone.
+    pub subnet_cidrs: Vec<String>,
+    pub ingress_ipv4_range: String,
+    pub http_port: u32,
+    pub staking_port: u32,
+    /// Provider-specific settings (e.g., CloudFormation parameters
+    /// for the private connectivity), ignored by the other providers.
+    pub parameters: BTreeMap<String, String>,
+}
+
+/// Represents the group of nodes of the same kind (e.g., AWS ASG).
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct NodeGroup {
+    pub name: String,
+    /// Provider resource ID (e.g., ASG name).
+    pub id: String,
+    pub desired_capacity: u32,
+    /// Provider-specific outputs (e.g., the NLB DNS name).
+    #[serde(default)]
+    pub outputs: BTreeMap<String, String>,
+}
+
+/// Defines the node group to create.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NodeGroupSpec {
+    pub name: String,
+    pub cluster_id: String,
+    pub kind: node::Kind,
+    pub desired_capacity: u32,
+    /// Provider-specific settings (e.g., CloudFormation parameters
+    /// for the instance profile and the NLB), ignored by the other providers.
+    /// Overrides the settings derived from the other fields.
+    pub parameters: BTreeMap<String, String>,
+}
+
+/// Represents a machine in the node group.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Instance {
+    pub id: String,
+    /// e.g., "pending", "running", "terminated".
+    pub state: String,
+    pub availability_zone: String,
+    pub public_ipv4: String,
+}
+
+/// Represents the customer-managed key (e.g., AWS KMS CMK).
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct Key {
+    pub id: String,
+    pub arn: String,
+}
+
+/// Cloud operations of the cluster orchestration, so the orchestration
+/// is written once over the AWS and the other backends, and tested
+/// without the cloud account.
+/// The methods return "Send" futures, so the callers can spawn them on
+/// the multi-threaded runtime. The implementations may still write
+/// "async fn" as long as they hold no "!Send" value across ".await".
+pub trait CloudProvider {
+    /// Returns the provider name (e.g., "aws").
+    fn name(&self) -> &'static str;
+
+    /// Creates the network and waits for its completion.
+    fn create_network(&self, spec: &NetworkSpec) -> impl Future<Output = Result<Network>> + Send;
+    /// Deletes the network, and succeeds if already deleted.
+    fn delete_network(&self, name: &str) -> impl Future<Output = Result<()>> + Send;
+
+    /// Creates the node group in the network and waits for its completion.
+    fn create_node_group(
+        &self,
+        network: &Network,
+        spec: &NodeGroupSpec,
+    ) -> impl Future<Output = Result<NodeGroup>> + Send;
+    /// Updates the desired capacity of the node group.
+    fn scale_node_group(
+        &self,
+        group: &NodeGroup,
+        desired_capacity: u32,
+    ) -> impl Future<Output = Result<NodeGroup>> + Send;
+    fn list_instances(
+        &self,
+        group: &NodeGroup,
+    ) -> impl Future<Output = Result<Vec<Instance>>> + Send;
+    /// Deletes the node group, and succeeds if already deleted.
+    fn delete_node_group(&self, name: &str) -> impl Future<Output = Result<()>> + Send;
+
+    fn create_bucket(&self, bucket: &str) -> impl Future<Output = Result<()>> + Send;
+    fn delete_bucket(&self, bucket: &str) -> impl Future<Output = Result<()>> + Send;
+    fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        data: Vec<u8>,
+    ) -> impl Future<Output = Result<()>> + Send;
+    /// Returns None if the object does not exist.
+    fn get_object(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> impl Future<Output = Result<Option<Vec<u8>>>> + Send;
+    fn delete_objects(
+        &self,
+        bucket: &str,
+        prefix: Option<&str>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn create_key(&self, description: &str) -> impl Future<Output = Result<Key>> + Send;
+    fn encrypt(
+        &self,
+        key_id: &str,
+        plaintext: Vec<u8>,
+    ) -> impl Future<Output = Result<Vec<u8>>> + Send;
+    fn decrypt(
+        &self,
+        key_id: &str,
+        ciphertext: Vec<u8>,
+    ) -> impl Future<Output = Result<Vec<u8>>> + Send;
+    /// Schedules the key deletion.
+    fn delete_key(&self, key_id: &str) -> impl Future<Output = Result<()>> + Send;
+}
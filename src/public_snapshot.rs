@@ -0,0 +1,333 @@
+use std::io::{self, Error, ErrorKind};
+
+use aws_types::SdkConfig as AwsSdkConfig;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    aws::lease,
+    utils::{compress, rfc3339},
+};
+
+pub const LEASE_TABLE_NAME: &str = "avalanche-ops-public-snapshot-leases";
+const LEASE_HASH_KEY: &str = "publisher_id";
+
+/// Written while "avalanched" has avalanchego stopped to archive the database,
+/// so the remediation does not restart the node, and the node stays out of
+/// the NLB target group.
+pub const IN_PROGRESS_FILE_PATH: &str = "/var/lib/avalanched/public-snapshot-in-progress";
+
+pub const DEFAULT_S3_PREFIX: &str = "snapshots";
+pub const DEFAULT_INTERVAL_HOURS: u64 = 24;
+pub const DEFAULT_RETENTION: usize = 3;
+
+/// Archive method of the published database, so the community nodes
+/// can unpack with "tar --zstd" or "avalanched backup download".
+pub const ENCODER: compress::DirEncoder = compress::DirEncoder::TarZstd(3);
+
+/// Represents the periodic database snapshot for the community to bootstrap
+/// the nodes of the subnet from. One API node (see "api_exposure") leases the
+/// publisher role, stops avalanchego to archive its pruned database, and uploads
+/// the archive with the checksums and the manifest to the separate bucket,
+/// which is public-read (or requester-pays) under "s3_prefix".
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct PublicSnapshot {
+    /// Created by "apply" in the cluster region, and kept on "delete"
+    /// for the community downloads. Must not be the cluster bucket.
+    pub s3_bucket: String,
+    /// None for "DEFAULT_S3_PREFIX".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub s3_prefix: Option<String>,
+    /// Set true for the downloaders to pay the transfer, which requires
+    /// the signed requests (e.g., "aws s3 cp --request-payer requester").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requester_pays: Option<bool>,
+    /// None for "DEFAULT_INTERVAL_HOURS".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval_hours: Option<u64>,
+    /// Number of the snapshots to keep. None for "DEFAULT_RETENTION".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<usize>,
+}
+
+impl PublicSnapshot {
+    pub fn s3_prefix(&self) -> String {
+        self.s3_prefix
+            .clone()
+            .unwrap_or_else(|| DEFAULT_S3_PREFIX.to_string())
+            .trim_matches('/')
+            .to_string()
+    }
+
+    pub fn requester_pays(&self) -> bool {
+        self.requester_pays.unwrap_or(false)
+    }
+
+    pub fn interval_hours(&self) -> u64 {
+        self.interval_hours.unwrap_or(DEFAULT_INTERVAL_HOURS)
+    }
+
+    pub fn retention(&self) -> usize {
+        self.retention.unwrap_or(DEFAULT_RETENTION)
+    }
+
+    pub fn validate(&self, cluster_s3_bucket: Option<&str>) -> io::Result<()> {
+        if self.s3_bucket.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "empty 'public_snapshot.s3_bucket'",
+            ));
+        }
+        // the cluster bucket has the sealed keys and the spec
+        if Some(self.s3_bucket.as_str()) == cluster_s3_bucket {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'public_snapshot.s3_bucket' must not be the cluster bucket 'aws_resources.s3_bucket'",
+            ));
+        }
+        if self.s3_prefix().is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "empty 'public_snapshot.s3_prefix' (the bucket root is not public)",
+            ));
+        }
+        if self.interval_hours() == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'public_snapshot.interval_hours' must be positive",
+            ));
+        }
+        if self.retention() == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "'public_snapshot.retention' must be positive",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the snapshot directory (e.g., "snapshots/20221001093000").
+    pub fn dir(&self, snapshot_id: &str) -> String {
+        format!("{}/{}", self.s3_prefix(), snapshot_id)
+    }
+
+    /// Returns the S3 key of the latest manifest, overwritten last
+    /// once the snapshot is complete.
+    pub fn latest_key(&self) -> String {
+        format!("{}/latest.json", self.s3_prefix())
+    }
+
+    /// Returns the public URL of the object, in the bucket region.
+    pub fn url(&self, region: &str, key: &str) -> String {
+        format!(
+            "https://{}.s3.{}.amazonaws.com/{}",
+            self.s3_bucket, region, key
+        )
+    }
+
+    /// Returns the bucket policy that allows anyone to list and download
+    /// the snapshots under the prefix, and nothing else in the bucket.
+    /// The partition is of the bucket region (e.g., "aws-cn" in China).
+    pub fn bucket_policy(&self, partition: &str) -> String {
+        serde_json::json!({
+            "Version": "2012-10-17",
+            "Statement": [
+                {
+                    "Sid": "PublicSnapshotRead",
+                    "Effect": "Allow",
+                    "Principal": "*",
+                    "Action": "s3:GetObject",
+                    "Resource": format!(
+                        "arn:{}:s3:::{}/{}/*",
+                        partition,
+                        self.s3_bucket,
+                        self.s3_prefix()
+                    ),
+                },
+                {
+                    "Sid": "PublicSnapshotList",
+                    "Effect": "Allow",
+                    "Principal": "*",
+                    "Action": "s3:ListBucket",
+                    "Resource": format!("arn:{}:s3:::{}", partition, self.s3_bucket),
+                    "Condition": {
+                        "StringLike": {"s3:prefix": format!("{}/*", self.s3_prefix())}
+                    }
+                }
+            ]
+        })
+        .to_string()
+    }
+
+    /// Returns the snapshot IDs to delete, given the S3 keys under the prefix,
+    /// keeping the newest "retention" complete snapshots.
+    pub fn expired_snapshot_ids(&self, keys: &[String]) -> Vec<String> {
+        let pfx = format!("{}/", self.s3_prefix());
+        let mut ids: Vec<String> = keys
+            .iter()
+            .filter_map(|k| k.strip_prefix(&pfx))
+            .filter_map(|k| k.strip_suffix("/manifest.json"))
+            .filter(|id| !id.is_empty() && !id.contains('/'))
+            .map(String::from)
+            .collect();
+        // the IDs are timestamps, so newest first
+        ids.sort_by(|a, b| b.cmp(a));
+        ids.dedup();
+        ids.into_iter().skip(self.retention()).collect()
+    }
+}
+
+/// Returns the lease of the publisher role, so only one API node
+/// of the cluster stops to archive its database.
+pub fn lease_manager(shared_config: &AwsSdkConfig) -> lease::Manager {
+    lease::Manager::new(shared_config, LEASE_TABLE_NAME, LEASE_HASH_KEY)
+}
+
+/// Returns the lease key of the publisher role (e.g., "my-cluster/public-snapshot").
+pub fn lease_key(cluster_id: &str) -> String {
+    format!("{}/public-snapshot", cluster_id)
+}
+
+pub fn archive_key(dir: &str) -> String {
+    format!("{}/db{}", dir, ENCODER.ext())
+}
+
+pub fn checksums_key(dir: &str) -> String {
+    format!("{}/SHA256SUMS", dir)
+}
+
+pub fn manifest_key(dir: &str) -> String {
+    format!("{}/manifest.json", dir)
+}
+
+/// Returns the new snapshot ID from the time, which sorts in order.
+pub fn snapshot_id(now: DateTime<Utc>) -> String {
+    now.format("%Y%m%d%H%M%S").to_string()
+}
+
+/// Represents the archived database in the snapshot,
+/// which unpacks into the avalanchego "--db-dir".
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Archive {
+    pub s3_key: String,
+    pub size_bytes: u64,
+    pub sha256: String,
+    /// e.g., "tar-zstd" for "avalanched backup download --unarchive-decompression-method".
+    pub decompression_method: String,
+}
+
+/// Represents the manifest of the public snapshot, uploaded next to the archive
+/// and as "latest.json", so the downloaders find and verify the newest snapshot.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Manifest {
+    pub snapshot_id: String,
+    pub network_id: u32,
+    /// Subnets tracked by the node, whose chain databases are in the archive.
+    #[serde(default)]
+    pub subnet_ids: Vec<String>,
+    #[serde(with = "rfc3339::serde_format")]
+    pub created_at: DateTime<Utc>,
+    pub requester_pays: bool,
+    pub archive: Archive,
+}
+
+impl Manifest {
+    pub fn encode_json(&self) -> io::Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to encode {}", e)))
+    }
+
+    pub fn decode_json(d: &[u8]) -> io::Result<Self> {
+        serde_json::from_slice(d).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid public snapshot manifest {}", e),
+            )
+        })
+    }
+
+    /// Returns the checksums in the "sha256sum" format,
+    /// for "sha256sum -c SHA256SUMS" next to the downloaded archive.
+    pub fn checksums(&self) -> String {
+        let file_name = self.archive.s3_key.rsplit('/').next().unwrap_or_default();
+        format!("{}  {}\n", self.archive.sha256, file_name)
+    }
+
+    /// Returns true if the next snapshot is due.
+    pub fn is_due(&self, now: DateTime<Utc>, interval_hours: u64) -> bool {
+        now.signed_duration_since(self.created_at).num_seconds() >= (interval_hours * 3600) as i64
+    }
+}
+
+#[test]
+fn test_public_snapshot() {
+    use chrono::TimeZone;
+
+    let p: PublicSnapshot = serde_yaml::from_str("s3_bucket: my-public-snapshots\n").unwrap();
+    assert!(p.validate(Some("my-cluster-bucket")).is_ok());
+    assert!(p.validate(Some("my-public-snapshots")).is_err());
+    assert_eq!(p.s3_prefix(), "snapshots");
+    assert!(!p.requester_pays());
+
+    let id = snapshot_id(Utc.with_ymd_and_hms(2022, 10, 1, 9, 30, 0).unwrap());
+    assert_eq!(id, "20221001093000");
+    let d = p.dir(&id);
+    assert_eq!(d, "snapshots/20221001093000");
+    assert_eq!(archive_key(&d), "snapshots/20221001093000/db.tar.zstd");
+    assert_eq!(p.latest_key(), "snapshots/latest.json");
+    assert_eq!(
+        p.url("us-west-2", &archive_key(&d)),
+        "https://my-public-snapshots.s3.us-west-2.amazonaws.com/snapshots/20221001093000/db.tar.zstd"
+    );
+
+    let policy: serde_json::Value = serde_json::from_str(&p.bucket_policy("aws")).unwrap();
+    assert_eq!(
+        policy["Statement"][0]["Resource"],
+        "arn:aws:s3:::my-public-snapshots/snapshots/*"
+    );
+    let policy: serde_json::Value = serde_json::from_str(&p.bucket_policy("aws-cn")).unwrap();
+    assert_eq!(
+        policy["Statement"][1]["Resource"],
+        "arn:aws-cn:s3:::my-public-snapshots"
+    );
+
+    let keys: Vec<String> = [
+        "snapshots/latest.json",
+        "snapshots/20221001000000/db.tar.zstd",
+        "snapshots/20221001000000/manifest.json",
+        "snapshots/20221002000000/manifest.json",
+        "snapshots/20221003000000/manifest.json",
+        "snapshots/20221004000000/manifest.json",
+        // incomplete, so never listed as expired
+        "snapshots/20221005000000/db.tar.zstd",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    assert_eq!(
+        p.expired_snapshot_ids(&keys),
+        vec![String::from("20221001000000")]
+    );
+
+    let manifest = Manifest {
+        snapshot_id: id.clone(),
+        network_id: 1000000,
+        subnet_ids: vec![],
+        created_at: Utc.with_ymd_and_hms(2022, 10, 1, 9, 30, 0).unwrap(),
+        requester_pays: false,
+        archive: Archive {
+            s3_key: archive_key(&d),
+            size_bytes: 5,
+            sha256: String::from("abc"),
+            decompression_method: String::from("tar-zstd"),
+        },
+    };
+    assert_eq!(manifest.checksums(), "abc  db.tar.zstd\n");
+    let decoded = Manifest::decode_json(manifest.encode_json().unwrap().as_bytes()).unwrap();
+    assert_eq!(decoded, manifest);
+    assert!(!manifest.is_due(Utc.with_ymd_and_hms(2022, 10, 2, 9, 0, 0).unwrap(), 24));
+    assert!(manifest.is_due(Utc.with_ymd_and_hms(2022, 10, 2, 9, 30, 0).unwrap(), 24));
+}
@@ -0,0 +1,267 @@
+use std::io::{self, Error, ErrorKind};
+
+use aws_sdk_cloudwatch::model::{Dimension, MetricDatum, StandardUnit};
+use serde::{Deserialize, Serialize};
+
+/// Default window to count the restarts for "reboot_after_restarts".
+pub const DEFAULT_RESTART_WINDOW_MINUTES: u32 = 60;
+
+/// Minimum "restart_after_unhealthy_minutes", to not restart the nodes
+/// that are still bootstrapping or briefly unhealthy.
+pub const MIN_UNHEALTHY_MINUTES: u32 = 5;
+
+/// Path to the JSON lines of the remediation actions on the node,
+/// shipped to CloudWatch Logs with the avalanched logs.
+pub const ACTION_LOG_FILE_PATH: &str = "/var/log/avalanched/remediation.log";
+
+/// Represents the remediation policies that "avalanched" applies
+/// to the local node once it becomes healthy for the first time.
+/// All policies are disabled by default.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Remediation {
+    /// Restarts avalanchego if the health check keeps failing for the minutes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart_after_unhealthy_minutes: Option<u32>,
+    /// Stops avalanchego, removes the database, and restarts avalanched
+    /// to download the database backup again (or bootstrap from the peers,
+    /// if no backup), if the avalanchego logs report the database corruption.
+    #[serde(default)]
+    pub rebootstrap_on_db_corruption: bool,
+    /// Reboots the instance if the node is still unhealthy after
+    /// the restarts within "restart_window_minutes".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reboot_after_restarts: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart_window_minutes: Option<u32>,
+}
+
+/// Represents a remediation action.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Action {
+    RestartNode,
+    Rebootstrap,
+    RebootInstance,
+}
+
+impl Action {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::RestartNode => "restart-node",
+            Action::Rebootstrap => "rebootstrap",
+            Action::RebootInstance => "reboot-instance",
+        }
+    }
+
+    /// Returns the CloudWatch metric of the action, with the node kind and
+    /// the action dimensions for the alarms.
+    pub fn to_cw_metric_data(&self, node_kind: &str) -> Vec<MetricDatum> {
+        vec![MetricDatum::builder()
+            .metric_name("remediation_actions")
+            .dimensions(
+                Dimension::builder()
+                    .name("NodeKind")
+                    .value(node_kind)
+                    .build(),
+            )
+            .dimensions(
+                Dimension::builder()
+                    .name("Action")
+                    .value(self.as_str())
+                    .build(),
+            )
+            .value(1_f64)
+            .unit(StandardUnit::Count)
+            .build()]
+    }
+}
+
+impl Remediation {
+    pub fn is_enabled(&self) -> bool {
+        self.restart_after_unhealthy_minutes.is_some() || self.rebootstrap_on_db_corruption
+    }
+
+    pub fn restart_window_minutes(&self) -> u32 {
+        self.restart_window_minutes
+            .unwrap_or(DEFAULT_RESTART_WINDOW_MINUTES)
+    }
+
+    pub fn validate(&self) -> io::Result<()> {
+        if let Some(v) = self.restart_after_unhealthy_minutes {
+            if v < MIN_UNHEALTHY_MINUTES {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "'remediation.restart_after_unhealthy_minutes' {} below min {}",
+                        v, MIN_UNHEALTHY_MINUTES
+                    ),
+                ));
+            }
+        }
+        if let Some(v) = self.reboot_after_restarts {
+            if v == 0 {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "'remediation.reboot_after_restarts' must be positive",
+                ));
+            }
+            let unhealthy = match self.restart_after_unhealthy_minutes {
+                Some(v) => v,
+                None => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "'remediation.reboot_after_restarts' requires 'restart_after_unhealthy_minutes'",
+                    ));
+                }
+            };
+            // otherwise, the restarts never add up within the window
+            if v * unhealthy > self.restart_window_minutes() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "'remediation.restart_window_minutes' {} shorter than {} restarts every {} minutes",
+                        self.restart_window_minutes(),
+                        v,
+                        unhealthy
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns true if the avalanchego log line reports the database corruption
+/// (e.g., LevelDB "leveldb: corruption on data-block", checksum mismatches).
+pub fn is_db_corruption(line: &str) -> bool {
+    let line = line.to_lowercase();
+    (line.contains("corruption") || line.contains("checksum mismatch"))
+        && (line.contains("leveldb") || line.contains("database") || line.contains("db"))
+}
+
+/// Tracks the node health to decide the remediation actions.
+/// The times are in seconds (e.g., since the tracker started).
+#[derive(Debug, Clone)]
+pub struct Tracker {
+    policy: Remediation,
+    unhealthy_since: Option<u64>,
+    restarts: Vec<u64>,
+}
+
+impl Tracker {
+    pub fn new(policy: Remediation) -> Self {
+        Self {
+            policy,
+            unhealthy_since: None,
+            restarts: Vec::new(),
+        }
+    }
+
+    /// Records the health check result and whether the database corruption
+    /// was found since the last check. Returns the action to take, if any.
+    /// "uptime_secs" is the instance uptime, to not reboot in a loop.
+    pub fn observe(
+        &mut self,
+        now: u64,
+        healthy: bool,
+        db_corrupted: bool,
+        uptime_secs: u64,
+    ) -> Option<Action> {
+        if db_corrupted && self.policy.rebootstrap_on_db_corruption {
+            self.unhealthy_since = None;
+            return Some(Action::Rebootstrap);
+        }
+        if healthy {
+            self.unhealthy_since = None;
+            return None;
+        }
+
+        let since = *self.unhealthy_since.get_or_insert(now);
+        let unhealthy_secs = match self.policy.restart_after_unhealthy_minutes {
+            Some(v) => v as u64 * 60,
+            None => return None,
+        };
+        if now - since < unhealthy_secs {
+            return None;
+        }
+
+        let window_secs = self.policy.restart_window_minutes() as u64 * 60;
+        self.restarts.retain(|t| now - *t < window_secs);
+        if let Some(max) = self.policy.reboot_after_restarts {
+            if self.restarts.len() as u32 >= max && uptime_secs >= window_secs {
+                self.restarts.clear();
+                self.unhealthy_since = None;
+                return Some(Action::RebootInstance);
+            }
+        }
+
+        self.restarts.push(now);
+        // wait for the full period again after the restart
+        self.unhealthy_since = Some(now);
+        Some(Action::RestartNode)
+    }
+}
+
+#[test]
+fn test_tracker() {
+    let policy = Remediation {
+        restart_after_unhealthy_minutes: Some(10),
+        rebootstrap_on_db_corruption: true,
+        reboot_after_restarts: Some(2),
+        restart_window_minutes: Some(60),
+    };
+    assert!(policy.validate().is_ok());
+
+    let mut t = Tracker::new(policy.clone());
+    let uptime = 24 * 3600;
+    assert_eq!(t.observe(0, false, false, uptime), None);
+    assert_eq!(t.observe(300, false, false, uptime), None);
+    assert_eq!(
+        t.observe(600, false, false, uptime),
+        Some(Action::RestartNode)
+    );
+    assert_eq!(t.observe(900, false, false, uptime), None);
+    assert_eq!(
+        t.observe(1200, false, false, uptime),
+        Some(Action::RestartNode)
+    );
+    // still unhealthy after 2 restarts within the window
+    assert_eq!(
+        t.observe(1800, false, false, uptime),
+        Some(Action::RebootInstance)
+    );
+
+    // healthy resets the unhealthy period
+    let mut t = Tracker::new(policy.clone());
+    assert_eq!(t.observe(0, false, false, uptime), None);
+    assert_eq!(t.observe(500, true, false, uptime), None);
+    assert_eq!(t.observe(700, false, false, uptime), None);
+    assert_eq!(
+        t.observe(1300, false, false, uptime),
+        Some(Action::RestartNode)
+    );
+
+    // no reboot right after the previous reboot
+    let mut t = Tracker::new(policy.clone());
+    for now in [0, 600, 1200, 1800] {
+        assert_ne!(
+            t.observe(now, false, false, 600),
+            Some(Action::RebootInstance)
+        );
+    }
+
+    let mut t = Tracker::new(policy);
+    assert_eq!(t.observe(0, true, true, uptime), Some(Action::Rebootstrap));
+
+    assert!(is_db_corruption(
+        "ERROR leveldb: corruption on data-block (pos=123): checksum mismatch"
+    ));
+    assert!(!is_db_corruption("INFO accepted block"));
+
+    let p = Remediation {
+        reboot_after_restarts: Some(10),
+        restart_after_unhealthy_minutes: Some(10),
+        ..Remediation::default()
+    };
+    assert!(p.validate().is_err());
+}
@@ -0,0 +1,244 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Error, ErrorKind},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::avalanche::avalanchego::api::platform::ValidatorUptime;
+
+/// Primary network validators are rewarded only with the uptime
+/// at or above 80% over the staking period.
+/// ref. https://docs.avax.network/nodes/validate/how-to-stake#validators
+pub const DEFAULT_UPTIME_THRESHOLD_PERCENT: f64 = 80_f64;
+
+/// Samples kept per validator (e.g., 2 days of the hourly runs).
+pub const MAX_SAMPLES: usize = 48;
+
+/// Represents an uptime measurement of the validator.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Sample {
+    /// Unix timestamp in seconds.
+    pub at: u64,
+    pub uptime_percent: f64,
+}
+
+/// Uptime samples of the validators from the previous "rewards" runs,
+/// stored in the S3 bucket to measure the uptime trend.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct History {
+    #[serde(default)]
+    pub samples: BTreeMap<String, Vec<Sample>>,
+}
+
+impl History {
+    pub fn parse(b: &[u8]) -> io::Result<Self> {
+        serde_json::from_slice(b).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid uptime history {}", e),
+            )
+        })
+    }
+
+    pub fn encode_json(&self) -> io::Result<Vec<u8>> {
+        serde_json::to_vec(self)
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to serialize {}", e)))
+    }
+
+    /// Adds the samples, and drops the validators no longer current.
+    pub fn record(&mut self, now: u64, uptimes: &[ValidatorUptime]) {
+        self.samples
+            .retain(|node_id, _| uptimes.iter().any(|u| &u.node_id == node_id));
+        for u in uptimes.iter() {
+            let samples = self.samples.entry(u.node_id.clone()).or_default();
+            samples.push(Sample {
+                at: now,
+                uptime_percent: u.uptime_percent,
+            });
+            if samples.len() > MAX_SAMPLES {
+                samples.drain(..samples.len() - MAX_SAMPLES);
+            }
+        }
+    }
+}
+
+/// Represents the reward status of a validator.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ValidatorReport {
+    pub node_id: String,
+    pub uptime_percent: f64,
+    pub connected: bool,
+    pub potential_reward: u64,
+    pub end_time: u64,
+    /// Uptime change per day over the samples, if two or more.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trend_percent_per_day: Option<f64>,
+    /// Uptime at the end time, if the trend continues.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projected_uptime_percent: Option<f64>,
+    /// True if the uptime is, or is projected to be, below the threshold.
+    pub at_risk: bool,
+}
+
+/// Represents the rewards of the cluster validators.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Report {
+    pub cluster_id: String,
+    pub uptime_threshold_percent: f64,
+    pub validators: Vec<ValidatorReport>,
+    /// Validators of the cluster not found in the current validators.
+    pub not_validating: Vec<String>,
+    pub total_weight: u64,
+    pub total_potential_reward: u64,
+    /// Potential rewards of the validators at risk.
+    pub at_risk_potential_reward: u64,
+}
+
+impl Report {
+    pub fn encode_yaml(&self) -> io::Result<String> {
+        serde_yaml::to_string(self)
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to serialize {}", e)))
+    }
+
+    pub fn at_risk(&self) -> Vec<&ValidatorReport> {
+        self.validators.iter().filter(|v| v.at_risk).collect()
+    }
+}
+
+/// Builds the report of the cluster validators ("node_ids") with the
+/// history that includes the current samples.
+pub fn report(
+    cluster_id: &str,
+    node_ids: &[String],
+    uptimes: &[ValidatorUptime],
+    history: &History,
+    now: u64,
+    uptime_threshold_percent: f64,
+) -> Report {
+    let mut validators = Vec::new();
+    let mut not_validating = Vec::new();
+    let (mut total_weight, mut total_potential_reward, mut at_risk_potential_reward) = (0, 0, 0);
+    for node_id in node_ids.iter() {
+        let u = match uptimes.iter().find(|u| &u.node_id == node_id) {
+            Some(v) => v,
+            None => {
+                not_validating.push(node_id.clone());
+                continue;
+            }
+        };
+        let trend = history
+            .samples
+            .get(node_id)
+            .and_then(|samples| trend_per_second(samples));
+        let projected = trend.map(|slope| {
+            let remaining = u.end_time.saturating_sub(now) as f64;
+            (u.uptime_percent + slope * remaining).clamp(0_f64, 100_f64)
+        });
+        let at_risk = u.uptime_percent < uptime_threshold_percent
+            || projected.map(|p| p < uptime_threshold_percent) == Some(true);
+
+        total_weight += u.weight;
+        total_potential_reward += u.potential_reward;
+        if at_risk {
+            at_risk_potential_reward += u.potential_reward;
+        }
+        validators.push(ValidatorReport {
+            node_id: node_id.clone(),
+            uptime_percent: u.uptime_percent,
+            connected: u.connected,
+            potential_reward: u.potential_reward,
+            end_time: u.end_time,
+            trend_percent_per_day: trend.map(|slope| slope * 86400_f64),
+            projected_uptime_percent: projected,
+            at_risk,
+        });
+    }
+    Report {
+        cluster_id: cluster_id.to_string(),
+        uptime_threshold_percent,
+        validators,
+        not_validating,
+        total_weight,
+        total_potential_reward,
+        at_risk_potential_reward,
+    }
+}
+
+/// Returns the uptime change per second between the oldest and the latest samples.
+fn trend_per_second(samples: &[Sample]) -> Option<f64> {
+    let (first, last) = (samples.first()?, samples.last()?);
+    if last.at <= first.at {
+        return None;
+    }
+    Some((last.uptime_percent - first.uptime_percent) / (last.at - first.at) as f64)
+}
+
+#[test]
+fn test_report() {
+    let u = |node_id: &str, uptime_percent: f64, end_time: u64| ValidatorUptime {
+        node_id: String::from(node_id),
+        end_time,
+        weight: 2000,
+        uptime_percent,
+        connected: true,
+        potential_reward: 100,
+    };
+    let day = 86400;
+    let node_ids = vec![
+        String::from("NodeID-A"),
+        String::from("NodeID-B"),
+        String::from("NodeID-C"),
+        String::from("NodeID-D"),
+    ];
+
+    let mut history = History::default();
+    history.record(
+        0,
+        &[u("NodeID-A", 99.0, 30 * day), u("NodeID-B", 95.0, 30 * day)],
+    );
+    let uptimes = vec![
+        // steady
+        u("NodeID-A", 99.0, 30 * day),
+        // losing 2% a day, 80% in 7.5 days
+        u("NodeID-B", 93.0, 30 * day),
+        // below the threshold
+        u("NodeID-C", 75.0, 30 * day),
+    ];
+    history.record(day, &uptimes);
+    assert_eq!(history.samples["NodeID-B"].len(), 2);
+    assert_eq!(history.samples["NodeID-C"].len(), 1);
+
+    let r = report(
+        "test",
+        &node_ids,
+        &uptimes,
+        &history,
+        day,
+        DEFAULT_UPTIME_THRESHOLD_PERCENT,
+    );
+    assert_eq!(r.validators.len(), 3);
+    assert_eq!(r.not_validating, vec![String::from("NodeID-D")]);
+    assert_eq!(r.total_weight, 6000);
+    assert_eq!(r.total_potential_reward, 300);
+    assert_eq!(r.at_risk_potential_reward, 200);
+
+    assert!(!r.validators[0].at_risk);
+    assert_eq!(r.validators[0].trend_percent_per_day, Some(0_f64));
+    assert!(r.validators[1].at_risk);
+    assert_eq!(r.validators[1].trend_percent_per_day, Some(-2_f64));
+    assert_eq!(r.validators[1].projected_uptime_percent, Some(35_f64));
+    assert!(r.validators[2].at_risk);
+    assert_eq!(r.validators[2].projected_uptime_percent, None);
+
+    let b = history.encode_json().unwrap();
+    assert_eq!(History::parse(&b).unwrap(), history);
+
+    // drops the validators no longer current
+    history.record(2 * day, &[u("NodeID-A", 99.0, 30 * day)]);
+    assert_eq!(history.samples.len(), 1);
+    for i in 0..MAX_SAMPLES as u64 {
+        history.record(3 * day + i, &[u("NodeID-A", 99.0, 30 * day)]);
+    }
+    assert_eq!(history.samples["NodeID-A"].len(), MAX_SAMPLES);
+}
@@ -0,0 +1,304 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::StorageNamespace;
+
+/// Filter pattern of the coreth RPC handler logs ("Served eth_call ..."),
+/// only written at the "debug" log level of the C-chain.
+/// ref. https://github.com/ava-labs/coreth/blob/master/rpc/handler.go
+pub const SERVED_LOG_FILTER_PATTERN: &str = "\"Served \"";
+
+/// Represents a TLS connection in the NLB access logs.
+/// ref. https://docs.aws.amazon.com/elasticloadbalancing/latest/network/load-balancer-access-logs.html#access-log-entry-format
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Connection {
+    /// End of the connection.
+    pub time: DateTime<Utc>,
+    pub client_ip: String,
+    pub received_bytes: u64,
+    pub sent_bytes: u64,
+    /// True if the TLS handshake failed or the client sent a TLS alert.
+    pub tls_error: bool,
+}
+
+/// Parses the NLB access log entry (space-separated fields).
+/// Returns None for the malformed lines.
+pub fn parse_nlb_log_line(line: &str) -> Option<Connection> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 12 || fields[0] != "tls" {
+        return None;
+    }
+    let time = NaiveDateTime::parse_from_str(fields[2], "%Y-%m-%dT%H:%M:%S").ok()?;
+    let (client_ip, _) = fields[5].rsplit_once(':')?;
+    Some(Connection {
+        time: Utc.from_utc_datetime(&time),
+        client_ip: client_ip.to_string(),
+        received_bytes: fields[9].parse().ok()?,
+        sent_bytes: fields[10].parse().ok()?,
+        tls_error: fields[8] == "-" || fields[11] != "-",
+    })
+}
+
+/// Parses the coreth "Served" log line into the RPC method
+/// and whether the call returned an error.
+pub fn parse_served_line(line: &str) -> Option<(String, bool)> {
+    let (_, rest) = line.split_once("Served ")?;
+    let method = rest.split_whitespace().next()?;
+    if method.is_empty() || !method.contains('_') {
+        return None;
+    }
+    Some((method.to_string(), rest.contains(" err=")))
+}
+
+/// Returns the S3 key prefix of the NLB access logs,
+/// the "access_logs.s3.prefix" of the NLB.
+pub fn nlb_access_logs_prefix(cluster_id: &str) -> String {
+    StorageNamespace::NlbAccessLogsDir(cluster_id.to_string()).encode()
+}
+
+/// Returns the S3 key prefixes of the NLB access logs of the UTC days in the window.
+/// ref. https://docs.aws.amazon.com/elasticloadbalancing/latest/network/load-balancer-access-logs.html#access-log-file-format
+pub fn nlb_access_logs_day_prefixes(
+    cluster_id: &str,
+    account_id: &str,
+    region: &str,
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let mut prefixes = Vec::new();
+    let mut day = since.date_naive();
+    while day <= now.date_naive() {
+        prefixes.push(format!(
+            "{}/AWSLogs/{}/elasticloadbalancing/{}/{}/",
+            nlb_access_logs_prefix(cluster_id),
+            account_id,
+            region,
+            day.format("%Y/%m/%d")
+        ));
+        day += Duration::days(1);
+    }
+    prefixes
+}
+
+/// Returns the bucket policy that allows the NLB to deliver the access logs
+/// under the prefix, with the caller account as the only log source.
+/// ref. https://docs.aws.amazon.com/elasticloadbalancing/latest/network/load-balancer-access-logs.html#access-logging-bucket-requirements
+pub fn nlb_access_logs_bucket_policy(s3_bucket: &str, prefix: &str, account_id: &str) -> String {
+    serde_json::json!({
+        "Version": "2012-10-17",
+        "Statement": [
+            {
+                "Sid": "AWSLogDeliveryWrite",
+                "Effect": "Allow",
+                "Principal": {"Service": "delivery.logs.amazonaws.com"},
+                "Action": "s3:PutObject",
+                "Resource": format!("arn:aws:s3:::{}/{}/AWSLogs/{}/*", s3_bucket, prefix, account_id),
+                "Condition": {
+                    "StringEquals": {
+                        "s3:x-amz-acl": "bucket-owner-full-control",
+                        "aws:SourceAccount": account_id,
+                    }
+                }
+            },
+            {
+                "Sid": "AWSLogDeliveryAclCheck",
+                "Effect": "Allow",
+                "Principal": {"Service": "delivery.logs.amazonaws.com"},
+                "Action": "s3:GetBucketAcl",
+                "Resource": format!("arn:aws:s3:::{}", s3_bucket),
+                "Condition": {
+                    "StringEquals": {"aws:SourceAccount": account_id}
+                }
+            }
+        ]
+    })
+    .to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct ClientUsage {
+    pub client_ip: String,
+    pub connections: u64,
+    pub tls_errors: u64,
+    pub received_bytes: u64,
+    pub sent_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct MethodUsage {
+    pub method: String,
+    pub calls: u64,
+    pub errors: u64,
+}
+
+/// Represents the public RPC usage over the window, to decide the rate limits.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Report {
+    /// RFC3339.
+    pub since: String,
+    /// RFC3339.
+    pub until: String,
+    pub connections: u64,
+    pub unique_clients: u64,
+    /// Failed TLS handshakes over the connections.
+    pub tls_error_rate: f64,
+    /// Top clients by the connections.
+    pub top_clients: Vec<ClientUsage>,
+    pub calls: u64,
+    /// Failed calls over the calls.
+    pub rpc_error_rate: f64,
+    /// Top methods by the calls.
+    pub top_methods: Vec<MethodUsage>,
+}
+
+impl Report {
+    /// Aggregates the connections and the served calls in the window,
+    /// keeping the top "top" clients and methods.
+    pub fn new(
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        connections: &[Connection],
+        calls: &[(String, bool)],
+        top: usize,
+    ) -> Self {
+        let mut clients: HashMap<&str, ClientUsage> = HashMap::new();
+        let mut total_connections = 0;
+        let mut tls_errors = 0;
+        for c in connections
+            .iter()
+            .filter(|c| c.time >= since && c.time <= until)
+        {
+            total_connections += 1;
+            let cu = clients
+                .entry(c.client_ip.as_str())
+                .or_insert_with(|| ClientUsage {
+                    client_ip: c.client_ip.clone(),
+                    ..ClientUsage::default()
+                });
+            cu.connections += 1;
+            cu.received_bytes += c.received_bytes;
+            cu.sent_bytes += c.sent_bytes;
+            if c.tls_error {
+                cu.tls_errors += 1;
+                tls_errors += 1;
+            }
+        }
+        let unique_clients = clients.len() as u64;
+        let mut top_clients: Vec<ClientUsage> = clients.into_values().collect();
+        top_clients.sort_by(|a, b| {
+            b.connections
+                .cmp(&a.connections)
+                .then_with(|| a.client_ip.cmp(&b.client_ip))
+        });
+        top_clients.truncate(top);
+
+        let mut methods: BTreeMap<&str, MethodUsage> = BTreeMap::new();
+        let mut rpc_errors = 0;
+        for (method, failed) in calls.iter() {
+            let mu = methods
+                .entry(method.as_str())
+                .or_insert_with(|| MethodUsage {
+                    method: method.clone(),
+                    ..MethodUsage::default()
+                });
+            mu.calls += 1;
+            if *failed {
+                mu.errors += 1;
+                rpc_errors += 1;
+            }
+        }
+        let mut top_methods: Vec<MethodUsage> = methods.into_values().collect();
+        top_methods.sort_by_key(|m| std::cmp::Reverse(m.calls));
+        top_methods.truncate(top);
+
+        Self {
+            since: since.to_rfc3339(),
+            until: until.to_rfc3339(),
+            connections: total_connections,
+            unique_clients,
+            tls_error_rate: rate(tls_errors, total_connections),
+            top_clients,
+            calls: calls.len() as u64,
+            rpc_error_rate: rate(rpc_errors, calls.len() as u64),
+            top_methods,
+        }
+    }
+}
+
+fn rate(n: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    (n as f64 / total as f64 * 10000.0).round() / 10000.0
+}
+
+#[test]
+fn test_rpc_usage() {
+    let line = "tls 2.0 2022-05-01T02:59:40 net/aops-custom-abc-nlb/c6e77e28c25b2234 g3d4b5e8bb8464cd 72.21.218.154:51341 172.100.100.185:9650 5 2 98 246 - arn:aws:acm:us-west-2:123:certificate/abc - ECDHE-RSA-AES128-SHA tlsv12 - aops-custom-abc-nlb.elb.us-west-2.amazonaws.com - - - 2022-05-01T02:59:35";
+    let c = parse_nlb_log_line(line).unwrap();
+    assert_eq!(c.client_ip, "72.21.218.154");
+    assert_eq!(c.time.to_rfc3339(), "2022-05-01T02:59:40+00:00");
+    assert_eq!(c.received_bytes, 98);
+    assert_eq!(c.sent_bytes, 246);
+    assert!(!c.tls_error);
+
+    let failed = line.replace(" 5 2 98 ", " 5 - 98 ");
+    assert!(parse_nlb_log_line(&failed).unwrap().tls_error);
+    let alert = line.replace(" 246 - arn", " 246 0x28 arn");
+    assert!(parse_nlb_log_line(&alert).unwrap().tls_error);
+    assert!(parse_nlb_log_line("tcp 2.0 garbage").is_none());
+
+    assert_eq!(
+        parse_served_line("DEBUG[05-01|02:59:40.123] <C Chain> rpc/handler.go:390: Served eth_call reqid=5 duration=1.2ms err=\"execution reverted\""),
+        Some((String::from("eth_call"), true))
+    );
+    assert_eq!(
+        parse_served_line("DEBUG <C Chain> Served eth_blockNumber reqid=1 duration=20µs"),
+        Some((String::from("eth_blockNumber"), false))
+    );
+    assert_eq!(parse_served_line("INFO Served"), None);
+
+    let since = Utc.with_ymd_and_hms(2022, 4, 30, 23, 0, 0).unwrap();
+    let now = Utc.with_ymd_and_hms(2022, 5, 1, 3, 0, 0).unwrap();
+    assert_eq!(
+        nlb_access_logs_day_prefixes("aops-custom-abc", "123", "us-west-2", since, now),
+        vec![
+            "aops-custom-abc/nlb-access-logs/AWSLogs/123/elasticloadbalancing/us-west-2/2022/04/30/",
+            "aops-custom-abc/nlb-access-logs/AWSLogs/123/elasticloadbalancing/us-west-2/2022/05/01/",
+        ]
+    );
+    let policy = nlb_access_logs_bucket_policy("b", "aops-custom-abc/nlb-access-logs", "123");
+    assert!(policy.contains("arn:aws:s3:::b/aops-custom-abc/nlb-access-logs/AWSLogs/123/*"));
+
+    let mut connections = vec![c.clone(), c.clone(), parse_nlb_log_line(&failed).unwrap()];
+    connections.push(Connection {
+        client_ip: String::from("10.0.0.1"),
+        ..c.clone()
+    });
+    connections.push(Connection {
+        time: since - Duration::hours(1),
+        ..c
+    });
+    let calls = vec![
+        (String::from("eth_call"), true),
+        (String::from("eth_call"), false),
+        (String::from("eth_getBalance"), false),
+        (String::from("eth_call"), false),
+    ];
+    let report = Report::new(since, now, &connections, &calls, 1);
+    assert_eq!(report.connections, 4);
+    assert_eq!(report.unique_clients, 2);
+    assert_eq!(report.tls_error_rate, 0.25);
+    assert_eq!(report.top_clients.len(), 1);
+    assert_eq!(report.top_clients[0].client_ip, "72.21.218.154");
+    assert_eq!(report.top_clients[0].connections, 3);
+    assert_eq!(report.calls, 4);
+    assert_eq!(report.rpc_error_rate, 0.25);
+    assert_eq!(report.top_methods[0].method, "eth_call");
+    assert_eq!(report.top_methods[0].calls, 3);
+}
@@ -0,0 +1,222 @@
+use std::{
+    io::{self, Error, ErrorKind},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use crate::{
+    avalanche::{avalanchego::api::eth, coreth::tx, key},
+    Spec,
+};
+
+/// Gas of the plain value transfer.
+const TRANSFER_GAS: u64 = 21_000;
+
+/// Result of a single check in the suite.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct Check {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// Results of the C-chain smoke test suite against the cluster endpoint.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+pub struct Report {
+    pub rpc_url: String,
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    pub fn encode_yaml(&self) -> io::Result<String> {
+        serde_yaml::to_string(self)
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed to serialize {}", e)))
+    }
+
+    pub fn failed(&self) -> Vec<&Check> {
+        self.checks.iter().filter(|c| !c.ok).collect()
+    }
+
+    fn push<T>(&mut self, name: &str, res: io::Result<T>, detail: impl FnOnce(&T) -> String) {
+        let (ok, detail) = match &res {
+            Ok(v) => (true, detail(v)),
+            Err(e) => (false, e.to_string()),
+        };
+        self.checks.push(Check {
+            name: name.to_string(),
+            ok,
+            detail,
+        });
+    }
+}
+
+/// Options of the smoke test suite.
+pub struct Options {
+    /// Full C-chain RPC URL (e.g., "http://[NLB_DNS]:9650/ext/bc/C/rpc").
+    pub rpc_url: String,
+    /// Skips the subscription check if None.
+    pub websocket: Option<String>,
+    /// Funded key for the transfer check, only on the custom networks
+    /// where the suite must not spend the real funds.
+    pub transfer_key: Option<key::Key>,
+    pub block_wait: Duration,
+}
+
+impl Options {
+    /// Loads the options from the applied spec.
+    pub fn from_spec(spec: &Spec, block_wait: Duration) -> io::Result<Self> {
+        let endpoints = spec.endpoints.clone().unwrap_or_default();
+        let rpc_url = match (&endpoints.http_rpc_c, &endpoints.http_rpc) {
+            (Some(u), _) => u.clone(),
+            (None, Some(u)) => format!("{}{}", u, eth::C_CHAIN_RPC_PATH),
+            (None, None) => {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("no endpoint for '{}' (not applied yet?)", spec.id),
+                ))
+            }
+        };
+
+        let mut transfer_key = None;
+        if spec.avalanchego_config.is_custom_network() {
+            // None without the private keys (e.g., the spec state from S3)
+            transfer_key = spec.prefunded_key(0).ok();
+        }
+
+        Ok(Self {
+            rpc_url,
+            websocket: endpoints.websocket,
+            transfer_key,
+            block_wait,
+        })
+    }
+}
+
+/// Runs the suite, and reports every check rather than stopping
+/// at the first failure.
+pub async fn run(opts: &Options) -> Report {
+    let mut report = Report {
+        rpc_url: opts.rpc_url.clone(),
+        checks: Vec::new(),
+    };
+
+    let chain_id = match eth::chain_id(&opts.rpc_url).await {
+        Ok(id) => {
+            report.push("eth_chainId", Ok(id), |id| format!("chain ID {}", id));
+            id
+        }
+        Err(e) => {
+            // the rest cannot pass without the RPC
+            report.push::<u64>("eth_chainId", Err(e), |_| String::new());
+            return report;
+        }
+    };
+
+    let start_block = eth::block_number(&opts.rpc_url).await;
+
+    // coreth does not build the empty blocks, so the transfer drives
+    // the block production that the subscription waits for
+    let subscribe = async {
+        match &opts.websocket {
+            Some(ws) => Some(eth::new_head_via_websocket(ws, opts.block_wait).await),
+            None => None,
+        }
+    };
+    let transfer = async {
+        match &opts.transfer_key {
+            Some(k) => Some(transfer(&opts.rpc_url, k, chain_id, opts.block_wait).await),
+            None => {
+                sleep(opts.block_wait).await;
+                None
+            }
+        }
+    };
+    let (subscribed, transferred) = tokio::join!(subscribe, transfer);
+
+    if let Some(res) = transferred {
+        report.push("transfer", res, |hash| format!("accepted {}", hash));
+    }
+    if let Some(res) = subscribed {
+        report.push("newHeads subscription", res, |n| {
+            format!("notified block {}", n)
+        });
+    }
+
+    let blocks = match start_block {
+        Ok(start) => eth::block_number(&opts.rpc_url).await.and_then(|end| {
+            if end > start {
+                Ok((start, end))
+            } else {
+                Err(Error::new(
+                    ErrorKind::Other,
+                    format!("no new block after {} (still at {})", start, end),
+                ))
+            }
+        }),
+        Err(e) => Err(e),
+    };
+    report.push("block production", blocks, |(start, end)| {
+        format!("block {} -> {}", start, end)
+    });
+
+    report
+}
+
+/// Sends 1 wei to itself, and waits for the successful receipt.
+async fn transfer(
+    rpc_url: &str,
+    k: &key::Key,
+    chain_id: u64,
+    timeout: Duration,
+) -> io::Result<String> {
+    let nonce = eth::get_transaction_count(rpc_url, &k.eth_address).await?;
+    let gas_price = eth::gas_price(rpc_url).await?;
+    let (raw, _) = tx::LegacyTx {
+        nonce,
+        gas_price,
+        gas: TRANSFER_GAS,
+        to: tx::parse_address(&k.eth_address)?,
+        value: 1,
+        data: Vec::new(),
+    }
+    .sign(k, chain_id)?;
+    let hash = eth::send_raw_transaction(rpc_url, &raw).await?;
+
+    let start = Instant::now();
+    loop {
+        match eth::get_transaction_receipt_status(rpc_url, &hash).await? {
+            Some(true) => return Ok(hash),
+            Some(false) => {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!("transfer {} reverted", hash),
+                ))
+            }
+            None => {}
+        }
+        if start.elapsed() > timeout {
+            return Err(Error::new(
+                ErrorKind::TimedOut,
+                format!("transfer {} not accepted in {:?}", hash, timeout),
+            ));
+        }
+        sleep(Duration::from_secs(2)).await;
+    }
+}
+
+#[test]
+fn test_report() {
+    let mut report = Report::default();
+    report.push("a", Ok(1), |v| format!("got {}", v));
+    report.push::<u64>("b", Err(Error::new(ErrorKind::Other, "failed")), |v| {
+        format!("got {}", v)
+    });
+    assert_eq!(report.checks[0].detail, "got 1");
+    let failed = report.failed();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].name, "b");
+    assert_eq!(failed[0].detail, "failed");
+    assert!(report.encode_yaml().unwrap().contains("ok: false"));
+}
@@ -0,0 +1,420 @@
+use std::{
+    fmt,
+    io::{self, stdout, Error, ErrorKind},
+};
+
+use crossterm::{
+    execute,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+};
+use serde_yaml::Value;
+
+use crate::{
+    spec_resolver::{Key, Path},
+    Spec, REDACTED,
+};
+
+/// Represents what applying the changed field does to the running network.
+/// Ordered by the disruption, so the highest impact of a diff is its maximum.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
+pub enum Impact {
+    /// Only the spec state changes (e.g., generated outputs).
+    NoOp,
+    /// Updates the AWS resources without restarting the nodes
+    /// (e.g., scaling the auto scaling groups).
+    InPlaceUpdate,
+    /// Restarts "avalanchego" on the nodes to pick up the change.
+    NodeRestart,
+    /// Requires deleting and re-creating the resources or the network.
+    Replacement,
+}
+
+impl fmt::Display for Impact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Impact::NoOp => write!(f, "no-op"),
+            Impact::InPlaceUpdate => write!(f, "in-place update"),
+            Impact::NodeRestart => write!(f, "node restart"),
+            Impact::Replacement => write!(f, "replacement"),
+        }
+    }
+}
+
+/// Impact of the changed fields, matched by the longest field path prefix.
+/// The unlisted fields are classified as "NodeRestart", since "avalanched"
+/// reads the spec on the node start.
+const IMPACTS: [(&str, Impact); 70] = [
+    ("id", Impact::Replacement),
+    ("aws_resources.region", Impact::Replacement),
+    ("aws_resources.s3_bucket", Impact::Replacement),
+    ("aws_resources.s3_lifecycle", Impact::InPlaceUpdate),
+    ("aws_resources.db_backup_s3_region", Impact::NoOp),
+    ("aws_resources.db_backup_s3_bucket", Impact::NoOp),
+    ("aws_resources.db_backup_s3_key", Impact::NoOp),
+    ("aws_resources.db_backup_s3_mirrors", Impact::NoOp),
+    ("aws_resources.s3_transfer_limits", Impact::NoOp),
+    ("aws_resources.replica_s3_region", Impact::InPlaceUpdate),
+    ("aws_resources.replica_s3_bucket", Impact::InPlaceUpdate),
+    (
+        "aws_resources.nlb_acm_certificate_arn",
+        Impact::InPlaceUpdate,
+    ),
+    // the target group settings only apply when the NLB is created
+    ("aws_resources.nlb_target_group", Impact::Replacement),
+    (
+        "aws_resources.nlb_cross_zone_load_balancing",
+        Impact::Replacement,
+    ),
+    (
+        "aws_resources.nlb_tcp_idle_timeout_seconds",
+        Impact::Replacement,
+    ),
+    ("aws_resources.nlb_access_logs", Impact::Replacement),
+    (
+        "aws_resources.clock_offset_alarm_threshold_ms",
+        Impact::Replacement,
+    ),
+    // the VPC is never updated
+    ("aws_resources.private_connectivity", Impact::Replacement),
+    ("aws_resources.vpc_endpoints", Impact::Replacement),
+    // "apply" syncs the endpoint service on every run
+    ("aws_resources.nlb_endpoint_service", Impact::InPlaceUpdate),
+    ("aws_resources.nlb_endpoint_service_", Impact::NoOp),
+    // "apply" allocates the missing addresses on every run
+    ("aws_resources.elastic_ip_pool", Impact::InPlaceUpdate),
+    ("aws_resources.elastic_ip_pool_", Impact::NoOp),
+    // "apply" updates the budget stack on every run
+    ("aws_resources.budget", Impact::InPlaceUpdate),
+    // "apply" updates the auditor role stack on every run
+    ("aws_resources.auditor", Impact::InPlaceUpdate),
+    ("aws_resources.identity", Impact::NoOp),
+    ("aws_resources.kms_cmk_id", Impact::NoOp),
+    ("aws_resources.kms_cmk_arn", Impact::NoOp),
+    ("aws_resources.ec2_key_name", Impact::NoOp),
+    (
+        "aws_resources.ec2_key_import_public_key",
+        Impact::Replacement,
+    ),
+    ("aws_resources.ec2_key_path", Impact::NoOp),
+    ("aws_resources.cloudformation_", Impact::NoOp),
+    ("aws_resources.cloudwatch_", Impact::NoOp),
+    ("aws_resources.cloudwatch_metrics_", Impact::NodeRestart),
+    ("machine.anchor_nodes", Impact::InPlaceUpdate),
+    ("machine.non_anchor_nodes", Impact::InPlaceUpdate),
+    ("machine.instance_types", Impact::Replacement),
+    ("machine.volume_size_in_gb", Impact::Replacement),
+    ("machine.os_hardening", Impact::Replacement),
+    ("machine.image_id", Impact::Replacement),
+    ("avalanchego_config.network_id", Impact::Replacement),
+    ("avalanchego_config.genesis", Impact::Replacement),
+    (
+        "avalanchego_config.staking-tls-key-file",
+        Impact::Replacement,
+    ),
+    (
+        "avalanchego_config.staking-tls-cert-file",
+        Impact::Replacement,
+    ),
+    ("avalanchego_genesis_template", Impact::Replacement),
+    ("subnet_evm_genesis", Impact::Replacement),
+    ("avalanchego_release", Impact::InPlaceUpdate),
+    ("maintenance_window", Impact::NoOp),
+    ("max_offline_stake_percent", Impact::NoOp),
+    ("stake_distribution", Impact::NoOp),
+    // only read by the update events
+    ("canary", Impact::NoOp),
+    // the instance role is only created once
+    ("static_node_keys", Impact::Replacement),
+    ("restore_from_snapshot", Impact::Replacement),
+    // the stack tags and the NLB only apply when the stacks are created
+    ("ci_preview", Impact::Replacement),
+    // "reap" reads the spec uploaded by "apply"
+    ("auto_delete_after", Impact::NoOp),
+    // the NLB registration only applies when the ASG is created
+    ("api_exposure.anchor_nodes.nlb_target", Impact::Replacement),
+    (
+        "api_exposure.non_anchor_nodes.nlb_target",
+        Impact::Replacement,
+    ),
+    // the index-enabled node classes get the extra disk
+    ("indexer", Impact::Replacement),
+    ("indexer.allow_incomplete", Impact::NodeRestart),
+    // the relayer instances reload the config from the spec uploaded by "apply"
+    ("icm_relayer", Impact::InPlaceUpdate),
+    ("icm_relayer.volume_size_in_gb", Impact::Replacement),
+    // the instance role only allows the bucket of the cluster creation
+    ("public_snapshot", Impact::Replacement),
+    ("public_snapshot.s3_prefix", Impact::NodeRestart),
+    ("public_snapshot.requester_pays", Impact::NodeRestart),
+    ("public_snapshot.interval_hours", Impact::NodeRestart),
+    ("public_snapshot.retention", Impact::NodeRestart),
+    ("generated_seed_", Impact::NoOp),
+    ("elastic_subnet", Impact::NoOp),
+    ("current_nodes", Impact::NoOp),
+    ("endpoints", Impact::NoOp),
+];
+
+/// Fields whose changes re-generate the node IDs, since the replaced
+/// instances generate new staking certificates on bootstrap.
+const NODE_ID_CHANGING: [&str; 9] = [
+    "machine.instance_types",
+    "machine.volume_size_in_gb",
+    "indexer.anchor_nodes",
+    "indexer.non_anchor_nodes",
+    "indexer.extra_volume_size_in_gb",
+    "machine.os_hardening",
+    "machine.image_id",
+    "avalanchego_config.staking-tls-key-file",
+    "avalanchego_config.staking-tls-cert-file",
+];
+
+/// Returns the impact of changing the field (e.g., "machine.instance_types[0]").
+pub fn impact_of(path: &str) -> Impact {
+    IMPACTS
+        .iter()
+        .filter(|(prefix, _)| matches(prefix, path))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, impact)| *impact)
+        .unwrap_or(Impact::NodeRestart)
+}
+
+/// Returns true if changing the field changes the node IDs.
+pub fn changes_node_ids(path: &str) -> bool {
+    NODE_ID_CHANGING.iter().any(|prefix| matches(prefix, path))
+}
+
+fn matches(prefix: &str, path: &str) -> bool {
+    // "_"-suffixed prefixes match the field name prefix
+    // (e.g., "aws_resources.cloudformation_vpc_id")
+    prefix.ends_with('_') && path.starts_with(prefix)
+        || path == prefix
+        || path.starts_with(&format!("{}.", prefix))
+        || path.starts_with(&format!("{}[", prefix))
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::Added => write!(f, "+"),
+            ChangeKind::Removed => write!(f, "-"),
+            ChangeKind::Changed => write!(f, "~"),
+        }
+    }
+}
+
+/// Represents a changed leaf field (or a whole added/removed subtree).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Change {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+    pub impact: Impact,
+    pub changes_node_ids: bool,
+}
+
+/// Returns the field-level changes from the old to the new spec,
+/// in the field order of the spec. The private keys are redacted.
+pub fn diff(old: &Spec, new: &Spec) -> io::Result<Vec<Change>> {
+    let to_value = |spec: &Spec| {
+        serde_yaml::to_value(spec).map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to serialize Spec to YAML {}", e),
+            )
+        })
+    };
+    let (old, new) = (to_value(old)?, to_value(new)?);
+
+    let mut changes = Vec::new();
+    walk(&mut Vec::new(), Some(&old), Some(&new), &mut changes);
+    Ok(changes)
+}
+
+/// Returns true if any change replaces the resources or changes the node IDs,
+/// which must be confirmed before applying.
+pub fn is_destructive(changes: &[Change]) -> bool {
+    changes
+        .iter()
+        .any(|c| c.impact == Impact::Replacement || c.changes_node_ids)
+}
+
+/// Returns the highest impact of the changes.
+pub fn max_impact(changes: &[Change]) -> Impact {
+    changes
+        .iter()
+        .map(|c| c.impact)
+        .max()
+        .unwrap_or(Impact::NoOp)
+}
+
+/// Prints one line per change, colored by the impact.
+pub fn print_changes(changes: &[Change]) -> io::Result<()> {
+    for c in changes.iter() {
+        let color = match c.impact {
+            Impact::NoOp => Color::DarkGrey,
+            Impact::InPlaceUpdate => Color::Green,
+            Impact::NodeRestart => Color::Yellow,
+            Impact::Replacement => Color::Red,
+        };
+        let line = match c.kind {
+            ChangeKind::Added => format!("{} {}: {}", c.kind, c.path, encode(&c.new)),
+            ChangeKind::Removed => format!("{} {}: {}", c.kind, c.path, encode(&c.old)),
+            ChangeKind::Changed => format!(
+                "{} {}: {} -> {}",
+                c.kind,
+                c.path,
+                encode(&c.old),
+                encode(&c.new)
+            ),
+        };
+        execute!(
+            stdout(),
+            SetForegroundColor(color),
+            Print(format!(
+                "{}  ({}{})\n",
+                line,
+                c.impact,
+                if c.changes_node_ids {
+                    ", changes node IDs"
+                } else {
+                    ""
+                }
+            )),
+            ResetColor
+        )?;
+    }
+    Ok(())
+}
+
+fn encode(v: &Option<Value>) -> String {
+    match v {
+        Some(v) => serde_json::to_string(v).unwrap_or_default(),
+        None => String::from("null"),
+    }
+}
+
+fn walk(path: &mut Vec<Key>, old: Option<&Value>, new: Option<&Value>, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Some(Value::Mapping(o)), Some(Value::Mapping(n))) => {
+            let mut keys: Vec<&Value> = o.iter().map(|(k, _)| k).collect();
+            keys.extend(n.iter().map(|(k, _)| k).filter(|k| !o.contains_key(k)));
+            for k in keys {
+                let field = k
+                    .as_str()
+                    .map(String::from)
+                    .unwrap_or_else(|| serde_json::to_string(k).unwrap_or_default());
+                path.push(Key::Field(field));
+                walk(path, o.get(k), n.get(k), changes);
+                path.pop();
+            }
+        }
+        (Some(Value::Sequence(o)), Some(Value::Sequence(n))) => {
+            for i in 0..o.len().max(n.len()) {
+                path.push(Key::Index(i));
+                walk(path, o.get(i), n.get(i), changes);
+                path.pop();
+            }
+        }
+        (o, n) if o == n => {}
+        (o, n) => {
+            let p = Path(path).to_string();
+            let kind = match (o, n) {
+                (None, _) | (Some(Value::Null), _) => ChangeKind::Added,
+                (_, None) | (_, Some(Value::Null)) => ChangeKind::Removed,
+                _ => ChangeKind::Changed,
+            };
+            let masked = |v: Option<&Value>| {
+                v.filter(|v| !v.is_null()).map(|v| {
+                    if p.contains("private_key") {
+                        Value::String(String::from(REDACTED))
+                    } else {
+                        v.clone()
+                    }
+                })
+            };
+            changes.push(Change {
+                impact: impact_of(&p),
+                changes_node_ids: changes_node_ids(&p),
+                old: masked(o),
+                new: masked(n),
+                kind,
+                path: p,
+            });
+        }
+    }
+}
+
+#[test]
+fn test_diff() {
+    use crate::avalanche::{
+        avalanchego::config as avalanchego_config, coreth::config as coreth_config,
+    };
+
+    assert_eq!(impact_of("id"), Impact::Replacement);
+    assert_eq!(impact_of("machine.instance_types[1]"), Impact::Replacement);
+    assert_eq!(
+        impact_of("aws_resources.cloudformation_vpc_id"),
+        Impact::NoOp
+    );
+    assert_eq!(
+        impact_of("avalanchego_config.log-level"),
+        Impact::NodeRestart
+    );
+    assert_eq!(impact_of("avalanchego_config.genesis"), Impact::Replacement);
+
+    let old: Spec = serde_json::from_value(serde_json::json!({
+        "id": "test",
+        "machine": { "non_anchor_nodes": 2, "instance_types": ["c5.large"] },
+        "install_artifacts": { "avalanched_bin": "a", "avalanchego_bin": "b" },
+        "avalanchego_config": avalanchego_config::Config::default(),
+        "coreth_config": coreth_config::Config::default(),
+    }))
+    .unwrap();
+    assert!(diff(&old, &old).unwrap().is_empty());
+
+    let mut new = old.clone();
+    new.machine.non_anchor_nodes = 3;
+    new.machine.instance_types = Some(vec![String::from("c5.large"), String::from("m5.large")]);
+    new.install_artifacts.avalanchego_bin = String::from("c");
+    new.max_offline_stake_percent = Some(10);
+
+    let changes = diff(&old, &new).unwrap();
+    let summary: Vec<(&str, ChangeKind, Impact)> = changes
+        .iter()
+        .map(|c| (c.path.as_str(), c.kind, c.impact))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (
+                "machine.non_anchor_nodes",
+                ChangeKind::Changed,
+                Impact::InPlaceUpdate
+            ),
+            (
+                "machine.instance_types[1]",
+                ChangeKind::Added,
+                Impact::Replacement
+            ),
+            (
+                "install_artifacts.avalanchego_bin",
+                ChangeKind::Changed,
+                Impact::NodeRestart
+            ),
+            ("max_offline_stake_percent", ChangeKind::Added, Impact::NoOp),
+        ]
+    );
+    assert_eq!(max_impact(&changes), Impact::Replacement);
+    assert!(changes[1].changes_node_ids);
+    assert!(is_destructive(&changes));
+    assert!(!is_destructive(&changes[2..]));
+    assert_eq!(max_impact(&[]), Impact::NoOp);
+}
@@ -0,0 +1,184 @@
+use std::{
+    env, fs,
+    io::{self, Error, ErrorKind},
+    path::Path,
+    thread,
+};
+
+use log::info;
+use tokio::runtime::Runtime;
+
+use crate::aws::{self, envelope, kms};
+
+/// First line of the encrypted spec file, followed by the encryption mode.
+/// e.g., "# avalanche-ops encrypted spec v1 kms arn:aws:kms:us-west-2:123:key/abc"
+/// e.g., "# avalanche-ops encrypted spec v1 passphrase"
+pub const HEADER: &str = "# avalanche-ops encrypted spec v1";
+
+/// Environment variable to read the spec passphrase from.
+pub const PASSPHRASE_ENV: &str = "AVALANCHE_OPS_SPEC_PASSPHRASE";
+
+/// Defines how the local spec file is encrypted at rest.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Encryption {
+    /// Envelope-encrypted with the data key from the KMS key (ARN).
+    Kms(String),
+    /// Encrypted with the key derived from the "AVALANCHE_OPS_SPEC_PASSPHRASE".
+    Passphrase,
+}
+
+impl Encryption {
+    fn encode(&self) -> String {
+        match self {
+            Encryption::Kms(key_arn) => format!("{} kms {}", HEADER, key_arn),
+            Encryption::Passphrase => format!("{} passphrase", HEADER),
+        }
+    }
+
+    fn decode(header: &str) -> io::Result<Self> {
+        let mode = header
+            .strip_prefix(HEADER)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid encrypted spec header '{}'", header),
+                )
+            })?
+            .trim();
+        match mode.split_once(' ') {
+            Some(("kms", key_arn)) => Ok(Encryption::Kms(key_arn.to_string())),
+            None if mode == "passphrase" => Ok(Encryption::Passphrase),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown spec encryption mode '{}'", mode),
+            )),
+        }
+    }
+}
+
+/// Returns the encryption of the spec file, or "None" if the file
+/// is in plaintext or does not exist.
+pub fn read(file_path: &str) -> io::Result<Option<Encryption>> {
+    if !Path::new(file_path).exists() {
+        return Ok(None);
+    }
+    let d = fs::read(file_path)?;
+    if !d.starts_with(HEADER.as_bytes()) {
+        return Ok(None);
+    }
+    let d = String::from_utf8_lossy(&d);
+    let header = d.lines().next().unwrap_or_default();
+    Encryption::decode(header).map(Some)
+}
+
+/// Encrypts the spec YAML into the file contents, with the header line.
+pub fn seal(encryption: &Encryption, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+    info!("encrypting spec ({})", encryption.encode());
+    let ciphertext = match encryption {
+        Encryption::Kms(key_arn) => {
+            let key_arn = key_arn.clone();
+            let d = plaintext.to_vec();
+            block_on_kms(key_arn, move |envelope| async move {
+                envelope.seal_aes_256(&d).await
+            })?
+        }
+        Encryption::Passphrase => {
+            envelope::seal_aes_256_passphrase(passphrase()?.as_bytes(), plaintext)
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))?
+        }
+    };
+    Ok(format!("{}\n{}\n", encryption.encode(), hex::encode(ciphertext)).into_bytes())
+}
+
+/// Decrypts the file contents into the spec YAML.
+/// Returns the contents as they are, if not encrypted.
+pub fn unseal(d: &[u8]) -> io::Result<Vec<u8>> {
+    if !d.starts_with(HEADER.as_bytes()) {
+        return Ok(d.to_vec());
+    }
+    let d = String::from_utf8_lossy(d);
+    let mut lines = d.lines();
+    let encryption = Encryption::decode(lines.next().unwrap_or_default())?;
+    let ciphertext = hex::decode(lines.next().unwrap_or_default().trim()).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid encrypted spec hex {}", e),
+        )
+    })?;
+
+    info!("decrypting spec ({})", encryption.encode());
+    match encryption {
+        Encryption::Kms(key_arn) => block_on_kms(key_arn, move |envelope| async move {
+            envelope.unseal_aes_256(&ciphertext).await
+        }),
+        Encryption::Passphrase => {
+            envelope::unseal_aes_256_passphrase(passphrase()?.as_bytes(), &ciphertext)
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))
+        }
+    }
+}
+
+fn passphrase() -> io::Result<String> {
+    match env::var(PASSPHRASE_ENV) {
+        Ok(v) if !v.is_empty() => Ok(v),
+        _ => Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "spec is passphrase-encrypted but '{}' is not set",
+                PASSPHRASE_ENV
+            ),
+        )),
+    }
+}
+
+/// Runs the KMS envelope operation on a separate thread with its own runtime,
+/// since the spec may be loaded from within the async contexts.
+fn block_on_kms<F, Fut>(key_arn: String, f: F) -> io::Result<Vec<u8>>
+where
+    F: FnOnce(envelope::Envelope) -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = crate::errors::Result<Vec<u8>>>,
+{
+    // e.g., "arn:aws:kms:us-west-2:123:key/abc"
+    let region = key_arn.split(':').nth(3).map(String::from);
+    thread::spawn(move || -> io::Result<Vec<u8>> {
+        let rt = Runtime::new()?;
+        rt.block_on(async move {
+            let shared_config = aws::load_config(region).await?;
+            let envelope =
+                envelope::Envelope::new(Some(kms::Manager::new(&shared_config)), Some(key_arn));
+            f(envelope)
+                .await
+                .map_err(|e| Error::new(ErrorKind::Other, e.message()))
+        })
+    })
+    .join()
+    .map_err(|_| Error::new(ErrorKind::Other, "KMS envelope thread panicked"))?
+}
+
+#[test]
+fn test_spec_encryption() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let kms = Encryption::Kms(String::from("arn:aws:kms:us-west-2:123:key/abc"));
+    assert_eq!(Encryption::decode(&kms.encode()).unwrap(), kms);
+    assert_eq!(
+        Encryption::decode(&Encryption::Passphrase.encode()).unwrap(),
+        Encryption::Passphrase
+    );
+    assert!(Encryption::decode(&format!("{} rot13", HEADER)).is_err());
+
+    // plaintext passes through
+    assert_eq!(unseal(b"id: test\n").unwrap(), b"id: test\n");
+
+    env::set_var(PASSPHRASE_ENV, "secret");
+    let sealed = seal(&Encryption::Passphrase, b"id: test\n").unwrap();
+    assert!(sealed.starts_with(HEADER.as_bytes()));
+    assert_eq!(unseal(&sealed).unwrap(), b"id: test\n");
+
+    let mut f = tempfile::NamedTempFile::new().unwrap();
+    std::io::Write::write_all(&mut f, &sealed).unwrap();
+    assert_eq!(
+        read(f.path().to_str().unwrap()).unwrap(),
+        Some(Encryption::Passphrase)
+    );
+}
@@ -0,0 +1,182 @@
+use std::io::{self, Error, ErrorKind};
+
+use crate::Spec;
+
+/// Represents a named preset of the spec for a common topology.
+/// The profile fills in the defaults, and the explicit "default-spec" flags
+/// take precedence over the profile.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Profile {
+    pub name: &'static str,
+    pub description: &'static str,
+
+    /// Network name for "DefaultSpecOption.network_name".
+    pub network_name: &'static str,
+    /// Sets "DefaultSpecOption.enable_subnet_evm".
+    pub enable_subnet_evm: bool,
+
+    pub anchor_nodes: Option<u32>,
+    pub non_anchor_nodes: u32,
+    pub instance_types: &'static [&'static str],
+    pub volume_size_in_gb: u32,
+
+    /// avalanchego flags.
+    pub api_admin_enabled: bool,
+    pub api_keystore_enabled: bool,
+    pub api_ipcs_enabled: bool,
+    pub index_enabled: bool,
+}
+
+const LARGE: &[&str] = &["c6a.large", "m6a.large", "m5.large", "c5.large"];
+const XLARGE_2: &[&str] = &["c6a.2xlarge", "m6a.2xlarge", "m5.2xlarge", "c5.2xlarge"];
+
+/// Lists all profiles.
+pub const PROFILES: [Profile; 5] = [
+    Profile {
+        name: "testnet-small",
+        description: "smallest custom network for testing (1 anchor, 1 non-anchor node)",
+        network_name: "custom",
+        enable_subnet_evm: false,
+        anchor_nodes: Some(1),
+        non_anchor_nodes: 1,
+        instance_types: LARGE,
+        volume_size_in_gb: 100,
+        api_admin_enabled: true,
+        api_keystore_enabled: true,
+        api_ipcs_enabled: true,
+        index_enabled: false,
+    },
+    Profile {
+        name: "fuji-api-pool",
+        description: "fuji API nodes behind the NLB, with indexing and without the admin APIs",
+        network_name: "fuji",
+        enable_subnet_evm: false,
+        anchor_nodes: None,
+        non_anchor_nodes: 5,
+        instance_types: XLARGE_2,
+        volume_size_in_gb: 500,
+        api_admin_enabled: false,
+        api_keystore_enabled: false,
+        api_ipcs_enabled: false,
+        index_enabled: true,
+    },
+    Profile {
+        name: "mainnet-validator",
+        description: "single mainnet validator with the admin APIs disabled",
+        network_name: "mainnet",
+        enable_subnet_evm: false,
+        anchor_nodes: None,
+        non_anchor_nodes: 1,
+        instance_types: XLARGE_2,
+        volume_size_in_gb: 1000,
+        api_admin_enabled: false,
+        api_keystore_enabled: false,
+        api_ipcs_enabled: false,
+        index_enabled: false,
+    },
+    Profile {
+        name: "subnet-dev",
+        description: "custom network with the subnet-evm genesis for subnet development",
+        network_name: "custom",
+        enable_subnet_evm: true,
+        anchor_nodes: Some(2),
+        non_anchor_nodes: 2,
+        instance_types: LARGE,
+        volume_size_in_gb: 100,
+        api_admin_enabled: true,
+        api_keystore_enabled: true,
+        api_ipcs_enabled: true,
+        index_enabled: false,
+    },
+    Profile {
+        name: "ci-preview",
+        description: "smallest custom network for the CI preview (use with '--ci-job-id')",
+        network_name: "custom",
+        enable_subnet_evm: true,
+        anchor_nodes: Some(1),
+        non_anchor_nodes: 1,
+        instance_types: LARGE,
+        volume_size_in_gb: 40,
+        api_admin_enabled: true,
+        api_keystore_enabled: true,
+        api_ipcs_enabled: false,
+        index_enabled: false,
+    },
+];
+
+impl Profile {
+    /// Returns the profile by name.
+    pub fn get(name: &str) -> io::Result<Self> {
+        PROFILES
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "unknown profile '{}' (must be one of {})",
+                        name,
+                        names().join(", ")
+                    ),
+                )
+            })
+    }
+
+    /// Overwrites the machine and avalanchego fields of the default spec.
+    /// The anchor nodes are only set for the custom networks.
+    pub fn apply(&self, spec: &mut Spec) {
+        if spec.avalanchego_config.is_custom_network() {
+            spec.machine.anchor_nodes = self.anchor_nodes.or(spec.machine.anchor_nodes);
+        } else {
+            spec.machine.anchor_nodes = None;
+        }
+        spec.machine.non_anchor_nodes = self.non_anchor_nodes;
+        spec.machine.instance_types =
+            Some(self.instance_types.iter().map(|v| v.to_string()).collect());
+        spec.machine.volume_size_in_gb = Some(self.volume_size_in_gb);
+
+        spec.avalanchego_config.api_admin_enabled = Some(self.api_admin_enabled);
+        spec.avalanchego_config.api_keystore_enabled = Some(self.api_keystore_enabled);
+        spec.avalanchego_config.api_ipcs_enabled = Some(self.api_ipcs_enabled);
+        spec.avalanchego_config.index_enabled = Some(self.index_enabled);
+    }
+}
+
+/// Returns all profile names.
+pub fn names() -> Vec<&'static str> {
+    PROFILES.iter().map(|p| p.name).collect()
+}
+
+#[test]
+fn test_profile() {
+    use crate::avalanche::{
+        avalanchego::config as avalanchego_config, coreth::config as coreth_config,
+    };
+
+    assert!(Profile::get("unknown").is_err());
+    for name in names() {
+        assert_eq!(Profile::get(name).unwrap().name, name);
+    }
+
+    let mut spec: Spec = serde_json::from_value(serde_json::json!({
+        "id": "test",
+        "machine": { "anchor_nodes": 2, "non_anchor_nodes": 2 },
+        "install_artifacts": { "avalanched_bin": "", "avalanchego_bin": "" },
+        "avalanchego_config": avalanchego_config::Config::default(),
+        "coreth_config": coreth_config::Config::default(),
+    }))
+    .unwrap();
+
+    // only the custom network has anchor nodes
+    Profile::get("testnet-small").unwrap().apply(&mut spec);
+    assert_eq!(spec.machine.anchor_nodes, Some(1));
+    assert_eq!(spec.machine.volume_size_in_gb, Some(100));
+
+    spec.avalanchego_config.network_id = 1;
+    Profile::get("mainnet-validator").unwrap().apply(&mut spec);
+    assert_eq!(spec.machine.anchor_nodes, None);
+    assert_eq!(spec.machine.non_anchor_nodes, 1);
+    assert_eq!(spec.machine.volume_size_in_gb, Some(1000));
+    assert_eq!(spec.avalanchego_config.api_admin_enabled, Some(false));
+}
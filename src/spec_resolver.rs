@@ -0,0 +1,277 @@
+use std::{
+    collections::BTreeMap,
+    env, fmt,
+    io::{self, Error, ErrorKind},
+    thread,
+};
+
+use log::info;
+use serde_yaml::Value;
+use tokio::runtime::Runtime;
+
+use crate::aws::{self, ssm};
+
+/// Placeholder prefix and suffix in the spec string values.
+/// e.g., "${env:NLB_ACM_CERTIFICATE_ARN}"
+/// e.g., "${ssm:/avalanche-ops/fuji/nlb-acm-certificate-arn}"
+const PREFIX: &str = "${";
+const SUFFIX: &str = "}";
+
+/// Represents a field path in the spec (e.g., "aws_resources.s3_bucket").
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Key {
+    Field(String),
+    Index(usize),
+}
+
+/// Represents a spec value resolved from the placeholders at load time.
+/// The template is restored when the spec is written back, so that
+/// the resolved values (e.g., secrets) never get written to the spec file.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Interpolation {
+    pub path: Vec<Key>,
+    pub template: String,
+    pub resolved: String,
+}
+
+/// Displays the path in the dotted form (e.g., "machine.instance_types[0]").
+pub struct Path<'a>(pub &'a [Key]);
+
+impl fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, k) in self.0.iter().enumerate() {
+            match k {
+                Key::Field(v) if i == 0 => write!(f, "{}", v)?,
+                Key::Field(v) => write!(f, ".{}", v)?,
+                Key::Index(v) => write!(f, "[{}]", v)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Resolves all "${env:VAR}" and "${ssm:/path/param}" placeholders
+/// in the string values of the YAML document, in place.
+/// The SSM parameters are fetched from the "aws_resources.region" of the spec.
+pub fn resolve(doc: &mut Value) -> io::Result<Vec<Interpolation>> {
+    let mut found: Vec<(Vec<Key>, String)> = Vec::new();
+    collect(doc, &mut Vec::new(), &mut found);
+    if found.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut ssm_names: Vec<String> = Vec::new();
+    for (path, template) in found.iter() {
+        for reference in references(path, template)? {
+            if let Some(name) = reference.strip_prefix("ssm:") {
+                if !ssm_names.iter().any(|v| v == name) {
+                    ssm_names.push(name.to_string());
+                }
+            }
+        }
+    }
+    let region = doc
+        .get("aws_resources")
+        .and_then(|v| v.get("region"))
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.contains(PREFIX))
+        .map(String::from);
+    let ssm_values = fetch_ssm_parameters(region, ssm_names)?;
+
+    let mut interpolations = Vec::new();
+    for (path, template) in found {
+        let mut resolved = template.clone();
+        for reference in references(&path, &template)? {
+            let value = match reference.split_once(':') {
+                Some(("env", name)) => env::var(name).map_err(|_| {
+                    Error::new(
+                        ErrorKind::NotFound,
+                        format!(
+                            "spec field '{}' references unset environment variable '{}'",
+                            Path(&path),
+                            name
+                        ),
+                    )
+                })?,
+                Some(("ssm", name)) => ssm_values.get(name).cloned().unwrap_or_default(),
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "spec field '{}' has unknown reference '{}' (must be 'env:' or 'ssm:')",
+                            Path(&path),
+                            reference
+                        ),
+                    ))
+                }
+            };
+            resolved = resolved.replacen(&format!("{}{}{}", PREFIX, reference, SUFFIX), &value, 1);
+        }
+        if let Some(v) = get_mut(doc, &path) {
+            *v = Value::String(resolved.clone());
+        }
+        info!("resolved spec field '{}'", Path(&path));
+        interpolations.push(Interpolation {
+            path,
+            template,
+            resolved,
+        });
+    }
+    Ok(interpolations)
+}
+
+/// Puts the templates back in place of the resolved values, unless the value
+/// was changed since the load (e.g., updated by the command).
+pub fn restore(doc: &mut Value, interpolations: &[Interpolation]) {
+    for interpolation in interpolations.iter() {
+        if let Some(v) = get_mut(doc, &interpolation.path) {
+            if v.as_str() == Some(interpolation.resolved.as_str()) {
+                *v = Value::String(interpolation.template.clone());
+            }
+        }
+    }
+}
+
+fn collect(v: &Value, path: &mut Vec<Key>, found: &mut Vec<(Vec<Key>, String)>) {
+    match v {
+        Value::String(s) if s.contains(PREFIX) => found.push((path.clone(), s.clone())),
+        Value::Mapping(m) => {
+            for (k, v) in m.iter() {
+                if let Some(k) = k.as_str() {
+                    path.push(Key::Field(k.to_string()));
+                    collect(v, path, found);
+                    path.pop();
+                }
+            }
+        }
+        Value::Sequence(seq) => {
+            for (i, v) in seq.iter().enumerate() {
+                path.push(Key::Index(i));
+                collect(v, path, found);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn get_mut<'a>(doc: &'a mut Value, path: &[Key]) -> Option<&'a mut Value> {
+    let mut cur = doc;
+    for k in path.iter() {
+        cur = match k {
+            Key::Field(f) => cur.get_mut(f.as_str())?,
+            Key::Index(i) => cur.get_mut(*i)?,
+        };
+    }
+    Some(cur)
+}
+
+/// Returns the references in the template (e.g., "env:VAR" for "${env:VAR}").
+fn references(path: &[Key], template: &str) -> io::Result<Vec<String>> {
+    let mut refs = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(PREFIX) {
+        let after = &rest[start + PREFIX.len()..];
+        let end = after.find(SUFFIX).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("spec field '{}' has unclosed '{}'", Path(path), PREFIX),
+            )
+        })?;
+        refs.push(after[..end].to_string());
+        rest = &after[end + SUFFIX.len()..];
+    }
+    Ok(refs)
+}
+
+/// Fetches the SSM parameters on a separate thread with its own runtime,
+/// since the spec may be loaded from within the async contexts.
+fn fetch_ssm_parameters(
+    region: Option<String>,
+    names: Vec<String>,
+) -> io::Result<BTreeMap<String, String>> {
+    if names.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    thread::spawn(move || -> io::Result<BTreeMap<String, String>> {
+        let rt = Runtime::new()?;
+        rt.block_on(async move {
+            let shared_config = aws::load_config(region).await?;
+            let ssm_manager = ssm::Manager::new(&shared_config);
+            let mut values = BTreeMap::new();
+            for name in names {
+                let value = ssm_manager.get_parameter(&name).await.map_err(|e| {
+                    Error::new(
+                        ErrorKind::Other,
+                        format!("failed to resolve '${{ssm:{}}}' ({})", name, e.message()),
+                    )
+                })?;
+                values.insert(name, value);
+            }
+            Ok(values)
+        })
+    })
+    .join()
+    .map_err(|_| Error::new(ErrorKind::Other, "SSM resolver thread panicked"))?
+}
+
+#[test]
+fn test_resolve() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    env::set_var("SPEC_RESOLVER_TEST_BUCKET", "my-bucket");
+    env::set_var("SPEC_RESOLVER_TEST_TYPE", "c5");
+    let mut doc: Value = serde_yaml::from_str(
+        r#"
+id: test
+aws_resources:
+  region: us-west-2
+  s3_bucket: ${env:SPEC_RESOLVER_TEST_BUCKET}
+machine:
+  instance_types:
+  - ${env:SPEC_RESOLVER_TEST_TYPE}.large
+  - m5.large
+"#,
+    )
+    .unwrap();
+    let orig = doc.clone();
+
+    let interpolations = resolve(&mut doc).unwrap();
+    assert_eq!(interpolations.len(), 2);
+    assert_eq!(
+        Path(&interpolations[1].path).to_string(),
+        "machine.instance_types[0]"
+    );
+    assert_eq!(
+        doc["aws_resources"]["s3_bucket"].as_str(),
+        Some("my-bucket")
+    );
+    assert_eq!(
+        doc["machine"]["instance_types"][0].as_str(),
+        Some("c5.large")
+    );
+
+    // changed values are kept
+    let mut updated = doc.clone();
+    updated["machine"]["instance_types"][0] = Value::String(String::from("c6a.large"));
+    restore(&mut updated, &interpolations);
+    assert_eq!(
+        updated["aws_resources"]["s3_bucket"].as_str(),
+        Some("${env:SPEC_RESOLVER_TEST_BUCKET}")
+    );
+    assert_eq!(
+        updated["machine"]["instance_types"][0].as_str(),
+        Some("c6a.large")
+    );
+
+    restore(&mut doc, &interpolations);
+    assert_eq!(doc, orig);
+
+    let mut doc: Value = serde_yaml::from_str("id: ${env:SPEC_RESOLVER_TEST_UNSET}").unwrap();
+    let err = resolve(&mut doc).unwrap_err();
+    assert!(err.to_string().contains("'id'"));
+    let mut doc: Value = serde_yaml::from_str("id: ${vault:x}").unwrap();
+    assert!(resolve(&mut doc).is_err());
+    let mut doc: Value = serde_yaml::from_str("id: ${env:X").unwrap();
+    assert!(resolve(&mut doc).is_err());
+}
@@ -0,0 +1,389 @@
+use std::io::{self, Error, ErrorKind};
+
+use aws_sdk_s3::model::ObjectVersion;
+use log::{info, warn};
+
+use crate::{aws::s3, Spec, StorageNamespace};
+
+/// Represents a past spec state in the versioned S3 bucket.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Version {
+    pub version_id: String,
+    /// RFC 3339 timestamp.
+    pub last_modified: String,
+    pub size: i64,
+    pub is_latest: bool,
+}
+
+impl From<&ObjectVersion> for Version {
+    fn from(v: &ObjectVersion) -> Self {
+        Self {
+            version_id: v.version_id().unwrap_or_default().to_string(),
+            last_modified: v
+                .last_modified()
+                .and_then(|t| t.fmt(aws_smithy_types::date_time::Format::DateTime).ok())
+                .unwrap_or_default(),
+            size: v.size(),
+            is_latest: v.is_latest(),
+        }
+    }
+}
+
+/// Downloads the spec state of the cluster, optionally at the version.
+/// Returns "None" if the cluster has no remote state yet.
+pub async fn download(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    id: &str,
+    version_id: Option<&str>,
+) -> io::Result<Option<Spec>> {
+    let d = s3_manager
+        .get_bytes(
+            s3_bucket,
+            &StorageNamespace::ConfigFile(id.to_string()).encode(),
+            version_id,
+        )
+        .await
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to download spec state ({})", e.message()),
+            )
+        })?;
+    match d {
+        Some(d) => serde_yaml::from_slice(&d).map(Some).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid spec state YAML: {}", e),
+            )
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Lists the spec states of the cluster, latest first.
+pub async fn versions(
+    s3_manager: &s3::Manager,
+    s3_bucket: &str,
+    id: &str,
+) -> io::Result<Vec<Version>> {
+    let versions = s3_manager
+        .list_object_versions(
+            s3_bucket,
+            &StorageNamespace::ConfigFile(id.to_string()).encode(),
+        )
+        .await
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to list spec state versions ({})", e.message()),
+            )
+        })?;
+    Ok(versions.iter().map(Version::from).collect())
+}
+
+/// Downloads the remote spec state and merges into the local spec.
+/// Returns the merged fields (empty if the local spec is up-to-date).
+pub async fn pull(s3_manager: &s3::Manager, spec: &mut Spec) -> io::Result<Vec<String>> {
+    let s3_bucket = match &spec.aws_resources {
+        Some(aws_resources) => aws_resources.s3_bucket.clone(),
+        None => return Ok(Vec::new()),
+    };
+    match download(s3_manager, &s3_bucket, &spec.id, None).await? {
+        Some(remote) => {
+            let merged = merge(spec, &remote);
+            if !merged.is_empty() {
+                warn!("merged remote spec state fields {:?}", merged);
+            }
+            Ok(merged)
+        }
+        None => {
+            info!("no remote spec state for '{}'", spec.id);
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// Merges the fields that the operations generate (e.g., stack IDs, node keys)
+/// from the remote state, which is authoritative for them. The user-defined
+/// fields of the local spec are kept, so the local edits are applied.
+/// Returns the merged fields.
+pub fn merge(local: &mut Spec, remote: &Spec) -> Vec<String> {
+    let mut merged = Vec::new();
+
+    if let Some(remote_resources) = &remote.aws_resources {
+        let local_resources = local
+            .aws_resources
+            .get_or_insert_with(|| remote_resources.clone());
+        let (l, r) = (local_resources, remote_resources);
+        take(
+            "aws_resources.identity",
+            &mut l.identity,
+            &r.identity,
+            &mut merged,
+        );
+        take(
+            "aws_resources.kms_cmk_id",
+            &mut l.kms_cmk_id,
+            &r.kms_cmk_id,
+            &mut merged,
+        );
+        take(
+            "aws_resources.kms_cmk_arn",
+            &mut l.kms_cmk_arn,
+            &r.kms_cmk_arn,
+            &mut merged,
+        );
+        take(
+            "aws_resources.ec2_key_name",
+            &mut l.ec2_key_name,
+            &r.ec2_key_name,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_ec2_instance_role",
+            &mut l.cloudformation_ec2_instance_role,
+            &r.cloudformation_ec2_instance_role,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_ec2_instance_profile_arn",
+            &mut l.cloudformation_ec2_instance_profile_arn,
+            &r.cloudformation_ec2_instance_profile_arn,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_vpc",
+            &mut l.cloudformation_vpc,
+            &r.cloudformation_vpc,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_vpc_id",
+            &mut l.cloudformation_vpc_id,
+            &r.cloudformation_vpc_id,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_vpc_security_group_id",
+            &mut l.cloudformation_vpc_security_group_id,
+            &r.cloudformation_vpc_security_group_id,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_vpc_public_subnet_ids",
+            &mut l.cloudformation_vpc_public_subnet_ids,
+            &r.cloudformation_vpc_public_subnet_ids,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_vpc_private_connection_id",
+            &mut l.cloudformation_vpc_private_connection_id,
+            &r.cloudformation_vpc_private_connection_id,
+            &mut merged,
+        );
+        take(
+            "aws_resources.nlb_endpoint_service_id",
+            &mut l.nlb_endpoint_service_id,
+            &r.nlb_endpoint_service_id,
+            &mut merged,
+        );
+        take(
+            "aws_resources.nlb_endpoint_service_name",
+            &mut l.nlb_endpoint_service_name,
+            &r.nlb_endpoint_service_name,
+            &mut merged,
+        );
+        take(
+            "aws_resources.elastic_ip_pool_addresses",
+            &mut l.elastic_ip_pool_addresses,
+            &r.elastic_ip_pool_addresses,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_asg_anchor_nodes",
+            &mut l.cloudformation_asg_anchor_nodes,
+            &r.cloudformation_asg_anchor_nodes,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_asg_anchor_nodes_logical_id",
+            &mut l.cloudformation_asg_anchor_nodes_logical_id,
+            &r.cloudformation_asg_anchor_nodes_logical_id,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_asg_non_anchor_nodes",
+            &mut l.cloudformation_asg_non_anchor_nodes,
+            &r.cloudformation_asg_non_anchor_nodes,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_asg_non_anchor_nodes_logical_id",
+            &mut l.cloudformation_asg_non_anchor_nodes_logical_id,
+            &r.cloudformation_asg_non_anchor_nodes_logical_id,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_asg_icm_relayer",
+            &mut l.cloudformation_asg_icm_relayer,
+            &r.cloudformation_asg_icm_relayer,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_asg_icm_relayer_logical_id",
+            &mut l.cloudformation_asg_icm_relayer_logical_id,
+            &r.cloudformation_asg_icm_relayer_logical_id,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_asg_nlb_arn",
+            &mut l.cloudformation_asg_nlb_arn,
+            &r.cloudformation_asg_nlb_arn,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_asg_nlb_target_group_arn",
+            &mut l.cloudformation_asg_nlb_target_group_arn,
+            &r.cloudformation_asg_nlb_target_group_arn,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_asg_nlb_dns_name",
+            &mut l.cloudformation_asg_nlb_dns_name,
+            &r.cloudformation_asg_nlb_dns_name,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_budget",
+            &mut l.cloudformation_budget,
+            &r.cloudformation_budget,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_budget_alert_topic_arn",
+            &mut l.cloudformation_budget_alert_topic_arn,
+            &r.cloudformation_budget_alert_topic_arn,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_auditor_role",
+            &mut l.cloudformation_auditor_role,
+            &r.cloudformation_auditor_role,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudformation_auditor_role_arn",
+            &mut l.cloudformation_auditor_role_arn,
+            &r.cloudformation_auditor_role_arn,
+            &mut merged,
+        );
+        take(
+            "aws_resources.cloudwatch_avalanche_metrics_namespace",
+            &mut l.cloudwatch_avalanche_metrics_namespace,
+            &r.cloudwatch_avalanche_metrics_namespace,
+            &mut merged,
+        );
+    }
+
+    // the remote state has no private keys of the prefunded keys
+    // (see "Spec::state"), so keeps the local ones to sign on the CLI
+    if !local.has_prefunded_private_keys() || remote.has_prefunded_private_keys() {
+        take(
+            "generated_seed_private_key_with_locked_p_chain_balance",
+            &mut local.generated_seed_private_key_with_locked_p_chain_balance,
+            &remote.generated_seed_private_key_with_locked_p_chain_balance,
+            &mut merged,
+        );
+        take(
+            "generated_seed_private_keys",
+            &mut local.generated_seed_private_keys,
+            &remote.generated_seed_private_keys,
+            &mut merged,
+        );
+    }
+    take(
+        "current_nodes",
+        &mut local.current_nodes,
+        &remote.current_nodes,
+        &mut merged,
+    );
+    take(
+        "endpoints",
+        &mut local.endpoints,
+        &remote.endpoints,
+        &mut merged,
+    );
+
+    merged
+}
+
+/// Overwrites the local field with the remote one, if set and different.
+fn take<T: Clone + PartialEq>(
+    field: &str,
+    local: &mut Option<T>,
+    remote: &Option<T>,
+    merged: &mut Vec<String>,
+) {
+    if remote.is_some() && local != remote {
+        *local = remote.clone();
+        merged.push(field.to_string());
+    }
+}
+
+#[test]
+fn test_merge() {
+    use crate::avalanche::{
+        avalanchego::config as avalanchego_config, coreth::config as coreth_config,
+    };
+
+    let local: Spec = serde_json::from_value(serde_json::json!({
+        "id": "test",
+        "aws_resources": { "region": "us-west-2", "s3_bucket": "local" },
+        "machine": { "non_anchor_nodes": 2 },
+        "install_artifacts": { "avalanched_bin": "a", "avalanchego_bin": "b" },
+        "avalanchego_config": avalanchego_config::Config::default(),
+        "coreth_config": coreth_config::Config::default(),
+    }))
+    .unwrap();
+
+    let mut remote = local.clone();
+    assert!(merge(&mut local.clone(), &remote).is_empty());
+
+    // remote generated fields win, local user fields are kept
+    let mut remote_resources = remote.aws_resources.clone().unwrap();
+    remote_resources.s3_bucket = String::from("remote");
+    remote_resources.kms_cmk_arn = Some(String::from("arn"));
+    remote_resources.cloudformation_vpc_public_subnet_ids = Some(vec![String::from("s")]);
+    remote.aws_resources = Some(remote_resources);
+    remote.machine.non_anchor_nodes = 5;
+
+    let mut merged_spec = local.clone();
+    let merged = merge(&mut merged_spec, &remote);
+    assert_eq!(
+        merged,
+        vec![
+            "aws_resources.kms_cmk_arn",
+            "aws_resources.cloudformation_vpc_public_subnet_ids"
+        ]
+    );
+    let merged_resources = merged_spec.aws_resources.unwrap();
+    assert_eq!(merged_resources.s3_bucket, "local");
+    assert_eq!(merged_resources.kms_cmk_arn, Some(String::from("arn")));
+    assert_eq!(merged_spec.machine.non_anchor_nodes, 2);
+
+    // the stripped remote keys never replace the local private keys
+    let mut local = local;
+    local.generated_seed_private_keys = Some(vec![crate::avalanche::key::TEST_KEYS[0]
+        .to_info(1)
+        .unwrap()]);
+    let remote = local.state();
+    assert!(!remote.has_prefunded_private_keys());
+    let mut merged_spec = local.clone();
+    assert!(merge(&mut merged_spec, &remote).is_empty());
+    assert!(merged_spec.prefunded_key(0).is_ok());
+    let mut new_local = local.clone();
+    new_local.generated_seed_private_keys = None;
+    merge(&mut new_local, &remote);
+    assert!(new_local.prefunded_key(0).is_err());
+}
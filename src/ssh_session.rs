@@ -0,0 +1,160 @@
+use std::{
+    fs,
+    io::{self, Error, ErrorKind},
+    path::Path,
+    process::Command,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::StorageNamespace;
+
+/// Login user of the Ubuntu AMIs.
+pub const DEFAULT_USER: &str = "ubuntu";
+
+/// Represents how the session reaches the instance.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Method {
+    /// OpenSSH with the stored private key ("aws_resources.ec2_key_path"),
+    /// or the ephemeral key pushed via EC2 Instance Connect.
+    Key { key_path: String, host: String },
+    /// "aws ssm start-session", without any inbound port or key.
+    Ssm { region: String },
+}
+
+impl Method {
+    /// Returns the command arguments that open the interactive session.
+    pub fn args(&self, instance_id: &str) -> Vec<String> {
+        match self {
+            Method::Key { key_path, host } => vec![
+                String::from("ssh"),
+                String::from("-o"),
+                String::from("StrictHostKeyChecking no"),
+                String::from("-i"),
+                key_path.clone(),
+                format!("{}@{}", DEFAULT_USER, host),
+            ],
+            Method::Ssm { region } => vec![
+                String::from("aws"),
+                String::from("ssm"),
+                String::from("start-session"),
+                String::from("--region"),
+                region.clone(),
+                String::from("--target"),
+                instance_id.to_string(),
+            ],
+        }
+    }
+}
+
+/// Returns the command arguments that run the session under "script"
+/// (util-linux), which writes the terminal transcript to the file.
+pub fn record_args(session_args: &[String], transcript_path: &str) -> Vec<String> {
+    let cmd: Vec<String> = session_args.iter().map(|a| shell_quote(a)).collect();
+    vec![
+        String::from("script"),
+        String::from("--quiet"),
+        String::from("--flush"),
+        String::from("--return"),
+        String::from("--command"),
+        cmd.join(" "),
+        transcript_path.to_string(),
+    ]
+}
+
+/// Generates the ED25519 key pair in the directory for EC2 Instance Connect,
+/// and returns the private key path and the OpenSSH public key.
+pub fn generate_ephemeral_key(dir: &Path) -> io::Result<(String, String)> {
+    let key_path = dir.join("id_ed25519");
+    let key_path = key_path.to_str().unwrap().to_string();
+    let output = Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-C", "avalanche-ops", "-f"])
+        .arg(&key_path)
+        .output()
+        .map_err(|e| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("failed to run 'ssh-keygen' ({})", e),
+            )
+        })?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "failed 'ssh-keygen' ({})",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+    let public_key = fs::read_to_string(format!("{}.pub", key_path))?;
+    Ok((key_path, public_key.trim().to_string()))
+}
+
+/// Returns the S3 key of the session transcript under the audit prefix.
+/// The keys sort by the start time.
+pub fn recording_s3_key(
+    id: &str,
+    started_at: &DateTime<Utc>,
+    instance_id: &str,
+    operator: &str,
+) -> String {
+    StorageNamespace::SshSessionRecording(
+        id.to_string(),
+        format!(
+            "{}-{}-{}",
+            started_at.format("%Y%m%dT%H%M%SZ"),
+            instance_id,
+            operator
+        ),
+    )
+    .encode()
+}
+
+/// Single-quotes the argument for "sh -c", unless it only has the safe characters.
+fn shell_quote(s: &str) -> String {
+    let safe = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./@:=".contains(c));
+    if safe {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+#[test]
+fn test_ssh_session() {
+    let key = Method::Key {
+        key_path: String::from("/tmp/my key.pem"),
+        host: String::from("1.2.3.4"),
+    };
+    let args = key.args("i-123");
+    assert_eq!(args.last().unwrap(), "ubuntu@1.2.3.4");
+
+    let recorded = record_args(&args, "/tmp/transcript.log");
+    assert_eq!(
+        recorded[5],
+        "ssh -o 'StrictHostKeyChecking no' -i '/tmp/my key.pem' ubuntu@1.2.3.4"
+    );
+    assert_eq!(recorded[6], "/tmp/transcript.log");
+
+    let ssm = Method::Ssm {
+        region: String::from("us-west-2"),
+    };
+    assert_eq!(
+        ssm.args("i-123").join(" "),
+        "aws ssm start-session --region us-west-2 --target i-123"
+    );
+
+    assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    assert_eq!(
+        recording_s3_key(
+            "my-id",
+            &DateTime::parse_from_rfc3339("2022-05-01T10:20:30Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            "i-123",
+            "alice"
+        ),
+        "my-id/audit/ssh-sessions/20220501T102030Z-i-123-alice.log"
+    );
+}
@@ -0,0 +1,123 @@
+use std::io::{self, Error, ErrorKind};
+
+use aws_sdk_cloudwatch::model::{Dimension, MetricDatum, StandardUnit};
+
+use crate::utils::bash;
+
+/// Amazon Time Sync Service, reachable from all instances via the link-local address.
+/// The user-data configures chrony with this server.
+/// ref. https://docs.aws.amazon.com/AWSEC2/latest/UserGuide/set-time.html
+pub const AMAZON_TIME_SYNC_SERVICE: &str = "169.254.169.123";
+
+/// Default threshold of the clock offset alarm, in milliseconds.
+pub const DEFAULT_CLOCK_OFFSET_ALARM_THRESHOLD_MS: u32 = 100;
+
+/// Stratum of an unsynchronized clock.
+pub const UNSYNCHRONIZED_STRATUM: u32 = 16;
+
+/// Represents the "chronyc -c tracking" output.
+/// ref. https://chrony.tuxfamily.org/doc/4.0/chronyc.html#tracking
+#[derive(Debug, PartialEq, Clone)]
+pub struct Tracking {
+    pub reference_name: String,
+    pub stratum: u32,
+    /// Offset of the system clock from the NTP time, in seconds
+    /// (positive if the system clock is fast).
+    pub system_time_offset_seconds: f64,
+    pub root_dispersion_seconds: f64,
+    /// e.g., "Normal", "Not synchronised".
+    pub leap_status: String,
+}
+
+impl Tracking {
+    /// Parses the CSV output of "chronyc -c tracking".
+    pub fn parse(s: &str) -> io::Result<Self> {
+        let fields: Vec<&str> = s.trim().split(',').collect();
+        if fields.len() < 14 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unexpected 'chronyc -c tracking' output '{}'", s.trim()),
+            ));
+        }
+        let parse_f64 = |i: usize| -> io::Result<f64> {
+            fields[i].parse::<f64>().map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid field {} '{}' ({})", i, fields[i], e),
+                )
+            })
+        };
+        Ok(Self {
+            reference_name: fields[1].to_string(),
+            stratum: fields[2].parse::<u32>().map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid stratum '{}' ({})", fields[2], e),
+                )
+            })?,
+            system_time_offset_seconds: parse_f64(4)?,
+            root_dispersion_seconds: parse_f64(11)?,
+            leap_status: fields[13].to_string(),
+        })
+    }
+
+    /// Runs "chronyc -c tracking" on the local host.
+    pub fn fetch() -> io::Result<Self> {
+        let (stdout, _) = bash::run("chronyc -c tracking")?;
+        Self::parse(&stdout)
+    }
+
+    pub fn is_synchronized(&self) -> bool {
+        self.leap_status != "Not synchronised" && self.stratum < UNSYNCHRONIZED_STRATUM
+    }
+
+    /// Absolute clock offset in milliseconds, including the root dispersion
+    /// (the worst-case error from the reference clock).
+    pub fn clock_offset_ms(&self) -> f64 {
+        (self.system_time_offset_seconds.abs() + self.root_dispersion_seconds) * 1000.0
+    }
+
+    /// Returns the CloudWatch metrics, with the node kind dimension
+    /// for the per-ASG alarms.
+    pub fn to_cw_metric_data(&self, node_kind: &str) -> Vec<MetricDatum> {
+        let dimension = Dimension::builder()
+            .name("NodeKind")
+            .value(node_kind)
+            .build();
+        vec![
+            MetricDatum::builder()
+                .metric_name("clock_offset_ms")
+                .dimensions(dimension.clone())
+                .value(self.clock_offset_ms())
+                .unit(StandardUnit::Milliseconds)
+                .build(),
+            MetricDatum::builder()
+                .metric_name("clock_synchronized")
+                .dimensions(dimension)
+                .value(if self.is_synchronized() { 1_f64 } else { 0_f64 })
+                .unit(StandardUnit::Count)
+                .build(),
+        ]
+    }
+}
+
+#[test]
+fn test_tracking() {
+    let t = Tracking::parse(
+        "A9FEA97B,169.254.169.123,4,1663000000.123456789,-0.000012345,0.000002,0.000010,-10.5,0.001,0.05,0.000123,0.000456,64.2,Normal\n",
+    )
+    .unwrap();
+    assert_eq!(t.reference_name, "169.254.169.123");
+    assert_eq!(t.stratum, 4);
+    assert!(t.is_synchronized());
+    assert!((t.clock_offset_ms() - 0.468345).abs() < 1e-6);
+    assert_eq!(t.to_cw_metric_data("anchor").len(), 2);
+
+    let t = Tracking::parse(
+        "00000000,,0,0.000000000,0.000000000,0.000000000,0.000000000,0.000,0.000,0.000,1.000000000,1.000000000,0.0,Not synchronised",
+    )
+    .unwrap();
+    assert!(!t.is_synchronized());
+
+    assert!(Tracking::parse("506 Cannot talk to daemon").is_err());
+}
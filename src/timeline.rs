@@ -0,0 +1,409 @@
+use std::{collections::BTreeSet, fmt};
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+
+use crate::{
+    avalanche::heartbeat::Heartbeat,
+    aws::{cloudformation::StackEvent, cloudwatch},
+};
+
+/// Messages in the avalanched logs that mark the node events.
+/// The first match of each line wins.
+const AVALANCHED_LOG_MARKERS: [(&str, Kind, &str); 4] = [
+    (
+        "STEP: fetching intance metadata using IMDSv2",
+        Kind::Started,
+        "avalanched started",
+    ),
+    (
+        "health/liveness check success",
+        Kind::Bootstrapped,
+        "avalanchego healthy after the start",
+    ),
+    (
+        "artifacts have been updated",
+        Kind::Upgraded,
+        "installed the updated artifacts ('events update-artifacts')",
+    ),
+    (
+        "STEP: stopping avalanche.service for the check",
+        Kind::Restarted,
+        "avalanchego stopped for 'check-db'",
+    ),
+];
+
+/// CloudFormation resources whose events apply to every node of the stack.
+const STACK_RESOURCE_TYPES: [&str; 3] = [
+    "AWS::CloudFormation::Stack",
+    "AWS::AutoScaling::AutoScalingGroup",
+    "AWS::EC2::LaunchTemplate",
+];
+
+/// Represents the kind of the node event.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
+pub enum Kind {
+    Launched,
+    Started,
+    Bootstrapped,
+    Restarted,
+    Backup,
+    Upgraded,
+    Remediation,
+    Alarm,
+    Stack,
+}
+
+impl Kind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Kind::Launched => "launched",
+            Kind::Started => "started",
+            Kind::Bootstrapped => "bootstrapped",
+            Kind::Restarted => "restarted",
+            Kind::Backup => "backup",
+            Kind::Upgraded => "upgraded",
+            Kind::Remediation => "remediation",
+            Kind::Alarm => "alarm",
+            Kind::Stack => "stack",
+        }
+    }
+}
+
+/// Represents where the event was found.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
+pub enum Source {
+    Heartbeat,
+    CloudFormation,
+    AvalanchedLog,
+    CloudWatchAlarm,
+}
+
+impl Source {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Source::Heartbeat => "heartbeat",
+            Source::CloudFormation => "cloudformation",
+            Source::AvalanchedLog => "avalanched-log",
+            Source::CloudWatchAlarm => "cloudwatch-alarm",
+        }
+    }
+}
+
+/// Represents an event on the node timeline.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
+pub struct Event {
+    pub timestamp: DateTime<Utc>,
+    pub kind: Kind,
+    pub source: Source,
+    pub message: String,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}  {:<12}  {:<16}  {}",
+            self.timestamp.format("%Y-%m-%dT%H:%M:%SZ"),
+            self.kind.as_str(),
+            self.source.as_str(),
+            self.message
+        )
+    }
+}
+
+/// Merges the events from all sources, oldest first, dropping the events
+/// before "since".
+pub fn merge(sources: Vec<Vec<Event>>, since: DateTime<Utc>) -> Vec<Event> {
+    let mut events: Vec<Event> = sources
+        .into_iter()
+        .flatten()
+        .filter(|ev| ev.timestamp >= since)
+        .collect();
+    events.sort();
+    events.dedup();
+    events
+}
+
+/// Derives the events from the consecutive heartbeats of the node
+/// (e.g., the S3 object versions of the heartbeat), in any order.
+pub fn from_heartbeats(heartbeats: &[Heartbeat]) -> Vec<Event> {
+    let mut sorted = heartbeats.to_vec();
+    sorted.sort_by_key(|hb| hb.timestamp);
+
+    let mut events = Vec::new();
+    let mut backups = BTreeSet::new();
+    let mut prev: Option<&Heartbeat> = None;
+    for hb in sorted.iter() {
+        let booted_at = at(hb.timestamp.saturating_sub(hb.uptime_seconds));
+        match prev {
+            None => events.push(Event {
+                timestamp: booted_at,
+                kind: Kind::Launched,
+                source: Source::Heartbeat,
+                message: format!("instance booted ({})", hb.machine_id),
+            }),
+            Some(p) => {
+                if hb.uptime_seconds < p.uptime_seconds {
+                    events.push(Event {
+                        timestamp: booted_at,
+                        kind: Kind::Restarted,
+                        source: Source::Heartbeat,
+                        message: String::from("instance rebooted"),
+                    });
+                }
+                if hb.avalanchego_version != p.avalanchego_version {
+                    events.push(Event {
+                        timestamp: at(hb.timestamp),
+                        kind: Kind::Upgraded,
+                        source: Source::Heartbeat,
+                        message: format!(
+                            "avalanchego '{}' -> '{}'",
+                            p.avalanchego_version, hb.avalanchego_version
+                        ),
+                    });
+                }
+                if hb.avalanched_version != p.avalanched_version {
+                    events.push(Event {
+                        timestamp: at(hb.timestamp),
+                        kind: Kind::Upgraded,
+                        source: Source::Heartbeat,
+                        message: format!(
+                            "avalanched '{}' -> '{}'",
+                            p.avalanched_version, hb.avalanched_version
+                        ),
+                    });
+                }
+            }
+        }
+
+        let was_healthy = prev.map(|p| p.healthy == Some(true)).unwrap_or(false);
+        if hb.healthy == Some(true) && !was_healthy {
+            events.push(Event {
+                timestamp: at(hb.timestamp),
+                kind: Kind::Bootstrapped,
+                source: Source::Heartbeat,
+                message: String::from("reported healthy"),
+            });
+        }
+        if hb.healthy == Some(false) && was_healthy {
+            events.push(Event {
+                timestamp: at(hb.timestamp),
+                kind: Kind::Alarm,
+                source: Source::Heartbeat,
+                message: String::from("reported unhealthy"),
+            });
+        }
+        if let Some(b) = hb.last_backup {
+            if backups.insert(b) {
+                events.push(Event {
+                    timestamp: at(b),
+                    kind: Kind::Backup,
+                    source: Source::Heartbeat,
+                    message: String::from("database backup uploaded"),
+                });
+            }
+        }
+        prev = Some(hb);
+    }
+    events
+}
+
+/// Returns the events of the node ASG stack that apply to the instance:
+/// the stack, the ASG, and the launch template changes (e.g., rolling
+/// updates), and the events that name the instance (e.g., the signals).
+pub fn from_stack_events(stack_events: &[StackEvent], machine_id: &str) -> Vec<Event> {
+    stack_events
+        .iter()
+        .filter(|ev| {
+            STACK_RESOURCE_TYPES.contains(&ev.resource_type.as_str())
+                || ev.physical_resource_id == machine_id
+                || ev.resource_status_reason.contains(machine_id)
+        })
+        .map(|ev| {
+            let mut message = format!("{} {}", ev.logical_resource_id, ev.resource_status);
+            if !ev.resource_status_reason.is_empty() {
+                message.push_str(&format!(" ({})", ev.resource_status_reason));
+            }
+            Event {
+                timestamp: ev.timestamp,
+                kind: Kind::Stack,
+                source: Source::CloudFormation,
+                message,
+            }
+        })
+        .collect()
+}
+
+/// Returns the CloudWatch Logs filter pattern that matches any marker
+/// in the avalanched logs.
+/// ref. https://docs.aws.amazon.com/AmazonCloudWatch/latest/logs/FilterAndPatternSyntax.html
+pub fn avalanched_log_filter_pattern() -> String {
+    AVALANCHED_LOG_MARKERS
+        .iter()
+        .map(|(m, _, _)| format!("?\"{}\"", m))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Returns the events of the avalanched log lines with the markers.
+pub fn from_avalanched_logs(log_events: &[cloudwatch::LogEvent]) -> Vec<Event> {
+    log_events
+        .iter()
+        .filter_map(|ev| {
+            let (_, kind, message) = AVALANCHED_LOG_MARKERS
+                .iter()
+                .find(|(m, _, _)| ev.message.contains(m))?;
+            Some(Event {
+                timestamp: ev.timestamp,
+                kind: *kind,
+                source: Source::AvalanchedLog,
+                message: message.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Represents a line of "remediation::ACTION_LOG_FILE_PATH".
+#[derive(Deserialize)]
+struct RemediationLine {
+    /// RFC3339.
+    timestamp: String,
+    action: String,
+    healthy: bool,
+    db_corrupted: bool,
+}
+
+/// Returns the events of the remediation action log lines.
+pub fn from_remediation_logs(log_events: &[cloudwatch::LogEvent]) -> Vec<Event> {
+    log_events
+        .iter()
+        .filter_map(|ev| {
+            let line = serde_json::from_str::<RemediationLine>(&ev.message).ok()?;
+            let timestamp = DateTime::parse_from_rfc3339(&line.timestamp).ok()?;
+            Some(Event {
+                timestamp: timestamp.with_timezone(&Utc),
+                kind: Kind::Remediation,
+                source: Source::AvalanchedLog,
+                message: format!(
+                    "{} (healthy {}, db corrupted {})",
+                    line.action, line.healthy, line.db_corrupted
+                ),
+            })
+        })
+        .collect()
+}
+
+/// Returns the events of the alarm state changes.
+pub fn from_alarm_state_changes(changes: &[cloudwatch::AlarmStateChange]) -> Vec<Event> {
+    changes
+        .iter()
+        .map(|c| Event {
+            timestamp: c.timestamp,
+            kind: Kind::Alarm,
+            source: Source::CloudWatchAlarm,
+            message: format!("{}: {}", c.alarm_name, c.summary),
+        })
+        .collect()
+}
+
+fn at(unix_secs: u64) -> DateTime<Utc> {
+    Utc.timestamp_opt(unix_secs as i64, 0).unwrap()
+}
+
+#[test]
+fn test_timeline() {
+    use std::collections::BTreeMap;
+
+    let hb = |timestamp: u64, uptime_seconds: u64, version: &str, healthy: bool| Heartbeat {
+        node_id: String::from("NodeID-abc"),
+        machine_id: String::from("i-123"),
+        kind: String::from("non-anchor"),
+        public_ip: String::from("1.2.3.4"),
+        avalanched_version: String::from("0.0.5"),
+        avalanchego_version: version.to_string(),
+        disk_total_bytes: 0,
+        disk_available_bytes: 0,
+        uptime_seconds,
+        last_backup: Some(1_500),
+        config_sha256: String::new(),
+        avalanchego_config: BTreeMap::new(),
+        avalanchego_bin_sha256: String::new(),
+        plugins: BTreeMap::new(),
+        index_backfill: None,
+        healthy: Some(healthy),
+        timestamp,
+    };
+    let events = from_heartbeats(&[
+        hb(3_000, 2_000, "v1.7.10", true),
+        hb(1_000, 0, "v1.7.9", false),
+        hb(2_000, 1_000, "v1.7.9", true),
+        hb(4_000, 100, "v1.7.10", false),
+    ]);
+    let kinds: Vec<Kind> = events.iter().map(|ev| ev.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            Kind::Launched,
+            Kind::Backup,
+            Kind::Bootstrapped,
+            Kind::Upgraded,
+            Kind::Restarted,
+            Kind::Alarm,
+        ]
+    );
+    assert_eq!(events[0].timestamp, at(1_000));
+    assert_eq!(events[4].timestamp, at(3_900));
+
+    let stack_event = |logical: &str, resource_type: &str, reason: &str| StackEvent {
+        timestamp: at(5_000),
+        logical_resource_id: logical.to_string(),
+        physical_resource_id: String::new(),
+        resource_type: resource_type.to_string(),
+        resource_status: String::from("UPDATE_COMPLETE"),
+        resource_status_reason: reason.to_string(),
+    };
+    let events = from_stack_events(
+        &[
+            stack_event("ASG", "AWS::AutoScaling::AutoScalingGroup", ""),
+            stack_event("NLB", "AWS::ElasticLoadBalancingV2::LoadBalancer", ""),
+            stack_event(
+                "ASG",
+                "AWS::AutoScaling::AutoScalingGroup",
+                "signal from i-123",
+            ),
+        ],
+        "i-123",
+    );
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[1].message, "ASG UPDATE_COMPLETE (signal from i-123)");
+
+    let log = |message: &str| cloudwatch::LogEvent {
+        timestamp: at(6_000),
+        log_stream_name: String::from("i-123-non-anchor-avalanched"),
+        message: message.to_string(),
+    };
+    let events = from_avalanched_logs(&[
+        log("[2022-05-01T10:20:30Z INFO  avalanched_aws::run] STEP: fetching intance metadata using IMDSv2"),
+        log("[2022-05-01T10:25:30Z INFO  avalanched_aws::run] health/liveness check success for i-123"),
+        log("[2022-05-01T10:26:30Z INFO  avalanched_aws::run] unrelated"),
+    ]);
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[1].kind, Kind::Bootstrapped);
+    assert!(avalanched_log_filter_pattern().starts_with("?\"STEP: fetching"));
+
+    let events = from_remediation_logs(&[log(
+        r#"{"timestamp":"2022-05-01T10:20:30Z","action":"restart-node","healthy":false,"db_corrupted":false}"#,
+    )]);
+    assert_eq!(
+        events[0].message,
+        "restart-node (healthy false, db corrupted false)"
+    );
+
+    let merged = merge(
+        vec![events.clone(), events, from_stack_events(&[], "i-123")],
+        at(0),
+    );
+    assert_eq!(merged.len(), 1);
+    assert!(merge(vec![merged], Utc::now()).is_empty());
+}
@@ -0,0 +1,193 @@
+use std::io::{self, Error, ErrorKind};
+
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Spreads the node reports over the first hour of the day,
+/// so the nodes rarely rebuild the cluster report at the same time.
+pub const MAX_REPORT_DELAY_MINUTES: u64 = 60;
+
+pub const CSV_HEADER: &str = "date,cluster_id,instance_id,instance_type,node_kind,node_id,availability_zone,instance_hours,ebs_gb_hours,network_in_gb,network_out_gb";
+
+const BYTES_PER_GB: f64 = 1_000_000_000.0;
+
+/// Represents the usage of a node in a UTC day, written by "avalanched"
+/// on the node after the day ends.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct NodeUsage {
+    /// UTC day in "YYYY-MM-DD".
+    pub date: String,
+    pub cluster_id: String,
+    pub instance_id: String,
+    pub instance_type: String,
+    pub node_kind: String,
+    pub node_id: String,
+    pub availability_zone: String,
+    /// Running hours of the instance in the day.
+    pub instance_hours: f64,
+    /// Sizes of the attached EBS volumes times the running hours.
+    pub ebs_gb_hours: f64,
+    /// Sum of the "AWS/EC2" "NetworkIn" in the day.
+    pub network_in_bytes: f64,
+    /// Sum of the "AWS/EC2" "NetworkOut" in the day, the upper bound of the
+    /// billed data transfer (includes the free intra-AZ traffic).
+    pub network_out_bytes: f64,
+}
+
+/// Represents the daily usage report of the cluster in S3,
+/// rebuilt from the node usages whenever a node reports.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Report {
+    pub cluster_id: String,
+    pub date: String,
+    /// RFC3339.
+    pub generated_at: String,
+    pub nodes: Vec<NodeUsage>,
+    pub total_instance_hours: f64,
+    pub total_ebs_gb_hours: f64,
+    /// Data transfer estimate in GB ("NetworkOut" sum of the nodes).
+    pub total_data_transfer_out_gb: f64,
+}
+
+impl Report {
+    pub fn new(
+        cluster_id: &str,
+        date: &str,
+        mut nodes: Vec<NodeUsage>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        nodes.sort_by(|a, b| a.instance_id.cmp(&b.instance_id));
+        Self {
+            cluster_id: cluster_id.to_string(),
+            date: date.to_string(),
+            generated_at: now.to_rfc3339(),
+            total_instance_hours: round(nodes.iter().map(|n| n.instance_hours).sum()),
+            total_ebs_gb_hours: round(nodes.iter().map(|n| n.ebs_gb_hours).sum()),
+            total_data_transfer_out_gb: round(
+                nodes.iter().map(|n| n.network_out_bytes).sum::<f64>() / BYTES_PER_GB,
+            ),
+            nodes,
+        }
+    }
+
+    /// Encodes the node usages in CSV, one row per node.
+    pub fn to_csv(&self) -> String {
+        let mut s = String::from(CSV_HEADER);
+        s.push('\n');
+        for n in self.nodes.iter() {
+            s.push_str(&format!(
+                "{},{},{},{},{},{},{},{:.3},{:.3},{:.3},{:.3}\n",
+                n.date,
+                n.cluster_id,
+                n.instance_id,
+                n.instance_type,
+                n.node_kind,
+                n.node_id,
+                n.availability_zone,
+                n.instance_hours,
+                n.ebs_gb_hours,
+                n.network_in_bytes / BYTES_PER_GB,
+                n.network_out_bytes / BYTES_PER_GB,
+            ));
+        }
+        s
+    }
+}
+
+/// Returns the start and the end of the UTC day.
+pub fn day_range(date: &str) -> io::Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let d = NaiveDate::parse_from_str(date, DATE_FORMAT).map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid date '{}' ({})", date, e),
+        )
+    })?;
+    let start = Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap());
+    Ok((start, start + Duration::days(1)))
+}
+
+/// Returns the previous UTC day of the time, the latest day to report.
+pub fn previous_day(now: DateTime<Utc>) -> String {
+    (now - Duration::days(1)).format(DATE_FORMAT).to_string()
+}
+
+/// Returns the running hours in the range of the instance launched at the time.
+pub fn running_hours(launched_at: DateTime<Utc>, start: DateTime<Utc>, end: DateTime<Utc>) -> f64 {
+    let from = if launched_at > start {
+        launched_at
+    } else {
+        start
+    };
+    if from >= end {
+        return 0.0;
+    }
+    round((end - from).num_seconds() as f64 / 3600.0)
+}
+
+/// Returns the delay of the node report after the day ends,
+/// derived from the instance ID.
+pub fn report_delay_minutes(instance_id: &str) -> u64 {
+    instance_id.bytes().map(|b| b as u64).sum::<u64>() % MAX_REPORT_DELAY_MINUTES
+}
+
+fn round(v: f64) -> f64 {
+    (v * 1000.0).round() / 1000.0
+}
+
+#[test]
+fn test_usage() {
+    let (start, end) = day_range("2022-05-01").unwrap();
+    assert_eq!(start.to_rfc3339(), "2022-05-01T00:00:00+00:00");
+    assert_eq!(end.to_rfc3339(), "2022-05-02T00:00:00+00:00");
+    assert!(day_range("2022/05/01").is_err());
+    assert_eq!(
+        previous_day(Utc.with_ymd_and_hms(2022, 5, 1, 0, 30, 0).unwrap()),
+        "2022-04-30"
+    );
+
+    assert_eq!(running_hours(start - Duration::days(3), start, end), 24.0);
+    assert_eq!(
+        running_hours(start + Duration::minutes(90), start, end),
+        22.5
+    );
+    assert_eq!(running_hours(end, start, end), 0.0);
+
+    assert!(report_delay_minutes("i-0123456789abcdef0") < MAX_REPORT_DELAY_MINUTES);
+
+    let node = |instance_id: &str| NodeUsage {
+        date: String::from("2022-05-01"),
+        cluster_id: String::from("aops-custom-202205-abc"),
+        instance_id: instance_id.to_string(),
+        instance_type: String::from("c6a.2xlarge"),
+        node_kind: String::from("anchor"),
+        node_id: String::from("NodeID-7Xhw2mDxuDS44j42TCB6U5579esbSt3Lg"),
+        availability_zone: String::from("us-west-2a"),
+        instance_hours: 24.0,
+        ebs_gb_hours: 7200.0,
+        network_in_bytes: 1_500_000_000.0,
+        network_out_bytes: 2_000_000_000.0,
+    };
+    let report = Report::new(
+        "aops-custom-202205-abc",
+        "2022-05-01",
+        vec![node("i-b"), node("i-a")],
+        end,
+    );
+    assert_eq!(report.nodes[0].instance_id, "i-a");
+    assert_eq!(report.total_instance_hours, 48.0);
+    assert_eq!(report.total_ebs_gb_hours, 14400.0);
+    assert_eq!(report.total_data_transfer_out_gb, 4.0);
+
+    let csv = report.to_csv();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], CSV_HEADER);
+    assert_eq!(
+        lines[1],
+        "2022-05-01,aops-custom-202205-abc,i-a,c6a.2xlarge,anchor,NodeID-7Xhw2mDxuDS44j42TCB6U5579esbSt3Lg,us-west-2a,24.000,7200.000,1.500,2.000"
+    );
+}
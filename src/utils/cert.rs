@@ -14,6 +14,8 @@ use openssl::{
     x509::{X509Builder, X509NameBuilder},
 };
 
+use crate::utils::secret_bytes::SecretBytes;
+
 /// Generates a X509 certificate pair.
 /// ref. https://pkg.go.dev/github.com/ava-labs/avalanchego/staking#NewCertAndKeyBytes
 pub fn generate(key_path: &str, cert_path: &str) -> io::Result<()> {
@@ -82,9 +84,9 @@ pub fn generate(key_path: &str, cert_path: &str) -> io::Result<()> {
     // while OpenSSL 1.0.0 generates PKCS #8 keys.
     // ref. "crypto/tls.parsePrivateKey"
     // ref. "crypto/x509.MarshalPKCS8PrivateKey"
-    let key_contents = priv_key.private_key_to_pem_pkcs8().unwrap();
+    let key_contents = SecretBytes::new(priv_key.private_key_to_pem_pkcs8().unwrap());
     let mut key_file = File::create(key_path)?;
-    key_file.write_all(&key_contents[..])?;
+    key_file.write_all(key_contents.expose())?;
     info!("saved key {}", key_path);
 
     Ok(())
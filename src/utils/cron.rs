@@ -0,0 +1,174 @@
+use std::io::{self, Error, ErrorKind};
+
+use chrono::{DateTime, Datelike, FixedOffset, Timelike};
+
+/// Represents the 5-field cron schedule
+/// ("minute hour day-of-month month day-of-week"),
+/// supporting "*", lists ("1,2"), ranges ("1-5"), and steps ("*/15").
+/// ref. https://man7.org/linux/man-pages/man5/crontab.5.html
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Schedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days_of_month: Vec<bool>,
+    months: Vec<bool>,
+    days_of_week: Vec<bool>,
+    /// Cron matches either day field if both are restricted.
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+const DAYS_OF_WEEK: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+impl Schedule {
+    pub fn parse(s: &str) -> io::Result<Self> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("cron '{}' must have 5 fields", s),
+            ));
+        }
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59, &[])?,
+            hours: parse_field(fields[1], 0, 23, &[])?,
+            days_of_month: parse_field(fields[2], 1, 31, &[])?,
+            months: parse_field(fields[3], 1, 12, &MONTHS)?,
+            // "7" is also Sunday
+            days_of_week: {
+                let mut v = parse_field(fields[4], 0, 7, &DAYS_OF_WEEK)?;
+                v[0] = v[0] || v[7];
+                v
+            },
+            dom_restricted: fields[2] != "*",
+            dow_restricted: fields[4] != "*",
+        })
+    }
+
+    /// Returns true if the schedule fires at the minute of the time.
+    pub fn matches(&self, t: &DateTime<FixedOffset>) -> bool {
+        if !self.minutes[t.minute() as usize]
+            || !self.hours[t.hour() as usize]
+            || !self.months[t.month() as usize]
+        {
+            return false;
+        }
+        let dom = self.days_of_month[t.day() as usize];
+        let dow = self.days_of_week[t.weekday().num_days_from_sunday() as usize];
+        if self.dom_restricted && self.dow_restricted {
+            dom || dow
+        } else {
+            dom && dow
+        }
+    }
+}
+
+/// Parses the field into the flags indexed by the value.
+fn parse_field(s: &str, min: u32, max: u32, names: &[&str]) -> io::Result<Vec<bool>> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid cron field '{}'", s),
+        )
+    };
+    let value = |v: &str| -> io::Result<u32> {
+        let upper = v.to_uppercase();
+        if let Some(i) = names.iter().position(|n| *n == upper) {
+            // months are 1-indexed
+            return Ok(i as u32 + if min == 1 { 1 } else { 0 });
+        }
+        let n = v.parse::<u32>().map_err(|_| invalid())?;
+        if n < min || n > max {
+            return Err(invalid());
+        }
+        Ok(n)
+    };
+
+    let mut flags = vec![false; max as usize + 1];
+    for part in s.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, st)) => (r, st.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (start, end) = {
+            if range == "*" {
+                (min, max)
+            } else if let Some((a, b)) = range.split_once('-') {
+                (value(a)?, value(b)?)
+            } else {
+                let v = value(range)?;
+                (v, if step > 1 { max } else { v })
+            }
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        let mut v = start;
+        while v <= end {
+            flags[v as usize] = true;
+            v += step;
+        }
+    }
+    Ok(flags)
+}
+
+/// Parses the timezone as a fixed UTC offset (e.g., "UTC", "+09:00", "UTC-05:00").
+pub fn parse_utc_offset(s: &str) -> io::Result<FixedOffset> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid timezone '{}' (expected 'UTC' or '+HH:MM')", s),
+        )
+    };
+    let offset = s.trim().trim_start_matches("UTC").trim_start_matches("GMT");
+    if offset.is_empty() || offset == "Z" {
+        return Ok(FixedOffset::east_opt(0).unwrap());
+    }
+
+    let (sign, hm) = match offset.chars().next() {
+        Some('+') => (1, &offset[1..]),
+        Some('-') => (-1, &offset[1..]),
+        _ => return Err(invalid()),
+    };
+    let (h, m) = hm.split_once(':').unwrap_or((hm, "0"));
+    let h = h.parse::<i32>().map_err(|_| invalid())?;
+    let m = m.parse::<i32>().map_err(|_| invalid())?;
+    if h > 14 || m > 59 {
+        return Err(invalid());
+    }
+    FixedOffset::east_opt(sign * (h * 3600 + m * 60)).ok_or_else(invalid)
+}
+
+#[test]
+fn test_schedule() {
+    let t = |s: &str| DateTime::parse_from_rfc3339(s).unwrap();
+
+    assert_eq!(
+        parse_utc_offset("UTC").unwrap(),
+        FixedOffset::east_opt(0).unwrap()
+    );
+    assert_eq!(
+        parse_utc_offset("UTC-05:30").unwrap(),
+        FixedOffset::west_opt(5 * 3600 + 30 * 60).unwrap()
+    );
+    assert!(parse_utc_offset("Asia/Seoul").is_err());
+
+    // 2022-04-02 is Saturday
+    let s = Schedule::parse("0 2 * * SAT").unwrap();
+    assert!(s.matches(&t("2022-04-02T02:00:00+09:00")));
+    assert!(!s.matches(&t("2022-04-02T02:01:00+09:00")));
+    assert!(!s.matches(&t("2022-04-03T02:00:00+09:00")));
+
+    let s = Schedule::parse("*/15 1-3 * * 0,6").unwrap();
+    assert!(s.matches(&t("2022-04-03T03:45:00+09:00")));
+    assert!(!s.matches(&t("2022-04-04T03:45:00+09:00")));
+
+    assert!(Schedule::parse("0 2 * *").is_err());
+    assert!(Schedule::parse("60 2 * * *").is_err());
+}
@@ -0,0 +1,39 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+};
+
+use ring::digest::{Context, SHA256};
+
+/// Computes the SHA-256 checksum of the file in hex, reading in chunks
+/// so that large binaries are not loaded onto memory at once.
+pub fn sha256_file(file_path: &str) -> io::Result<String> {
+    let f = File::open(file_path)?;
+    let mut reader = BufReader::new(f);
+
+    let mut ctx = Context::new(&SHA256);
+    let mut buf = [0; 65536];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        ctx.update(&buf[..n]);
+    }
+    Ok(hex::encode(ctx.finish().as_ref()))
+}
+
+#[test]
+fn test_sha256_file() {
+    use std::io::Write;
+
+    let mut f = tempfile::NamedTempFile::new().unwrap();
+    f.write_all(b"hello").unwrap();
+    let p = f.path().to_str().unwrap();
+
+    // echo -n hello | sha256sum
+    assert_eq!(
+        sha256_file(p).unwrap(),
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    );
+}
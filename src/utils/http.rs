@@ -1,11 +1,14 @@
 use std::{
+    collections::HashMap,
     io,
     io::{Error, ErrorKind},
+    sync::RwLock,
     time::Duration,
 };
 
-use hyper::{body::Bytes, client::HttpConnector, Body, Client, Method, Request, Response};
+use hyper::{body::Bytes, client::HttpConnector, header, Body, Client, Method, Request, Response};
 use hyper_tls::HttpsConnector;
+use lazy_static::lazy_static;
 use log::warn;
 use tokio::time::timeout;
 use url::Url;
@@ -114,12 +117,124 @@ pub async fn read_bytes(
     Ok(bytes)
 }
 
+/// Sends a HTTPS GET request with the user agent (e.g., required by GitHub API),
+/// follows redirects if any, and reads the response in "hyper::body::Bytes".
+pub async fn get_https_following_redirects(url: &str, timeout_dur: Duration) -> io::Result<Bytes> {
+    let mut url = url.to_string();
+    for _ in 0..MAX_REDIRECTS {
+        let req = match Request::builder()
+            .method(Method::GET)
+            .header("user-agent", USER_AGENT)
+            .uri(url.as_str())
+            .body(Body::empty())
+        {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::new(
+                    ErrorKind::Other,
+                    format!("failed to create request {}", e),
+                ));
+            }
+        };
+
+        let resp = send_req(req, timeout_dur, true).await?;
+        if resp.status().is_redirection() {
+            url = match resp.headers().get("location") {
+                Some(v) => v.to_str().unwrap_or_default().to_string(),
+                None => {
+                    return Err(Error::new(
+                        ErrorKind::Other,
+                        format!("redirect {} without location", resp.status()),
+                    ));
+                }
+            };
+            continue;
+        }
+        if !resp.status().is_success() {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "unexpected HTTP response code {} for {}",
+                    resp.status(),
+                    url
+                ),
+            ));
+        }
+
+        return match timeout(timeout_dur, hyper::body::to_bytes(resp)).await {
+            Ok(Ok(b)) => Ok(b),
+            Ok(Err(e)) => Err(Error::new(
+                ErrorKind::Other,
+                format!("failed to read response {}", e),
+            )),
+            Err(e) => Err(Error::new(
+                ErrorKind::Other,
+                format!("failed to read response {}", e),
+            )),
+        };
+    }
+
+    Err(Error::new(
+        ErrorKind::Other,
+        format!("too many redirects for {}", url),
+    ))
+}
+
+const USER_AGENT: &str = "avalanche-ops";
+const MAX_REDIRECTS: usize = 5;
+
+lazy_static! {
+    /// Bearer tokens by the URL origin (e.g., "http://1.2.3.4:9650").
+    static ref BEARER_TOKENS: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
+}
+
+/// Attaches the bearer token to all subsequent requests to the origin of the URL.
+pub fn set_bearer_token(url: &str, token: &str) -> io::Result<()> {
+    let origin = origin(url)?;
+    BEARER_TOKENS
+        .write()
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to lock tokens {}", e)))?
+        .insert(origin, token.to_string());
+    Ok(())
+}
+
+/// Returns the bearer token for the origin of the URL, if any.
+pub fn bearer_token(url: &str) -> Option<String> {
+    let origin = origin(url).ok()?;
+    BEARER_TOKENS.read().ok()?.get(&origin).cloned()
+}
+
+/// Returns the "curl" arguments to attach the bearer token, if any.
+pub fn curl_auth_args(url: &str) -> Vec<String> {
+    match bearer_token(url) {
+        Some(token) => vec![
+            String::from("--header"),
+            format!("Authorization: Bearer {}", token),
+        ],
+        None => Vec::new(),
+    }
+}
+
+fn origin(url: &str) -> io::Result<String> {
+    Url::parse(url)
+        .map(|u| u.origin().ascii_serialization())
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to parse URL {}", e)))
+}
+
 /// Sends a HTTP(s) request and wait for its response.
 async fn send_req(
-    req: Request<Body>,
+    mut req: Request<Body>,
     timeout_dur: Duration,
     enable_https: bool,
 ) -> io::Result<Response<Body>> {
+    if !req.headers().contains_key(header::AUTHORIZATION) {
+        if let Some(token) = bearer_token(&req.uri().to_string()) {
+            let v = header::HeaderValue::from_str(&format!("Bearer {}", token))
+                .map_err(|e| Error::new(ErrorKind::Other, format!("invalid token {}", e)))?;
+            req.headers_mut().insert(header::AUTHORIZATION, v);
+        }
+    }
+
     // ref. https://github.com/tokio-rs/tokio-tls/blob/master/examples/hyper-client.rs
     // ref. https://docs.rs/hyper/latest/hyper/client/struct.HttpConnector.html
     // ref. https://github.com/hyperium/hyper-tls/blob/master/examples/client.rs
@@ -176,6 +291,19 @@ fn test_read_bytes_timeout() {
     assert!(!ret.is_ok());
 }
 
+#[test]
+fn test_bearer_token() {
+    assert!(bearer_token("http://10.0.0.1:9650/ext/info").is_none());
+    set_bearer_token("http://10.0.0.1:9650", "abc").unwrap();
+    assert_eq!(
+        bearer_token("http://10.0.0.1:9650/ext/bc/C/rpc"),
+        Some(String::from("abc"))
+    );
+    assert!(bearer_token("http://10.0.0.1:9651/ext/info").is_none());
+    assert!(bearer_token("https://10.0.0.1:9650/ext/info").is_none());
+    assert!(set_bearer_token("invalid", "abc").is_err());
+}
+
 pub fn join_uri(url: &str, path: &str) -> io::Result<Url> {
     let mut uri = match Url::parse(url) {
         Ok(u) => u,
@@ -1,3 +1,5 @@
+use std::io::{self, Error, ErrorKind};
+
 use ripemd::{Digest, Ripemd160};
 
 use crate::{random, time};
@@ -31,9 +33,25 @@ pub fn system(n: usize) -> String {
         whoami::platform(),
         whoami::devicename(),
     );
+    deterministic(&id, n)
+}
+
+/// Creates an ID with the prefix followed by the time (e.g., "time::get(6)")
+/// and the host-based ID, so the resource is attributable to the cluster
+/// and to the host that created it (e.g., "avalanche-ops-202208-2vnym3tu4d").
+/// Skips the time if empty.
+pub fn system_with_prefix(pfx: &str, time: &str, n: usize) -> String {
+    if time.is_empty() {
+        return format!("{}-{}", pfx, system(n));
+    }
+    format!("{}-{}-{}", pfx, time, system(n))
+}
 
+/// Creates an ID from the seed (e.g., CI job ID), so the re-runs with
+/// the same seed generate the same ID regardless of the host and time.
+pub fn deterministic(seed: &str, n: usize) -> String {
     let mut hasher = Ripemd160::new();
-    hasher.update(id.as_bytes());
+    hasher.update(seed.as_bytes());
     let result = hasher.finalize();
 
     let mut id = bs58::encode(&result[..]).into_string();
@@ -43,6 +61,188 @@ pub fn system(n: usize) -> String {
     id.to_lowercase()
 }
 
+/// Creates an ID with the prefix followed by the seed-based ID.
+pub fn deterministic_with_prefix(pfx: &str, seed: &str, n: usize) -> String {
+    format!("{}-{}", pfx, deterministic(seed, n))
+}
+
+#[test]
+fn test_deterministic() {
+    let id1 = deterministic_with_prefix("aops-custom", "ci-1234", 10);
+    let id2 = deterministic_with_prefix("aops-custom", "ci-1234", 10);
+    assert_eq!(id1, id2);
+    assert_eq!(id1.len(), "aops-custom-".len() + 10);
+    assert_ne!(id1, deterministic_with_prefix("aops-custom", "ci-1235", 10));
+    assert_eq!(id1, id1.to_lowercase());
+
+    let id = system_with_prefix("avalanche-ops", "202208", 10);
+    assert!(id.starts_with("avalanche-ops-202208-"));
+    assert!(system_with_prefix("avalanche-ops", "", 10).starts_with("avalanche-ops-"));
+    assert!(id.ends_with(&system(10)));
+}
+
+/// Adjectives of at most 8 characters, so the pet names fit in the 28-character cluster ID.
+const PETNAME_ADJECTIVES: [&str; 64] = [
+    "able", "agile", "amber", "ample", "azure", "bold", "brave", "brisk", "calm", "clever",
+    "cosmic", "crisp", "curious", "daring", "deft", "eager", "early", "easy", "epic", "fair",
+    "fancy", "fast", "fierce", "fluffy", "frosty", "gentle", "giant", "glad", "golden", "grand",
+    "happy", "hardy", "honest", "humble", "jolly", "keen", "kind", "lively", "lucky", "lunar",
+    "mellow", "mighty", "modest", "nimble", "noble", "polite", "proud", "quick", "quiet", "rapid",
+    "ready", "royal", "rustic", "shiny", "silent", "smart", "snowy", "solar", "steady", "sunny",
+    "swift", "tidy", "vivid", "witty",
+];
+
+/// Animals of at most 8 characters.
+const PETNAME_ANIMALS: [&str; 64] = [
+    "alpaca", "badger", "beaver", "bison", "bobcat", "camel", "cheetah", "cobra", "condor",
+    "cougar", "coyote", "crane", "dingo", "dolphin", "donkey", "eagle", "falcon", "ferret",
+    "finch", "gazelle", "gecko", "gibbon", "giraffe", "gopher", "heron", "hippo", "husky", "ibex",
+    "iguana", "impala", "jackal", "jaguar", "koala", "lemur", "leopard", "llama", "lynx", "magpie",
+    "marmot", "meerkat", "mole", "moose", "narwhal", "newt", "ocelot", "orca", "osprey", "otter",
+    "panda", "parrot", "pelican", "penguin", "puffin", "quokka", "rabbit", "raven", "salmon",
+    "seal", "sloth", "tapir", "tiger", "toucan", "walrus", "zebra",
+];
+
+/// Creates a human-readable ID of an adjective, an animal, and
+/// "hex_len" random hex characters (e.g., "brave-otter-3fa2"),
+/// to tell the clusters apart in the AWS console.
+pub fn petname(hex_len: usize) -> String {
+    petname_from_bytes(&random::bytes(2 + (hex_len + 1) / 2), hex_len)
+}
+
+/// Creates the pet name from the seed (e.g., CI job ID),
+/// so the re-runs with the same seed generate the same name.
+pub fn petname_from_seed(seed: &str, hex_len: usize) -> String {
+    let mut hasher = Ripemd160::new();
+    hasher.update(seed.as_bytes());
+    petname_from_bytes(&hasher.finalize()[..], hex_len)
+}
+
+fn petname_from_bytes(b: &[u8], hex_len: usize) -> String {
+    let adjective = PETNAME_ADJECTIVES[b[0] as usize % PETNAME_ADJECTIVES.len()];
+    let animal = PETNAME_ANIMALS[b[1] as usize % PETNAME_ANIMALS.len()];
+    let mut suffix = hex::encode(&b[2..]);
+    suffix.truncate(hex_len);
+    format!("{}-{}-{}", adjective, animal, suffix)
+}
+
+#[test]
+fn test_petname() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let name = petname(4);
+    log::info!("petname: {}", name);
+    let parts: Vec<&str> = name.split('-').collect();
+    assert_eq!(parts.len(), 3);
+    assert!(PETNAME_ADJECTIVES.contains(&parts[0]));
+    assert!(PETNAME_ANIMALS.contains(&parts[1]));
+    assert_eq!(parts[2].len(), 4);
+    assert!(parts[2].chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(petname(5).split('-').last().unwrap().len(), 5);
+
+    assert_eq!(
+        petname_from_seed("ci-1234", 4),
+        petname_from_seed("ci-1234", 4)
+    );
+    assert_ne!(
+        petname_from_seed("ci-1234", 4),
+        petname_from_seed("ci-1235", 4)
+    );
+
+    // longest words still fit in the cluster ID
+    let longest = |words: &[&str]| words.iter().map(|w| w.len()).max().unwrap();
+    assert!(longest(&PETNAME_ADJECTIVES) + longest(&PETNAME_ANIMALS) + 2 + 4 <= 28);
+}
+
+/// Represents the AWS resource types that are named after the cluster ID.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Resource {
+    S3Bucket,
+    CloudFormationStack,
+    Ec2KeyPair,
+    IamRole,
+    /// Names of the load balancers and the target groups.
+    Elb,
+    AutoScalingGroup,
+    LambdaFunction,
+    SnsTopic,
+}
+
+impl Resource {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Resource::S3Bucket => "S3 bucket",
+            Resource::CloudFormationStack => "CloudFormation stack",
+            Resource::Ec2KeyPair => "EC2 key pair",
+            Resource::IamRole => "IAM role",
+            Resource::Elb => "ELB",
+            Resource::AutoScalingGroup => "Auto Scaling group",
+            Resource::LambdaFunction => "Lambda function",
+            Resource::SnsTopic => "SNS topic",
+        }
+    }
+
+    /// Returns the maximum name length.
+    /// ref. https://docs.aws.amazon.com/AmazonS3/latest/userguide/bucketnamingrules.html
+    /// ref. https://docs.aws.amazon.com/IAM/latest/UserGuide/reference_iam-quotas.html
+    /// ref. https://docs.aws.amazon.com/elasticloadbalancing/latest/APIReference/API_CreateLoadBalancer.html
+    pub fn max_len(&self) -> usize {
+        match self {
+            Resource::S3Bucket => 63,
+            Resource::CloudFormationStack => 128,
+            Resource::Ec2KeyPair => 255,
+            Resource::IamRole => 64,
+            Resource::Elb => 32,
+            Resource::AutoScalingGroup => 255,
+            Resource::LambdaFunction => 64,
+            Resource::SnsTopic => 256,
+        }
+    }
+}
+
+/// Fails if the name exceeds the name length limit of the resource type,
+/// before any resource is created.
+pub fn check_len(resource: Resource, name: &str) -> io::Result<()> {
+    if name.is_empty() || name.len() > resource.max_len() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} name '{}' length must be 1-{} (got {})",
+                resource.as_str(),
+                name,
+                resource.max_len(),
+                name.len()
+            ),
+        ));
+    }
+    if resource == Resource::S3Bucket
+        && (name.len() < 3
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.'))
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "S3 bucket name '{}' must be 3-63 lowercase letters, numbers, dots, and hyphens",
+                name
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_check_len() {
+    assert!(check_len(Resource::Elb, "aops-custom-202208-2vNYM-nlb").is_ok());
+    assert!(check_len(Resource::Elb, "aops-custom-202208-2vNYM-abcde-nlb").is_err());
+    assert!(check_len(Resource::IamRole, "").is_err());
+    assert!(check_len(Resource::S3Bucket, "avalanche-ops-202208-2vnym3tu4d").is_ok());
+    assert!(check_len(Resource::S3Bucket, "Avalanche-Ops").is_err());
+    assert!(check_len(Resource::S3Bucket, "ab").is_err());
+    assert!(check_len(Resource::S3Bucket, &"a".repeat(64)).is_err());
+}
+
 #[test]
 fn test_system() {
     let _ = env_logger::builder().is_test(true).try_init();
@@ -2,12 +2,18 @@ pub mod bash;
 pub mod big_int;
 pub mod cert;
 pub mod compress;
+pub mod cron;
+pub mod hash;
 pub mod home_dir;
 pub mod http;
 pub mod humanize;
 pub mod id;
 pub mod prefix;
 pub mod prometheus;
+pub mod provenance;
 pub mod random;
+pub mod rate_limit;
 pub mod rfc3339;
+pub mod secret_bytes;
 pub mod time;
+pub mod websocket;
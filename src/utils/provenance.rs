@@ -0,0 +1,194 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Error, ErrorKind},
+    path::Path,
+};
+
+use log::info;
+use ring::signature::{UnparsedPublicKey, ED25519};
+
+use crate::utils::hash;
+
+/// S3 user-defined metadata key for the checksum of the original (uncompressed) file.
+pub const METADATA_KEY_SHA256: &str = "sha256";
+/// S3 user-defined metadata key for the hex-encoded signature of the original file.
+pub const METADATA_KEY_SIGNATURE: &str = "signature";
+
+/// Published checksum file next to the artifact (e.g., "avalanchego.sha256").
+pub const CHECKSUM_FILE_EXT: &str = ".sha256";
+/// Ed25519 signature file next to the artifact (e.g., "avalanchego.sig"),
+/// either raw 64 bytes (e.g., "openssl pkeyutl -sign -rawin") or hex-encoded.
+pub const SIGNATURE_FILE_EXT: &str = ".sig";
+
+/// Returns true if the file is a checksum or signature file,
+/// which must not be installed as an artifact.
+pub fn is_provenance_file(file_path: &str) -> bool {
+    file_path.ends_with(CHECKSUM_FILE_EXT) || file_path.ends_with(SIGNATURE_FILE_EXT)
+}
+
+/// Represents the provenance of a binary artifact.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Provenance {
+    /// Hex-encoded SHA-256 checksum of the file.
+    pub sha256: String,
+    /// Hex-encoded ed25519 signature of the file, if signed.
+    pub signature: Option<String>,
+}
+
+impl Provenance {
+    /// Loads the provenance of the local file, verifying the published checksum
+    /// in "[FILE].sha256" (if any) and reading the signature in "[FILE].sig" (if any).
+    pub fn load(file_path: &str) -> io::Result<Self> {
+        let sha256 = hash::sha256_file(file_path)?;
+
+        let checksum_path = format!("{}{}", file_path, CHECKSUM_FILE_EXT);
+        if Path::new(&checksum_path).exists() {
+            // e.g., "[HEX]  avalanchego" from "sha256sum"
+            let published = fs::read_to_string(&checksum_path)?;
+            let published = published.split_whitespace().next().unwrap_or("");
+            if !published.eq_ignore_ascii_case(&sha256) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "checksum mismatch for {} (published {}, computed {})",
+                        file_path, published, sha256
+                    ),
+                ));
+            }
+            info!("verified published checksum for {}", file_path);
+        }
+
+        let signature_path = format!("{}{}", file_path, SIGNATURE_FILE_EXT);
+        let signature = {
+            if Path::new(&signature_path).exists() {
+                let d = fs::read(&signature_path)?;
+                if d.len() == 64 {
+                    Some(hex::encode(d))
+                } else {
+                    Some(String::from_utf8_lossy(&d).trim().to_string())
+                }
+            } else {
+                None
+            }
+        };
+
+        Ok(Self { sha256, signature })
+    }
+
+    pub fn to_metadata(&self) -> HashMap<String, String> {
+        let mut metadata = HashMap::new();
+        metadata.insert(METADATA_KEY_SHA256.to_string(), self.sha256.clone());
+        if let Some(signature) = &self.signature {
+            metadata.insert(METADATA_KEY_SIGNATURE.to_string(), signature.clone());
+        }
+        metadata
+    }
+
+    /// Returns "None" if the metadata has no checksum.
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> Option<Self> {
+        metadata.get(METADATA_KEY_SHA256).map(|sha256| Self {
+            sha256: sha256.clone(),
+            signature: metadata.get(METADATA_KEY_SIGNATURE).cloned(),
+        })
+    }
+
+    /// Verifies the file against the checksum, and the signature against
+    /// the hex-encoded ed25519 public key (if any). If "require_signed" is true,
+    /// unsigned artifacts (or no public key to verify) are refused.
+    pub fn verify(
+        &self,
+        file_path: &str,
+        public_key: Option<&str>,
+        require_signed: bool,
+    ) -> io::Result<()> {
+        let sha256 = hash::sha256_file(file_path)?;
+        if sha256 != self.sha256 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "checksum mismatch for {} (expected {}, computed {})",
+                    file_path, self.sha256, sha256
+                ),
+            ));
+        }
+
+        let (signature, public_key) = match (&self.signature, public_key) {
+            (Some(signature), Some(public_key)) => (signature, public_key),
+            _ => {
+                if require_signed {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "refusing unsigned artifact {} (signature {}, public key {})",
+                            file_path,
+                            self.signature.is_some(),
+                            public_key.is_some()
+                        ),
+                    ));
+                }
+                return Ok(());
+            }
+        };
+
+        let signature = hex::decode(signature).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid signature hex {}", e),
+            )
+        })?;
+        let public_key = hex::decode(public_key).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid public key hex {}", e),
+            )
+        })?;
+        let d = fs::read(file_path)?;
+        UnparsedPublicKey::new(&ED25519, &public_key)
+            .verify(&d, &signature)
+            .map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid signature for {}", file_path),
+                )
+            })?;
+        info!("verified signature for {}", file_path);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_provenance() {
+    use ring::{
+        rand::SystemRandom,
+        signature::{Ed25519KeyPair, KeyPair},
+    };
+    use std::io::Write;
+
+    let rng = SystemRandom::new();
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+    let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+    let public_key = hex::encode(key_pair.public_key().as_ref());
+
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir.path().join("avalanchego");
+    let file_path = file_path.to_str().unwrap();
+    let mut f = fs::File::create(file_path).unwrap();
+    f.write_all(b"hello").unwrap();
+
+    let p = Provenance::load(file_path).unwrap();
+    assert!(p.signature.is_none());
+    assert!(p.verify(file_path, Some(&public_key), false).is_ok());
+    assert!(p.verify(file_path, Some(&public_key), true).is_err());
+
+    let mut f = fs::File::create(format!("{}{}", file_path, SIGNATURE_FILE_EXT)).unwrap();
+    f.write_all(key_pair.sign(b"hello").as_ref()).unwrap();
+    let p = Provenance::load(file_path).unwrap();
+    assert_eq!(Provenance::from_metadata(&p.to_metadata()), Some(p.clone()));
+    assert!(p.verify(file_path, Some(&public_key), true).is_ok());
+    assert!(p.verify(file_path, None, true).is_err());
+
+    let mut f = fs::File::create(format!("{}{}", file_path, CHECKSUM_FILE_EXT)).unwrap();
+    f.write_all(b"0000  avalanchego\n").unwrap();
+    assert!(Provenance::load(file_path).is_err());
+}
@@ -13,6 +13,11 @@ pub fn string(n: usize) -> String {
     d
 }
 
+/// Generates "n" random bytes.
+pub fn bytes(n: usize) -> Vec<u8> {
+    rand_bytes(n).unwrap()
+}
+
 /// Generates a random string of length "n".
 fn rand_bytes(n: usize) -> Result<Vec<u8>, String> {
     let mut d: Vec<u8> = vec![0u8; n];
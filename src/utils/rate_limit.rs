@@ -0,0 +1,53 @@
+use std::time::{Duration, Instant};
+
+/// Limits the throughput of a transfer to the average bytes per second,
+/// by sleeping whenever the transfer gets ahead of the schedule.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    bytes_per_second: u64,
+    started: Instant,
+    consumed: u64,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes_per_second,
+            started: Instant::now(),
+            consumed: 0,
+        }
+    }
+
+    /// Records "n" transferred bytes, and waits until the average
+    /// throughput falls back to the limit.
+    pub async fn consume(&mut self, n: u64) {
+        self.consumed += n;
+        let d = delay(self.bytes_per_second, self.consumed, self.started.elapsed());
+        if !d.is_zero() {
+            tokio::time::sleep(d).await;
+        }
+    }
+}
+
+/// Returns the time to wait, so that "consumed" bytes over the "elapsed"
+/// time does not exceed "bytes_per_second".
+pub fn delay(bytes_per_second: u64, consumed: u64, elapsed: Duration) -> Duration {
+    if bytes_per_second == 0 {
+        return Duration::ZERO;
+    }
+    let expected = Duration::from_secs_f64(consumed as f64 / bytes_per_second as f64);
+    expected.saturating_sub(elapsed)
+}
+
+#[test]
+fn test_delay() {
+    assert_eq!(delay(100, 100, Duration::from_secs(1)), Duration::ZERO);
+    assert_eq!(delay(100, 100, Duration::from_secs(5)), Duration::ZERO);
+    assert_eq!(
+        delay(100, 300, Duration::from_secs(1)),
+        Duration::from_secs(2)
+    );
+    assert_eq!(delay(1024, 512, Duration::ZERO), Duration::from_millis(500));
+    // no limit
+    assert_eq!(delay(0, 300, Duration::ZERO), Duration::ZERO);
+}
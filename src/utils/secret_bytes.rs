@@ -0,0 +1,164 @@
+use std::fmt;
+
+use ring::constant_time;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroize;
+
+use crate::REDACTED;
+
+/// Represents the key material (e.g., plaintext data keys, staking keys,
+/// prefunded keys) that is zeroized on drop, and never printed or serialized
+/// unless explicitly exposed. The fields to persist must opt in with
+/// "#[serde(with = "secret_bytes::exposed")]".
+#[derive(Clone, Default)]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    pub fn new(d: Vec<u8>) -> Self {
+        Self(d)
+    }
+
+    /// Returns the zero-filled bytes, to be filled in place.
+    pub fn zeroed(len: usize) -> Self {
+        Self(vec![0u8; len])
+    }
+
+    pub fn expose(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn expose_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+
+    /// Returns the secret as a string, for the secrets created from strings
+    /// (e.g., CB58-encoded private keys).
+    pub fn expose_str(&self) -> &str {
+        std::str::from_utf8(&self.0).expect("unexpected non-UTF-8 secret")
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(d: Vec<u8>) -> Self {
+        Self(d)
+    }
+}
+
+impl From<String> for SecretBytes {
+    fn from(s: String) -> Self {
+        Self(s.into_bytes())
+    }
+}
+
+impl From<&str> for SecretBytes {
+    fn from(s: &str) -> Self {
+        Self(s.as_bytes().to_vec())
+    }
+}
+
+/// Compares in constant time, so the comparison does not leak
+/// the length of the common prefix.
+impl PartialEq for SecretBytes {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time::verify_slices_are_equal(&self.0, &other.0).is_ok()
+    }
+}
+
+impl Eq for SecretBytes {}
+
+impl PartialEq<str> for SecretBytes {
+    fn eq(&self, other: &str) -> bool {
+        constant_time::verify_slices_are_equal(&self.0, other.as_bytes()).is_ok()
+    }
+}
+
+impl PartialEq<&str> for SecretBytes {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBytes({})", REDACTED)
+    }
+}
+
+impl Serialize for SecretBytes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretBytes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        exposed::deserialize(deserializer)
+    }
+}
+
+/// Serializes the secret string as is, for the fields that must be persisted
+/// (e.g., the generated keys in the spec file).
+pub mod exposed {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::SecretBytes;
+
+    pub fn serialize<S>(v: &SecretBytes, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(v.expose_str())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<SecretBytes, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(SecretBytes::from)
+    }
+}
+
+#[test]
+fn test_secret_bytes() {
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Keys {
+        redacted: SecretBytes,
+        #[serde(with = "exposed")]
+        persisted: SecretBytes,
+    }
+
+    let keys = Keys {
+        redacted: SecretBytes::from("PrivateKey-abc"),
+        persisted: SecretBytes::from("PrivateKey-def"),
+    };
+    assert_eq!(format!("{:?}", keys.redacted), "SecretBytes(REDACTED)");
+    let d = serde_yaml::to_string(&keys).unwrap();
+    assert!(!d.contains("PrivateKey-abc"));
+    assert!(d.contains("PrivateKey-def"));
+
+    let loaded: Keys = serde_yaml::from_str(&d).unwrap();
+    assert_eq!(loaded.redacted, "REDACTED");
+    assert_eq!(loaded.persisted, keys.persisted);
+    assert_eq!(loaded.persisted.expose_str(), "PrivateKey-def");
+    assert!(loaded.persisted != SecretBytes::from("PrivateKey-de"));
+}
@@ -0,0 +1,210 @@
+use std::{
+    io::{self, Error, ErrorKind},
+    time::Duration,
+};
+
+use hyper::{client::HttpConnector, header, Body, Client, Method, Request, StatusCode};
+use hyper_tls::HttpsConnector;
+use log::info;
+use ring::rand::{SecureRandom, SystemRandom};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    time::timeout,
+};
+
+use crate::utils::http;
+
+/// Nonce from RFC 6455 with its known accept value. The server does not
+/// check the nonce uniqueness, and this client only checks the connectivity.
+/// ref. https://datatracker.ietf.org/doc/html/rfc6455#section-1.3
+const KEY: &str = "dGhlIHNhbXBsZSBub25jZQ==";
+const ACCEPT: &str = "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=";
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+
+/// Maximum response message size, enough for the JSON-RPC responses.
+const MAX_PAYLOAD: u64 = 1024 * 1024;
+
+/// Sends a single text message over the websocket ("ws://" or "wss://")
+/// and returns the first text message back (e.g., JSON-RPC request and response).
+/// Fails if the handshake is not upgraded (e.g., proxies that drop the upgrade).
+pub async fn request(url: &str, msg: &str, timeout_dur: Duration) -> io::Result<String> {
+    let mut msgs = request_messages(url, msg, 1, timeout_dur).await?;
+    Ok(msgs.remove(0))
+}
+
+/// Sends a single text message over the websocket, and returns the first "n"
+/// text messages back (e.g., subscription response and notifications).
+pub async fn request_messages(
+    url: &str,
+    msg: &str,
+    n: usize,
+    timeout_dur: Duration,
+) -> io::Result<Vec<String>> {
+    let (enable_https, http_url) = if let Some(rest) = url.strip_prefix("wss://") {
+        (true, format!("https://{}", rest))
+    } else if let Some(rest) = url.strip_prefix("ws://") {
+        (false, format!("http://{}", rest))
+    } else {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "websocket URL must start with 'ws://' or 'wss://' (got '{}')",
+                url
+            ),
+        ));
+    };
+    info!("sending websocket request to {}", url);
+
+    let mut builder = Request::builder()
+        .method(Method::GET)
+        .uri(http_url.as_str())
+        .header(header::CONNECTION, "Upgrade")
+        .header(header::UPGRADE, "websocket")
+        .header(header::SEC_WEBSOCKET_VERSION, "13")
+        .header(header::SEC_WEBSOCKET_KEY, KEY);
+    // same token as the HTTP API of the node
+    if let Some(token) = http::bearer_token(&http_url) {
+        builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let req = builder
+        .body(Body::empty())
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed to create request {}", e)))?;
+
+    let mut connector = HttpConnector::new();
+    connector.set_connect_timeout(Some(Duration::from_secs(5)));
+    let task = async move {
+        let resp = if enable_https {
+            let https_connector = HttpsConnector::new_with_connector(connector);
+            Client::builder().build(https_connector).request(req).await
+        } else {
+            Client::builder().build(connector).request(req).await
+        }
+        .map_err(|e| Error::new(ErrorKind::Other, format!("failed handshake {}", e)))?;
+        if resp.status() != StatusCode::SWITCHING_PROTOCOLS {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("websocket not upgraded (HTTP {})", resp.status()),
+            ));
+        }
+        let accept = resp
+            .headers()
+            .get(header::SEC_WEBSOCKET_ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        if accept != ACCEPT {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unexpected Sec-WebSocket-Accept '{}'", accept),
+            ));
+        }
+
+        let mut upgraded = hyper::upgrade::on(resp)
+            .await
+            .map_err(|e| Error::new(ErrorKind::Other, format!("failed upgrade {}", e)))?;
+        upgraded.write_all(&encode_frame(msg.as_bytes())?).await?;
+        upgraded.flush().await?;
+        let mut msgs = Vec::new();
+        while msgs.len() < n.max(1) {
+            let (opcode, payload) = read_frame(&mut upgraded).await?;
+            match opcode {
+                OPCODE_TEXT => msgs.push(
+                    String::from_utf8(payload)
+                        .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?,
+                ),
+                OPCODE_CLOSE => {
+                    return Err(Error::new(
+                        ErrorKind::ConnectionAborted,
+                        "websocket closed by the server",
+                    ))
+                }
+                // e.g., ping
+                _ => continue,
+            }
+        }
+        Ok(msgs)
+    };
+    timeout(timeout_dur, task).await?
+}
+
+/// Encodes the text frame, masked as required for the client frames.
+/// ref. https://datatracker.ietf.org/doc/html/rfc6455#section-5.2
+fn encode_frame(payload: &[u8]) -> io::Result<Vec<u8>> {
+    let mut mask = [0u8; 4];
+    SystemRandom::new()
+        .fill(&mut mask)
+        .map_err(|_| Error::new(ErrorKind::Other, "failed to generate mask"))?;
+
+    let mut frame = vec![0x80 | OPCODE_TEXT];
+    let n = payload.len();
+    if n < 126 {
+        frame.push(0x80 | n as u8);
+    } else if n <= u16::MAX as usize {
+        frame.push(0x80 | 126);
+        frame.extend_from_slice(&(n as u16).to_be_bytes());
+    } else {
+        frame.push(0x80 | 127);
+        frame.extend_from_slice(&(n as u64).to_be_bytes());
+    }
+    frame.extend_from_slice(&mask);
+    frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+    Ok(frame)
+}
+
+/// Reads a single (unfragmented) frame, and returns its opcode and payload.
+async fn read_frame<R: AsyncRead + Unpin>(r: &mut R) -> io::Result<(u8, Vec<u8>)> {
+    let mut head = [0u8; 2];
+    r.read_exact(&mut head).await?;
+    let opcode = head[0] & 0x0f;
+    let masked = head[1] & 0x80 != 0;
+    let n = match head[1] & 0x7f {
+        126 => r.read_u16().await? as u64,
+        127 => r.read_u64().await?,
+        n => n as u64,
+    };
+    if n > MAX_PAYLOAD {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("websocket frame too large ({} bytes)", n),
+        ));
+    }
+    let mut mask = [0u8; 4];
+    if masked {
+        r.read_exact(&mut mask).await?;
+    }
+    let mut payload = vec![0u8; n as usize];
+    r.read_exact(&mut payload).await?;
+    if masked {
+        for (i, b) in payload.iter_mut().enumerate() {
+            *b ^= mask[i % 4];
+        }
+    }
+    Ok((opcode, payload))
+}
+
+#[test]
+fn test_frame() {
+    macro_rules! ab {
+        ($e:expr) => {
+            tokio_test::block_on($e)
+        };
+    }
+
+    for n in [0, 5, 125, 126, 70_000] {
+        let payload = vec![b'a'; n];
+        let frame = encode_frame(&payload).unwrap();
+        assert_eq!(frame[0], 0x81);
+        assert!(frame[1] & 0x80 != 0, "client frames must be masked");
+        let (opcode, decoded) = ab!(read_frame(&mut frame.as_slice())).unwrap();
+        assert_eq!(opcode, OPCODE_TEXT);
+        assert_eq!(decoded, payload);
+    }
+
+    // unmasked server frame
+    let (opcode, decoded) = ab!(read_frame(&mut &[0x81, 0x02, b'o', b'k'][..])).unwrap();
+    assert_eq!(opcode, OPCODE_TEXT);
+    assert_eq!(decoded, b"ok");
+
+    assert!(ab!(request("http://localhost:9650", "", Duration::from_secs(1))).is_err());
+}
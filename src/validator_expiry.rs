@@ -0,0 +1,204 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Error, ErrorKind},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{avalanche::avalanchego::api::platform::SubnetValidator, utils::http};
+
+/// Warns two weeks before the staking ends, by default.
+pub const DEFAULT_WARN_DAYS: u64 = 14;
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+/// Tracks the staking end times of the cluster validators, to warn before
+/// the validators leave the primary network.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct ValidatorExpiry {
+    /// Days before the end time to start warning.
+    /// Defaults to "DEFAULT_WARN_DAYS" if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warn_days: Option<u64>,
+    /// Posts the warnings in JSON (e.g., Slack incoming webhook), if set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    /// Last known staking per node ID, updated by "status" and "renew-validators".
+    /// Kept after the staking ends, so "renew-validators" re-adds the same stake.
+    /// READ ONLY -- DO NOT SET.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub validators: BTreeMap<String, Staking>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Staking {
+    /// Unix time in seconds.
+    pub end_time: u64,
+    /// Stake in nano-AVAX.
+    pub weight: u64,
+}
+
+/// Represents the validator ending its staking within the warning days.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Expiry {
+    pub node_id: String,
+    pub end_time: u64,
+    pub weight: u64,
+    /// Zero if already ended.
+    pub days_left: u64,
+    pub expired: bool,
+}
+
+impl ValidatorExpiry {
+    pub fn warn_days(&self) -> u64 {
+        self.warn_days.unwrap_or(DEFAULT_WARN_DAYS)
+    }
+
+    /// Updates the staking of the cluster nodes ("node_ids") from the current
+    /// and pending validators, and drops the nodes no longer in the cluster.
+    /// Returns true if anything changed.
+    pub fn track(&mut self, node_ids: &[String], validators: &[SubnetValidator]) -> bool {
+        let before = self.validators.clone();
+        self.validators
+            .retain(|node_id, _| node_ids.contains(node_id));
+        for node_id in node_ids.iter() {
+            // the pending (renewed) staking ends after the current one
+            let latest = validators
+                .iter()
+                .filter(|v| &v.node_id == node_id)
+                .max_by_key(|v| v.end_time);
+            if let Some(v) = latest {
+                self.validators.insert(
+                    node_id.clone(),
+                    Staking {
+                        end_time: v.end_time,
+                        weight: v.weight,
+                    },
+                );
+            }
+        }
+        before != self.validators
+    }
+
+    /// Returns the validators ending within the warning days (or already ended),
+    /// sorted by the end time.
+    pub fn expiring(&self, now: u64, warn_days: u64) -> Vec<Expiry> {
+        let mut expiring: Vec<Expiry> = self
+            .validators
+            .iter()
+            .filter(|(_, s)| s.end_time <= now + warn_days * SECONDS_PER_DAY)
+            .map(|(node_id, s)| Expiry {
+                node_id: node_id.clone(),
+                end_time: s.end_time,
+                weight: s.weight,
+                days_left: s.end_time.saturating_sub(now) / SECONDS_PER_DAY,
+                expired: s.end_time <= now,
+            })
+            .collect();
+        expiring.sort_by_key(|e| e.end_time);
+        expiring
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct WebhookMessage<'a> {
+    /// Shown as is by the chat webhooks.
+    text: String,
+    cluster_id: &'a str,
+    validators: &'a [Expiry],
+}
+
+/// Encodes the webhook payload of the expiry warnings.
+pub fn encode_webhook_message(cluster_id: &str, expiring: &[Expiry]) -> io::Result<String> {
+    let mut lines = vec![format!(
+        "{} validator(s) of '{}' ending staking soon",
+        expiring.len(),
+        cluster_id
+    )];
+    for e in expiring.iter() {
+        if e.expired {
+            lines.push(format!("- {} ended (run 'renew-validators')", e.node_id));
+        } else {
+            lines.push(format!("- {} ends in {} day(s)", e.node_id, e.days_left));
+        }
+    }
+    serde_json::to_string(&WebhookMessage {
+        text: lines.join("\n"),
+        cluster_id,
+        validators: expiring,
+    })
+    .map_err(|e| Error::new(ErrorKind::Other, format!("failed to serialize {}", e)))
+}
+
+/// Posts the payload to the webhook.
+pub async fn notify(webhook_url: &str, payload: &str) -> io::Result<()> {
+    let req = http::create_json_post(webhook_url, "", payload)?;
+    http::read_bytes(
+        req,
+        Duration::from_secs(15),
+        webhook_url.starts_with("https"),
+        true,
+    )
+    .await?;
+    Ok(())
+}
+
+#[test]
+fn test_validator_expiry() {
+    let v = |node_id: &str, end_time: u64, weight: u64, pending: bool| SubnetValidator {
+        node_id: String::from(node_id),
+        start_time: 0,
+        end_time,
+        weight,
+        pending,
+    };
+    let day = SECONDS_PER_DAY;
+    let node_ids = vec![
+        String::from("NodeID-A"),
+        String::from("NodeID-B"),
+        String::from("NodeID-C"),
+    ];
+
+    let mut expiry = ValidatorExpiry::default();
+    assert_eq!(expiry.warn_days(), DEFAULT_WARN_DAYS);
+    assert!(expiry.track(
+        &node_ids,
+        &[
+            v("NodeID-A", 100 * day, 2000, false),
+            v("NodeID-B", 10 * day, 3000, false),
+            // renewed
+            v("NodeID-C", 5 * day, 2000, false),
+            v("NodeID-C", 400 * day, 2500, true),
+            // not in the cluster
+            v("NodeID-D", 1 * day, 2000, false),
+        ],
+    ));
+    assert_eq!(expiry.validators.len(), 3);
+    assert_eq!(expiry.validators["NodeID-C"].end_time, 400 * day);
+    assert_eq!(expiry.validators["NodeID-C"].weight, 2500);
+
+    let expiring = expiry.expiring(day, DEFAULT_WARN_DAYS);
+    assert_eq!(expiring.len(), 1);
+    assert_eq!(expiring[0].node_id, "NodeID-B");
+    assert_eq!(expiring[0].days_left, 9);
+    assert!(!expiring[0].expired);
+
+    // the ended staking is kept for the renewal
+    assert!(!expiry.track(&node_ids, &[v("NodeID-A", 100 * day, 2000, false)]));
+    let expiring = expiry.expiring(11 * day, DEFAULT_WARN_DAYS);
+    assert_eq!(expiring.len(), 1);
+    assert!(expiring[0].expired);
+    assert_eq!(expiring[0].days_left, 0);
+    assert_eq!(expiring[0].weight, 3000);
+
+    let payload = encode_webhook_message("test", &expiring).unwrap();
+    assert!(payload.contains("NodeID-B ended"));
+
+    // drops the nodes no longer in the cluster
+    assert!(expiry.track(&node_ids[..1], &[]));
+    assert_eq!(expiry.validators.len(), 1);
+}
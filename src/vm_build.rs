@@ -0,0 +1,228 @@
+use std::io::{self, Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+use crate::StorageNamespace;
+
+/// Go toolchain version of the builder, unless specified.
+pub const DEFAULT_GO_VERSION: &str = "1.19.3";
+
+/// Build command in the repository root, where "{output}" is replaced
+/// with the plugin binary path (e.g., "subnet-evm" scripts).
+pub const DEFAULT_BUILD_COMMAND: &str = "./scripts/build.sh {output}";
+
+/// Files that the builder uploads to the build directory on success.
+pub const PLUGIN_FILE: &str = "plugin";
+pub const CHECKSUM_FILE: &str = "plugin.sha256";
+pub const COMMIT_FILE: &str = "commit";
+
+/// Represents a VM plugin built by "build-vm".
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Plugin {
+    /// File name in the plugins directory.
+    pub vm_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vm_name: Option<String>,
+    pub repo: String,
+    /// Git tag, branch, or commit to build.
+    pub git_ref: String,
+    /// Resolved commit of "git_ref".
+    pub commit: String,
+    /// Checksum of the uncompressed plugin binary.
+    pub sha256: String,
+    pub s3_key: String,
+    /// RFC3339.
+    pub built_at: String,
+}
+
+/// Represents the VM plugins built for the cluster, stored in S3
+/// next to the plugins directory, so the plugins stay traceable to the sources.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Manifest {
+    pub plugins: Vec<Plugin>,
+}
+
+impl Manifest {
+    pub fn decode(d: &[u8]) -> io::Result<Self> {
+        serde_yaml::from_slice(d).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid plugins manifest ({})", e),
+            )
+        })
+    }
+
+    pub fn encode_yaml(&self) -> io::Result<String> {
+        serde_yaml::to_string(self).map_err(|e| {
+            Error::new(
+                ErrorKind::Other,
+                format!("failed to serialize plugins manifest {}", e),
+            )
+        })
+    }
+
+    /// Replaces the plugin with the same VM ID, or appends the new one.
+    /// Returns the replaced plugin, if any.
+    pub fn upsert(&mut self, plugin: Plugin) -> Option<Plugin> {
+        match self.plugins.iter_mut().find(|p| p.vm_id == plugin.vm_id) {
+            Some(p) => Some(std::mem::replace(p, plugin)),
+            None => {
+                self.plugins.push(plugin);
+                self.plugins.sort_by(|a, b| a.vm_id.cmp(&b.vm_id));
+                None
+            }
+        }
+    }
+}
+
+/// Returns the S3 key of the build output file.
+pub fn build_file_key(cluster_id: &str, build_id: &str, file_name: &str) -> String {
+    format!(
+        "{}/{}",
+        StorageNamespace::VmBuildDir(cluster_id.to_string(), build_id.to_string()).encode(),
+        file_name
+    )
+}
+
+/// Fails the invalid build inputs, which end up in the builder script.
+pub fn validate(repo: &str, git_ref: &str, build_command: &str) -> io::Result<()> {
+    let safe = |s: &str| {
+        s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:@~+".contains(c))
+    };
+    if !(repo.starts_with("https://") || repo.starts_with("git@")) || !safe(repo) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid git repository URL '{}'", repo),
+        ));
+    }
+    if git_ref.is_empty() || git_ref.starts_with('-') || !safe(git_ref) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid git ref '{}'", git_ref),
+        ));
+    }
+    if !build_command.contains("{output}") || build_command.contains('\'') {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "build command must contain '{{output}}' and no single quote (got '{}')",
+                build_command
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Generates the builder user data that builds the plugin at the git ref,
+/// uploads the binary, the checksum, and the resolved commit to the build directory,
+/// and shuts down (stops) regardless of the result.
+pub fn user_data(
+    s3_bucket: &str,
+    cluster_id: &str,
+    build_id: &str,
+    repo: &str,
+    git_ref: &str,
+    build_command: &str,
+    go_version: &str,
+) -> String {
+    let output = "/tmp/vm-build/plugin";
+    let s3_dir = format!(
+        "s3://{}/{}",
+        s3_bucket,
+        StorageNamespace::VmBuildDir(cluster_id.to_string(), build_id.to_string()).encode()
+    );
+    format!(
+        "#!/bin/bash
+set -xeu
+trap 'sudo shutdown -h now' EXIT
+
+export DEBIAN_FRONTEND=noninteractive
+export HOME=/root
+sudo apt-get update -y && sudo apt-get install -y git gcc make curl unzip
+
+curl https://awscli.amazonaws.com/awscli-exe-linux-x86_64.zip -o awscli.v2.zip
+unzip -q awscli.v2.zip
+sudo ./aws/install
+
+curl -sL https://go.dev/dl/go{go_version}.linux-amd64.tar.gz | sudo tar -C /usr/local -xz
+export PATH=/usr/local/go/bin:$PATH
+export GOPATH=/root/go
+
+mkdir -p /tmp/vm-build
+git clone '{repo}' /tmp/vm-build/src
+cd /tmp/vm-build/src
+git checkout '{git_ref}'
+git rev-parse HEAD > /tmp/vm-build/{commit_file}
+
+{build_command}
+sha256sum {output} | cut -d' ' -f1 > /tmp/vm-build/{checksum_file}
+
+aws s3 cp {output} {s3_dir}/{plugin_file}
+aws s3 cp /tmp/vm-build/{commit_file} {s3_dir}/{commit_file}
+# uploaded last, marks the build success
+aws s3 cp /tmp/vm-build/{checksum_file} {s3_dir}/{checksum_file}
+",
+        go_version = go_version,
+        repo = repo,
+        git_ref = git_ref,
+        build_command = build_command.replace("{output}", output),
+        output = output,
+        s3_dir = s3_dir,
+        plugin_file = PLUGIN_FILE,
+        checksum_file = CHECKSUM_FILE,
+        commit_file = COMMIT_FILE,
+    )
+}
+
+#[test]
+fn test_vm_build() {
+    assert!(validate(
+        "https://github.com/ava-labs/subnet-evm.git",
+        "v0.4.3",
+        DEFAULT_BUILD_COMMAND
+    )
+    .is_ok());
+    assert!(validate("https://x; rm -rf /", "v0.4.3", DEFAULT_BUILD_COMMAND).is_err());
+    assert!(validate("file:///etc", "v0.4.3", DEFAULT_BUILD_COMMAND).is_err());
+    assert!(validate(
+        "https://github.com/a/b",
+        "--upload-pack=x",
+        DEFAULT_BUILD_COMMAND
+    )
+    .is_err());
+    assert!(validate("https://github.com/a/b", "main", "make").is_err());
+
+    let s = user_data(
+        "bucket",
+        "aops-custom-abc",
+        "20221001000000",
+        "https://github.com/ava-labs/subnet-evm.git",
+        "v0.4.3",
+        DEFAULT_BUILD_COMMAND,
+        DEFAULT_GO_VERSION,
+    );
+    assert!(s.contains("git checkout 'v0.4.3'"));
+    assert!(s.contains("./scripts/build.sh /tmp/vm-build/plugin"));
+    assert!(s.contains("s3://bucket/aops-custom-abc/vm-builds/20221001000000/plugin.sha256"));
+    assert_eq!(
+        build_file_key("aops-custom-abc", "20221001000000", PLUGIN_FILE),
+        "aops-custom-abc/vm-builds/20221001000000/plugin"
+    );
+
+    let plugin = |vm_id: &str, commit: &str| Plugin {
+        vm_id: vm_id.to_string(),
+        commit: commit.to_string(),
+        ..Plugin::default()
+    };
+    let mut manifest = Manifest::default();
+    assert!(manifest.upsert(plugin("b", "1")).is_none());
+    assert!(manifest.upsert(plugin("a", "1")).is_none());
+    assert_eq!(manifest.plugins[0].vm_id, "a");
+    assert_eq!(manifest.upsert(plugin("b", "2")).unwrap().commit, "1");
+    assert_eq!(manifest.plugins.len(), 2);
+    let decoded = Manifest::decode(manifest.encode_yaml().unwrap().as_bytes()).unwrap();
+    assert_eq!(decoded, manifest);
+}